@@ -0,0 +1,402 @@
+use std::env;
+use std::fs;
+use std::io::{stdin, stdout, BufRead, IsTerminal, Write};
+use std::path::Path;
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
+use catan::board::layout::BoardLayout;
+use catan::board::Layout;
+use catan::game::{Phase, Action, Checkpoint, Lang, legal, check_coherence};
+use catan::player::{generate_possible_actions, Mcts, MctsConfig};
+use catan::notation::Record;
+use catan::replay::{Replay, Replayer, DigestTrail, verify, find_blunders, first_divergence};
+use catan::state::{State, StateSnapshot, PlayerId, TricellState, diff};
+use catan::utils::Resources;
+
+const HELP: &str = "\
+Commands:
+  n [count]   steps forward (an empty line steps once)
+  b [count]   steps backward
+  g <ply>     goes to a ply
+  s           prints the state
+  m           prints the action mask of the player to act
+  h           prints the hash of the state
+  c           runs the invariant checks
+  d <ply>     prints the board of a ply beside the current one, with their differences
+  p <index>   plays the action of the mask index, dropping the following plies
+  w <path>    writes the replay, as JSON when the path ends with .json
+  t <path> [n] writes the MCTS search tree of the decision with n playouts, as Graphviz when the path ends with .dot
+  q           quits";
+
+/// Replay being debugged, at one of its plies
+struct Debugger {
+    replay: Replay,
+    layout: &'static Layout,
+    ply: usize,
+    /// Resources held by the bank and players at the start
+    total: Resources,
+    rng: SmallRng,
+    /// Language describing the plies, their debug format without one
+    lang: Option<Lang>,
+}
+
+impl Debugger {
+    fn new(replay: Replay, layout: &'static Layout, seed: u64, lang: Option<Lang>) -> Result<Debugger, String> {
+        let (_, state) = replay.state_at(0, layout).map_err(|error| format!("Failed restoring start: {:?}", error))?;
+        let total = (0..state.player_count())
+            .fold(state.get_bank_resources(), |total, p| total + state.get_player_hand(PlayerId::from(p)).resources);
+        Ok(Debugger {
+            replay,
+            layout,
+            ply: 0,
+            total,
+            rng: SmallRng::seed_from_u64(seed),
+            lang,
+        })
+    }
+
+    fn describe(&self, action: Action) -> String {
+        self.lang.map_or_else(|| format!("{:?}", action), |lang| action.describe(lang))
+    }
+
+    fn position(&self) -> (Phase, State) {
+        self.replay.state_at(self.ply, self.layout).expect("Failed replaying")
+    }
+
+    /// Moves to a ply, describing each ply played on the way forward
+    fn seek(&mut self, ply: usize) {
+        let ply = ply.min(self.replay.plies.len());
+        let mut replayer = Replayer::new(&self.replay, self.layout).expect("Failed restoring start");
+        replayer.seek(self.ply.min(ply)).expect("Failed replaying");
+        while replayer.ply() < ply {
+            let action = self.replay.plies[replayer.ply()].action;
+            let legality = legal::legal(replayer.phase(), replayer.state(), action);
+            let played = replayer.step().expect("Ply out of the replay");
+            println!("{:>5} {:?} {}{}{}",
+                replayer.ply(),
+                played.player,
+                self.describe(played.action),
+                played.roll.map_or(String::new(), |roll| format!(" rolled {}", roll)),
+                played.drawn.map_or(String::new(), |card| format!(" drew {:?}", card)),
+            );
+            if let Err(error) = legality {
+                println!("      [ILLEGAL] {:?}", error);
+            }
+            if let Err(error) = check_coherence(replayer.state(), self.total) {
+                println!("      [INCOHERENCE] {}", error);
+            }
+        }
+        self.ply = ply;
+        self.print_position();
+    }
+
+    fn print_position(&self) {
+        let (phase, state) = self.position();
+        println!("Ply {}/{} {:?} #{:016x}", self.ply, self.replay.plies.len(), phase, StateSnapshot::new(&state).digest());
+    }
+
+    fn print_state(&self) {
+        let (phase, state) = self.position();
+        print!("{}", state);
+        println!("Turn {} Thief {} Bank {:?} Deck {:?}", state.get_turn(), state.get_thief_hex(), state.get_bank_resources(), state.get_development_cards());
+        for p in 0..state.player_count() {
+            let player = PlayerId::from(p);
+            println!("{:?} [{} vp] {:?}", player, state.get_player_total_vp(player), state.get_player_hand(player));
+        }
+        println!("{:?}", phase);
+    }
+
+    /// Possible actions of the player to act, with the legal ones, none once the game is finished
+    fn mask(&self) -> (Vec<Action>, Vec<bool>) {
+        let (phase, state) = self.position();
+        let mut actions = Vec::new();
+        if let Phase::FinishedGame { winner: _ } = phase {
+            return (actions, Vec::new());
+        }
+        generate_possible_actions(&mut actions, phase.player(), &state);
+        let mask = actions.iter().map(|action| legal::legal(&phase, &state, *action).is_ok()).collect();
+        (actions, mask)
+    }
+
+    fn print_mask(&self) {
+        let (actions, mask) = self.mask();
+        let mask_line: String = mask.iter().map(|legal| if *legal { '1' } else { '0' }).collect();
+        println!("{}", mask_line);
+        for (i, (action, legal)) in actions.iter().zip(mask.iter()).enumerate() {
+            if *legal {
+                println!("{:>5} {}", i, self.describe(*action));
+            }
+        }
+    }
+
+    fn print_checks(&self) {
+        let (phase, state) = self.position();
+        match check_coherence(&state, self.total) {
+            Ok(()) => println!("Resources: ok"),
+            Err(error) => println!("Resources: {}", error),
+        }
+        let (_, mask) = self.mask();
+        let legal_count = mask.iter().filter(|legal| **legal).count();
+        match phase {
+            Phase::FinishedGame { winner } => println!("Finished, won by {:?} with {} vp", winner, state.get_player_total_vp(winner)),
+            _ if legal_count == 0 => println!("Actions: none legal for {:?}", phase.player()),
+            _ => println!("Actions: {} legal for {:?}", legal_count, phase.player()),
+        }
+        let restored = StateSnapshot::new(&state).restore::<TricellState>(self.layout);
+        match restored {
+            Ok(restored) if StateSnapshot::new(&restored) == StateSnapshot::new(&state) => println!("Snapshot: ok"),
+            Ok(_) => println!("Snapshot: restored state differs"),
+            Err(error) => println!("Snapshot: {:?}", error),
+        }
+    }
+
+    fn print_diff(&self, ply: usize) {
+        let ply = ply.min(self.replay.plies.len());
+        let (_, other) = self.replay.state_at(ply, self.layout).expect("Failed replaying");
+        let (_, state) = self.position();
+        println!("Ply {} beside ply {}", ply, self.ply);
+        print!("{}", diff::side_by_side(&other, &state, stdout().is_terminal()));
+    }
+
+    fn play(&mut self, index: usize) -> Result<(), String> {
+        let (actions, mask) = self.mask();
+        if !mask.get(index).copied().unwrap_or(false) {
+            return Err(format!("No legal action at index {}", index));
+        }
+        let (mut phase, mut state) = self.position();
+        self.replay.plies.truncate(self.ply);
+        self.replay.winner = None;
+        self.replay.record(&mut phase, &mut state, actions[index], &mut self.rng);
+        if let Phase::FinishedGame { winner } = phase {
+            self.replay.winner = Some(winner);
+        }
+        self.seek(self.ply + 1);
+        Ok(())
+    }
+
+    fn write(&self, path: &str) -> Result<(), String> {
+        let bytes = if path.ends_with(".json") { self.replay.to_json().into_bytes() } else { self.replay.to_bytes() };
+        fs::write(path, bytes).map_err(|error| format!("Failed writing {}: {}", path, error))
+    }
+
+    fn write_search_tree(&mut self, path: &str, playouts: usize) -> Result<(), String> {
+        let (phase, state) = self.position();
+        if let Phase::FinishedGame { winner: _ } = phase {
+            return Err("No decision once the game is finished".to_string());
+        }
+        let config = MctsConfig { playouts, ..MctsConfig::DEFAULT };
+        let tree = Mcts::search_tree(config, self.rng.random(), &phase, &state);
+        let text = if path.ends_with(".dot") { tree.to_dot(1) } else { tree.to_json() };
+        fs::write(path, text).map_err(|error| format!("Failed writing {}: {}", path, error))
+    }
+
+    fn run(&mut self, command: &str) -> Result<bool, String> {
+        let mut words = command.split_whitespace();
+        let name = words.next().unwrap_or("n");
+        let argument = words.next();
+        let number = |default: Option<usize>| match argument {
+            Some(word) => word.parse::<usize>().map_err(|_| format!("Bad number {}", word)),
+            None => default.ok_or_else(|| "Missing number".to_string()),
+        };
+        match (name, argument) {
+            ("n", _) => self.seek(self.ply + number(Some(1))?),
+            ("b", _) => self.seek(self.ply.saturating_sub(number(Some(1))?)),
+            ("g", _) => self.seek(number(None)?),
+            ("s", _) => self.print_state(),
+            ("m", _) => self.print_mask(),
+            ("h", _) => self.print_position(),
+            ("c", _) => self.print_checks(),
+            ("d", _) => self.print_diff(number(None)?),
+            ("p", _) => self.play(number(None)?)?,
+            ("w", Some(path)) => self.write(path)?,
+            ("t", Some(path)) => {
+                let playouts = match words.next() {
+                    Some(word) => word.parse::<usize>().map_err(|_| format!("Bad number {}", word))?,
+                    None => MctsConfig::DEFAULT.playouts,
+                };
+                self.write_search_tree(path, playouts)?
+            }
+            ("q", _) => return Ok(false),
+            _ => println!("{}", HELP),
+        }
+        Ok(true)
+    }
+}
+
+/// Replay of a file holding a replay, as JSON or bytes, a record, as JSON or notation, or a checkpoint to play from
+fn load(path: &Path, layout: &'static Layout) -> Result<Replay, String> {
+    let bytes = fs::read(path).map_err(|error| format!("Failed reading {}: {}", path.display(), error))?;
+    if let Ok(replay) = Replay::from_bytes(&bytes) {
+        return Ok(replay);
+    }
+    if let Ok(text) = std::str::from_utf8(&bytes) {
+        if let Ok(replay) = Replay::from_json(text) {
+            return Ok(replay);
+        }
+        if let Ok(record) = Record::from_json(text).or_else(|_| Record::from_text(text)) {
+            return record.to_replay();
+        }
+    }
+    let checkpoint = Checkpoint::from_bytes(&bytes)?;
+    let state = checkpoint.state.restore::<TricellState>(layout)
+        .map_err(|error| format!("Failed restoring checkpoint: {:?}", error))?;
+    let seating = (0..state.player_count() as usize).collect();
+    Ok(Replay::new(checkpoint.phase, &state, seating))
+}
+
+/// Verifies each file of a game against the engine, printing its path and JSON report, false when any of them failed
+fn verify_files(paths: &[&String], layout: &'static Layout) -> bool {
+    let mut verified = true;
+    for path in paths {
+        let verification = match fs::read(path) {
+            Ok(bytes) => verify(&bytes, layout),
+            Err(error) => {
+                eprintln!("Failed reading {}: {}", path, error);
+                verified = false;
+                continue;
+            }
+        };
+        println!("{} {}", path, verification.to_json());
+        verified &= verification.is_ok();
+    }
+    verified
+}
+
+/// Prints the blunders of each decision of a game, see [find_blunders]
+fn print_blunders(path: &str, layout: &'static Layout, config: MctsConfig, seed: u64, threshold: f32) -> Result<(), String> {
+    let replay = load(Path::new(path), layout)?;
+    let report = find_blunders(&replay, layout, config, seed, threshold)?;
+    for blunder in report.blunders.iter() {
+        println!("{:>5} {:?} turn {}: {:?} ({:.3}) instead of {:?} ({:.3}), losing {:.3}",
+            blunder.ply + 1, blunder.player, blunder.turn, blunder.played, blunder.played_value, blunder.preferred, blunder.preferred_value, blunder.drop());
+    }
+    println!("{} blunders in {} decisions, {} of them scored", report.blunders.len(), report.decisions, report.scored);
+    Ok(())
+}
+
+/// Prints the digest trail of a game, or its first divergence from the trail of another build of the engine, false when it diverges
+fn print_digests(path: &str, layout: &'static Layout, trail: Option<&String>) -> Result<bool, String> {
+    let replay = load(Path::new(path), layout)?;
+    let Some(trail) = trail else {
+        println!("{}", DigestTrail::new(&replay, layout)?.to_json());
+        return Ok(true);
+    };
+    let text = fs::read_to_string(trail).map_err(|error| format!("Failed reading {}: {}", trail, error))?;
+    let trail = DigestTrail::from_json(&text)?;
+    match first_divergence(&replay, layout, &trail) {
+        Some(divergence) => {
+            println!("{}", divergence.to_json());
+            Ok(false)
+        }
+        None => {
+            println!("Plies: {} matching the trail of engine {}", replay.plies.len(), trail.engine);
+            Ok(true)
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let option = |name: &str| args.iter().position(|arg| arg == name).and_then(|i| args.get(i + 1));
+    // Verifies uploaded games without debugging them, exiting with an error when any fails
+    if args.get(1).is_some_and(|arg| arg == "verify") {
+        let paths: Vec<&String> = args.iter().enumerate().skip(2)
+            .filter(|(i, arg)| !arg.starts_with("--") && args[i - 1] != "--layout")
+            .map(|(_, arg)| arg)
+            .collect();
+        let verified = BoardLayout::named(option("--layout").map_or("standard", |layout| layout.as_str()))
+            .map(|board| verify_files(&paths, board.layout));
+        match verified {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(2);
+            }
+        }
+    }
+    // Reports the decisions of a game losing value according to the search
+    if args.get(1).is_some_and(|arg| arg == "blunders") {
+        let run = || -> Result<(), String> {
+            let path = args.get(2).filter(|arg| !arg.starts_with("--")).ok_or_else(|| "Missing game".to_string())?;
+            let board = BoardLayout::named(option("--layout").map_or("standard", |layout| layout.as_str()))?;
+            let seed = option("--seed").map_or(Ok(0), |seed| seed.parse().map_err(|_| format!("Bad seed {}", seed)))?;
+            let playouts = option("--playouts").map_or(Ok(MctsConfig::DEFAULT.playouts), |playouts| playouts.parse().map_err(|_| format!("Bad number {}", playouts)))?;
+            let threshold = option("--threshold").map_or(Ok(0.1), |threshold| threshold.parse().map_err(|_| format!("Bad threshold {}", threshold)))?;
+            print_blunders(path, board.layout, MctsConfig { playouts, ..MctsConfig::DEFAULT }, seed, threshold)
+        };
+        if let Err(error) = run() {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        }
+        return;
+    }
+    // Writes the digest trail of a game, or checks a game against the trail another build of the engine wrote
+    if args.get(1).is_some_and(|arg| arg == "digests") {
+        let run = || -> Result<bool, String> {
+            let path = args.get(2).filter(|arg| !arg.starts_with("--")).ok_or_else(|| "Missing game".to_string())?;
+            let board = BoardLayout::named(option("--layout").map_or("standard", |layout| layout.as_str()))?;
+            print_digests(path, board.layout, option("--trail"))
+        };
+        match run() {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(2);
+            }
+        }
+    }
+    let path = match args.get(1).filter(|arg| !arg.starts_with("--")) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: catan-debug <replay, record or checkpoint> [--layout standard|extension|<description>] [--seed <seed>] [--lang en|fr] [--dump] [--diff <other replay or checkpoint>]");
+            eprintln!("       catan-debug verify <replay or record>... [--layout standard|extension|<description>]");
+            eprintln!("       catan-debug blunders <replay or record> [--layout standard|extension|<description>] [--seed <seed>] [--playouts <playouts>] [--threshold <value drop>]");
+            eprintln!("       catan-debug digests <replay or record> [--layout standard|extension|<description>] [--trail <digest trail>]");
+            return;
+        }
+    };
+    let run = || -> Result<(), String> {
+        let board = BoardLayout::named(option("--layout").map_or("standard", |layout| layout.as_str()))?;
+        let seed = option("--seed").map_or(Ok(0), |seed| seed.parse().map_err(|_| format!("Bad seed {}", seed)))?;
+        let lang = option("--lang").map(|code| Lang::from_code(code).ok_or_else(|| format!("Unknown language {}", code))).transpose()?;
+        let replay = load(Path::new(path), board.layout)?;
+        let mut debugger = Debugger::new(replay, board.layout, seed, lang)?;
+        // Compares the last positions of two files without prompting
+        if let Some(other) = option("--diff") {
+            let other = load(Path::new(other), board.layout)?;
+            let last = |replay: &Replay| replay.state_at(replay.plies.len(), board.layout).map_err(|error| format!("Failed replaying: {:?}", error));
+            let (_, state) = last(&debugger.replay)?;
+            let (_, other) = last(&other)?;
+            print!("{}", diff::side_by_side(&state, &other, stdout().is_terminal()));
+            return Ok(());
+        }
+        // Dumps every ply and the final checks without prompting
+        if args.iter().any(|arg| arg == "--dump") {
+            debugger.seek(debugger.replay.plies.len());
+            debugger.print_checks();
+            return Ok(());
+        }
+        debugger.print_position();
+        println!("{}", HELP);
+        let mut lines = stdin().lock().lines();
+        loop {
+            print!("> ");
+            stdout().flush().expect("Failed flushing prompt");
+            let line = match lines.next() {
+                Some(line) => line.map_err(|error| error.to_string())?,
+                None => return Ok(()),
+            };
+            match debugger.run(&line) {
+                Ok(true) => (),
+                Ok(false) => return Ok(()),
+                Err(error) => println!("[ERROR] {}", error),
+            }
+        }
+    };
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+    }
+}
@@ -39,6 +39,9 @@ pub fn parse_action(raw: String) -> Result<Action, ParsingError> {
         Some("Quit") | Some("Q") => {
             Ok(Action::Exit)
         }
+        Some("Resign") => {
+            Ok(Action::Resign)
+        }
         Some("BuildRoad") | Some("Road") | Some("R") => {
             let path = end_parse_coord(splited.next().ok_or(ParsingError::NotEnoughtParameters)?)?;
             Ok(Action::BuildRoad{ path })
@@ -51,6 +54,10 @@ pub fn parse_action(raw: String) -> Result<Action, ParsingError> {
             let intersection = end_parse_coord(splited.next().ok_or(ParsingError::NotEnoughtParameters)?)?;
             Ok(Action::BuildCity { intersection })
         }
+        Some("ReserveCity") | Some("Reserve") => {
+            let intersection = end_parse_coord(splited.next().ok_or(ParsingError::NotEnoughtParameters)?)?;
+            Ok(Action::ReserveCity { intersection })
+        }
         Some("BuyDevelopmentCard") | Some("DevelopmentCard") | Some("Development") | Some("D") => {
             Ok(Action::BuyDevelopment)
         }
@@ -73,7 +80,9 @@ Resource: [B]rick [L]umber [O]re [G]rain [W]ool
 Coord: <x>,<y>
 Action: [E]ndTurn
         Build[R]oad <Coord> / Build[S]ettlement <Coord> / Build[C]ity <Coord>
+        ReserveCity <Coord>
         Buy[D]evelopmentCard
         [T]radeBank <Resource> <Resource>
+        Resign
         [Q]uit
 "}
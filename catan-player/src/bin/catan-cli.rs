@@ -0,0 +1,115 @@
+//! Game in the terminal against bots
+//!
+//! `catan-cli --opponents 3 --opponent heuristic,mcts,random --playouts 200 --seed 7 --theme colorblind --lang fr`
+//! plays against three bots, `--opponent` naming the bot of each opponent, or a single bot for all of them,
+//! and `--lang` the language of the actions and notifications.
+//!
+//! `catan-cli demo --players heuristic,mcts,random --delay 500 --svg board.svg` lets bots play each other instead,
+//! pausing 500 milliseconds after each event to draw the board and comment it, and writing the board to `board.svg` to watch it from a browser.
+
+use std::path::PathBuf;
+use std::time::Duration;
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
+use catan::game::{Game, Notification};
+use catan::player::{CatanPlayer, PlayerInfo, Randomy, Heuristic, Mcts, MctsConfig};
+use catan::state::PlayerId;
+use catan_player::{TerminalPlayer, Spectator, option_value, theme_from_args, lang_from_args};
+
+fn bot(name: &str, playouts: usize, seed: u64) -> Result<Box<dyn CatanPlayer>, String> {
+    match name {
+        "random" => Ok(Box::new(Randomy::new_seeded_player(seed))),
+        "heuristic" => Ok(Box::new(Heuristic::new_seeded_player(seed))),
+        "mcts" => Ok(Box::new(Mcts::new_seeded_player(MctsConfig { playouts, ..MctsConfig::DEFAULT }, seed))),
+        _ => Err(format!("Unknown bot {}, expected random, heuristic or mcts", name)),
+    }
+}
+
+fn parse<T: std::str::FromStr>(args: &[String], name: &str, default: T) -> Result<T, String> {
+    match option_value(args, name) {
+        Some(value) => value.parse().map_err(|_| format!("Invalid value {} of {}", value, name)),
+        None => Ok(default),
+    }
+}
+
+fn seeded_rng(args: &[String]) -> Result<SmallRng, String> {
+    match option_value(args, "--seed") {
+        Some(_) => Ok(SmallRng::seed_from_u64(parse(args, "--seed", 0)?)),
+        None => Ok(SmallRng::from_rng(&mut rand::rng())),
+    }
+}
+
+fn print_results(game: &Game, winner: PlayerId) {
+    for (seat, info) in game.seating_report() {
+        println!("{:?}: {}{}", seat, info, if seat == winner { " (winner)" } else { "" });
+    }
+}
+
+fn play(args: &[String]) -> Result<(), String> {
+    if args.get(1).is_some_and(|arg| arg == "demo") {
+        return demo(args);
+    }
+    let theme = theme_from_args(args)?;
+    let lang = lang_from_args(args)?;
+    let opponents: usize = parse(args, "--opponents", 2)?;
+    if !(1..=3).contains(&opponents) {
+        return Err(format!("Can't play against {} opponents, expected 1 to 3", opponents));
+    }
+    let bots: Vec<&str> = option_value(args, "--opponent").unwrap_or("heuristic").split(',').collect();
+    if bots.len() != 1 && bots.len() != opponents {
+        return Err(format!("Got {} bots for {} opponents", bots.len(), opponents));
+    }
+    let playouts = parse(args, "--playouts", MctsConfig::DEFAULT.playouts)?;
+    let mut rng = seeded_rng(args)?;
+
+    let mut game = Game::new();
+    game.add_named_player(Box::new(TerminalPlayer::with_theme(theme).with_lang(lang)), PlayerInfo::new("you"));
+    for i in 0..opponents {
+        let name = bots[i % bots.len()];
+        game.add_named_player(bot(name, playouts, rng.random())?, PlayerInfo::new(&format!("{} {}", name, i + 1)));
+    }
+    let winner = match game.setup_and_play_with_rng(&mut rng) {
+        Notification::GameFinished { winner } => winner,
+        _ => PlayerId::NONE,
+    };
+    print_results(&game, winner);
+    Ok(())
+}
+
+/// Game of bots against each other, drawn and commented at the pace of the `--delay` in milliseconds
+fn demo(args: &[String]) -> Result<(), String> {
+    let theme = theme_from_args(args)?;
+    let lang = lang_from_args(args)?;
+    let bots: Vec<&str> = option_value(args, "--players").unwrap_or("heuristic,heuristic,mcts").split(',').collect();
+    if !(2..=4).contains(&bots.len()) {
+        return Err(format!("Can't watch {} bots, expected 2 to 4", bots.len()));
+    }
+    let playouts = parse(args, "--playouts", MctsConfig::DEFAULT.playouts)?;
+    let delay = Duration::from_millis(parse(args, "--delay", 800)?);
+    let mut rng = seeded_rng(args)?;
+
+    let mut spectator = Spectator::new(theme, lang, delay);
+    if let Some(path) = option_value(args, "--svg") {
+        spectator = spectator.with_svg(PathBuf::from(path));
+    }
+    let mut game = Game::new();
+    game.subscribe(Box::new(spectator));
+    for (i, name) in bots.iter().enumerate() {
+        game.add_named_player(bot(name, playouts, rng.random())?, PlayerInfo::new(&format!("{} {}", name, i + 1)));
+    }
+    let winner = match game.setup_and_play_with_rng(&mut rng) {
+        Notification::GameFinished { winner } => winner,
+        _ => PlayerId::NONE,
+    };
+    print_results(&game, winner);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let Err(error) = play(&args) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
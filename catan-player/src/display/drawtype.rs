@@ -4,6 +4,7 @@ use termion::color;
 use termion::color::AnsiValue;
 
 use catan::utils::{Hex, LandHex, Harbor, Resource};
+use super::theme::Theme;
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum DrawType {
@@ -29,7 +30,6 @@ pub trait ToDrawType where Self: Sized {
     }
 }
 
-static WHITE: Lazy<AnsiValue> = Lazy::new(|| AnsiValue::rgb(5,5,5));
 static GREY: Lazy<AnsiValue> = Lazy::new(|| AnsiValue::rgb(2,2,2));
 static BLACK: Lazy<AnsiValue> = Lazy::new(|| AnsiValue::rgb(0,0,0));
 
@@ -48,38 +48,33 @@ impl DrawType {
         }
     }
 
-    pub fn fg(&self) -> AnsiValue {
+    /// Index of the tile in the theme, None for void
+    fn tile(&self) -> Option<usize> {
         match self {
-            DrawType::Void => *GREY,
-            DrawType::Water => *WHITE,
-            DrawType::GenericHarbor => *BLACK,
-            DrawType::Desert => *GREY,
-            DrawType::Brick => *WHITE,
-            DrawType::Lumber => *WHITE,
-            DrawType::Ore => *WHITE,
-            DrawType::Grain => *BLACK,
-            DrawType::Wool => *BLACK,
+            DrawType::Void => None,
+            DrawType::Water => Some(0),
+            DrawType::GenericHarbor => Some(1),
+            DrawType::Desert => Some(2),
+            DrawType::Brick => Some(3),
+            DrawType::Lumber => Some(4),
+            DrawType::Ore => Some(5),
+            DrawType::Grain => Some(6),
+            DrawType::Wool => Some(7),
         }
     }
 
-    pub fn bg(&self) -> AnsiValue {
-        match self {
-            DrawType::Void => AnsiValue::rgb(0,0,0),
-            DrawType::Water => AnsiValue::rgb(0,3,5),
-            DrawType::GenericHarbor => AnsiValue::rgb(4,4,4),
-            DrawType::Desert => AnsiValue::rgb(4,4,3),
-            DrawType::Brick => AnsiValue::rgb(4,2,1),
-            DrawType::Lumber => AnsiValue::rgb(0,2,1),
-            DrawType::Ore => AnsiValue::rgb(2,2,3),
-            DrawType::Grain => AnsiValue::rgb(4,4,2),
-            DrawType::Wool => AnsiValue::rgb(1,4,2),
-        }
+    pub fn fg(&self, theme: &Theme) -> AnsiValue {
+        self.tile().map_or(*GREY, |tile| theme.tiles[tile].1)
+    }
+
+    pub fn bg(&self, theme: &Theme) -> AnsiValue {
+        self.tile().map_or(*BLACK, |tile| theme.tiles[tile].0)
     }
 
-    pub fn color(&self) -> String {
+    pub fn color(&self, theme: &Theme) -> String {
         match self {
             DrawType::Void => "".to_string(),
-            _ => format!("{}{}", color::Bg(self.bg()), color::Fg(self.fg()))
+            _ => format!("{}{}", color::Bg(self.bg(theme)), color::Fg(self.fg(theme)))
         }
     }
 }
@@ -124,26 +119,14 @@ impl ToDrawType for Resource {
      }
 }
 
-pub fn player_bg_color(player: PlayerId) -> AnsiValue {
-    match player.to_u8() {
-        0 => AnsiValue::rgb(5, 1, 1),
-        1 => AnsiValue::rgb(1, 1, 5),
-        2 => AnsiValue::rgb(5, 5, 5),
-        3 => AnsiValue::rgb(4, 4, 1),
-        _ => *BLACK,
-    }
+pub fn player_bg_color(player: PlayerId, theme: &Theme) -> AnsiValue {
+    theme.players.get(player.to_usize()).map_or(*BLACK, |(color, _)| *color)
 }
 
-pub fn player_letter(player: PlayerId) -> char {
-    match player.to_u8() {
-        0 => 'r',
-        1 => 'b',
-        2 => 'w',
-        3 => 'o',
-        _ => ' ',
-    }
+pub fn player_letter(player: PlayerId, theme: &Theme) -> char {
+    theme.players.get(player.to_usize()).map_or(' ', |(_, letter)| *letter)
 }
 
-pub fn player_color(player: PlayerId) -> String {
-    format!("{}{}", color::Bg(player_bg_color(player)), color::Fg(*BLACK))
+pub fn player_color(player: PlayerId, theme: &Theme) -> String {
+    format!("{}{}", color::Bg(player_bg_color(player, theme)), color::Fg(*BLACK))
 }
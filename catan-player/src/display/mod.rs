@@ -1,5 +1,9 @@
 pub mod utils;
 mod drawtype;
 mod pretty_terminal;
+mod svg;
+mod theme;
 
 pub(crate) use pretty_terminal::{PrettyGridDisplay, pretty_public_player_hand, pretty_private_player_hand};
+pub use theme::{Theme, DEFAULT_THEME, COLORBLIND_THEME};
+pub use svg::board_svg;
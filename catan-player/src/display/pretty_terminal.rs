@@ -7,6 +7,7 @@ use catan::utils::{Resource, Hex, DevelopmentCard};
 use catan::board::Coord;
 
 use super::drawtype::{ToDrawType, DrawType, player_color, player_letter};
+use super::theme::Theme;
 use super::utils::{GridDisplayable, Pattern};
 
 static HEX_PATTERN: Lazy<Pattern> = Lazy::new(|| Pattern::new(vec![
@@ -50,9 +51,14 @@ static ROAD_Z_PATTERN: Lazy<Pattern> = Lazy::new(|| Pattern::new(vec![
     " %%"
 ]));
 
-pub(crate) struct PrettyGridDisplay;
+pub(crate) struct PrettyGridDisplay {
+    theme: &'static Theme,
+}
+
 impl PrettyGridDisplay {
-    pub const INSTANCE: PrettyGridDisplay = PrettyGridDisplay;
+    pub fn new(theme: &'static Theme) -> PrettyGridDisplay {
+        PrettyGridDisplay { theme }
+    }
 }
 
 impl GridDisplayable for PrettyGridDisplay {
@@ -62,7 +68,7 @@ impl GridDisplayable for PrettyGridDisplay {
         let value = hex.get_num();
         let drawtype = hex.to_draw_type();
         let letter = drawtype.letter();
-        let clr = drawtype.color();
+        let clr = drawtype.color(self.theme);
 
         write!(f, "{}", clr)?;
 
@@ -78,7 +84,7 @@ impl GridDisplayable for PrettyGridDisplay {
         let y = if is_i { y } else { y };
 
         if let Ok(Some(player)) = state.get_dynamic_path(coord) {
-            write!(f, "{}", player_color(player))?;
+            write!(f, "{}", player_color(player, self.theme))?;
 
             if is_i {
                 &ROAD_I_PATTERN
@@ -88,7 +94,7 @@ impl GridDisplayable for PrettyGridDisplay {
                 } else {
                     &ROAD_Z_PATTERN
                 }
-            }.display(x, y, f, &player_letter(player))?;
+            }.display(x, y, f, &player_letter(player, self.theme))?;
         }
         Ok(())
     }
@@ -101,7 +107,7 @@ impl GridDisplayable for PrettyGridDisplay {
             let drawtype = harbor.to_draw_type();
             if drawtype != DrawType::Void {
                 let letter = drawtype.letter();
-                let clr = drawtype.color();
+                let clr = drawtype.color(self.theme);
 
                 write!(f, "{}", clr)?;
 
@@ -110,13 +116,13 @@ impl GridDisplayable for PrettyGridDisplay {
         }
 
         if let Ok(Some((player, is_city))) = state.get_dynamic_intersection(coord) {
-            write!(f, "{}", player_color(player))?;
+            write!(f, "{}", player_color(player, self.theme))?;
 
             if is_city {
                 &INTERSECTION_CITY_PATTERN
             } else {
                 &INTERSECTION_SETTLEMENT_PATTERN
-            }.display(x, y, f, &player_letter(player))?;
+            }.display(x, y, f, &player_letter(player, self.theme))?;
         }
         Ok(())
     }
@@ -149,23 +155,23 @@ fn lr_la(player: PlayerId, state: &State) -> String {
     format!("{} {}", lr, la)
 }
 
-pub fn pretty_public_player_hand(f: &mut dyn Write, player: PlayerId, state: &State) ->  Result<(), Error> {
+pub fn pretty_public_player_hand(f: &mut dyn Write, player: PlayerId, state: &State, theme: &Theme) ->  Result<(), Error> {
     let hand: &PlayerHand = state.get_player_hand(player);
-    let player_color = player_color(player);
+    let player_color = player_color(player, theme);
     write!(f, "{}{}", player_color, FULL_LINE)?;
     write!(f, "{}{} [{:>2}]", cursor::Left(10), lr_la(player, state), state.get_player_total_vp(player))?;
     write!(f, "{}{} ", cursor::Down(1), cursor::Left(WIDTH))?;
     for resource in Resource::ALL.iter() {
         let resource_draw_type = resource.to_draw_type();
         write!(f, "{resource_color}{resource_letter}{resource_amount:>2}{player_color} ",
-            resource_color = resource_draw_type.color(),
+            resource_color = resource_draw_type.color(theme),
             resource_letter = resource_draw_type.letter(),
             resource_amount = hand.resources[*resource],
             player_color = player_color,
         )?;
     }
     write!(f, " ")?;
-    let dvp_color = DrawType::GenericHarbor.color();
+    let dvp_color = DrawType::GenericHarbor.color(theme);
     for development_card in DevelopmentCard::ALL.iter() {
         write!(f, "{dvp_color}{dvp_amount:>1}{player_color} ",
             dvp_color = dvp_color,
@@ -183,13 +189,13 @@ pub fn pretty_public_player_hand(f: &mut dyn Write, player: PlayerId, state: &St
     Ok(())
 }
 
-pub fn pretty_private_player_hand(f: &mut dyn Write, player: PlayerId, state: &State) ->  Result<(), Error> {
+pub fn pretty_private_player_hand(f: &mut dyn Write, player: PlayerId, state: &State, theme: &Theme) ->  Result<(), Error> {
     let hand: &PlayerHand = state.get_player_hand(player);
-    let player_color = player_color(player);
+    let player_color = player_color(player, theme);
     write!(f, "{}{}", player_color, FULL_LINE)?;
     write!(f, "{}{} [{:>2}]", cursor::Left(10), lr_la(player, state), state.get_player_total_vp(player))?;
     write!(f, "{}{} ", cursor::Down(1), cursor::Left(WIDTH))?;
-    let generic_color = DrawType::GenericHarbor.color();
+    let generic_color = DrawType::GenericHarbor.color(theme);
     write!(f, "         {generic_color}{resource_amount:>2}{player_color}         ",
         resource_amount = hand.resources.total(),
         generic_color = generic_color,
@@ -0,0 +1,94 @@
+use std::fmt::Write;
+use termion::color::AnsiValue;
+
+use catan::state::State;
+use catan::utils::{Hex, Harbor};
+use catan::board::{Coord, DetailedCoordType};
+use catan::board::utils::topology::Topology;
+
+use super::drawtype::{ToDrawType, player_bg_color};
+use super::theme::Theme;
+
+/// Distance from the center of a hex to its corners, in pixels
+const RADIUS: f64 = 40.0;
+const MARGIN: f64 = 20.0;
+
+/// Color of the 6x6x6 cube of the ansi palette, as `#rrggbb`
+fn rgb(value: AnsiValue) -> String {
+    let cube = value.0.saturating_sub(16);
+    let (r, g, b) = (cube / 36, cube / 6 % 6, cube % 6);
+    format!("#{:02x}{:02x}{:02x}", r * 51, g * 51, b * 51)
+}
+
+/// Position of a coord, hexes being pointy topped
+///
+/// Rows of coords are three quarters of a radius apart, intersections at the top of an `A` being raised by a quarter
+/// and the ones at the bottom of a `V` lowered by a quarter, so that hexes are regular.
+fn point(coord: Coord, state: &State) -> (f64, f64) {
+    let layout = state.get_layout();
+    let x = (coord.x as f64 + layout.half_width as f64) * RADIUS * 3f64.sqrt() / 4.0;
+    let y = (layout.half_height as f64 - coord.y as f64) * RADIUS * 0.75;
+    let shift = match coord.get_detailed_type() {
+        DetailedCoordType::AIntersection => -RADIUS / 4.0,
+        DetailedCoordType::VIntersection => RADIUS / 4.0,
+        _ => 0.0,
+    };
+    (MARGIN + x, MARGIN + RADIUS / 4.0 + y + shift)
+}
+
+/// Drawing of the board as an SVG image, with the pieces, harbors and thief, colored by the theme
+pub fn board_svg(state: &State, theme: &Theme) -> String {
+    let layout = state.get_layout();
+    let width = 2.0 * MARGIN + (layout.width - 1) as f64 * RADIUS * 3f64.sqrt() / 4.0;
+    let height = 2.0 * MARGIN + RADIUS / 2.0 + (layout.height - 1) as f64 * RADIUS * 0.75;
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" font-family="sans-serif" text-anchor="middle">"#, width, height);
+    for hex in layout.hexes.iter() {
+        let tile = state.get_static_hex(*hex).unwrap_or(Hex::Water);
+        let drawtype = tile.to_draw_type();
+        let (x, y) = (hex.x, hex.y);
+        let corners: Vec<String> = [(2, 1), (0, 1), (-2, 1), (-2, -1), (0, -1), (2, -1)].iter()
+            .map(|(dx, dy)| point(Coord::new(x + dx, y + dy), state))
+            .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+            .collect();
+        let _ = writeln!(svg, r#"<polygon points="{}" fill="{}" stroke="{}"/>"#, corners.join(" "), rgb(drawtype.bg(theme)), rgb(drawtype.fg(theme)));
+        let (cx, cy) = point(*hex, state);
+        if let Some(number) = tile.get_num() {
+            let weight = if number == 6 || number == 8 { "bold" } else { "normal" };
+            let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" font-size="16" font-weight="{}" fill="{}">{}{}</text>"#, cx, cy + 6.0, weight, rgb(drawtype.fg(theme)), number, drawtype.letter());
+        }
+        if *hex == state.get_thief_hex() {
+            let _ = writeln!(svg, r#"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="none" stroke="black" stroke-width="4"/>"#, cx, cy, RADIUS / 2.0);
+        }
+    }
+    for intersection in layout.intersections.iter() {
+        let drawtype = state.get_static_harbor(*intersection).unwrap_or(Harbor::None).to_draw_type();
+        if drawtype.letter() != ' ' {
+            let (x, y) = point(*intersection, state);
+            let _ = writeln!(svg, r#"<circle cx="{:.1}" cy="{:.1}" r="8" fill="{}" stroke="{}"/>"#, x, y, rgb(drawtype.bg(theme)), rgb(drawtype.fg(theme)));
+        }
+    }
+    for path in layout.paths.iter() {
+        if let Ok(Some(player)) = state.get_dynamic_path(*path) {
+            let ends = state.path_intersection_neighbours(*path).unwrap_or_default();
+            if let [a, b] = ends[..] {
+                let ((x1, y1), (x2, y2)) = (point(a, state), point(b, state));
+                let _ = writeln!(svg, r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="6" stroke-linecap="round"/>"#,
+                    x1, y1, x2, y2, rgb(player_bg_color(player, theme)));
+            }
+        }
+    }
+    for intersection in layout.intersections.iter() {
+        if let Ok(Some((player, city))) = state.get_dynamic_intersection(*intersection) {
+            let (x, y) = point(*intersection, state);
+            let color = rgb(player_bg_color(player, theme));
+            let _ = if city {
+                writeln!(svg, r#"<rect x="{:.1}" y="{:.1}" width="16" height="16" fill="{}" stroke="black"/>"#, x - 8.0, y - 8.0, color)
+            } else {
+                writeln!(svg, r#"<circle cx="{:.1}" cy="{:.1}" r="7" fill="{}" stroke="black"/>"#, x, y, color)
+            };
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
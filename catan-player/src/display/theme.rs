@@ -0,0 +1,67 @@
+use once_cell::sync::Lazy;
+use termion::color::AnsiValue;
+
+/// Colors and glyphs of the board and players
+pub struct Theme {
+    pub name: &'static str,
+    /// Background and letter of the pieces of each seat, up to the six seats of the extension board
+    pub players: [(AnsiValue, char); 6],
+    /// Background and foreground of water, generic harbors, desert, brick, lumber, ore, grain and wool
+    pub tiles: [(AnsiValue, AnsiValue); 8],
+}
+
+static WHITE: Lazy<AnsiValue> = Lazy::new(|| AnsiValue::rgb(5,5,5));
+static GREY: Lazy<AnsiValue> = Lazy::new(|| AnsiValue::rgb(2,2,2));
+static BLACK: Lazy<AnsiValue> = Lazy::new(|| AnsiValue::rgb(0,0,0));
+
+pub static DEFAULT_THEME: Lazy<Theme> = Lazy::new(|| Theme {
+    name: "default",
+    players: [
+        (AnsiValue::rgb(5,1,1), 'r'),
+        (AnsiValue::rgb(1,1,5), 'b'),
+        (AnsiValue::rgb(5,5,5), 'w'),
+        (AnsiValue::rgb(4,4,1), 'o'),
+        (AnsiValue::rgb(1,4,1), 'g'),
+        (AnsiValue::rgb(3,1,0), 'n'),
+    ],
+    tiles: [
+        (AnsiValue::rgb(0,3,5), *WHITE),
+        (AnsiValue::rgb(4,4,4), *BLACK),
+        (AnsiValue::rgb(4,4,3), *GREY),
+        (AnsiValue::rgb(4,2,1), *WHITE),
+        (AnsiValue::rgb(0,2,1), *WHITE),
+        (AnsiValue::rgb(2,2,3), *WHITE),
+        (AnsiValue::rgb(4,4,2), *BLACK),
+        (AnsiValue::rgb(1,4,2), *BLACK),
+    ],
+});
+
+/// Theme built on the Okabe-Ito palette, which stays distinct under the common color vision deficiencies,
+/// seats being also told apart by the shape of their glyphs
+pub static COLORBLIND_THEME: Lazy<Theme> = Lazy::new(|| Theme {
+    name: "colorblind",
+    players: [
+        (AnsiValue::rgb(5,3,0), '#'),
+        (AnsiValue::rgb(1,4,5), '+'),
+        (AnsiValue::rgb(5,5,5), '*'),
+        (AnsiValue::rgb(4,2,3), 'x'),
+        (AnsiValue::rgb(0,3,2), 'o'),
+        (AnsiValue::rgb(0,0,0), '%'),
+    ],
+    tiles: [
+        (AnsiValue::rgb(0,1,2), *WHITE),
+        (AnsiValue::rgb(4,4,4), *BLACK),
+        (AnsiValue::rgb(3,3,3), *BLACK),
+        (AnsiValue::rgb(4,2,0), *WHITE),
+        (AnsiValue::rgb(0,2,4), *WHITE),
+        (AnsiValue::rgb(1,1,1), *WHITE),
+        (AnsiValue::rgb(5,5,1), *BLACK),
+        (AnsiValue::rgb(3,5,4), *BLACK),
+    ],
+});
+
+impl Theme {
+    pub fn from_name(name: &str) -> Option<&'static Theme> {
+        [&*DEFAULT_THEME, &*COLORBLIND_THEME].iter().copied().find(|theme| theme.name == name)
+    }
+}
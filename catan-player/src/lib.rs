@@ -0,0 +1,36 @@
+pub mod display;
+mod terminal_player;
+mod spectator;
+mod action_parser;
+
+#[cfg(test)]
+mod test;
+
+pub use action_parser::parse_action;
+pub use terminal_player::TerminalPlayer;
+pub use spectator::Spectator;
+
+use catan::game::Lang;
+
+use display::{Theme, DEFAULT_THEME};
+
+/// Value following an option of the command line, like `colorblind` in `--theme colorblind`
+pub fn option_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter().position(|arg| arg == name).and_then(|i| args.get(i + 1)).map(|value| value.as_str())
+}
+
+/// Theme named by the `--theme` option, the default one without it
+pub fn theme_from_args(args: &[String]) -> Result<&'static Theme, String> {
+    match option_value(args, "--theme") {
+        Some(name) => Theme::from_name(name).ok_or_else(|| format!("Unknown theme {}, expected default or colorblind", name)),
+        None => Ok(&DEFAULT_THEME),
+    }
+}
+
+/// Language named by the `--lang` option, English without it
+pub fn lang_from_args(args: &[String]) -> Result<Lang, String> {
+    match option_value(args, "--lang") {
+        Some(code) => Lang::from_code(code).ok_or_else(|| format!("Unknown language {}, expected en or fr", code)),
+        None => Ok(Lang::En),
+    }
+}
@@ -1,19 +1,27 @@
-pub mod display;
-mod terminal_player;
-mod action_parser;
-
-pub use action_parser::parse_action;
-
-use catan::game::Game;
+use catan::game::{Game, Phase};
 use catan::player::Randomy;
 
-use terminal_player::TerminalPlayer;
+use catan_player::{TerminalPlayer, theme_from_args, lang_from_args};
 
 fn main() {
+    // Prints the phase machine instead of playing, for `dot -Tsvg`
+    if std::env::args().any(|arg| arg == "--phases") {
+        print!("{}", Phase::to_graphviz());
+        return;
+    }
+    // Draws the board with another theme, like `--theme colorblind`, and describes the actions in another language, like `--lang fr`
+    let args: Vec<String> = std::env::args().collect();
+    let (theme, lang) = match theme_from_args(&args).and_then(|theme| Ok((theme, lang_from_args(&args)?))) {
+        Ok(choice) => choice,
+        Err(error) => {
+            eprintln!("{}", error);
+            return;
+        }
+    };
     println!("[START]");
 
     let mut game = Game::new();
-    game.add_player(Box::new(TerminalPlayer::new()));
+    game.add_player(Box::new(TerminalPlayer::with_theme(theme).with_lang(lang)));
     game.add_player(Box::new(Randomy::new_player()));
     game.setup_and_play();
 
@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{stdout, Stdout, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use termion::clear;
+
+use catan::state::{State, PlayerId};
+use catan::game::{GameEvent, GameObserver, Lang};
+
+use crate::display::utils::grid_display;
+use crate::display::{PrettyGridDisplay, Theme, board_svg, pretty_public_player_hand};
+
+/// Number of lines of commentary kept under the board
+const COMMENTARY: usize = 8;
+
+/// Observer drawing the games of bots in the terminal as they are played, slowly enough to be watched
+///
+/// Each event is described under the board, every hand being shown, before pausing for the delay.
+/// The board can also be written as an SVG image after each event, to be watched from a browser.
+pub struct Spectator {
+    screen: Stdout,
+    theme: &'static Theme,
+    lang: Lang,
+    delay: Duration,
+    svg: Option<PathBuf>,
+    commentary: VecDeque<String>,
+}
+
+impl Spectator {
+    pub fn new(theme: &'static Theme, lang: Lang, delay: Duration) -> Spectator {
+        Spectator {
+            screen: stdout(),
+            theme,
+            lang,
+            delay,
+            svg: None,
+            commentary: VecDeque::new(),
+        }
+    }
+
+    /// Same spectator also writing the board to an SVG file after each event
+    pub fn with_svg(self, path: PathBuf) -> Spectator {
+        Spectator {
+            svg: Some(path),
+            ..self
+        }
+    }
+
+    fn show(&mut self, state: &State) {
+        write!(self.screen, "{clear}", clear = clear::All).expect("Failed to clear screen");
+        grid_display(&PrettyGridDisplay::new(self.theme), &mut self.screen, state).expect("Failed to draw grid");
+        for i in 0..state.player_count() {
+            pretty_public_player_hand(&mut self.screen, PlayerId::from(i), state, self.theme).expect("Failed to draw player hand");
+            writeln!(&mut self.screen).expect("Failed to return line");
+        }
+        writeln!(self.screen, "Turn {}", state.get_turn()).expect("Failed to write turn");
+        for line in self.commentary.iter() {
+            writeln!(self.screen, "{}", line).expect("Failed to write commentary");
+        }
+        self.screen.flush().expect("Failed to flush screen");
+        if let Some(path) = &self.svg {
+            if let Err(error) = fs::write(path, board_svg(state, self.theme)) {
+                eprintln!("Failed writing {}: {}", path.display(), error);
+            }
+        }
+        thread::sleep(self.delay);
+    }
+}
+
+impl GameObserver for Spectator {
+    fn new_game(&mut self, state: &State) {
+        self.commentary.clear();
+        self.show(state);
+    }
+
+    fn event(&mut self, event: &GameEvent, state: &State) {
+        if self.commentary.len() == COMMENTARY {
+            self.commentary.pop_front();
+        }
+        self.commentary.push_back(event.describe(self.lang));
+        self.show(state);
+    }
+}
@@ -4,11 +4,11 @@ use termion::clear;
 //use termion::screen::AlternateScreen;
 
 use catan::state::{State, PlayerId};
-use catan::game::{Action, Error, Phase, Notification};
-use catan::player::CatanPlayer;
+use catan::game::{Action, Error, Phase, Notification, Lang, legal};
+use catan::player::{CatanPlayer, generate_possible_actions};
 
 use crate::display::utils::grid_display;
-use crate::display::{PrettyGridDisplay, pretty_public_player_hand, pretty_private_player_hand};
+use crate::display::{PrettyGridDisplay, Theme, DEFAULT_THEME, pretty_public_player_hand, pretty_private_player_hand};
 use super::action_parser::{parse_action, parse_help};
 
 pub struct TerminalPlayer {
@@ -16,6 +16,9 @@ pub struct TerminalPlayer {
     player: PlayerId,
     bad_action: Option<Error>,
     notifications: VecDeque<Notification>,
+    theme: &'static Theme,
+    lang: Lang,
+    possible_actions: Vec<Action>,
 }
 
 impl TerminalPlayer {
@@ -26,17 +29,36 @@ impl TerminalPlayer {
             player: PlayerId::NONE,
             bad_action: None,
             notifications: VecDeque::new(),
+            theme: &DEFAULT_THEME,
+            lang: Lang::En,
+            possible_actions: Vec::new(),
+        }
+    }
+
+    /// Player drawing the game with another theme
+    pub fn with_theme(theme: &'static Theme) -> TerminalPlayer {
+        TerminalPlayer {
+            theme,
+            ..TerminalPlayer::new()
+        }
+    }
+
+    /// Same player describing the actions and notifications in another language
+    pub fn with_lang(self, lang: Lang) -> TerminalPlayer {
+        TerminalPlayer {
+            lang,
+            ..self
         }
     }
 
     pub fn write_state(&mut self, state: &State) {
-        grid_display(&PrettyGridDisplay::INSTANCE, &mut self.screen, state).expect("Failed to draw grid");
+        grid_display(&PrettyGridDisplay::new(self.theme), &mut self.screen, state).expect("Failed to draw grid");
         for i in 0..state.player_count() {
             let player = PlayerId::from(i);
             if player == self.player {
-                pretty_public_player_hand(&mut self.screen, player, state).expect("Failed to draw player hand");
+                pretty_public_player_hand(&mut self.screen, player, state, self.theme).expect("Failed to draw player hand");
             } else {
-                pretty_private_player_hand(&mut self.screen, player, state).expect("Failed to draw player hand");
+                pretty_private_player_hand(&mut self.screen, player, state, self.theme).expect("Failed to draw player hand");
 
             }
             writeln!(&mut self.screen).expect("Failed to return line");
@@ -45,10 +67,25 @@ impl TerminalPlayer {
 
     pub fn write_notifications(&mut self) {
         for notification in self.notifications.iter() {
-            writeln!(self.screen, "{:?}", notification).expect("Failed to write notification");
+            writeln!(self.screen, "{}", notification.describe(self.lang)).expect("Failed to write notification");
         }
     }
 
+    /// Numbered legal actions, several by line
+    pub fn write_legal_actions(&mut self, legal_actions: &[Action]) {
+        let mut line = String::new();
+        for (i, action) in legal_actions.iter().enumerate() {
+            let entry = format!("[{}] {}", i, action.describe(self.lang));
+            if !line.is_empty() && line.len() + entry.len() > 120 {
+                writeln!(self.screen, "{}", line).expect("Failed to write legal actions");
+                line.clear();
+            }
+            line.push_str(&entry);
+            line.push_str("  ");
+        }
+        writeln!(self.screen, "{}", line).expect("Failed to write legal actions");
+    }
+
     pub fn write_error(&mut self) {
         if let Some(error) = self.bad_action {
             writeln!(self.screen, "[ERROR] {:?}", error).expect("Failed to write error");
@@ -56,9 +93,16 @@ impl TerminalPlayer {
     }
 }
 
+impl Default for TerminalPlayer {
+    fn default() -> Self {
+        TerminalPlayer::new()
+    }
+}
+
 impl CatanPlayer for TerminalPlayer {
-    fn new_game(&mut self, position: PlayerId, _: &State) {
+    fn new_game(&mut self, position: PlayerId, state: &State) {
         self.player = position;
+        generate_possible_actions(&mut self.possible_actions, position, state);
         write!(self.screen, "{clear}", clear = clear::All).unwrap();
         writeln!(self.screen, "[New game]").unwrap();
         self.screen.flush().unwrap();
@@ -71,14 +115,23 @@ impl CatanPlayer for TerminalPlayer {
         // Displays notifications
         self.write_notifications();
         writeln!(self.screen, "{:?}", phase).unwrap();
+        let legal_actions: Vec<Action> = self.possible_actions.iter()
+            .filter(|action| legal::legal(phase, state, **action).is_ok())
+            .copied()
+            .collect();
+        self.write_legal_actions(&legal_actions);
         loop {
             // Displays previous error
             self.write_error();
             // Asks action
-            writeln!(self.screen, "Enter action among:{help}{clear}", help = parse_help(), clear = clear::AfterCursor).unwrap();
+            writeln!(self.screen, "Enter the number of a legal action, or an action among:{help}{clear}", help = parse_help(), clear = clear::AfterCursor).unwrap();
             let mut raw_action = String::new();
             stdin().read_line(&mut raw_action)
                 .expect("Failed to read line");
+            if let Some(action) = raw_action.trim().parse::<usize>().ok().and_then(|i| legal_actions.get(i)) {
+                self.bad_action = None;
+                return *action;
+            }
             match parse_action(raw_action) {
                 // If wrong keyword passed, display error and try again
                 Err(err) => {
@@ -0,0 +1,37 @@
+use std::collections::HashSet;
+
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use catan::game::Game;
+use catan::state::{State, TricellState};
+use catan::player::Randomy;
+use catan::board::layout::BoardLayout;
+
+use super::display::{Theme, DEFAULT_THEME, COLORBLIND_THEME, board_svg};
+
+#[test]
+fn six_player_board_svg() {
+   let mut game = Game::new();
+   game.board = BoardLayout::named("extension").unwrap();
+   for seed in 0..6 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state: State = Box::new(TricellState::new(game.board.layout, 6));
+   game.setup_and_play_on(&mut rng, &mut state);
+   for theme in [&*DEFAULT_THEME, &*COLORBLIND_THEME].iter() {
+      // Every seat has its own color and letter
+      assert_eq!(theme.players.iter().map(|(color, _)| color.0).collect::<HashSet<_>>().len(), 6, "{}", theme.name);
+      assert_eq!(theme.players.iter().map(|(_, letter)| *letter).collect::<HashSet<_>>().len(), 6, "{}", theme.name);
+      assert_eq!(Theme::from_name(theme.name).map(|found| found.name), Some(theme.name));
+      // The settlements and cities of the six seats are drawn in six colors
+      let svg = board_svg(&state, theme);
+      let pieces: HashSet<&str> = svg.lines()
+         .filter(|line| line.ends_with(r#"stroke="black"/>"#))
+         .filter_map(|line| line.split(r#"fill=""#).nth(1))
+         .filter_map(|rest| rest.split('"').next())
+         .collect();
+      assert_eq!(pieces.len(), 6, "{}: {:?}", theme.name, pieces);
+   }
+}
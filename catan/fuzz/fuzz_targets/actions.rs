@@ -0,0 +1,61 @@
+//! Plays arbitrary action sequences, checking the reducer never panics nor creates resources,
+//! and that the recorded game replays to the same state
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use catan::board::setup;
+use catan::game::{Phase, Action, legal, check_coherence};
+use catan::player::generate_possible_actions;
+use catan::replay::Replay;
+use catan::state::{State, StateSnapshot, PlayerId, TricellState};
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 9 {
+        return;
+    }
+    let mut seed = [0; 8];
+    seed.copy_from_slice(&data[..8]);
+    let mut rng = SmallRng::seed_from_u64(u64::from_le_bytes(seed));
+    let player_count = 2 + data[8] % 3;
+    let mut state: State = setup::random_default::<TricellState, SmallRng>(&mut rng, player_count);
+    let total = (0..player_count)
+        .fold(state.get_bank_resources(), |total, p| total + state.get_player_hand(PlayerId::from(p)).resources);
+    let mut phase = Phase::START_GAME;
+    let mut replay = Replay::new(phase, &state, (0..player_count as usize).collect());
+    let mut actions = Vec::new();
+    let mut bytes = &data[9..];
+    while let Some((&kind, rest)) = bytes.split_first() {
+        if let Phase::FinishedGame { winner: _ } = phase {
+            break;
+        }
+        // Even bytes pick a possible action, odd ones decode any action, most often illegal
+        let action = if kind % 2 == 0 {
+            let (&index, rest) = match rest.split_first() {
+                Some(split) => split,
+                None => break,
+            };
+            bytes = rest;
+            generate_possible_actions(&mut actions, phase.player(), &state);
+            actions[index as usize % actions.len()]
+        } else {
+            let mut reader = rest;
+            match bincode::deserialize_from::<_, Action>(&mut reader) {
+                Ok(action) => {
+                    bytes = reader;
+                    action
+                }
+                Err(_) => break,
+            }
+        };
+        if legal::legal(&phase, &state, action).is_ok() {
+            replay.record(&mut phase, &mut state, action, &mut rng);
+            check_coherence(&state, total).expect("Resources created or lost");
+        }
+    }
+    let (replayed_phase, replayed) = replay.state_at(replay.plies.len(), state.get_layout()).expect("Failed replaying");
+    assert_eq!(replayed_phase, phase);
+    assert_eq!(StateSnapshot::new(&replayed), StateSnapshot::new(&state));
+});
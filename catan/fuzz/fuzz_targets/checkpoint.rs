@@ -0,0 +1,29 @@
+//! Parses arbitrary checkpoints, checking restoring them and asking the legality of every action never panics
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use catan::board::layout;
+use catan::game::{Phase, Checkpoint, legal};
+use catan::player::generate_possible_actions;
+use catan::state::{StateSnapshot, TricellState};
+
+fuzz_target!(|data: &[u8]| {
+    let checkpoint = match Checkpoint::from_bytes(data) {
+        Ok(checkpoint) => checkpoint,
+        Err(_) => return,
+    };
+    let state = match checkpoint.state.restore::<TricellState>(&layout::DEFAULT) {
+        Ok(state) => state,
+        Err(_) => return,
+    };
+    StateSnapshot::new(&state).digest();
+    if let Phase::FinishedGame { winner: _ } = checkpoint.phase {
+        return;
+    }
+    let mut actions = Vec::new();
+    generate_possible_actions(&mut actions, checkpoint.phase.player(), &state);
+    for action in actions {
+        let _ = legal::legal(&checkpoint.phase, &state, action);
+    }
+});
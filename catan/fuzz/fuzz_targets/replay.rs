@@ -0,0 +1,17 @@
+//! Parses arbitrary replays, as bytes and JSON, checking parsed replays serialize back to themselves
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use catan::replay::Replay;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(replay) = Replay::from_bytes(data) {
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()).as_ref(), Ok(&replay));
+    }
+    if let Ok(json) = std::str::from_utf8(data) {
+        if let Ok(replay) = Replay::from_json(json) {
+            assert_eq!(Replay::from_json(&replay.to_json()).as_ref(), Ok(&replay));
+        }
+    }
+});
@@ -0,0 +1,77 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::ops::Sub;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// System allocator counting the allocations of every thread, installed as the global allocator by the `alloc-counter` feature
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // A reallocation counts as a new allocation of the new size
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES.fetch_add(new_size as u64, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Allocations made since the program started, or between two counts when subtracted
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Allocations {
+    pub count: u64,
+    pub bytes: u64,
+}
+
+impl Allocations {
+    /// Allocations made so far by every thread
+    pub fn now() -> Allocations {
+        Allocations {
+            count: ALLOCATIONS.load(Ordering::Relaxed),
+            bytes: BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Allocations made while running the closure, by every thread
+    pub fn during<T>(run: impl FnOnce() -> T) -> (T, Allocations) {
+        let start = Allocations::now();
+        let result = run();
+        (result, Allocations::now() - start)
+    }
+
+    /// Average count of allocations of each of the steps, like the actions or the games of a run
+    pub fn per(&self, steps: u64) -> f64 {
+        if steps == 0 { 0.0 } else { self.count as f64 / steps as f64 }
+    }
+}
+
+impl Sub for Allocations {
+    type Output = Allocations;
+
+    fn sub(self, start: Allocations) -> Allocations {
+        Allocations {
+            count: self.count - start.count,
+            bytes: self.bytes - start.bytes,
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+use rand::seq::SliceRandom;
+
+use crate::game::{Game, GameConfig, Notification, RecordVersion};
+use crate::board::layout::{self, BoardLayout};
+use crate::state::{PlayerId, TricellState, StateSnapshot};
+use crate::player::{CatanPlayer, PlayerInfo};
+use crate::metrics::MetricsSink;
+use crate::replay::Replay;
+
+/// Builds the player of an entrant for one game, from a seed
+pub type PlayerFactory = Box<dyn Fn(u64) -> Box<dyn CatanPlayer> + Send + Sync>;
+
+/// Rating of the entrants before their first game
+pub const INITIAL_ELO: f64 = 1500.0;
+
+struct Entrant {
+    info: PlayerInfo,
+    /// Rating before the tournament
+    elo: f64,
+    factory: PlayerFactory,
+}
+
+/// Sink of the metrics of an arena, with the results of the games written so far
+struct ArenaMetrics {
+    sink: Box<dyn MetricsSink>,
+    /// Games written, across the tournaments of the arena
+    step: u64,
+    /// Finished games and wins of each entrant
+    results: Vec<(u32, u32)>,
+    /// First error of the sink, after which nothing is written
+    error: Option<String>,
+}
+
+impl ArenaMetrics {
+    fn write_game(&mut self, entrants: &[Entrant], game: &GameRecord) {
+        if self.error.is_some() {
+            return;
+        }
+        self.step += 1;
+        self.results.resize(entrants.len(), (0, 0));
+        let mut written = Ok(());
+        if let Some(winner) = game.winner {
+            written = self.sink.scalar("arena/turns", self.step, game.turns as f64);
+            for (seat, entrant) in game.entrants.iter().enumerate() {
+                let (games, wins) = &mut self.results[*entrant];
+                *games += 1;
+                *wins += (seat == winner.to_usize()) as u32;
+                let win_rate = *wins as f64 / *games as f64;
+                let name = &entrants[*entrant].info.name;
+                written = written
+                    .and_then(|_| self.sink.scalar(&format!("arena/win_rate/{}", name), self.step, win_rate))
+                    .and_then(|_| self.sink.scalar(&format!("arena/victory_points/{}", name), self.step, game.victory_points[seat] as f64));
+            }
+        }
+        written = written
+            .and_then(|_| self.sink.scalar("arena/interrupted", self.step, game.winner.is_none() as u8 as f64))
+            .and_then(|_| self.sink.flush());
+        self.error = written.err();
+    }
+
+    fn write_ratings(&mut self, report: &ArenaReport) -> Result<(), String> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        for entrant in report.entrants.iter() {
+            self.sink.scalar(&format!("arena/elo/{}", entrant.info.name), self.step, entrant.elo)?;
+        }
+        self.sink.flush()
+    }
+}
+
+/// Outcome of a game of a tournament
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameRecord {
+    /// Entrant sitting at each seat
+    pub entrants: Vec<usize>,
+    /// Victory points of each seat
+    pub victory_points: Vec<u8>,
+    /// Seat of the winner, [None] if the game was interrupted or ended without a result
+    pub winner: Option<PlayerId>,
+    /// Turns played
+    pub turns: u32,
+    /// Engine and rules the game was played with
+    pub version: RecordVersion,
+    /// [StateSnapshot::board_digest] of the board of the game
+    pub board: u64,
+    /// Every action of the game, when the arena [records replays](Arena::record_replays)
+    pub replay: Option<Replay>,
+}
+
+/// Results of an entrant over a tournament
+#[derive(Clone, PartialEq, Debug)]
+pub struct EntrantReport {
+    pub info: PlayerInfo,
+    /// Finished games played
+    pub games: u32,
+    pub wins: u32,
+    /// Total victory points of the finished games
+    pub victory_points: u32,
+    pub elo: f64,
+}
+
+impl EntrantReport {
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 { 0.0 } else { self.wins as f64 / self.games as f64 }
+    }
+
+    pub fn average_vp(&self) -> f64 {
+        if self.games == 0 { 0.0 } else { self.victory_points as f64 / self.games as f64 }
+    }
+}
+
+/// Results of a tournament
+#[derive(Clone, PartialEq, Debug)]
+pub struct ArenaReport {
+    /// Every game, in the order of their seeds
+    pub games: Vec<GameRecord>,
+    /// Every entrant, in the order they were added
+    pub entrants: Vec<EntrantReport>,
+}
+
+impl ArenaReport {
+    fn new(arena: &Arena, games: Vec<GameRecord>) -> ArenaReport {
+        let mut entrants: Vec<EntrantReport> = arena.entrants.iter().map(|entrant| EntrantReport {
+            info: entrant.info.clone(),
+            games: 0,
+            wins: 0,
+            victory_points: 0,
+            elo: entrant.elo,
+        }).collect();
+        for game in games.iter() {
+            let winner = match game.winner {
+                Some(winner) => winner,
+                None => continue,
+            };
+            for (seat, entrant) in game.entrants.iter().enumerate() {
+                let report = &mut entrants[*entrant];
+                report.games += 1;
+                report.wins += (seat == winner.to_usize()) as u32;
+                report.victory_points += game.victory_points[seat] as u32;
+            }
+            // ## Elo, every pair of seats of the game being a match won by the better ranked
+            let rank = |seat: usize| (seat == winner.to_usize(), game.victory_points[seat]);
+            let seats = game.entrants.len();
+            let deltas: Vec<f64> = (0..seats).map(|a| {
+                (0..seats).filter(|b| *b != a).map(|b| {
+                    let score = match rank(a).cmp(&rank(b)) {
+                        std::cmp::Ordering::Greater => 1.0,
+                        std::cmp::Ordering::Equal => 0.5,
+                        std::cmp::Ordering::Less => 0.0,
+                    };
+                    let difference = entrants[game.entrants[b]].elo - entrants[game.entrants[a]].elo;
+                    let expected = 1.0 / (1.0 + 10f64.powf(difference / 400.0));
+                    arena.elo_k / (seats - 1) as f64 * (score - expected)
+                }).sum()
+            }).collect();
+            for (entrant, delta) in game.entrants.iter().zip(deltas) {
+                entrants[*entrant].elo += delta;
+            }
+        }
+        ArenaReport {
+            games,
+            entrants,
+        }
+    }
+
+    /// Games interrupted before their end or ended without a result, left out of the results of the entrants
+    pub fn interrupted(&self) -> usize {
+        self.games.iter().filter(|game| game.winner.is_none()).count()
+    }
+
+    /// Average turns of the finished games
+    pub fn average_length(&self) -> f64 {
+        let finished: Vec<u32> = self.games.iter().filter(|game| game.winner.is_some()).map(|game| game.turns).collect();
+        if finished.is_empty() { 0.0 } else { finished.iter().sum::<u32>() as f64 / finished.len() as f64 }
+    }
+}
+
+/// Tournament between players over seeded games, played in parallel
+///
+/// Each game seats [seats](Arena::seats) entrants, going through every combination of entrants in turn,
+/// and the board, the seating, the dice and the seed of each player all come from the seed of the game.
+/// The games, and so the report, only depend on the seed of the tournament, whatever the number of threads.
+pub struct Arena {
+    pub config: GameConfig,
+    pub board: &'static BoardLayout,
+    /// Entrants playing each game, every entrant when [None]
+    pub seats: Option<usize>,
+    /// Threads playing the games, games being played on the calling thread with a single one
+    pub threads: usize,
+    /// Largest rating change of an entrant in a game
+    pub elo_k: f64,
+    /// Games keep their [replay](GameRecord::replay), to sample positions from them
+    pub record_replays: bool,
+    entrants: Vec<Entrant>,
+    metrics: Option<Mutex<ArenaMetrics>>,
+}
+
+impl Arena {
+    pub fn new() -> Arena {
+        Arena {
+            config: GameConfig::STANDARD,
+            board: &layout::STANDARD_BOARD,
+            seats: None,
+            threads: thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1),
+            elo_k: 32.0,
+            record_replays: false,
+            entrants: Vec::new(),
+            metrics: None,
+        }
+    }
+
+    /// Writes metrics into the sink as the games are played: the `arena/turns` of each finished game, whether it was `arena/interrupted`,
+    /// and for each of its entrants its `arena/win_rate/<name>` so far and its `arena/victory_points/<name>` in the game,
+    /// and at the end of each tournament the `arena/elo/<name>` of every entrant
+    ///
+    /// The step of a game is its number among the games played by the arena, in the order they end.
+    pub fn set_metrics(&mut self, sink: Box<dyn MetricsSink>) {
+        self.metrics = Some(Mutex::new(ArenaMetrics {
+            sink,
+            step: 0,
+            results: Vec::new(),
+            error: None,
+        }));
+    }
+
+    /// Adds an entrant, whose player is built for each game by the factory
+    pub fn add(&mut self, info: PlayerInfo, factory: impl Fn(u64) -> Box<dyn CatanPlayer> + Send + Sync + 'static) {
+        self.add_rated(info, INITIAL_ELO, factory);
+    }
+
+    /// Adds an entrant already rated `elo`, like a member of a [HallOfFame](crate::hall_of_fame::HallOfFame)
+    pub fn add_rated(&mut self, info: PlayerInfo, elo: f64, factory: impl Fn(u64) -> Box<dyn CatanPlayer> + Send + Sync + 'static) {
+        self.entrants.push(Entrant {
+            info,
+            elo,
+            factory: Box::new(factory),
+        });
+    }
+
+    /// Rates the entrants as at the end of a tournament of the arena, for the next ones to go on from there
+    pub fn update_ratings(&mut self, report: &ArenaReport) {
+        for (entrant, report) in self.entrants.iter_mut().zip(report.entrants.iter()) {
+            entrant.elo = report.elo;
+        }
+    }
+
+    /// Entrants of each game, one combination after the other
+    fn tables(&self) -> Result<Vec<Vec<usize>>, String> {
+        let seats = self.seats.unwrap_or(self.entrants.len());
+        if seats < 2 || seats > self.entrants.len() {
+            return Err(format!("Can't seat {} of {} entrants", seats, self.entrants.len()));
+        }
+        let mut tables = Vec::new();
+        let mut table: Vec<usize> = (0..seats).collect();
+        loop {
+            tables.push(table.clone());
+            // Next combination, in lexicographic order
+            let i = match (0..seats).rev().find(|i| table[*i] < self.entrants.len() - seats + i) {
+                Some(i) => i,
+                None => return Ok(tables),
+            };
+            table[i] += 1;
+            for j in i + 1..seats {
+                table[j] = table[j - 1] + 1;
+            }
+        }
+    }
+
+    fn play_game(&self, table: &[usize], seed: u64) -> GameRecord {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let mut game = Game::with_config(self.config);
+        game.board = self.board;
+        game.record_replays = self.record_replays;
+        for entrant in table.iter() {
+            let entrant = &self.entrants[*entrant];
+            game.add_named_player((entrant.factory)(rng.random()), entrant.info.clone());
+        }
+        let mut state = self.board.setup::<TricellState, SmallRng>(&mut rng, table.len() as u8);
+        let board = StateSnapshot::new(&state).board_digest();
+        let mut players_order: Vec<usize> = (0..table.len()).collect();
+        players_order.shuffle(&mut rng);
+        // Anything but the end of the game is a game without a result, counted with the interrupted ones
+        let winner = match game.play(&mut rng, &mut state, players_order.clone()) {
+            Notification::GameFinished { winner } => winner.option(),
+            _ => None,
+        };
+        GameRecord {
+            entrants: players_order.iter().map(|player| table[*player]).collect(),
+            victory_points: (0..table.len()).map(|seat| state.get_player_total_vp(PlayerId::from(seat))).collect(),
+            winner,
+            turns: state.get_turn(),
+            version: RecordVersion::new(&state.get_rules()),
+            board,
+            replay: game.replay.take(),
+        }
+    }
+
+    /// Plays `games` games, the game `i` being seeded by `seed + i`
+    pub fn run(&self, games: usize, seed: u64) -> Result<ArenaReport, String> {
+        let tables = self.tables()?;
+        let play = |i: usize| {
+            let record = self.play_game(&tables[i % tables.len()], seed.wrapping_add(i as u64));
+            if let Some(metrics) = &self.metrics {
+                metrics.lock().unwrap().write_game(&self.entrants, &record);
+            }
+            record
+        };
+        if self.threads <= 1 {
+            let records = (0..games).map(play).collect();
+            return self.report(records);
+        }
+        let next = AtomicUsize::new(0);
+        let records = Mutex::new(vec![None; games]);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(games) {
+                scope.spawn(|| loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= games {
+                        break;
+                    }
+                    let record = play(i);
+                    records.lock().unwrap()[i] = Some(record);
+                });
+            }
+        });
+        let records = records.into_inner().unwrap().into_iter().map(|record| record.expect("Game not played")).collect();
+        self.report(records)
+    }
+
+    fn report(&self, records: Vec<GameRecord>) -> Result<ArenaReport, String> {
+        let report = ArenaReport::new(self, records);
+        if let Some(metrics) = &self.metrics {
+            metrics.lock().unwrap().write_ratings(&report)?;
+        }
+        Ok(report)
+    }
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Arena::new()
+    }
+}
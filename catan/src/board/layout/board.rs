@@ -0,0 +1,351 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use super::{Layout, Coord, STANDARD_BOARD, EXTENSION_BOARD};
+use crate::board::utils::CoordTransform;
+use crate::board::utils::topology::Topology;
+use crate::state::{State, StateMaker, StateTrait};
+use crate::utils::{Hex, LandHex, Resource, Resources, Harbor, DevelopmentCards};
+
+/// Layouts built from hexes, leaked once per list of hexes as states borrow them for the rest of the program
+static LAYOUTS: Lazy<Mutex<Vec<&'static Layout>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Boards loaded by [BoardLayout::named], leaked once per description
+static LOADED_BOARDS: Lazy<Mutex<Vec<&'static BoardLayout>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Layout of the hexes, shared by every board built on the same hexes
+fn layout_of(hexes: &[Coord]) -> Result<&'static Layout, String> {
+    let mut layouts = LAYOUTS.lock().unwrap();
+    if let Some(layout) = layouts.iter().find(|layout| layout.hexes == hexes) {
+        return Ok(layout);
+    }
+    let layout: &'static Layout = Box::leak(Box::new(Layout::from_hexes(hexes.to_vec()).map_err(|error| format!("Bad hexes: {:?}", error))?));
+    layouts.push(layout);
+    Ok(layout)
+}
+
+/// A layout and the pools of tiles, number tokens, harbors and development cards randomly placed on it
+#[derive(PartialEq, Debug)]
+pub struct BoardLayout {
+    pub layout: &'static Layout,
+    /// Land hexes, in the order number tokens are placed on them
+    pub land: Vec<Coord>,
+    /// Land tiles, `None` being a desert
+    pub tiles: Vec<Option<Resource>>,
+    pub numbers: Vec<u8>,
+    /// Number tokens are shuffled, otherwise placed in order along the land hexes
+    pub shuffle_numbers: bool,
+    /// Paths next to the sea where harbors are placed
+    pub harbor_paths: Vec<Coord>,
+    pub harbors: Vec<Harbor>,
+    pub development_cards: DevelopmentCards,
+    pub bank: Resources,
+    /// The layout is left unchanged by rotations around its center, which are applied randomly
+    pub symmetric: bool,
+}
+
+/// Builder of a [BoardLayout] on arbitrary hexes
+///
+/// ```
+/// use catan::board::layout::LayoutBuilder;
+/// use catan::utils::{Coord, Resource};
+///
+/// let board = LayoutBuilder::new(vec![Coord::new(0, 0), Coord::new(4, 0)])
+///     .tiles(vec![Some(Resource::Ore), None])
+///     .numbers(vec![6])
+///     .build()
+///     .unwrap();
+/// assert_eq!(board.layout.paths.len(), 11);
+/// ```
+pub struct LayoutBuilder {
+    hexes: Vec<Coord>,
+    layout: Option<&'static Layout>,
+    tiles: Vec<Option<Resource>>,
+    numbers: Vec<u8>,
+    shuffle_numbers: bool,
+    harbor_paths: Vec<Coord>,
+    harbors: Vec<Harbor>,
+    development_cards: DevelopmentCards,
+    bank: Resources,
+    symmetric: bool,
+}
+
+impl LayoutBuilder {
+    /// Board of the land hexes, number tokens being placed in their order when not shuffled
+    pub fn new(hexes: Vec<Coord>) -> LayoutBuilder {
+        LayoutBuilder {
+            hexes,
+            layout: None,
+            tiles: Vec::new(),
+            numbers: Vec::new(),
+            shuffle_numbers: true,
+            harbor_paths: Vec::new(),
+            harbors: Vec::new(),
+            development_cards: DevelopmentCards::new(),
+            bank: Resources::STARTING_BANK,
+            symmetric: false,
+        }
+    }
+
+    /// Existing layout holding the hexes, instead of a new one
+    pub fn on_layout(mut self, layout: &'static Layout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Land tiles, one per hex, `None` being a desert
+    pub fn tiles(mut self, tiles: Vec<Option<Resource>>) -> Self {
+        self.tiles = tiles;
+        self
+    }
+
+    /// Number tokens, one per land tile other than a desert
+    pub fn numbers(mut self, numbers: Vec<u8>) -> Self {
+        self.numbers = numbers;
+        self
+    }
+
+    /// Number tokens are placed in order along the hexes instead of being shuffled
+    pub fn numbers_in_order(mut self) -> Self {
+        self.shuffle_numbers = false;
+        self
+    }
+
+    /// Harbors, shuffled among the paths
+    pub fn harbors(mut self, paths: Vec<Coord>, harbors: Vec<Harbor>) -> Self {
+        self.harbor_paths = paths;
+        self.harbors = harbors;
+        self
+    }
+
+    pub fn development_cards(mut self, cards: DevelopmentCards) -> Self {
+        self.development_cards = cards;
+        self
+    }
+
+    pub fn bank(mut self, bank: Resources) -> Self {
+        self.bank = bank;
+        self
+    }
+
+    /// The hexes are left unchanged by the rotations around the center, which are then applied randomly
+    pub fn symmetric(mut self) -> Self {
+        self.symmetric = true;
+        self
+    }
+
+    /// Checks the pools fit the hexes, and the harbors lie on the coast
+    ///
+    /// A new layout is leaked the first time its hexes are built, as states borrow it for the rest of the program.
+    pub fn build(self) -> Result<BoardLayout, String> {
+        if self.hexes.is_empty() {
+            return Err("A board needs hexes".to_string());
+        }
+        if self.tiles.len() != self.hexes.len() {
+            return Err(format!("{} tiles for {} hexes", self.tiles.len(), self.hexes.len()));
+        }
+        let producing = self.tiles.iter().filter(|tile| tile.is_some()).count();
+        if self.numbers.len() != producing {
+            return Err(format!("{} number tokens for {} producing tiles", self.numbers.len(), producing));
+        }
+        if let Some(number) = self.numbers.iter().find(|number| **number < 2 || **number > 12 || **number == 7) {
+            return Err(format!("Bad number token {}", number));
+        }
+        if self.harbors.len() != self.harbor_paths.len() {
+            return Err(format!("{} harbors for {} harbor paths", self.harbors.len(), self.harbor_paths.len()));
+        }
+        let layout: &'static Layout = match self.layout {
+            Some(layout) => {
+                if let Some(hex) = self.hexes.iter().find(|hex| !layout.hexes.contains(hex)) {
+                    return Err(format!("Hex {:?} isn't on the layout", hex));
+                }
+                layout
+            }
+            None => layout_of(&self.hexes)?,
+        };
+        if let Some(path) = self.harbor_paths.iter().find(|path| !layout.paths.contains(path)) {
+            return Err(format!("Harbor on {:?}, which isn't a path of the board", path));
+        }
+        // A coast path has land on one side only
+        let coast = |path: &Coord| Coord::TOPOLOGY.path_hex_neighbours(*path)
+            .is_ok_and(|hexes| hexes.iter().filter(|hex| self.hexes.contains(hex)).count() == 1);
+        if let Some(path) = self.harbor_paths.iter().find(|path| !coast(path)) {
+            return Err(format!("Harbor on {:?}, which isn't on the coast", path));
+        }
+        Ok(BoardLayout {
+            layout,
+            land: self.hexes,
+            tiles: self.tiles,
+            numbers: self.numbers,
+            shuffle_numbers: self.shuffle_numbers,
+            harbor_paths: self.harbor_paths,
+            harbors: self.harbors,
+            development_cards: self.development_cards,
+            bank: self.bank,
+            symmetric: self.symmetric,
+        })
+    }
+}
+
+impl BoardLayout {
+    /// New state of the board with randomly placed tiles, number tokens and harbors
+    pub fn setup<T: StateMaker, R: Rng>(&self, rng: &mut R, player_count: u8) -> State {
+        let mut state = T::new_empty(self.layout, player_count);
+        self.setup_existing_state(rng, state.as_mut());
+        state
+    }
+
+    /// Randomly places the tiles, number tokens and harbors of the board on an empty state of its layout
+    pub fn setup_existing_state<S: StateTrait + ?Sized, R: Rng>(&self, rng: &mut R, state: &mut S) {
+        // hexes
+        let mut tiles = self.tiles.clone();
+        tiles.shuffle(rng);
+        let mut numbers = self.numbers.clone();
+        if self.shuffle_numbers {
+            numbers.shuffle(rng);
+        }
+        let transform = if self.symmetric { CoordTransform::random(Coord::ZERO, rng) } else { CoordTransform::new(Coord::ZERO, 0, false) };
+        let mut numbers = numbers.into_iter();
+        for (coord, tile) in self.land.iter().map(|&coord| transform.transform(coord)).zip(tiles.iter()) {
+            state.set_static_hex(coord, match tile {
+                Some(res) => Hex::Land(LandHex::Prod(*res, numbers.next().unwrap())),
+                None => Hex::Land(LandHex::Desert),
+            }).expect("Failed setting hexes");
+        }
+        // harbors
+        let mut harbors = self.harbors.clone();
+        harbors.shuffle(rng);
+        let transform = CoordTransform::new(
+            Coord::ZERO,
+            if !self.symmetric || rng.random() {0} else {3},
+            false
+        );
+        for (path, harbor) in self.harbor_paths.iter().map(|&coord| transform.transform(coord)).zip(harbors.iter()) {
+            for intersection in Coord::TOPOLOGY.path_intersection_neighbours(path).expect("Wrong path").iter() {
+                state.set_static_harbor(*intersection, *harbor).expect("Failed setting harbor");
+            }
+        }
+        // development cards and bank
+        *state.get_development_cards_mut() = self.development_cards;
+        *state.get_bank_resources_mut() = self.bank;
+    }
+
+    /// Board of a TOML or JSON description, depending on the extension of the file
+    pub fn load(path: &Path) -> Result<BoardLayout, String> {
+        let content = fs::read_to_string(path).map_err(|error| format!("Failed reading {}: {}", path.display(), error))?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => BoardLayout::from_toml(&content),
+            Some("json") => BoardLayout::from_json(&content),
+            _ => Err(format!("Unknown board description format of {}", path.display())),
+        }
+    }
+
+    /// Board of a name, `standard` or `extension`, or of the path of a TOML or JSON description,
+    /// leaked the first time such a board is loaded
+    pub fn named(name: &str) -> Result<&'static BoardLayout, String> {
+        match name {
+            "standard" => Ok(&STANDARD_BOARD),
+            "extension" => Ok(&EXTENSION_BOARD),
+            path => {
+                let board = BoardLayout::load(Path::new(path))?;
+                let mut boards = LOADED_BOARDS.lock().unwrap();
+                if let Some(loaded) = boards.iter().find(|loaded| ***loaded == board) {
+                    return Ok(loaded);
+                }
+                let board: &'static BoardLayout = Box::leak(Box::new(board));
+                boards.push(board);
+                Ok(board)
+            }
+        }
+    }
+
+    /// Board of a TOML description, see [BoardLayout::from_json] for its fields
+    pub fn from_toml(description: &str) -> Result<BoardLayout, String> {
+        let description: Description = toml::from_str(description).map_err(|error| format!("Bad board description: {}", error))?;
+        description.build()
+    }
+
+    /// Board of a JSON description
+    ///
+    /// ```json
+    /// {
+    ///     "hexes": [[0, 0], [4, 0]],
+    ///     "tiles": ["ore", "desert"],
+    ///     "numbers": [6],
+    ///     "shuffle_numbers": true,
+    ///     "harbor_paths": [[6, 0]],
+    ///     "harbors": ["generic"],
+    ///     "development_cards": { "knight": 14, "road_building": 2, "year_of_plenty": 2, "monopole": 2, "victory_point": 5 },
+    ///     "bank": 19
+    /// }
+    /// ```
+    ///
+    /// Hexes are `[x, y]` coordinates, only the hexes, tiles and numbers are required.
+    pub fn from_json(description: &str) -> Result<BoardLayout, String> {
+        let description: Description = serde_json::from_str(description).map_err(|error| format!("Bad board description: {}", error))?;
+        description.build()
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Board description of TOML and JSON files
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Description {
+    hexes: Vec<(i8, i8)>,
+    tiles: Vec<String>,
+    numbers: Vec<u8>,
+    #[serde(default = "default_true")]
+    shuffle_numbers: bool,
+    #[serde(default)]
+    harbor_paths: Vec<(i8, i8)>,
+    #[serde(default)]
+    harbors: Vec<String>,
+    development_cards: Option<DevelopmentCards>,
+    bank: Option<i8>,
+}
+
+fn parse_resource(name: &str) -> Result<Resource, String> {
+    match name {
+        "brick" => Ok(Resource::Brick),
+        "lumber" => Ok(Resource::Lumber),
+        "ore" => Ok(Resource::Ore),
+        "grain" => Ok(Resource::Grain),
+        "wool" => Ok(Resource::Wool),
+        _ => Err(format!("Unknown resource {}", name)),
+    }
+}
+
+impl Description {
+    fn build(self) -> Result<BoardLayout, String> {
+        let coord = |(x, y): (i8, i8)| Coord::new(x, y);
+        let tiles = self.tiles.iter()
+            .map(|name| if name == "desert" { Ok(None) } else { parse_resource(name).map(Some) })
+            .collect::<Result<Vec<_>, _>>()?;
+        let harbors = self.harbors.iter()
+            .map(|name| if name == "generic" { Ok(Harbor::Generic) } else { parse_resource(name).map(Harbor::Special) })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut builder = LayoutBuilder::new(self.hexes.into_iter().map(coord).collect())
+            .tiles(tiles)
+            .numbers(self.numbers)
+            .harbors(self.harbor_paths.into_iter().map(coord).collect(), harbors);
+        if !self.shuffle_numbers {
+            builder = builder.numbers_in_order();
+        }
+        if let Some(cards) = self.development_cards {
+            builder = builder.development_cards(cards);
+        }
+        if let Some(count) = self.bank {
+            builder = builder.bank(Resources::new(count, count, count, count, count));
+        }
+        builder.build()
+    }
+}
@@ -1,29 +1,8 @@
 use once_cell::sync::Lazy;
-use std::collections::BTreeSet;
-
-use super::{Layout, Coord, c};
-use crate::board::utils::topology::Topology;
-use crate::board::Error;
-
-fn paths_from_hexes(hexes: &Vec<Coord>) -> Result<Vec<Coord>, Error> {
-    let mut result = BTreeSet::<Coord>::new();
-    for hex in hexes.iter() {
-        for path in Coord::TOPOLOGY.hex_path_neighbours(*hex)?.into_iter() {
-            result.insert(path);
-        }
-    }
-    Ok(result.into_iter().collect())
-}
 
-fn intersections_from_hexes(hexes: &Vec<Coord>) -> Result<Vec<Coord>, Error> {
-    let mut result = BTreeSet::<Coord>::new();
-    for hex in hexes.iter() {
-        for intersection in Coord::TOPOLOGY.hex_intersection_neighbours(*hex)?.into_iter() {
-            result.insert(intersection);
-        }
-    }
-    Ok(result.into_iter().collect())
-}
+use super::{Layout, Coord, BoardLayout, LayoutBuilder, c};
+use crate::utils::{Resource, Resources, Harbor, DevelopmentCards};
+use super::layout::{paths_from_hexes, intersections_from_hexes};
 
 fn default_layout() -> Layout {
     let hexes = vec![
@@ -44,3 +23,104 @@ fn default_layout() -> Layout {
 pub static DEFAULT: Lazy<Layout> = Lazy::new(||
     default_layout()
 );
+
+// ## Standard board
+
+const LAND_TILES: [Option<Resource>; 19] = [
+    Some(Resource::Brick), Some(Resource::Brick), Some(Resource::Brick),
+    Some(Resource::Lumber), Some(Resource::Lumber), Some(Resource::Lumber), Some(Resource::Lumber),
+    Some(Resource::Ore), Some(Resource::Ore), Some(Resource::Ore),
+    Some(Resource::Grain), Some(Resource::Grain), Some(Resource::Grain), Some(Resource::Grain),
+    Some(Resource::Wool), Some(Resource::Wool), Some(Resource::Wool), Some(Resource::Wool),
+    None
+];
+
+const NUM_TOKENS: [u8; 18] = [
+    11, 3, 6, 5, 4, 9, 10, 8, 4, 11, 12, 9, 10, 8, 3, 6, 2, 5
+];
+
+const NUM_TRACK: [Coord; 19] = [
+    c( 0, 0),
+    c( 0, 4), c( 2, 2), c( 2,-2), c( 0,-4), c(-2,-2), c(-2, 2),
+    c(-2, 6), c( 0, 8), c( 2, 6), c( 4, 4), c( 4, 0), c( 4,-4),
+    c( 2,-6), c( 0,-8), c(-2,-6), c(-4,-4), c(-4, 0), c(-4, 4),
+];
+
+const PORT_TILES: [Harbor; 9] = [
+    Harbor::Special(Resource::Brick), Harbor::Special(Resource::Lumber), Harbor::Special(Resource::Ore), Harbor::Special(Resource::Grain), Harbor::Special(Resource::Wool),
+    Harbor::Generic, Harbor::Generic, Harbor::Generic, Harbor::Generic
+];
+
+const PORT_PATHS: [Coord; 9] = [
+    c( 0,10), c( 3, 7), c( 5, 1), c( 5,-5), c( 2,-8),
+    c(-2,-8), c(-5,-5), c(-5, 1), c(-3, 7)
+];
+
+const DVP_CARDS: DevelopmentCards =
+    DevelopmentCards {
+        knight: 14,
+        road_building: 2,
+        year_of_plenty: 2,
+        monopole: 2,
+        victory_point: 5,
+    };
+
+/// Board of the base game, on the [DEFAULT] layout, with number tokens placed along a spiral
+pub static STANDARD_BOARD: Lazy<BoardLayout> = Lazy::new(|| {
+    LayoutBuilder::new(NUM_TRACK.to_vec())
+        .on_layout(&DEFAULT)
+        .tiles(LAND_TILES.to_vec())
+        .numbers(NUM_TOKENS.to_vec())
+        .numbers_in_order()
+        .harbors(PORT_PATHS.to_vec(), PORT_TILES.to_vec())
+        .development_cards(DVP_CARDS)
+        .symmetric()
+        .build()
+        .expect("Failed building standard board")
+});
+
+// ## 5-6 player extension board
+
+/// Rows of 3 to 6 hexes, the longest one being off the center row of the coordinates
+const EXTENSION_HEXES: [Coord; 30] = [
+                        c(-4,-4), c(-4, 0), c(-4, 4),
+                   c(-2,-6), c(-2,-2), c(-2, 2), c(-2, 6),
+              c( 0,-8), c( 0,-4), c( 0, 0), c( 0, 4), c( 0, 8),
+        c( 2,-10), c( 2,-6), c( 2,-2), c( 2, 2), c( 2, 6), c( 2,10),
+              c( 4,-8), c( 4,-4), c( 4, 0), c( 4, 4), c( 4, 8),
+                   c( 6,-6), c( 6,-2), c( 6, 2), c( 6, 6),
+                        c( 8,-4), c( 8, 0), c( 8, 4),
+];
+
+const EXTENSION_PORT_PATHS: [Coord; 11] = [
+    c( 2,-12), c( 5,-9), c( 9,-5), c( 9, 1), c( 7, 7), c( 4,10),
+    c( 0,10), c(-3, 7), c(-5, 1), c(-5,-5), c(-1,-9),
+];
+
+/// Board of the 5-6 player extension, with shuffled number tokens
+pub static EXTENSION_BOARD: Lazy<BoardLayout> = Lazy::new(|| {
+    let pool = |counts: [(Resource, usize); 5]| counts.iter()
+        .flat_map(|(res, count)| std::iter::repeat_n(Some(*res), *count))
+        .collect::<Vec<_>>();
+    let mut tiles = pool([(Resource::Brick, 5), (Resource::Lumber, 6), (Resource::Ore, 5), (Resource::Grain, 6), (Resource::Wool, 6)]);
+    tiles.extend([None, None]);
+    let mut numbers = vec![2, 2, 12, 12];
+    numbers.extend([3, 4, 5, 6, 8, 9, 10, 11].iter().flat_map(|number| std::iter::repeat_n(*number, 3)));
+    let mut harbors = vec![Harbor::Generic; 5];
+    harbors.extend(Resource::ALL.iter().map(|res| Harbor::Special(*res)));
+    harbors.push(Harbor::Special(Resource::Wool));
+    LayoutBuilder::new(EXTENSION_HEXES.to_vec())
+        .tiles(tiles)
+        .numbers(numbers)
+        .harbors(EXTENSION_PORT_PATHS.to_vec(), harbors)
+        .development_cards(DevelopmentCards {
+            knight: 20,
+            road_building: 3,
+            year_of_plenty: 3,
+            monopole: 3,
+            victory_point: 5,
+        })
+        .bank(Resources::new(24, 24, 24, 24, 24))
+        .build()
+        .expect("Failed building extension board")
+});
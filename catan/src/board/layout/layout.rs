@@ -1,5 +1,9 @@
+use std::collections::BTreeSet;
+
 use super::{Coord, Error};
+use crate::board::utils::topology::Topology;
 
+#[derive(PartialEq, Debug)]
 pub struct Layout {
     pub half_width: u8,
     pub half_height: u8,
@@ -29,12 +33,26 @@ impl Layout {
         }
     }
 
+    /// Smallest layout holding the hexes, with every path and intersection around them
+    pub fn from_hexes(hexes: Vec<Coord>) -> Result<Layout, Error> {
+        let paths = paths_from_hexes(&hexes)?;
+        let intersections = intersections_from_hexes(&hexes)?;
+        let mut ray = 1;
+        loop {
+            let layout = Layout::new(ray, hexes.clone(), paths.clone(), intersections.clone());
+            if paths.iter().chain(intersections.iter()).all(|coord| layout.contains_coord(*coord)) {
+                return Ok(layout);
+            }
+            ray += 1;
+        }
+    }
+
     pub fn flat_index(&self, coord: Coord) -> Result<usize, Error> {
         let x = coord.x as isize;
         let y = coord.y as isize;
         let half_width = self.half_width as isize;
         let half_height = self.half_height as isize;
-        if -half_width <= x && x >= half_width && -half_height <= y && y >= half_height {
+        if x < -half_width || x > half_width || y < -half_height || y > half_height {
             Err(Error::OutOfBoard)
         } else {
             Ok(((half_width + x) + (half_height + y) * self.width as isize) as usize)
@@ -44,7 +62,7 @@ impl Layout {
     pub fn static_flat_index(coord: Coord, half_width: isize, half_height: isize, width: isize) -> Result<usize, Error> {
         let x = coord.x as isize;
         let y = coord.y as isize;
-        if -half_width <= x && x >= half_width && -half_height <= y && y >= half_height {
+        if x < -half_width || x > half_width || y < -half_height || y > half_height {
             Err(Error::OutOfBoard)
         } else {
             Ok(((half_width + x) + (half_height + y) * width) as usize)
@@ -68,6 +86,26 @@ impl Layout {
     }
 }
 
+pub(super) fn paths_from_hexes(hexes: &[Coord]) -> Result<Vec<Coord>, Error> {
+    let mut result = BTreeSet::<Coord>::new();
+    for hex in hexes.iter() {
+        for path in Coord::TOPOLOGY.hex_path_neighbours(*hex)?.into_iter() {
+            result.insert(path);
+        }
+    }
+    Ok(result.into_iter().collect())
+}
+
+pub(super) fn intersections_from_hexes(hexes: &[Coord]) -> Result<Vec<Coord>, Error> {
+    let mut result = BTreeSet::<Coord>::new();
+    for hex in hexes.iter() {
+        for intersection in Coord::TOPOLOGY.hex_intersection_neighbours(*hex)?.into_iter() {
+            result.insert(intersection);
+        }
+    }
+    Ok(result.into_iter().collect())
+}
+
 #[allow(dead_code)]
 pub fn print_layout(layout: &Layout) {
     println!("hexes {:?}", layout.hexes);
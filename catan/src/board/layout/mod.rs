@@ -1,8 +1,10 @@
 mod layout;
 mod default;
+mod board;
 
 pub use layout::{Layout, print_layout};
-pub use default::DEFAULT;
+pub use default::{DEFAULT, STANDARD_BOARD, EXTENSION_BOARD};
+pub use board::{BoardLayout, LayoutBuilder};
 
 use super::{Coord, Error};
 
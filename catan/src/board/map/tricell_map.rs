@@ -8,6 +8,7 @@ union Tricell<H: Copy, P: Copy, I: Copy> {
     intersection: I,
 }
 
+#[derive(Clone)]
 pub struct TricellMap<H: Copy, P: Copy, I: Copy> {
     map: Vec<Option<Tricell<H,P,I>>>,
     defaults: (H,P,I),
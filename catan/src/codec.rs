@@ -0,0 +1,216 @@
+//! Versioned encoding of the actions as indices of an action mask, with test vectors to check other implementations against
+//!
+//! Version 1 of the codec lists the actions of a seat in this order, see [action_list]:
+//!
+//! 1. `MoveThief` on each hex of the layout, for each victim relative to the seat, the seat itself first
+//! 2. `BuildRoad` on each path of the layout
+//! 3. `BuildSettlement` then `BuildCity` on each intersection of the layout
+//! 4. `RollDice`, `EndTurn`
+//! 5. `TradeBank` for each resource given and each other resource asked
+//! 6. `BuyDevelopment`, `DevelopmentKnight`, `DevelopmentRoadBuilding`, `DevelopmentYearOfPlenty`,
+//!    `ChooseFreeResource` of each resource, `DevelopmentMonopole` of each resource
+//! 7. `Keep` of each way of keeping 4 cards
+//! 8. With trade bundles, `TradeBankBundle` of each pair of trades for each pair of resources asked
+//! 9. `ProposeTrade` of 1 for 1, 2 for 1 and 1 for 2 cards, for each resource given and each other resource asked,
+//!    `AcceptTrade`, `RejectTrade`, `CounterTrade` of 1 for 1 card for each resource given and each other resource asked
+//! 10. `Discard` of each resource
+//! 11. `ReserveCity` on each intersection of the layout
+//! 12. `WithdrawTrade`, `ReviseTrade` of the terms of `ProposeTrade`
+//!
+//! Actions only ever get appended to the list, the ones of a mask keeping their index in later versions.
+//!
+//! Resources go in the order brick, lumber, ore, grain, wool, and the layout lists hexes, paths and intersections row by row.
+//! The action of an index of the mask is legal when [legal::legal] accepts it, `Resign` being never part of the mask.
+//!
+//! [Fixtures] publish the [CodecSpec] of a board, with every action written in the [notation](crate::notation) of the seat 0,
+//! and the mask of positions of games given as [Record]s, so that another implementation reading the records can compare
+//! the masks it computes, and [Fixtures::validate] checks fixtures against this engine.
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+use serde::{Serialize, Deserialize};
+
+use crate::board::layout::BoardLayout;
+use crate::game::{Action, Game, Phase, legal};
+use crate::notation::{Record, write_action, board_name};
+use crate::player::{Randomy, generate_possible_actions, generate_trade_bundles};
+use crate::replay::Replayer;
+use crate::state::{State, StateMaker, PlayerId, TricellState};
+
+/// Version of the codec described by the [module](self), changed whenever an action moves in the mask
+pub const CODEC_VERSION: u32 = 1;
+
+/// Actions of the mask of a seat, in the order of their indices
+pub fn action_list(player: PlayerId, state: &State, trade_bundles: bool) -> Vec<Action> {
+    let mut actions = Vec::new();
+    generate_possible_actions(&mut actions, player, state);
+    if trade_bundles {
+        generate_trade_bundles(&mut actions);
+    }
+    actions
+}
+
+/// Legal actions of the player to act, as a mask of its [action_list]
+pub fn action_mask(phase: &Phase, state: &State, trade_bundles: bool) -> Vec<bool> {
+    action_list(phase.player(), state, trade_bundles).iter()
+        .map(|action| legal::legal(phase, state, *action).is_ok())
+        .collect()
+}
+
+/// Mask written as `0` and `1` characters
+fn write_mask(mask: &[bool]) -> String {
+    mask.iter().map(|legal| if *legal { '1' } else { '0' }).collect()
+}
+
+/// Actions of each index of the masks of a board, for a number of players
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CodecSpec {
+    /// [CODEC_VERSION] of the codec
+    pub version: u32,
+    /// Board of the games, `standard` or `extension`
+    pub board: String,
+    pub players: u8,
+    pub trade_bundles: bool,
+    /// Each action of the mask of the seat 0, in the [notation](crate::notation)
+    pub actions: Vec<String>,
+}
+
+impl CodecSpec {
+    pub fn new(board: &'static BoardLayout, players: u8, trade_bundles: bool) -> Result<CodecSpec, String> {
+        let name = board_name(board.layout).ok_or_else(|| "Fixtures are only written for the standard and extension boards".to_string())?;
+        let state = TricellState::new_empty(board.layout, players);
+        Ok(CodecSpec {
+            version: CODEC_VERSION,
+            board: name.to_string(),
+            players,
+            trade_bundles,
+            actions: action_list(PlayerId::FIRST, &state, trade_bundles).into_iter().map(write_action).collect(),
+        })
+    }
+}
+
+/// Position of a game of the [Fixtures], and the mask of the player to act
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TestVector {
+    /// Index of the game in the fixtures
+    pub game: usize,
+    /// Number of moves of the game played before the position
+    pub ply: usize,
+    pub player: PlayerId,
+    /// Legal actions of the player, as `0` and `1` characters
+    pub mask: String,
+    /// Index in the mask of the action the game went on with, the length of the mask for an action out of it like `Resign`
+    pub played: usize,
+}
+
+/// Test vectors of the codec, see the [module](self)
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Fixtures {
+    pub spec: CodecSpec,
+    pub games: Vec<Record>,
+    pub vectors: Vec<TestVector>,
+}
+
+impl Fixtures {
+    /// Fixtures of `games` games of random players, with `positions` positions of each, spread over the game
+    pub fn generate(board: &'static BoardLayout, players: u8, trade_bundles: bool, games: usize, positions: usize, seed: u64) -> Result<Fixtures, String> {
+        let mut fixtures = Fixtures { spec: CodecSpec::new(board, players, trade_bundles)?, games: Vec::new(), vectors: Vec::new() };
+        let mut rng = SmallRng::seed_from_u64(seed);
+        for index in 0..games {
+            let mut game = Game::new();
+            game.board = board;
+            for _ in 0..players {
+                game.add_player(Box::new(Randomy::new_seeded_player(rng.random())));
+            }
+            game.record_replays = true;
+            game.setup_and_play_with_rng(&mut rng);
+            let record = game.export_record()?;
+            let replay = game.replay.take().ok_or_else(|| "No replay of the game".to_string())?;
+            let stride = (replay.plies.len() / positions.max(1)).max(1);
+            let mut replayer = Replayer::new(&replay, board.layout).map_err(|error| format!("Bad replay: {:?}", error))?;
+            let mut taken = 0;
+            while let Some(ply) = replay.plies.get(replayer.ply()) {
+                if replayer.ply() % stride == 0 && taken < positions {
+                    taken += 1;
+                    let actions = action_list(ply.player, replayer.state(), trade_bundles);
+                    fixtures.vectors.push(TestVector {
+                        game: index,
+                        ply: replayer.ply(),
+                        player: ply.player,
+                        mask: write_mask(&action_mask(replayer.phase(), replayer.state(), trade_bundles)),
+                        played: actions.iter().position(|action| *action == ply.action).unwrap_or(actions.len()),
+                    });
+                }
+                replayer.step();
+            }
+            fixtures.games.push(record);
+        }
+        Ok(fixtures)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed serializing fixtures")
+    }
+
+    pub fn from_json(json: &str) -> Result<Fixtures, String> {
+        serde_json::from_str(json).map_err(|error| format!("Bad fixtures: {}", error))
+    }
+
+    /// Checks the fixtures against this engine, giving each difference found
+    ///
+    /// The spec has to be the one of this engine, and each vector the mask and action index of its position.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        if self.spec.version != CODEC_VERSION {
+            return Err(vec![format!("Fixtures of codec version {}, this engine encodes version {}", self.spec.version, CODEC_VERSION)]);
+        }
+        let board = BoardLayout::named(&self.spec.board).map_err(|error| vec![error])?;
+        let spec = CodecSpec::new(board, self.spec.players, self.spec.trade_bundles).map_err(|error| vec![error])?;
+        let mut errors = Vec::new();
+        if spec.actions.len() != self.spec.actions.len() {
+            errors.push(format!("Spec of {} actions instead of {}", self.spec.actions.len(), spec.actions.len()));
+        }
+        for (i, (action, expected)) in self.spec.actions.iter().zip(spec.actions.iter()).enumerate() {
+            if action != expected {
+                errors.push(format!("Action {} is {} instead of {}", i, action, expected));
+            }
+        }
+        let replays: Vec<_> = self.games.iter().map(|record| record.to_replay()).collect();
+        for (i, vector) in self.vectors.iter().enumerate() {
+            let replay = match replays.get(vector.game) {
+                Some(Ok(replay)) => replay,
+                Some(Err(error)) => {
+                    errors.push(format!("Vector {}: bad game {}: {}", i, vector.game, error));
+                    continue;
+                }
+                None => {
+                    errors.push(format!("Vector {}: no game {}", i, vector.game));
+                    continue;
+                }
+            };
+            let (phase, state) = match replay.state_at(vector.ply, board.layout) {
+                Ok(position) if vector.ply < replay.plies.len() => position,
+                _ => {
+                    errors.push(format!("Vector {}: no ply {} in game {}", i, vector.ply, vector.game));
+                    continue;
+                }
+            };
+            if phase.player() != vector.player {
+                errors.push(format!("Vector {}: {:?} to act instead of {:?}", i, vector.player, phase.player()));
+                continue;
+            }
+            let mask = write_mask(&action_mask(&phase, &state, self.spec.trade_bundles));
+            let differing: Vec<usize> = (0..mask.len().max(vector.mask.len()))
+                .filter(|index| mask.as_bytes().get(*index) != vector.mask.as_bytes().get(*index))
+                .collect();
+            if !differing.is_empty() {
+                errors.push(format!("Vector {}: mask differs at indices {:?}", i, differing));
+            }
+            let actions = action_list(phase.player(), &state, self.spec.trade_bundles);
+            let played = actions.iter().position(|action| *action == replay.plies[vector.ply].action).unwrap_or(actions.len());
+            if played != vector.played {
+                errors.push(format!("Vector {}: played index {} instead of {}", i, vector.played, played));
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
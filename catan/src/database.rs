@@ -0,0 +1,363 @@
+use std::path::Path;
+
+use rand::Rng;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+use rusqlite::types::Value;
+
+use crate::arena::{ArenaReport, EntrantReport, GameRecord};
+use crate::board::Layout;
+use crate::game::{Action, ActionCategory, Checkpoint, RecordVersion};
+use crate::player::PlayerInfo;
+use crate::replay::{Replay, Replayer};
+use crate::state::{State, PlayerId};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS tournaments (
+    id INTEGER PRIMARY KEY,
+    label TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS entrants (
+    tournament INTEGER NOT NULL REFERENCES tournaments(id),
+    entrant INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    games INTEGER NOT NULL,
+    wins INTEGER NOT NULL,
+    victory_points INTEGER NOT NULL,
+    elo REAL NOT NULL,
+    PRIMARY KEY (tournament, entrant)
+);
+CREATE TABLE IF NOT EXISTS games (
+    id INTEGER PRIMARY KEY,
+    tournament INTEGER REFERENCES tournaments(id),
+    winner INTEGER,
+    turns INTEGER NOT NULL,
+    engine TEXT NOT NULL,
+    rules INTEGER NOT NULL,
+    board INTEGER NOT NULL,
+    replay BLOB
+);
+CREATE TABLE IF NOT EXISTS seats (
+    game INTEGER NOT NULL REFERENCES games(id),
+    seat INTEGER NOT NULL,
+    entrant INTEGER NOT NULL,
+    player TEXT NOT NULL,
+    victory_points INTEGER NOT NULL,
+    PRIMARY KEY (game, seat)
+);
+CREATE INDEX IF NOT EXISTS games_rules ON games(rules);
+CREATE INDEX IF NOT EXISTS games_board ON games(board);
+CREATE INDEX IF NOT EXISTS seats_player ON seats(player);
+";
+
+fn sql_error(error: rusqlite::Error) -> String {
+    format!("Database error: {}", error)
+}
+
+/// Game of a [GameDatabase], with the names of its players
+#[derive(Clone, PartialEq, Debug)]
+pub struct StoredGame {
+    pub id: i64,
+    /// Tournament the game was played in, [None] for the games inserted alone
+    pub tournament: Option<i64>,
+    /// Name of the player at each seat
+    pub players: Vec<String>,
+    /// The game, its entrants being the ones of its tournament
+    pub record: GameRecord,
+}
+
+/// Tournament of a [GameDatabase]
+#[derive(Clone, PartialEq, Debug)]
+pub struct StoredTournament {
+    pub id: i64,
+    pub label: String,
+    pub report: ArenaReport,
+}
+
+/// Criteria of the games looked up in a [GameDatabase], any game matching the default one
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct GameQuery {
+    /// Names of players who all played the game
+    pub players: Vec<String>,
+    /// [Rules::fingerprint](crate::game::Rules::fingerprint) of the rules of the game
+    pub rules: Option<u64>,
+    /// [StateSnapshot::board_digest](crate::state::StateSnapshot::board_digest) of the board of the game
+    pub board: Option<u64>,
+    pub tournament: Option<i64>,
+    /// Only the finished games, leaving out the interrupted ones
+    pub finished: bool,
+    /// Only the games with a replay
+    pub replays: bool,
+    /// Most games returned, the first inserted ones
+    pub limit: Option<usize>,
+}
+
+/// Criteria of the positions sampled from a [GameDatabase], any decision of its games matching the default one
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct PositionQuery {
+    /// Games the positions are taken from, among the ones with a replay
+    pub games: GameQuery,
+    /// First and last turns of the positions
+    pub turns: Option<(u32, u32)>,
+    /// Least and most victory points of the player to act over the best of its opponents
+    pub vp_difference: Option<(i32, i32)>,
+    /// Categories of the action played from the position, any when empty
+    pub actions: Vec<ActionCategory>,
+}
+
+/// Decision of a game of a [GameDatabase]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Position {
+    pub game: i64,
+    /// Plies of the replay of the game played before the decision
+    pub ply: usize,
+    pub player: PlayerId,
+    /// Action played from the position
+    pub action: Action,
+    pub checkpoint: Checkpoint,
+}
+
+/// SQLite store of the games and tournaments of experiments, queried by players, rules and boards
+///
+/// The games keeping their [replay](GameRecord::replay) are a dataset, whose positions are [sampled](GameDatabase::sample_positions) by criteria.
+/// Integers of 64 bits, like the fingerprints of the rules, are stored as their signed reinterpretation.
+pub struct GameDatabase {
+    connection: Connection,
+}
+
+impl GameDatabase {
+    /// Database of the file, created when missing
+    pub fn open(path: impl AsRef<Path>) -> Result<GameDatabase, String> {
+        let path = path.as_ref();
+        let connection = Connection::open(path).map_err(|error| format!("Failed opening {}: {}", path.display(), error))?;
+        GameDatabase::with_connection(connection)
+    }
+
+    /// Database living in memory, gone once dropped
+    pub fn in_memory() -> Result<GameDatabase, String> {
+        GameDatabase::with_connection(Connection::open_in_memory().map_err(sql_error)?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<GameDatabase, String> {
+        connection.execute_batch(SCHEMA).map_err(sql_error)?;
+        Ok(GameDatabase {
+            connection,
+        })
+    }
+
+    /// Inserts a tournament with its games, returning its id
+    pub fn insert_tournament(&mut self, label: &str, report: &ArenaReport) -> Result<i64, String> {
+        let transaction = self.connection.transaction().map_err(sql_error)?;
+        transaction.execute("INSERT INTO tournaments (label) VALUES (?1)", params![label]).map_err(sql_error)?;
+        let tournament = transaction.last_insert_rowid();
+        for (i, entrant) in report.entrants.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO entrants (tournament, entrant, name, games, wins, victory_points, elo) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![tournament, i as i64, entrant.info.name, entrant.games, entrant.wins, entrant.victory_points, entrant.elo],
+            ).map_err(sql_error)?;
+        }
+        let names: Vec<&str> = report.entrants.iter().map(|entrant| entrant.info.name.as_str()).collect();
+        for game in report.games.iter() {
+            insert_game(&transaction, Some(tournament), game, &names)?;
+        }
+        transaction.commit().map_err(sql_error)?;
+        Ok(tournament)
+    }
+
+    /// Inserts a game played outside of a tournament, `names` naming its entrants, returning its id
+    pub fn insert_game(&mut self, game: &GameRecord, names: &[&str]) -> Result<i64, String> {
+        let transaction = self.connection.transaction().map_err(sql_error)?;
+        let id = insert_game(&transaction, None, game, names)?;
+        transaction.commit().map_err(sql_error)?;
+        Ok(id)
+    }
+
+    /// Games matching the query, in the order they were inserted
+    pub fn games(&self, query: &GameQuery) -> Result<Vec<StoredGame>, String> {
+        let mut sql = String::from("SELECT id, tournament, winner, turns, engine, rules, board, replay FROM games WHERE 1");
+        let mut values: Vec<Value> = Vec::new();
+        for player in query.players.iter() {
+            sql.push_str(" AND id IN (SELECT game FROM seats WHERE player = ?)");
+            values.push(Value::Text(player.clone()));
+        }
+        if let Some(rules) = query.rules {
+            sql.push_str(" AND rules = ?");
+            values.push(Value::Integer(rules as i64));
+        }
+        if let Some(board) = query.board {
+            sql.push_str(" AND board = ?");
+            values.push(Value::Integer(board as i64));
+        }
+        if let Some(tournament) = query.tournament {
+            sql.push_str(" AND tournament = ?");
+            values.push(Value::Integer(tournament));
+        }
+        if query.replays {
+            sql.push_str(" AND replay IS NOT NULL");
+        }
+        if query.finished {
+            sql.push_str(" AND winner IS NOT NULL");
+        }
+        sql.push_str(" ORDER BY id");
+        if let Some(limit) = query.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        let mut statement = self.connection.prepare(&sql).map_err(sql_error)?;
+        let rows = statement.query_map(params_from_iter(values.iter()), |row| {
+            Ok((StoredGame {
+                id: row.get(0)?,
+                tournament: row.get(1)?,
+                players: Vec::new(),
+                record: GameRecord {
+                    entrants: Vec::new(),
+                    victory_points: Vec::new(),
+                    winner: row.get::<_, Option<u8>>(2)?.map(PlayerId::from),
+                    turns: row.get(3)?,
+                    version: RecordVersion {
+                        engine: row.get(4)?,
+                        rules: row.get::<_, i64>(5)? as u64,
+                    },
+                    board: row.get::<_, i64>(6)? as u64,
+                    replay: None,
+                },
+            }, row.get::<_, Option<Vec<u8>>>(7)?))
+        }).map_err(sql_error)?;
+        let mut games = Vec::new();
+        for row in rows {
+            let (mut game, replay) = row.map_err(sql_error)?;
+            game.record.replay = replay.map(|replay| Replay::from_bytes(&replay)).transpose()?;
+            self.fill_seats(&mut game)?;
+            games.push(game);
+        }
+        Ok(games)
+    }
+
+    fn fill_seats(&self, game: &mut StoredGame) -> Result<(), String> {
+        let mut statement = self.connection
+            .prepare_cached("SELECT entrant, player, victory_points FROM seats WHERE game = ?1 ORDER BY seat")
+            .map_err(sql_error)?;
+        let seats = statement.query_map(params![game.id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, u8>(2)?)))
+            .map_err(sql_error)?;
+        for seat in seats {
+            let (entrant, player, victory_points) = seat.map_err(sql_error)?;
+            game.record.entrants.push(entrant as usize);
+            game.record.victory_points.push(victory_points);
+            game.players.push(player);
+        }
+        Ok(())
+    }
+
+    /// Tournament of the id, with its games and the final results of its entrants
+    pub fn tournament(&self, id: i64) -> Result<Option<StoredTournament>, String> {
+        let label: Option<String> = self.connection
+            .query_row("SELECT label FROM tournaments WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()
+            .map_err(sql_error)?;
+        let label = match label {
+            Some(label) => label,
+            None => return Ok(None),
+        };
+        let mut statement = self.connection
+            .prepare("SELECT name, games, wins, victory_points, elo FROM entrants WHERE tournament = ?1 ORDER BY entrant")
+            .map_err(sql_error)?;
+        let entrants = statement.query_map(params![id], |row| Ok(EntrantReport {
+            info: PlayerInfo::new(&row.get::<_, String>(0)?),
+            games: row.get(1)?,
+            wins: row.get(2)?,
+            victory_points: row.get(3)?,
+            elo: row.get(4)?,
+        })).map_err(sql_error)?.collect::<Result<Vec<EntrantReport>, _>>().map_err(sql_error)?;
+        let games = self.games(&GameQuery { tournament: Some(id), ..GameQuery::default() })?
+            .into_iter()
+            .map(|game| game.record)
+            .collect();
+        Ok(Some(StoredTournament {
+            id,
+            label,
+            report: ArenaReport { games, entrants },
+        }))
+    }
+
+    /// Up to `count` positions matching the query, drawn uniformly among all of them, in the order of their games and plies
+    ///
+    /// The games are replayed on the layout, one after the other.
+    pub fn sample_positions<R: Rng>(&self, query: &PositionQuery, count: usize, layout: &'static Layout, rng: &mut R) -> Result<Vec<Position>, String> {
+        let games = self.games(&GameQuery { replays: true, ..query.games.clone() })?;
+        // Reservoir of the positions, each matching position replacing one of them with the right odds
+        let mut positions = Vec::with_capacity(count);
+        let mut matching = 0;
+        for game in games.iter() {
+            let replay = game.record.replay.as_ref().expect("Game without replay");
+            let mut replayer = Replayer::new(replay, layout).map_err(|error| format!("Failed replaying game {}: {:?}", game.id, error))?;
+            for played in replay.plies.iter() {
+                if query.matches(replayer.state(), played.player, played.action) {
+                    matching += 1;
+                    let slot = if positions.len() < count { positions.len() } else { rng.random_range(0..matching) };
+                    if slot < count {
+                        let position = Position {
+                            game: game.id,
+                            ply: replayer.ply(),
+                            player: played.player,
+                            action: played.action,
+                            checkpoint: Checkpoint::new(*replayer.phase(), replayer.state()),
+                        };
+                        if slot == positions.len() { positions.push(position) } else { positions[slot] = position }
+                    }
+                }
+                replayer.step();
+            }
+        }
+        positions.sort_by_key(|position| (position.game, position.ply));
+        Ok(positions)
+    }
+
+    /// Ids and labels of the tournaments, in the order they were inserted
+    pub fn tournaments(&self) -> Result<Vec<(i64, String)>, String> {
+        let mut statement = self.connection.prepare("SELECT id, label FROM tournaments ORDER BY id").map_err(sql_error)?;
+        let tournaments = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).map_err(sql_error)?;
+        tournaments.collect::<Result<Vec<_>, _>>().map_err(sql_error)
+    }
+}
+
+fn insert_game(connection: &Connection, tournament: Option<i64>, game: &GameRecord, names: &[&str]) -> Result<i64, String> {
+    connection.execute(
+        "INSERT INTO games (tournament, winner, turns, engine, rules, board, replay) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            tournament, game.winner.map(|winner| winner.to_u8()), game.turns, game.version.engine, game.version.rules as i64, game.board as i64,
+            game.replay.as_ref().map(|replay| replay.to_bytes()),
+        ],
+    ).map_err(sql_error)?;
+    let id = connection.last_insert_rowid();
+    for (seat, (entrant, victory_points)) in game.entrants.iter().zip(game.victory_points.iter()).enumerate() {
+        let name = names.get(*entrant).ok_or_else(|| format!("No name for entrant {}", entrant))?;
+        connection.execute(
+            "INSERT INTO seats (game, seat, entrant, player, victory_points) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, seat as i64, *entrant as i64, name, victory_points],
+        ).map_err(sql_error)?;
+    }
+    Ok(id)
+}
+
+impl PositionQuery {
+    fn matches(&self, state: &State, player: PlayerId, action: Action) -> bool {
+        if !self.actions.is_empty() && !self.actions.contains(&action.category()) {
+            return false;
+        }
+        if let Some((first, last)) = self.turns {
+            if !(first..=last).contains(&state.get_turn()) {
+                return false;
+            }
+        }
+        if let Some((least, most)) = self.vp_difference {
+            let best_opponent = (0..state.player_count()).map(PlayerId::from)
+                .filter(|opponent| *opponent != player)
+                .map(|opponent| state.get_player_total_vp(opponent))
+                .max()
+                .unwrap_or(0);
+            let difference = state.get_player_total_vp(player) as i32 - best_opponent as i32;
+            if !(least..=most).contains(&difference) {
+                return false;
+            }
+        }
+        true
+    }
+}
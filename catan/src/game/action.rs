@@ -1,12 +1,14 @@
+use serde::{Serialize, Deserialize};
+
 use crate::utils::{Coord, Resource, Resources, PlayerId};
 
 //typeCatanPlayer= u8;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+/// Action of a player, serialized by its variant index in the binary replays and checkpoints, new variants being appended after the existing ones
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Action {
     EndTurn,
     RollDice,
-    //Discard(Resources),
     MoveThief {
         hex: Coord,
         victim: PlayerId,
@@ -26,10 +28,6 @@ pub enum Action {
         given: Resource,
         asked: Resource
     },
-    //TradePlayer(Resources, Vec<bool>),
-    //TradePlayerAccept,
-    //TradePlayerAlternative(Resources),
-    //TradePlayerDecline,
 
     BuyDevelopment,
     DevelopmentKnight,
@@ -47,8 +45,46 @@ pub enum Action {
     },
 
     Exit,
+
+    /// Several bank trades at once: `trades` counts the trades paid with each resource,
+    /// each one costing the harbor rate of the player, for the `asked` resources
+    TradeBankBundle {
+        trades: Resources,
+        asked: Resources,
+    },
+    /// Offers the opponents, one after the other, to give and receive resources
+    ProposeTrade {
+        give: Resources,
+        receive: Resources,
+    },
+    AcceptTrade,
+    RejectTrade,
+    /// Answers an offer with other terms, from the point of view of the countering opponent
+    CounterTrade {
+        give: Resources,
+        receive: Resources,
+    },
+    /// Discards one card of the resource, the discard of the player being done once half of its hand is picked
+    Discard {
+        resource: Resource,
+    },
+    /// Declares the settlement the player will upgrade into a city from its next turn on, see [Rules::city_reservation](super::Rules::city_reservation)
+    ReserveCity {
+        intersection: Coord
+    },
+    /// Leaves the game, the others playing on without the player, see [Rules::resigned_pieces](super::Rules::resigned_pieces)
+    Resign,
+
+    /// Takes back the offer of the player whose turn it is as it answers a counter offer, ending the negotiation
+    WithdrawTrade,
+    /// Answers a counter offer with new terms of the player whose turn it is, offered to the same opponent
+    ReviseTrade {
+        give: Resources,
+        receive: Resources,
+    },
 }
 
+/// Kind of an action, its value staying the same across versions, new categories being appended after the existing ones
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum ActionCategory {
     EndTurn = 0,
@@ -66,6 +102,16 @@ pub enum ActionCategory {
     DevelopmentMonopole = 12,
     Keep = 13,
     Exit = 14,
+    TradeBankBundle = 15,
+    ProposeTrade = 16,
+    AcceptTrade = 17,
+    RejectTrade = 18,
+    CounterTrade = 19,
+    Discard = 20,
+    ReserveCity = 21,
+    Resign = 22,
+    WithdrawTrade = 23,
+    ReviseTrade = 24,
 }
 
 impl Action {
@@ -86,10 +132,48 @@ impl Action {
             Action::DevelopmentMonopole { resource: _ }  => ActionCategory::DevelopmentMonopole,
             Action::Keep { resources: _ } => ActionCategory::Keep,
             Action::Exit => ActionCategory::Exit,
+            Action::TradeBankBundle { trades: _, asked: _ } => ActionCategory::TradeBankBundle,
+            Action::ProposeTrade { give: _, receive: _ } => ActionCategory::ProposeTrade,
+            Action::AcceptTrade => ActionCategory::AcceptTrade,
+            Action::RejectTrade => ActionCategory::RejectTrade,
+            Action::CounterTrade { give: _, receive: _ } => ActionCategory::CounterTrade,
+            Action::Discard { resource: _ } => ActionCategory::Discard,
+            Action::ReserveCity { intersection: _ } => ActionCategory::ReserveCity,
+            Action::Resign => ActionCategory::Resign,
+            Action::WithdrawTrade => ActionCategory::WithdrawTrade,
+            Action::ReviseTrade { give: _, receive: _ } => ActionCategory::ReviseTrade,
         }
     }
 }
 
 impl ActionCategory {
-    pub const COUNT: usize = 15;
+    pub const COUNT: usize = 25;
+
+    pub const ALL: [ActionCategory; ActionCategory::COUNT] = [
+        ActionCategory::EndTurn,
+        ActionCategory::RollDice,
+        ActionCategory::MoveThief,
+        ActionCategory::BuildRoad,
+        ActionCategory::BuildSettlement,
+        ActionCategory::BuildCity,
+        ActionCategory::TradeBank,
+        ActionCategory::BuyDevelopment,
+        ActionCategory::DevelopmentKnight,
+        ActionCategory::DevelopmentRoadBuilding,
+        ActionCategory::DevelopmentYearOfPlenty,
+        ActionCategory::ChooseFreeResource,
+        ActionCategory::DevelopmentMonopole,
+        ActionCategory::Keep,
+        ActionCategory::Exit,
+        ActionCategory::TradeBankBundle,
+        ActionCategory::ProposeTrade,
+        ActionCategory::AcceptTrade,
+        ActionCategory::RejectTrade,
+        ActionCategory::CounterTrade,
+        ActionCategory::Discard,
+        ActionCategory::ReserveCity,
+        ActionCategory::Resign,
+        ActionCategory::WithdrawTrade,
+        ActionCategory::ReviseTrade,
+    ];
 }
@@ -1,17 +1,18 @@
 use rand::Rng;
 
-use crate::state::{State, PlayerId};
-use crate::utils::{Resource, Resources, Hex, LandHex, DevelopmentCard};
+use crate::state::{State, PlayerId, TransferKind, TradeAnswer, AccessibleHarbor};
+use crate::utils::{Resource, Resources, Hex, LandHex, DevelopmentCard, Coord};
 use crate::board::utils::topology::Topology;
 
-use super::{Action, Phase, TurnPhase, DevelopmentPhase, Notification};
+use super::{Action, Phase, TurnPhase, DevelopmentPhase, Notification, TradeOffer, ResignedPieces};
+use super::legal::road_building_roads;
 
 /// Applies a legal action
 ///
 /// Modifies a state by applying a given action, and/or changes the phase action.
 /// The function assumes that the action is legal and that it can be applied without problem.
 /// It is necessary to call [legal](crate::game::legal::legal) beforehand to check if the action can indeed be applied without problem
-pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Action, rng: &mut R) -> Option<Notification> {
+pub(crate) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Action, rng: &mut R) -> Option<Notification> {
     static ERROR_MESSAGE: &'static str = "Apply function failed because action supplied was illegal";
     let player = phase.player();
     match action {
@@ -24,91 +25,24 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
                 hand.development_cards += hand.new_development_cards;
                 hand.new_development_cards.clear();
             }
-            *phase = Phase::Turn {
-                player: PlayerId::from((player.to_u8() + 1) % state.player_count()),
-                turn_phase: TurnPhase::PreRoll,
-                development_phase: DevelopmentPhase::Ready,
-            };
+            if let Phase::Turn { player: turn_player, turn_phase, development_phase } = phase {
+                let player_count = state.player_count();
+                let builder = next_in_game(player, state);
+                // Each opponent in turn builds after the turn, before the next player's turn starts
+                if state.get_rules().special_building_active(player_count) && builder != *turn_player {
+                    *turn_phase = TurnPhase::SpecialBuilding(builder);
+                    *development_phase = DevelopmentPhase::Ready;
+                } else {
+                    next_turn(*turn_player, phase, state, rng);
+                }
+            }
         }
         //
         // ## Rolling Dice
         //
         Action::RollDice => {
             let roll = rng.random_range(1..=6) + rng.random_range(1..=6);
-            // ### Rolling 7
-            if roll == 7 {
-                let mut discards = Vec::<(PlayerId, Option<Resources>)>::new();
-                for p in 0..state.player_count() {
-                    let player = PlayerId::from(p);
-                    let player_resources = state.get_player_hand(player).resources;
-                    if player_resources.total() >= 8 {
-                        discards.push((player, None))
-                    }
-                }
-                if let Phase::Turn { player: _, turn_phase, development_phase: _ } = phase {
-                    if discards.is_empty() {
-                        *turn_phase = TurnPhase::MoveThief;
-                    } else {
-                        *turn_phase = TurnPhase::Discard(discards[0].0);
-                        state.hold_discards(discards);
-                    }
-                }
-                return Some(Notification::ThiefRolled);
-            // ### Rolling Production
-            } else {
-                let mut received_resources = vec![Resources::ZERO; state.player_count() as usize];
-                let mut taken_resources = Resources::ZERO;
-                // For each hex...
-                for hex in state.get_layout().hexes.iter() {
-                    // ...that produces resources...
-                    if let Hex::Land(LandHex::Prod(res, num_token)) = state.get_static_hex(*hex).expect("Failed to inspect hex") {
-                        // ..and has the correct number token and no thief on it...
-                        if num_token == roll && *hex != state.get_thief_hex() {
-                            // Look at every neighbour intersection...
-                            for intersection in state.hex_intersection_neighbours(*hex).expect("Failed to inspect intersection") {
-                                // ...with a settlement or city...
-                                if let Some((player, is_city)) = state.get_dynamic_intersection(intersection).expect("Failed to inspect intersection") {
-                                    // ...and add the resources to the corresponding player
-                                    let r = if is_city {2} else {1};
-                                    received_resources[player.to_usize()][res] += r;
-                                    taken_resources[res] += r;
-                                }
-                            }
-                        }
-                    }
-                }
-                // Check that the bank has enough Resources
-                let bank = state.get_bank_resources_mut();
-                for res in Resource::ALL.iter() {
-                    // If there is enough resource in the bank for everyone...
-                    if bank[*res] >= taken_resources[*res] {
-                        // ...remove them
-                        bank[*res] -= taken_resources[*res];
-                    } else {
-                        let mut askers: Vec<&mut Resources> = received_resources.iter_mut()
-                            .filter(|resources| resources[*res] > 0).collect();
-                        // If there is only one player that requires the resource...
-                        if askers.len() == 1 {
-                            // ...give him what is left
-                            askers[0][*res] = bank[*res];
-                            bank[*res] = 0;
-                        } else {
-                            // ...no player gets anything
-                            for asker in askers {
-                                asker[*res] = 0;
-                            }
-                        }
-                    }
-                }
-                // Then give the resources to the players
-                for (i,resources) in received_resources.iter().enumerate() {
-                    state.get_player_hand_mut(PlayerId::from(i as u8)).resources += *resources;
-                }
-                if let Phase::Turn { player: _, turn_phase, development_phase: _ } = phase {
-                    *turn_phase = TurnPhase::Free;
-                }
-                return Some(Notification::ResourcesRolled { roll, resources: received_resources });
-            }
+            return Some(roll_dice(phase, state, roll));
         }
         //
         // ## Discard
@@ -134,28 +68,18 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
                         }
                         total_discards -= 1;
                     }
-                    // Move turn
                     state.set_discard(*player, discarded);
-                    let mut iter = state.peek_discards().iter();
-                    loop {
-                        match iter.next() {
-                            Some((p, _)) => {
-                                if p == player {
-                                    match iter.next() {
-                                        Some((next_p, _)) => {
-                                            *player = *next_p;
-                                            return None;
-                                        }
-                                        None => {
-                                            state.apply_discards();
-                                            *turn_phase = TurnPhase::MoveThief;
-                                            return None; // Should return discard messages
-                                        }
-                                    }
-                                }
-                            }
-                            None => panic!("State should be holding the discarding player's discard")
-                        }
+                    next_discard(turn_phase, state);
+                }
+            }
+        }
+        Action::Discard { resource } => {
+            if let Phase::Turn { player: _ , turn_phase, development_phase: _ } = phase {
+                if let TurnPhase::Discard(player) = turn_phase {
+                    let (discarded, left) = state.get_pending_discard(*player).expect("State should be holding the discarding player's discard");
+                    state.set_discard(*player, discarded + Resources::new_one(resource, 1));
+                    if left == 1 {
+                        next_discard(turn_phase, state);
                     }
                 }
             }
@@ -173,6 +97,7 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
                         if picked < resources[*res] {
                             state.get_player_hand_mut(victim).resources[*res] -= 1;
                             state.get_player_hand_mut(player).resources[*res] += 1;
+                            state.record_transfer(TransferKind::Steal, victim, player, Resources::new_one(*res, 1));
                             break;
                         } else {
                             picked -= resources[*res];
@@ -202,9 +127,9 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
                 turn_phase: _,
                 development_phase
             } = phase {
-                // Spend free roads from Road Building Development Card
+                // Spend free roads from Road Building Development Card, the second one is lost if it can't be built
                 if let DevelopmentPhase::RoadBuildingActive { two_left } = development_phase {
-                    if *two_left {
+                    if *two_left && road_building_roads(player, state) > 0 {
                         *two_left = false;
                     } else {
                         *development_phase = DevelopmentPhase::DevelopmentPlayed;
@@ -213,6 +138,7 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
                 } else {
                     state.get_player_hand_mut(player).resources -= Resources::ROAD;
                     *state.get_bank_resources_mut() += Resources::ROAD;
+                    state.record_transfer(TransferKind::Purchase, player, PlayerId::NONE, Resources::ROAD);
                 };
             }
 
@@ -231,14 +157,18 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
             if phase.is_turn() {
                 hand.resources -= Resources::SETTLEMENT;
                 *state.get_bank_resources_mut() += Resources::SETTLEMENT;
-            } else if *phase == (Phase::InitialPlacement { player, placing_second: true, placing_road: false }) {
-                // Gives surrounding resources when placing the second settlement of the initial phase
+                state.record_transfer(TransferKind::Purchase, player, PlayerId::NONE, Resources::SETTLEMENT);
+            } else if matches!(*phase, Phase::InitialPlacement { placing_second, .. } if state.get_rules().setup_resources.grants(placing_second)) {
+                // Gives surrounding resources when placing an initial settlement the rules reward
+                let mut received = Resources::ZERO;
                 for hex in state.intersection_hex_neighbours(intersection).expect(ERROR_MESSAGE) {
                     if let Hex::Land(LandHex::Prod(res, _)) = state.get_static_hex(hex).expect(ERROR_MESSAGE) {
                         state.get_player_hand_mut(player).resources[res] += 1;
                         state.get_bank_resources_mut()[res] -= 1;
+                        received[res] += 1;
                     }
                 }
+                state.record_transfer(TransferKind::Setup, PlayerId::NONE, player, received);
             }
             // Checks if an enemy road was broken
             let mut neighbour_players = vec![false; state.player_count() as usize];
@@ -248,7 +178,7 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
                         // If it's the p-player's second neighbour road
                         if neighbour_players[p.to_usize()] {
                             // Reset his longest road (in case it just got broken by this placement)
-                            state.reset_longest_road(player);
+                            state.reset_longest_road(p);
                             // And exit, since there can only be one broken longest road per settlement
                             break;
                         // Else, if it's the first neighbour road
@@ -270,6 +200,17 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
             hand.settlement_pieces += 1;
             hand.city_pieces -= 1;
             hand.building_vp += 1;
+            if hand.reserved_city.map(|(reserved, _)| reserved) == Some(intersection) {
+                hand.reserved_city = None;
+            }
+            state.record_transfer(TransferKind::Purchase, player, PlayerId::NONE, Resources::CITY);
+        }
+        //
+        // ## Reserving a City
+        //
+        Action::ReserveCity { intersection } => {
+            let turn = state.get_turn();
+            state.get_player_hand_mut(player).reserved_city = Some((intersection, turn));
         }
         //
         // ## Trade Bank
@@ -277,11 +218,92 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
         Action::TradeBank { given, asked } => {
             let hand = state.get_player_hand_mut(player);
             let given_count = hand.harbor.rate(given) as i8;
+            let harbor = hand.harbor.used_for(given);
             hand.resources[given] -= given_count;
             hand.resources[asked] += 1;
             let bank = state.get_bank_resources_mut();
             bank[given] += given_count;
             bank[asked] -= 1;
+            state.record_transfer(TransferKind::TradeBank, player, PlayerId::NONE, Resources::new_one(given, given_count));
+            state.record_transfer(TransferKind::TradeBank, PlayerId::NONE, player, Resources::new_one(asked, 1));
+            state.record_harbor_trade(player, harbor, 1);
+        }
+        //
+        // ## Trade Bank Bundle
+        //
+        Action::TradeBankBundle { trades, asked } => {
+            let hand = state.get_player_hand_mut(player);
+            let given = hand.harbor.bundle_cost(trades);
+            hand.resources -= given;
+            hand.resources += asked;
+            let bank = state.get_bank_resources_mut();
+            *bank += given;
+            *bank -= asked;
+            state.record_transfer(TransferKind::TradeBank, player, PlayerId::NONE, given);
+            state.record_transfer(TransferKind::TradeBank, PlayerId::NONE, player, asked);
+            for resource in Resource::ALL.iter() {
+                let harbor = state.get_player_hand(player).harbor.used_for(*resource);
+                state.record_harbor_trade(player, harbor, trades[*resource] as u8);
+            }
+        }
+        //
+        // ## Trade Between Players
+        //
+        Action::ProposeTrade { give, receive } => {
+            state.record_trade_proposal(player, give, receive);
+            let partner = next_in_game(player, state);
+            if let Phase::Turn { player: _, turn_phase, development_phase: _ } = phase {
+                *turn_phase = TurnPhase::Trade(TradeOffer { partner, give, receive, countered: false });
+            }
+        }
+        Action::AcceptTrade => {
+            if let Phase::Turn { player: proposer, turn_phase, development_phase: _ } = phase {
+                if let TurnPhase::Trade(offer) = *turn_phase {
+                    let hand = state.get_player_hand_mut(*proposer);
+                    hand.resources += offer.receive - offer.give;
+                    let partner_hand = state.get_player_hand_mut(offer.partner);
+                    partner_hand.resources += offer.give - offer.receive;
+                    state.record_transfer(TransferKind::TradePlayer, *proposer, offer.partner, offer.give);
+                    state.record_transfer(TransferKind::TradePlayer, offer.partner, *proposer, offer.receive);
+                    // Accepting a counter offer closes the trade of the opponent who made it
+                    state.record_trade_response(*proposer, offer.partner, TradeAnswer::Accepted);
+                    *turn_phase = TurnPhase::Free;
+                }
+            }
+        }
+        Action::RejectTrade => {
+            if let Phase::Turn { player: proposer, turn_phase, development_phase: _ } = phase {
+                state.record_trade_response(*proposer, player, TradeAnswer::Rejected);
+                if let TurnPhase::Trade(offer) = turn_phase {
+                    // The offer moves on to the next opponent, unless it was a counter offer
+                    let next = next_in_game(offer.partner, state);
+                    if offer.countered || next == *proposer {
+                        *turn_phase = TurnPhase::Free;
+                    } else {
+                        offer.partner = next;
+                    }
+                }
+            }
+        }
+        Action::CounterTrade { give, receive } => {
+            if let Phase::Turn { player: proposer, turn_phase: TurnPhase::Trade(offer), development_phase: _ } = phase {
+                state.record_trade_response(*proposer, player, TradeAnswer::Countered);
+                offer.give = receive;
+                offer.receive = give;
+                offer.countered = true;
+            }
+        }
+        Action::WithdrawTrade => {
+            if let Phase::Turn { player: _, turn_phase, development_phase: _ } = phase {
+                *turn_phase = TurnPhase::Free;
+            }
+        }
+        Action::ReviseTrade { give, receive } => {
+            state.record_trade_proposal(player, give, receive);
+            // The opponent who countered answers the new terms
+            if let Phase::Turn { player: _, turn_phase: TurnPhase::Trade(offer), development_phase: _ } = phase {
+                *offer = TradeOffer { partner: offer.partner, give, receive, countered: false };
+            }
         }
         //
         // ## Buy Development Card
@@ -289,6 +311,7 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
         Action::BuyDevelopment => {
             state.get_player_hand_mut(player).resources -= Resources::DVP_CARD;
             *state.get_bank_resources_mut() += Resources::DVP_CARD;
+            state.record_transfer(TransferKind::Purchase, player, PlayerId::NONE, Resources::DVP_CARD);
             let development = state.get_development_cards_mut();
             let mut picked = rng.random_range(0..development.total());
             for dvp in DevelopmentCard::ALL.iter() {
@@ -307,6 +330,7 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
         //
         Action::DevelopmentKnight => {
             state.get_player_hand_mut(player).development_cards.knight -= 1;
+            state.get_player_hand_mut(player).played_development_cards.knight += 1;
             state.get_player_hand_mut(player).knights += 1;
             if let Phase::Turn { player: _, turn_phase: _, development_phase } = phase {
                 *development_phase = DevelopmentPhase::KnightActive;
@@ -318,8 +342,11 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
         //
         Action::DevelopmentRoadBuilding => {
             state.get_player_hand_mut(player).development_cards.road_building -= 1;
+            state.get_player_hand_mut(player).played_development_cards.road_building += 1;
+            let roads = road_building_roads(player, state);
             if let Phase::Turn { player: _, turn_phase: _, development_phase } = phase {
-                *development_phase = DevelopmentPhase::RoadBuildingActive { two_left: true };
+                // A free road without a piece left is lost
+                *development_phase = DevelopmentPhase::RoadBuildingActive { two_left: roads >= 2 };
             }
         }
         //
@@ -327,6 +354,7 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
         //
         Action::DevelopmentYearOfPlenty => {
             state.get_player_hand_mut(player).development_cards.year_of_plenty -= 1;
+            state.get_player_hand_mut(player).played_development_cards.year_of_plenty += 1;
             if let Phase::Turn { player: _, turn_phase: _, development_phase } = phase {
                 *development_phase = DevelopmentPhase::YearOfPlentyActive { two_left: true };
             }
@@ -334,9 +362,11 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
         Action::ChooseFreeResource { resource } => {
             state.get_bank_resources_mut()[resource] -= 1;
             state.get_player_hand_mut(player).resources[resource] += 1;
+            state.record_transfer(TransferKind::YearOfPlenty, PlayerId::NONE, player, Resources::new_one(resource, 1));
             if let Phase::Turn { player: _, turn_phase: _, development_phase } = phase {
                 if let DevelopmentPhase::YearOfPlentyActive { two_left } = development_phase {
-                    if *two_left {
+                    // The second resource is lost if the bank has none left
+                    if *two_left && state.get_bank_resources().total() > 0 {
                         *two_left = false;
                     } else {
                         *development_phase = DevelopmentPhase::DevelopmentPlayed;
@@ -349,13 +379,18 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
         //
         Action::DevelopmentMonopole { resource } => {
             state.get_player_hand_mut(player).development_cards.monopole -= 1;
+            state.get_player_hand_mut(player).played_development_cards.monopole += 1;
 
             let mut total_taken = 0;
             for p in 0..state.player_count() {
                 let p = PlayerId::from(p);
                 if p != player {
-                    total_taken += state.get_player_hand(p).resources[resource];
+                    let taken = state.get_player_hand(p).resources[resource];
+                    total_taken += taken;
                     state.get_player_hand_mut(p).resources[resource] = 0;
+                    if taken > 0 {
+                        state.record_transfer(TransferKind::Monopole, p, player, Resources::new_one(resource, taken));
+                    }
                 }
             }
             state.get_player_hand_mut(player).resources[resource] += total_taken;
@@ -364,6 +399,68 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
                 *development_phase = DevelopmentPhase::DevelopmentPlayed;
             }
         }
+        //
+        // ## Resigning
+        //
+        Action::Resign => {
+            let hand = state.get_player_hand_mut(player);
+            let (resources, mut cards) = (hand.resources, hand.development_cards);
+            cards += hand.new_development_cards;
+            hand.resources = Resources::ZERO;
+            hand.development_cards.clear();
+            hand.new_development_cards.clear();
+            hand.reserved_city = None;
+            hand.resigned = true;
+            // The cards go back to the bank and the deck
+            *state.get_bank_resources_mut() += resources;
+            *state.get_development_cards_mut() += cards;
+            if resources.total() > 0 {
+                state.record_transfer(TransferKind::Resignation, player, PlayerId::NONE, resources);
+            }
+            if state.get_rules().resigned_pieces == ResignedPieces::Removed {
+                state.remove_pieces(player);
+                let hand = state.get_player_hand_mut(player);
+                hand.building_vp = 0;
+                hand.continous_road = 0;
+                hand.harbor = AccessibleHarbor::new();
+                for p in 0..state.player_count() {
+                    let p = PlayerId::from(p);
+                    if !state.get_player_hand(p).resigned {
+                        state.reset_longest_road(p);
+                    }
+                }
+            }
+            hand_over_titles(state);
+            if let Phase::Turn { player: turn_player, turn_phase, development_phase: _ } = phase {
+                match *turn_phase {
+                    // The turn player leaving drops the pending discards, its turn ending without moving the thief
+                    TurnPhase::Discard(_) if player == *turn_player => {
+                        state.hold_discards(Vec::new());
+                        next_turn(*turn_player, phase, state, rng);
+                    }
+                    // The discard of the player is empty, its cards being already back in the bank
+                    TurnPhase::Discard(_) => {
+                        state.set_discard(player, Resources::ZERO);
+                        next_discard(turn_phase, state);
+                    }
+                    // An opponent leaving rejects the offer, which moves on to the next one
+                    TurnPhase::Trade(mut offer) if offer.partner == player && !offer.countered => {
+                        offer.partner = next_in_game(player, state);
+                        *turn_phase = if offer.partner == *turn_player { TurnPhase::Free } else { TurnPhase::Trade(offer) };
+                    }
+                    TurnPhase::SpecialBuilding(_) => {
+                        let builder = next_in_game(player, state);
+                        if builder != *turn_player {
+                            *turn_phase = TurnPhase::SpecialBuilding(builder);
+                        } else {
+                            next_turn(*turn_player, phase, state, rng);
+                        }
+                    }
+                    // The turn of the player ends, without special building
+                    _ => next_turn(*turn_player, phase, state, rng),
+                }
+            }
+        }
         _ => unimplemented!(),
     }
     // Special phase change if initial placement
@@ -395,13 +492,202 @@ pub(super) fn apply<R : Rng>(phase: &mut Phase, state: &mut State, action: Actio
     // Check if a player just won
     } else {
         // We have to check every player because there is a (very rare) chance that another player just won
-        // if we broke a third player's longest road and enabled this other player to get the longest road and go over 10 victory points
+        // if we broke a third player's longest road and enabled this other player to get the longest road and go over the victory points to win
         for p in 0..state.player_count() {
             let player = PlayerId::from(p);
-            if state.get_player_total_vp(player) >= 10 {
+            if !state.get_player_hand(player).resigned && state.get_player_total_vp(player) >= state.get_rules().victory_points {
                 *phase = Phase::FinishedGame { winner: player };
             }
         }
+        // The last player still in the game wins
+        let mut in_game = (0..state.player_count()).map(PlayerId::from).filter(|p| !state.get_player_hand(*p).resigned);
+        if let (Some(last), None) = (in_game.next(), in_game.next()) {
+            *phase = Phase::FinishedGame { winner: last };
+        }
     }
     None
 }
+
+/// Applies a roll of the dice, for the roll drawn by [apply] or one given by an exact solver
+pub(crate) fn roll_dice(phase: &mut Phase, state: &mut State, roll: u8) -> Notification {
+    // ### Rolling 7 without robber
+    if roll == 7 && !state.get_rules().robber_active(state.get_turn()) {
+        if let Phase::Turn { player: _, turn_phase, development_phase: _ } = phase {
+            *turn_phase = TurnPhase::Free;
+        }
+        Notification::ResourcesRolled { roll, resources: vec![Resources::ZERO; state.player_count() as usize] }
+    // ### Rolling 7
+    } else if roll == 7 {
+        let mut discards = Vec::<(PlayerId, Option<Resources>)>::new();
+        for p in 0..state.player_count() {
+            let player = PlayerId::from(p);
+            let player_resources = state.get_player_hand(player).resources;
+            if player_resources.total() as i32 > state.get_rules().discard_limit as i32 {
+                discards.push((player, None))
+            }
+        }
+        if let Phase::Turn { player: _, turn_phase, development_phase: _ } = phase {
+            if discards.is_empty() {
+                *turn_phase = TurnPhase::MoveThief;
+            } else {
+                *turn_phase = TurnPhase::Discard(discards[0].0);
+                state.hold_discards(discards);
+            }
+        }
+        Notification::ThiefRolled
+    // ### Rolling Production
+    } else {
+        let mut received_resources = vec![Resources::ZERO; state.player_count() as usize];
+        let mut taken_resources = Resources::ZERO;
+        // For each hex...
+        for hex in state.get_layout().hexes.iter() {
+            // ...that produces resources...
+            if let Hex::Land(LandHex::Prod(res, num_token)) = state.get_static_hex(*hex).expect("Failed to inspect hex") {
+                // ..and has the correct number token and no thief on it...
+                if num_token == roll && *hex != state.get_thief_hex() {
+                    // Look at every neighbour intersection...
+                    for intersection in state.hex_intersection_neighbours(*hex).expect("Failed to inspect intersection") {
+                        // ...with a settlement or city...
+                        if let Some((player, is_city)) = state.get_dynamic_intersection(intersection).expect("Failed to inspect intersection") {
+                            // ...of a player still in the game...
+                            if state.get_player_hand(player).resigned {
+                                continue;
+                            }
+                            // ...and add the resources to the corresponding player
+                            let r = if is_city {2} else {1};
+                            received_resources[player.to_usize()][res] += r;
+                            taken_resources[res] += r;
+                        }
+                    }
+                }
+            }
+        }
+        // Check that the bank has enough Resources
+        let bank = state.get_bank_resources_mut();
+        for res in Resource::ALL.iter() {
+            // If there is enough resource in the bank for everyone...
+            if bank[*res] >= taken_resources[*res] {
+                // ...remove them
+                bank[*res] -= taken_resources[*res];
+            } else {
+                let mut askers: Vec<&mut Resources> = received_resources.iter_mut()
+                    .filter(|resources| resources[*res] > 0).collect();
+                // If there is only one player that requires the resource...
+                if askers.len() == 1 {
+                    // ...give him what is left
+                    askers[0][*res] = bank[*res];
+                    bank[*res] = 0;
+                } else {
+                    // ...no player gets anything
+                    for asker in askers {
+                        asker[*res] = 0;
+                    }
+                }
+            }
+        }
+        // Then give the resources to the players
+        for (i,resources) in received_resources.iter().enumerate() {
+            let receiver = PlayerId::from(i as u8);
+            state.get_player_hand_mut(receiver).resources += *resources;
+            if resources.total() > 0 {
+                state.record_transfer(TransferKind::Production, PlayerId::NONE, receiver, *resources);
+            }
+        }
+        if let Phase::Turn { player: _, turn_phase, development_phase: _ } = phase {
+            *turn_phase = TurnPhase::Free;
+        }
+        Notification::ResourcesRolled { roll, resources: received_resources }
+    }
+}
+
+/// Next player clockwise still in the game, the player itself when it is the last one
+fn next_in_game(player: PlayerId, state: &State) -> PlayerId {
+    let player_count = state.player_count();
+    (1..=player_count)
+        .map(|offset| PlayerId::from((player.to_u8() + offset) % player_count))
+        .find(|p| !state.get_player_hand(*p).resigned)
+        .unwrap_or(player)
+}
+
+/// Starts the turn of the next player still in the game
+fn next_turn<R: Rng>(turn_player: PlayerId, phase: &mut Phase, state: &mut State, rng: &mut R) {
+    state.next_turn();
+    if state.get_rules().token_swap_due(state.get_turn()) {
+        swap_random_tokens(state, rng);
+    }
+    *phase = Phase::Turn {
+        player: next_in_game(turn_player, state),
+        turn_phase: TurnPhase::PreRoll,
+        development_phase: DevelopmentPhase::Ready,
+    };
+}
+
+/// Swaps the number tokens of two producing hexes drawn at random, see [Rules::token_swap_every](super::Rules::token_swap_every)
+fn swap_random_tokens<R: Rng>(state: &mut State, rng: &mut R) {
+    let producing: Vec<Coord> = state.get_layout().hexes.iter().copied()
+        .filter(|hex| matches!(state.get_static_hex(*hex), Ok(Hex::Land(LandHex::Prod(_, _)))))
+        .collect();
+    if producing.len() < 2 {
+        return;
+    }
+    let first = rng.random_range(0..producing.len());
+    let mut second = rng.random_range(0..producing.len() - 1);
+    if second >= first {
+        second += 1;
+    }
+    state.swap_number_tokens(producing[first], producing[second]).expect("Failed swapping number tokens");
+}
+
+/// Gives the longest road and the largest army to the players standing out among the ones still in the game,
+/// a holder keeping its title as long as it is at least tied
+fn hand_over_titles(state: &mut State) {
+    let in_game: Vec<PlayerId> = (0..state.player_count()).map(PlayerId::from).filter(|p| !state.get_player_hand(*p).resigned).collect();
+    let holder = |title: Option<(PlayerId, u8)>, length: &dyn Fn(PlayerId) -> u8, minimum: u8| {
+        let best = in_game.iter().map(|p| length(*p)).max().unwrap_or(0);
+        match title {
+            Some((p, _)) if in_game.contains(&p) && length(p) == best => Some(p),
+            _ => {
+                let mut leaders = in_game.iter().filter(|p| length(**p) == best && best >= minimum);
+                match (leaders.next(), leaders.next()) {
+                    (Some(p), None) => Some(*p),
+                    _ => None,
+                }
+            }
+        }
+    };
+    let longest_road = holder(state.get_longest_road(), &|p| state.get_player_hand(p).continous_road, 5);
+    let largest_army = holder(state.get_largest_army(), &|p| state.get_player_hand(p).knights, 3);
+    state.set_longest_road(longest_road);
+    state.set_largest_army(largest_army);
+}
+
+/// Moves the discard to the next player holding one, or applies every discard and lets the thief be moved after the last one
+fn next_discard(turn_phase: &mut TurnPhase, state: &mut State) {
+    if let TurnPhase::Discard(player) = turn_phase {
+        let mut iter = state.peek_discards().iter();
+        loop {
+            match iter.next() {
+                Some((p, _)) => {
+                    if p == player {
+                        match iter.next() {
+                            Some((next_p, _)) => {
+                                *player = *next_p;
+                                return;
+                            }
+                            None => {
+                                let discards = state.peek_discards().clone();
+                                for (p, discarded) in discards {
+                                    state.record_transfer(TransferKind::Discard, p, PlayerId::NONE, discarded.unwrap());
+                                }
+                                state.apply_discards();
+                                *turn_phase = TurnPhase::MoveThief;
+                                return;
+                            }
+                        }
+                    }
+                }
+                None => panic!("State should be holding the discarding player's discard")
+            }
+        }
+    }
+}
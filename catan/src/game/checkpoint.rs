@@ -0,0 +1,101 @@
+use serde::{Serialize, Deserialize};
+
+use crate::state::{State, StateSnapshot, PlayerId};
+use super::{Phase, TurnPhase, Rules};
+
+/// Version of the crate, stamped on the records it writes
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Engine and rules a record was written with, checked when it is loaded
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct RecordVersion {
+    /// [ENGINE_VERSION] of the engine which wrote the record
+    pub engine: String,
+    /// [Rules::fingerprint] of the rules of the game
+    pub rules: u64,
+}
+
+impl RecordVersion {
+    pub fn new(rules: &Rules) -> RecordVersion {
+        RecordVersion {
+            engine: ENGINE_VERSION.to_string(),
+            rules: rules.fingerprint(),
+        }
+    }
+
+    /// Checks the record was written by a compatible engine, and that its rules are still read as the ones it was played under
+    ///
+    /// Engines are compatible when their versions share their first non-zero number, as semantic versioning goes.
+    pub fn check(&self, rules: &Rules) -> Result<(), String> {
+        let compatible = |version: &str| {
+            let mut numbers = version.split('.');
+            match numbers.next() {
+                Some("0") => ["0", numbers.next().unwrap_or("")].join("."),
+                major => major.unwrap_or("").to_string(),
+            }
+        };
+        if compatible(&self.engine) != compatible(ENGINE_VERSION) {
+            return Err(format!("Record of engine {}, incompatible with engine {}", self.engine, ENGINE_VERSION));
+        }
+        if self.rules != rules.fingerprint() {
+            return Err(format!("Record of rules {:016x}, read as rules {:016x} {:?}", self.rules, rules.fingerprint(), rules));
+        }
+        Ok(())
+    }
+}
+
+/// A game paused between two actions, which can be saved and resumed with [Game::resume](super::Game::resume)
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Phase of the next decision
+    pub phase: Phase,
+    pub state: StateSnapshot,
+    pub version: RecordVersion,
+}
+
+impl Checkpoint {
+    pub fn new(phase: Phase, state: &State) -> Checkpoint {
+        let state = StateSnapshot::new(state);
+        Checkpoint {
+            phase,
+            version: RecordVersion::new(&state.rules),
+            state,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("Failed serializing checkpoint")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Checkpoint, String> {
+        let checkpoint: Checkpoint = bincode::deserialize(bytes).map_err(|error| format!("Bad checkpoint: {}", error))?;
+        checkpoint.check()?;
+        Ok(checkpoint)
+    }
+
+    /// Checks the checkpoint was written by a compatible engine under its rules, with its players at the table
+    pub fn check(&self) -> Result<(), String> {
+        self.version.check(&self.state.rules).map_err(|error| format!("Bad checkpoint: {}", error))?;
+        self.check_seats()
+    }
+
+    /// Checks the players of the phase sit at the table
+    fn check_seats(&self) -> Result<(), String> {
+        let seated = |player: PlayerId| player.to_usize() < self.state.players.len();
+        let valid = match self.phase {
+            Phase::InitialPlacement { player, placing_second: _, placing_road: _ } => seated(player),
+            Phase::Turn { player, turn_phase, development_phase: _ } => seated(player) && match turn_phase {
+                TurnPhase::Discard(discarding) => seated(discarding),
+                TurnPhase::Trade(offer) => seated(offer.partner),
+                TurnPhase::SpecialBuilding(builder) => seated(builder),
+                _ => true,
+            },
+            Phase::FinishedGame { winner: _ } => true,
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("Bad checkpoint: {:?} out of the {} players", self.phase, self.state.players.len()))
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::board::layout::BoardLayout;
+use crate::state::{State, PlayerId, TransferKind};
+use crate::utils::{Resource, Resources, DevelopmentCards};
+use super::Rules;
+
+/// Rules and starting conditions of the games played by a [Game](super::Game)
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GameConfig {
+    pub rules: Rules,
+    /// Resources each player takes from the bank before the game starts
+    pub initial_resources: Resources,
+    /// Bank replacing the one of the board
+    pub bank: Option<Resources>,
+    /// Development deck replacing the one of the board
+    pub development_cards: Option<DevelopmentCards>,
+}
+
+impl GameConfig {
+    pub const STANDARD: GameConfig = GameConfig {
+        rules: Rules::STANDARD,
+        initial_resources: Resources::ZERO,
+        bank: None,
+        development_cards: None,
+    };
+
+    /// Checks that the bank of games on the board can give the initial resources of every player
+    pub fn check(&self, board: &BoardLayout, player_count: u8) -> Result<(), String> {
+        let bank = self.bank.unwrap_or(board.bank);
+        for res in Resource::ALL.iter() {
+            if self.initial_resources[*res] < 0 {
+                return Err(format!("Negative initial {:?}", res));
+            }
+            if (bank[*res] as i32) < self.initial_resources[*res] as i32 * player_count as i32 {
+                return Err(format!("The bank {:?} can't give the initial resources of {} players", bank, player_count));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the bank and development deck of a new state, and hands out the initial resources
+    pub fn setup(&self, state: &mut State) -> Result<(), String> {
+        if let Some(bank) = self.bank {
+            *state.get_bank_resources_mut() = bank;
+        }
+        if let Some(development_cards) = self.development_cards {
+            *state.get_development_cards_mut() = development_cards;
+        }
+        if self.initial_resources == Resources::ZERO {
+            return Ok(());
+        }
+        for p in 0..state.player_count() {
+            let player = PlayerId::from(p);
+            let bank = state.get_bank_resources();
+            if Resource::ALL.iter().any(|res| bank[*res] < self.initial_resources[*res]) {
+                return Err(format!("The bank {:?} can't give the initial resources of every player", bank));
+            }
+            *state.get_bank_resources_mut() -= self.initial_resources;
+            state.get_player_hand_mut(player).resources += self.initial_resources;
+            state.record_transfer(TransferKind::Setup, PlayerId::NONE, player, self.initial_resources);
+        }
+        Ok(())
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig::STANDARD
+    }
+}
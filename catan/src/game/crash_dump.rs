@@ -0,0 +1,55 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::{Serialize, Deserialize};
+
+use crate::state::{State, StateSnapshot, PlayerId};
+use crate::replay::Replay;
+use super::{Action, Phase, RecordVersion};
+
+/// Plies kept in the [history](CrashDump::history) of a dump
+pub const CRASH_HISTORY: usize = 32;
+
+static DUMPS_WRITTEN: AtomicUsize = AtomicUsize::new(0);
+
+/// Game at the moment the engine panicked, written by [Game](super::Game) into its [crash_dumps](super::Game::crash_dumps) directory
+///
+/// The state is the one at the panic, possibly halfway through the pending action.
+/// Games recording their replays also dump the replay up to the pending action, which plays the game back to the panic.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CrashDump {
+    /// Message of the panic
+    pub message: String,
+    /// Phase of the decision in progress
+    pub phase: Phase,
+    /// Legal action being played, None when the panic came while the player picked it
+    pub action: Option<Action>,
+    /// Player and action of the last plies, the oldest first
+    pub history: Vec<(PlayerId, Action)>,
+    /// None when the state couldn't be read
+    pub state: Option<StateSnapshot>,
+    pub replay: Option<Replay>,
+    pub version: RecordVersion,
+}
+
+impl CrashDump {
+    pub fn new(message: String, phase: Phase, action: Option<Action>, history: Vec<(PlayerId, Action)>, state: &State, replay: Option<Replay>) -> CrashDump {
+        let rules = state.get_rules();
+        // The state may be left incoherent by the panic, and fail to be read
+        let state = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| StateSnapshot::new(state))).ok();
+        CrashDump { message, phase, action, history, state, replay, version: RecordVersion::new(&rules) }
+    }
+
+    /// Writes the dump as JSON into a new file of the directory, returning its path
+    pub fn write(&self, directory: &Path) -> Result<PathBuf, String> {
+        fs::create_dir_all(directory).map_err(|error| format!("Failed creating {}: {}", directory.display(), error))?;
+        let path = directory.join(format!("catan-crash-{}-{}.json", std::process::id(), DUMPS_WRITTEN.fetch_add(1, Ordering::Relaxed)));
+        let json = serde_json::to_string(self).map_err(|error| format!("Failed serializing crash dump: {}", error))?;
+        fs::write(&path, json).map_err(|error| format!("Failed writing {}: {}", path.display(), error))?;
+        Ok(path)
+    }
+
+    pub fn from_json(json: &str) -> Result<CrashDump, String> {
+        serde_json::from_str(json).map_err(|error| format!("Bad crash dump: {}", error))
+    }
+}
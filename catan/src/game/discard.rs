@@ -0,0 +1,98 @@
+use crate::utils::{Resource, Resources};
+use super::Action;
+
+/// Number of ways to split `total` cards into `parts` resource counts
+fn compositions(total: usize, parts: usize) -> usize {
+    if parts == 0 {
+        return (total == 0) as usize;
+    }
+    // C(total + parts - 1, parts - 1)
+    let mut count = 1;
+    for i in 1..parts {
+        count = count * (total + i) / i;
+    }
+    count
+}
+
+/// Codec between the discards of a given number of cards and a bounded range of indices
+///
+/// A discard is a multiset of resources: for `count` cards there are `(count+4 choose 4)` of them,
+/// indexed in the lexicographic order of their (brick, lumber, ore, grain, wool) counts.
+/// Index 0 discards only wool, the last index discards only brick.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DiscardCodec {
+    count: u8,
+}
+
+impl DiscardCodec {
+    pub fn new(count: u8) -> DiscardCodec {
+        DiscardCodec { count }
+    }
+
+    /// Codec of the cards the hand has to discard, half of them rounded down
+    pub fn for_hand(hand: Resources) -> DiscardCodec {
+        DiscardCodec::new((hand.total() / 2) as u8)
+    }
+
+    pub fn count(&self) -> u8 {
+        self.count
+    }
+
+    /// Number of different discards
+    pub fn size(&self) -> usize {
+        compositions(self.count as usize, Resource::COUNT)
+    }
+
+    /// Index of the discarded resources, None if they are not exactly `count` cards
+    pub fn encode(&self, discarded: Resources) -> Option<usize> {
+        if discarded.total() != self.count as i8 || Resource::ALL.iter().any(|res| discarded[*res] < 0) {
+            return None;
+        }
+        let mut index = 0;
+        let mut left = self.count as usize;
+        for (i, res) in Resource::ALL.iter().enumerate().take(Resource::COUNT - 1) {
+            let parts = Resource::COUNT - 1 - i;
+            for value in 0..discarded[*res] as usize {
+                index += compositions(left - value, parts);
+            }
+            left -= discarded[*res] as usize;
+        }
+        Some(index)
+    }
+
+    /// Discarded resources of the index, None if out of range
+    pub fn decode(&self, mut index: usize) -> Option<Resources> {
+        if index >= self.size() {
+            return None;
+        }
+        let mut discarded = Resources::ZERO;
+        let mut left = self.count as usize;
+        for (i, res) in Resource::ALL.iter().enumerate().take(Resource::COUNT - 1) {
+            let parts = Resource::COUNT - 1 - i;
+            let mut value = 0;
+            while index >= compositions(left - value, parts) {
+                index -= compositions(left - value, parts);
+                value += 1;
+            }
+            discarded[*res] = value as i8;
+            left -= value;
+        }
+        discarded[Resource::Wool] = left as i8;
+        Some(discarded)
+    }
+
+    /// Legal discards of the hand: the ones it has enough resources for
+    pub fn mask(&self, hand: Resources) -> Vec<bool> {
+        (0..self.size())
+            .map(|index| {
+                let discarded = self.decode(index).unwrap();
+                Resource::ALL.iter().all(|res| discarded[*res] <= hand[*res])
+            })
+            .collect()
+    }
+
+    /// Keep action discarding the resources of the index from the hand
+    pub fn keep(&self, hand: Resources, index: usize) -> Option<Action> {
+        self.decode(index).map(|discarded| Action::Keep { resources: hand - discarded })
+    }
+}
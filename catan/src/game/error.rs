@@ -31,9 +31,20 @@ pub enum Error {
     NoCard {
         card_type: DevelopmentCard
     },
+    DevelopmentCapReached {
+        card_type: DevelopmentCard
+    },
     ThiefNotMoved {
         hex: Coord,
-    }
+    },
+    /// The settlement wasn't reserved on an earlier turn for its upgrade into a city
+    CityNotReserved {
+        coord: Coord,
+    },
+    /// The player whose turn it is already offered as many trades as the rules allow in a turn
+    TradeProposalLimit {
+        limit: u8,
+    },
 }
 
 impl From<BoardError> for Error {
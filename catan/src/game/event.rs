@@ -0,0 +1,166 @@
+use std::sync::{Arc, Mutex};
+use serde::{Serialize, Deserialize};
+
+use crate::utils::{Coord, Resource, Resources};
+use crate::state::{State, PlayerId, Transfer, TransferKind};
+use super::{Action, Phase, Notification, Rules};
+
+/// Kind of a building placed on the board
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Building {
+    Road,
+    Settlement,
+    City,
+}
+
+/// Something which happened in a game, told to the [GameObserver]s of the [Game](super::Game)
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GameEvent {
+    DiceRolled {
+        player: PlayerId,
+        roll: u8,
+    },
+    /// Resources produced by a roll, for each player, when any player received some
+    ResourcesDistributed {
+        roll: u8,
+        resources: Vec<Resources>,
+    },
+    RobberMoved {
+        player: PlayerId,
+        hex: Coord,
+    },
+    /// A card stolen by the thief, its resource [None] when hidden from the observer, see [GameEvent::seen_by]
+    CardStolen {
+        thief: PlayerId,
+        victim: PlayerId,
+        resource: Option<Resource>,
+    },
+    BuildingPlaced {
+        player: PlayerId,
+        building: Building,
+        coord: Coord,
+    },
+    /// Resources exchanged by the player with the bank or with a partner
+    TradeExecuted {
+        player: PlayerId,
+        /// [None] for the bank
+        partner: Option<PlayerId>,
+        given: Resources,
+        received: Resources,
+    },
+    /// New holder of the longest road and its length, [None] when nobody holds it anymore
+    LongestRoadChanged {
+        holder: Option<(PlayerId, u8)>,
+    },
+    /// End of the game, not told for the games interrupted
+    GameEnded {
+        winner: PlayerId,
+    },
+}
+
+impl GameEvent {
+    /// The event as the player sees it, the stolen cards of other players being hidden when the hands are
+    pub fn seen_by(&self, player: PlayerId, rules: &Rules) -> GameEvent {
+        match *self {
+            GameEvent::CardStolen { thief, victim, resource: _ } if rules.hidden_hands && player != thief && player != victim => {
+                GameEvent::CardStolen { thief, victim, resource: None }
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Events of an action just applied
+    ///
+    /// `transfers` are the ones it recorded in the ledger, and `longest_road` the holder of the longest road before it.
+    pub(crate) fn of_action(
+        player: PlayerId,
+        action: Action,
+        notification: Option<&Notification>,
+        transfers: &[Transfer],
+        longest_road: Option<(PlayerId, u8)>,
+        phase: &Phase,
+        state: &State,
+    ) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if let Some(roll) = notification.and_then(|notification| notification.roll()) {
+            events.push(GameEvent::DiceRolled { player, roll });
+        }
+        if let Some(Notification::ResourcesRolled { roll, resources }) = notification {
+            if resources.iter().any(|resources| resources.total() > 0) {
+                events.push(GameEvent::ResourcesDistributed { roll: *roll, resources: resources.clone() });
+            }
+        }
+        match action {
+            Action::MoveThief { hex, victim: _ } => events.push(GameEvent::RobberMoved { player, hex }),
+            Action::BuildRoad { path } => events.push(GameEvent::BuildingPlaced { player, building: Building::Road, coord: path }),
+            Action::BuildSettlement { intersection } => {
+                events.push(GameEvent::BuildingPlaced { player, building: Building::Settlement, coord: intersection });
+            }
+            Action::BuildCity { intersection } => {
+                events.push(GameEvent::BuildingPlaced { player, building: Building::City, coord: intersection });
+            }
+            _ => (),
+        }
+        for transfer in transfers.iter().filter(|transfer| transfer.kind == TransferKind::Steal) {
+            let resource = Resource::ALL.iter().copied().find(|res| transfer.resources[*res] > 0);
+            events.push(GameEvent::CardStolen { thief: transfer.to, victim: transfer.from, resource });
+        }
+        // Trades are recorded as what the player gives followed by what it receives
+        let trades: Vec<&Transfer> = transfers.iter()
+            .filter(|transfer| transfer.kind == TransferKind::TradeBank || transfer.kind == TransferKind::TradePlayer)
+            .collect();
+        for trade in trades.chunks(2) {
+            if let [given, received] = trade {
+                events.push(GameEvent::TradeExecuted {
+                    player: given.from,
+                    partner: given.to.option(),
+                    given: given.resources,
+                    received: received.resources,
+                });
+            }
+        }
+        if state.get_longest_road() != longest_road {
+            events.push(GameEvent::LongestRoadChanged { holder: state.get_longest_road() });
+        }
+        if let Phase::FinishedGame { winner } = *phase {
+            events.push(GameEvent::GameEnded { winner });
+        }
+        events
+    }
+}
+
+/// Observer of the games played by a [Game](super::Game), subscribed with [Game::subscribe](super::Game::subscribe)
+pub trait GameObserver {
+    /// Called before the first action of each game
+    fn new_game(&mut self, _state: &State) {}
+
+    /// Called for each event, the state being the one after the action which caused it
+    fn event(&mut self, event: &GameEvent, state: &State);
+}
+
+/// Observer keeping the events of the current game until they are taken, possibly from another thread
+#[derive(Clone, Default)]
+pub struct EventLog {
+    events: Arc<Mutex<Vec<GameEvent>>>,
+}
+
+impl EventLog {
+    pub fn new() -> EventLog {
+        EventLog::default()
+    }
+
+    /// Events since the last time they were taken
+    pub fn take(&self) -> Vec<GameEvent> {
+        std::mem::take(&mut *self.events.lock().unwrap())
+    }
+}
+
+impl GameObserver for EventLog {
+    fn new_game(&mut self, _state: &State) {
+        self.events.lock().unwrap().clear();
+    }
+
+    fn event(&mut self, event: &GameEvent, _state: &State) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
@@ -1,6 +1,8 @@
-use crate::utils::{Coord, CoordType, Resources, DevelopmentCard};
+use std::collections::BTreeMap;
+
+use crate::utils::{Coord, CoordType, Resource, Resources, DevelopmentCard};
 use crate::state::{State, PlayerId};
-use crate::game::{Phase, TurnPhase, DevelopmentPhase, Action, Error};
+use crate::game::{Phase, TurnPhase, DevelopmentPhase, Action, Error, TradeOffer, RoadBuildingFallback};
 use crate::board::utils::topology::Topology;
 use crate::board::Error as BoardError;
 
@@ -41,17 +43,17 @@ pub fn allowed_initial_road_placement(coord: Coord, player: PlayerId, state: &St
 
 /// Does this victim have a settlement or city around the hex
 ///
-/// Useful to check if the player can steal from the victim
+/// Useful to check if the player can steal from the victim, the players who resigned being out of reach
 pub fn can_steal_victim(player: PlayerId, target_hex: Coord, victim: PlayerId, state: &State) -> Result<(), Error> {
     let mut potential_victims = vec![false; state.player_count() as usize];
     for intersection in state.hex_intersection_neighbours(target_hex)?.iter() {
         if let Some((p, _)) = state.get_dynamic_intersection(*intersection)? {
-            if p != player {
+            if p != player && !state.get_player_hand(p).resigned {
                 potential_victims[p.to_usize()] = true;
             }
         }
     }
-    if potential_victims[victim.to_usize()] {
+    if victim.option().is_some_and(|victim| potential_victims[victim.to_usize()]) {
         Ok(())
     } else if victim != player && victim != PlayerId::NONE {
         Err(Error::WrongVictim { victim })
@@ -84,6 +86,67 @@ pub fn can_put_road(player: PlayerId, path: Coord, state: &State) -> Result<(),
     }
 }
 
+/// Number of free roads the player can build with a road building card, up to two
+///
+/// Zero without a place for the first road, two when a second road can be built after one of the first ones,
+/// both being limited by the road pieces left.
+pub fn road_building_roads(player: PlayerId, state: &State) -> u8 {
+    let pieces = state.get_player_hand(player).road_pieces;
+    if pieces == 0 {
+        return 0;
+    }
+    let free = |path: &Coord| matches!(state.get_dynamic_path(*path), Ok(None));
+    let neighbours = |path: &Coord| state.path_path_neighbours(*path).unwrap_or_default();
+    // Free paths next to the roads of the player, where the first road can go
+    let mut first: Vec<Coord> = state.get_layout().paths.iter()
+        .filter(|path| matches!(state.get_dynamic_path(**path), Ok(Some(p)) if p == player))
+        .flat_map(neighbours)
+        .filter(free)
+        .collect();
+    first.sort();
+    first.dedup();
+    match first[..] {
+        [] => 0,
+        _ if pieces == 1 => 1,
+        // The second road can only go next to the first one
+        [only] => if neighbours(&only).iter().any(free) { 2 } else { 1 },
+        _ => 2,
+    }
+}
+
+/// Roads the player still has to build before settling on each intersection, up to `max_roads`
+///
+/// Roads are added as they would be legally built, on free paths next to the player's roads and within its road pieces left.
+/// Only the intersections free for a settlement and within reach are given, in the order of the layout,
+/// those the player may already settle on with 0 roads.
+pub fn settlement_reach(player: PlayerId, state: &State, max_roads: u8) -> Vec<(Coord, u8)> {
+    let layout = state.get_layout();
+    let mut roads: BTreeMap<Coord, u8> = layout.paths.iter()
+        .filter(|path| matches!(state.get_dynamic_path(**path), Ok(Some(p)) if p == player))
+        .map(|path| (*path, 0))
+        .collect();
+    let mut frontier: Vec<Coord> = roads.keys().copied().collect();
+    for built in 1..=max_roads.min(state.get_player_hand(player).road_pieces) {
+        let mut next = Vec::new();
+        for path in frontier.iter() {
+            for neighbour in state.path_path_neighbours(*path).unwrap_or_default() {
+                if !roads.contains_key(&neighbour) && matches!(state.get_dynamic_path(neighbour), Ok(None)) {
+                    roads.insert(neighbour, built);
+                    next.push(neighbour);
+                }
+            }
+        }
+        frontier = next;
+    }
+    layout.intersections.iter()
+        .filter(|intersection| available_settlement_position(**intersection, state).unwrap_or(false))
+        .filter_map(|intersection| {
+            let paths = state.intersection_path_neighbours(*intersection).ok()?;
+            paths.iter().filter_map(|path| roads.get(path)).min().map(|built| (*intersection, *built))
+        })
+        .collect()
+}
+
 /// Is the path or intersection connected to a piece owned by the player
 ///
 /// Returns true if the path or intersection coord is next to a road owned by the player
@@ -151,7 +214,7 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
             // ## Ending Turn
             //
             Action::EndTurn => {
-                if *turn_phase == TurnPhase::Free {
+                if turn_phase.can_build() {
                     Ok(())
                 } else {
                     Err(Error::IncoherentAction(action))
@@ -173,7 +236,10 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
             Action::Keep { resources } => {
                 if let TurnPhase::Discard(player) = *turn_phase {
                     let current = state.get_player_hand(player).resources;
-                    if current >= resources && resources.total() <= (current.total()+1) / 2 {
+                    // A player who started discarding card by card finishes that way
+                    if state.get_pending_discard(player).is_some_and(|(discarded, _)| discarded.total() > 0) {
+                        Err(Error::IncoherentAction(action))
+                    } else if current >= resources && resources.total() <= (current.total()+1) / 2 {
                         Ok(())
                     } else {
                         Err(Error::NotEnoughResources {
@@ -185,11 +251,27 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
                     Err(Error::IncoherentAction(action))
                 }
             }
+            Action::Discard { resource } => {
+                if let TurnPhase::Discard(player) = *turn_phase {
+                    let current = state.get_player_hand(player).resources;
+                    let discarded = state.get_pending_discard(player).map(|(discarded, _)| discarded).unwrap_or(Resources::ZERO);
+                    if current[resource] > discarded[resource] {
+                        Ok(())
+                    } else {
+                        Err(Error::NotEnoughResources {
+                            required: discarded + Resources::new_one(resource, 1),
+                            have: current,
+                        })
+                    }
+                } else {
+                    Err(Error::IncoherentAction(action))
+                }
+            }
             //
             // ## Moving Thief
             //
             Action::MoveThief { hex, victim } => {
-                if *turn_phase == TurnPhase::MoveThief || (!turn_phase.is_discard() && *development_phase == DevelopmentPhase::KnightActive) {
+                if *turn_phase == TurnPhase::MoveThief || (turn_phase.unbound() && *development_phase == DevelopmentPhase::KnightActive) {
                     if hex == state.get_thief_hex() {
                         Err(Error::ThiefNotMoved { hex })
                     } else {
@@ -208,9 +290,11 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
                 } else {
                     false
                 };
-                if *turn_phase != TurnPhase::Free && !road_building {
+                if !state.get_rules().building_allowed(*turn_phase) && !road_building {
                     return Err(Error::IncoherentAction(action));
                 }
+                // The builder of the special building phase, or the player of the turn
+                let player = &phase.player();
                 can_put_road(*player, path, state)?;
                 if road_building || state.get_player_hand(*player).resources >= Resources::ROAD {
                     Ok(())
@@ -225,9 +309,10 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
             // ## Building Settlement
             //
             Action::BuildSettlement { intersection } => {
-                if *turn_phase != TurnPhase::Free {
+                if !state.get_rules().building_allowed(*turn_phase) {
                     return Err(Error::IncoherentAction(action));
                 }
+                let player = &phase.player();
                 // If: we are next to a road...
                 if connected_position(intersection, *player, state)?
                     // ...the position is available (no settlement on it or next to it)...
@@ -245,15 +330,41 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
             // ## Building City
             //
             Action::BuildCity { intersection } => {
-                if *turn_phase != TurnPhase::Free {
+                if !state.get_rules().building_allowed(*turn_phase) {
                     return Err(Error::IncoherentAction(action));
                 }
+                let player = &phase.player();
                 // If: we already own a settlement at the position
                 if Some((*player, false)) == state.get_dynamic_intersection(intersection)?
                     // ...and the player has a city piece left...
                     && state.get_player_hand(*player).city_pieces >= 1
                     // ...and the player has enough resources for the city
                     && state.get_player_hand(*player).resources >= Resources::CITY {
+                    match state.get_player_hand(*player).reserved_city {
+                        _ if !state.get_rules().city_reservation => Ok(()),
+                        Some((reserved, turn)) if reserved == intersection && turn < state.get_turn() => Ok(()),
+                        _ => Err(Error::CityNotReserved { coord: intersection }),
+                    }
+                } else {
+                    Err(Error::IllegalAction(action))
+                }
+            }
+            //
+            // ## Reserving a City
+            //
+            Action::ReserveCity { intersection } => {
+                if !state.get_rules().building_allowed(*turn_phase) {
+                    return Err(Error::IncoherentAction(action));
+                }
+                let player = &phase.player();
+                // If: the rules ask for reservations...
+                if state.get_rules().city_reservation
+                    // ...the player owns a settlement at the position...
+                    && Some((*player, false)) == state.get_dynamic_intersection(intersection)?
+                    // ...has a city piece left...
+                    && state.get_player_hand(*player).city_pieces >= 1
+                    // ...and no settlement reserved yet
+                    && state.get_player_hand(*player).reserved_city.is_none() {
                     Ok(())
                 } else {
                     Err(Error::IllegalAction(action))
@@ -263,7 +374,7 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
             // ## Trade Bank
             //
             Action::TradeBank { given, asked } => {
-                if *turn_phase != TurnPhase::Free {
+                if !state.get_rules().bank_trading_allowed(*turn_phase) {
                     return Err(Error::IncoherentAction(action));
                 }
                 if given == asked {
@@ -280,13 +391,127 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
                 }
             }
             //
+            // ## Trade Bank Bundle
+            //
+            Action::TradeBankBundle { trades, asked } => {
+                if !state.get_rules().bank_trading_allowed(*turn_phase) {
+                    return Err(Error::IncoherentAction(action));
+                }
+                let negative = Resource::ALL.iter().any(|res| trades[*res] < 0 || asked[*res] < 0);
+                if negative || asked.total() < 1 || trades.total() != asked.total() {
+                    return Err(Error::IllegalAction(action));
+                }
+                if let Some(resource) = Resource::ALL.iter().find(|res| trades[**res] > 0 && asked[**res] > 0) {
+                    return Err(Error::IllegalTradeSameResources(*resource));
+                }
+                let hand = state.get_player_hand(*player);
+                let given = hand.harbor.bundle_cost(trades);
+                let bank = state.get_bank_resources();
+                if Resource::ALL.iter().any(|res| hand.resources[*res] < given[*res]) {
+                    Err(Error::NotEnoughResources { required: given, have: hand.resources })
+                } else if let Some(resource) = Resource::ALL.iter().find(|res| bank[**res] < asked[**res]) {
+                    Err(Error::NoMoreResourceInBank(*resource))
+                } else {
+                    Ok(())
+                }
+            }
+            //
+            // ## Trade Between Players
+            //
+            Action::ProposeTrade { give, receive } => {
+                if *turn_phase != TurnPhase::Free {
+                    return Err(Error::IncoherentAction(action));
+                }
+                if !state.get_rules().domestic_trading || !TradeOffer::valid_terms(give, receive) {
+                    return Err(Error::IllegalAction(action));
+                }
+                let limit = state.get_rules().trade_proposals_per_turn;
+                if limit > 0 && state.get_ledger().proposals_of_turn(*player, state.get_turn()) >= limit as usize {
+                    return Err(Error::TradeProposalLimit { limit });
+                }
+                let have = state.get_player_hand(*player).resources;
+                if have >= give {
+                    Ok(())
+                } else {
+                    Err(Error::NotEnoughResources { required: give, have })
+                }
+            }
+            Action::AcceptTrade => {
+                if let TurnPhase::Trade(offer) = turn_phase {
+                    let have = state.get_player_hand(*player).resources;
+                    let partner_have = state.get_player_hand(offer.partner).resources;
+                    if have >= offer.give {
+                        if partner_have >= offer.receive {
+                            Ok(())
+                        } else {
+                            Err(Error::NotEnoughResources { required: offer.receive, have: partner_have })
+                        }
+                    } else {
+                        Err(Error::NotEnoughResources { required: offer.give, have })
+                    }
+                } else {
+                    Err(Error::IncoherentAction(action))
+                }
+            }
+            Action::RejectTrade => {
+                if let TurnPhase::Trade(_) = turn_phase {
+                    Ok(())
+                } else {
+                    Err(Error::IncoherentAction(action))
+                }
+            }
+            Action::CounterTrade { give, receive } => {
+                if let TurnPhase::Trade(offer) = turn_phase {
+                    // A counter offer can't be countered again
+                    if offer.countered || !TradeOffer::valid_terms(give, receive) {
+                        return Err(Error::IllegalAction(action));
+                    }
+                    let have = state.get_player_hand(offer.partner).resources;
+                    if have >= give {
+                        Ok(())
+                    } else {
+                        Err(Error::NotEnoughResources { required: give, have })
+                    }
+                } else {
+                    Err(Error::IncoherentAction(action))
+                }
+            }
+            // The player whose turn it is takes back or revises its offer as it answers a counter offer
+            Action::WithdrawTrade => {
+                match turn_phase {
+                    TurnPhase::Trade(offer) if offer.countered => Ok(()),
+                    _ => Err(Error::IncoherentAction(action)),
+                }
+            }
+            Action::ReviseTrade { give, receive } => {
+                if !matches!(turn_phase, TurnPhase::Trade(offer) if offer.countered) {
+                    return Err(Error::IncoherentAction(action));
+                }
+                if !TradeOffer::valid_terms(give, receive) {
+                    return Err(Error::IllegalAction(action));
+                }
+                // A revised offer is a new proposal, bounding the revisions and counter offers of a turn
+                let limit = state.get_rules().trade_proposals_per_turn;
+                if limit > 0 && state.get_ledger().proposals_of_turn(*player, state.get_turn()) >= limit as usize {
+                    return Err(Error::TradeProposalLimit { limit });
+                }
+                let have = state.get_player_hand(*player).resources;
+                if have >= give {
+                    Ok(())
+                } else {
+                    Err(Error::NotEnoughResources { required: give, have })
+                }
+            }
+            //
             // ## Buy Development Card
             //
             Action::BuyDevelopment => {
-                if *turn_phase != TurnPhase::Free {
+                if !state.get_rules().building_allowed(*turn_phase) {
                     return Err(Error::IncoherentAction(action));
                 }
-                if state.get_development_cards().total() >= 1
+                let player = &phase.player();
+                if state.get_rules().development_cards
+                    && state.get_development_cards().total() >= 1
                     && state.get_player_hand(*player).resources >= Resources::DVP_CARD {
                         Ok(())
                 } else {
@@ -299,10 +524,14 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
             Action::DevelopmentKnight => {
                 if !turn_phase.unbound() {
                     Err(Error::IncoherentAction(action))
+                } else if !state.get_rules().robber {
+                    Err(Error::IllegalAction(action))
                 } else if *development_phase != DevelopmentPhase::Ready {
                     Err(Error::DevelopmentCardAlreadyPlayed)
                 } else if state.get_player_hand(*player).development_cards.knight == 0 {
                     Err(Error::NoCard { card_type: DevelopmentCard::Knight })
+                } else if !state.get_rules().development_caps.allows(DevelopmentCard::Knight, state.get_player_hand(*player).played_development_cards.knight) {
+                    Err(Error::DevelopmentCapReached { card_type: DevelopmentCard::Knight })
                 } else {
                     Ok(())
                 }
@@ -317,8 +546,18 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
                     Err(Error::DevelopmentCardAlreadyPlayed)
                 } else if state.get_player_hand(*player).development_cards.road_building == 0 {
                     Err(Error::NoCard { card_type: DevelopmentCard::RoadBuilding })
+                } else if !state.get_rules().development_caps.allows(DevelopmentCard::RoadBuilding, state.get_player_hand(*player).played_development_cards.road_building) {
+                    Err(Error::DevelopmentCapReached { card_type: DevelopmentCard::RoadBuilding })
                 } else {
-                    Ok(())
+                    let required = match state.get_rules().road_building_fallback {
+                        RoadBuildingFallback::Forfeit => 1,
+                        RoadBuildingFallback::Forbid => 2,
+                    };
+                    if road_building_roads(*player, state) >= required {
+                        Ok(())
+                    } else {
+                        Err(Error::IllegalAction(action))
+                    }
                 }
             }
             //
@@ -331,6 +570,11 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
                     Err(Error::DevelopmentCardAlreadyPlayed)
                 } else if state.get_player_hand(*player).development_cards.year_of_plenty == 0 {
                     Err(Error::NoCard { card_type: DevelopmentCard::YearOfPlenty })
+                } else if !state.get_rules().development_caps.allows(DevelopmentCard::YearOfPlenty, state.get_player_hand(*player).played_development_cards.year_of_plenty) {
+                    Err(Error::DevelopmentCapReached { card_type: DevelopmentCard::YearOfPlenty })
+                // The bank must have a resource to give
+                } else if state.get_bank_resources().total() == 0 {
+                    Err(Error::IllegalAction(action))
                 } else {
                     Ok(())
                 }
@@ -360,13 +604,20 @@ pub fn legal(phase: &Phase, state: &State, action: Action) -> Result<(), Error>
                     Err(Error::DevelopmentCardAlreadyPlayed)
                 } else if state.get_player_hand(*player).development_cards.monopole == 0 {
                     Err(Error::NoCard { card_type: DevelopmentCard::Monopole })
+                } else if !state.get_rules().development_caps.allows(DevelopmentCard::Monopole, state.get_player_hand(*player).played_development_cards.monopole) {
+                    Err(Error::DevelopmentCapReached { card_type: DevelopmentCard::Monopole })
                 } else {
                     Ok(())
                 }
             },
+            //
+            // ## Resigning
+            //
+            // The player deciding can always leave, be it its turn, a discard or an answer to an offer
+            Action::Resign => Ok(()),
 
-            _ => unimplemented!(),
+            _ => Err(Error::IncoherentAction(action)),
         }
-        _ => panic!("Game already finished"),
+        Phase::FinishedGame { winner: _ } => Err(Error::IncoherentAction(action)),
     }
 }
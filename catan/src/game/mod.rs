@@ -3,39 +3,157 @@ mod error;
 mod phase;
 mod notification;
 mod apply;
+mod win_probability;
+mod statistics;
+mod rules;
+mod config;
+mod discard;
+mod trade;
+mod checkpoint;
+mod reward;
+mod transitions;
+mod event;
+mod crash_dump;
+mod simulator;
+mod text;
 pub mod legal;
 
 pub use error::Error;
 pub use action::{Action, ActionCategory};
-pub use phase::{Phase, TurnPhase, DevelopmentPhase};
+pub use phase::{Phase, TurnPhase, DevelopmentPhase, DecisionStage};
 pub use notification::Notification;
+pub use statistics::{DiceStatistics, RollCounts, SeatStatistics, DevelopmentUsage, GameStats};
+pub use rules::{Rules, Curriculum, RoadBuildingFallback, DevelopmentCaps, ResignedPieces, SetupResources};
+pub use config::GameConfig;
+pub use discard::DiscardCodec;
+pub use trade::{TradeOffer, Negotiation, responses_of_turn};
+pub use checkpoint::{Checkpoint, RecordVersion, ENGINE_VERSION};
+pub use reward::{RewardConfig, RewardTracker};
+pub use transitions::{PhaseKind, Transition, TRANSITIONS};
+pub use event::{GameEvent, GameObserver, EventLog, Building};
+pub use crash_dump::{CrashDump, CRASH_HISTORY};
+pub use simulator::Simulator;
+pub use text::{Lang, Texts};
+pub use win_probability::{WinProbEstimator, LogisticEstimator, WinFeatures, production_pips};
+#[cfg(feature = "onnx")]
+pub use win_probability::OnnxEstimator;
 
 // --------------------------------------------------------------------------------------------- //
 
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+
 use rand::SeedableRng;
 use rand::rngs::SmallRng;
 use rand::seq::SliceRandom;
 
-use crate::state::{State, TricellState};
-use crate::board::setup;
+use crate::state::{State, StateMaker, TricellState};
+use crate::board::layout::{self, BoardLayout};
 use crate::state::PlayerId;
-use crate::player::CatanPlayer;
+use crate::player::{CatanPlayer, PlayerInfo};
+use crate::replay::Replay;
+use crate::notation::Record;
 
-use apply::apply;
+pub(crate) use apply::{apply, roll_dice};
 
 pub struct Game {
     pub players: Vec<Box<dyn CatanPlayer>>,
+    /// Name and metadata of each player, in the order they were added
+    pub infos: Vec<PlayerInfo>,
+    /// Player index sitting at each seat in the last game played
+    pub seating: Vec<usize>,
+    /// Win probabilities estimated after each turn of the last game played, indexed by [PlayerId]
+    pub win_probabilities: Vec<Vec<f32>>,
+    /// Dice rolled during the last game and since the game was created
+    pub dice: DiceStatistics,
+    /// Rules and starting conditions of the games played
+    pub config: GameConfig,
+    /// Board of the games set up, and of the checkpoints resumed
+    pub board: &'static BoardLayout,
+    /// Games are recorded into [Game::replay]
+    pub record_replays: bool,
+    /// Replay of the last game played, when recorded
+    pub replay: Option<Replay>,
+    /// Metrics of the games are collected into [Game::stats]
+    pub collect_stats: bool,
+    /// Metrics of the last game played, when collected
+    pub stats: Option<GameStats>,
+    /// Directory where a [CrashDump] of the game is written when the engine panics, the panic message then giving its path
+    pub crash_dumps: Option<PathBuf>,
+    estimator: Option<Box<dyn WinProbEstimator>>,
+    observers: Vec<Box<dyn GameObserver>>,
 }
 
 impl Game {
     pub fn new() -> Game {
+        Game::with_config(GameConfig::STANDARD)
+    }
+
+    pub fn with_config(config: GameConfig) -> Game {
         Game {
             players: Vec::new(),
+            infos: Vec::new(),
+            seating: Vec::new(),
+            win_probabilities: Vec::new(),
+            dice: DiceStatistics::new(),
+            config,
+            board: &layout::STANDARD_BOARD,
+            record_replays: false,
+            replay: None,
+            collect_stats: false,
+            stats: None,
+            crash_dumps: None,
+            estimator: None,
+            observers: Vec::new(),
         }
     }
 
     pub fn add_player(&mut self, player: Box<dyn CatanPlayer>) {
+        let info = PlayerInfo::new(&format!("Player {}", self.players.len()));
+        self.add_named_player(player, info);
+    }
+
+    pub fn add_named_player(&mut self, player: Box<dyn CatanPlayer>, info: PlayerInfo) {
         self.players.push(player);
+        self.infos.push(info);
+    }
+
+    /// Seat and info of each player of the last game played, in seat order
+    pub fn seating_report(&self) -> Vec<(PlayerId, &PlayerInfo)> {
+        self.seating.iter().enumerate()
+            .map(|(seat, player)| (PlayerId::from(seat), &self.infos[*player]))
+            .collect()
+    }
+
+    /// Sets the estimator evaluated after each turn, filling [win_probabilities](Game::win_probabilities),
+    /// the ones of the [stats](Game::stats) and of the [exported records](Game::export_record)
+    pub fn set_win_prob_estimator(&mut self, estimator: Box<dyn WinProbEstimator>) {
+        self.estimator = Some(estimator);
+    }
+
+    /// Record of the last game played, see [notation](crate::notation), when its replay was recorded
+    pub fn export_record(&self) -> Result<Record, String> {
+        let replay = self.replay.as_ref().ok_or_else(|| "No replay of the last game, see record_replays".to_string())?;
+        let names = replay.seating.iter()
+            .map(|player| self.infos.get(*player).map_or(String::new(), |info| info.name.clone()))
+            .collect();
+        let mut record = Record::from_replay(replay, self.board.layout, names)?;
+        record.win_probabilities = self.win_probabilities.clone();
+        Ok(record)
+    }
+
+    /// Plays the moves of a record back, making its game the [replay](Game::replay) of the last game, on its board
+    pub fn import_record(&mut self, record: &Record) -> Result<(), String> {
+        let replay = record.to_replay()?;
+        self.board = record.board_layout()?;
+        self.replay = Some(replay);
+        Ok(())
+    }
+
+    /// Adds an observer told about the events of every game played from now on
+    pub fn subscribe(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
     }
 
     fn notify_all(&mut self, notification: Notification) {
@@ -45,23 +163,101 @@ impl Game {
     }
 
     pub fn setup_and_play(&mut self) -> Notification {
-        let player_count = self.players.len();
         let mut rng = SmallRng::from_rng(&mut rand::rng());
-        let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, player_count as u8);
-        let mut players_order: Vec<usize> = (0..player_count).collect();
-        players_order.shuffle(&mut rng);
-        self.play(&mut rng, &mut state, players_order)
+        self.setup_and_play_with_rng(&mut rng)
+    }
+
+    /// Plays a game whose board, players order, dice and development cards all come from the rng
+    ///
+    /// Games of the same seed, with seeded players, are the same on every 64-bit platform,
+    /// nothing in the rules, the enumeration of the actions or the setup depending on the order of hashed containers.
+    pub fn setup_and_play_with_rng(&mut self, rng: &mut SmallRng) -> Notification {
+        let mut state = TricellState::new_empty(self.board.layout, self.players.len() as u8);
+        self.setup_and_play_on(rng, &mut state)
     }
 
+    /// Plays a game as [setup_and_play_with_rng](Game::setup_and_play_with_rng), on an empty state of the layout of the board
+    pub fn setup_and_play_on(&mut self, rng: &mut SmallRng, state: &mut State) -> Notification {
+        self.board.setup_existing_state(rng, state.as_mut());
+        let mut players_order: Vec<usize> = (0..self.players.len()).collect();
+        players_order.shuffle(rng);
+        self.play(rng, state, players_order)
+    }
+
+    /// Plays a game on a new state, set up by the [config](Game::config)
     pub fn play(&mut self, rng: &mut SmallRng, state: &mut State, players_order: Vec<usize>) -> Notification {
-        let mut phase = Phase::START_GAME;
+        state.set_rules(self.config.rules);
+        self.config.setup(state).expect("Failed setting up the game");
+        self.play_from(rng, state, Phase::START_GAME, players_order)
+    }
+
+    /// Plays the rest of the game of a checkpoint, keeping its rules
+    ///
+    /// The players are seated in the given order, and are told about the game as if it was starting
+    pub fn resume(&mut self, rng: &mut SmallRng, checkpoint: &Checkpoint, players_order: Vec<usize>) -> Result<Notification, crate::board::Error> {
+        let mut state = TricellState::new_empty(self.board.layout, checkpoint.state.players.len() as u8);
+        self.resume_on(rng, checkpoint, &mut state, players_order)
+    }
+
+    /// Resumes a checkpoint as [resume](Game::resume), on an empty state of the layout of the board
+    pub fn resume_on(&mut self, rng: &mut SmallRng, checkpoint: &Checkpoint, state: &mut State, players_order: Vec<usize>) -> Result<Notification, crate::board::Error> {
+        checkpoint.state.restore_into(state)?;
+        Ok(self.play_from(rng, state, checkpoint.phase, players_order))
+    }
+
+    fn play_from(&mut self, rng: &mut SmallRng, state: &mut State, phase: Phase, players_order: Vec<usize>) -> Notification {
+        self.win_probabilities.clear();
+        self.dice.new_game();
+        self.seating = players_order.clone();
+        let replay = if self.record_replays { Some(Replay::new(phase, state, players_order.clone())) } else { None };
+        self.replay = None;
+        self.stats = if self.collect_stats { Some(GameStats::new(state.player_count())) } else { None };
 
         for (i, player) in players_order.iter().enumerate() {
             self.players[*player].new_game(PlayerId::from(i), &state);
         }
+        for observer in self.observers.iter_mut() {
+            observer.new_game(state);
+        }
+        let mut progress = Progress { phase, action: None, history: VecDeque::with_capacity(CRASH_HISTORY), replay };
+        let played = panic::catch_unwind(AssertUnwindSafe(|| self.play_actions(rng, state, &players_order, &mut progress)));
+        match (played, self.crash_dumps.as_ref()) {
+            (Ok(notification), _) => notification,
+            (Err(payload), None) => panic::resume_unwind(payload),
+            (Err(payload), Some(directory)) => {
+                let message = payload.downcast_ref::<&str>().map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                let history = progress.history.into_iter().collect();
+                // The phase of a pending action is the one before it, which the action may have changed halfway
+                let (phase, action) = match progress.action {
+                    Some((phase, action)) => (phase, Some(action)),
+                    None => (progress.phase, None),
+                };
+                let dump = CrashDump::new(message.clone(), phase, action, history, state, progress.replay.take());
+                match dump.write(directory) {
+                    Ok(path) => panic!("{} (crash dump written to {})", message, path.display()),
+                    Err(error) => panic!("{} ({})", message, error),
+                }
+            }
+        }
+    }
+
+    /// Plays the actions of the players until the end of the game, keeping track of its progress for crash dumps
+    fn play_actions(&mut self, rng: &mut SmallRng, state: &mut State, players_order: &[usize], progress: &mut Progress) -> Notification {
+        let total = (0..state.player_count())
+            .fold(state.get_bank_resources(), |total, p| total + state.get_player_hand(PlayerId::from(p)).resources);
+        let phase = &mut progress.phase;
         loop {
             // If the game is finished, exit
-            if let Phase::FinishedGame { winner } = phase {
+            if let Phase::FinishedGame { winner } = *phase {
+                if let Some(replay) = progress.replay.as_mut() {
+                    replay.winner = Some(winner);
+                }
+                self.replay = progress.replay.take();
+                if let Some(stats) = self.stats.as_mut() {
+                    stats.finish(state, winner);
+                }
                 for player in players_order.iter() {
                     self.players[*player].results(&state, winner);
                 }
@@ -73,13 +269,17 @@ impl Game {
             let mut action;
             loop {
                 // Ask player to take action
-                action = player.pick_action(&phase, &state);
+                action = player.pick_action(phase, &state);
                 if action == Action::Exit {
+                    self.replay = progress.replay.take();
+                    if let Some(stats) = self.stats.as_mut() {
+                        stats.finish(state, PlayerId::NONE);
+                    }
                     return Notification::GameFinished { winner: PlayerId::NONE };
                 }
 
                 // Checks if action is legal
-                let result = legal::legal(&phase, &state, action);
+                let result = legal::legal(phase, &state, action);
                 if let Err(error) = result {
                     // Tells player if action was invalid
                     player.bad_action(error);
@@ -88,30 +288,83 @@ impl Game {
                 }
             }
 
+            progress.action = Some((*phase, action));
+
             // Notifies every player of action played
-            let prev_phase = phase;
+            let prev_phase = *phase;
             self.notify_all(Notification::ActionPlayed { by: phase.player(), action });
-            // Applies action
-            apply(&mut phase, state, action, rng);
-            let coherence = check_coherence(state);
+            let (transfers, longest_road, turn) = (state.get_ledger().transfers().len(), state.get_longest_road(), state.get_turn());
+            // Applies action and notifies its outcome
+            let outcome = match progress.replay.as_mut() {
+                Some(replay) => replay.record(phase, state, action, rng),
+                None => apply(phase, state, action, rng),
+            };
+            if !self.observers.is_empty() {
+                let transfers = &state.get_ledger().transfers()[transfers..];
+                for event in GameEvent::of_action(prev_phase.player(), action, outcome.as_ref(), transfers, longest_road, phase, state) {
+                    for observer in self.observers.iter_mut() {
+                        observer.event(&event, state);
+                    }
+                }
+            }
+            if let Some(stats) = self.stats.as_mut() {
+                stats.record_action(prev_phase.player(), action);
+            }
+            if let Some(notification) = outcome {
+                if let Some(roll) = notification.roll() {
+                    self.dice.record(roll);
+                    if let Some(stats) = self.stats.as_mut() {
+                        stats.record_roll(roll);
+                    }
+                }
+                self.notify_all(notification);
+            }
+            debug_assert!(transitions::listed(prev_phase.kind(), action.category(), phase.kind()),
+                "Unlisted transition {:?} --({:?})-> {:?}", prev_phase, action, phase);
+            let coherence = check_coherence(state, total);
             if coherence.is_err() {
                 println!("[INCOHERENCE] {:?} --({:?})-> {:?}", prev_phase, action, phase);
                 panic!("{:?}", coherence.err());
             }
+            if progress.history.len() == CRASH_HISTORY {
+                progress.history.pop_front();
+            }
+            progress.history.push_back((prev_phase.player(), action));
+            progress.action = None;
+            // Once per turn, the special building of the opponents being ended by EndTurn too
+            if state.get_turn() != turn {
+                if let Some(estimator) = &self.estimator {
+                    let estimates = estimator.estimate(state);
+                    if let Some(stats) = self.stats.as_mut() {
+                        stats.win_probabilities.push(estimates.clone());
+                    }
+                    self.win_probabilities.push(estimates);
+                }
+            }
         }
     }
 }
 
+/// Phase, pending action and last plies of the game being played, dumped when the engine panics
+struct Progress {
+    phase: Phase,
+    /// Action being applied, and the phase it was played in
+    action: Option<(Phase, Action)>,
+    history: VecDeque<(PlayerId, Action)>,
+    replay: Option<Replay>,
+}
+
 use crate::utils::{Resource, Resources};
 
-fn check_coherence(state: &State) -> Result<(),String> {
+/// Checks no resource was created nor lost, out of the total held by the bank and players
+pub fn check_coherence(state: &State, total: Resources) -> Result<(),String> {
     let mut players_resources = Resources::ZERO;
     for p in 0..state.player_count() {
         let player = PlayerId::from(p);
         let hand = state.get_player_hand(player).resources;
         for res in Resource::ALL.iter() {
             let v = hand[*res];
-            if v > 19 || v < 0 {
+            if v > total[*res] || v < 0 {
                 return Err(format!("Player {:?} has {} of {}", player, v, res));
             }
         }
@@ -121,7 +374,7 @@ fn check_coherence(state: &State) -> Result<(),String> {
     for res in Resource::ALL.iter() {
         let v = bank_resources[*res];
         let pv = players_resources[*res];
-        if v > 19 || v < 0 || pv+v != 19 {
+        if v > total[*res] || v < 0 || pv+v != total[*res] {
             return Err(format!("For resource {}: Bank has {} / Players have {}", res, v, pv));
         }
     }
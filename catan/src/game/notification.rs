@@ -1,8 +1,10 @@
+use serde::{Serialize, Deserialize};
+
 use crate::utils::Resources;
 use crate::game::Action;
 use crate::state::PlayerId;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Notification {
     ActionPlayed {
         by: PlayerId,
@@ -18,3 +20,14 @@ pub enum Notification {
     ThiefRolled,
     InitialPlacementFinished,
 }
+
+impl Notification {
+    /// The dice roll announced by this notification, if any
+    pub fn roll(&self) -> Option<u8> {
+        match self {
+            Notification::ResourcesRolled { roll, resources: _ } => Some(*roll),
+            Notification::ThiefRolled => Some(7),
+            _ => None,
+        }
+    }
+}
@@ -1,6 +1,10 @@
+use serde::{Serialize, Deserialize};
+
 use crate::state::PlayerId;
+use super::{Action, TradeOffer};
+use super::transitions::{PhaseKind, TRANSITIONS};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Phase {
     InitialPlacement {
         player: PlayerId,
@@ -25,6 +29,8 @@ impl Phase {
         match self {
             Phase::InitialPlacement { player, placing_second: _, placing_road: _ } => *player,
             Phase::Turn { player: _, turn_phase: TurnPhase::Discard(player), development_phase: _} => *player,
+            Phase::Turn { player: _, turn_phase: TurnPhase::SpecialBuilding(player), development_phase: _} => *player,
+            Phase::Turn { player, turn_phase: TurnPhase::Trade(offer), development_phase: _} => if offer.countered { *player } else { offer.partner },
             Phase::Turn { player, turn_phase: _, development_phase: _} => *player,
             Phase::FinishedGame { winner } => *winner,
         }
@@ -44,17 +50,114 @@ impl Phase {
             false
         }
     }
+
+    /// Node of the phase machine the phase belongs to
+    pub fn kind(&self) -> PhaseKind {
+        match self {
+            Phase::InitialPlacement { player: _, placing_second: _, placing_road: false } => PhaseKind::InitialSettlement,
+            Phase::InitialPlacement { player: _, placing_second: _, placing_road: true } => PhaseKind::InitialRoad,
+            Phase::Turn { player: _, turn_phase: TurnPhase::Discard(_), development_phase: _ } => PhaseKind::Discard,
+            Phase::Turn { player: _, turn_phase: TurnPhase::MoveThief, development_phase: _ } => PhaseKind::MoveThief,
+            Phase::Turn { player: _, turn_phase: TurnPhase::Trade(_), development_phase: _ } => PhaseKind::Trade,
+            Phase::Turn { player: _, turn_phase: TurnPhase::SpecialBuilding(_), development_phase: _ } => PhaseKind::SpecialBuilding,
+            Phase::Turn { player: _, turn_phase: _, development_phase: DevelopmentPhase::KnightActive } => PhaseKind::Knight,
+            Phase::Turn { player: _, turn_phase: _, development_phase: DevelopmentPhase::RoadBuildingActive { two_left: _ } } => PhaseKind::RoadBuilding,
+            Phase::Turn { player: _, turn_phase: _, development_phase: DevelopmentPhase::YearOfPlentyActive { two_left: _ } } => PhaseKind::YearOfPlenty,
+            Phase::Turn { player: _, turn_phase: TurnPhase::PreRoll, development_phase: _ } => PhaseKind::PreRoll,
+            Phase::Turn { player: _, turn_phase: TurnPhase::Free, development_phase: _ } => PhaseKind::Free,
+            Phase::FinishedGame { winner: _ } => PhaseKind::Finished,
+        }
+    }
+
+    /// Phase machine of the [transition table](crate::game::TRANSITIONS) in the graphviz dot format, one edge per action category
+    pub fn to_graphviz() -> String {
+        let mut dot = String::from("digraph phases {\n");
+        for kind in PhaseKind::ALL.iter() {
+            dot.push_str(&format!("    {:?};\n", kind));
+        }
+        for transition in TRANSITIONS.iter() {
+            for to in transition.to.iter() {
+                dot.push_str(&format!("    {:?} -> {:?} [label=\"{:?}\"];\n", transition.from, to, transition.action));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Kind of decision expected from the player, see [DecisionStage]
+    pub fn decision_stage(&self) -> DecisionStage {
+        match self {
+            Phase::InitialPlacement { player: _, placing_second: _, placing_road: false } => DecisionStage::InitialSettlement,
+            Phase::InitialPlacement { player: _, placing_second: _, placing_road: true } => DecisionStage::InitialRoad,
+            Phase::Turn { player: _, turn_phase: TurnPhase::Discard(_), development_phase: _ } => DecisionStage::Discard,
+            Phase::Turn { player: _, turn_phase: TurnPhase::Trade(_), development_phase: _ } => DecisionStage::TradeResponse,
+            Phase::Turn { player: _, turn_phase: TurnPhase::MoveThief, development_phase: _ } => DecisionStage::MoveThief,
+            Phase::Turn { player: _, turn_phase: _, development_phase: DevelopmentPhase::KnightActive } => DecisionStage::MoveThief,
+            Phase::Turn { player: _, turn_phase: _, development_phase: DevelopmentPhase::RoadBuildingActive { two_left: _ } } => DecisionStage::RoadBuilding,
+            Phase::Turn { player: _, turn_phase: _, development_phase: DevelopmentPhase::YearOfPlentyActive { two_left: _ } } => DecisionStage::YearOfPlenty,
+            _ => DecisionStage::Main,
+        }
+    }
 }
 
+/// Decisions made of several actions, or restricted to a single kind of action
+///
+/// Outside of the main stage, the actions of the stage are the expected ones,
+/// though during road building, year of plenty and knight the main actions stay legal.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecisionStage {
+    Main = 0,
+    InitialSettlement = 1,
+    InitialRoad = 2,
+    Discard = 3,
+    MoveThief = 4,
+    RoadBuilding = 5,
+    YearOfPlenty = 6,
+    TradeResponse = 7,
+}
+
+impl DecisionStage {
+    pub const COUNT: usize = 8;
+
+    pub const ALL: [DecisionStage; DecisionStage::COUNT] = [
+        DecisionStage::Main,
+        DecisionStage::InitialSettlement,
+        DecisionStage::InitialRoad,
+        DecisionStage::Discard,
+        DecisionStage::MoveThief,
+        DecisionStage::RoadBuilding,
+        DecisionStage::YearOfPlenty,
+        DecisionStage::TradeResponse,
+    ];
+
+    /// Is the action one of the actions of the stage, every action belongs to the main stage
+    pub fn contains(&self, action: &Action) -> bool {
+        match self {
+            DecisionStage::Main => true,
+            DecisionStage::InitialSettlement => matches!(action, Action::BuildSettlement { intersection: _ }),
+            DecisionStage::InitialRoad | DecisionStage::RoadBuilding => matches!(action, Action::BuildRoad { path: _ }),
+            DecisionStage::Discard => matches!(action, Action::Keep { resources: _ } | Action::Discard { resource: _ }),
+            DecisionStage::MoveThief => matches!(action, Action::MoveThief { hex: _, victim: _ }),
+            DecisionStage::YearOfPlenty => matches!(action, Action::ChooseFreeResource { resource: _ }),
+            DecisionStage::TradeResponse => matches!(action, Action::AcceptTrade | Action::RejectTrade | Action::CounterTrade { give: _, receive: _ }
+                | Action::WithdrawTrade | Action::ReviseTrade { give: _, receive: _ }),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TurnPhase {
     PreRoll,
     Discard(PlayerId),
     MoveThief,
-    Free
+    Free,
+    /// An opponent, or the player after a counter offer, answers a trade offer
+    Trade(TradeOffer),
+    /// An opponent builds or buys development cards after the turn, in games of 5 or 6 players, see [Rules::special_building](super::Rules::special_building)
+    SpecialBuilding(PlayerId),
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DevelopmentPhase {
     Ready,
     KnightActive,
@@ -75,6 +178,11 @@ impl TurnPhase {
         }
     }
 
+    /// Can the player build or buy development cards
+    pub fn can_build(&self) -> bool {
+        matches!(self, TurnPhase::Free | TurnPhase::SpecialBuilding(_))
+    }
+
     pub fn is_discard(&self) -> bool {
         match *self {
             TurnPhase::Discard(_) => true,
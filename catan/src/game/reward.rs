@@ -0,0 +1,105 @@
+use crate::state::{State, PlayerId};
+use super::{Action, Notification};
+
+/// Weights of the rewards a player receives during a game
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RewardConfig {
+    /// By victory point won or lost
+    pub vp_delta: f32,
+    /// Once the game is won
+    pub win_bonus: f32,
+    /// By resource produced by the dice
+    pub income: f32,
+    /// By road, settlement or city built
+    pub building: f32,
+    /// By action refused by the game
+    pub illegal_penalty: f32,
+    /// Whether victory point rewards are divided by the victory points winning the game
+    pub normalize_vp: bool,
+    /// Factor of every reward
+    pub scale: f32,
+}
+
+impl RewardConfig {
+    /// Only victory points are rewarded
+    pub const VP: RewardConfig = RewardConfig {
+        vp_delta: 1.0,
+        win_bonus: 0.0,
+        income: 0.0,
+        building: 0.0,
+        illegal_penalty: 0.0,
+        normalize_vp: false,
+        scale: 1.0,
+    };
+}
+
+impl Default for RewardConfig {
+    fn default() -> Self {
+        RewardConfig::VP
+    }
+}
+
+/// Rewards of a player accumulated between two of its decisions
+#[derive(Clone, Debug)]
+pub struct RewardTracker {
+    pub config: RewardConfig,
+    player: PlayerId,
+    /// Victory points of the player when last rewarded
+    victory_points: u8,
+    pending: f32,
+}
+
+impl RewardTracker {
+    pub fn new(config: RewardConfig) -> RewardTracker {
+        RewardTracker {
+            config,
+            player: PlayerId::NONE,
+            victory_points: 0,
+            pending: 0.0,
+        }
+    }
+
+    pub fn new_game(&mut self, player: PlayerId, state: &State) {
+        self.player = player;
+        self.victory_points = state.get_player_total_vp(player);
+        self.pending = 0.0;
+    }
+
+    pub fn notify(&mut self, notification: &Notification) {
+        match notification {
+            Notification::ActionPlayed {
+                by,
+                action: Action::BuildRoad { path: _ } | Action::BuildSettlement { intersection: _ } | Action::BuildCity { intersection: _ },
+            } if *by == self.player => self.pending += self.config.building,
+            Notification::ResourcesRolled { roll: _, resources } => {
+                if let Some(resources) = resources.get(self.player.to_usize()) {
+                    self.pending += self.config.income * resources.total() as f32;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn illegal_action(&mut self) {
+        self.pending += self.config.illegal_penalty;
+    }
+
+    /// Reward since the last one taken
+    pub fn take(&mut self, state: &State) -> f32 {
+        let victory_points = state.get_player_total_vp(self.player);
+        let mut vp_reward = self.config.vp_delta * (victory_points as f32 - self.victory_points as f32);
+        if self.config.normalize_vp {
+            vp_reward /= state.get_rules().victory_points as f32;
+        }
+        let reward = (self.pending + vp_reward) * self.config.scale;
+        self.victory_points = victory_points;
+        self.pending = 0.0;
+        reward
+    }
+
+    /// Reward since the last one taken, at the end of the game
+    pub fn finish(&mut self, state: &State, winner: PlayerId) -> f32 {
+        let bonus = if winner == self.player { self.config.win_bonus } else { 0.0 };
+        self.take(state) + bonus * self.config.scale
+    }
+}
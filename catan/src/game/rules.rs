@@ -0,0 +1,332 @@
+use serde::{Serialize, Deserialize};
+
+use crate::utils::DevelopmentCard;
+use super::TurnPhase;
+
+/// What happens to a road building card when fewer than its two roads can be built
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum RoadBuildingFallback {
+    /// The card can be played as long as one road can be built, the roads that can't be built are lost
+    Forfeit,
+    /// The card can only be played when both roads can be built
+    Forbid,
+}
+
+/// What happens to the roads, settlements and cities of a player who [resigns](crate::game::Action::Resign)
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ResignedPieces {
+    /// The pieces stay on the board, still blocking the others, but produce nothing and can't be stolen from
+    #[default]
+    Frozen,
+    /// The pieces are taken off the board, freeing their spots
+    Removed,
+}
+
+/// Initial settlements giving the player a resource of each producing hex around them
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum SetupResources {
+    /// The second settlement, as in the standard game
+    #[default]
+    Second,
+    First,
+    Both,
+    /// No settlement gives resources, the players only starting with the [initial resources](crate::game::GameConfig::initial_resources)
+    None,
+}
+
+impl SetupResources {
+    /// Whether the first or second initial settlement gives resources
+    pub fn grants(&self, placing_second: bool) -> bool {
+        match self {
+            SetupResources::Second => placing_second,
+            SetupResources::First => !placing_second,
+            SetupResources::Both => true,
+            SetupResources::None => false,
+        }
+    }
+}
+
+fn default_victory_points() -> u8 {
+    10
+}
+
+fn default_discard_limit() -> u8 {
+    7
+}
+
+fn default_special_building() -> bool {
+    true
+}
+
+fn default_roll_before_building() -> bool {
+    true
+}
+
+/// Most development cards of each kind a player can play in a game, a kind without a cap being unlimited
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct DevelopmentCaps {
+    pub knight: Option<u8>,
+    pub road_building: Option<u8>,
+    pub year_of_plenty: Option<u8>,
+    pub monopole: Option<u8>,
+}
+
+impl DevelopmentCaps {
+    pub const NONE: DevelopmentCaps = DevelopmentCaps {
+        knight: None,
+        road_building: None,
+        year_of_plenty: None,
+        monopole: None,
+    };
+
+    /// Cap of the card, victory points never being played
+    pub fn cap(&self, card: DevelopmentCard) -> Option<u8> {
+        match card {
+            DevelopmentCard::Knight => self.knight,
+            DevelopmentCard::RoadBuilding => self.road_building,
+            DevelopmentCard::YearOfPlenty => self.year_of_plenty,
+            DevelopmentCard::Monopole => self.monopole,
+            DevelopmentCard::VictoryPoint => None,
+        }
+    }
+
+    pub fn set_cap(&mut self, card: DevelopmentCard, cap: Option<u8>) -> Result<(), String> {
+        match card {
+            DevelopmentCard::Knight => self.knight = cap,
+            DevelopmentCard::RoadBuilding => self.road_building = cap,
+            DevelopmentCard::YearOfPlenty => self.year_of_plenty = cap,
+            DevelopmentCard::Monopole => self.monopole = cap,
+            DevelopmentCard::VictoryPoint => return Err("Victory point cards aren't played".to_string()),
+        }
+        Ok(())
+    }
+
+    /// Whether a player having played `played` cards of the kind can play one more
+    pub fn allows(&self, card: DevelopmentCard, played: u8) -> bool {
+        match self.cap(card) {
+            Some(cap) => played < cap,
+            None => true,
+        }
+    }
+}
+
+/// Rule toggles and house rules of a game, checked by [legal](crate::game::legal::legal) and applied by the game loop
+///
+/// Every toggle is on in a standard game, they can be switched off to simplify the game
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Rules {
+    /// Rolling a 7 makes players discard and the thief move. When off, a 7 produces nothing and knights can't be played
+    pub robber: bool,
+    /// Development cards can be bought
+    pub development_cards: bool,
+    /// Opponents' hands are concealed. When off, observations may show them entirely
+    pub hidden_hands: bool,
+    /// The player whose turn it is can offer trades to the opponents
+    pub domestic_trading: bool,
+    /// Whether a road building card can be played with a single road to build
+    pub road_building_fallback: RoadBuildingFallback,
+    /// Victory points winning the game
+    #[serde(default = "default_victory_points")]
+    pub victory_points: u8,
+    /// Most cards a player keeps without discarding when a 7 is rolled
+    #[serde(default = "default_discard_limit")]
+    pub discard_limit: u8,
+    /// Turn from which a 7 makes players discard and the thief move, earlier ones producing nothing
+    #[serde(default)]
+    pub robber_from_turn: u32,
+    /// In games of 5 or 6 players, each opponent in turn can build after the turn of a player, see [TurnPhase::SpecialBuilding](crate::game::TurnPhase::SpecialBuilding)
+    #[serde(default = "default_special_building")]
+    pub special_building: bool,
+    /// Building, buying development cards and trading with the bank wait for the dice to be rolled.
+    /// When off, they are allowed before rolling too, the offers to the opponents still waiting for the roll
+    #[serde(default = "default_roll_before_building")]
+    pub roll_before_building: bool,
+    /// Caps on the development cards each player can play in a game, for balance experiments
+    #[serde(default)]
+    pub development_caps: DevelopmentCaps,
+    /// Cities are only built on a settlement declared with [ReserveCity](crate::game::Action::ReserveCity) on an earlier turn
+    #[serde(default)]
+    pub city_reservation: bool,
+    /// Pieces of the players who resign, the others playing on without them
+    #[serde(default)]
+    pub resigned_pieces: ResignedPieces,
+    /// Every that many turns, the number tokens of two producing hexes drawn at random swap places,
+    /// training policies robust to a drifting board. Never when 0
+    #[serde(default)]
+    pub token_swap_every: u32,
+    /// Observations only give the size of the development deck in buckets of [DECK_BUCKET](Rules::DECK_BUCKET) cards,
+    /// to study what knowing the exact count is worth
+    #[serde(default)]
+    pub bucketed_deck: bool,
+    /// Initial settlements giving the resources around them
+    #[serde(default)]
+    pub setup_resources: SetupResources,
+    /// Most trades the player whose turn it is can offer to the opponents in a turn, bounding the negotiations of bots. Unlimited when 0
+    #[serde(default)]
+    pub trade_proposals_per_turn: u8,
+}
+
+impl Rules {
+    pub const STANDARD: Rules = Rules {
+        robber: true,
+        development_cards: true,
+        hidden_hands: true,
+        domestic_trading: true,
+        road_building_fallback: RoadBuildingFallback::Forfeit,
+        victory_points: 10,
+        discard_limit: 7,
+        robber_from_turn: 0,
+        special_building: true,
+        roll_before_building: true,
+        development_caps: DevelopmentCaps::NONE,
+        city_reservation: false,
+        resigned_pieces: ResignedPieces::Frozen,
+        token_swap_every: 0,
+        bucketed_deck: false,
+        setup_resources: SetupResources::Second,
+        trade_proposals_per_turn: 0,
+    };
+
+    /// Cards of a bucket of the development deck size, see [bucketed_deck](Rules::bucketed_deck)
+    pub const DECK_BUCKET: u8 = 5;
+
+    pub const SIMPLIFIED: Rules = Rules {
+        robber: false,
+        development_cards: false,
+        hidden_hands: false,
+        domestic_trading: false,
+        road_building_fallback: RoadBuildingFallback::Forfeit,
+        victory_points: 10,
+        discard_limit: 7,
+        robber_from_turn: 0,
+        special_building: false,
+        roll_before_building: false,
+        development_caps: DevelopmentCaps::NONE,
+        city_reservation: false,
+        resigned_pieces: ResignedPieces::Frozen,
+        token_swap_every: 0,
+        bucketed_deck: false,
+        setup_resources: SetupResources::Second,
+        trade_proposals_per_turn: 0,
+    };
+
+    /// Toggles of other rules, keeping the house rules of these ones
+    pub fn with_toggles(self, toggles: Rules) -> Rules {
+        Rules {
+            victory_points: self.victory_points,
+            discard_limit: self.discard_limit,
+            robber_from_turn: self.robber_from_turn,
+            development_caps: self.development_caps,
+            city_reservation: self.city_reservation,
+            resigned_pieces: self.resigned_pieces,
+            token_swap_every: self.token_swap_every,
+            bucketed_deck: self.bucketed_deck,
+            setup_resources: self.setup_resources,
+            trade_proposals_per_turn: self.trade_proposals_per_turn,
+            ..toggles
+        }
+    }
+
+    /// Whether a 7 rolled during the turn makes players discard and the thief move
+    pub fn robber_active(&self, turn: u32) -> bool {
+        self.robber && turn >= self.robber_from_turn
+    }
+
+    /// Whether the player of the phase can build and buy development cards
+    pub fn building_allowed(&self, turn_phase: TurnPhase) -> bool {
+        turn_phase.can_build() || (!self.roll_before_building && turn_phase == TurnPhase::PreRoll)
+    }
+
+    /// Whether the player whose turn it is can trade with the bank
+    pub fn bank_trading_allowed(&self, turn_phase: TurnPhase) -> bool {
+        turn_phase == TurnPhase::Free || (!self.roll_before_building && turn_phase == TurnPhase::PreRoll)
+    }
+
+    /// Whether two number tokens swap places as the turn starts
+    pub fn token_swap_due(&self, turn: u32) -> bool {
+        self.token_swap_every > 0 && turn.is_multiple_of(self.token_swap_every)
+    }
+
+    /// Size of the development deck as observed, rounded up to a whole bucket when the deck is bucketed, an empty deck staying empty
+    pub fn observed_deck_size(&self, size: u8) -> u8 {
+        if self.bucketed_deck {
+            size.div_ceil(Rules::DECK_BUCKET) * Rules::DECK_BUCKET
+        } else {
+            size
+        }
+    }
+
+    /// Whether the opponents get a special building phase after each turn of a game of the player count
+    pub fn special_building_active(&self, player_count: u8) -> bool {
+        self.special_building && player_count >= 5
+    }
+
+    /// Hash of the rules, the same across runs and platforms, telling records played under other rules apart
+    pub fn fingerprint(&self) -> u64 {
+        let bytes = bincode::serialize(self).expect("Failed serializing rules");
+        // FNV-1a
+        bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::STANDARD
+    }
+}
+
+/// A schedule of rules, from the simplest to the standard game
+///
+/// The trainer moves from one stage to the next with [set_stage](Curriculum::set_stage),
+/// and the rules of the current stage are used for every new game.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Curriculum {
+    stages: Vec<Rules>,
+    stage: usize,
+}
+
+impl Curriculum {
+    pub fn new(stages: Vec<Rules>) -> Curriculum {
+        assert!(!stages.is_empty(), "A curriculum needs at least one stage");
+        Curriculum {
+            stages,
+            stage: 0,
+        }
+    }
+
+    /// A single stage curriculum, always playing standard games
+    pub fn standard() -> Curriculum {
+        Curriculum::new(vec![Rules::STANDARD])
+    }
+
+    pub fn stage(&self) -> usize {
+        self.stage
+    }
+
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    pub fn set_stage(&mut self, stage: usize) {
+        assert!(stage < self.stages.len(), "Stage {} out of the {} stages of the curriculum", stage, self.stages.len());
+        self.stage = stage;
+    }
+
+    /// Rules of the current stage
+    pub fn rules(&self) -> Rules {
+        self.stages[self.stage]
+    }
+}
+
+impl Default for Curriculum {
+    /// Starts from the simplified game, then enables the robber, the development cards and finally hides hands,
+    /// allows trading and makes the players roll before building
+    fn default() -> Self {
+        Curriculum::new(vec![
+            Rules::SIMPLIFIED,
+            Rules { robber: true, ..Rules::SIMPLIFIED },
+            Rules { robber: true, development_cards: true, ..Rules::SIMPLIFIED },
+            Rules::STANDARD,
+        ])
+    }
+}
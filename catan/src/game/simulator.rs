@@ -0,0 +1,106 @@
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use crate::board::Layout;
+use crate::state::{State, PlayerId, TricellState, audit};
+use crate::player::generate_possible_actions;
+use super::{Action, Checkpoint, Error, Notification, Phase, legal, apply};
+
+/// Position rolled forward and back by tree searches, without players nor threads
+///
+/// Each [applied action](Simulator::apply_action) pushes a copy of the position it left,
+/// popped back by [undo_action](Simulator::undo_action).
+/// Dice, development cards and stolen resources are drawn from the seeded rng of the simulator.
+pub struct Simulator {
+    phase: Phase,
+    state: State,
+    rng: SmallRng,
+    /// Every action the state allows, legal or not
+    possible_actions: Vec<Action>,
+    /// Positions the applied actions were played from, the last one on top
+    undo_stack: Vec<(Phase, State)>,
+}
+
+impl Simulator {
+    pub fn new(phase: Phase, state: State, seed: u64) -> Simulator {
+        let mut possible_actions = Vec::new();
+        generate_possible_actions(&mut possible_actions, PlayerId::FIRST, &state);
+        Simulator { phase, state, rng: SmallRng::seed_from_u64(seed), possible_actions, undo_stack: Vec::new() }
+    }
+
+    /// Simulator of the position of a checkpoint, on a board of the layout
+    pub fn from_checkpoint(checkpoint: &Checkpoint, layout: &'static Layout, seed: u64) -> Result<Simulator, String> {
+        checkpoint.check()?;
+        let state = checkpoint.state.restore::<TricellState>(layout).map_err(|error| format!("Failed restoring checkpoint: {:?}", error))?;
+        Ok(Simulator::new(checkpoint.phase, state, seed))
+    }
+
+    /// Copy of the position as seen by the player, the hidden hands and development cards of the others being dealt again
+    pub fn determinized(&self, player: PlayerId, seed: u64) -> Simulator {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let state = audit::conceal(&self.state, self.state.get_layout(), &self.phase, player, &mut rng);
+        Simulator { phase: self.phase, state, rng, possible_actions: self.possible_actions.clone(), undo_stack: Vec::new() }
+    }
+
+    pub fn phase(&self) -> &Phase {
+        &self.phase
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Player to decide, [PlayerId::NONE] once the game is finished
+    pub fn player(&self) -> PlayerId {
+        match self.phase {
+            Phase::FinishedGame { winner: _ } => PlayerId::NONE,
+            phase => phase.player(),
+        }
+    }
+
+    pub fn winner(&self) -> Option<PlayerId> {
+        match self.phase {
+            Phase::FinishedGame { winner } => Some(winner),
+            _ => None,
+        }
+    }
+
+    /// Actions applied and not undone yet
+    pub fn depth(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    pub fn legal_actions(&self) -> Vec<Action> {
+        if self.winner().is_some() {
+            return Vec::new();
+        }
+        self.possible_actions.iter()
+            .filter(|action| legal::legal(&self.phase, &self.state, **action).is_ok())
+            .copied()
+            .collect()
+    }
+
+    /// Plays the action of the player to decide, which is refused when illegal
+    pub fn apply_action(&mut self, action: Action) -> Result<Option<Notification>, Error> {
+        legal::legal(&self.phase, &self.state, action)?;
+        self.undo_stack.push((self.phase, self.state.clone_state()));
+        Ok(apply(&mut self.phase, &mut self.state, action, &mut self.rng))
+    }
+
+    /// Takes back the last action applied, false when there is none
+    pub fn undo_action(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some((phase, state)) => {
+                self.phase = phase;
+                self.state = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Checkpoint of the current position, resumed by [Game::resume](super::Game::resume)
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(self.phase, &self.state)
+    }
+}
@@ -0,0 +1,296 @@
+use crate::state::{State, PlayerId, TransferKind};
+use crate::utils::{Coord, Resource, Harbor, DevelopmentCard, DevelopmentCards};
+use super::Action;
+
+/// Number of occurrences of each dice roll, from 2 to 12
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct RollCounts {
+    counts: [u32; RollCounts::COUNT],
+}
+
+impl RollCounts {
+    pub const COUNT: usize = 11;
+    pub const MIN_ROLL: u8 = 2;
+
+    pub fn new() -> RollCounts {
+        RollCounts {
+            counts: [0; RollCounts::COUNT],
+        }
+    }
+
+    pub fn record(&mut self, roll: u8) {
+        self.counts[(roll - RollCounts::MIN_ROLL) as usize] += 1;
+    }
+
+    pub fn get(&self, roll: u8) -> u32 {
+        self.counts[(roll - RollCounts::MIN_ROLL) as usize]
+    }
+
+    pub fn counts(&self) -> &[u32; RollCounts::COUNT] {
+        &self.counts
+    }
+
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.counts = [0; RollCounts::COUNT];
+    }
+
+    /// Probability of rolling `roll` with two fair dice
+    pub fn probability(roll: u8) -> f64 {
+        (6 - (7 - roll as i8).unsigned_abs()) as f64 / 36.0
+    }
+
+    /// Pearson's chi-square statistic of the counts against two fair dice
+    ///
+    /// Returns 0 if no roll was recorded
+    pub fn chi_square(&self) -> f64 {
+        let total = self.total() as f64;
+        if total == 0.0 {
+            return 0.0;
+        }
+        let mut chi_square = 0.0;
+        for (i, count) in self.counts.iter().enumerate() {
+            let expected = total * RollCounts::probability(i as u8 + RollCounts::MIN_ROLL);
+            let diff = *count as f64 - expected;
+            chi_square += diff * diff / expected;
+        }
+        chi_square
+    }
+
+    /// Probability of observing a chi-square statistic at least this large with fair dice
+    ///
+    /// With 10 degrees of freedom the survival function has a closed form.
+    /// A very small p-value over a long run is a strong hint of a seeding or rng bug.
+    pub fn p_value(&self) -> f64 {
+        let half = self.chi_square() / 2.0;
+        let mut term = 1.0;
+        let mut sum = 1.0;
+        for i in 1..(RollCounts::COUNT - 1) / 2 {
+            term *= half / i as f64;
+            sum += term;
+        }
+        (-half).exp() * sum
+    }
+}
+
+impl Default for RollCounts {
+    fn default() -> Self {
+        RollCounts::new()
+    }
+}
+
+/// Dice rolls of the current game and of every game played so far
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct DiceStatistics {
+    pub game: RollCounts,
+    pub run: RollCounts,
+}
+
+impl DiceStatistics {
+    pub fn new() -> DiceStatistics {
+        DiceStatistics {
+            game: RollCounts::new(),
+            run: RollCounts::new(),
+        }
+    }
+
+    pub fn new_game(&mut self) {
+        self.game.clear();
+    }
+
+    pub fn record(&mut self, roll: u8) {
+        self.game.record(roll);
+        self.run.record(roll);
+    }
+}
+
+/// Wins and victory points of each seat over many games
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct SeatStatistics {
+    games: u32,
+    wins: Vec<u32>,
+    victory_points: Vec<u32>,
+}
+
+impl SeatStatistics {
+    pub fn new(player_count: u8) -> SeatStatistics {
+        SeatStatistics {
+            games: 0,
+            wins: vec![0; player_count as usize],
+            victory_points: vec![0; player_count as usize],
+        }
+    }
+
+    /// Records a finished game, the winner being [PlayerId::NONE] if the game was interrupted
+    pub fn record(&mut self, state: &State, winner: PlayerId) {
+        self.games += 1;
+        if let Some(winner) = winner.option() {
+            self.wins[winner.to_usize()] += 1;
+        }
+        for (seat, vp) in self.victory_points.iter_mut().enumerate() {
+            *vp += state.get_player_total_vp(PlayerId::from(seat)) as u32;
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.games
+    }
+
+    pub fn wins(&self) -> &Vec<u32> {
+        &self.wins
+    }
+
+    /// Total victory points of each seat
+    pub fn victory_points(&self) -> &Vec<u32> {
+        &self.victory_points
+    }
+
+    pub fn win_rate(&self, seat: PlayerId) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.wins[seat.to_usize()] as f64 / self.games as f64
+        }
+    }
+}
+
+/// Development cards bought and played by a player, split by whether the dice were already rolled
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DevelopmentUsage {
+    pub bought: u8,
+    pub pre_roll: DevelopmentCards,
+    pub post_roll: DevelopmentCards,
+}
+
+impl DevelopmentUsage {
+    pub fn new() -> DevelopmentUsage {
+        DevelopmentUsage {
+            bought: 0,
+            pre_roll: DevelopmentCards::new(),
+            post_roll: DevelopmentCards::new(),
+        }
+    }
+
+    /// Records an action of the player, `rolled` telling if the dice were rolled this turn
+    pub fn record(&mut self, action: Action, rolled: bool) {
+        let card = match action {
+            Action::BuyDevelopment => {
+                self.bought += 1;
+                return;
+            }
+            Action::DevelopmentKnight => DevelopmentCard::Knight,
+            Action::DevelopmentRoadBuilding => DevelopmentCard::RoadBuilding,
+            Action::DevelopmentYearOfPlenty => DevelopmentCard::YearOfPlenty,
+            Action::DevelopmentMonopole { resource: _ } => DevelopmentCard::Monopole,
+            _ => return,
+        };
+        if rolled {
+            self.post_roll[card] += 1;
+        } else {
+            self.pre_roll[card] += 1;
+        }
+    }
+
+    /// Cards played at any time of the turn
+    pub fn played(&self) -> DevelopmentCards {
+        let mut played = self.pre_roll;
+        played += self.post_roll;
+        played
+    }
+}
+
+impl Default for DevelopmentUsage {
+    fn default() -> Self {
+        DevelopmentUsage::new()
+    }
+}
+
+/// Metrics of a single game, to check players learn sensible play rather than quirks of the engine
+///
+/// Actions and rolls are recorded while the game is played, the resources and trades being read
+/// from the ledger by [finish](GameStats::finish). Counts are indexed by [PlayerId].
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameStats {
+    pub dice: RollCounts,
+    /// Resources received from the bank, the thief and monopolies, trades apart
+    pub resources_gained: Vec<[u32; Resource::COUNT]>,
+    /// Player who moved the thief and its hex, in order
+    pub robber_placements: Vec<(PlayerId, Coord)>,
+    pub development_played: Vec<DevelopmentCards>,
+    pub bank_trades: Vec<u32>,
+    /// Trades with the bank made at the rate of each harbor, indexed by [Harbor::to_usize]
+    pub harbor_trades: Vec<[u32; Harbor::COUNT]>,
+    /// Accepted trades between players, counted for both of them
+    pub player_trades: Vec<u32>,
+    /// Turns ended when the game finished
+    pub turns: u32,
+    /// None until the game is finished, and for interrupted games
+    pub winner: Option<PlayerId>,
+    /// Chance of winning of each player after each turn, when the game has a [WinProbEstimator](super::WinProbEstimator)
+    pub win_probabilities: Vec<Vec<f32>>,
+}
+
+impl GameStats {
+    pub fn new(player_count: u8) -> GameStats {
+        let players = player_count as usize;
+        GameStats {
+            dice: RollCounts::new(),
+            resources_gained: vec![[0; Resource::COUNT]; players],
+            robber_placements: Vec::new(),
+            development_played: vec![DevelopmentCards::new(); players],
+            bank_trades: vec![0; players],
+            harbor_trades: vec![[0; Harbor::COUNT]; players],
+            player_trades: vec![0; players],
+            turns: 0,
+            winner: None,
+            win_probabilities: Vec::new(),
+        }
+    }
+
+    pub fn record_roll(&mut self, roll: u8) {
+        self.dice.record(roll);
+    }
+
+    /// Records an action played by the player
+    pub fn record_action(&mut self, player: PlayerId, action: Action) {
+        let card = match action {
+            Action::MoveThief { hex, victim: _ } => {
+                self.robber_placements.push((player, hex));
+                return;
+            }
+            Action::DevelopmentKnight => DevelopmentCard::Knight,
+            Action::DevelopmentRoadBuilding => DevelopmentCard::RoadBuilding,
+            Action::DevelopmentYearOfPlenty => DevelopmentCard::YearOfPlenty,
+            Action::DevelopmentMonopole { resource: _ } => DevelopmentCard::Monopole,
+            _ => return,
+        };
+        self.development_played[player.to_usize()][card] += 1;
+    }
+
+    /// Reads the resources, trades and harbors used in the finished game from the ledger of its state,
+    /// the winner being [PlayerId::NONE] if the game was interrupted
+    pub fn finish(&mut self, state: &State, winner: PlayerId) {
+        for transfer in state.get_ledger().transfers().iter() {
+            match transfer.kind {
+                TransferKind::Setup | TransferKind::Production | TransferKind::Steal | TransferKind::YearOfPlenty | TransferKind::Monopole => {
+                    let gained = &mut self.resources_gained[transfer.to.to_usize()];
+                    for resource in Resource::ALL.iter() {
+                        gained[resource.to_usize()] += transfer.resources[*resource] as u32;
+                    }
+                }
+                // A trade is recorded as what each party gives, the bank giving to the player last
+                TransferKind::TradeBank if transfer.from != PlayerId::NONE => self.bank_trades[transfer.from.to_usize()] += 1,
+                TransferKind::TradePlayer => self.player_trades[transfer.from.to_usize()] += 1,
+                _ => (),
+            }
+        }
+        for (p, harbor_trades) in self.harbor_trades.iter_mut().enumerate() {
+            *harbor_trades = state.get_ledger().harbor_usage(PlayerId::from(p));
+        }
+        self.turns = state.get_turn();
+        self.winner = winner.option();
+    }
+}
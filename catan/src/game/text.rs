@@ -0,0 +1,313 @@
+use serde::{Serialize, Deserialize};
+
+use crate::utils::{Resource, Resources};
+use crate::state::PlayerId;
+use super::{Action, GameEvent, Building, Notification};
+
+/// Language of the descriptions of actions, events and notifications shown to people
+///
+/// Each language has a [Texts] catalog, so that adding one only takes translating a catalog.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Lang {
+    #[default]
+    En,
+    Fr,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::En, Lang::Fr];
+
+    /// Language of its ISO 639-1 code, like `en` or `fr`
+    pub fn from_code(code: &str) -> Option<Lang> {
+        Lang::ALL.iter().copied().find(|lang| lang.code() == code)
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.texts().code
+    }
+
+    pub fn texts(&self) -> &'static Texts {
+        match self {
+            Lang::En => &EN,
+            Lang::Fr => &FR,
+        }
+    }
+}
+
+/// Words and templates of a language, each `{}` of a template being replaced by an argument, in order
+pub struct Texts {
+    pub code: &'static str,
+    /// Names of the resources, in the order of [Resource::ALL]
+    pub resources: [&'static str; Resource::COUNT],
+    pub road: &'static str,
+    pub settlement: &'static str,
+    pub city: &'static str,
+    pub player: &'static str,
+    pub bank: &'static str,
+    pub nothing: &'static str,
+    pub nobody: &'static str,
+
+    pub end_turn: &'static str,
+    pub roll_dice: &'static str,
+    pub move_thief: &'static str,
+    pub move_thief_alone: &'static str,
+    pub build_road: &'static str,
+    pub build_settlement: &'static str,
+    pub build_city: &'static str,
+    pub reserve_city: &'static str,
+    pub trade_bank: &'static str,
+    pub propose_trade: &'static str,
+    pub accept_trade: &'static str,
+    pub reject_trade: &'static str,
+    pub counter_trade: &'static str,
+    pub withdraw_trade: &'static str,
+    pub revise_trade: &'static str,
+    pub buy_development: &'static str,
+    pub knight: &'static str,
+    pub road_building: &'static str,
+    pub year_of_plenty: &'static str,
+    pub choose_free_resource: &'static str,
+    pub monopole: &'static str,
+    pub keep: &'static str,
+    pub discard: &'static str,
+    pub resign: &'static str,
+    pub exit: &'static str,
+
+    pub dice_rolled: &'static str,
+    pub resources_produced: &'static str,
+    pub thief_moved: &'static str,
+    pub card_stolen: &'static str,
+    pub hidden_card_stolen: &'static str,
+    pub building_placed: &'static str,
+    pub trade_executed: &'static str,
+    pub longest_road: &'static str,
+    pub longest_road_lost: &'static str,
+    pub game_ended: &'static str,
+    pub action_played: &'static str,
+    pub thief_rolled: &'static str,
+    pub placement_finished: &'static str,
+}
+
+pub const EN: Texts = Texts {
+    code: "en",
+    resources: ["brick", "lumber", "ore", "grain", "wool"],
+    road: "road",
+    settlement: "settlement",
+    city: "city",
+    player: "player {}",
+    bank: "the bank",
+    nothing: "nothing",
+    nobody: "nobody",
+
+    end_turn: "End the turn",
+    roll_dice: "Roll the dice",
+    move_thief: "Move the thief to {} and steal from {}",
+    move_thief_alone: "Move the thief to {}",
+    build_road: "Build a road at {}",
+    build_settlement: "Build a settlement at {}",
+    build_city: "Build a city at {}",
+    reserve_city: "Reserve a city at {}",
+    trade_bank: "Trade {} for {} with the bank",
+    propose_trade: "Offer {} for {}",
+    accept_trade: "Accept the trade",
+    reject_trade: "Reject the trade",
+    counter_trade: "Counter with {} for {}",
+    withdraw_trade: "Withdraw the offer",
+    revise_trade: "Offer {} for {} instead",
+    buy_development: "Buy a development card",
+    knight: "Play a knight",
+    road_building: "Play road building",
+    year_of_plenty: "Play year of plenty",
+    choose_free_resource: "Take {} from the bank",
+    monopole: "Play a monopoly on {}",
+    keep: "Keep {}",
+    discard: "Discard {}",
+    resign: "Resign",
+    exit: "Leave the game",
+
+    dice_rolled: "{} rolled {}",
+    resources_produced: "{} produced {}",
+    thief_moved: "{} moved the thief to {}",
+    card_stolen: "{} stole {} from {}",
+    hidden_card_stolen: "{} stole a card from {}",
+    building_placed: "{} built a {} at {}",
+    trade_executed: "{} traded {} for {} with {}",
+    longest_road: "{} holds the longest road, of {} roads",
+    longest_road_lost: "Nobody holds the longest road anymore",
+    game_ended: "{} won the game",
+    action_played: "{}: {}",
+    thief_rolled: "A 7 was rolled, the thief moves",
+    placement_finished: "The initial placement is over",
+};
+
+pub const FR: Texts = Texts {
+    code: "fr",
+    resources: ["argile", "bois", "minerai", "blé", "laine"],
+    road: "route",
+    settlement: "colonie",
+    city: "ville",
+    player: "joueur {}",
+    bank: "la banque",
+    nothing: "rien",
+    nobody: "personne",
+
+    end_turn: "Finir le tour",
+    roll_dice: "Lancer les dés",
+    move_thief: "Déplacer le voleur en {} et voler {}",
+    move_thief_alone: "Déplacer le voleur en {}",
+    build_road: "Construire une route en {}",
+    build_settlement: "Construire une colonie en {}",
+    build_city: "Construire une ville en {}",
+    reserve_city: "Réserver une ville en {}",
+    trade_bank: "Échanger {} contre {} avec la banque",
+    propose_trade: "Proposer {} contre {}",
+    accept_trade: "Accepter l'échange",
+    reject_trade: "Refuser l'échange",
+    counter_trade: "Contre-proposer {} contre {}",
+    withdraw_trade: "Retirer l'offre",
+    revise_trade: "Proposer plutôt {} contre {}",
+    buy_development: "Acheter une carte développement",
+    knight: "Jouer un chevalier",
+    road_building: "Jouer construction de routes",
+    year_of_plenty: "Jouer invention",
+    choose_free_resource: "Prendre {} à la banque",
+    monopole: "Jouer un monopole sur {}",
+    keep: "Garder {}",
+    discard: "Défausser {}",
+    resign: "Abandonner",
+    exit: "Quitter la partie",
+
+    dice_rolled: "{} a fait {}",
+    resources_produced: "{} a produit {}",
+    thief_moved: "{} a déplacé le voleur en {}",
+    card_stolen: "{} a volé {} à {}",
+    hidden_card_stolen: "{} a volé une carte à {}",
+    building_placed: "{} a construit : {} en {}",
+    trade_executed: "{} a échangé {} contre {} avec {}",
+    longest_road: "{} a la route la plus longue, de {} routes",
+    longest_road_lost: "Personne n'a plus la route la plus longue",
+    game_ended: "{} a gagné la partie",
+    action_played: "{} : {}",
+    thief_rolled: "Un 7 est sorti, le voleur se déplace",
+    placement_finished: "La mise en place est terminée",
+};
+
+/// Template with each `{}` replaced by the next argument
+fn fill(template: &str, args: &[&str]) -> String {
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    let mut text = parts.next().unwrap_or("").to_string();
+    for part in parts {
+        text.push_str(args.next().copied().unwrap_or(""));
+        text.push_str(part);
+    }
+    text
+}
+
+impl Texts {
+    pub fn resource(&self, resource: Resource) -> &'static str {
+        self.resources[resource as usize]
+    }
+
+    /// Counts of the resources, like `2 brick, 1 ore`
+    pub fn resources(&self, resources: Resources) -> String {
+        let counts: Vec<String> = Resource::ALL.iter()
+            .filter(|res| resources[**res] != 0)
+            .map(|res| format!("{} {}", resources[*res], self.resource(*res)))
+            .collect();
+        if counts.is_empty() { self.nothing.to_string() } else { counts.join(", ") }
+    }
+
+    pub fn player(&self, player: PlayerId) -> String {
+        fill(self.player, &[&player.to_u8().to_string()])
+    }
+
+    pub fn building(&self, building: Building) -> &'static str {
+        match building {
+            Building::Road => self.road,
+            Building::Settlement => self.settlement,
+            Building::City => self.city,
+        }
+    }
+}
+
+impl Action {
+    /// Description of the action, as the player deciding reads it
+    pub fn describe(&self, lang: Lang) -> String {
+        let texts = lang.texts();
+        match *self {
+            Action::EndTurn => texts.end_turn.to_string(),
+            Action::RollDice => texts.roll_dice.to_string(),
+            Action::MoveThief { hex, victim } if victim == PlayerId::NONE => fill(texts.move_thief_alone, &[&hex.to_string()]),
+            Action::MoveThief { hex, victim } => fill(texts.move_thief, &[&hex.to_string(), &texts.player(victim)]),
+            Action::BuildRoad { path } => fill(texts.build_road, &[&path.to_string()]),
+            Action::BuildSettlement { intersection } => fill(texts.build_settlement, &[&intersection.to_string()]),
+            Action::BuildCity { intersection } => fill(texts.build_city, &[&intersection.to_string()]),
+            Action::ReserveCity { intersection } => fill(texts.reserve_city, &[&intersection.to_string()]),
+            Action::TradeBank { given, asked } => fill(texts.trade_bank, &[texts.resource(given), texts.resource(asked)]),
+            Action::TradeBankBundle { trades, asked } => fill(texts.trade_bank, &[&texts.resources(trades), &texts.resources(asked)]),
+            Action::ProposeTrade { give, receive } => fill(texts.propose_trade, &[&texts.resources(give), &texts.resources(receive)]),
+            Action::AcceptTrade => texts.accept_trade.to_string(),
+            Action::RejectTrade => texts.reject_trade.to_string(),
+            Action::CounterTrade { give, receive } => fill(texts.counter_trade, &[&texts.resources(give), &texts.resources(receive)]),
+            Action::BuyDevelopment => texts.buy_development.to_string(),
+            Action::DevelopmentKnight => texts.knight.to_string(),
+            Action::DevelopmentRoadBuilding => texts.road_building.to_string(),
+            Action::DevelopmentYearOfPlenty => texts.year_of_plenty.to_string(),
+            Action::ChooseFreeResource { resource } => fill(texts.choose_free_resource, &[texts.resource(resource)]),
+            Action::DevelopmentMonopole { resource } => fill(texts.monopole, &[texts.resource(resource)]),
+            Action::Keep { resources } => fill(texts.keep, &[&texts.resources(resources)]),
+            Action::Discard { resource } => fill(texts.discard, &[texts.resource(resource)]),
+            Action::Resign => texts.resign.to_string(),
+            Action::Exit => texts.exit.to_string(),
+            Action::WithdrawTrade => texts.withdraw_trade.to_string(),
+            Action::ReviseTrade { give, receive } => fill(texts.revise_trade, &[&texts.resources(give), &texts.resources(receive)]),
+        }
+    }
+}
+
+impl GameEvent {
+    /// Description of the event, players being named by their seat
+    pub fn describe(&self, lang: Lang) -> String {
+        let texts = lang.texts();
+        match self {
+            GameEvent::DiceRolled { player, roll } => fill(texts.dice_rolled, &[&texts.player(*player), &roll.to_string()]),
+            GameEvent::ResourcesDistributed { roll, resources } => {
+                let produced: Vec<String> = resources.iter().enumerate()
+                    .filter(|(_, resources)| **resources != Resources::ZERO)
+                    .map(|(p, resources)| format!("{} ({})", texts.resources(*resources), texts.player(PlayerId::from(p))))
+                    .collect();
+                let produced = if produced.is_empty() { texts.nothing.to_string() } else { produced.join(", ") };
+                fill(texts.resources_produced, &[&roll.to_string(), &produced])
+            }
+            GameEvent::RobberMoved { player, hex } => fill(texts.thief_moved, &[&texts.player(*player), &hex.to_string()]),
+            GameEvent::CardStolen { thief, victim, resource: Some(resource) } =>
+                fill(texts.card_stolen, &[&texts.player(*thief), texts.resource(*resource), &texts.player(*victim)]),
+            GameEvent::CardStolen { thief, victim, resource: None } => fill(texts.hidden_card_stolen, &[&texts.player(*thief), &texts.player(*victim)]),
+            GameEvent::BuildingPlaced { player, building, coord } =>
+                fill(texts.building_placed, &[&texts.player(*player), texts.building(*building), &coord.to_string()]),
+            GameEvent::TradeExecuted { player, partner, given, received } => {
+                let partner = partner.map_or(texts.bank.to_string(), |partner| texts.player(partner));
+                fill(texts.trade_executed, &[&texts.player(*player), &texts.resources(*given), &texts.resources(*received), &partner])
+            }
+            GameEvent::LongestRoadChanged { holder: Some((player, length)) } => fill(texts.longest_road, &[&texts.player(*player), &length.to_string()]),
+            GameEvent::LongestRoadChanged { holder: None } => texts.longest_road_lost.to_string(),
+            GameEvent::GameEnded { winner } => fill(texts.game_ended, &[&texts.player(*winner)]),
+        }
+    }
+}
+
+impl Notification {
+    /// Description of the notification, players being named by their seat
+    pub fn describe(&self, lang: Lang) -> String {
+        let texts = lang.texts();
+        match self {
+            Notification::ActionPlayed { by, action } => fill(texts.action_played, &[&texts.player(*by), &action.describe(lang)]),
+            Notification::ResourcesRolled { roll, resources } => GameEvent::ResourcesDistributed { roll: *roll, resources: resources.clone() }.describe(lang),
+            Notification::GameFinished { winner } if *winner == PlayerId::NONE => fill(texts.game_ended, &[texts.nobody]),
+            Notification::GameFinished { winner } => fill(texts.game_ended, &[&texts.player(*winner)]),
+            Notification::ThiefRolled => texts.thief_rolled.to_string(),
+            Notification::InitialPlacementFinished => texts.placement_finished.to_string(),
+        }
+    }
+}
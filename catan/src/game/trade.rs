@@ -0,0 +1,55 @@
+use serde::{Serialize, Deserialize};
+
+use crate::utils::{Resource, Resources};
+use crate::state::{PlayerId, TradeAnswer, TradeResponse};
+use super::{Phase, TurnPhase};
+
+/// Trade offered by the player whose turn it is to one opponent at a time
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct TradeOffer {
+    /// Opponent answering the offer, or who countered it
+    pub partner: PlayerId,
+    /// Resources given by the player whose turn it is
+    pub give: Resources,
+    /// Resources received by the player whose turn it is
+    pub receive: Resources,
+    /// Once countered, the player whose turn it is answers the counter offer
+    pub countered: bool,
+}
+
+impl TradeOffer {
+    /// Can these resources be exchanged: something on each side, and no resource on both sides
+    pub fn valid_terms(give: Resources, receive: Resources) -> bool {
+        give.total() > 0 && receive.total() > 0
+            && Resource::ALL.iter().all(|res| give[*res] >= 0 && receive[*res] >= 0 && (give[*res] == 0 || receive[*res] == 0))
+    }
+}
+
+/// Offer waiting for an answer, with the answers given to the offers of the turn so far, as every player sees them
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Negotiation {
+    /// Player whose turn it is
+    pub proposer: PlayerId,
+    pub offer: TradeOffer,
+    /// Players who answered the offers of the turn and how, in order
+    pub responses: Vec<(PlayerId, TradeAnswer)>,
+}
+
+impl Negotiation {
+    /// Negotiation of the phase, from the trade responses of the ledger, None when no offer waits for an answer
+    pub fn pending(phase: &Phase, turn: u32, responses: &[TradeResponse]) -> Option<Negotiation> {
+        match phase {
+            Phase::Turn { player: proposer, turn_phase: TurnPhase::Trade(offer), development_phase: _ } => Some(Negotiation {
+                proposer: *proposer,
+                offer: *offer,
+                responses: responses_of_turn(responses, turn).iter().map(|response| (response.responder, response.answer)).collect(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Trade responses of the turn, the ledger recording them in order
+pub fn responses_of_turn(responses: &[TradeResponse], turn: u32) -> &[TradeResponse] {
+    &responses[responses.partition_point(|response| response.turn < turn)..]
+}
@@ -0,0 +1,166 @@
+use super::ActionCategory;
+
+/// Kinds of [Phase](super::Phase), the nodes of the phase machine
+///
+/// A pending discard, thief move or trade answer takes precedence over an active development card,
+/// which takes precedence over whether the dice were rolled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PhaseKind {
+    InitialSettlement,
+    InitialRoad,
+    PreRoll,
+    Discard,
+    MoveThief,
+    Free,
+    Trade,
+    /// The thief of a knight is still to be moved
+    Knight,
+    /// Free roads of a road building card are left
+    RoadBuilding,
+    /// Free resources of a year of plenty card are left
+    YearOfPlenty,
+    /// An opponent builds after the turn, in games of 5 or 6 players
+    SpecialBuilding,
+    Finished,
+}
+
+impl PhaseKind {
+    pub const COUNT: usize = 12;
+
+    pub const ALL: [PhaseKind; PhaseKind::COUNT] = [
+        PhaseKind::InitialSettlement,
+        PhaseKind::InitialRoad,
+        PhaseKind::PreRoll,
+        PhaseKind::Discard,
+        PhaseKind::MoveThief,
+        PhaseKind::Free,
+        PhaseKind::Trade,
+        PhaseKind::Knight,
+        PhaseKind::RoadBuilding,
+        PhaseKind::YearOfPlenty,
+        PhaseKind::SpecialBuilding,
+        PhaseKind::Finished,
+    ];
+}
+
+/// Phases an action of a category can lead to from a phase
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transition {
+    pub from: PhaseKind,
+    pub action: ActionCategory,
+    pub to: &'static [PhaseKind],
+}
+
+use PhaseKind::*;
+
+const fn t(from: PhaseKind, action: ActionCategory, to: &'static [PhaseKind]) -> Transition {
+    Transition { from, action, to }
+}
+
+/// Every transition [apply](super::apply) can make, checked after each action of a game in debug builds
+///
+/// The building actions may end the game, rolling the dice may call for discards or the thief,
+/// and an active development card stays active through the other actions of the turn.
+/// Ending the turn of a game of 5 or 6 players starts the special building of the opponents.
+/// The player deciding can resign from any phase of the turns, which moves on as if it answered or ended its turn.
+pub const TRANSITIONS: &[Transition] = &[
+    // ## Initial placement
+    t(InitialSettlement, ActionCategory::BuildSettlement, &[InitialRoad]),
+    t(InitialRoad, ActionCategory::BuildRoad, &[InitialSettlement, PreRoll]),
+    // ## Before rolling
+    t(PreRoll, ActionCategory::RollDice, &[Free, Discard, MoveThief]),
+    t(PreRoll, ActionCategory::DevelopmentKnight, &[Knight, Finished]),
+    t(PreRoll, ActionCategory::DevelopmentRoadBuilding, &[RoadBuilding]),
+    t(PreRoll, ActionCategory::DevelopmentYearOfPlenty, &[YearOfPlenty]),
+    t(PreRoll, ActionCategory::DevelopmentMonopole, &[PreRoll]),
+    // Building and bank trades before rolling, when the rules allow them
+    t(PreRoll, ActionCategory::BuildRoad, &[PreRoll, Finished]),
+    t(PreRoll, ActionCategory::BuildSettlement, &[PreRoll, Finished]),
+    t(PreRoll, ActionCategory::BuildCity, &[PreRoll, Finished]),
+    t(PreRoll, ActionCategory::ReserveCity, &[PreRoll]),
+    t(PreRoll, ActionCategory::TradeBank, &[PreRoll]),
+    t(PreRoll, ActionCategory::TradeBankBundle, &[PreRoll]),
+    t(PreRoll, ActionCategory::BuyDevelopment, &[PreRoll, Finished]),
+    // ## Rolling a 7
+    t(Discard, ActionCategory::Keep, &[Discard, MoveThief]),
+    t(Discard, ActionCategory::Discard, &[Discard, MoveThief]),
+    t(MoveThief, ActionCategory::MoveThief, &[Free, Knight, RoadBuilding, YearOfPlenty]),
+    // ## After rolling
+    t(Free, ActionCategory::EndTurn, &[PreRoll, SpecialBuilding]),
+    t(Free, ActionCategory::BuildRoad, &[Free, Finished]),
+    t(Free, ActionCategory::BuildSettlement, &[Free, Finished]),
+    t(Free, ActionCategory::BuildCity, &[Free, Finished]),
+    t(Free, ActionCategory::ReserveCity, &[Free]),
+    t(Free, ActionCategory::TradeBank, &[Free]),
+    t(Free, ActionCategory::TradeBankBundle, &[Free]),
+    t(Free, ActionCategory::ProposeTrade, &[Trade]),
+    t(Free, ActionCategory::BuyDevelopment, &[Free, Finished]),
+    t(Free, ActionCategory::DevelopmentKnight, &[Knight, Finished]),
+    t(Free, ActionCategory::DevelopmentRoadBuilding, &[RoadBuilding]),
+    t(Free, ActionCategory::DevelopmentYearOfPlenty, &[YearOfPlenty]),
+    t(Free, ActionCategory::DevelopmentMonopole, &[Free]),
+    // ## Trading between players
+    t(Trade, ActionCategory::AcceptTrade, &[Free, Knight, RoadBuilding, YearOfPlenty]),
+    t(Trade, ActionCategory::RejectTrade, &[Trade, Free, Knight, RoadBuilding, YearOfPlenty]),
+    t(Trade, ActionCategory::CounterTrade, &[Trade]),
+    t(Trade, ActionCategory::WithdrawTrade, &[Free, Knight, RoadBuilding, YearOfPlenty]),
+    t(Trade, ActionCategory::ReviseTrade, &[Trade]),
+    // ## Knight
+    t(Knight, ActionCategory::MoveThief, &[PreRoll, Free]),
+    t(Knight, ActionCategory::RollDice, &[Knight, Discard, MoveThief]),
+    t(Knight, ActionCategory::EndTurn, &[PreRoll, SpecialBuilding]),
+    t(Knight, ActionCategory::BuildRoad, &[Knight, Finished]),
+    t(Knight, ActionCategory::BuildSettlement, &[Knight, Finished]),
+    t(Knight, ActionCategory::BuildCity, &[Knight, Finished]),
+    t(Knight, ActionCategory::ReserveCity, &[Knight]),
+    t(Knight, ActionCategory::TradeBank, &[Knight]),
+    t(Knight, ActionCategory::TradeBankBundle, &[Knight]),
+    t(Knight, ActionCategory::ProposeTrade, &[Trade]),
+    t(Knight, ActionCategory::BuyDevelopment, &[Knight, Finished]),
+    // ## Road building
+    t(RoadBuilding, ActionCategory::BuildRoad, &[RoadBuilding, PreRoll, Free, Finished]),
+    t(RoadBuilding, ActionCategory::RollDice, &[RoadBuilding, Discard, MoveThief]),
+    t(RoadBuilding, ActionCategory::EndTurn, &[PreRoll, SpecialBuilding]),
+    t(RoadBuilding, ActionCategory::BuildSettlement, &[RoadBuilding, Finished]),
+    t(RoadBuilding, ActionCategory::BuildCity, &[RoadBuilding, Finished]),
+    t(RoadBuilding, ActionCategory::ReserveCity, &[RoadBuilding]),
+    t(RoadBuilding, ActionCategory::TradeBank, &[RoadBuilding]),
+    t(RoadBuilding, ActionCategory::TradeBankBundle, &[RoadBuilding]),
+    t(RoadBuilding, ActionCategory::ProposeTrade, &[Trade]),
+    t(RoadBuilding, ActionCategory::BuyDevelopment, &[RoadBuilding, Finished]),
+    // ## Year of plenty
+    t(YearOfPlenty, ActionCategory::ChooseFreeResource, &[YearOfPlenty, PreRoll, Free]),
+    t(YearOfPlenty, ActionCategory::RollDice, &[YearOfPlenty, Discard, MoveThief]),
+    t(YearOfPlenty, ActionCategory::EndTurn, &[PreRoll, SpecialBuilding]),
+    t(YearOfPlenty, ActionCategory::BuildRoad, &[YearOfPlenty, Finished]),
+    t(YearOfPlenty, ActionCategory::BuildSettlement, &[YearOfPlenty, Finished]),
+    t(YearOfPlenty, ActionCategory::BuildCity, &[YearOfPlenty, Finished]),
+    t(YearOfPlenty, ActionCategory::ReserveCity, &[YearOfPlenty]),
+    t(YearOfPlenty, ActionCategory::TradeBank, &[YearOfPlenty]),
+    t(YearOfPlenty, ActionCategory::TradeBankBundle, &[YearOfPlenty]),
+    t(YearOfPlenty, ActionCategory::ProposeTrade, &[Trade]),
+    t(YearOfPlenty, ActionCategory::BuyDevelopment, &[YearOfPlenty, Finished]),
+    // ## Special building, after the turn in games of 5 or 6 players
+    t(SpecialBuilding, ActionCategory::BuildRoad, &[SpecialBuilding, Finished]),
+    t(SpecialBuilding, ActionCategory::BuildSettlement, &[SpecialBuilding, Finished]),
+    t(SpecialBuilding, ActionCategory::BuildCity, &[SpecialBuilding, Finished]),
+    t(SpecialBuilding, ActionCategory::ReserveCity, &[SpecialBuilding]),
+    t(SpecialBuilding, ActionCategory::BuyDevelopment, &[SpecialBuilding, Finished]),
+    t(SpecialBuilding, ActionCategory::EndTurn, &[SpecialBuilding, PreRoll]),
+    // ## Resigning, the last player still in the game winning
+    t(PreRoll, ActionCategory::Resign, &[PreRoll, Finished]),
+    // The turn player leaving ends the turn, the last opponent to discard leaving lets the turn player move the thief
+    t(Discard, ActionCategory::Resign, &[Discard, MoveThief, PreRoll, Finished]),
+    t(MoveThief, ActionCategory::Resign, &[PreRoll, Finished]),
+    t(Free, ActionCategory::Resign, &[PreRoll, Finished]),
+    t(Trade, ActionCategory::Resign, &[Trade, Free, Knight, RoadBuilding, YearOfPlenty, PreRoll, Finished]),
+    t(Knight, ActionCategory::Resign, &[PreRoll, Finished]),
+    t(RoadBuilding, ActionCategory::Resign, &[PreRoll, Finished]),
+    t(YearOfPlenty, ActionCategory::Resign, &[PreRoll, Finished]),
+    t(SpecialBuilding, ActionCategory::Resign, &[SpecialBuilding, PreRoll, Finished]),
+];
+
+/// Is the transition listed in [TRANSITIONS]
+pub fn listed(from: PhaseKind, action: ActionCategory, to: PhaseKind) -> bool {
+    TRANSITIONS.iter().any(|transition| transition.from == from && transition.action == action && transition.to.contains(&to))
+}
@@ -0,0 +1,182 @@
+#[cfg(feature = "onnx")]
+use std::path::Path;
+
+#[cfg(feature = "onnx")]
+use tract_onnx::prelude::*;
+
+use crate::state::{State, PlayerId};
+use crate::board::utils::topology::Topology;
+
+/// Estimates every player's chance of winning from a given state
+///
+/// Estimators are evaluated by the [Game](crate::game::Game) after each turn.
+/// The returned vector is indexed by [PlayerId] and should sum to 1.
+pub trait WinProbEstimator {
+    fn estimate(&self, state: &State) -> Vec<f32>;
+}
+
+/// Simple features of a player's position used by [LogisticEstimator]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct WinFeatures {
+    pub victory_points: f32,
+    pub production: f32,
+    pub resources: f32,
+    pub development_cards: f32,
+    pub knights: f32,
+    pub continous_road: f32,
+}
+
+impl WinFeatures {
+    pub const COUNT: usize = 6;
+
+    pub fn new(player: PlayerId, state: &State) -> WinFeatures {
+        let hand = state.get_player_hand(player);
+        WinFeatures {
+            victory_points: state.get_player_total_vp(player) as f32,
+            production: production_pips(player, state) as f32,
+            resources: hand.resources.total() as f32,
+            development_cards: (hand.development_cards.total() + hand.new_development_cards.total()) as f32,
+            knights: hand.knights as f32,
+            continous_road: hand.continous_road as f32,
+        }
+    }
+
+    pub fn to_array(&self) -> [f32; WinFeatures::COUNT] {[
+        self.victory_points,
+        self.production,
+        self.resources,
+        self.development_cards,
+        self.knights,
+        self.continous_road,
+    ]}
+}
+
+/// Sum of the pips of the hexes around the player's buildings (cities count twice)
+pub fn production_pips(player: PlayerId, state: &State) -> u32 {
+    let mut pips = 0;
+    for intersection in state.get_layout().intersections.iter() {
+        if let Ok(Some((p, is_city))) = state.get_dynamic_intersection(*intersection) {
+            if p != player {
+                continue;
+            }
+            let multiplier = if is_city { 2 } else { 1 };
+            for hex in state.intersection_hex_neighbours(*intersection).unwrap_or_default() {
+                if let Ok(hex) = state.get_static_hex(hex) {
+                    pips += multiplier * hex.get_pips() as u32;
+                }
+            }
+        }
+    }
+    pips
+}
+
+/// Logistic model over [WinFeatures]
+///
+/// Each player gets a logistic score from its features, and the scores are normalized so they sum to 1.
+/// The default weights are hand-tuned and only meant as a reasonable baseline.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LogisticEstimator {
+    pub weights: [f32; WinFeatures::COUNT],
+    pub bias: f32,
+}
+
+impl LogisticEstimator {
+    pub const DEFAULT: LogisticEstimator = LogisticEstimator {
+        weights: [0.9, 0.08, 0.05, 0.3, 0.1, 0.1],
+        bias: -6.0,
+    };
+
+    pub fn new(weights: [f32; WinFeatures::COUNT], bias: f32) -> LogisticEstimator {
+        LogisticEstimator {
+            weights,
+            bias,
+        }
+    }
+
+    pub fn score(&self, features: &WinFeatures) -> f32 {
+        let z = features.to_array().iter()
+            .zip(self.weights.iter())
+            .fold(self.bias, |z, (x, w)| z + x * w);
+        1.0 / (1.0 + (-z).exp())
+    }
+}
+
+impl Default for LogisticEstimator {
+    fn default() -> Self {
+        LogisticEstimator::DEFAULT
+    }
+}
+
+impl WinProbEstimator for LogisticEstimator {
+    fn estimate(&self, state: &State) -> Vec<f32> {
+        let scores: Vec<f32> = (0..state.player_count())
+            .map(|p| self.score(&WinFeatures::new(PlayerId::from(p), state)))
+            .collect();
+        normalize(&scores)
+    }
+}
+
+/// Scores scaled to sum to 1, even chances when they are all 0
+fn normalize(scores: &[f32]) -> Vec<f32> {
+    let total: f32 = scores.iter().sum();
+    if total > 0.0 {
+        scores.iter().map(|score| score / total).collect()
+    } else {
+        vec![1.0 / scores.len() as f32; scores.len()]
+    }
+}
+
+/// Model trained outside, read from an ONNX file
+///
+/// The model takes the [WinFeatures] of every player as a `[players, WinFeatures::COUNT]` float tensor,
+/// and gives a score per player, normalized like the ones of [LogisticEstimator].
+/// It is optimized for one number of players, the one of the games it estimates.
+#[cfg(feature = "onnx")]
+pub struct OnnxEstimator {
+    players: u8,
+    model: TypedRunnableModel<TypedModel>,
+}
+
+#[cfg(feature = "onnx")]
+impl OnnxEstimator {
+    pub fn from_path(path: &Path, players: u8) -> Result<OnnxEstimator, String> {
+        let model = tract_onnx::onnx()
+            .model_for_path(path)
+            .and_then(|model| model.with_input_fact(0, f32::fact([players as usize, WinFeatures::COUNT]).into()))
+            .and_then(|model| model.into_optimized())
+            .and_then(|model| model.into_runnable())
+            .map_err(|error| format!("Bad ONNX model {}: {:#}", path.display(), error))?;
+        Ok(OnnxEstimator {
+            players,
+            model,
+        })
+    }
+
+    pub fn players(&self) -> u8 {
+        self.players
+    }
+
+    /// Scores of the players, one per player
+    pub fn scores(&self, state: &State) -> Result<Vec<f32>, String> {
+        if state.player_count() != self.players {
+            return Err(format!("Model of {} players, for a game of {}", self.players, state.player_count()));
+        }
+        let features: Vec<f32> = (0..state.player_count())
+            .flat_map(|p| WinFeatures::new(PlayerId::from(p), state).to_array())
+            .collect();
+        let input = Tensor::from_shape(&[self.players as usize, WinFeatures::COUNT], &features).map_err(|error| error.to_string())?;
+        let outputs = self.model.run(tvec!(input.into())).map_err(|error| error.to_string())?;
+        let scores = outputs[0].as_slice::<f32>().map_err(|error| error.to_string())?;
+        if scores.len() != self.players as usize {
+            return Err(format!("Model gave {} scores for {} players", scores.len(), self.players));
+        }
+        Ok(scores.to_vec())
+    }
+}
+
+#[cfg(feature = "onnx")]
+impl WinProbEstimator for OnnxEstimator {
+    fn estimate(&self, state: &State) -> Vec<f32> {
+        normalize(&self.scores(state).expect("Failed running the ONNX model"))
+    }
+}
@@ -0,0 +1,168 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use crate::arena::{ArenaReport, INITIAL_ELO};
+
+/// Index of the members, in the directory of a hall of fame
+pub const INDEX: &str = "hall_of_fame.toml";
+
+/// Checkpoint or bot of a hall of fame, with its rating
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Member {
+    pub name: String,
+    /// Model file, in the directory of the hall of fame
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Bot played by the member instead of a model, like `heuristic` or `mcts`
+    #[serde(default)]
+    pub bot: Option<String>,
+    pub elo: f64,
+    /// Finished games played in recorded tournaments
+    #[serde(default)]
+    pub games: u32,
+    #[serde(default)]
+    pub wins: u32,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct Index {
+    #[serde(default)]
+    members: Vec<Member>,
+}
+
+/// Directory of model checkpoints and bots, with their ratings
+///
+/// Models are copied into the directory, and the members are listed in its [INDEX], saved after every change.
+/// Tournaments between members update their ratings through [record](HallOfFame::record).
+pub struct HallOfFame {
+    directory: PathBuf,
+    members: Vec<Member>,
+}
+
+impl HallOfFame {
+    /// Hall of fame of the directory, created when missing
+    pub fn open(directory: impl AsRef<Path>) -> Result<HallOfFame, String> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory).map_err(|error| format!("Failed creating {}: {}", directory.display(), error))?;
+        let index = directory.join(INDEX);
+        let index: Index = if index.exists() {
+            let content = fs::read_to_string(&index).map_err(|error| format!("Failed reading {}: {}", index.display(), error))?;
+            toml::from_str(&content).map_err(|error| format!("Bad hall of fame index {}: {}", index.display(), error))?
+        } else {
+            Index::default()
+        };
+        Ok(HallOfFame {
+            directory,
+            members: index.members,
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let content = toml::to_string(&Index { members: self.members.clone() }).map_err(|error| error.to_string())?;
+        // Written aside then renamed, not to leave a truncated index behind
+        let path = self.directory.join(INDEX);
+        let written = self.directory.join(format!("{}.tmp", INDEX));
+        fs::write(&written, content).map_err(|error| format!("Failed writing {}: {}", written.display(), error))?;
+        fs::rename(&written, &path).map_err(|error| format!("Failed writing {}: {}", path.display(), error))
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Members, in the order they were added
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    pub fn member(&self, name: &str) -> Option<&Member> {
+        self.members.iter().find(|member| member.name == name)
+    }
+
+    /// Members from the best rated one
+    pub fn ranked(&self) -> Vec<&Member> {
+        let mut ranked: Vec<&Member> = self.members.iter().collect();
+        ranked.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+        ranked
+    }
+
+    /// Path of the model of the member, [None] for bots
+    pub fn model_path(&self, member: &Member) -> Option<PathBuf> {
+        member.model.as_ref().map(|model| self.directory.join(model))
+    }
+
+    fn insert(&mut self, member: Member) -> Result<&Member, String> {
+        self.members.push(member);
+        self.save()?;
+        Ok(self.members.last().unwrap())
+    }
+
+    fn check_new_name(&self, name: &str) -> Result<(), String> {
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(format!("Bad member name {:?}", name));
+        }
+        if self.member(name).is_some() {
+            return Err(format!("Member {} already in the hall of fame", name));
+        }
+        Ok(())
+    }
+
+    /// Adds a checkpoint, copying its model file into the directory, rated [INITIAL_ELO]
+    pub fn add_model(&mut self, name: &str, model: &Path) -> Result<&Member, String> {
+        self.check_new_name(name)?;
+        let file = match model.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) => format!("{}.{}", name, extension),
+            None => name.to_string(),
+        };
+        let copy = self.directory.join(&file);
+        fs::copy(model, &copy).map_err(|error| format!("Failed copying {} to {}: {}", model.display(), copy.display(), error))?;
+        self.insert(Member {
+            name: name.to_string(),
+            model: Some(file),
+            bot: None,
+            elo: INITIAL_ELO,
+            games: 0,
+            wins: 0,
+        })
+    }
+
+    /// Adds a bot, rated [INITIAL_ELO]
+    pub fn add_bot(&mut self, name: &str, bot: &str) -> Result<&Member, String> {
+        self.check_new_name(name)?;
+        self.insert(Member {
+            name: name.to_string(),
+            model: None,
+            bot: Some(bot.to_string()),
+            elo: INITIAL_ELO,
+            games: 0,
+            wins: 0,
+        })
+    }
+
+    /// Removes a member, deleting its model file
+    pub fn remove(&mut self, name: &str) -> Result<Member, String> {
+        let i = self.members.iter().position(|member| member.name == name).ok_or_else(|| format!("No member {} in the hall of fame", name))?;
+        let member = self.members.remove(i);
+        if let Some(path) = self.model_path(&member) {
+            fs::remove_file(&path).map_err(|error| format!("Failed removing {}: {}", path.display(), error))?;
+        }
+        self.save()?;
+        Ok(member)
+    }
+
+    /// Updates the members playing a tournament, the entrants named after them, with their results and ratings
+    ///
+    /// The entrants should have been added to the arena with the rating of their member.
+    pub fn record(&mut self, report: &ArenaReport) -> Result<(), String> {
+        for entrant in report.entrants.iter() {
+            if let Some(member) = self.members.iter_mut().find(|member| member.name == entrant.info.name) {
+                member.elo = entrant.elo;
+                member.games += entrant.games;
+                member.wins += entrant.wins;
+            }
+        }
+        self.save()
+    }
+}
@@ -3,6 +3,20 @@ pub mod board;
 pub mod state;
 pub mod game;
 pub mod player;
+pub mod replay;
+pub mod notation;
+pub mod codec;
+pub mod rules;
+pub mod arena;
+pub mod hall_of_fame;
+pub mod tablebase;
+pub mod metrics;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "database")]
+pub mod database;
+#[cfg(feature = "alloc-counter")]
+pub mod alloc_counter;
 
 #[cfg(test)]
 mod test;
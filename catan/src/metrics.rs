@@ -0,0 +1,158 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Destination of scalar metrics, like the win rates of a tournament, to monitor long runs
+pub trait MetricsSink: Send {
+    /// Writes the value of the metric `tag` at `step`
+    fn scalar(&mut self, tag: &str, step: u64, value: f64) -> Result<(), String>;
+
+    /// Makes the written metrics visible to the readers of the sink
+    fn flush(&mut self) -> Result<(), String>;
+}
+
+/// Sink of the path, a CSV file for paths ending with `.csv`, or else a directory of TensorBoard event files
+pub fn open(path: impl AsRef<Path>) -> Result<Box<dyn MetricsSink>, String> {
+    let path = path.as_ref();
+    if path.extension().is_some_and(|extension| extension == "csv") {
+        Ok(Box::new(CsvSink::create(path)?))
+    } else {
+        Ok(Box::new(TensorBoardSink::create(path)?))
+    }
+}
+
+fn wall_time() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |time| time.as_secs_f64())
+}
+
+/// CSV file with a `wall_time,step,tag,value` row per metric
+pub struct CsvSink {
+    writer: BufWriter<File>,
+}
+
+impl CsvSink {
+    /// Creates the file, replacing any previous one
+    pub fn create(path: impl AsRef<Path>) -> Result<CsvSink, String> {
+        let path = path.as_ref();
+        let file = File::create(path).map_err(|error| format!("Failed creating {}: {}", path.display(), error))?;
+        let mut sink = CsvSink { writer: BufWriter::new(file) };
+        writeln!(sink.writer, "wall_time,step,tag,value").map_err(|error| error.to_string())?;
+        Ok(sink)
+    }
+}
+
+impl MetricsSink for CsvSink {
+    fn scalar(&mut self, tag: &str, step: u64, value: f64) -> Result<(), String> {
+        let tag = if tag.contains([',', '"', '\n']) { format!("\"{}\"", tag.replace('"', "\"\"")) } else { tag.to_string() };
+        writeln!(self.writer, "{:.3},{},{},{}", wall_time(), step, tag, value).map_err(|error| error.to_string())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|error| error.to_string())
+    }
+}
+
+/// TensorBoard event file, in a directory read by `tensorboard --logdir`
+///
+/// Events are TFRecords of `Event` protocol buffers, encoded here for the few fields of scalar summaries.
+pub struct TensorBoardSink {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl TensorBoardSink {
+    /// Creates a new event file in the directory, created when missing
+    pub fn create(directory: impl AsRef<Path>) -> Result<TensorBoardSink, String> {
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory).map_err(|error| format!("Failed creating {}: {}", directory.display(), error))?;
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "localhost".to_string());
+        let path = directory.join(format!("events.out.tfevents.{}.{}.{}", wall_time() as u64, host, std::process::id()));
+        let file = File::create(&path).map_err(|error| format!("Failed creating {}: {}", path.display(), error))?;
+        let mut sink = TensorBoardSink { path, writer: BufWriter::new(file) };
+        // Event { wall_time, step: 0, file_version }
+        let mut event = Vec::new();
+        event_header(&mut event, 0);
+        field_bytes(&mut event, 3, b"brain.Event:2");
+        sink.record(&event)?;
+        Ok(sink)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn record(&mut self, data: &[u8]) -> Result<(), String> {
+        let length = (data.len() as u64).to_le_bytes();
+        let mut record = Vec::with_capacity(data.len() + 16);
+        record.extend_from_slice(&length);
+        record.extend_from_slice(&masked_crc32c(&length).to_le_bytes());
+        record.extend_from_slice(data);
+        record.extend_from_slice(&masked_crc32c(data).to_le_bytes());
+        self.writer.write_all(&record).map_err(|error| format!("Failed writing {}: {}", self.path.display(), error))
+    }
+}
+
+impl MetricsSink for TensorBoardSink {
+    fn scalar(&mut self, tag: &str, step: u64, value: f64) -> Result<(), String> {
+        // Event { wall_time, step, summary: Summary { value: [Value { tag, simple_value }] } }
+        let mut value_message = Vec::new();
+        field_bytes(&mut value_message, 1, tag.as_bytes());
+        value_message.push(2 << 3 | 5);
+        value_message.extend_from_slice(&(value as f32).to_le_bytes());
+        let mut summary = Vec::new();
+        field_bytes(&mut summary, 1, &value_message);
+        let mut event = Vec::new();
+        event_header(&mut event, step);
+        field_bytes(&mut event, 5, &summary);
+        self.record(&event)
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|error| format!("Failed writing {}: {}", self.path.display(), error))
+    }
+}
+
+// ## Protocol buffers
+
+fn varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn field_bytes(buffer: &mut Vec<u8>, field: u8, bytes: &[u8]) {
+    buffer.push(field << 3 | 2);
+    varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+/// `wall_time` and `step` fields of an event
+fn event_header(buffer: &mut Vec<u8>, step: u64) {
+    buffer.push(1 << 3 | 1);
+    buffer.extend_from_slice(&wall_time().to_le_bytes());
+    buffer.push(2 << 3);
+    varint(buffer, step);
+}
+
+// ## TFRecord checksums
+
+/// CRC-32C (Castagnoli) of the bytes
+pub fn crc32c(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x82f6_3b78 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Checksum stored by TFRecords
+pub fn masked_crc32c(bytes: &[u8]) -> u32 {
+    let crc = crc32c(bytes);
+    crc.rotate_right(15).wrapping_add(0xa282_ead8)
+}
@@ -0,0 +1,720 @@
+//! Human-readable notation of whole games, to share them and to check them against other implementations
+//!
+//! A record is a header of tags, the setup of the board, then one numbered move per line:
+//!
+//! ```text
+//! [Engine "0.1.0"]
+//! [Board "standard"]
+//! [Seating "2 0 1"]
+//! [Seat0 "Player 2"]
+//! [Result "1"]
+//! rules {"robber":true,...}
+//! hex 2,1 brick 6
+//! hex 0,0 desert
+//! harbor 3,2 generic
+//! thief 0,0
+//! bank brick=19 lumber=19 ore=19 grain=19 wool=19
+//! deck knight=14 road_building=2 year_of_plenty=2 monopole=2 victory_point=5
+//! hand 0 brick=1
+//! estimate 1 0.31 0.25 0.44
+//! 1. 0 BuildSettlement 1,1
+//! 25. 2 RollDice => rolled 8
+//! 26. 2 BuyDevelopment => drew knight
+//! 31. 1 MoveThief 0,0 2 => stole ore
+//! 40. 1 ProposeTrade brick=1 for ore=1
+//! 41. 2 RejectTrade
+//! offer 41 1 0 2:rejected brick=1 for ore=1
+//! ```
+//!
+//! Actions are written as in the rules scenarios, their variant name followed by their parameters,
+//! like `BuildRoad 1,1`, `TradeBank brick ore`, `ProposeTrade brick=1 for ore=1`, `MoveThief 0,0 1`, `Keep brick=2 ore=1` or `Discard wool`.
+//! Moves whose outcome is random are followed by it: the dice rolled, the development card drawn,
+//! the resource stolen, and the resources kept by a discarding player keeping less than allowed.
+//! `Result` is the winning seat, `*` for a game which didn't finish, and `hand` lines the resources of the players before the first move.
+//! `estimate` lines give the chance of winning of each seat after each turn, when the game had a [WinProbEstimator](crate::game::WinProbEstimator).
+//! `offer` lines follow the moves leaving a trade offer waiting for an answer, giving the [Negotiation]: the number of the move,
+//! the proposer, the player answering, the answers of the turn so far (`-` for none), `counter` for a counter offer, then the terms of the proposer.
+//! `#` starts a comment, and the engine and seat tags are only informative.
+
+use serde::{Serialize, Deserialize};
+
+use crate::board::layout::{Layout, BoardLayout, STANDARD_BOARD, EXTENSION_BOARD};
+use crate::game::{Action, Phase, Rules, Negotiation, TradeOffer, ENGINE_VERSION, legal};
+use crate::replay::{Replay, Replayer, Ply, OutcomeRng};
+use crate::state::{State, StateMaker, TricellState, PlayerId, TransferKind, TradeAnswer};
+use crate::utils::{Coord, Hex, LandHex, Harbor, Resource, Resources, DevelopmentCard, DevelopmentCards};
+
+/// Random outcome of a move
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Outcome {
+    Rolled(u8),
+    Drew(DevelopmentCard),
+    Stole(Resource),
+    /// Resources kept by a discarding player who kept less than allowed, the engine picking the other ones
+    Kept(Resources),
+}
+
+/// An action of a record, and its outcome when random
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Move {
+    pub player: PlayerId,
+    pub action: Action,
+    pub outcome: Option<Outcome>,
+}
+
+/// A game from its start, in the notation of the [module](self)
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Record {
+    /// Board the game was played on, `standard` or `extension`
+    pub board: String,
+    /// Player index sitting at each seat
+    pub seating: Vec<usize>,
+    /// Name of the player at each seat
+    pub names: Vec<String>,
+    pub rules: Rules,
+    /// Land hexes
+    pub hexes: Vec<(Coord, LandHex)>,
+    /// Harbor of each intersection next to one
+    pub harbors: Vec<(Coord, Harbor)>,
+    pub thief: Coord,
+    pub bank: Resources,
+    pub development_cards: DevelopmentCards,
+    /// Resources of each player before the first move
+    pub hands: Vec<Resources>,
+    pub moves: Vec<Move>,
+    /// Winner of the game, None when it didn't finish
+    pub winner: Option<PlayerId>,
+    /// Chance of winning of each seat after each turn, empty when the game had no estimator
+    #[serde(default)]
+    pub win_probabilities: Vec<Vec<f32>>,
+    /// Trade offer left waiting for an answer by a move, with the number of the move
+    #[serde(default)]
+    pub negotiations: Vec<(usize, Negotiation)>,
+}
+
+impl Record {
+    /// Record of a replay played on the layout from the start of the game, `names` being the ones of the seats
+    pub fn from_replay(replay: &Replay, layout: &'static Layout, names: Vec<String>) -> Result<Record, String> {
+        let start = &replay.start.state;
+        if replay.start.phase != Phase::START_GAME || !start.placements.is_empty() || !start.roads.is_empty() {
+            return Err("Records start at the beginning of a game".to_string());
+        }
+        let board = board_name(layout).ok_or_else(|| "Records are only written for the standard and extension boards".to_string())?;
+        let mut replayer = Replayer::new(replay, layout).map_err(|error| format!("Bad replay: {:?}", error))?;
+        let (mut moves, mut negotiations) = (Vec::new(), Vec::new());
+        loop {
+            let (hand, transfers) = (before(replayer.state(), replayer.phase()), replayer.state().get_ledger().transfers().len());
+            match replayer.step() {
+                Some(ply) => moves.push(Move { player: ply.player, action: ply.action, outcome: outcome(ply, hand, transfers, replayer.state()) }),
+                None => break,
+            }
+            let state = replayer.state();
+            if let Some(negotiation) = Negotiation::pending(replayer.phase(), state.get_turn(), state.get_ledger().trade_responses()) {
+                negotiations.push((moves.len(), negotiation));
+            }
+        }
+        Ok(Record {
+            board: board.to_string(),
+            seating: replay.seating.clone(),
+            names,
+            rules: start.rules,
+            hexes: start.hexes.iter()
+                .filter_map(|(coord, hex)| match hex {
+                    Hex::Land(hex) => Some((*coord, *hex)),
+                    Hex::Water => None,
+                })
+                .collect(),
+            harbors: start.harbors.clone(),
+            thief: start.thief,
+            bank: start.bank,
+            development_cards: start.development_cards,
+            hands: start.players.iter().map(|hand| hand.resources).collect(),
+            moves,
+            winner: replay.winner,
+            win_probabilities: Vec::new(),
+            negotiations,
+        })
+    }
+
+    /// Board the game was played on
+    pub fn board_layout(&self) -> Result<&'static BoardLayout, String> {
+        match self.board.as_str() {
+            "standard" | "extension" => BoardLayout::named(&self.board),
+            other => Err(format!("Unknown board {}", other)),
+        }
+    }
+
+    /// Replay of the game, playing each move with its outcome
+    pub fn to_replay(&self) -> Result<Replay, String> {
+        let player_count = self.hands.len();
+        if player_count == 0 || player_count > u8::MAX as usize || self.seating.len() != player_count {
+            return Err(format!("Record of {} hands for {} seats", player_count, self.seating.len()));
+        }
+        let mut state = TricellState::new_empty(self.board_layout()?.layout, player_count as u8);
+        state.set_rules(self.rules);
+        for (coord, hex) in self.hexes.iter() {
+            state.set_static_hex(*coord, Hex::Land(*hex)).map_err(|error| format!("Bad hex {}: {:?}", coord, error))?;
+        }
+        for (coord, harbor) in self.harbors.iter() {
+            state.set_static_harbor(*coord, *harbor).map_err(|error| format!("Bad harbor {}: {:?}", coord, error))?;
+        }
+        state.set_thief_hex(self.thief);
+        *state.get_bank_resources_mut() = self.bank;
+        *state.get_development_cards_mut() = self.development_cards;
+        for (p, hand) in self.hands.iter().enumerate().filter(|(_, hand)| **hand != Resources::ZERO) {
+            state.get_player_hand_mut(PlayerId::from(p)).resources = *hand;
+            state.record_transfer(TransferKind::Setup, PlayerId::NONE, PlayerId::from(p), *hand);
+        }
+
+        let mut phase = Phase::START_GAME;
+        let mut replay = Replay::new(phase, &state, self.seating.clone());
+        for (i, played) in self.moves.iter().enumerate() {
+            let number = i + 1;
+            if played.player != phase.player() || matches!(phase, Phase::FinishedGame { .. }) {
+                return Err(format!("Move {}: {:?} plays instead of {:?} in {:?}", number, played.player, phase.player(), phase));
+            }
+            legal::legal(&phase, &state, played.action)
+                .map_err(|error| format!("Move {}: illegal {:?}: {:?}", number, played.action, error))?;
+            let picks = picks(played, &phase, &state).map_err(|error| format!("Move {}: {}", number, error))?;
+            let (hand, transfers) = (before(&state, &phase), state.get_ledger().transfers().len());
+            replay.record(&mut phase, &mut state, played.action, &mut OutcomeRng::new(picks));
+            let applied = outcome(replay.plies.last().unwrap(), hand, transfers, &state);
+            if applied != played.outcome {
+                return Err(format!("Move {}: {:?} gave {:?} instead of {:?}", number, played.action, applied, played.outcome));
+            }
+        }
+        replay.winner = match phase {
+            Phase::FinishedGame { winner } => Some(winner),
+            _ => None,
+        };
+        if replay.winner != self.winner {
+            return Err(format!("Record won by {:?}, but its moves by {:?}", self.winner, replay.winner));
+        }
+        Ok(replay)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed serializing record")
+    }
+
+    pub fn from_json(json: &str) -> Result<Record, String> {
+        serde_json::from_str(json).map_err(|error| format!("Bad record: {}", error))
+    }
+
+    /// Text of the record, see the [module](self)
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![
+            format!("[Engine \"{}\"]", ENGINE_VERSION),
+            format!("[Board \"{}\"]", self.board),
+            format!("[Seating \"{}\"]", self.seating.iter().map(|player| player.to_string()).collect::<Vec<_>>().join(" ")),
+        ];
+        for (seat, name) in self.names.iter().enumerate().filter(|(_, name)| !name.is_empty()) {
+            lines.push(format!("[Seat{} \"{}\"]", seat, name));
+        }
+        lines.push(format!("[Result \"{}\"]", self.winner.map_or("*".to_string(), |winner| winner.to_u8().to_string())));
+        lines.push(format!("rules {}", serde_json::to_string(&self.rules).expect("Failed serializing rules")));
+        for (coord, hex) in self.hexes.iter() {
+            lines.push(match hex {
+                LandHex::Prod(res, number) => format!("hex {} {} {}", write_coord(*coord), resource_name(*res), number),
+                LandHex::Desert => format!("hex {} desert", write_coord(*coord)),
+            });
+        }
+        for (coord, harbor) in self.harbors.iter() {
+            lines.push(match harbor {
+                Harbor::Special(res) => format!("harbor {} {}", write_coord(*coord), resource_name(*res)),
+                _ => format!("harbor {} generic", write_coord(*coord)),
+            });
+        }
+        lines.push(format!("thief {}", write_coord(self.thief)));
+        lines.push(format!("bank {}", write_resources(self.bank)));
+        let cards: Vec<String> = DevelopmentCard::ALL.iter()
+            .map(|card| format!("{}={}", card_name(*card), self.development_cards[*card]))
+            .collect();
+        lines.push(format!("deck {}", cards.join(" ")));
+        for (p, hand) in self.hands.iter().enumerate().filter(|(_, hand)| **hand != Resources::ZERO) {
+            lines.push(format!("hand {} {}", p, write_resources(*hand)));
+        }
+        for (turn, estimates) in self.win_probabilities.iter().enumerate() {
+            let estimates: Vec<String> = estimates.iter().map(|estimate| estimate.to_string()).collect();
+            lines.push(format!("estimate {} {}", turn + 1, estimates.join(" ")));
+        }
+        let mut negotiations = self.negotiations.iter().peekable();
+        for (i, played) in self.moves.iter().enumerate() {
+            let outcome = match played.outcome {
+                Some(Outcome::Rolled(roll)) => format!(" => rolled {}", roll),
+                Some(Outcome::Drew(card)) => format!(" => drew {}", card_name(card)),
+                Some(Outcome::Stole(res)) => format!(" => stole {}", resource_name(res)),
+                Some(Outcome::Kept(resources)) => format!(" => kept {}", write_resources(resources)).trim_end().to_string(),
+                None => String::new(),
+            };
+            lines.push(format!("{}. {} {}{}", i + 1, played.player.to_u8(), write_action(played.action), outcome));
+            while let Some((_, negotiation)) = negotiations.next_if(|(number, _)| *number <= i + 1) {
+                lines.push(format!("offer {} {}", i + 1, write_negotiation(negotiation)));
+            }
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+
+    /// Record of a text, see the [module](self)
+    pub fn from_text(text: &str) -> Result<Record, String> {
+        let mut record = Record {
+            board: String::new(),
+            seating: Vec::new(),
+            names: Vec::new(),
+            rules: Rules::STANDARD,
+            hexes: Vec::new(),
+            harbors: Vec::new(),
+            thief: Coord::ZERO,
+            bank: Resources::ZERO,
+            development_cards: DevelopmentCards::new(),
+            hands: Vec::new(),
+            moves: Vec::new(),
+            winner: None,
+            win_probabilities: Vec::new(),
+            negotiations: Vec::new(),
+        };
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            // Names in tags may hold a `#`
+            let line = if line.starts_with('[') { line } else { line.split('#').next().unwrap_or("").trim() };
+            if line.is_empty() {
+                continue;
+            }
+            read_line(&mut record, line).map_err(|error| format!("Bad record line {}: {}", i + 1, error))?;
+        }
+        if record.board.is_empty() {
+            return Err("Bad record: missing Board tag".to_string());
+        }
+        record.names.resize(record.seating.len(), String::new());
+        Ok(record)
+    }
+}
+
+fn read_line(record: &mut Record, line: &str) -> Result<(), String> {
+    if let Some(tag) = line.strip_prefix('[') {
+        let (name, value) = tag.strip_suffix(']')
+            .and_then(|tag| tag.split_once(' '))
+            .ok_or_else(|| format!("Bad tag {}", line))?;
+        let value = value.trim().trim_matches('"');
+        match name {
+            "Board" => record.board = value.to_string(),
+            "Seating" => {
+                record.seating = value.split_whitespace()
+                    .map(|player| player.parse::<usize>().map_err(|_| format!("Bad seating {}", value)))
+                    .collect::<Result<_, _>>()?;
+                record.hands = vec![Resources::ZERO; record.seating.len()];
+            }
+            "Result" => record.winner = if value == "*" { None } else { Some(parse_player(value)?) },
+            seat if seat.starts_with("Seat") => {
+                let seat: usize = seat[4..].parse().map_err(|_| format!("Bad tag {}", name))?;
+                if record.names.len() <= seat {
+                    record.names.resize(seat + 1, String::new());
+                }
+                record.names[seat] = value.to_string();
+            }
+            // Other tags, like the engine, are informative
+            _ => (),
+        }
+        return Ok(());
+    }
+    let args: Vec<&str> = line.split_whitespace().collect();
+    match args[0] {
+        "rules" => {
+            let json = line["rules".len()..].trim();
+            record.rules = serde_json::from_str(json).map_err(|error| format!("Bad rules: {}", error))?;
+        }
+        "hex" => {
+            let coord = parse_coord(arg(&args, 1)?)?;
+            let hex = match arg(&args, 2)? {
+                "desert" => LandHex::Desert,
+                res => LandHex::Prod(parse_resource(res)?, arg(&args, 3)?.parse::<u8>().map_err(|_| format!("Bad number {}", line))?),
+            };
+            record.hexes.push((coord, hex));
+        }
+        "harbor" => {
+            let coord = parse_coord(arg(&args, 1)?)?;
+            let harbor = match arg(&args, 2)? {
+                "generic" => Harbor::Generic,
+                res => Harbor::Special(parse_resource(res)?),
+            };
+            record.harbors.push((coord, harbor));
+        }
+        "thief" => record.thief = parse_coord(arg(&args, 1)?)?,
+        "bank" => record.bank = parse_resources(&args[1..])?,
+        "deck" => {
+            for (name, count) in parse_counts(&args[1..])? {
+                record.development_cards[parse_card(name)?] = count as u8;
+            }
+        }
+        "hand" => {
+            let player = parse_player(arg(&args, 1)?)?;
+            let hand = record.hands.get_mut(player.to_usize()).ok_or_else(|| format!("Hand of {:?} out of the seating", player))?;
+            *hand = parse_resources(&args[2..])?;
+        }
+        "estimate" => {
+            let turn: usize = arg(&args, 1)?.parse().map_err(|_| format!("Bad turn {}", args[1]))?;
+            if turn != record.win_probabilities.len() + 1 {
+                return Err(format!("Estimate of turn {} after {} turns", turn, record.win_probabilities.len()));
+            }
+            let estimates = args[2..].iter()
+                .map(|estimate| estimate.parse::<f32>().map_err(|_| format!("Bad estimate {}", estimate)))
+                .collect::<Result<_, _>>()?;
+            record.win_probabilities.push(estimates);
+        }
+        "offer" => {
+            let number: usize = arg(&args, 1)?.parse().map_err(|_| format!("Bad move number {}", args[1]))?;
+            if number != record.moves.len() || number == 0 {
+                return Err(format!("Offer after move {} following move {}", number, record.moves.len()));
+            }
+            record.negotiations.push((number, read_negotiation(&args[2..])?));
+        }
+        number if number.ends_with('.') => {
+            let (action, outcome) = match args.iter().position(|arg| *arg == "=>") {
+                Some(i) => (&args[2..i], Some(&args[i + 1..])),
+                None => (&args[2..], None),
+            };
+            record.moves.push(Move {
+                player: parse_player(arg(&args, 1)?)?,
+                action: read_action(action)?,
+                outcome: outcome.map(parse_outcome).transpose()?,
+            });
+        }
+        other => return Err(format!("Unknown line {}", other)),
+    }
+    Ok(())
+}
+
+fn parse_outcome(args: &[&str]) -> Result<Outcome, String> {
+    Ok(match arg(args, 0)? {
+        "rolled" => Outcome::Rolled(arg(args, 1)?.parse::<u8>().map_err(|_| format!("Bad roll {}", args.join(" ")))?),
+        "drew" => Outcome::Drew(parse_card(arg(args, 1)?)?),
+        "stole" => Outcome::Stole(parse_resource(arg(args, 1)?)?),
+        "kept" => Outcome::Kept(parse_resources(&args[1..])?),
+        other => return Err(format!("Unknown outcome {}", other)),
+    })
+}
+
+/// Name of the board of a layout, when it has one
+pub(crate) fn board_name(layout: &Layout) -> Option<&'static str> {
+    if *layout == *STANDARD_BOARD.layout {
+        Some("standard")
+    } else if *layout == *EXTENSION_BOARD.layout {
+        Some("extension")
+    } else {
+        None
+    }
+}
+
+/// Hand of the player about to play, read to find the resources kept by a discard
+fn before(state: &State, phase: &Phase) -> Resources {
+    match phase {
+        Phase::FinishedGame { winner: _ } => Resources::ZERO,
+        _ => state.get_player_hand(phase.player()).resources,
+    }
+}
+
+/// Random outcome of a ply just applied, `hand` being the one of its player before it and `transfers` the length of the ledger
+fn outcome(ply: &Ply, hand: Resources, transfers: usize, state: &State) -> Option<Outcome> {
+    let new_transfers = &state.get_ledger().transfers()[transfers..];
+    match ply.action {
+        Action::RollDice => ply.roll.map(Outcome::Rolled),
+        Action::BuyDevelopment => ply.drawn.map(Outcome::Drew),
+        Action::MoveThief { hex: _, victim: _ } => new_transfers.iter()
+            .find(|transfer| transfer.kind == TransferKind::Steal)
+            .and_then(|transfer| Resource::ALL.iter().copied().find(|res| transfer.resources[*res] > 0))
+            .map(Outcome::Stole),
+        Action::Keep { resources } => {
+            // The engine only picks the resources kept when the player kept less than allowed
+            if (hand - resources).total() <= hand.total() / 2 {
+                return None;
+            }
+            let discarded = state.peek_discards().iter()
+                .find(|(player, _)| *player == ply.player)
+                .and_then(|(_, discarded)| *discarded)
+                .or_else(|| new_transfers.iter()
+                    .find(|transfer| transfer.kind == TransferKind::Discard && transfer.from == ply.player)
+                    .map(|transfer| transfer.resources))
+                .unwrap_or(Resources::ZERO);
+            Some(Outcome::Kept(hand - discarded))
+        }
+        _ => None,
+    }
+}
+
+/// Index of the first card of a kind among the cards counted in order
+fn first_index(counts: &[u64], kind: usize) -> u64 {
+    counts[..kind].iter().sum()
+}
+
+/// Indices the engine picks while applying a move to give its outcome, with the length of the range of each
+fn picks(played: &Move, phase: &Phase, state: &State) -> Result<Vec<(u64, u64)>, String> {
+    let missing = || format!("{:?} misses its outcome", played.action);
+    let resource_counts = |resources: Resources| -> Vec<u64> { Resource::ALL.iter().map(|res| resources[*res].max(0) as u64).collect() };
+    match (played.action, played.outcome) {
+        (Action::RollDice, Some(Outcome::Rolled(roll))) if (2..=12).contains(&roll) => {
+            let first = roll.saturating_sub(6).max(1);
+            Ok(vec![(first as u64 - 1, 6), ((roll - first) as u64 - 1, 6)])
+        }
+        (Action::RollDice, _) => Err(missing()),
+        (Action::BuyDevelopment, Some(Outcome::Drew(card))) => {
+            let deck = state.get_development_cards();
+            if deck[card] == 0 {
+                return Err(format!("No {:?} left to draw", card));
+            }
+            let counts: Vec<u64> = DevelopmentCard::ALL.iter().map(|card| deck[*card] as u64).collect();
+            Ok(vec![(first_index(&counts, card as usize), deck.total() as u64)])
+        }
+        (Action::BuyDevelopment, _) => Err(missing()),
+        (Action::MoveThief { hex: _, victim }, outcome) => {
+            let resources = if victim != played.player && victim != PlayerId::NONE { state.get_player_hand(victim).resources } else { Resources::ZERO };
+            match outcome {
+                _ if resources.total() == 0 => Ok(Vec::new()),
+                Some(Outcome::Stole(res)) if resources[res] > 0 => {
+                    Ok(vec![(first_index(&resource_counts(resources), res.to_usize()), resources.total() as u64)])
+                }
+                Some(Outcome::Stole(res)) => Err(format!("{:?} holds no {:?} to steal", victim, res)),
+                _ => Err(missing()),
+            }
+        }
+        (Action::Keep { resources: kept }, outcome) => {
+            let hand = state.get_player_hand(phase.player()).resources;
+            let (should_discard, mut discarded) = (hand.total() / 2, hand - kept);
+            if discarded.total() <= should_discard {
+                return Ok(Vec::new());
+            }
+            let target = match outcome {
+                Some(Outcome::Kept(target)) if target >= kept && hand >= target && (hand - target).total() == should_discard => hand - target,
+                Some(Outcome::Kept(target)) => return Err(format!("Keeping {:?} out of {:?} after keeping {:?}", target, hand, kept)),
+                _ => return Err(missing()),
+            };
+            // Each draw picks one of the discarded cards to keep instead
+            let mut picks = Vec::new();
+            while discarded.total() > should_discard {
+                let res = *Resource::ALL.iter().find(|res| discarded[**res] > target[**res]).unwrap();
+                picks.push((first_index(&resource_counts(discarded), res.to_usize()), discarded.total() as u64));
+                discarded[res] -= 1;
+            }
+            Ok(picks)
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Text of an action, see the [module](self)
+pub fn write_action(action: Action) -> String {
+    match action {
+        Action::EndTurn => "EndTurn".to_string(),
+        Action::RollDice => "RollDice".to_string(),
+        Action::MoveThief { hex, victim } => format!("MoveThief {} {}", write_coord(hex), write_player(victim)),
+        Action::BuildRoad { path } => format!("BuildRoad {}", write_coord(path)),
+        Action::BuildSettlement { intersection } => format!("BuildSettlement {}", write_coord(intersection)),
+        Action::BuildCity { intersection } => format!("BuildCity {}", write_coord(intersection)),
+        Action::ReserveCity { intersection } => format!("ReserveCity {}", write_coord(intersection)),
+        Action::TradeBank { given, asked } => format!("TradeBank {} {}", resource_name(given), resource_name(asked)),
+        Action::TradeBankBundle { trades, asked } => format!("TradeBankBundle {}", write_exchange(trades, asked)),
+        Action::ProposeTrade { give, receive } => format!("ProposeTrade {}", write_exchange(give, receive)),
+        Action::AcceptTrade => "AcceptTrade".to_string(),
+        Action::RejectTrade => "RejectTrade".to_string(),
+        Action::CounterTrade { give, receive } => format!("CounterTrade {}", write_exchange(give, receive)),
+        Action::BuyDevelopment => "BuyDevelopment".to_string(),
+        Action::DevelopmentKnight => "DevelopmentKnight".to_string(),
+        Action::DevelopmentRoadBuilding => "DevelopmentRoadBuilding".to_string(),
+        Action::DevelopmentYearOfPlenty => "DevelopmentYearOfPlenty".to_string(),
+        Action::ChooseFreeResource { resource } => format!("ChooseFreeResource {}", resource_name(resource)),
+        Action::DevelopmentMonopole { resource } => format!("DevelopmentMonopole {}", resource_name(resource)),
+        Action::Keep { resources } => format!("Keep {}", write_resources(resources)).trim_end().to_string(),
+        Action::Discard { resource } => format!("Discard {}", resource_name(resource)),
+        Action::Resign => "Resign".to_string(),
+        Action::Exit => "Exit".to_string(),
+        Action::WithdrawTrade => "WithdrawTrade".to_string(),
+        Action::ReviseTrade { give, receive } => format!("ReviseTrade {}", write_exchange(give, receive)),
+    }
+}
+
+/// Action of a text, see the [module](self)
+pub fn parse_action(text: &str) -> Result<Action, String> {
+    read_action(&text.split_whitespace().collect::<Vec<_>>())
+}
+
+fn write_coord(coord: Coord) -> String {
+    format!("{},{}", coord.x, coord.y)
+}
+
+fn write_player(player: PlayerId) -> String {
+    match player.option() {
+        Some(player) => player.to_u8().to_string(),
+        None => "none".to_string(),
+    }
+}
+
+fn resource_name(res: Resource) -> &'static str {
+    match res {
+        Resource::Brick => "brick",
+        Resource::Lumber => "lumber",
+        Resource::Ore => "ore",
+        Resource::Grain => "grain",
+        Resource::Wool => "wool",
+    }
+}
+
+fn card_name(card: DevelopmentCard) -> &'static str {
+    match card {
+        DevelopmentCard::Knight => "knight",
+        DevelopmentCard::RoadBuilding => "road_building",
+        DevelopmentCard::YearOfPlenty => "year_of_plenty",
+        DevelopmentCard::Monopole => "monopole",
+        DevelopmentCard::VictoryPoint => "victory_point",
+    }
+}
+
+/// Counts of the resources held, like `brick=2 ore=1`
+pub(crate) fn write_resources(resources: Resources) -> String {
+    Resource::ALL.iter()
+        .filter(|res| resources[**res] != 0)
+        .map(|res| format!("{}={}", resource_name(*res), resources[*res]))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn write_exchange(give: Resources, receive: Resources) -> String {
+    format!("{} for {}", write_resources(give), write_resources(receive)).trim().to_string()
+}
+
+pub(crate) fn parse_coord(raw: &str) -> Result<Coord, String> {
+    let mut split = raw.split(',');
+    let mut next = || split.next().and_then(|v| v.parse::<i8>().ok()).ok_or_else(|| format!("Bad coord {}", raw));
+    Ok(Coord::new(next()?, next()?))
+}
+
+pub(crate) fn parse_player(raw: &str) -> Result<PlayerId, String> {
+    match raw {
+        "none" => Ok(PlayerId::NONE),
+        _ => raw.parse::<u8>().map(PlayerId::from).map_err(|_| format!("Bad player {}", raw)),
+    }
+}
+
+pub(crate) fn parse_number(raw: &str) -> Result<i8, String> {
+    raw.parse::<i8>().map_err(|_| format!("Bad number {}", raw))
+}
+
+pub(crate) fn parse_resource(raw: &str) -> Result<Resource, String> {
+    Resource::ALL.iter().copied().find(|res| resource_name(*res) == raw).ok_or_else(|| format!("Bad resource {}", raw))
+}
+
+pub(crate) fn parse_card(raw: &str) -> Result<DevelopmentCard, String> {
+    DevelopmentCard::ALL.iter().copied().find(|card| card_name(*card) == raw).ok_or_else(|| format!("Bad development card {}", raw))
+}
+
+/// Parses `<name>=<count>` pairs
+pub(crate) fn parse_counts<'a>(raw: &[&'a str]) -> Result<Vec<(&'a str, i8)>, String> {
+    raw.iter().map(|pair| {
+        let mut split = pair.split('=');
+        match (split.next(), split.next()) {
+            (Some(name), Some(count)) => Ok((name, parse_number(count)?)),
+            _ => Err(format!("Bad count {}", pair)),
+        }
+    }).collect()
+}
+
+pub(crate) fn parse_resources(raw: &[&str]) -> Result<Resources, String> {
+    let mut resources = Resources::ZERO;
+    for (name, count) in parse_counts(raw)? {
+        resources[parse_resource(name)?] = count;
+    }
+    Ok(resources)
+}
+
+pub(crate) fn arg<'a>(args: &[&'a str], i: usize) -> Result<&'a str, String> {
+    args.get(i).copied().ok_or_else(|| "Missing parameter".to_string())
+}
+
+/// Resources on each side of `for`, like `brick=1 wool=1 for ore=2`
+fn parse_exchange(args: &[&str]) -> Result<(Resources, Resources), String> {
+    let split = args.iter().position(|arg| *arg == "for").ok_or_else(|| "Missing for".to_string())?;
+    Ok((parse_resources(&args[1..split])?, parse_resources(&args[split+1..])?))
+}
+
+pub(crate) fn answer_name(answer: TradeAnswer) -> &'static str {
+    match answer {
+        TradeAnswer::Accepted => "accepted",
+        TradeAnswer::Rejected => "rejected",
+        TradeAnswer::Countered => "countered",
+    }
+}
+
+/// Negotiation of an `offer` line, after the number of the move, see the [module](self)
+fn write_negotiation(negotiation: &Negotiation) -> String {
+    let responses: Vec<String> = negotiation.responses.iter()
+        .map(|(responder, answer)| format!("{}:{}", write_player(*responder), answer_name(*answer)))
+        .collect();
+    let offer = &negotiation.offer;
+    format!("{} {} {}{} {}", write_player(negotiation.proposer), write_player(offer.partner),
+        if responses.is_empty() { "-".to_string() } else { responses.join(",") },
+        if offer.countered { " counter" } else { "" }, write_exchange(offer.give, offer.receive))
+}
+
+fn read_negotiation(args: &[&str]) -> Result<Negotiation, String> {
+    let responses = match arg(args, 2)? {
+        "-" => Vec::new(),
+        raw => raw.split(',').map(|response| {
+            let (responder, name) = response.split_once(':').ok_or_else(|| format!("Bad answer {}", response))?;
+            let answer = [TradeAnswer::Accepted, TradeAnswer::Rejected, TradeAnswer::Countered].iter().copied()
+                .find(|answer| answer_name(*answer) == name)
+                .ok_or_else(|| format!("Bad answer {}", response))?;
+            Ok((parse_player(responder)?, answer))
+        }).collect::<Result<_, String>>()?,
+    };
+    // The terms follow the answers, or `counter`
+    let countered = args.get(3) == Some(&"counter");
+    let (give, receive) = parse_exchange(&args[if countered { 3 } else { 2 }..])?;
+    Ok(Negotiation {
+        proposer: parse_player(arg(args, 0)?)?,
+        offer: TradeOffer { partner: parse_player(arg(args, 1)?)?, give, receive, countered },
+        responses,
+    })
+}
+
+/// Action of its words, the variant name followed by its parameters
+pub(crate) fn read_action(args: &[&str]) -> Result<Action, String> {
+    Ok(match arg(args, 0)? {
+        "EndTurn" => Action::EndTurn,
+        "RollDice" => Action::RollDice,
+        "MoveThief" => Action::MoveThief { hex: parse_coord(arg(args, 1)?)?, victim: parse_player(arg(args, 2)?)? },
+        "BuildRoad" => Action::BuildRoad { path: parse_coord(arg(args, 1)?)? },
+        "BuildSettlement" => Action::BuildSettlement { intersection: parse_coord(arg(args, 1)?)? },
+        "BuildCity" => Action::BuildCity { intersection: parse_coord(arg(args, 1)?)? },
+        "ReserveCity" => Action::ReserveCity { intersection: parse_coord(arg(args, 1)?)? },
+        "TradeBank" => Action::TradeBank { given: parse_resource(arg(args, 1)?)?, asked: parse_resource(arg(args, 2)?)? },
+        "TradeBankBundle" => {
+            let (trades, asked) = parse_exchange(args)?;
+            Action::TradeBankBundle { trades, asked }
+        }
+        "ProposeTrade" => {
+            let (give, receive) = parse_exchange(args)?;
+            Action::ProposeTrade { give, receive }
+        }
+        "AcceptTrade" => Action::AcceptTrade,
+        "RejectTrade" => Action::RejectTrade,
+        "CounterTrade" => {
+            let (give, receive) = parse_exchange(args)?;
+            Action::CounterTrade { give, receive }
+        }
+        "WithdrawTrade" => Action::WithdrawTrade,
+        "ReviseTrade" => {
+            let (give, receive) = parse_exchange(args)?;
+            Action::ReviseTrade { give, receive }
+        }
+        "BuyDevelopment" => Action::BuyDevelopment,
+        "DevelopmentKnight" => Action::DevelopmentKnight,
+        "DevelopmentRoadBuilding" => Action::DevelopmentRoadBuilding,
+        "DevelopmentYearOfPlenty" => Action::DevelopmentYearOfPlenty,
+        "ChooseFreeResource" => Action::ChooseFreeResource { resource: parse_resource(arg(args, 1)?)? },
+        "DevelopmentMonopole" => Action::DevelopmentMonopole { resource: parse_resource(arg(args, 1)?)? },
+        "Keep" => Action::Keep { resources: parse_resources(&args[1..])? },
+        "Discard" => Action::Discard { resource: parse_resource(arg(args, 1)?)? },
+        "Resign" => Action::Resign,
+        "Exit" => Action::Exit,
+        other => return Err(format!("Unknown action {}", other)),
+    })
+}
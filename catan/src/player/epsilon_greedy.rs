@@ -0,0 +1,69 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
+use crate::game::{Phase, Action, Error, Notification, legal};
+use crate::state::{State, PlayerId};
+use super::{CatanPlayer, generate_possible_actions};
+
+/// Player picking a random legal action with probability `eps`, and the action of the inner player otherwise
+///
+/// The inner player is still told about every game and notification, so that it keeps track of the game.
+pub struct EpsilonGreedy<P: CatanPlayer> {
+    inner: P,
+    eps: f64,
+    rng: SmallRng,
+    possible_actions: Vec<Action>,
+}
+
+impl<P: CatanPlayer> EpsilonGreedy<P> {
+    pub fn new(inner: P, eps: f64) -> EpsilonGreedy<P> {
+        EpsilonGreedy::with_rng(inner, eps, SmallRng::from_rng(&mut rand::rng()))
+    }
+
+    /// A wrapper always making the same random choices in the same situations
+    pub fn new_seeded(inner: P, eps: f64, seed: u64) -> EpsilonGreedy<P> {
+        EpsilonGreedy::with_rng(inner, eps, SmallRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(inner: P, eps: f64, rng: SmallRng) -> EpsilonGreedy<P> {
+        EpsilonGreedy {
+            inner,
+            eps,
+            rng,
+            possible_actions: Vec::new(),
+        }
+    }
+}
+
+impl<P: CatanPlayer> CatanPlayer for EpsilonGreedy<P> {
+    fn new_game(&mut self, position: PlayerId, state: &State) {
+        generate_possible_actions(&mut self.possible_actions, position, state);
+        self.inner.new_game(position, state)
+    }
+
+    fn pick_action(&mut self, phase: &Phase, state: &State) -> Action {
+        if self.eps > 0.0 && self.rng.random_bool(self.eps.min(1.0)) {
+            let legal_actions: Vec<Action> = self.possible_actions.iter()
+                .filter(|action| legal::legal(phase, state, **action).is_ok())
+                .copied()
+                .collect();
+            // Discards of more than four cards are left to the inner player
+            if !legal_actions.is_empty() {
+                return legal_actions[self.rng.random_range(0..legal_actions.len())];
+            }
+        }
+        self.inner.pick_action(phase, state)
+    }
+
+    fn bad_action(&mut self, error: Error) {
+        self.inner.bad_action(error)
+    }
+
+    fn notify(&mut self, notification: &Notification) {
+        self.inner.notify(notification)
+    }
+
+    fn results(&mut self, state: &State, winner: PlayerId) {
+        self.inner.results(state, winner)
+    }
+}
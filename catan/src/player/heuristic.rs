@@ -0,0 +1,287 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
+use crate::board::Coord;
+use crate::board::utils::topology::Topology;
+use crate::game::{Phase, TurnPhase, Action, Error, Notification};
+use crate::state::{State, PlayerId};
+use crate::utils::{Hex, LandHex, Harbor, Resource, Resources};
+use super::{CatanPlayer, ActionPickerPlayer, PickerPlayerTrait, sample_softmax};
+
+/// Player scoring its legal actions with usual Catan heuristics, and picking the best one
+///
+/// Settlements go to the intersections with the most pips, rare resources weighing more,
+/// the thief targets the leader, and development cards are played when they help at once.
+/// It only reads what the player may see, and is fast enough to serve as an evaluation baseline.
+pub struct Heuristic {
+    rng: SmallRng,
+    /// Actions are sampled with the softmax of their scores divided by the temperature, the best one being played at 0
+    temperature: f32,
+    position: PlayerId,
+    /// Weight of each resource, higher for the ones the board produces less
+    scarcity: [f32; Resource::COUNT],
+}
+
+/// Costs the player saves for, with the value of completing them
+const GOALS: [(Resources, f32); 4] = [
+    (Resources::CITY, 4.0),
+    (Resources::SETTLEMENT, 3.0),
+    (Resources::DVP_CARD, 1.5),
+    (Resources::ROAD, 1.0),
+];
+
+impl PickerPlayerTrait for Heuristic {
+    type ACTIONS = Vec<Action>;
+    type PICKED = Action;
+
+    fn new_game(&mut self, position: PlayerId, state: &State, _: &Vec<Action>) {
+        self.position = position;
+        self.weigh_scarcity(state);
+    }
+
+    fn pick_action(&mut self, phase: &Phase, state: &State, legal_actions: &Vec<Action>) -> Action {
+        if self.temperature > 0.0 {
+            let scores: Vec<f32> = legal_actions.iter().map(|action| self.score(phase, state, action)).collect();
+            return legal_actions[sample_softmax(&mut self.rng, &scores, self.temperature)];
+        }
+        let mut best = (f32::NEG_INFINITY, legal_actions[0]);
+        for action in legal_actions.iter() {
+            // Ties are broken randomly
+            let score = self.score(phase, state, action) + self.rng.random::<f32>() * 0.01;
+            if score > best.0 {
+                best = (score, *action);
+            }
+        }
+        best.1
+    }
+
+    fn bad_action(&mut self, error: Error) {
+        println!("{:?}", error);
+    }
+
+    fn notify(&mut self, _: &Notification) {}
+
+    fn results(&mut self, _: &State, _: PlayerId) {}
+}
+
+impl Heuristic {
+    fn new(rng: SmallRng, temperature: f32) -> Heuristic {
+        Heuristic {
+            rng,
+            temperature,
+            position: PlayerId::NONE,
+            scarcity: [1.0; Resource::COUNT],
+        }
+    }
+
+    pub fn new_player() -> impl CatanPlayer {
+        ActionPickerPlayer::new(Heuristic::new(SmallRng::from_rng(&mut rand::rng()), 0.0))
+    }
+
+    /// A heuristic player always making the same choices in the same situations
+    pub fn new_seeded_player(seed: u64) -> impl CatanPlayer {
+        ActionPickerPlayer::new(Heuristic::new(SmallRng::seed_from_u64(seed), 0.0))
+    }
+
+    /// A seeded heuristic player sampling its actions, the lower the temperature the more often the best one
+    pub fn new_tempered_player(temperature: f32, seed: u64) -> impl CatanPlayer {
+        ActionPickerPlayer::new(Heuristic::new(SmallRng::seed_from_u64(seed), temperature))
+    }
+
+    /// Scores of the actions of the player at the position as a heuristic player rates them, higher being better, to guide other players
+    pub fn action_scores(position: PlayerId, phase: &Phase, state: &State, actions: &[Action]) -> Vec<f32> {
+        let mut heuristic = Heuristic::new(SmallRng::seed_from_u64(0), 0.0);
+        heuristic.position = position;
+        heuristic.weigh_scarcity(state);
+        actions.iter().map(|action| heuristic.score(phase, state, action)).collect()
+    }
+
+    fn weigh_scarcity(&mut self, state: &State) {
+        let mut pips = [0.0; Resource::COUNT];
+        for hex in state.get_layout().hexes.iter() {
+            if let Ok(hex @ Hex::Land(LandHex::Prod(res, _))) = state.get_static_hex(*hex) {
+                pips[res.to_usize()] += hex.get_pips() as f32;
+            }
+        }
+        let average = pips.iter().sum::<f32>() / Resource::COUNT as f32;
+        for (weight, pips) in self.scarcity.iter_mut().zip(pips.iter()) {
+            *weight = (average / pips.max(1.0)).clamp(0.5, 2.0);
+        }
+    }
+
+    fn score(&self, phase: &Phase, state: &State, action: &Action) -> f32 {
+        let hand = state.get_player_hand(self.position);
+        match *action {
+            Action::BuildSettlement { intersection } => 90.0 + self.spot_value(state, intersection),
+            Action::BuildCity { intersection } => 100.0 + self.spot_value(state, intersection),
+            Action::ReserveCity { intersection } => 20.0 + self.spot_value(state, intersection),
+            Action::BuildRoad { path } => {
+                let value = self.road_value(state, path);
+                match phase {
+                    Phase::InitialPlacement { player: _, placing_second: _, placing_road: _ } => value,
+                    _ if value > 0.0 && hand.settlement_pieces > 0 => 40.0 + value,
+                    _ => 5.0,
+                }
+            }
+            Action::BuyDevelopment => 30.0,
+            Action::DevelopmentKnight => {
+                if self.thief_blocks(state) {
+                    70.0
+                } else if hand.knights + 1 > state.get_largest_army().map_or(2, |(_, knights)| knights) {
+                    50.0
+                } else {
+                    -1.0
+                }
+            }
+            Action::DevelopmentRoadBuilding => if hand.road_pieces >= 2 { 60.0 } else { -1.0 },
+            Action::DevelopmentYearOfPlenty => 60.0,
+            Action::DevelopmentMonopole { resource } => 55.0 + self.need(hand.resources, resource) + self.scarcity[resource.to_usize()],
+            Action::ChooseFreeResource { resource } => self.need(hand.resources, resource) + self.scarcity[resource.to_usize()] * 0.1,
+            Action::TradeBank { given, asked } => {
+                let given = Resources::new_one(given, hand.harbor.rate(given) as i8);
+                self.trade_value(hand.resources, given, Resources::new_one(asked, 1))
+            }
+            Action::TradeBankBundle { trades, asked } => self.trade_value(hand.resources, hand.harbor.bundle_cost(trades), asked),
+            Action::AcceptTrade => {
+                let (given, received) = match phase {
+                    Phase::Turn { player, turn_phase: TurnPhase::Trade(offer), development_phase: _ } if *player == self.position => (offer.give, offer.receive),
+                    Phase::Turn { player: _, turn_phase: TurnPhase::Trade(offer), development_phase: _ } => (offer.receive, offer.give),
+                    _ => (Resources::ZERO, Resources::ZERO),
+                };
+                self.progress(hand.resources - given + received) - self.progress(hand.resources)
+            }
+            Action::MoveThief { hex, victim } => self.thief_value(state, hex, victim),
+            Action::Keep { resources } => self.progress(resources) + self.weighted(resources) * 0.1,
+            Action::RollDice => 10.0,
+            Action::EndTurn | Action::RejectTrade | Action::WithdrawTrade => 0.0,
+            // Keeping a whole hand at once is preferred to discarding card by card
+            Action::ProposeTrade { give: _, receive: _ } | Action::CounterTrade { give: _, receive: _ } | Action::ReviseTrade { give: _, receive: _ } | Action::Discard { resource: _ } | Action::Resign | Action::Exit => -10.0,
+        }
+    }
+
+    /// Pips of the hexes around an intersection weighted by scarcity, with bonuses for new resources and useful harbors
+    fn spot_value(&self, state: &State, intersection: Coord) -> f32 {
+        let produced = self.produced(state);
+        let mut value = 0.0;
+        for hex in state.intersection_hex_neighbours(intersection).unwrap_or_default() {
+            if let Ok(hex_value @ Hex::Land(LandHex::Prod(res, _))) = state.get_static_hex(hex) {
+                let pips = hex_value.get_pips() as f32 * if hex == state.get_thief_hex() { 0.5 } else { 1.0 };
+                value += pips * self.scarcity[res.to_usize()];
+                if !produced[res.to_usize()] {
+                    value += 1.0;
+                }
+            }
+        }
+        match state.get_static_harbor(intersection) {
+            Ok(Harbor::Generic) => value + 1.0,
+            Ok(Harbor::Special(res)) if produced[res.to_usize()] => value + 1.5,
+            _ => value,
+        }
+    }
+
+    /// Value of the best spot a road leads to, 0 when it leads nowhere to build
+    fn road_value(&self, state: &State, path: Coord) -> f32 {
+        let mut value: f32 = 0.0;
+        for end in state.path_intersection_neighbours(path).unwrap_or_default() {
+            if buildable(state, end) {
+                value = value.max(self.spot_value(state, end));
+            }
+            for next in state.intersection_intersection_neighbours(end).unwrap_or_default() {
+                if buildable(state, next) {
+                    value = value.max(self.spot_value(state, next) * 0.7);
+                }
+            }
+        }
+        value
+    }
+
+    /// Resources produced by the player's buildings
+    fn produced(&self, state: &State) -> [bool; Resource::COUNT] {
+        let mut produced = [false; Resource::COUNT];
+        for intersection in state.get_layout().intersections.iter() {
+            if let Ok(Some((player, _))) = state.get_dynamic_intersection(*intersection) {
+                if player != self.position {
+                    continue;
+                }
+                for hex in state.intersection_hex_neighbours(*intersection).unwrap_or_default() {
+                    if let Ok(Hex::Land(LandHex::Prod(res, _))) = state.get_static_hex(hex) {
+                        produced[res.to_usize()] = true;
+                    }
+                }
+            }
+        }
+        produced
+    }
+
+    fn thief_blocks(&self, state: &State) -> bool {
+        state.hex_intersection_neighbours(state.get_thief_hex()).unwrap_or_default().iter()
+            .any(|intersection| matches!(state.get_dynamic_intersection(*intersection), Ok(Some((player, _))) if player == self.position))
+    }
+
+    /// Opponent with the most public victory points
+    fn leader(&self, state: &State) -> PlayerId {
+        (0..state.player_count()).map(PlayerId::from)
+            .filter(|player| *player != self.position)
+            .max_by_key(|player| state.get_player_public_vp(*player))
+            .unwrap_or(PlayerId::NONE)
+    }
+
+    /// Production taken from the opponents, the leader's counting double, and cards the victim may give
+    fn thief_value(&self, state: &State, hex: Coord, victim: PlayerId) -> f32 {
+        let leader = self.leader(state);
+        let pips = state.get_static_hex(hex).map_or(0, |hex| hex.get_pips()) as f32;
+        let mut value = 0.0;
+        let mut victims = Vec::new();
+        for intersection in state.hex_intersection_neighbours(hex).unwrap_or_default() {
+            if let Ok(Some((player, is_city))) = state.get_dynamic_intersection(intersection) {
+                if player == self.position {
+                    return -100.0;
+                }
+                let buildings = if is_city { 2.0 } else { 1.0 };
+                value += pips * buildings * if player == leader { 2.0 } else { 1.0 };
+                victims.push(player);
+            }
+        }
+        if victim != self.position && victims.contains(&victim) {
+            let cards = state.get_player_hand(victim).resources.total() as f32;
+            value += cards.min(7.0) * 0.5 + if victim == leader { 5.0 } else { 0.0 };
+        }
+        value
+    }
+
+    /// How close a hand gets to the goals
+    fn progress(&self, resources: Resources) -> f32 {
+        GOALS.iter().map(|(cost, value)| {
+            let covered: i8 = Resource::ALL.iter().map(|res| resources[*res].clamp(0, cost[*res])).sum();
+            value * covered as f32 / cost.total() as f32
+        }).sum()
+    }
+
+    /// Progress one more resource brings
+    fn need(&self, resources: Resources, resource: Resource) -> f32 {
+        self.progress(resources + Resources::new_one(resource, 1)) - self.progress(resources)
+    }
+
+    fn weighted(&self, resources: Resources) -> f32 {
+        Resource::ALL.iter().map(|res| resources[*res] as f32 * self.scarcity[res.to_usize()]).sum()
+    }
+
+    /// Worth trading when it completes a settlement or a city
+    fn trade_value(&self, resources: Resources, given: Resources, asked: Resources) -> f32 {
+        let after = resources - given + asked;
+        let completes = |resources: Resources| [Resources::CITY, Resources::SETTLEMENT].iter()
+            .any(|cost| Resource::ALL.iter().all(|res| resources[*res] >= cost[*res]));
+        if completes(after) && !completes(resources) {
+            20.0
+        } else {
+            -1.0
+        }
+    }
+}
+
+/// Free intersection whose neighbours are free too
+fn buildable(state: &State, intersection: Coord) -> bool {
+    let free = |intersection: Coord| matches!(state.get_dynamic_intersection(intersection), Ok(None));
+    free(intersection) && state.intersection_intersection_neighbours(intersection).unwrap_or_default().iter()
+        .all(|neighbour| !matches!(state.get_dynamic_intersection(*neighbour), Ok(Some(_))))
+}
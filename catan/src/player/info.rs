@@ -0,0 +1,36 @@
+use std::fmt;
+
+/// Name and free-form metadata of a player, such as the checkpoint it was loaded from
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PlayerInfo {
+    pub name: String,
+    pub metadata: Vec<(String, String)>,
+}
+
+impl PlayerInfo {
+    pub fn new(name: &str) -> PlayerInfo {
+        PlayerInfo {
+            name: name.to_string(),
+            metadata: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, key: &str, value: &str) -> PlayerInfo {
+        self.metadata.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.metadata.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+impl fmt::Display for PlayerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for (key, value) in self.metadata.iter() {
+            write!(f, " {}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+use std::time::{Duration, Instant};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+use rand::seq::IndexedRandom;
+
+use serde::{Serialize, Deserialize};
+
+use crate::game::{Phase, Action, Error, Notification, legal, apply};
+use crate::state::{State, PlayerId, audit};
+use crate::notation::write_action;
+use super::{CatanPlayer, ActionPickerPlayer, PickerPlayerTrait, generate_possible_actions, sample_softmax};
+
+/// Search settings of [Mcts]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct MctsConfig {
+    /// Playouts run for each decision
+    pub playouts: usize,
+    /// Search time of each decision, the remaining playouts being skipped once it is spent
+    pub time_budget: Option<Duration>,
+    /// Random actions played at the end of each playout before the position is evaluated
+    pub playout_depth: usize,
+    /// Exploration constant of the UCB selection
+    pub exploration: f32,
+    /// Actions are sampled with probabilities proportional to their visits to the power of `1 / temperature`,
+    /// the most visited one being always played at 0
+    pub temperature: f32,
+}
+
+impl MctsConfig {
+    pub const DEFAULT: MctsConfig = MctsConfig {
+        playouts: 100,
+        time_budget: None,
+        playout_depth: 50,
+        exploration: 1.4,
+        temperature: 0.0,
+    };
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig::DEFAULT
+    }
+}
+
+/// Node of the search tree, reached by playing its action from its parent
+struct Node {
+    action: Action,
+    /// Player who played the action
+    player: PlayerId,
+    visits: u32,
+    /// Sum of the rewards of the player over the playouts through the node
+    reward: f32,
+    children: Vec<usize>,
+}
+
+impl Node {
+    fn new(action: Action, player: PlayerId) -> Node {
+        Node { action, player, visits: 0, reward: 0.0, children: Vec::new() }
+    }
+}
+
+/// Node of an exported [SearchTree]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SearchNode {
+    /// Action reaching the node from its parent, [None] for the root
+    pub action: Option<Action>,
+    /// Player who played the action
+    pub player: PlayerId,
+    pub visits: u32,
+    /// Mean reward of the player over the playouts through the node
+    pub value: f32,
+    /// Indices of the children in the tree
+    pub children: Vec<usize>,
+}
+
+/// Search tree grown by [Mcts] for a decision, the root first, exported to debug the search on a position
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SearchTree {
+    pub nodes: Vec<SearchNode>,
+}
+
+impl SearchTree {
+    fn new(nodes: &[Node]) -> SearchTree {
+        let nodes = nodes.iter().enumerate().map(|(i, node)| SearchNode {
+            action: if i == 0 { None } else { Some(node.action) },
+            player: node.player,
+            visits: node.visits,
+            value: if node.visits > 0 { node.reward / node.visits as f32 } else { 0.0 },
+            children: node.children.clone(),
+        }).collect();
+        SearchTree { nodes }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed serializing search tree")
+    }
+
+    /// Graphviz description of the tree, the nodes visited less than `min_visits` times being left out with their subtrees
+    pub fn to_dot(&self, min_visits: u32) -> String {
+        let mut dot = String::from("digraph search {\n    node [shape=box];\n");
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            let label = node.action.map_or("root".to_string(), |action| format!("{:?} {}", node.player, write_action(action)));
+            writeln!(dot, "    n{} [label=\"{}\\nN={} V={:.3}\"];", i, label, node.visits, node.value).unwrap();
+            for child in node.children.iter().copied().filter(|child| self.nodes[*child].visits >= min_visits) {
+                writeln!(dot, "    n{} -> n{};", i, child).unwrap();
+                stack.push(child);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Visits and values of the root of a decision searched by [Mcts], soft targets to distill the search into a policy
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SearchRecord {
+    /// Player who searched the decision
+    pub player: PlayerId,
+    pub turn: u32,
+    /// Legal actions of the decision, with the visits and mean reward of each, 0 for the ones never tried
+    pub actions: Vec<Action>,
+    pub visits: Vec<u32>,
+    pub values: Vec<f32>,
+    /// Action picked
+    pub played: Action,
+}
+
+/// Log of the decisions searched by the [Mcts] players given it, until they are taken, possibly from another thread
+#[derive(Clone, Default)]
+pub struct SearchLog {
+    records: Arc<Mutex<Vec<SearchRecord>>>,
+}
+
+impl SearchLog {
+    pub fn new() -> SearchLog {
+        SearchLog::default()
+    }
+
+    /// Decisions searched since the last time they were taken
+    pub fn take(&self) -> Vec<SearchRecord> {
+        std::mem::take(&mut *self.records.lock().unwrap())
+    }
+
+    fn push(&self, record: SearchRecord) {
+        self.records.lock().unwrap().push(record);
+    }
+}
+
+/// Player searching its actions with determinized Monte-Carlo tree search
+///
+/// Each playout samples the hidden information, opponents' hands and the development deck, anew.
+/// The tree is open loop: nodes are action sequences, and only the children legal in the playout's state are selected.
+/// Playouts end with random actions, and positions are evaluated by their victory points when the game isn't over.
+pub struct Mcts {
+    rng: SmallRng,
+    config: MctsConfig,
+    position: PlayerId,
+    possible_actions: Vec<Action>,
+    log: Option<SearchLog>,
+}
+
+impl PickerPlayerTrait for Mcts {
+    type ACTIONS = Vec<Action>;
+    type PICKED = Action;
+
+    fn new_game(&mut self, position: PlayerId, _: &State, possible_actions: &Vec<Action>) {
+        self.position = position;
+        self.possible_actions = possible_actions.clone();
+    }
+
+    fn pick_action(&mut self, phase: &Phase, state: &State, legal_actions: &Vec<Action>) -> Action {
+        if legal_actions.len() == 1 {
+            return legal_actions[0];
+        }
+        self.search(phase, state, legal_actions)
+    }
+
+    fn bad_action(&mut self, error: Error) {
+        println!("{:?}", error);
+    }
+
+    fn notify(&mut self, _: &Notification) {}
+
+    fn results(&mut self, _: &State, _: PlayerId) {}
+}
+
+impl Mcts {
+    fn new(rng: SmallRng, config: MctsConfig) -> Mcts {
+        Mcts {
+            rng,
+            config,
+            position: PlayerId::NONE,
+            possible_actions: Vec::new(),
+            log: None,
+        }
+    }
+
+    pub fn new_player(config: MctsConfig) -> impl CatanPlayer {
+        ActionPickerPlayer::new(Mcts::new(SmallRng::from_rng(&mut rand::rng()), config))
+    }
+
+    /// A player always making the same choices in the same situations, when its search isn't cut by a time budget
+    pub fn new_seeded_player(config: MctsConfig, seed: u64) -> impl CatanPlayer {
+        ActionPickerPlayer::new(Mcts::new(SmallRng::seed_from_u64(seed), config))
+    }
+
+    /// A seeded player recording the root of each decision it searches into the log
+    pub fn new_logged_player(config: MctsConfig, seed: u64, log: SearchLog) -> impl CatanPlayer {
+        let mut mcts = Mcts::new(SmallRng::seed_from_u64(seed), config);
+        mcts.log = Some(log);
+        ActionPickerPlayer::new(mcts)
+    }
+
+    /// Tree searched by a seeded player for the decision of the phase, see [SearchTree]
+    pub fn search_tree(config: MctsConfig, seed: u64, phase: &Phase, state: &State) -> SearchTree {
+        let mut mcts = Mcts::new(SmallRng::seed_from_u64(seed), config);
+        mcts.position = phase.player();
+        generate_possible_actions(&mut mcts.possible_actions, mcts.position, state);
+        let legal_actions = mcts.legal_actions(phase, state);
+        SearchTree::new(&mcts.grow(phase, state, &legal_actions))
+    }
+
+    fn legal_actions(&self, phase: &Phase, state: &State) -> Vec<Action> {
+        self.possible_actions.iter()
+            .filter(|action| legal::legal(phase, state, **action).is_ok())
+            .copied()
+            .collect()
+    }
+
+    fn search(&mut self, phase: &Phase, state: &State, legal_actions: &[Action]) -> Action {
+        let nodes = self.grow(phase, state, legal_actions);
+        let children = &nodes[0].children;
+        let played = if self.config.temperature > 0.0 && !children.is_empty() {
+            let scores: Vec<f32> = children.iter().map(|child| (nodes[*child].visits as f32).ln()).collect();
+            nodes[children[sample_softmax(&mut self.rng, &scores, self.config.temperature)]].action
+        } else {
+            children.iter()
+                .max_by_key(|child| nodes[**child].visits)
+                .map(|child| nodes[*child].action)
+                .unwrap_or(legal_actions[0])
+        };
+        if let Some(log) = &self.log {
+            let child = |action: &Action| children.iter().map(|child| &nodes[*child]).find(|child| child.action == *action);
+            log.push(SearchRecord {
+                player: self.position,
+                turn: state.get_turn(),
+                actions: legal_actions.to_vec(),
+                visits: legal_actions.iter().map(|action| child(action).map_or(0, |child| child.visits)).collect(),
+                values: legal_actions.iter().map(|action| child(action).map_or(0.0, |child| child.reward / child.visits as f32)).collect(),
+                played,
+            });
+        }
+        played
+    }
+
+    /// Runs the playouts of a decision, returning the nodes of the tree, the root first
+    fn grow(&mut self, phase: &Phase, state: &State, legal_actions: &[Action]) -> Vec<Node> {
+        let start = Instant::now();
+        let mut nodes = vec![Node::new(Action::Exit, PlayerId::NONE)];
+        for _ in 0..self.config.playouts {
+            if self.config.time_budget.is_some_and(|budget| start.elapsed() >= budget) {
+                break;
+            }
+            let mut state = audit::conceal(state, state.get_layout(), phase, self.position, &mut self.rng);
+            let mut phase = *phase;
+            let mut path = vec![0];
+            // ## Selection and expansion
+            loop {
+                if let Phase::FinishedGame { winner: _ } = phase {
+                    break;
+                }
+                let node = *path.last().unwrap();
+                let legal = if node == 0 { legal_actions.to_vec() } else { self.legal_actions(&phase, &state) };
+                if legal.is_empty() {
+                    break;
+                }
+                let untried: Vec<Action> = legal.iter()
+                    .filter(|action| nodes[node].children.iter().all(|child| nodes[*child].action != **action))
+                    .copied()
+                    .collect();
+                let expanded = !untried.is_empty();
+                let child = match untried.choose(&mut self.rng) {
+                    Some(action) => {
+                        let child = nodes.len();
+                        nodes.push(Node::new(*action, phase.player()));
+                        nodes[node].children.push(child);
+                        child
+                    }
+                    None => self.select(&nodes, node, &legal),
+                };
+                apply(&mut phase, &mut state, nodes[child].action, &mut self.rng);
+                path.push(child);
+                if expanded {
+                    break;
+                }
+            }
+            // ## Playout and backpropagation
+            let rewards = self.playout(phase, state);
+            for node in path {
+                nodes[node].visits += 1;
+                if nodes[node].player != PlayerId::NONE {
+                    nodes[node].reward += rewards[nodes[node].player.to_usize()];
+                }
+            }
+        }
+        nodes
+    }
+
+    /// Child of the node with the best upper confidence bound, among the ones whose action is legal
+    fn select(&self, nodes: &[Node], node: usize, legal: &[Action]) -> usize {
+        let parent_visits = (nodes[node].visits.max(1) as f32).ln();
+        let ucb = |child: usize| {
+            let child = &nodes[child];
+            child.reward / child.visits as f32 + self.config.exploration * (parent_visits / child.visits as f32).sqrt()
+        };
+        nodes[node].children.iter().copied()
+            .filter(|child| legal.contains(&nodes[*child].action))
+            .max_by(|a, b| ucb(*a).total_cmp(&ucb(*b)))
+            .expect("No legal child")
+    }
+
+    /// Rewards of every player after random actions, 1 for the winner or a tenth of each player's victory points
+    fn playout(&mut self, mut phase: Phase, mut state: State) -> Vec<f32> {
+        for _ in 0..self.config.playout_depth {
+            if let Phase::FinishedGame { winner: _ } = phase {
+                break;
+            }
+            match self.legal_actions(&phase, &state).choose(&mut self.rng) {
+                Some(action) => { apply(&mut phase, &mut state, *action, &mut self.rng); }
+                None => break,
+            }
+        }
+        (0..state.player_count()).map(|p| {
+            let player = PlayerId::from(p);
+            match phase {
+                Phase::FinishedGame { winner } => if winner == player { 1.0 } else { 0.0 },
+                _ => (state.get_player_total_vp(player) as f32 / state.get_rules().victory_points as f32).min(1.0),
+            }
+        }).collect()
+    }
+}
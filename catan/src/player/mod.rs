@@ -1,10 +1,21 @@
 mod picker_player;
 mod randomy;
+mod mcts;
+mod heuristic;
+mod info;
+mod epsilon_greedy;
 pub mod relative;
 
+use rand::Rng;
+use rand::rngs::SmallRng;
+
 use crate::utils::Empty;
-pub use picker_player::{ActionPickerPlayer, IndexPickerPlayer, PickerPlayerTrait, generate_possible_actions};
+pub use picker_player::{ActionPickerPlayer, IndexPickerPlayer, PickerPlayerTrait, generate_possible_actions, generate_trade_bundles};
 pub use randomy::Randomy;
+pub use mcts::{Mcts, MctsConfig, SearchTree, SearchNode, SearchLog, SearchRecord};
+pub use heuristic::Heuristic;
+pub use info::PlayerInfo;
+pub use epsilon_greedy::EpsilonGreedy;
 
 use crate::state::{State, PlayerId};
 use crate::game::{Action, Notification, Error, Phase};
@@ -48,3 +59,19 @@ pub trait Player{
     fn notify(&mut self, notification: &Notification);
     fn results(&mut self, state: &State, winner: PlayerId);
 }
+
+/// Index picked with the probabilities of the softmax of the scores divided by the temperature
+///
+/// The temperature must be positive, the best scores being picked more and more surely as it goes to 0.
+pub(crate) fn sample_softmax(rng: &mut SmallRng, scores: &[f32], temperature: f32) -> usize {
+    let best = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let weights: Vec<f32> = scores.iter().map(|score| ((score - best) / temperature).exp()).collect();
+    let mut picked = rng.random::<f32>() * weights.iter().sum::<f32>();
+    for (i, weight) in weights.iter().enumerate() {
+        if picked < *weight {
+            return i;
+        }
+        picked -= weight;
+    }
+    weights.iter().rposition(|weight| *weight > 0.0).unwrap_or(0)
+}
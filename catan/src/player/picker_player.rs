@@ -71,6 +71,71 @@ pub fn generate_possible_actions(possible_actions: &mut Vec<Action>, player: Pla
             }
         }
     }
+    // ## Trade between players
+    for (give_count, receive_count) in [(1, 1), (2, 1), (1, 2)] {
+        for given in Resource::ALL.iter() {
+            for asked in Resource::ALL.iter() {
+                if given != asked {
+                    let give = Resources::new_one(*given, give_count);
+                    let receive = Resources::new_one(*asked, receive_count);
+                    possible_actions.push(Action::ProposeTrade { give, receive });
+                }
+            }
+        }
+    }
+    possible_actions.push(Action::AcceptTrade);
+    possible_actions.push(Action::RejectTrade);
+    for given in Resource::ALL.iter() {
+        for asked in Resource::ALL.iter() {
+            if given != asked {
+                possible_actions.push(Action::CounterTrade { give: Resources::new_one(*given, 1), receive: Resources::new_one(*asked, 1) });
+            }
+        }
+    }
+    // ## Card by card discards
+    for resource in Resource::ALL.iter() {
+        possible_actions.push(Action::Discard { resource: *resource });
+    }
+    // ## City reservations
+    for intersection in state.get_layout().intersections.iter() {
+        possible_actions.push(Action::ReserveCity { intersection: *intersection });
+    }
+    // ## Answers to counter offers
+    possible_actions.push(Action::WithdrawTrade);
+    for (give_count, receive_count) in [(1, 1), (2, 1), (1, 2)] {
+        for given in Resource::ALL.iter() {
+            for asked in Resource::ALL.iter() {
+                if given != asked {
+                    let give = Resources::new_one(*given, give_count);
+                    let receive = Resources::new_one(*asked, receive_count);
+                    possible_actions.push(Action::ReviseTrade { give, receive });
+                }
+            }
+        }
+    }
+}
+
+/// Adds the bank trade bundles made of two trades, as a compact alternative to trading twice
+///
+/// Each bundle gives two trades worth of resources for two resources, none of them both given and asked.
+/// They go right after the Keep actions, the actions listed after them having been added later on.
+pub fn generate_trade_bundles(possible_actions: &mut Vec<Action>) {
+    let mut bundles = Vec::new();
+    let mut pairs = Vec::new();
+    for (i, first) in Resource::ALL.iter().enumerate() {
+        for second in Resource::ALL[i..].iter() {
+            pairs.push(Resources::new_one(*first, 1) + Resources::new_one(*second, 1));
+        }
+    }
+    for trades in pairs.iter() {
+        for asked in pairs.iter() {
+            if Resource::ALL.iter().all(|res| trades[*res] == 0 || asked[*res] == 0) {
+                bundles.push(Action::TradeBankBundle { trades: *trades, asked: *asked });
+            }
+        }
+    }
+    let index = possible_actions.iter().rposition(|action| matches!(action, Action::Keep { .. })).map_or(possible_actions.len(), |i| i + 1);
+    possible_actions.splice(index..index, bundles);
 }
 
 pub struct ActionPickerPlayer<T : PickerPlayerTrait<ACTIONS = Vec<Action>, PICKED = Action>> {
@@ -29,13 +29,18 @@ impl PickerPlayerTrait for Randomy {
 }
 
 impl Randomy {
-    fn new() -> Randomy {
+    fn new(rng: SmallRng) -> Randomy {
         Randomy {
-            rng: SmallRng::from_rng(&mut rand::rng()),
+            rng,
         }
     }
 
     pub fn new_player() -> impl CatanPlayer {
-        ActionPickerPlayer::new(Randomy::new())
+        ActionPickerPlayer::new(Randomy::new(SmallRng::from_rng(&mut rand::rng())))
+    }
+
+    /// A random player always making the same choices in the same situations
+    pub fn new_seeded_player(seed: u64) -> impl CatanPlayer {
+        ActionPickerPlayer::new(Randomy::new(SmallRng::seed_from_u64(seed)))
     }
 }
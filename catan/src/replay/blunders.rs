@@ -0,0 +1,92 @@
+use serde::Serialize;
+
+use crate::board::Layout;
+use crate::game::{Action, Phase, legal};
+use crate::player::{Mcts, MctsConfig, SearchTree, generate_possible_actions};
+use crate::state::PlayerId;
+use super::{Replay, Replayer};
+
+/// Decision whose played action the search values well below the action it prefers, see [find_blunders]
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Blunder {
+    /// Index of the ply in the replay
+    pub ply: usize,
+    pub player: PlayerId,
+    pub turn: u32,
+    pub played: Action,
+    /// Most visited action of the search
+    pub preferred: Action,
+    /// Mean reward of the player over the playouts through each action
+    pub played_value: f32,
+    pub preferred_value: f32,
+}
+
+impl Blunder {
+    /// Value lost by playing the played action instead of the preferred one
+    pub fn drop(&self) -> f32 {
+        self.preferred_value - self.played_value
+    }
+}
+
+/// Move-quality report of a game, see [find_blunders]
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct BlunderReport {
+    /// Plies with more than one legal action
+    pub decisions: usize,
+    /// Decisions whose played action was tried by the search
+    pub scored: usize,
+    /// Decisions losing at least the threshold, in the order they were played
+    pub blunders: Vec<Blunder>,
+}
+
+impl BlunderReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed serializing blunder report")
+    }
+}
+
+/// Replays a game on the layout, searching each decision with [Mcts] and reporting the played actions losing at least `threshold` of value
+///
+/// The value of an action is the mean reward of the player over the playouts of the search through it, so bots and imported human games
+/// are scored alike. Each decision is searched from the seed and its ply, the report being the same whatever the plies looked at before.
+/// Searches with fewer playouts than legal actions don't try every action, and the decisions whose played action wasn't tried aren't scored.
+pub fn find_blunders(replay: &Replay, layout: &'static Layout, config: MctsConfig, seed: u64, threshold: f32) -> Result<BlunderReport, String> {
+    let mut replayer = Replayer::new(replay, layout).map_err(|error| format!("Bad replay: {:?}", error))?;
+    let mut report = BlunderReport { decisions: 0, scored: 0, blunders: Vec::new() };
+    let mut actions = Vec::new();
+    while let Some(ply) = replay.plies.get(replayer.ply()) {
+        if let Phase::FinishedGame { winner: _ } = replayer.phase() {
+            break;
+        }
+        generate_possible_actions(&mut actions, ply.player, replayer.state());
+        let legal_count = actions.iter().filter(|action| legal::legal(replayer.phase(), replayer.state(), **action).is_ok()).count();
+        if legal_count > 1 {
+            report.decisions += 1;
+            let tree = Mcts::search_tree(config, seed.wrapping_add(replayer.ply() as u64), replayer.phase(), replayer.state());
+            if let Some(blunder) = score(&tree, replayer.ply(), ply.player, replayer.state().get_turn(), ply.action) {
+                report.scored += 1;
+                if blunder.played != blunder.preferred && blunder.drop() >= threshold {
+                    report.blunders.push(blunder);
+                }
+            }
+        }
+        replayer.step();
+    }
+    Ok(report)
+}
+
+/// Played and preferred actions of the root of the tree, None when the played action wasn't tried
+fn score(tree: &SearchTree, ply: usize, player: PlayerId, turn: u32, played: Action) -> Option<Blunder> {
+    let children: Vec<_> = tree.nodes[0].children.iter().map(|child| &tree.nodes[*child]).filter(|child| child.visits > 0).collect();
+    let preferred = children.iter().max_by_key(|child| child.visits)?;
+    let played_node = children.iter().find(|child| child.action == Some(played))?;
+    Some(Blunder {
+        ply,
+        player,
+        turn,
+        played,
+        preferred: preferred.action?,
+        played_value: played_node.value,
+        preferred_value: preferred.value,
+    })
+}
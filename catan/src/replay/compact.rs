@@ -0,0 +1,145 @@
+//! Compact storage of replays, for datasets of many games
+//!
+//! A compact replay keeps the starting checkpoint and the seating, then only the action of each ply, as its index in the action list
+//! of the observations, and the random values of the plies which drew some, as bincode with variable-length integers.
+//! The players, dice rolls, drawn cards and winner are regenerated by playing the plies again.
+//! Random values are cut to their high 16 bits when the ply gives the same outcome with them, which is the case of nearly every draw.
+
+use std::convert::TryFrom;
+
+use bincode::Options;
+use serde::{Serialize, Deserialize};
+
+use crate::board::Layout;
+use crate::game::{Action, Phase, Checkpoint, apply, legal};
+use crate::player::generate_possible_actions;
+use crate::state::{State, StateSnapshot, TricellState, PlayerId};
+use super::Replay;
+use super::rng::CheckedRng;
+
+/// Random values drawn by a ply
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+enum Draws {
+    /// High 16 bits of values drawn as u32
+    Short(Vec<u16>),
+    Full(Vec<u64>),
+}
+
+impl Draws {
+    /// Values drawn back when playing the ply again
+    fn values(&self) -> Vec<u64> {
+        match self {
+            // Halfway through the values with the same high bits, away from the boundaries of the ranges
+            Draws::Short(values) => values.iter().map(|value| ((*value as u64) << 16) | 0x8000).collect(),
+            Draws::Full(values) => values.clone(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct CompactReplay {
+    start: Checkpoint,
+    seating: Vec<usize>,
+    /// Index of the action of each ply in the [action list](action_list), the length of the list standing for the next action of `others`
+    actions: Vec<u16>,
+    others: Vec<Action>,
+    /// Plies which drew random values, as the number of plies since the previous one, and their draws
+    draws: Vec<(u32, Draws)>,
+}
+
+/// Actions of every seat, as the action masks of the observations list them
+fn action_list(state: &State) -> Vec<Action> {
+    let mut actions = Vec::new();
+    generate_possible_actions(&mut actions, PlayerId::from(0u8), state);
+    actions
+}
+
+impl Replay {
+    /// Bytes of the replay keeping only its start, seating, actions and random values, the layout being the one the game was played on
+    ///
+    /// The replay read back from them by [from_compact](Replay::from_compact) plays the same game,
+    /// though with other random values for the plies whose values were cut.
+    pub fn to_compact(&self, layout: &'static Layout) -> Result<Vec<u8>, String> {
+        let mut phase = self.start.phase;
+        let mut state = self.start.state.restore::<TricellState>(layout).map_err(|error| format!("Bad replay: {:?}", error))?;
+        let list = action_list(&state);
+        let mut compact = CompactReplay { start: self.start.clone(), seating: self.seating.clone(), actions: Vec::with_capacity(self.plies.len()), others: Vec::new(), draws: Vec::new() };
+        let mut last_draw = 0;
+        for (i, ply) in self.plies.iter().enumerate() {
+            match list.iter().position(|action| *action == ply.action) {
+                Some(index) => compact.actions.push(index as u16),
+                None => {
+                    compact.actions.push(list.len() as u16);
+                    compact.others.push(ply.action);
+                }
+            }
+            if ply.random.is_empty() {
+                self.play_ply(i, &mut phase, &mut state);
+                continue;
+            }
+            let short: Option<Vec<u16>> = ply.random.iter().map(|value| u32::try_from(*value).ok().map(|value| (value >> 16) as u16)).collect();
+            let short = short.map(|short| {
+                let draws = Draws::Short(short);
+                let (mut short_phase, mut short_state) = (phase, state.clone_state());
+                let values = draws.values();
+                let mut rng = CheckedRng::new(&values);
+                apply(&mut short_phase, &mut short_state, ply.action, &mut rng);
+                (draws, rng.exhausted(), short_phase, short_state)
+            });
+            self.play_ply(i, &mut phase, &mut state);
+            let draws = match short {
+                Some((draws, true, short_phase, short_state)) if short_phase == phase && StateSnapshot::new(&short_state) == StateSnapshot::new(&state) => draws,
+                _ => Draws::Full(ply.random.clone()),
+            };
+            compact.draws.push(((i - last_draw) as u32, draws));
+            last_draw = i;
+        }
+        Ok(bincode::DefaultOptions::new().serialize(&compact).expect("Failed serializing compact replay"))
+    }
+
+    /// Replay of bytes from [to_compact](Replay::to_compact), playing its plies again on the layout
+    pub fn from_compact(bytes: &[u8], layout: &'static Layout) -> Result<Replay, String> {
+        let compact: CompactReplay = bincode::DefaultOptions::new().deserialize(bytes).map_err(|error| format!("Bad compact replay: {}", error))?;
+        let mut replay = Replay {
+            start: compact.start,
+            seating: compact.seating,
+            plies: Vec::with_capacity(compact.actions.len()),
+            winner: None,
+        };
+        replay.check()?;
+        let mut phase = replay.start.phase;
+        let mut state = replay.start.state.restore::<TricellState>(layout).map_err(|error| format!("Bad compact replay: {:?}", error))?;
+        let list = action_list(&state);
+        let (mut others, mut draws) = (compact.others.iter(), compact.draws.iter().peekable());
+        let mut last_draw = 0;
+        for (i, code) in compact.actions.iter().enumerate() {
+            let action = match list.get(*code as usize) {
+                Some(action) => *action,
+                None => *others.next().ok_or_else(|| format!("Bad compact replay: ply {} of unknown action {}", i, code))?,
+            };
+            legal::legal(&phase, &state, action)
+                .map_err(|error| format!("Bad compact replay: ply {} plays illegal {:?}: {:?}", i, action, error))?;
+            let values = match draws.peek() {
+                Some((delta, ply_draws)) if last_draw + *delta as usize == i => {
+                    last_draw = i;
+                    draws.next();
+                    ply_draws.values()
+                }
+                _ => Vec::new(),
+            };
+            let mut rng = CheckedRng::new(&values);
+            replay.record(&mut phase, &mut state, action, &mut rng);
+            if !rng.exhausted() {
+                return Err(format!("Bad compact replay: ply {} didn't draw its {} random values", i, values.len()));
+            }
+        }
+        if others.next().is_some() || draws.next().is_some() {
+            return Err("Bad compact replay: actions or draws past the last ply".to_string());
+        }
+        replay.winner = match phase {
+            Phase::FinishedGame { winner } => Some(winner),
+            _ => None,
+        };
+        Ok(replay)
+    }
+}
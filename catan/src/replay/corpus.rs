@@ -0,0 +1,76 @@
+use rand::Rng;
+
+use crate::board::Layout;
+use crate::game::{Phase, Checkpoint};
+use super::{Replay, Replayer};
+use super::verify::read_game;
+
+/// Games whose positions new games start from, to train on the decisions of their later turns
+pub struct Corpus {
+    replays: Vec<Replay>,
+    /// Turn of the position before each ply of each game
+    turns: Vec<Vec<u32>>,
+    layout: &'static Layout,
+    player_count: usize,
+}
+
+impl Corpus {
+    /// Corpus of games of as many players played on the layout, given as the bytes or JSON of replays, or as records in JSON or notation
+    pub fn read<'a>(games: impl IntoIterator<Item = &'a [u8]>, layout: &'static Layout) -> Result<Corpus, String> {
+        let mut corpus = Corpus { replays: Vec::new(), turns: Vec::new(), layout, player_count: 0 };
+        for (i, bytes) in games.into_iter().enumerate() {
+            let game = |error: String| format!("Game {}: {}", i + 1, error);
+            let replay = read_game(bytes).1.map_err(game)?;
+            let mut replayer = Replayer::new(&replay, layout).map_err(|error| game(format!("Bad start: {:?}", error)))?;
+            let player_count = replayer.state().player_count() as usize;
+            if i > 0 && player_count != corpus.player_count {
+                return Err(game(format!("Game of {} players in a corpus of games of {}", player_count, corpus.player_count)));
+            }
+            corpus.player_count = player_count;
+            let mut turns = Vec::with_capacity(replay.plies.len());
+            while !matches!(replayer.phase(), Phase::FinishedGame { winner: _ }) {
+                let turn = replayer.state().get_turn();
+                if replayer.step().is_none() {
+                    break;
+                }
+                turns.push(turn);
+            }
+            corpus.turns.push(turns);
+            corpus.replays.push(replay);
+        }
+        Ok(corpus)
+    }
+
+    pub fn len(&self) -> usize {
+        self.replays.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replays.is_empty()
+    }
+
+    /// Players of the games of the corpus, 0 when empty
+    pub fn player_count(&self) -> usize {
+        self.player_count
+    }
+
+    /// Checkpoint of the position before a ply drawn uniformly among the plies of the corpus played from turn `min_turn` on,
+    /// None when no game lasted that long
+    pub fn sample<R: Rng>(&self, rng: &mut R, min_turn: u32) -> Option<Checkpoint> {
+        let first = |turns: &Vec<u32>| turns.partition_point(|turn| *turn < min_turn);
+        let count: usize = self.turns.iter().map(|turns| turns.len() - first(turns)).sum();
+        if count == 0 {
+            return None;
+        }
+        let mut drawn = rng.random_range(0..count);
+        for (replay, turns) in self.replays.iter().zip(self.turns.iter()) {
+            let plies = turns.len() - first(turns);
+            if drawn < plies {
+                let (phase, state) = replay.state_at(first(turns) + drawn, self.layout).expect("Corpus game restored when read");
+                return Some(Checkpoint::new(phase, &state));
+            }
+            drawn -= plies;
+        }
+        unreachable!("Ply drawn out of the corpus")
+    }
+}
@@ -0,0 +1,93 @@
+use std::panic::{self, AssertUnwindSafe};
+use serde::{Serialize, Deserialize};
+
+use crate::board::Layout;
+use crate::game::{ENGINE_VERSION, legal, apply};
+use crate::state::{StateSnapshot, TricellState};
+use super::Replay;
+use super::rng::CheckedRng;
+
+/// [StateSnapshot::digest] of the start of a replay and of the state after each of its plies, written by an engine
+/// to check later builds of the engine play the replay the same way, see [first_divergence]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DigestTrail {
+    /// [ENGINE_VERSION] of the engine which wrote the trail
+    pub engine: String,
+    pub digests: Vec<u64>,
+}
+
+impl DigestTrail {
+    /// Trail of the replay played on the layout by this engine
+    pub fn new(replay: &Replay, layout: &'static Layout) -> Result<DigestTrail, String> {
+        let mut digests = Vec::new();
+        play_digests(replay, layout, &mut digests)?;
+        Ok(DigestTrail { engine: ENGINE_VERSION.to_string(), digests })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed serializing digest trail")
+    }
+
+    pub fn from_json(json: &str) -> Result<DigestTrail, String> {
+        serde_json::from_str(json).map_err(|error| format!("Bad digest trail: {}", error))
+    }
+}
+
+/// First state of a replay this engine doesn't reach as the engine of a [DigestTrail] did
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Divergence {
+    /// Plies played before the state, 0 when the starts differ
+    pub ply: usize,
+    /// Digest of the trail, None past its end
+    pub expected: Option<u64>,
+    /// Digest of this engine, None when it couldn't play the ply
+    pub found: Option<u64>,
+    /// Why this engine couldn't play the ply
+    pub error: Option<String>,
+}
+
+impl Divergence {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed serializing divergence")
+    }
+}
+
+/// Plays the replay on the layout, comparing the digest of each state to the trail, None when every state matches
+///
+/// The plies are played with their recorded random values, so a change of the rules engine, of its caching or of its random draws
+/// is found at the first ply it changes the state of. Plies this engine finds illegal, draws other random values for or panics on
+/// diverge without a digest.
+pub fn first_divergence(replay: &Replay, layout: &'static Layout, trail: &DigestTrail) -> Option<Divergence> {
+    let mut found = Vec::with_capacity(trail.digests.len());
+    let played = panic::catch_unwind(AssertUnwindSafe(|| play_digests(replay, layout, &mut found)));
+    let mut error = match played {
+        Ok(result) => result.err(),
+        Err(_) => Some(format!("Ply {}: the engine panicked", found.len())),
+    };
+    for ply in 0..trail.digests.len().max(found.len()) {
+        let (expected, digest) = (trail.digests.get(ply).copied(), found.get(ply).copied());
+        if expected != digest {
+            let error = if digest.is_none() { error.take() } else { None };
+            return Some(Divergence { ply, expected, found: digest, error });
+        }
+    }
+    error.map(|error| Divergence { ply: found.len(), expected: None, found: None, error: Some(error) })
+}
+
+/// Plays the plies of the replay, pushing the digest of the start and of each state reached
+fn play_digests(replay: &Replay, layout: &'static Layout, digests: &mut Vec<u64>) -> Result<(), String> {
+    replay.check()?;
+    let mut phase = replay.start.phase;
+    let mut state = replay.start.state.restore::<TricellState>(layout).map_err(|error| format!("Bad start: {:?}", error))?;
+    digests.push(StateSnapshot::new(&state).digest());
+    for (i, ply) in replay.plies.iter().enumerate() {
+        legal::legal(&phase, &state, ply.action).map_err(|error| format!("Ply {}: illegal {:?}: {:?}", i + 1, ply.action, error))?;
+        let mut rng = CheckedRng::new(&ply.random);
+        apply(&mut phase, &mut state, ply.action, &mut rng);
+        if !rng.exhausted() {
+            return Err(format!("Ply {}: {:?} didn't draw its {} random values", i + 1, ply.action, ply.random.len()));
+        }
+        digests.push(StateSnapshot::new(&state).digest());
+    }
+    Ok(())
+}
@@ -0,0 +1,166 @@
+mod rng;
+mod compact;
+mod verify;
+mod blunders;
+mod corpus;
+mod divergence;
+
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::board::{Layout, Error};
+use crate::game::{Action, Phase, Checkpoint, Notification, apply};
+use crate::state::{State, PlayerId, TricellState};
+use crate::utils::DevelopmentCard;
+use rng::{RecordingRng, ReplayRng};
+pub(crate) use rng::OutcomeRng;
+pub use verify::{Verification, verify, verify_replay};
+pub use blunders::{Blunder, BlunderReport, find_blunders};
+pub use corpus::Corpus;
+pub use divergence::{DigestTrail, Divergence, first_divergence};
+
+/// An action of a replay and its random outcome
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Ply {
+    pub player: PlayerId,
+    pub action: Action,
+    /// Dice rolled by the action
+    pub roll: Option<u8>,
+    /// Development card drawn by the action
+    pub drawn: Option<DevelopmentCard>,
+    /// Values drawn from the game rng while applying the action, replayed to rebuild the state
+    random: Vec<u64>,
+}
+
+/// Every action of a game from its start, recorded by [Game](crate::game::Game) when asked
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub start: Checkpoint,
+    /// Player index sitting at each seat
+    pub seating: Vec<usize>,
+    pub plies: Vec<Ply>,
+    /// Winner of the game, None until it is finished or when it was stopped
+    pub winner: Option<PlayerId>,
+}
+
+impl Replay {
+    pub fn new(phase: Phase, state: &State, seating: Vec<usize>) -> Replay {
+        Replay {
+            start: Checkpoint::new(phase, state),
+            seating,
+            plies: Vec::new(),
+            winner: None,
+        }
+    }
+
+    /// Applies a legal action, recording it with its outcome
+    pub fn record<R: Rng>(&mut self, phase: &mut Phase, state: &mut State, action: Action, rng: &mut R) -> Option<Notification> {
+        let player = phase.player();
+        let cards = state.get_player_hand(player).new_development_cards;
+        let mut recording = RecordingRng::new(rng);
+        let notification = apply(phase, state, action, &mut recording);
+        let drawn = match action {
+            Action::BuyDevelopment => DevelopmentCard::ALL.iter().copied()
+                .find(|card| state.get_player_hand(player).new_development_cards[*card] > cards[*card]),
+            _ => None,
+        };
+        self.plies.push(Ply {
+            player,
+            action,
+            roll: notification.as_ref().and_then(|notification| notification.roll()),
+            drawn,
+            random: recording.values,
+        });
+        notification
+    }
+
+    /// Plays a ply of the replay on the phase and state it was played from, None past the end of the replay
+    pub fn play_ply(&self, ply: usize, phase: &mut Phase, state: &mut State) -> Option<&Ply> {
+        let played = self.plies.get(ply)?;
+        apply(phase, state, played.action, &mut ReplayRng::new(&played.random));
+        Some(played)
+    }
+
+    /// Phase and state once the first plies are played
+    pub fn state_at(&self, ply: usize, layout: &'static Layout) -> Result<(Phase, State), Error> {
+        let mut replayer = Replayer::new(self, layout)?;
+        while replayer.ply() < ply && replayer.step().is_some() {}
+        Ok((replayer.phase, replayer.state))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed serializing replay")
+    }
+
+    pub fn from_json(json: &str) -> Result<Replay, String> {
+        let replay: Replay = serde_json::from_str(json).map_err(|error| format!("Bad replay: {}", error))?;
+        replay.check()?;
+        Ok(replay)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("Failed serializing replay")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, String> {
+        let replay: Replay = bincode::deserialize(bytes).map_err(|error| format!("Bad replay: {}", error))?;
+        replay.check()?;
+        Ok(replay)
+    }
+
+    /// Checks the replay was recorded by a compatible engine, under the rules it holds
+    pub fn check(&self) -> Result<(), String> {
+        self.start.version.check(&self.start.state.rules).map_err(|error| format!("Bad replay: {}", error))
+    }
+}
+
+/// Plays a replay back step by step
+pub struct Replayer<'a> {
+    replay: &'a Replay,
+    layout: &'static Layout,
+    ply: usize,
+    phase: Phase,
+    state: State,
+}
+
+impl<'a> Replayer<'a> {
+    /// Replayer at the start of the game, the layout being the one the game was played on
+    pub fn new(replay: &'a Replay, layout: &'static Layout) -> Result<Replayer<'a>, Error> {
+        Ok(Replayer {
+            replay,
+            layout,
+            ply: 0,
+            phase: replay.start.phase,
+            state: replay.start.state.restore::<TricellState>(layout)?,
+        })
+    }
+
+    /// Number of plies played so far
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    pub fn phase(&self) -> &Phase {
+        &self.phase
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Plays the next ply, None at the end of the replay
+    pub fn step(&mut self) -> Option<&'a Ply> {
+        let ply = self.replay.play_ply(self.ply, &mut self.phase, &mut self.state)?;
+        self.ply += 1;
+        Some(ply)
+    }
+
+    /// Goes to a ply, starting over when it was already played
+    pub fn seek(&mut self, ply: usize) -> Result<(), Error> {
+        if ply < self.ply {
+            *self = Replayer::new(self.replay, self.layout)?;
+        }
+        while self.ply < ply && self.step().is_some() {}
+        Ok(())
+    }
+}
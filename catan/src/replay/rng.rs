@@ -0,0 +1,128 @@
+use rand::RngCore;
+use rand::rand_core::impls;
+
+/// Rng recording every value it draws from another rng
+pub(crate) struct RecordingRng<'a, R: RngCore> {
+    rng: &'a mut R,
+    pub(crate) values: Vec<u64>,
+}
+
+impl<'a, R: RngCore> RecordingRng<'a, R> {
+    pub(crate) fn new(rng: &'a mut R) -> Self {
+        RecordingRng { rng, values: Vec::new() }
+    }
+}
+
+impl<R: RngCore> RngCore for RecordingRng<'_, R> {
+    fn next_u32(&mut self) -> u32 {
+        let value = self.rng.next_u32();
+        self.values.push(value.into());
+        value
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let value = self.rng.next_u64();
+        self.values.push(value);
+        value
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+/// Rng drawing back the values recorded by a [RecordingRng]
+pub(crate) struct ReplayRng<'a> {
+    values: std::slice::Iter<'a, u64>,
+}
+
+impl<'a> ReplayRng<'a> {
+    pub(crate) fn new(values: &'a [u64]) -> Self {
+        ReplayRng { values: values.iter() }
+    }
+}
+
+impl RngCore for ReplayRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        *self.values.next().expect("Replay drew more random values than recorded")
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+/// Rng making each `random_range` of the engine pick a given index, to apply an action with a known outcome
+///
+/// Ranges are sampled by multiplying a random value by their length and keeping the high bits,
+/// so the value `ceil(index * 2^bits / length)` picks the index, and is never rejected as biased.
+pub(crate) struct OutcomeRng {
+    /// Index to pick and length of the range of each draw
+    picks: std::vec::IntoIter<(u64, u64)>,
+}
+
+impl OutcomeRng {
+    pub(crate) fn new(picks: Vec<(u64, u64)>) -> Self {
+        OutcomeRng { picks: picks.into_iter() }
+    }
+}
+
+impl RngCore for OutcomeRng {
+    fn next_u32(&mut self) -> u32 {
+        // Draws beyond the picks give outcomes the caller then finds unexpected
+        let (index, length) = self.picks.next().unwrap_or((0, 1));
+        ((index << 32).div_ceil(length)) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let (index, length) = self.picks.next().unwrap_or((0, 1));
+        (((index as u128) << 64).div_ceil(length as u128)) as u64
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+/// Rng drawing given values, then zeros, noting whether the caller drew more than given
+///
+/// Used to check random draws which may not be the recorded ones, without panicking as a [ReplayRng] does.
+pub(crate) struct CheckedRng<'a> {
+    values: std::slice::Iter<'a, u64>,
+    overdrawn: bool,
+}
+
+impl<'a> CheckedRng<'a> {
+    pub(crate) fn new(values: &'a [u64]) -> Self {
+        CheckedRng { values: values.iter(), overdrawn: false }
+    }
+
+    /// Whether every value was drawn, and no more
+    pub(crate) fn exhausted(&self) -> bool {
+        !self.overdrawn && self.values.len() == 0
+    }
+}
+
+impl RngCore for CheckedRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self.values.next() {
+            Some(value) => *value,
+            None => {
+                self.overdrawn = true;
+                0
+            }
+        }
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
@@ -0,0 +1,135 @@
+use std::panic::{self, AssertUnwindSafe};
+use serde::Serialize;
+
+use crate::board::Layout;
+use crate::game::{Action, Phase, legal, apply, check_coherence};
+use crate::notation::Record;
+use crate::state::{StateSnapshot, PlayerId, TricellState};
+use crate::utils::DevelopmentCard;
+use super::Replay;
+use super::rng::CheckedRng;
+
+/// Report of the verification of an uploaded game against the engine, see [verify]
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Verification {
+    /// Format the game was read as, `replay`, `record` or `notation`
+    pub format: String,
+    /// [ENGINE_VERSION](crate::game::ENGINE_VERSION) the game was recorded with
+    pub engine: Option<String>,
+    /// [Rules::fingerprint](crate::game::Rules::fingerprint) of the rules of the game
+    pub rules: Option<u64>,
+    /// [StateSnapshot::board_digest] of the board the game was played on
+    pub board: Option<u64>,
+    /// Number of plies found legal and played back with their recorded outcome
+    pub plies: usize,
+    pub winner: Option<PlayerId>,
+    /// [StateSnapshot::digest] of the last state, None unless every ply was verified
+    pub digest: Option<u64>,
+    /// First problem found, None when the game plays back as recorded
+    pub error: Option<String>,
+}
+
+impl Verification {
+    fn new(format: &str) -> Verification {
+        Verification { format: format.to_string(), engine: None, rules: None, board: None, plies: 0, winner: None, digest: None, error: None }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed serializing verification")
+    }
+}
+
+/// Verifies a game uploaded as the bytes or JSON of a [Replay], the JSON of a [Record] or its text notation, played on the layout
+///
+/// Records are played back from their moves and outcomes, and every ply of the replay is then checked against the rules of this engine:
+/// played in turn, legal, drawing exactly its recorded random values to give its recorded roll and card, without creating nor losing resources,
+/// and leading to the recorded winner. Malformed games are reported, never panicking.
+pub fn verify(bytes: &[u8], layout: &'static Layout) -> Verification {
+    let (format, replay) = read_game(bytes);
+    match replay {
+        Ok(replay) => Verification { format: format.to_string(), ..verify_replay(&replay, layout) },
+        Err(error) => Verification { error: Some(error), ..Verification::new(format) },
+    }
+}
+
+/// Format and replay of a game given as the bytes or JSON of a [Replay], the JSON of a [Record] or its text notation
+pub(crate) fn read_game(bytes: &[u8]) -> (&'static str, Result<Replay, String>) {
+    let text = std::str::from_utf8(bytes).ok().map(str::trim_start);
+    match text {
+        Some(json) if json.starts_with('{') => match serde_json::from_str::<serde_json::Value>(json) {
+            Ok(value) if value.get("moves").is_some() => ("record", Record::from_json(json).and_then(|record| record.to_replay())),
+            Ok(_) => ("replay", Replay::from_json(json)),
+            Err(error) => ("replay", Err(format!("Bad JSON: {}", error))),
+        },
+        Some(notation) if notation.starts_with('[') => ("notation", Record::from_text(notation).and_then(|record| record.to_replay())),
+        _ => ("replay", Replay::from_bytes(bytes)),
+    }
+}
+
+/// Verifies every ply of a replay played on the layout, see [verify]
+pub fn verify_replay(replay: &Replay, layout: &'static Layout) -> Verification {
+    let mut verification = Verification::new("replay");
+    verification.engine = Some(replay.start.version.engine.clone());
+    verification.rules = Some(replay.start.version.rules);
+    verification.board = Some(replay.start.state.board_digest());
+    // A forged start may hold states the engine never reaches, on which it may panic
+    let played = panic::catch_unwind(AssertUnwindSafe(|| play_back(replay, layout, &mut verification)));
+    let result = played.unwrap_or_else(|_| Err(format!("Ply {}: the engine panicked", verification.plies + 1)));
+    match result {
+        Ok(digest) => verification.digest = Some(digest),
+        Err(error) => verification.error = Some(error),
+    }
+    verification
+}
+
+/// Plays the plies back, counting the verified ones, giving the digest of the last state
+fn play_back(replay: &Replay, layout: &'static Layout, verification: &mut Verification) -> Result<u64, String> {
+    replay.check()?;
+    let mut phase = replay.start.phase;
+    let mut state = replay.start.state.restore::<TricellState>(layout).map_err(|error| format!("Bad start: {:?}", error))?;
+    if replay.seating.len() != state.player_count() as usize {
+        return Err(format!("Seating of {} players for {}", replay.seating.len(), state.player_count()));
+    }
+    let total = (0..state.player_count())
+        .fold(state.get_bank_resources(), |total, p| total + state.get_player_hand(PlayerId::from(p)).resources);
+    for (i, ply) in replay.plies.iter().enumerate() {
+        let number = i + 1;
+        if ply.player != phase.player() || matches!(phase, Phase::FinishedGame { .. }) {
+            return Err(format!("Ply {}: {:?} plays instead of {:?} in {:?}", number, ply.player, phase.player(), phase));
+        }
+        legal::legal(&phase, &state, ply.action)
+            .map_err(|error| format!("Ply {}: illegal {:?}: {:?}", number, ply.action, error))?;
+        let cards = state.get_player_hand(ply.player).new_development_cards;
+        let mut rng = CheckedRng::new(&ply.random);
+        let notification = apply(&mut phase, &mut state, ply.action, &mut rng);
+        if !rng.exhausted() {
+            return Err(format!("Ply {}: {:?} didn't draw its {} random values", number, ply.action, ply.random.len()));
+        }
+        let roll = notification.as_ref().and_then(|notification| notification.roll());
+        if roll != ply.roll {
+            return Err(format!("Ply {}: {:?} rolled {:?} instead of {:?}", number, ply.action, roll, ply.roll));
+        }
+        let drawn = match ply.action {
+            Action::BuyDevelopment => DevelopmentCard::ALL.iter().copied()
+                .find(|card| state.get_player_hand(ply.player).new_development_cards[*card] > cards[*card]),
+            _ => None,
+        };
+        if drawn != ply.drawn {
+            return Err(format!("Ply {}: {:?} drew {:?} instead of {:?}", number, ply.action, drawn, ply.drawn));
+        }
+        check_coherence(&state, total).map_err(|error| format!("Ply {}: {}", number, error))?;
+        verification.plies = number;
+    }
+    verification.winner = match phase {
+        Phase::FinishedGame { winner } => Some(winner),
+        _ => None,
+    };
+    if verification.winner != replay.winner {
+        return Err(format!("Replay won by {:?}, but its plies by {:?}", replay.winner, verification.winner));
+    }
+    Ok(StateSnapshot::new(&state).digest())
+}
@@ -0,0 +1,133 @@
+//! Explanations of the decisions of a game, to build tutorials and to see what an agent is asked
+//!
+//! [why] tells, for a phase of a game, who decides, what the phase is about,
+//! which categories of actions are legal and what leads out of the phase.
+
+use crate::game::{Action, ActionCategory, Phase, PhaseKind, Transition, TurnPhase, DevelopmentPhase, DiscardCodec, TRANSITIONS, legal};
+use crate::notation::write_resources;
+use crate::player::{generate_possible_actions, generate_trade_bundles};
+use crate::state::{State, PlayerId};
+
+/// Explanation of the decision of a phase, see [why]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Why {
+    pub kind: PhaseKind,
+    /// Player to decide, or the winner of a finished game
+    pub player: PlayerId,
+    /// What the player is asked to do
+    pub summary: String,
+    /// What leads out of the phase
+    pub next: String,
+    /// Categories of the legal actions of the player, in the order of their values
+    pub available: Vec<ActionCategory>,
+    /// Transitions out of the kind of the phase, whether their actions are legal or not
+    pub transitions: Vec<Transition>,
+}
+
+/// Explanation of the decision the phase of the state asks for
+pub fn why(state: &State, phase: &Phase) -> Why {
+    let player = phase.player();
+    let (summary, next) = explain(state, phase);
+    Why {
+        kind: phase.kind(),
+        player,
+        summary,
+        next,
+        available: available(state, phase),
+        transitions: TRANSITIONS.iter().filter(|transition| transition.from == phase.kind()).copied().collect(),
+    }
+}
+
+/// Categories of the legal actions of the player to decide
+fn available(state: &State, phase: &Phase) -> Vec<ActionCategory> {
+    if let Phase::FinishedGame { .. } = phase {
+        return Vec::new();
+    }
+    let player = phase.player();
+    let mut actions = Vec::new();
+    generate_possible_actions(&mut actions, player, state);
+    generate_trade_bundles(&mut actions);
+    if phase.kind() == PhaseKind::Discard {
+        let hand = state.get_player_hand(player).resources;
+        let codec = DiscardCodec::for_hand(hand);
+        actions.extend((0..codec.size()).filter_map(|index| codec.keep(hand, index)));
+    }
+    actions.push(Action::Resign);
+    let mut categories: Vec<ActionCategory> = Vec::new();
+    for action in actions.iter() {
+        if !categories.contains(&action.category()) && legal::legal(phase, state, *action).is_ok() {
+            categories.push(action.category());
+        }
+    }
+    categories.sort_by_key(|category| *category as u8);
+    categories
+}
+
+fn ordinal(second: bool) -> &'static str {
+    if second { "second" } else { "first" }
+}
+
+fn free_left(two_left: bool) -> &'static str {
+    if two_left { "2" } else { "1" }
+}
+
+/// Summary of the decision and what leads out of it
+fn explain(state: &State, phase: &Phase) -> (String, String) {
+    let player = phase.player().to_u8();
+    match *phase {
+        Phase::InitialPlacement { placing_second, placing_road: false, .. } => (
+            format!("Player {} places its {} initial settlement", player, ordinal(placing_second)),
+            "Building the settlement asks for a road next to it".to_string(),
+        ),
+        Phase::InitialPlacement { placing_second, placing_road: true, .. } => (
+            format!("Player {} places the road next to its {} initial settlement", player, ordinal(placing_second)),
+            "Building the road passes the placement on, the turns starting after the last road".to_string(),
+        ),
+        Phase::Turn { turn_phase: TurnPhase::Discard(_), .. } => (
+            format!("Player {} discards half of its {} resources after a 7", player, state.get_player_hand(phase.player()).resources.total()),
+            format!("Discarding passes on to the next player holding more than {} resources, then to the thief", state.get_rules().discard_limit),
+        ),
+        Phase::Turn { turn_phase: TurnPhase::MoveThief, .. } => (
+            format!("Player {} moves the thief after a 7, stealing a resource next to it", player),
+            "Moving the thief gives the rest of the turn back".to_string(),
+        ),
+        Phase::Turn { player: proposer, turn_phase: TurnPhase::Trade(offer), .. } if offer.countered => (
+            format!("Player {} answers the counter offer of player {}, which would have it give {} for {}", player, offer.partner.to_u8(),
+                write_resources(offer.give), write_resources(offer.receive)),
+            format!("Accepting makes the trade, rejecting or withdrawing gives the turn back to player {}, revising offers new terms to the same opponent", proposer.to_u8()),
+        ),
+        Phase::Turn { player: proposer, turn_phase: TurnPhase::Trade(offer), .. } => (
+            format!("Player {} answers the offer of player {} to give {} for {}", player, proposer.to_u8(),
+                write_resources(offer.give), write_resources(offer.receive)),
+            "Accepting makes the trade, rejecting passes the offer on to the next opponent, the last one giving the turn back".to_string(),
+        ),
+        Phase::Turn { player: active, turn_phase: TurnPhase::SpecialBuilding(_), .. } => (
+            format!("Player {} builds in the special building phase after the turn of player {}", player, active.to_u8()),
+            "Ending passes the special building on, the last one starting the next turn".to_string(),
+        ),
+        Phase::Turn { development_phase: DevelopmentPhase::KnightActive, .. } => (
+            format!("Player {} moves the thief of its knight, stealing a resource next to it", player),
+            "Moving the thief ends the knight".to_string(),
+        ),
+        Phase::Turn { development_phase: DevelopmentPhase::RoadBuildingActive { two_left }, .. } => (
+            format!("Player {} builds the {} free roads left of its road building card", player, free_left(two_left)),
+            "Building the last free road ends the card".to_string(),
+        ),
+        Phase::Turn { development_phase: DevelopmentPhase::YearOfPlentyActive { two_left }, .. } => (
+            format!("Player {} takes the {} free resources left of its year of plenty card", player, free_left(two_left)),
+            "Choosing the last free resource ends the card".to_string(),
+        ),
+        Phase::Turn { turn_phase: TurnPhase::PreRoll, .. } => (
+            format!("Player {} starts turn {}, before rolling the dice", player, state.get_turn()),
+            "Rolling the dice produces the resources and opens the rest of the turn, a 7 calling for discards and the thief".to_string(),
+        ),
+        Phase::Turn { turn_phase: TurnPhase::Free, .. } => (
+            format!("Player {} builds, trades and plays development cards after rolling", player),
+            "Ending the turn passes the dice to the next player".to_string(),
+        ),
+        Phase::FinishedGame { .. } => (
+            format!("Player {} won the game", player),
+            "The game is over".to_string(),
+        ),
+    }
+}
@@ -0,0 +1,240 @@
+//! Games hosted for remote players, exchanging JSON messages over WebSocket
+//!
+//! A remote player connects and sends `{"type": "join", "name": <name>}`, then receives:
+//! - `{"type": "new_game", "seat": <seat>, "players": <count>}` when a game starts
+//! - `{"type": "observation", "phase", "turn", "hexes", "harbors", "thief", "roads", "buildings", "hand", "players", "bank", "deck", "legal_actions"}`
+//!   when it has to decide, and answers `{"index": <index in legal_actions>}` or `{"action": <action>}`,
+//!   with the `sequence` number of the observation or without it
+//! - `{"type": "error", "message": <message>}` when its answer can't be read, the observation waiting for another answer
+//! - `{"type": "timeout", "action": <action>}` when it didn't answer within the [read timeout](Server::read_timeout) of the server,
+//!   the action, ending the turn or rolling the dice when legal, being played instead and a late answer being dropped
+//! - `{"type": "bad_action", "error": <error>}` when its action is refused, a new observation following
+//! - `{"type": "notification", "notification": <notification>}` for every notification of the game
+//! - `{"type": "results", "winner": <seat or null>, "victory_points": [...]}` when the game is over
+//!
+//! Actions, phases, hands and notifications are serialized as in replays and checkpoints,
+//! and `players` holds the public hand of every seat.
+//! A player answering `{"action": "Resign"}` leaves the game, as does a player whose connection drops once the turns started,
+//! the others playing on without it. A connection dropping during the initial placement ends the game.
+
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs, SocketAddr};
+use std::time::Duration;
+
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+use serde_json::{json, Value};
+use tungstenite::{WebSocket, Message};
+
+use crate::game::{Game, Action, Error, Notification, Phase, legal};
+use crate::state::{State, PlayerId};
+use crate::utils::Harbor;
+use crate::player::{CatanPlayer, PlayerInfo, generate_possible_actions};
+
+/// Observation of a decision, see the [module](self) documentation
+fn observation(phase: &Phase, state: &State, position: PlayerId, legal_actions: &[Action]) -> Value {
+    let view = state.view_for(position);
+    let layout = view.get_layout();
+    json!({
+        "type": "observation",
+        "phase": phase,
+        "turn": view.get_turn(),
+        "hexes": layout.hexes.iter().map(|hex| (*hex, view.get_static_hex(*hex).unwrap())).collect::<Vec<_>>(),
+        "harbors": layout.intersections.iter()
+            .map(|intersection| (*intersection, view.get_static_harbor(*intersection).unwrap()))
+            .filter(|(_, harbor)| *harbor != Harbor::None)
+            .collect::<Vec<_>>(),
+        "thief": view.get_thief_hex(),
+        "roads": layout.paths.iter()
+            .filter_map(|path| view.get_dynamic_path(*path).unwrap().map(|player| (*path, player)))
+            .collect::<Vec<_>>(),
+        "buildings": layout.intersections.iter()
+            .filter_map(|intersection| view.get_dynamic_intersection(*intersection).unwrap().map(|(player, is_city)| (*intersection, player, is_city)))
+            .collect::<Vec<_>>(),
+        "hand": view.hand(),
+        "players": (0..view.player_count()).map(|p| view.public_hand(PlayerId::from(p))).collect::<Vec<_>>(),
+        "bank": view.get_bank_resources(),
+        "deck": view.development_deck_size(),
+        "legal_actions": legal_actions,
+    })
+}
+
+/// Player of a remote client, see the [module](self) documentation
+///
+/// Once the connection is lost, the player exits the games at its next decision.
+pub struct RemotePlayer {
+    socket: WebSocket<TcpStream>,
+    /// Name sent by the client when joining
+    pub info: PlayerInfo,
+    position: PlayerId,
+    possible_actions: Vec<Action>,
+    /// Number of the last observation sent
+    sequence: u64,
+    disconnected: bool,
+}
+
+impl RemotePlayer {
+    /// Remote player of a client, once it joined
+    pub fn new(mut socket: WebSocket<TcpStream>) -> Result<RemotePlayer, String> {
+        let join = match read_text(&mut socket) {
+            Ok(join) => join,
+            Err(ReadFailure::TimedOut) => return Err("No join message in time".to_string()),
+            Err(ReadFailure::Lost(error)) => return Err(error),
+        };
+        let join: Value = serde_json::from_str(&join).map_err(|error| error.to_string())?;
+        if join["type"] != "join" {
+            return Err(format!("Expected a join message, got {}", join));
+        }
+        let name = join["name"].as_str().ok_or("Join message without name")?;
+        Ok(RemotePlayer {
+            socket,
+            info: PlayerInfo::new(name),
+            position: PlayerId::NONE,
+            possible_actions: Vec::new(),
+            sequence: 0,
+            disconnected: false,
+        })
+    }
+
+    fn send(&mut self, message: Value) {
+        if !self.disconnected && self.socket.send(Message::text(message.to_string())).is_err() {
+            self.disconnected = true;
+        }
+    }
+
+    /// Action answering an observation, [None] if the answer can't be read
+    fn parse_answer(&self, answer: &Value, legal_actions: &[Action]) -> Option<Action> {
+        if let Some(index) = answer["index"].as_u64() {
+            legal_actions.get(index as usize).copied()
+        } else {
+            serde_json::from_value(answer["action"].clone()).ok()
+        }
+    }
+}
+
+/// Why no message could be read from a socket
+enum ReadFailure {
+    /// The read timeout of the socket is spent, the connection staying open
+    TimedOut,
+    Lost(String),
+}
+
+/// Next text message of the socket
+fn read_text(socket: &mut WebSocket<TcpStream>) -> Result<String, ReadFailure> {
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => return Ok(text.as_str().to_string()),
+            Ok(Message::Close(_)) => return Err(ReadFailure::Lost("Connection closed".to_string())),
+            Ok(_) => (),
+            Err(tungstenite::Error::Io(error)) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return Err(ReadFailure::TimedOut),
+            Err(error) => return Err(ReadFailure::Lost(error.to_string())),
+        }
+    }
+}
+
+/// Action played for a player who didn't answer in time: ending the turn or rolling if possible, else the first legal action
+fn fallback_action(legal_actions: &[Action]) -> Action {
+    [Action::EndTurn, Action::RollDice].iter().copied()
+        .find(|action| legal_actions.contains(action))
+        .or_else(|| legal_actions.first().copied())
+        .unwrap_or(Action::Exit)
+}
+
+impl CatanPlayer for RemotePlayer {
+    fn new_game(&mut self, position: PlayerId, state: &State) {
+        self.position = position;
+        generate_possible_actions(&mut self.possible_actions, position, state);
+        self.send(json!({ "type": "new_game", "seat": position, "players": state.player_count() }));
+    }
+
+    fn pick_action(&mut self, phase: &Phase, state: &State) -> Action {
+        let legal_actions: Vec<Action> = self.possible_actions.iter()
+            .filter(|action| legal::legal(phase, state, **action).is_ok())
+            .copied()
+            .collect();
+        self.sequence += 1;
+        let mut observation = observation(phase, state, self.position, &legal_actions);
+        observation["sequence"] = json!(self.sequence);
+        self.send(observation);
+        while !self.disconnected {
+            match read_text(&mut self.socket) {
+                Ok(text) => {
+                    let answer: Option<Value> = serde_json::from_str(&text).ok();
+                    // Answers to an observation that timed out are dropped
+                    if answer.as_ref().and_then(|answer| answer["sequence"].as_u64()).is_some_and(|sequence| sequence != self.sequence) {
+                        continue;
+                    }
+                    match answer.and_then(|answer| self.parse_answer(&answer, &legal_actions)) {
+                        Some(action) => return action,
+                        None => self.send(json!({ "type": "error", "message": format!("Unreadable answer {}", text) })),
+                    }
+                }
+                Err(ReadFailure::TimedOut) => {
+                    let action = fallback_action(&legal_actions);
+                    self.send(json!({ "type": "timeout", "action": action }));
+                    return action;
+                }
+                Err(ReadFailure::Lost(_)) => self.disconnected = true,
+            }
+        }
+        if legal::legal(phase, state, Action::Resign).is_ok() {
+            Action::Resign
+        } else {
+            Action::Exit
+        }
+    }
+
+    fn bad_action(&mut self, error: Error) {
+        self.send(json!({ "type": "bad_action", "error": format!("{:?}", error) }));
+    }
+
+    fn notify(&mut self, notification: &Notification) {
+        self.send(json!({ "type": "notification", "notification": notification }));
+    }
+
+    fn results(&mut self, state: &State, winner: PlayerId) {
+        let victory_points: Vec<u8> = (0..state.player_count()).map(|p| state.get_player_total_vp(PlayerId::from(p))).collect();
+        self.send(json!({ "type": "results", "winner": winner.option(), "victory_points": victory_points }));
+    }
+}
+
+/// Server hosting games for remote players
+pub struct Server {
+    listener: TcpListener,
+    /// Time a remote player has to join and to answer each observation, without limit when None
+    pub read_timeout: Option<Duration>,
+}
+
+impl Server {
+    pub fn bind(address: impl ToSocketAddrs) -> Result<Server, String> {
+        let listener = TcpListener::bind(address).map_err(|error| error.to_string())?;
+        Ok(Server {
+            listener,
+            read_timeout: None,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, String> {
+        self.listener.local_addr().map_err(|error| error.to_string())
+    }
+
+    /// Waits for the next client to connect and join
+    pub fn accept(&self) -> Result<RemotePlayer, String> {
+        let (stream, _) = self.listener.accept().map_err(|error| error.to_string())?;
+        // Messages are small and each one waits for an answer
+        stream.set_nodelay(true).map_err(|error| error.to_string())?;
+        stream.set_read_timeout(self.read_timeout).map_err(|error| error.to_string())?;
+        let socket = tungstenite::accept(stream).map_err(|error| error.to_string())?;
+        RemotePlayer::new(socket)
+    }
+
+    /// Waits for `remotes` clients to join the players of the game, and plays a game of the seed
+    pub fn host(&self, game: &mut Game, remotes: usize, seed: u64) -> Result<Notification, String> {
+        for _ in 0..remotes {
+            let player = self.accept()?;
+            let info = player.info.clone();
+            game.add_named_player(Box::new(player), info);
+        }
+        Ok(game.setup_and_play_with_rng(&mut SmallRng::seed_from_u64(seed)))
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt::Debug;
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::board::Layout;
+use crate::utils::{Resource, Resources, DevelopmentCard, DevelopmentCards};
+use crate::game::{Phase, TurnPhase};
+use super::{State, StateSnapshot, TricellState, PlayerId};
+
+/// Copy of the state a player can't tell apart from it
+///
+/// The resources of the other players are redistributed among them when the rules hide the hands,
+/// as are their development cards together with the development deck. Every count stays the same,
+/// and the player who made the pending offer keeps the resources of its side of it.
+pub fn conceal<R: Rng>(state: &State, layout: &'static Layout, phase: &Phase, player: PlayerId, rng: &mut R) -> State {
+    let mut snapshot = StateSnapshot::new(state);
+    let others: Vec<usize> = (0..snapshot.players.len()).filter(|p| *p != player.to_usize()).collect();
+    if snapshot.rules.hidden_hands {
+        // Resources
+        let mut known = vec![Resources::ZERO; snapshot.players.len()];
+        // Only the player who made the offer is known to hold its side of it, the other one answering it
+        if let Phase::Turn { player: proposer, turn_phase: TurnPhase::Trade(offer), development_phase: _ } = phase {
+            if offer.countered {
+                known[offer.partner.to_usize()] = offer.receive;
+            } else {
+                known[proposer.to_usize()] = offer.give;
+            }
+        }
+        let mut pool: Vec<Resource> = others.iter()
+            .flat_map(|p| resource_list(snapshot.players[*p].resources - known[*p]))
+            .collect();
+        pool.shuffle(rng);
+        let mut pool = pool.into_iter();
+        for p in others.iter() {
+            let hand = &mut snapshot.players[*p];
+            let count = (hand.resources - known[*p]).total() as usize;
+            hand.resources = pool.by_ref().take(count).fold(known[*p], |mut resources, res| {
+                resources[res] += 1;
+                resources
+            });
+        }
+        // Development cards
+        let mut pool: Vec<DevelopmentCard> = others.iter()
+            .flat_map(|p| card_list(snapshot.players[*p].development_cards).chain(card_list(snapshot.players[*p].new_development_cards)))
+            .chain(card_list(snapshot.development_cards))
+            .collect();
+        pool.shuffle(rng);
+        let mut pool = pool.into_iter();
+        let mut deal = |cards: &mut DevelopmentCards| {
+            *cards = pool.by_ref().take(cards.total() as usize).fold(DevelopmentCards::new(), |mut cards, card| {
+                cards[card] += 1;
+                cards
+            });
+        };
+        for p in others.iter() {
+            deal(&mut snapshot.players[*p].development_cards);
+            deal(&mut snapshot.players[*p].new_development_cards);
+        }
+        deal(&mut snapshot.development_cards);
+    }
+    snapshot.restore::<TricellState>(layout).expect("Failed restoring concealed state")
+}
+
+/// Checks an observation of a player only depends on what the player may see, by comparing it on concealed copies of the state
+///
+/// Observations built through [VisibleState](super::VisibleState) pass, while reading the hidden parts of the state
+/// fails as soon as a concealed copy differs from the state there.
+pub fn assert_view_only<T, R, F>(state: &State, layout: &'static Layout, phase: &Phase, player: PlayerId, rng: &mut R, attempts: usize, mut observe: F)
+    where T: PartialEq + Debug, R: Rng, F: FnMut(&State) -> T {
+    let observation = observe(state);
+    for _ in 0..attempts {
+        let concealed = conceal(state, layout, phase, player, rng);
+        assert_eq!(observation, observe(&concealed), "Observation of {:?} reads hidden information", player);
+    }
+}
+
+fn resource_list(resources: Resources) -> impl Iterator<Item = Resource> {
+    Resource::ALL.iter().flat_map(move |res| std::iter::repeat_n(*res, resources[*res] as usize))
+}
+
+fn card_list(cards: DevelopmentCards) -> impl Iterator<Item = DevelopmentCard> {
+    DevelopmentCard::ALL.iter().flat_map(move |card| std::iter::repeat_n(*card, cards[*card] as usize))
+}
@@ -0,0 +1,196 @@
+use crate::utils::{Resource, Resources};
+use super::{PlayerId, TransferKind, VisibleState, SeenTransfer};
+
+/// Resources of a hand, as deduced by an observer
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct HandBelief {
+    /// Resources surely in the hand
+    pub minimum: Resources,
+    /// Expected count of each resource, adding up to [count](HandBelief::count)
+    pub expected: [f32; Resource::COUNT],
+    /// Resource cards in the hand
+    pub count: u8,
+}
+
+impl HandBelief {
+    const EMPTY: HandBelief = HandBelief {
+        minimum: Resources::ZERO,
+        expected: [0.0; Resource::COUNT],
+        count: 0,
+    };
+
+    fn exact(resources: Resources) -> HandBelief {
+        let mut expected = [0.0; Resource::COUNT];
+        for res in Resource::ALL.iter() {
+            expected[res.to_usize()] = resources[*res] as f32;
+        }
+        HandBelief {
+            minimum: resources,
+            expected,
+            count: resources.total() as u8,
+        }
+    }
+
+    /// Most cards of each resource the hand may hold, every card of unknown resource being of it
+    pub fn maximum(&self) -> Resources {
+        let unknown = self.count as i8 - self.minimum.total();
+        let mut maximum = self.minimum;
+        for res in Resource::ALL.iter() {
+            maximum[*res] += unknown;
+        }
+        maximum
+    }
+
+    /// Probability that a card drawn from the hand is of the resource
+    pub fn probability(&self, res: Resource) -> f32 {
+        if self.count == 0 { 0.0 } else { self.expected[res.to_usize()] / self.count as f32 }
+    }
+
+    fn gain(&mut self, resources: Resources) {
+        self.minimum += resources;
+        for res in Resource::ALL.iter() {
+            self.expected[res.to_usize()] += resources[*res] as f32;
+        }
+        self.count += resources.total() as u8;
+        self.normalize();
+    }
+
+    fn lose(&mut self, resources: Resources) {
+        for res in Resource::ALL.iter() {
+            self.minimum[*res] = (self.minimum[*res] - resources[*res]).max(0);
+            self.expected[res.to_usize()] -= resources[*res] as f32;
+        }
+        self.count = (self.count as i8 - resources.total()).max(0) as u8;
+        self.normalize();
+    }
+
+    /// Loses a card the observer didn't see, returning the probability of each resource to be the one lost
+    fn lose_unknown(&mut self) -> [f32; Resource::COUNT] {
+        let mut lost = [0.0; Resource::COUNT];
+        for res in Resource::ALL.iter() {
+            lost[res.to_usize()] = self.probability(*res);
+            // Any of the resources surely held may be the one lost
+            self.minimum[*res] = (self.minimum[*res] - 1).max(0);
+            self.expected[res.to_usize()] -= lost[res.to_usize()];
+        }
+        self.count = self.count.saturating_sub(1);
+        self.normalize();
+        lost
+    }
+
+    fn gain_unknown(&mut self, gained: [f32; Resource::COUNT]) {
+        for res in Resource::ALL.iter() {
+            self.expected[res.to_usize()] += gained[res.to_usize()];
+        }
+        self.count += 1;
+        self.normalize();
+    }
+
+    /// Spreads the cards of unknown resource over the resources, in proportion of their expected counts beyond the minimum
+    fn normalize(&mut self) {
+        let unknown = self.count as f32 - self.minimum.total() as f32;
+        let mut uncertain = [0.0; Resource::COUNT];
+        for res in Resource::ALL.iter() {
+            uncertain[res.to_usize()] = (self.expected[res.to_usize()] - self.minimum[*res] as f32).max(0.0);
+        }
+        let total: f32 = uncertain.iter().sum();
+        for res in Resource::ALL.iter() {
+            let share = if unknown <= 0.0 {
+                0.0
+            } else if total > 0.0 {
+                uncertain[res.to_usize()] * unknown / total
+            } else {
+                unknown / Resource::COUNT as f32
+            };
+            self.expected[res.to_usize()] = self.minimum[*res] as f32 + share;
+        }
+    }
+}
+
+/// What a player can deduce of the resources of every player from the resource transfers it saw
+///
+/// Every transfer is seen but the cards stolen between two other players, see [VisibleState::seen_transfers],
+/// so the tracker knows the count of every hand, the resources surely in it,
+/// and the probabilities of the cards whose resource it couldn't see.
+/// Hands seen by the player, its own and all of them when the rules open the hands, are known exactly.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BeliefTracker {
+    observer: PlayerId,
+    hands: Vec<HandBelief>,
+    /// Transfers of the ledger already read
+    read: usize,
+}
+
+impl BeliefTracker {
+    pub fn new(observer: PlayerId, player_count: u8) -> BeliefTracker {
+        BeliefTracker {
+            observer,
+            hands: vec![HandBelief::EMPTY; player_count as usize],
+            read: 0,
+        }
+    }
+
+    /// Tracker of the player having read the whole ledger of its view
+    pub fn from_view(view: &VisibleState) -> BeliefTracker {
+        let mut tracker = BeliefTracker::new(view.player(), view.player_count());
+        tracker.update(view);
+        tracker
+    }
+
+    pub fn observer(&self) -> PlayerId {
+        self.observer
+    }
+
+    pub fn hand(&self, player: PlayerId) -> &HandBelief {
+        &self.hands[player.to_usize()]
+    }
+
+    /// Reads the transfers of the view since the last update, starting over when the ledger is shorter, as in a new game
+    pub fn update(&mut self, view: &VisibleState) {
+        if view.transfer_count() < self.read || self.hands.len() != view.player_count() as usize {
+            *self = BeliefTracker::new(self.observer, view.player_count());
+        }
+        for transfer in view.seen_transfers(self.read) {
+            self.read += 1;
+            self.apply(&transfer);
+        }
+        for p in 0..view.player_count() {
+            let player = PlayerId::from(p);
+            if let Some(hand) = view.revealed_hand(player) {
+                self.hands[player.to_usize()] = HandBelief::exact(hand.resources);
+            }
+        }
+    }
+
+    fn apply(&mut self, transfer: &SeenTransfer) {
+        let (from, to) = (transfer.from.option(), transfer.to.option());
+        match transfer.resources {
+            Some(resources) => {
+                if let Some(from) = from {
+                    let hand = &mut self.hands[from.to_usize()];
+                    hand.lose(resources);
+                    // Monopoles take every card of the resource
+                    if transfer.kind == TransferKind::Monopole {
+                        for res in Resource::ALL.iter().filter(|res| resources[**res] > 0) {
+                            hand.minimum[*res] = 0;
+                            hand.expected[res.to_usize()] = 0.0;
+                        }
+                        hand.normalize();
+                    }
+                }
+                if let Some(to) = to {
+                    self.hands[to.to_usize()].gain(resources);
+                }
+            }
+            None => {
+                let lost = match from {
+                    Some(from) => self.hands[from.to_usize()].lose_unknown(),
+                    None => [1.0 / Resource::COUNT as f32; Resource::COUNT],
+                };
+                if let Some(to) = to {
+                    self.hands[to.to_usize()].gain_unknown(lost);
+                }
+            }
+        }
+    }
+}
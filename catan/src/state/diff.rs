@@ -0,0 +1,142 @@
+use std::fmt::{Debug, Write};
+
+use crate::utils::{Hex, LandHex, Harbor, CoordType};
+use super::{State, StateSnapshot, PlayerId, PlayerHand};
+
+/// Highlights a changed cell on a terminal, reverting to the normal style after it
+const HIGHLIGHT: (&str, &str) = ("\x1b[7m", "\x1b[0m");
+
+/// Drawing of the board like the [Display](std::fmt::Display) of the state, with its pieces
+///
+/// Roads show the digit of their player, like `=1=`, settlements `(1)` and cities `[1]`, and the thief surrounds its hex, like `<[ 8B]>`.
+/// Every cell keeps its width, so that the drawings of two states of a layout line up.
+pub fn board_text(state: &State) -> String {
+    let layout = state.get_layout();
+    let w = layout.width as usize;
+    let mut text = String::new();
+    for i in 0..layout.size {
+        let coord = layout.coord_index(i).unwrap();
+        if i % w == 0 {
+            match coord.y.rem_euclid(4) {
+                0 | 1 | 3 => text.push_str("  "),
+                _ => (),
+            };
+        }
+        let (open, close) = if coord == state.get_thief_hex() { ('<', '>') } else { (' ', ' ') };
+        let _ = match coord.get_type() {
+            CoordType::Hex => match state.get_static_hex(coord).unwrap_or(Hex::Water) {
+                Hex::Water => write!(text, "  ~~~  "),
+                Hex::Land(LandHex::Desert) => write!(text, "{}[ D ]{}", open, close),
+                Hex::Land(LandHex::Prod(res, v)) => write!(text, "{}[{:>2}{}]{}", open, v, res, close),
+            },
+            CoordType::Path => {
+                let side = if coord.y & 1 == 0 { '|' } else { '=' };
+                match state.get_dynamic_path(coord).ok().flatten() {
+                    Some(player) => write!(text, "{0}{1}{0}", side, player.to_u8()),
+                    None => write!(text, "{0}.{0}", side),
+                }
+            }
+            CoordType::Intersection => match (state.get_dynamic_intersection(coord).ok().flatten(), state.get_static_harbor(coord).unwrap_or(Harbor::None)) {
+                (Some((player, false)), _) => write!(text, "({})", player.to_u8()),
+                (Some((player, true)), _) => write!(text, "[{}]", player.to_u8()),
+                (None, Harbor::Special(res)) => write!(text, "{0} {0}", res),
+                (None, Harbor::Generic) => write!(text, "X X"),
+                (None, Harbor::None) => write!(text, "( )"),
+            },
+            CoordType::Void => write!(text, " "),
+        };
+        if (i + 1) % w == 0 {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+fn changed<T: PartialEq + Debug>(lines: &mut Vec<String>, name: &str, before: &T, after: &T) {
+    if before != after {
+        lines.push(format!("{}: {:?} -> {:?}", name, before, after));
+    }
+}
+
+fn hand_changes(lines: &mut Vec<String>, player: usize, before: &PlayerHand, after: &PlayerHand) {
+    let name = |field: &str| format!("player {} {}", player, field);
+    changed(lines, &name("resources"), &before.resources, &after.resources);
+    changed(lines, &name("pieces"), &(before.road_pieces, before.settlement_pieces, before.city_pieces), &(after.road_pieces, after.settlement_pieces, after.city_pieces));
+    changed(lines, &name("building vp"), &before.building_vp, &after.building_vp);
+    changed(lines, &name("knights"), &before.knights, &after.knights);
+    changed(lines, &name("continuous road"), &before.continous_road, &after.continous_road);
+    changed(lines, &name("development cards"), &before.development_cards, &after.development_cards);
+    changed(lines, &name("new development cards"), &before.new_development_cards, &after.new_development_cards);
+    changed(lines, &name("played development cards"), &before.played_development_cards, &after.played_development_cards);
+    changed(lines, &name("reserved city"), &before.reserved_city, &after.reserved_city);
+    changed(lines, &name("resigned"), &before.resigned, &after.resigned);
+    changed(lines, &name("harbors"), &before.harbor, &after.harbor);
+}
+
+/// Everything differing between two states of a layout, one line each, like `player 1 resources: ... -> ...`
+pub fn differences(before: &State, after: &State) -> Vec<String> {
+    let (a, b) = (StateSnapshot::new(before), StateSnapshot::new(after));
+    let mut lines = Vec::new();
+    changed(&mut lines, "turn", &a.turn, &b.turn);
+    changed(&mut lines, "rules fingerprint", &a.rules.fingerprint(), &b.rules.fingerprint());
+    for ((coord, hex), (_, other)) in a.hexes.iter().zip(b.hexes.iter()) {
+        changed(&mut lines, &format!("hex {}", coord), hex, other);
+    }
+    changed(&mut lines, "harbors", &a.harbors, &b.harbors);
+    changed(&mut lines, "thief", &a.thief, &b.thief);
+    let layout = before.get_layout();
+    for path in layout.paths.iter() {
+        let owner = |state: &State| state.get_dynamic_path(*path).ok().flatten();
+        changed(&mut lines, &format!("road {}", path), &owner(before), &owner(after));
+    }
+    for intersection in layout.intersections.iter() {
+        let building = |state: &State| state.get_dynamic_intersection(*intersection).ok().flatten();
+        changed(&mut lines, &format!("building {}", intersection), &building(before), &building(after));
+    }
+    for (p, (x, y)) in a.players.iter().zip(b.players.iter()).enumerate() {
+        hand_changes(&mut lines, p, x, y);
+        let vp = |state: &State| state.get_player_total_vp(PlayerId::from(p));
+        changed(&mut lines, &format!("player {} total vp", p), &vp(before), &vp(after));
+    }
+    changed(&mut lines, "players", &a.players.len(), &b.players.len());
+    changed(&mut lines, "bank", &a.bank, &b.bank);
+    changed(&mut lines, "development deck", &a.development_cards, &b.development_cards);
+    changed(&mut lines, "longest road", &a.longest_road, &b.longest_road);
+    changed(&mut lines, "largest army", &a.largest_army, &b.largest_army);
+    changed(&mut lines, "pending discards", &a.discards, &b.discards);
+    changed(&mut lines, "ledger transfers", &a.ledger.transfers().len(), &b.ledger.transfers().len());
+    lines
+}
+
+/// Boards of two states of a layout side by side, followed by their [differences]
+///
+/// Lines of the boards differing are marked by a `*` between them, and with `color` the changed cells are highlighted for a terminal.
+pub fn side_by_side(before: &State, after: &State, color: bool) -> String {
+    let (left, right) = (board_text(before), board_text(after));
+    let width = left.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+    let mut text = String::new();
+    for (a, b) in left.lines().zip(right.lines()) {
+        let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+        let differs = |i: usize| a.get(i) != b.get(i);
+        let draw = |line: &[char]| -> String {
+            let mut drawn = String::new();
+            for (i, c) in line.iter().enumerate() {
+                if color && differs(i) && (i == 0 || !differs(i - 1)) {
+                    drawn.push_str(HIGHLIGHT.0);
+                }
+                drawn.push(*c);
+                if color && differs(i) && (i + 1 == line.len() || !differs(i + 1)) {
+                    drawn.push_str(HIGHLIGHT.1);
+                }
+            }
+            drawn
+        };
+        let gutter = if (0..a.len().max(b.len())).any(differs) { " * " } else { " | " };
+        let _ = writeln!(text, "{}{}{}{}", draw(&a), " ".repeat(width - a.len()), gutter, draw(&b));
+    }
+    for line in differences(before, after) {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
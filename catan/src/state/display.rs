@@ -53,9 +53,9 @@ impl Display for State {
                 };
             }
             match coord.get_type() {
-                CoordType::Hex => hex(self.get_static_hex(coord).unwrap(), f),
+                CoordType::Hex => hex(self.get_static_hex(coord).unwrap_or(Hex::Water), f),
                 CoordType::Path => path(coord.y & 1 == 0, f),
-                CoordType::Intersection => intersection(self.get_static_harbor(coord).unwrap(), f),
+                CoordType::Intersection => intersection(self.get_static_harbor(coord).unwrap_or(Harbor::None), f),
                 CoordType::Void => void(f),
             }?;
             if (i + 1) % w == 0 {
@@ -0,0 +1,54 @@
+use serde::{Serialize, Deserialize};
+
+use crate::utils::Coord;
+use super::PlayerId;
+
+/// A settlement or city being placed on an intersection
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Placement {
+    pub turn: u32,
+    pub intersection: Coord,
+    pub player: PlayerId,
+    pub is_city: bool,
+}
+
+/// Every settlement and city placement of a game, in order
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct BuildingHistory {
+    placements: Vec<Placement>,
+}
+
+impl BuildingHistory {
+    pub fn new() -> BuildingHistory {
+        BuildingHistory {
+            placements: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, placement: Placement) {
+        self.placements.push(placement);
+    }
+
+    pub fn placements(&self) -> &Vec<Placement> {
+        &self.placements
+    }
+
+    /// Forgets the placements of the player, whose pieces were taken off the board
+    pub fn remove_player(&mut self, player: PlayerId) {
+        self.placements.retain(|p| p.player != player);
+    }
+
+    pub fn for_player(&self, player: PlayerId) -> impl Iterator<Item = &Placement> {
+        self.placements.iter().filter(move |p| p.player == player)
+    }
+
+    /// The placement that first settled the intersection
+    pub fn settled(&self, intersection: Coord) -> Option<&Placement> {
+        self.placements.iter().find(|p| p.intersection == intersection)
+    }
+
+    /// The placement that turned the settlement at the intersection into a city
+    pub fn upgraded(&self, intersection: Coord) -> Option<&Placement> {
+        self.placements.iter().find(|p| p.intersection == intersection && p.is_city)
+    }
+}
@@ -0,0 +1,209 @@
+use std::fmt;
+use std::io::{self, Write};
+use serde::{Serialize, Deserialize};
+
+use crate::utils::{Resource, Resources, Harbor};
+use super::PlayerId;
+
+/// Reason of a resource transfer
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TransferKind {
+    /// Resources received when placing the second initial settlement
+    Setup,
+    Production,
+    TradeBank,
+    /// Resources exchanged by an accepted trade offer
+    TradePlayer,
+    Steal,
+    Discard,
+    /// Resources paid for a building or a development card
+    Purchase,
+    YearOfPlenty,
+    Monopole,
+    /// Resources handed back to the bank by a player who resigns
+    Resignation,
+}
+
+/// A single movement of resources between two parties
+///
+/// [PlayerId::NONE] stands for the bank
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Transfer {
+    pub turn: u32,
+    pub kind: TransferKind,
+    pub from: PlayerId,
+    pub to: PlayerId,
+    pub resources: Resources,
+}
+
+/// A transfer as seen by a player, see [VisibleState::seen_transfers](super::VisibleState::seen_transfers)
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct SeenTransfer {
+    pub turn: u32,
+    pub kind: TransferKind,
+    pub from: PlayerId,
+    pub to: PlayerId,
+    /// Resources transferred, [None] when the player couldn't see them
+    pub resources: Option<Resources>,
+}
+
+/// Trades with the bank made at the rate of a harbor, recorded alongside their [TradeBank](TransferKind::TradeBank) transfers
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct HarborTrade {
+    pub turn: u32,
+    pub player: PlayerId,
+    pub harbor: Harbor,
+    /// Resources received at the rate of the harbor
+    pub trades: u8,
+}
+
+/// How a player answered a trade offer, every player seeing it
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TradeAnswer {
+    Accepted,
+    Rejected,
+    Countered,
+}
+
+/// Answer of a player to a trade offer, the proposer answering the counter offers
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TradeResponse {
+    pub turn: u32,
+    pub proposer: PlayerId,
+    /// Player answering the offer
+    pub responder: PlayerId,
+    pub answer: TradeAnswer,
+}
+
+/// Trade offered by the player whose turn it is, see [Rules::trade_proposals_per_turn](crate::game::Rules::trade_proposals_per_turn)
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TradeProposal {
+    pub turn: u32,
+    pub proposer: PlayerId,
+    pub give: Resources,
+    pub receive: Resources,
+}
+
+/// Every resource transfer of a game, in order
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Ledger {
+    transfers: Vec<Transfer>,
+    #[serde(default)]
+    harbor_trades: Vec<HarborTrade>,
+    #[serde(default)]
+    trade_responses: Vec<TradeResponse>,
+    #[serde(default)]
+    trade_proposals: Vec<TradeProposal>,
+}
+
+impl fmt::Display for TransferKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            TransferKind::Setup => "setup",
+            TransferKind::Production => "production",
+            TransferKind::TradeBank => "trade_bank",
+            TransferKind::TradePlayer => "trade_player",
+            TransferKind::Steal => "steal",
+            TransferKind::Discard => "discard",
+            TransferKind::Purchase => "purchase",
+            TransferKind::YearOfPlenty => "year_of_plenty",
+            TransferKind::Monopole => "monopole",
+            TransferKind::Resignation => "resignation",
+        })
+    }
+}
+
+fn party(player: PlayerId) -> String {
+    match player.option() {
+        Some(player) => player.to_u8().to_string(),
+        None => "bank".to_string(),
+    }
+}
+
+impl Ledger {
+    pub fn new() -> Ledger {
+        Ledger {
+            transfers: Vec::new(),
+            harbor_trades: Vec::new(),
+            trade_responses: Vec::new(),
+            trade_proposals: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, transfer: Transfer) {
+        self.transfers.push(transfer);
+    }
+
+    pub fn transfers(&self) -> &Vec<Transfer> {
+        &self.transfers
+    }
+
+    pub fn record_harbor_trade(&mut self, trade: HarborTrade) {
+        self.harbor_trades.push(trade);
+    }
+
+    pub fn harbor_trades(&self) -> &Vec<HarborTrade> {
+        &self.harbor_trades
+    }
+
+    pub fn record_trade_response(&mut self, response: TradeResponse) {
+        self.trade_responses.push(response);
+    }
+
+    pub fn trade_responses(&self) -> &Vec<TradeResponse> {
+        &self.trade_responses
+    }
+
+    pub fn record_trade_proposal(&mut self, proposal: TradeProposal) {
+        self.trade_proposals.push(proposal);
+    }
+
+    pub fn trade_proposals(&self) -> &Vec<TradeProposal> {
+        &self.trade_proposals
+    }
+
+    /// Trades the player offered during the turn
+    pub fn proposals_of_turn(&self, player: PlayerId, turn: u32) -> usize {
+        let first = self.trade_proposals.partition_point(|proposal| proposal.turn < turn);
+        self.trade_proposals[first..].iter().filter(|proposal| proposal.proposer == player).count()
+    }
+
+    /// Trades made by the player at the rate of each harbor, indexed by [Harbor::to_usize]
+    pub fn harbor_usage(&self, player: PlayerId) -> [u32; Harbor::COUNT] {
+        let mut usage = [0; Harbor::COUNT];
+        for trade in self.harbor_trades.iter().filter(|trade| trade.player == player) {
+            usage[trade.harbor.to_usize()] += trade.trades as u32;
+        }
+        usage
+    }
+
+    /// Transfers given or received by the player
+    pub fn for_player(&self, player: PlayerId) -> impl Iterator<Item = &Transfer> {
+        self.transfers.iter().filter(move |t| t.from == player || t.to == player)
+    }
+
+    /// Total of each resource received minus given by the player (or the bank)
+    pub fn net(&self, player: PlayerId) -> [i32; Resource::COUNT] {
+        let mut net = [0; Resource::COUNT];
+        for transfer in self.for_player(player) {
+            let sign = if transfer.to == player { 1 } else { -1 };
+            for res in Resource::ALL.iter() {
+                net[res.to_usize()] += sign * transfer.resources[*res] as i32;
+            }
+        }
+        net
+    }
+
+    /// Writes the ledger as CSV, with one transfer per line
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "turn,kind,from,to,brick,lumber,ore,grain,wool")?;
+        for t in self.transfers.iter() {
+            write!(writer, "{},{},{},{}", t.turn, t.kind, party(t.from), party(t.to))?;
+            for res in Resource::ALL.iter() {
+                write!(writer, ",{}", t.resources[*res])?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
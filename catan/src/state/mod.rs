@@ -1,18 +1,36 @@
 mod display;
 mod player_hand;
 mod tricell_state;
+mod ledger;
+mod history;
+mod snapshot;
+mod road_network;
+mod belief;
+mod opponent_model;
+mod visible;
 pub mod topology;
+pub mod audit;
+pub mod diff;
 
-pub use player_hand::PlayerHand;
+pub use player_hand::{PlayerHand, AccessibleHarbor};
 pub use tricell_state::TricellState;
-
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub use ledger::{Ledger, Transfer, TransferKind, SeenTransfer, HarborTrade, TradeAnswer, TradeResponse, TradeProposal};
+pub use history::{BuildingHistory, Placement};
+pub use snapshot::StateSnapshot;
+pub use road_network::RoadNetwork;
+pub use belief::{BeliefTracker, HandBelief};
+pub use opponent_model::{OpponentModel, OpponentProfile};
+pub use visible::{VisibleState, PublicHand};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct PlayerId(u8);
 
 use std::any::Any;
+use serde::{Serialize, Deserialize};
 
-use crate::utils::{Hex, Harbor, Coord, DevelopmentCards, Resources};
+use crate::utils::{Hex, LandHex, Harbor, Coord, DevelopmentCards, Resources};
 use crate::board::{Layout, Error};
+use crate::game::Rules;
 
 impl PlayerId {
     pub const NONE: PlayerId = PlayerId(std::u8::MAX as u8);
@@ -55,7 +73,7 @@ pub type State = Box<dyn StateTrait>;
 
 pub trait StateTrait {
 
-    fn get_layout(&self) -> &Layout;
+    fn get_layout(&self) -> &'static Layout;
 
     fn player_count(&self) -> u8;
 
@@ -79,6 +97,56 @@ pub trait StateTrait {
 
     fn apply_discards(&mut self);
 
+    /// Resources a player discarding card by card picked so far, and the count of cards it still has to discard,
+    /// None when the player has no discard left to make
+    fn get_pending_discard(&self, player: PlayerId) -> Option<(Resources, u8)> {
+        let (_, discarded) = self.peek_discards().iter().find(|(p, _)| *p == player)?;
+        let discarded = discarded.unwrap_or(Resources::ZERO);
+        let left = self.get_player_hand(player).resources.total() / 2 - discarded.total();
+        if left > 0 { Some((discarded, left as u8)) } else { None }
+    }
+
+    fn get_rules(&self) -> Rules;
+
+    fn set_rules(&mut self, rules: Rules);
+
+    /// Number of turns ended since the end of the initial placement
+    fn get_turn(&self) -> u32;
+
+    fn next_turn(&mut self);
+
+    fn set_turn(&mut self, turn: u32);
+
+    fn get_ledger(&self) -> &Ledger;
+
+    fn get_ledger_mut(&mut self) -> &mut Ledger;
+
+    /// Records a resource transfer in the ledger, stamped with the current turn
+    fn record_transfer(&mut self, kind: TransferKind, from: PlayerId, to: PlayerId, resources: Resources) {
+        let turn = self.get_turn();
+        self.get_ledger_mut().record(Transfer { turn, kind, from, to, resources });
+    }
+
+    /// Records trades with the bank at the rate of a harbor, those without a harbor not being recorded
+    fn record_harbor_trade(&mut self, player: PlayerId, harbor: Harbor, trades: u8) {
+        if harbor != Harbor::None && trades > 0 {
+            let turn = self.get_turn();
+            self.get_ledger_mut().record_harbor_trade(HarborTrade { turn, player, harbor, trades });
+        }
+    }
+
+    /// Records the answer of a player to a trade offer in the ledger
+    fn record_trade_response(&mut self, proposer: PlayerId, responder: PlayerId, answer: TradeAnswer) {
+        let turn = self.get_turn();
+        self.get_ledger_mut().record_trade_response(TradeResponse { turn, proposer, responder, answer });
+    }
+
+    /// Records a trade offered by the player whose turn it is in the ledger
+    fn record_trade_proposal(&mut self, proposer: PlayerId, give: Resources, receive: Resources) {
+        let turn = self.get_turn();
+        self.get_ledger_mut().record_trade_proposal(TradeProposal { turn, proposer, give, receive });
+    }
+
     // Player
     fn get_player_hand(&self, player: PlayerId) -> &PlayerHand;
 
@@ -105,23 +173,29 @@ pub trait StateTrait {
 
     fn get_longest_road(&self) -> Option<(PlayerId, u8)>;
 
+    /// Gives the longest road title without checking the roads, used when restoring a state
+    fn set_longest_road(&mut self, player: Option<PlayerId>);
+
     /// Resets and recomputes the longest road of a player
-    /// This operation can potentially be expensive since all the possible paths have to be enumerated
-    /// It's better to call it only when a player's road has been broken
-    /// In most situations the only difference is that a new road piece has been placed. In this case calling [update_longest_road] is more efficient
+    /// Only the road components of the player interrupted by a building since are explored again, see [RoadNetwork]
+    /// It's meant to be called when a player's road has been broken
+    /// When a new road piece has been placed, [update_longest_road] only looks at the component of this road
     fn reset_longest_road(&mut self, player: PlayerId);
 
     /// Updates a player's longest continous road using a new path
     /// Tries to find the longest road passing through "root_path", and updates the longest continous road of the player if this path is longer
-    /// Doesn't look at potential long path not using this road
+    /// Doesn't look at potential long path not connected to this road
     /// This function is useful to be called when a new road has been placed, as the new longest road can either be the previous longest road, or a new long road using this new road piece
-    /// In more complicated situation, it's better to call [reset_longest_road], but it is more expensive
+    /// When a road has been broken, call [reset_longest_road] instead
     fn update_longest_road(&mut self, player: PlayerId, root_path: Coord);
 
     fn get_largest_army(&self) -> Option<(PlayerId, u8)>;
 
     fn update_largest_army(&mut self, player: PlayerId);
 
+    /// Gives the largest army title without checking the knights, used when restoring a state
+    fn set_largest_army(&mut self, player: Option<PlayerId>);
+
     // Static Board
     fn set_static_hex(&mut self, coord: Coord, hex: Hex) -> Result<(), Error>;
 
@@ -131,6 +205,18 @@ pub trait StateTrait {
 
     fn get_static_harbor(&self, coord: Coord) -> Result<Harbor, Error>;
 
+    /// Swaps the number tokens of two producing hexes, their resources staying in place
+    fn swap_number_tokens(&mut self, a: Coord, b: Coord) -> Result<(), Error> {
+        match (self.get_static_hex(a)?, self.get_static_hex(b)?) {
+            (Hex::Land(LandHex::Prod(res_a, number_a)), Hex::Land(LandHex::Prod(res_b, number_b))) => {
+                self.set_static_hex(a, Hex::Land(LandHex::Prod(res_a, number_b)))?;
+                self.set_static_hex(b, Hex::Land(LandHex::Prod(res_b, number_a)))
+            }
+            (Hex::Land(LandHex::Prod(_, _)), _) => Err(Error::InvalidCoord(b)),
+            _ => Err(Error::InvalidCoord(a)),
+        }
+    }
+
     // Dynamic Board
     fn set_dynamic_path(&mut self, coord: Coord, player: PlayerId) -> Result<(), Error>;
 
@@ -140,5 +226,16 @@ pub trait StateTrait {
 
     fn get_dynamic_intersection(&self, coord: Coord) -> Result<Option<(PlayerId, bool)>, Error>;
 
+    /// Takes the roads, settlements and cities of a player off the board and out of the building history,
+    /// see [ResignedPieces::Removed](crate::game::ResignedPieces::Removed)
+    /// The longest roads aren't computed again, the roads of the others joined by the freed intersections needing a [reset_longest_road]
+    fn remove_pieces(&mut self, player: PlayerId);
+
+    /// Every settlement and city placed so far, stamped with the turn they were placed in
+    fn get_building_history(&self) -> &BuildingHistory;
+
+    /// Copy of the state, cheaper than going through a [StateSnapshot] to push and pop the positions of a tree search
+    fn clone_state(&self) -> State;
+
     fn as_any(&self) -> &dyn Any;
 }
@@ -0,0 +1,85 @@
+use super::{PlayerId, TransferKind, TradeAnswer, VisibleState};
+
+/// Behavior of a player so far in the game, as every other player saw it
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct OpponentProfile {
+    /// Trade offers the player answered, counter offers included
+    pub offers_answered: u32,
+    pub offers_accepted: u32,
+    /// Cards the player stole with the thief
+    pub steals: u32,
+    /// Cards the player stole from the observer
+    pub steals_from_observer: u32,
+    /// Buildings and development cards the player paid for
+    pub purchases: u32,
+    /// Turn of the last purchase
+    pub last_purchase: Option<u32>,
+}
+
+impl OpponentProfile {
+    /// Share of the offers answered which were accepted, 0 without any answer
+    pub fn acceptance_rate(&self) -> f32 {
+        if self.offers_answered == 0 { 0.0 } else { self.offers_accepted as f32 / self.offers_answered as f32 }
+    }
+
+    /// Share of the steals made against the observer, 0 without any steal
+    pub fn observer_targeting(&self) -> f32 {
+        if self.steals == 0 { 0.0 } else { self.steals_from_observer as f32 / self.steals as f32 }
+    }
+
+    /// Purchases per turn of the game, up to the turn `turn`
+    pub fn tempo(&self, turn: u32) -> f32 {
+        if turn == 0 { 0.0 } else { self.purchases as f32 / turn as f32 }
+    }
+}
+
+/// What a player knows of the habits of every player from the history of the game
+///
+/// The profiles are computed from the public part of the ledger only: the answers to trade offers,
+/// who stole from whom, whatever the card, and the purchases.
+#[derive(Clone, PartialEq, Debug)]
+pub struct OpponentModel {
+    observer: PlayerId,
+    profiles: Vec<OpponentProfile>,
+}
+
+impl OpponentModel {
+    /// Profiles of the players from the whole ledger of the view
+    pub fn from_view(view: &VisibleState) -> OpponentModel {
+        let observer = view.player();
+        let mut profiles = vec![OpponentProfile::default(); view.player_count() as usize];
+        for response in view.trade_responses() {
+            let profile = &mut profiles[response.responder.to_usize()];
+            profile.offers_answered += 1;
+            if response.answer == TradeAnswer::Accepted {
+                profile.offers_accepted += 1;
+            }
+        }
+        for transfer in view.seen_transfers(0) {
+            match (transfer.kind, transfer.to.option(), transfer.from.option()) {
+                (TransferKind::Steal, Some(thief), Some(victim)) => {
+                    let profile = &mut profiles[thief.to_usize()];
+                    profile.steals += 1;
+                    if victim == observer {
+                        profile.steals_from_observer += 1;
+                    }
+                }
+                (TransferKind::Purchase, _, Some(buyer)) => {
+                    let profile = &mut profiles[buyer.to_usize()];
+                    profile.purchases += 1;
+                    profile.last_purchase = Some(transfer.turn);
+                }
+                _ => (),
+            }
+        }
+        OpponentModel { observer, profiles }
+    }
+
+    pub fn observer(&self) -> PlayerId {
+        self.observer
+    }
+
+    pub fn profile(&self, player: PlayerId) -> &OpponentProfile {
+        &self.profiles[player.to_usize()]
+    }
+}
@@ -1,12 +1,13 @@
 use std::ops::{Index, IndexMut};
-use crate::utils::{Resource, Resources, Harbor, DevelopmentCards};
+use serde::{Serialize, Deserialize};
+use crate::utils::{Coord, Resource, Resources, Harbor, DevelopmentCards};
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct AccessibleHarbor {
     harbors: [bool; Harbor::COUNT],
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct PlayerHand {
     pub resources: Resources,
     pub road_pieces: u8,
@@ -17,6 +18,15 @@ pub struct PlayerHand {
     pub continous_road: u8,
     pub development_cards: DevelopmentCards,
     pub new_development_cards: DevelopmentCards,
+    /// Development cards played so far in the game, checked against the [caps](crate::game::DevelopmentCaps) of the rules
+    #[serde(default)]
+    pub played_development_cards: DevelopmentCards,
+    /// Settlement declared for a city upgrade and the turn it was declared on, see [Rules::city_reservation](crate::game::Rules::city_reservation)
+    #[serde(default)]
+    pub reserved_city: Option<(Coord, u32)>,
+    /// The player left the game, which goes on among the others, see [Action::Resign](crate::game::Action::Resign)
+    #[serde(default)]
+    pub resigned: bool,
     pub harbor: AccessibleHarbor,
 }
 
@@ -28,13 +38,31 @@ impl AccessibleHarbor {
     }
 
     pub fn rate(&self, resource: Resource) -> u8 {
-        let mut required = 4;
+        match self.used_for(resource) {
+            Harbor::Special(_) => 2,
+            Harbor::Generic => 3,
+            Harbor::None => 4,
+        }
+    }
+
+    /// Harbor giving the best rate for the resource, [Harbor::None] when it is traded at 4 for 1
+    pub fn used_for(&self, resource: Resource) -> Harbor {
         if self[Harbor::Special(resource)] {
-            required = 2;
+            Harbor::Special(resource)
         } else if self[Harbor::Generic] {
-            required = 3;
+            Harbor::Generic
+        } else {
+            Harbor::None
         }
-        required
+    }
+
+    /// Resources paid for the given number of trades with each resource
+    pub fn bundle_cost(&self, trades: Resources) -> Resources {
+        let mut cost = Resources::ZERO;
+        for resource in Resource::ALL.iter() {
+            cost[*resource] = trades[*resource] * self.rate(*resource) as i8;
+        }
+        cost
     }
 
     pub fn add(&mut self, harbor: Harbor) {
@@ -46,6 +74,12 @@ impl AccessibleHarbor {
     }
 }
 
+impl Default for AccessibleHarbor {
+    fn default() -> Self {
+        AccessibleHarbor::new()
+    }
+}
+
 impl Index<Harbor> for AccessibleHarbor {
     type Output = bool;
 
@@ -86,6 +120,9 @@ impl PlayerHand {
             continous_road: 0,
             development_cards: DevelopmentCards::new(),
             new_development_cards: DevelopmentCards::new(),
+            played_development_cards: DevelopmentCards::new(),
+            reserved_city: None,
+            resigned: false,
             harbor: AccessibleHarbor::new(),
         }
     }
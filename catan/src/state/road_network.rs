@@ -0,0 +1,139 @@
+use crate::utils::Coord;
+use crate::board::utils::topology::Topology;
+use super::PlayerId;
+
+/// Roads of a player connected to each other, with their longest continuous road
+///
+/// The longest road is [None] once the component changed, and computed again when asked for.
+#[derive(Clone)]
+struct RoadComponent {
+    player: PlayerId,
+    /// Roads, with the two intersections they link
+    roads: Vec<(Coord, [Coord; 2])>,
+    longest: Option<u8>,
+}
+
+impl RoadComponent {
+    fn touches(&self, intersection: Coord) -> usize {
+        self.roads.iter().filter(|(_, ends)| ends.contains(&intersection)).count()
+    }
+
+    fn longest<F: Fn(PlayerId, Coord) -> bool>(&mut self, blocked: &F) -> u8 {
+        if let Some(longest) = self.longest {
+            return longest;
+        }
+        let mut used = vec![false; self.roads.len()];
+        let mut longest = 0;
+        for (_, ends) in self.roads.iter() {
+            for end in ends.iter() {
+                longest = longest.max(self.longest_from(*end, &mut used, blocked));
+            }
+        }
+        self.longest = Some(longest);
+        longest
+    }
+
+    /// Longest road starting at the intersection without going through used roads
+    fn longest_from<F: Fn(PlayerId, Coord) -> bool>(&self, intersection: Coord, used: &mut [bool], blocked: &F) -> u8 {
+        let mut longest = 0;
+        for (i, (_, ends)) in self.roads.iter().enumerate() {
+            if used[i] || !ends.contains(&intersection) {
+                continue;
+            }
+            let next = if ends[0] == intersection { ends[1] } else { ends[0] };
+            used[i] = true;
+            // Roads are interrupted by the buildings of other players
+            let length = 1 + if blocked(self.player, next) { 0 } else { self.longest_from(next, used, blocked) };
+            used[i] = false;
+            longest = longest.max(length);
+        }
+        longest
+    }
+}
+
+/// Roads of every player, grouped in connected components caching their longest roads
+///
+/// Placing a road merges the components it links, and a building splits the components of the other players going through it,
+/// so only the components changed have their longest roads computed again.
+/// `blocked(player, intersection)` tells if a building of another player stands on the intersection.
+#[derive(Clone, Default)]
+pub struct RoadNetwork {
+    components: Vec<RoadComponent>,
+}
+
+impl RoadNetwork {
+    pub fn new() -> RoadNetwork {
+        RoadNetwork::default()
+    }
+
+    fn component_of(&self, path: Coord) -> Option<usize> {
+        self.components.iter().position(|component| component.roads.iter().any(|(road, _)| *road == path))
+    }
+
+    /// Adds a road, merging it with the roads of the player it is connected to
+    pub fn add<F: Fn(PlayerId, Coord) -> bool>(&mut self, player: PlayerId, path: Coord, blocked: &F) {
+        if self.component_of(path).is_some() {
+            return;
+        }
+        let intersections = Coord::TOPOLOGY.path_intersection_neighbours(path).expect("Wrong path");
+        let ends = [intersections[0], intersections[1]];
+        let mut merged = RoadComponent { player, roads: vec![(path, ends)], longest: None };
+        let mut i = 0;
+        while i < self.components.len() {
+            let component = &self.components[i];
+            let connected = component.player == player && ends.iter()
+                .any(|end| !blocked(player, *end) && component.touches(*end) > 0);
+            if connected {
+                merged.roads.append(&mut self.components.swap_remove(i).roads);
+            } else {
+                i += 1;
+            }
+        }
+        self.components.push(merged);
+    }
+
+    /// Splits the roads of the other players going through an intersection where a building was placed
+    pub fn interrupt<F: Fn(PlayerId, Coord) -> bool>(&mut self, intersection: Coord, blocked: &F) {
+        let mut i = 0;
+        while i < self.components.len() {
+            let component = &self.components[i];
+            // A single road ending on the building doesn't change
+            if component.touches(intersection) < 2 || !blocked(component.player, intersection) {
+                i += 1;
+                continue;
+            }
+            let RoadComponent { player, mut roads, .. } = self.components.swap_remove(i);
+            while let Some(road) = roads.pop() {
+                let mut split = RoadComponent { player, roads: vec![road], longest: None };
+                let mut next = 0;
+                while next < split.roads.len() {
+                    let ends = split.roads[next].1;
+                    let (connected, rest): (Vec<_>, Vec<_>) = roads.into_iter()
+                        .partition(|(_, other)| ends.iter().any(|end| other.contains(end) && !blocked(player, *end)));
+                    roads = rest;
+                    split.roads.extend(connected);
+                    next += 1;
+                }
+                self.components.insert(i, split);
+                i += 1;
+            }
+        }
+    }
+
+    /// Longest road going through the path
+    pub fn longest_through<F: Fn(PlayerId, Coord) -> bool>(&mut self, path: Coord, blocked: &F) -> u8 {
+        match self.component_of(path) {
+            Some(i) => self.components[i].longest(blocked),
+            None => 0,
+        }
+    }
+
+    /// Longest road of the player
+    pub fn longest<F: Fn(PlayerId, Coord) -> bool>(&mut self, player: PlayerId, blocked: &F) -> u8 {
+        self.components.iter_mut()
+            .filter(|component| component.player == player)
+            .map(|component| component.longest(blocked))
+            .max()
+            .unwrap_or(0)
+    }
+}
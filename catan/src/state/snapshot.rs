@@ -0,0 +1,111 @@
+use serde::{Serialize, Deserialize};
+
+use crate::board::{Layout, Error};
+use crate::utils::{Coord, Hex, Harbor, DevelopmentCards, Resources};
+use crate::game::Rules;
+use super::{State, StateMaker, PlayerId, PlayerHand, Ledger, Placement};
+
+/// Everything held by a state, independently of how the state stores it
+///
+/// A snapshot is taken through the [StateTrait](super::StateTrait) getters and restored through its setters,
+/// so it can be restored into any kind of state built on the same layout.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub hexes: Vec<(Coord, Hex)>,
+    pub harbors: Vec<(Coord, Harbor)>,
+    pub thief: Coord,
+    pub roads: Vec<(Coord, PlayerId)>,
+    /// Settlements and cities, in the order they were placed
+    pub placements: Vec<Placement>,
+    pub players: Vec<PlayerHand>,
+    pub bank: Resources,
+    pub development_cards: DevelopmentCards,
+    pub longest_road: Option<PlayerId>,
+    pub largest_army: Option<PlayerId>,
+    pub discards: Vec<(PlayerId, Option<Resources>)>,
+    pub rules: Rules,
+    pub turn: u32,
+    pub ledger: Ledger,
+}
+
+impl StateSnapshot {
+    pub fn new(state: &State) -> StateSnapshot {
+        let layout = state.get_layout();
+        StateSnapshot {
+            hexes: layout.hexes.iter()
+                .map(|hex| (*hex, state.get_static_hex(*hex).unwrap()))
+                .collect(),
+            harbors: layout.intersections.iter()
+                .map(|intersection| (*intersection, state.get_static_harbor(*intersection).unwrap()))
+                .filter(|(_, harbor)| *harbor != Harbor::None)
+                .collect(),
+            thief: state.get_thief_hex(),
+            roads: layout.paths.iter()
+                .filter_map(|path| state.get_dynamic_path(*path).unwrap().map(|player| (*path, player)))
+                .collect(),
+            placements: state.get_building_history().placements().clone(),
+            players: (0..state.player_count()).map(|p| *state.get_player_hand(PlayerId::from(p))).collect(),
+            bank: state.get_bank_resources(),
+            development_cards: state.get_development_cards(),
+            longest_road: state.get_longest_road().map(|(player, _)| player),
+            largest_army: state.get_largest_army().map(|(player, _)| player),
+            discards: state.peek_discards().clone(),
+            rules: state.get_rules(),
+            turn: state.get_turn(),
+            ledger: state.get_ledger().clone(),
+        }
+    }
+
+    /// Hash of the snapshot, the same across runs and platforms, to tell states apart
+    pub fn digest(&self) -> u64 {
+        fnv(&bincode::serialize(self).expect("Failed serializing state"))
+    }
+
+    /// Hash of the hexes and harbors of the snapshot, telling boards apart whatever is played on them
+    pub fn board_digest(&self) -> u64 {
+        fnv(&bincode::serialize(&(&self.hexes, &self.harbors)).expect("Failed serializing board"))
+    }
+
+    /// Restores the snapshot into a new empty state of the layout
+    pub fn restore<S: StateMaker>(&self, layout: &'static Layout) -> Result<State, Error> {
+        let mut state = S::new_empty(layout, self.players.len() as u8);
+        self.restore_into(&mut state)?;
+        Ok(state)
+    }
+
+    /// Restores the snapshot into an empty state, which must have as many players
+    pub fn restore_into(&self, state: &mut State) -> Result<(), Error> {
+        state.set_rules(self.rules);
+        for (coord, hex) in self.hexes.iter() {
+            state.set_static_hex(*coord, *hex)?;
+        }
+        for (coord, harbor) in self.harbors.iter() {
+            state.set_static_harbor(*coord, *harbor)?;
+        }
+        state.set_thief_hex(self.thief);
+        for (coord, player) in self.roads.iter() {
+            state.set_dynamic_path(*coord, *player)?;
+        }
+        // Buildings are placed again in order, rebuilding the building history
+        for placement in self.placements.iter() {
+            state.set_turn(placement.turn);
+            state.set_dynamic_intersection(placement.intersection, placement.player, placement.is_city)?;
+        }
+        for (p, hand) in self.players.iter().enumerate() {
+            *state.get_player_hand_mut(PlayerId::from(p)) = *hand;
+        }
+        *state.get_bank_resources_mut() = self.bank;
+        *state.get_development_cards_mut() = self.development_cards;
+        state.set_longest_road(self.longest_road);
+        state.set_largest_army(self.largest_army);
+        state.hold_discards(self.discards.clone());
+        state.set_turn(self.turn);
+        *state.get_ledger_mut() = self.ledger.clone();
+        Ok(())
+    }
+}
+
+/// FNV-1a hash of the bytes
+fn fnv(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
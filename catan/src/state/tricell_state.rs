@@ -3,10 +3,11 @@ use std::any::Any;
 use crate::board::map::TricellMap;
 use crate::board::{Layout, Error};
 use crate::utils::{Empty, Hex, Harbor, Coord, DevelopmentCards, Resources};
-use crate::board::utils::topology::Topology;
-use super::PlayerHand;
+use crate::game::Rules;
+use super::{PlayerHand, Ledger, BuildingHistory, Placement, RoadNetwork};
 use super::{State, StateTrait, StateMaker, PlayerId};
 
+#[derive(Clone)]
 pub struct TricellState {
     layout: &'static Layout,
     static_board: Box<TricellMap<Hex,Empty,Harbor>>,
@@ -18,6 +19,11 @@ pub struct TricellState {
     discards: Vec<(PlayerId,Option<Resources>)>,
     players: Vec<PlayerHand>,
     bank_resources: Resources,
+    rules: Rules,
+    turn: u32,
+    ledger: Ledger,
+    building_history: BuildingHistory,
+    roads: RoadNetwork,
 }
 
 impl TricellState {
@@ -33,96 +39,21 @@ impl TricellState {
             discards: Vec::new(),
             players: vec![PlayerHand::new();players],
             bank_resources: Resources::STARTING_BANK,
+            rules: Rules::STANDARD,
+            turn: 0,
+            ledger: Ledger::new(),
+            building_history: BuildingHistory::new(),
+            roads: RoadNetwork::new(),
         }
     }
 
-    /// Returns a (path, next_intersection) vector with the potential next paths
-    /// No path is returned if 'intersection' is an enemy settlement or city
-    /// The returned paths are connected to 'intersection' and aren't any path from 'used_paths'
-    fn next_chain_paths(&self, player: PlayerId, used_paths: &Vec<Coord>, intersection: Coord) -> Vec<(Coord, Coord)> {
-        // If another player occupies the intersection, interupt pathchain
-        if let Some((p, _)) = self.get_dynamic_intersection(intersection).unwrap() {
-            if player != p {
-                return Vec::new();
-            }
-        }
-        let mut paths = Vec::new();
-        // Check every paths connected to the current intersection
-        for path in self.intersection_path_neighbours(intersection).unwrap() {
-            // If the path has a player's road placed on it...
-            if let Some(p) = self.get_dynamic_path(path).unwrap() {
-                // ...and this player is the correct one...
-                // ...and the road hasn't been used yet...
-                if player == p && !used_paths.contains(&path) {
-                    // ...add this road as a potential path:
-                    // finds the intersection that is connected to the path that isn't the current_intersection
-                    for next_intersection in self.path_intersection_neighbours(path).unwrap() {
-                        if next_intersection != intersection {
-                            paths.push((path, next_intersection));
-                        }
-                    }
-                }
-            }
-        }
-        paths
-    }
-
-    /// Recursive function that returns the longest chain that has 'chain' as a sub-chain
-    fn longest_chain(&self, player: PlayerId, mut chain: PathChain) -> usize {
-        if let Some(head_intersection) = chain.head {
-            let nexts = self.next_chain_paths(player, &chain.paths, head_intersection);
-            if nexts.len() == 0 {
-                chain.head = None
-            } else {
-                let mut length = 0;
-                for (next_path, next_intersection) in nexts {
-                    let mut paths = chain.paths.clone();
-                    paths.push(next_path);
-                    let r = self.longest_chain(player,
-                        PathChain {
-                            paths,
-                            head: Some(next_intersection),
-                            tail: chain.tail,
-                        }
-                    );
-                    if r > length {
-                        length = r;
-                    }
-                }
-                return length;
-            }
-        }
-        if let Some(tail_intersection) = chain.tail {
-            let nexts = self.next_chain_paths(player, &chain.paths, tail_intersection);
-            if nexts.len() > 0 {
-                let mut length = 0;
-                for (next_path, next_intersection) in nexts {
-                    let mut paths = chain.paths.clone();
-                    paths.push(next_path);
-                    let r = self.longest_chain(player,
-                        PathChain {
-                            paths,
-                            head: chain.head,
-                            tail: Some(next_intersection),
-                        }
-                    );
-                    if r > length {
-                        length = r;
-                    }
-                }
-                return length;
-            }
-        }
-        chain.paths.len()
+    /// Tells if a building of another player than `player` stands on the intersection, interrupting its roads
+    fn blocked(board: &TricellMap<Empty,PlayerId,(PlayerId,bool)>, player: PlayerId, intersection: Coord) -> bool {
+        let (p, _) = board.get_intersection(intersection).unwrap();
+        p != PlayerId::NONE && p != player
     }
 }
 
-struct PathChain {
-    paths: Vec<Coord>,
-    head: Option<Coord>,
-    tail: Option<Coord>,
-}
-
 impl StateMaker for TricellState {
     fn new_empty(layout: &'static Layout, player_count: u8) -> State {
         Box::new(TricellState::new(layout, player_count as usize))
@@ -130,7 +61,7 @@ impl StateMaker for TricellState {
 }
 
 impl StateTrait for TricellState {
-    fn get_layout(&self) -> &Layout {
+    fn get_layout(&self) -> &'static Layout {
         self.layout
     }
 
@@ -190,6 +121,34 @@ impl StateTrait for TricellState {
         }
     }
 
+    fn get_rules(&self) -> Rules {
+        self.rules
+    }
+
+    fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    fn get_turn(&self) -> u32 {
+        self.turn
+    }
+
+    fn next_turn(&mut self) {
+        self.turn += 1;
+    }
+
+    fn set_turn(&mut self, turn: u32) {
+        self.turn = turn;
+    }
+
+    fn get_ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    fn get_ledger_mut(&mut self) -> &mut Ledger {
+        &mut self.ledger
+    }
+
     // --- player related --- //
 
     fn get_player_hand(&self, player: PlayerId) -> &PlayerHand {
@@ -208,15 +167,30 @@ impl StateTrait for TricellState {
         }
     }
 
-    // TODO: Try to optimise this function a little more
-    // Some paths are explored about number_of_roads to many times
+    fn set_longest_road(&mut self, player: Option<PlayerId>) {
+        self.longest_road = player.unwrap_or(PlayerId::NONE);
+    }
+
     fn reset_longest_road(&mut self, player: PlayerId) {
-        let paths = self.get_layout().paths.clone();
-        for path in paths {
-            if let Some(p) = self.get_dynamic_path(path).unwrap() {
-                if player == p {
-                    self.update_longest_road(player, path)
-                }
+        let board = &self.dynamic_board;
+        let length = self.roads.longest(player, &|p, intersection| TricellState::blocked(board, p, intersection));
+        self.get_player_hand_mut(player).continous_road = length;
+        // A broken road keeps the title as long as it is at least tied for the longest
+        if self.longest_road == player {
+            let length = self.get_player_hand(player).continous_road;
+            let best = self.players.iter().enumerate()
+                .filter(|(i, hand)| *i != player.to_usize() && !hand.resigned)
+                .map(|(_, hand)| hand.continous_road)
+                .max()
+                .unwrap_or(0);
+            if length < 5 || length < best {
+                let mut holders = self.players.iter().enumerate()
+                    .filter(|(i, hand)| *i != player.to_usize() && !hand.resigned && hand.continous_road == best && best >= 5);
+                // Nobody takes the title when several players are tied
+                self.longest_road = match (holders.next(), holders.next()) {
+                    (Some((i, _)), None) => PlayerId::from(i),
+                    _ => PlayerId::NONE,
+                };
             }
         }
     }
@@ -224,14 +198,8 @@ impl StateTrait for TricellState {
     fn update_longest_road(&mut self, player: PlayerId, root_path: Coord) {
         let old_length = self.get_player_hand(player).continous_road;
 
-        let intersections = self.path_intersection_neighbours(root_path).unwrap();
-        let new_length = self.longest_chain(player,
-            PathChain {
-                paths: vec![root_path],
-                head: Some(intersections[0]),
-                tail: Some(intersections[1]),
-            }
-        ) as u8;
+        let board = &self.dynamic_board;
+        let new_length = self.roads.longest_through(root_path, &|p, intersection| TricellState::blocked(board, p, intersection));
 
         if new_length > old_length {
             self.get_player_hand_mut(player).continous_road = new_length;
@@ -240,7 +208,7 @@ impl StateTrait for TricellState {
             return;
         }
         for (i, hand) in self.players.iter().enumerate() {
-            if i != player.to_usize() && new_length <= hand.continous_road {
+            if i != player.to_usize() && !hand.resigned && new_length <= hand.continous_road {
                 return;
             }
         }
@@ -261,13 +229,17 @@ impl StateTrait for TricellState {
             return;
         }
         for (i, hand) in self.players.iter().enumerate() {
-            if i != player.to_usize() && size <= hand.knights {
+            if i != player.to_usize() && !hand.resigned && size <= hand.knights {
                 return;
             }
         }
         self.largest_army = player;
     }
 
+    fn set_largest_army(&mut self, player: Option<PlayerId>) {
+        self.largest_army = player.unwrap_or(PlayerId::NONE);
+    }
+
     // --- static board --- //
 
     fn set_static_hex(&mut self, coord: Coord, hex: Hex) -> Result<(), Error>{
@@ -289,7 +261,10 @@ impl StateTrait for TricellState {
     // --- dynamic board --- //
 
     fn set_dynamic_path(&mut self, coord: Coord, player: PlayerId) -> Result<(), Error>{
-        Ok(self.dynamic_board.set_path(coord, player)?)
+        self.dynamic_board.set_path(coord, player)?;
+        let board = &self.dynamic_board;
+        self.roads.add(player, coord, &|p, intersection| TricellState::blocked(board, p, intersection));
+        Ok(())
     }
 
     fn get_dynamic_path(&self, coord: Coord) -> Result<Option<PlayerId>, Error>{
@@ -298,7 +273,11 @@ impl StateTrait for TricellState {
     }
 
     fn set_dynamic_intersection(&mut self, coord: Coord, player: PlayerId, is_city: bool) -> Result<(), Error>{
-        Ok(self.dynamic_board.set_intersection(coord, (player, is_city))?)
+        self.dynamic_board.set_intersection(coord, (player, is_city))?;
+        let board = &self.dynamic_board;
+        self.roads.interrupt(coord, &|p, intersection| TricellState::blocked(board, p, intersection));
+        self.building_history.record(Placement { turn: self.turn, intersection: coord, player, is_city });
+        Ok(())
     }
 
     fn get_dynamic_intersection(&self, coord: Coord) -> Result<Option<(PlayerId, bool)>, Error>{
@@ -310,6 +289,38 @@ impl StateTrait for TricellState {
         }
     }
 
+    fn remove_pieces(&mut self, player: PlayerId) {
+        let layout = self.layout;
+        for path in layout.paths.iter() {
+            if self.dynamic_board.get_path(*path).unwrap() == player {
+                self.dynamic_board.set_path(*path, PlayerId::NONE).unwrap();
+            }
+        }
+        for intersection in layout.intersections.iter() {
+            if self.dynamic_board.get_intersection(*intersection).unwrap().0 == player {
+                self.dynamic_board.set_intersection(*intersection, (PlayerId::NONE, false)).unwrap();
+            }
+        }
+        self.building_history.remove_player(player);
+        // The freed intersections may join roads of the others, so the network is built again
+        self.roads = RoadNetwork::new();
+        let board = &self.dynamic_board;
+        for path in layout.paths.iter() {
+            let owner = board.get_path(*path).unwrap();
+            if owner != PlayerId::NONE {
+                self.roads.add(owner, *path, &|p, intersection| TricellState::blocked(board, p, intersection));
+            }
+        }
+    }
+
+    fn get_building_history(&self) -> &BuildingHistory {
+        &self.building_history
+    }
+
+    fn clone_state(&self) -> State {
+        Box::new(self.clone())
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -0,0 +1,171 @@
+use serde::{Serialize, Deserialize};
+
+use crate::utils::{Hex, Harbor, Coord, Resources, DevelopmentCards};
+use crate::board::{Layout, Error};
+use crate::game::Rules;
+use super::{StateTrait, PlayerId, PlayerHand, BuildingHistory, TransferKind, SeenTransfer, TradeResponse};
+
+/// What every player sees of a hand
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PublicHand {
+    pub resource_count: u8,
+    pub road_pieces: u8,
+    pub settlement_pieces: u8,
+    pub city_pieces: u8,
+    pub knights: u8,
+    /// Development cards held, played ones excluded
+    pub development_card_count: u8,
+    /// Development cards played so far, each one being shown when played
+    pub played_development_cards: DevelopmentCards,
+    pub public_vp: u8,
+    pub has_longest_road: bool,
+    pub has_largest_army: bool,
+    /// The player left the game, its pieces staying or being removed after the [rules](crate::game::Rules::resigned_pieces)
+    pub resigned: bool,
+}
+
+/// The part of a state a player may legally see
+///
+/// Observations of a player should only be built from its view: the other players' resources and development cards,
+/// and the content of the development deck, are only given as counts, unless the rules open the hands.
+pub struct VisibleState<'a> {
+    state: &'a dyn StateTrait,
+    player: PlayerId,
+}
+
+impl dyn StateTrait + '_ {
+    /// View of the state by a player
+    pub fn view_for(&self, player: PlayerId) -> VisibleState<'_> {
+        VisibleState { state: self, player }
+    }
+}
+
+impl<'a> VisibleState<'a> {
+    /// Player whose view it is
+    pub fn player(&self) -> PlayerId {
+        self.player
+    }
+
+    pub fn get_layout(&self) -> &Layout {
+        self.state.get_layout()
+    }
+
+    pub fn player_count(&self) -> u8 {
+        self.state.player_count()
+    }
+
+    pub fn get_rules(&self) -> Rules {
+        self.state.get_rules()
+    }
+
+    pub fn get_turn(&self) -> u32 {
+        self.state.get_turn()
+    }
+
+    pub fn get_bank_resources(&self) -> Resources {
+        self.state.get_bank_resources()
+    }
+
+    /// Number of cards left in the development deck, only known by buckets when the [rules](crate::game::Rules::bucketed_deck) say so
+    pub fn development_deck_size(&self) -> u8 {
+        self.get_rules().observed_deck_size(self.state.get_development_cards().total())
+    }
+
+    pub fn get_thief_hex(&self) -> Coord {
+        self.state.get_thief_hex()
+    }
+
+    pub fn get_longest_road(&self) -> Option<(PlayerId, u8)> {
+        self.state.get_longest_road()
+    }
+
+    pub fn get_largest_army(&self) -> Option<(PlayerId, u8)> {
+        self.state.get_largest_army()
+    }
+
+    /// Number of transfers in the ledger
+    pub fn transfer_count(&self) -> usize {
+        self.state.get_ledger().transfers().len()
+    }
+
+    /// Transfers of the ledger from the index `start`, the card stolen between two other players being unseen when the rules hide the hands
+    pub fn seen_transfers(&self, start: usize) -> impl Iterator<Item = SeenTransfer> + '_ {
+        let hidden_hands = self.get_rules().hidden_hands;
+        self.state.get_ledger().transfers()[start.min(self.transfer_count())..].iter().map(move |transfer| {
+            let unseen = hidden_hands && transfer.kind == TransferKind::Steal && transfer.from != self.player && transfer.to != self.player;
+            SeenTransfer {
+                turn: transfer.turn,
+                kind: transfer.kind,
+                from: transfer.from,
+                to: transfer.to,
+                resources: if unseen { None } else { Some(transfer.resources) },
+            }
+        })
+    }
+
+    /// Answers to the trade offers, every one of them being public
+    pub fn trade_responses(&self) -> &[TradeResponse] {
+        self.state.get_ledger().trade_responses()
+    }
+
+    // ## Board
+
+    pub fn get_static_hex(&self, coord: Coord) -> Result<Hex, Error> {
+        self.state.get_static_hex(coord)
+    }
+
+    pub fn get_static_harbor(&self, coord: Coord) -> Result<Harbor, Error> {
+        self.state.get_static_harbor(coord)
+    }
+
+    pub fn get_dynamic_path(&self, coord: Coord) -> Result<Option<PlayerId>, Error> {
+        self.state.get_dynamic_path(coord)
+    }
+
+    pub fn get_dynamic_intersection(&self, coord: Coord) -> Result<Option<(PlayerId, bool)>, Error> {
+        self.state.get_dynamic_intersection(coord)
+    }
+
+    pub fn get_building_history(&self) -> &BuildingHistory {
+        self.state.get_building_history()
+    }
+
+    // ## Hands
+
+    /// Full hand of the player
+    pub fn hand(&self) -> &PlayerHand {
+        self.state.get_player_hand(self.player)
+    }
+
+    /// Resources the player picked so far to discard card by card, and the count of cards it still has to discard
+    pub fn pending_discard(&self) -> Option<(Resources, u8)> {
+        self.state.get_pending_discard(self.player)
+    }
+
+    /// Full hand of another player, only seen when the rules open the hands
+    pub fn revealed_hand(&self, player: PlayerId) -> Option<&PlayerHand> {
+        if player == self.player || !self.get_rules().hidden_hands {
+            Some(self.state.get_player_hand(player))
+        } else {
+            None
+        }
+    }
+
+    /// Publicly known part of a player's hand
+    pub fn public_hand(&self, player: PlayerId) -> PublicHand {
+        let hand = self.state.get_player_hand(player);
+        PublicHand {
+            resource_count: hand.resources.total() as u8,
+            road_pieces: hand.road_pieces,
+            settlement_pieces: hand.settlement_pieces,
+            city_pieces: hand.city_pieces,
+            knights: hand.knights,
+            development_card_count: hand.development_cards.total() + hand.new_development_cards.total(),
+            played_development_cards: hand.played_development_cards,
+            public_vp: self.state.get_player_public_vp(player),
+            has_longest_road: self.get_longest_road().map(|(p, _)| p) == Some(player),
+            has_largest_army: self.get_largest_army().map(|(p, _)| p) == Some(player),
+            resigned: hand.resigned,
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Exact values of the positions of simplified games, to check learned value functions against
+//!
+//! A [Tablebase] is built from a position by solving every position reachable from it until a last turn,
+//! each player picking the action giving it the best chance to win, and the dice being averaged over.
+//! Only rules leaving no other chance than the dice and no trading between players can be solved:
+//! no robber, no development cards, no domestic trading and no swapping number tokens.
+//! Games of a few victory points on tiny boards, or the last turns of a game, keep the tablebase small enough.
+
+use std::collections::HashMap;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use crate::game::{Action, Phase, Rules, legal, apply, roll_dice};
+use crate::player::generate_possible_actions;
+use crate::state::{State, StateSnapshot, PlayerId, TransferKind};
+use crate::utils::{Resource, Resources};
+
+/// Probability of rolling each total of two dice
+pub fn roll_probability(roll: u8) -> f64 {
+    (6 - (roll as i8 - 7).abs()).max(0) as f64 / 36.0
+}
+
+/// Value of a position of a [Tablebase]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Solved {
+    /// Probability of each player to win by the last turn of the tablebase, when every player plays its best
+    pub wins: Vec<f64>,
+    /// Action the player to decide plays, the first one of the best when several are as good, [None] once the game is over
+    pub best: Option<Action>,
+}
+
+/// Solved positions of a game of simplified rules, on a board, up to a last turn
+///
+/// Players only care about their own chance to win, and nobody wins once the last turn is over.
+/// Trades giving back to the bank resources received from it during the same turn are never better than trading less,
+/// and are left out so that every turn comes to an end.
+pub struct Tablebase {
+    rules: u64,
+    board: u64,
+    last_turn: u32,
+    positions: HashMap<Vec<u8>, Solved>,
+}
+
+impl Tablebase {
+    /// Checks the rules leave no other chance than the dice and no trading between players
+    pub fn check_rules(rules: &Rules) -> Result<(), String> {
+        if rules.robber || rules.development_cards || rules.domestic_trading || rules.token_swap_every > 0 {
+            Err(format!("Rules with the robber, development cards, domestic trading or swapping tokens can't be solved exactly: {:?}", rules))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Solves the position of the phase and state, and every position reachable from it until the end of `turns` more turns
+    pub fn build(phase: &Phase, state: &State, turns: u32) -> Result<Tablebase, String> {
+        Tablebase::check_rules(&state.get_rules())?;
+        let mut tablebase = Tablebase {
+            rules: state.get_rules().fingerprint(),
+            board: StateSnapshot::new(state).board_digest(),
+            last_turn: state.get_turn() + turns,
+            positions: HashMap::new(),
+        };
+        let mut actions = Vec::new();
+        generate_possible_actions(&mut actions, phase.player(), state);
+        // Nothing is drawn at random once the dice are given
+        let mut rng = SmallRng::seed_from_u64(0);
+        tablebase.solve(phase, state, &actions, &mut rng);
+        Ok(tablebase)
+    }
+
+    /// Number of positions solved
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Turn after which nobody wins
+    pub fn last_turn(&self) -> u32 {
+        self.last_turn
+    }
+
+    /// Value of a position, [None] when it is of other rules or another board, or wasn't reached from the position the tablebase was built from
+    pub fn probe(&self, phase: &Phase, state: &State) -> Option<&Solved> {
+        if state.get_rules().fingerprint() != self.rules || StateSnapshot::new(state).board_digest() != self.board {
+            return None;
+        }
+        self.positions.get(&self.key(phase, state)?)
+    }
+
+    /// Resources the player received from the bank by trades during the current turn
+    fn traded_this_turn(player: PlayerId, state: &State) -> Resources {
+        let turn = state.get_turn();
+        state.get_ledger().transfers().iter().rev()
+            .take_while(|transfer| transfer.turn == turn)
+            .filter(|transfer| transfer.kind == TransferKind::TradeBank && transfer.to == player)
+            .fold(Resources::ZERO, |received, transfer| received + transfer.resources)
+    }
+
+    /// Everything the value of a position depends on, [None] after the last turn
+    ///
+    /// The order of the placements, the ledger and the turn itself are left out, so that transpositions share their value.
+    fn key(&self, phase: &Phase, state: &State) -> Option<Vec<u8>> {
+        let turn = state.get_turn();
+        if turn > self.last_turn {
+            return None;
+        }
+        let layout = state.get_layout();
+        let roads: Vec<Option<PlayerId>> = layout.paths.iter().map(|path| state.get_dynamic_path(*path).unwrap()).collect();
+        let buildings: Vec<Option<(PlayerId, bool)>> = layout.intersections.iter().map(|intersection| state.get_dynamic_intersection(*intersection).unwrap()).collect();
+        let hands: Vec<_> = (0..state.player_count()).map(|p| *state.get_player_hand(PlayerId::from(p))).collect();
+        let traded = Tablebase::traded_this_turn(phase.player(), state);
+        let received: Vec<bool> = Resource::ALL.iter().map(|res| traded[*res] > 0).collect();
+        let position = (phase, self.last_turn - turn, roads, buildings, hands, state.get_bank_resources(), state.get_longest_road(), state.get_largest_army(), received);
+        Some(bincode::serialize(&position).expect("Failed serializing position"))
+    }
+
+    fn solve(&mut self, phase: &Phase, state: &State, actions: &[Action], rng: &mut SmallRng) -> Vec<f64> {
+        let player_count = state.player_count() as usize;
+        let key = match self.key(phase, state) {
+            Some(key) => key,
+            None => return vec![0.0; player_count],
+        };
+        if let Some(solved) = self.positions.get(&key) {
+            return solved.wins.clone();
+        }
+        let solved = match phase {
+            Phase::FinishedGame { winner } => {
+                let mut wins = vec![0.0; player_count];
+                wins[winner.to_usize()] = 1.0;
+                Solved { wins, best: None }
+            }
+            _ => {
+                let player = phase.player();
+                let traded = Tablebase::traded_this_turn(player, state);
+                let mut best: Option<(Action, Vec<f64>)> = None;
+                for action in actions.iter() {
+                    if let Action::TradeBank { given, asked: _ } = action {
+                        if traded[*given] > 0 {
+                            continue;
+                        }
+                    }
+                    if legal::legal(phase, state, *action).is_err() {
+                        continue;
+                    }
+                    let wins = if *action == Action::RollDice {
+                        let mut wins = vec![0.0; player_count];
+                        for roll in 2..=12 {
+                            let (mut phase, mut state) = (*phase, state.clone_state());
+                            roll_dice(&mut phase, &mut state, roll);
+                            for (win, rolled) in wins.iter_mut().zip(self.solve(&phase, &state, actions, rng)) {
+                                *win += roll_probability(roll) * rolled;
+                            }
+                        }
+                        wins
+                    } else {
+                        let (mut phase, mut state) = (*phase, state.clone_state());
+                        apply(&mut phase, &mut state, *action, rng);
+                        self.solve(&phase, &state, actions, rng)
+                    };
+                    if best.as_ref().is_none_or(|(_, best)| wins[player.to_usize()] > best[player.to_usize()]) {
+                        best = Some((*action, wins));
+                    }
+                }
+                match best {
+                    Some((action, wins)) => Solved { wins, best: Some(action) },
+                    None => Solved { wins: vec![0.0; player_count], best: None },
+                }
+            }
+        };
+        let wins = solved.wins.clone();
+        self.positions.insert(key, solved);
+        wins
+    }
+}
@@ -1,6 +1,26 @@
-use crate::game::{Game, Notification};
-use crate::state::PlayerId;
-use crate::player::Randomy;
+mod scenario;
+
+use std::fs;
+use std::path::Path;
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
+use crate::game::{Game, GameConfig, Action, ActionCategory, Notification, Rules, ResignedPieces, DiscardCodec, Phase, PhaseKind, TurnPhase, DevelopmentPhase, Error, Checkpoint, ENGINE_VERSION, Lang, GameEvent, GameObserver, EventLog, CrashDump, CRASH_HISTORY, Simulator, Building, RewardConfig, RewardTracker, LogisticEstimator, TRANSITIONS, legal, apply};
+use crate::state::{State, PlayerId, PublicHand, TricellState, TransferKind, TradeAnswer, StateSnapshot, BeliefTracker, OpponentModel, audit};
+use crate::board::{setup, layout};
+use crate::board::layout::BoardLayout;
+use crate::board::utils::topology::Topology;
+use crate::player::{Randomy, Mcts, MctsConfig, SearchTree, SearchLog, Heuristic, EpsilonGreedy, CatanPlayer, PlayerInfo, generate_possible_actions};
+use crate::arena::{Arena, INITIAL_ELO};
+use crate::hall_of_fame::HallOfFame;
+use crate::tablebase::{self, Tablebase};
+use crate::metrics::{self, MetricsSink};
+use crate::replay::{Replay, Replayer, Corpus, DigestTrail, Divergence, verify, find_blunders, first_divergence};
+use crate::codec;
+use crate::notation::{Record, Outcome, parse_action, write_action};
+use crate::rules::why;
+use crate::utils::{Resource, Resources, Coord, Hex, LandHex, Harbor};
 
 #[test]
 fn play_random_game() {
@@ -12,3 +32,1808 @@ fn play_random_game() {
    let notif = game.setup_and_play();
    assert_ne!(notif, Notification::GameFinished{ winner: PlayerId::NONE });
 }
+
+#[test]
+fn ledger_matches_hands() {
+   let mut game = Game::new();
+   for _ in 0..3 {
+      game.add_player(Box::new(Randomy::new_player()));
+   }
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   game.play(&mut rng, &mut state, vec![0, 1, 2]);
+   for p in 0..3 {
+      let player = PlayerId::from(p as u8);
+      let net = state.get_ledger().net(player);
+      let hand = state.get_player_hand(player).resources;
+      for res in Resource::ALL.iter() {
+         assert_eq!(net[res.to_usize()], hand[*res] as i32, "{:?} {}", player, res);
+      }
+   }
+}
+
+#[test]
+fn simplified_rules_game() {
+   let mut game = Game::new();
+   for _ in 0..3 {
+      game.add_player(Box::new(Randomy::new_player()));
+   }
+   game.config.rules = Rules::SIMPLIFIED;
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let notif = game.play(&mut rng, &mut state, vec![0, 1, 2]);
+   assert_ne!(notif, Notification::GameFinished{ winner: PlayerId::NONE });
+   assert_eq!(state.get_development_cards().total(), 25);
+   for transfer in state.get_ledger().transfers().iter() {
+      assert_ne!(transfer.kind, TransferKind::Discard);
+      assert_ne!(transfer.kind, TransferKind::Steal);
+   }
+}
+
+#[test]
+fn house_rules_game() {
+   let mut config = GameConfig::STANDARD;
+   config.rules.victory_points = 12;
+   config.rules.robber_from_turn = 10;
+   config.initial_resources = Resources::new(1, 1, 0, 1, 1);
+   let mut game = Game::with_config(config);
+   // Seeded players, as knights played before the robber of 7s is active would steal too
+   for seed in 3..6 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let winner = match game.play(&mut rng, &mut state, vec![0, 1, 2]) {
+      Notification::GameFinished { winner } => winner,
+      notif => panic!("Unexpected end {:?}", notif),
+   };
+   assert!(state.get_player_total_vp(winner) >= 12);
+   let transfers = state.get_ledger().transfers();
+   for (p, transfer) in transfers.iter().take(3).enumerate() {
+      assert_eq!((transfer.kind, transfer.to, transfer.resources), (TransferKind::Setup, PlayerId::from(p), config.initial_resources));
+   }
+   for transfer in transfers.iter().filter(|transfer| transfer.kind == TransferKind::Discard || transfer.kind == TransferKind::Steal) {
+      assert!(transfer.turn >= 10, "{:?}", transfer);
+   }
+   config.initial_resources = Resources::new(7, 0, 0, 0, 0);
+   assert!(config.check(&layout::STANDARD_BOARD, 3).is_err());
+}
+
+#[test]
+fn rules_scenarios() {
+   let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/rules");
+   let mut failures = Vec::new();
+   for entry in fs::read_dir(directory).expect("Failed reading rules scenarios") {
+      failures.extend(scenario::run_file(&entry.unwrap().path()));
+   }
+   assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn discard_codec_round_trip() {
+   for count in 0..10 {
+      let codec = DiscardCodec::new(count);
+      assert_eq!(codec.size(), (1..5).fold(1, |acc, i| acc * (count as usize + i) / i));
+      for index in 0..codec.size() {
+         let discarded = codec.decode(index).unwrap();
+         assert_eq!(discarded.total(), count as i8);
+         assert_eq!(codec.encode(discarded), Some(index));
+      }
+      assert_eq!(codec.decode(codec.size()), None);
+   }
+   let hand = Resources::new(3, 0, 0, 0, 5);
+   let codec = DiscardCodec::for_hand(hand);
+   let legal: Vec<Resources> = codec.mask(hand).iter().enumerate()
+      .filter(|(_, legal)| **legal)
+      .map(|(index, _)| codec.decode(index).unwrap())
+      .collect();
+   assert_eq!(legal, vec![
+      Resources::new(0, 0, 0, 0, 4),
+      Resources::new(1, 0, 0, 0, 3),
+      Resources::new(2, 0, 0, 0, 2),
+      Resources::new(3, 0, 0, 0, 1),
+   ]);
+}
+
+#[test]
+fn checkpoint_round_trip() {
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let mut players: Vec<Box<dyn CatanPlayer>> = (0..3).map(|seed| Box::new(Randomy::new_seeded_player(seed)) as Box<dyn CatanPlayer>).collect();
+   for (p, player) in players.iter_mut().enumerate() {
+      player.new_game(PlayerId::from(p), &state);
+   }
+   let mut phase = Phase::START_GAME;
+   for _ in 0..400 {
+      let action = players[phase.player().to_usize()].pick_action(&phase, &state);
+      if legal::legal(&phase, &state, action).is_ok() {
+         apply(&mut phase, &mut state, action, &mut rng);
+      }
+   }
+   let checkpoint = Checkpoint::new(phase, &state);
+   let loaded = Checkpoint::from_bytes(&checkpoint.to_bytes()).unwrap();
+   assert_eq!(loaded, checkpoint);
+   let restored = loaded.state.restore::<TricellState>(&layout::DEFAULT).unwrap();
+   assert_eq!(StateSnapshot::new(&restored), checkpoint.state);
+   assert_eq!(restored.get_building_history(), state.get_building_history());
+   assert_eq!(StateSnapshot::new(&restored).digest(), checkpoint.state.digest());
+   assert_ne!(StateSnapshot::new(&setup::random_default::<TricellState, SmallRng>(&mut rng, 3)).digest(), checkpoint.state.digest());
+   assert!(Checkpoint::from_bytes(&[1, 2, 3]).is_err());
+   let mut off_board = checkpoint.state.clone();
+   off_board.roads.push((Coord::new(101, 3), PlayerId::FIRST));
+   assert!(off_board.restore::<TricellState>(&layout::DEFAULT).is_err());
+
+   let mut game = Game::new();
+   for seed in 0..3 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   let notif = game.resume(&mut rng, &loaded, vec![0, 1, 2]).unwrap();
+   assert_ne!(notif, Notification::GameFinished{ winner: PlayerId::NONE });
+}
+
+#[test]
+fn rewards_add_up() {
+   let mut rng = SmallRng::seed_from_u64(3);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let mut players: Vec<Box<dyn CatanPlayer>> = (0..3).map(|seed| Box::new(Randomy::new_seeded_player(seed)) as Box<dyn CatanPlayer>).collect();
+   for (p, player) in players.iter_mut().enumerate() {
+      player.new_game(PlayerId::from(p), &state);
+   }
+   let config = RewardConfig { vp_delta: 1.0, win_bonus: 10.0, income: 0.5, building: 0.25, illegal_penalty: -1.0, normalize_vp: true, scale: 2.0 };
+   let mut trackers: Vec<RewardTracker> = (0..3).map(|_| RewardTracker::new(config)).collect();
+   for (p, tracker) in trackers.iter_mut().enumerate() {
+      tracker.new_game(PlayerId::from(p), &state);
+   }
+   let mut totals = [0.0; 3];
+   let mut buildings = [0; 3];
+   let mut illegal = [0; 3];
+   let mut phase = Phase::START_GAME;
+   while !matches!(phase, Phase::FinishedGame { winner: _ }) {
+      let player = phase.player();
+      totals[player.to_usize()] += trackers[player.to_usize()].take(&state);
+      let action = players[player.to_usize()].pick_action(&phase, &state);
+      if legal::legal(&phase, &state, action).is_err() {
+         trackers[player.to_usize()].illegal_action();
+         illegal[player.to_usize()] += 1;
+         continue;
+      }
+      let notification = apply(&mut phase, &mut state, action, &mut rng);
+      if matches!(action, Action::BuildRoad { path: _ } | Action::BuildSettlement { intersection: _ } | Action::BuildCity { intersection: _ }) {
+         buildings[player.to_usize()] += 1;
+      }
+      for tracker in trackers.iter_mut() {
+         tracker.notify(&Notification::ActionPlayed { by: player, action });
+         if let Some(notification) = &notification {
+            tracker.notify(notification);
+         }
+      }
+   }
+   let winner = match phase {
+      Phase::FinishedGame { winner } => winner,
+      _ => PlayerId::NONE,
+   };
+   for p in 0..3usize {
+      let player = PlayerId::from(p);
+      let total = totals[p] + trackers[p].finish(&state, winner);
+      let income: i32 = state.get_ledger().transfers().iter()
+         .filter(|t| t.to == player && t.kind == TransferKind::Production)
+         .map(|t| t.resources.total() as i32)
+         .sum();
+      let expected = 2.0 * (state.get_player_total_vp(player) as f32 / 10.0 + if player == winner { 10.0 } else { 0.0 } + 0.5 * income as f32
+         + 0.25 * buildings[p] as f32 - illegal[p] as f32);
+      assert!((total - expected).abs() < 1e-3, "{:?}: {} rewarded, {} expected", player, total, expected);
+   }
+}
+
+#[test]
+fn phase_transitions() {
+   // Every phase kind is reachable from the start of the game, and only the end of the game is final
+   let mut reached = vec![PhaseKind::InitialSettlement];
+   let mut i = 0;
+   while i < reached.len() {
+      let from = reached[i];
+      for transition in TRANSITIONS.iter().filter(|transition| transition.from == from) {
+         for to in transition.to.iter() {
+            if !reached.contains(to) {
+               reached.push(*to);
+            }
+         }
+      }
+      i += 1;
+   }
+   for kind in PhaseKind::ALL.iter() {
+      assert!(reached.contains(kind), "{:?} is unreachable", kind);
+      assert_eq!(*kind == PhaseKind::Finished, TRANSITIONS.iter().all(|transition| transition.from != *kind), "{:?}", kind);
+   }
+   let dot = Phase::to_graphviz();
+   assert!(dot.starts_with("digraph phases {"));
+   assert!(dot.contains("PreRoll -> Discard [label=\"RollDice\"];"));
+
+   // Games are played in a debug build, checking each transition against the table
+   let mut game = Game::new();
+   for seed in 0..3 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   for seed in 0..3 {
+      let notif = game.setup_and_play_with_rng(&mut SmallRng::seed_from_u64(seed));
+      assert_ne!(notif, Notification::GameFinished{ winner: PlayerId::NONE });
+   }
+}
+
+#[test]
+fn special_building() {
+   for (players, special_building) in [(5, true), (6, true), (6, false), (4, true)] {
+      let mut rng = SmallRng::seed_from_u64(players);
+      let mut state = layout::EXTENSION_BOARD.setup::<TricellState, SmallRng>(&mut rng, players as u8);
+      state.set_rules(Rules { special_building, ..Rules::STANDARD });
+      let mut randomies: Vec<Box<dyn CatanPlayer>> = (0..players).map(|p| Box::new(Randomy::new_seeded_player(p)) as Box<dyn CatanPlayer>).collect();
+      for (p, player) in randomies.iter_mut().enumerate() {
+         player.new_game(PlayerId::from(p), &state);
+      }
+      let mut phase = Phase::START_GAME;
+      let (mut builders, mut built) = (Vec::new(), 0);
+      for _ in 0..200000 {
+         if let Phase::FinishedGame { winner: _ } = phase {
+            break;
+         }
+         let action = randomies[phase.player().to_usize()].pick_action(&phase, &state);
+         if legal::legal(&phase, &state, action).is_err() {
+            continue;
+         }
+         let turn = state.get_turn();
+         apply(&mut phase, &mut state, action, &mut rng);
+         if let Phase::Turn { player, turn_phase: TurnPhase::SpecialBuilding(builder), development_phase: _ } = phase {
+            if action == Action::EndTurn {
+               builders.push(builder);
+               // Every opponent builds in turn, the turn lasting until the last one is done
+               assert_eq!(builder.to_u8(), (player.to_u8() + builders.len() as u8) % players as u8);
+               assert_eq!(state.get_turn(), turn);
+            } else {
+               built += 1;
+            }
+            // Neither trades nor development cards during the special building
+            let trade = Action::ProposeTrade { give: Resources::new_one(Resource::Ore, 1), receive: Resources::new_one(Resource::Wool, 1) };
+            for action in [Action::RollDice, Action::DevelopmentKnight, trade] {
+               assert!(matches!(legal::legal(&phase, &state, action), Err(Error::IncoherentAction(_))), "{:?}", action);
+            }
+         } else if action == Action::EndTurn {
+            assert!(builders.is_empty() || builders.len() == players as usize - 1, "{} builders", builders.len());
+            builders.clear();
+         }
+      }
+      assert!(matches!(phase, Phase::FinishedGame { winner: _ }));
+      assert_eq!(built > 0, special_building && players >= 5, "{} players", players);
+   }
+}
+
+/// Random player resigning once the turn is reached
+struct Resigning {
+   player: Box<dyn CatanPlayer>,
+   turn: u32,
+}
+
+impl CatanPlayer for Resigning {
+   fn new_game(&mut self, position: PlayerId, state: &State) {
+      self.player.new_game(position, state);
+   }
+
+   fn pick_action(&mut self, phase: &Phase, state: &State) -> Action {
+      if phase.is_turn() && state.get_turn() >= self.turn {
+         Action::Resign
+      } else {
+         self.player.pick_action(phase, state)
+      }
+   }
+
+   fn bad_action(&mut self, error: Error) {
+      self.player.bad_action(error);
+   }
+
+   fn notify(&mut self, notification: &Notification) {
+      self.player.notify(notification);
+   }
+
+   fn results(&mut self, state: &State, winner: PlayerId) {
+      self.player.results(state, winner);
+   }
+}
+
+#[test]
+fn resigned_players_games() {
+   for (seed, resigned_pieces) in [ResignedPieces::Frozen, ResignedPieces::Removed].iter().enumerate() {
+      let mut config = GameConfig::STANDARD;
+      config.rules.resigned_pieces = *resigned_pieces;
+      let mut game = Game::with_config(config);
+      game.add_player(Box::new(Randomy::new_seeded_player(seed as u64)));
+      game.add_player(Box::new(Resigning { player: Box::new(Randomy::new_seeded_player(10)), turn: 8 }));
+      game.add_player(Box::new(Randomy::new_seeded_player(seed as u64 + 20)));
+      game.add_player(Box::new(Resigning { player: Box::new(Randomy::new_seeded_player(30)), turn: 20 }));
+      let mut rng = SmallRng::seed_from_u64(seed as u64);
+      let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 4);
+      let winner = match game.play(&mut rng, &mut state, vec![0, 1, 2, 3]) {
+         Notification::GameFinished { winner } => winner,
+         notif => panic!("Unexpected end {:?}", notif),
+      };
+      assert!(winner == PlayerId::FIRST || winner == PlayerId::from(2u8), "{:?}", winner);
+      let layout = state.get_layout();
+      for p in [1u8, 3] {
+         let player = PlayerId::from(p);
+         let hand = state.get_player_hand(player);
+         assert!(hand.resigned);
+         assert_eq!((hand.resources.total(), hand.development_cards.total()), (0, 0));
+         assert_ne!(state.get_longest_road().map(|(p, _)| p), Some(player));
+         assert_ne!(state.get_largest_army().map(|(p, _)| p), Some(player));
+         let roads = layout.paths.iter().filter(|path| state.get_dynamic_path(**path).unwrap() == Some(player)).count();
+         assert_eq!(roads == 0, *resigned_pieces == ResignedPieces::Removed);
+         // Resigned players neither produce nor trade once they left
+         let resigned_on = state.get_ledger().transfers().iter()
+            .find(|transfer| transfer.kind == TransferKind::Resignation && transfer.from == player)
+            .map_or(0, |transfer| transfer.turn);
+         assert!(state.get_ledger().transfers().iter().all(|transfer| transfer.turn <= resigned_on || (transfer.to != player && transfer.from != player)));
+      }
+      assert_longest_roads(&state);
+   }
+}
+
+#[test]
+fn board_layouts() {
+   // The standard board is set up on the default layout
+   let mut rng = SmallRng::seed_from_u64(0);
+   let state = layout::STANDARD_BOARD.setup::<TricellState, SmallRng>(&mut rng, 3);
+   assert_eq!(state.get_layout(), &*layout::DEFAULT);
+   // A 6 player game on the extension board
+   let mut game = Game::new();
+   game.board = &layout::EXTENSION_BOARD;
+   for _ in 0..6 {
+      game.add_player(Box::new(Randomy::new_player()));
+   }
+   let mut rng = SmallRng::seed_from_u64(1);
+   let notif = game.setup_and_play_with_rng(&mut rng);
+   assert_ne!(notif, Notification::GameFinished{ winner: PlayerId::NONE });
+   assert_eq!(layout::EXTENSION_BOARD.layout.hexes.len(), 30);
+   // Descriptions
+   let json = r#"{ "hexes": [[0, 0], [4, 0]], "tiles": ["ore", "desert"], "numbers": [6], "harbor_paths": [[6, 0]], "harbors": ["wool"] }"#;
+   let toml = "hexes = [[0, 0], [4, 0]]\ntiles = [\"ore\", \"desert\"]\nnumbers = [6]\nharbor_paths = [[6, 0]]\nharbors = [\"wool\"]\n";
+   let from_json = BoardLayout::from_json(json).unwrap();
+   let from_toml = BoardLayout::from_toml(toml).unwrap();
+   assert_eq!(from_json, from_toml);
+   let state = from_json.setup::<TricellState, SmallRng>(&mut rng, 2);
+   let hexes = [state.get_static_hex(Coord::new(0, 0)).unwrap(), state.get_static_hex(Coord::new(4, 0)).unwrap()];
+   assert!(hexes.contains(&Hex::Land(LandHex::Prod(Resource::Ore, 6))) && hexes.contains(&Hex::Land(LandHex::Desert)));
+   assert_eq!(state.get_static_harbor(Coord::new(6, 1)).unwrap(), Harbor::Special(Resource::Wool));
+   assert!(BoardLayout::from_json(r#"{ "hexes": [[0, 0]], "tiles": ["ore"], "numbers": [7] }"#).is_err());
+   assert!(BoardLayout::from_json(r#"{ "hexes": [[0, 0]], "tiles": ["sand"], "numbers": [] }"#).is_err());
+   // Harbors are on the coast, not between two land hexes
+   let inland = json.replace("[[6, 0]]", "[[2, 0]]");
+   assert_eq!(BoardLayout::from_json(&inland).unwrap_err(), "Harbor on Coord(2,0), which isn't on the coast");
+   // Boards of the same hexes share their layout, and a description is loaded once
+   assert!(std::ptr::eq(from_json.layout, from_toml.layout));
+   let path = std::env::temp_dir().join(format!("catan_board_{}.json", std::process::id()));
+   fs::write(&path, json).unwrap();
+   let loaded = BoardLayout::named(path.to_str().unwrap()).unwrap();
+   assert!(std::ptr::eq(loaded, BoardLayout::named(path.to_str().unwrap()).unwrap()));
+   assert!(std::ptr::eq(loaded.layout, from_json.layout));
+   fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn visible_state() {
+   let mut game = Game::new();
+   for _ in 0..3 {
+      game.add_player(Box::new(Randomy::new_player()));
+   }
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   game.play(&mut rng, &mut state, vec![0, 1, 2]);
+   let view = state.view_for(PlayerId::FIRST);
+   assert_eq!(view.hand(), state.get_player_hand(PlayerId::FIRST));
+   for p in 1..3 {
+      let player = PlayerId::from(p as u8);
+      let hand = state.get_player_hand(player);
+      assert!(view.revealed_hand(player).is_none());
+      let public = view.public_hand(player);
+      assert_eq!(public.resource_count as i8, hand.resources.total());
+      assert_eq!(public.development_card_count, hand.development_cards.total() + hand.new_development_cards.total());
+      assert_eq!(public.played_development_cards, hand.played_development_cards);
+      assert_eq!(public.public_vp, state.get_player_public_vp(player));
+   }
+   // Open hands are seen by everyone
+   state.set_rules(Rules::SIMPLIFIED);
+   assert!(state.view_for(PlayerId::FIRST).revealed_hand(PlayerId::from(1u8)).is_some());
+   // A bucketed deck only shows its size rounded up to a whole bucket
+   let deck = state.get_development_cards().total();
+   state.set_rules(Rules { bucketed_deck: true, ..Rules::STANDARD });
+   let size = state.view_for(PlayerId::FIRST).development_deck_size();
+   assert!(size.is_multiple_of(Rules::DECK_BUCKET) && size >= deck && size < deck + Rules::DECK_BUCKET);
+   state.get_development_cards_mut().clear();
+   assert_eq!(state.view_for(PlayerId::FIRST).development_deck_size(), 0);
+}
+
+#[test]
+fn information_leaks() {
+   // Legal actions and observations built from the view are the same on every state the player can't tell apart
+   let observe = |phase: &Phase, player: PlayerId, state: &State| {
+      let mut actions = Vec::new();
+      generate_possible_actions(&mut actions, player, state);
+      let legal: Vec<bool> = actions.iter().map(|action| legal::legal(phase, state, *action).is_ok()).collect();
+      let view = state.view_for(player);
+      let hands: Vec<PublicHand> = (0..state.player_count()).map(|p| view.public_hand(PlayerId::from(p))).collect();
+      (legal, hands, *view.hand(), view.development_deck_size(), view.get_bank_resources())
+   };
+   for seed in 0..4 {
+      let mut rng = SmallRng::seed_from_u64(seed);
+      let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+      let mut players: Vec<Box<dyn CatanPlayer>> = (0..3).map(|p| Box::new(Randomy::new_seeded_player(seed * 3 + p)) as Box<dyn CatanPlayer>).collect();
+      for (p, player) in players.iter_mut().enumerate() {
+         player.new_game(PlayerId::from(p), &state);
+      }
+      let mut phase = Phase::START_GAME;
+      for _ in 0..600 {
+         if let Phase::FinishedGame { winner: _ } = phase {
+            break;
+         }
+         let player = phase.player();
+         audit::assert_view_only(&state, &layout::DEFAULT, &phase, player, &mut rng, 2, |state| observe(&phase, player, state));
+         let action = players[player.to_usize()].pick_action(&phase, &state);
+         if legal::legal(&phase, &state, action).is_ok() {
+            apply(&mut phase, &mut state, action, &mut rng);
+         }
+      }
+   }
+}
+
+/// Longest road of the player, going through every sequence of its roads
+fn brute_force_longest_road(state: &State, player: PlayerId) -> u8 {
+   fn longest_from(state: &State, player: PlayerId, roads: &[(Coord, Vec<Coord>)], used: &mut Vec<bool>, intersection: Coord) -> u8 {
+      let mut longest = 0;
+      for (i, (_, ends)) in roads.iter().enumerate() {
+         if used[i] || !ends.contains(&intersection) {
+            continue;
+         }
+         let next = if ends[0] == intersection { ends[1] } else { ends[0] };
+         let blocked = matches!(state.get_dynamic_intersection(next).unwrap(), Some((p, _)) if p != player);
+         used[i] = true;
+         longest = longest.max(1 + if blocked { 0 } else { longest_from(state, player, roads, used, next) });
+         used[i] = false;
+      }
+      longest
+   }
+   let roads: Vec<(Coord, Vec<Coord>)> = state.get_layout().paths.iter()
+      .filter(|path| state.get_dynamic_path(**path).unwrap() == Some(player))
+      .map(|path| (*path, state.path_intersection_neighbours(*path).unwrap()))
+      .collect();
+   let mut used = vec![false; roads.len()];
+   roads.iter()
+      .flat_map(|(_, ends)| ends.clone())
+      .map(|intersection| longest_from(state, player, &roads, &mut used, intersection))
+      .max()
+      .unwrap_or(0)
+}
+
+fn assert_longest_roads(state: &State) {
+   for p in 0..state.player_count() {
+      let player = PlayerId::from(p);
+      assert_eq!(state.get_player_hand(player).continous_road, brute_force_longest_road(state, player), "{:?} at turn {}", player, state.get_turn());
+   }
+}
+
+#[test]
+fn longest_road_matches_brute_force() {
+   // Roads and buildings placed anywhere, mostly next to the roads of their player
+   for seed in 0..40 {
+      let mut rng = SmallRng::seed_from_u64(seed);
+      let mut state: State = Box::new(TricellState::new(&layout::DEFAULT, 3));
+      for turn in 0..60 {
+         state.set_turn(turn);
+         let player = PlayerId::from(rng.random_range(0..3u8));
+         if rng.random_bool(0.2) {
+            let free: Vec<Coord> = state.get_layout().intersections.iter().copied()
+               .filter(|intersection| state.get_dynamic_intersection(*intersection).unwrap().is_none())
+               .collect();
+            let intersection = free[rng.random_range(0..free.len())];
+            state.set_dynamic_intersection(intersection, player, false).unwrap();
+            for p in 0..3u8 {
+               if PlayerId::from(p) != player {
+                  state.reset_longest_road(PlayerId::from(p));
+               }
+            }
+         } else {
+            let free: Vec<Coord> = state.get_layout().paths.iter().copied()
+               .filter(|path| state.get_dynamic_path(*path).unwrap().is_none())
+               .collect();
+            let next: Vec<Coord> = free.iter().copied()
+               .filter(|path| state.path_path_neighbours(*path).unwrap().iter().any(|other| state.get_dynamic_path(*other).unwrap() == Some(player)))
+               .collect();
+            let candidates = if next.is_empty() || rng.random_bool(0.1) { free } else { next };
+            let path = candidates[rng.random_range(0..candidates.len())];
+            state.set_dynamic_path(path, player).unwrap();
+            state.update_longest_road(player, path);
+         }
+         assert_longest_roads(&state);
+      }
+      // Roads restored from a snapshot are grouped again
+      let mut restored = StateSnapshot::new(&state).restore::<TricellState>(&layout::DEFAULT).unwrap();
+      for p in 0..3u8 {
+         restored.reset_longest_road(PlayerId::from(p));
+      }
+      assert_longest_roads(&restored);
+   }
+
+   // And along random games
+   for seed in 0..4 {
+      let mut rng = SmallRng::seed_from_u64(seed);
+      let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 4);
+      let mut players: Vec<Box<dyn CatanPlayer>> = (0..4).map(|p| Box::new(Randomy::new_seeded_player(seed * 4 + p)) as Box<dyn CatanPlayer>).collect();
+      for (p, player) in players.iter_mut().enumerate() {
+         player.new_game(PlayerId::from(p), &state);
+      }
+      let mut phase = Phase::START_GAME;
+      while !matches!(phase, Phase::FinishedGame { .. }) {
+         let action = players[phase.player().to_usize()].pick_action(&phase, &state);
+         if legal::legal(&phase, &state, action).is_ok() {
+            apply(&mut phase, &mut state, action, &mut rng);
+            if matches!(action, Action::BuildRoad { .. } | Action::BuildSettlement { .. }) {
+               assert_longest_roads(&state);
+            }
+         }
+      }
+   }
+}
+
+#[test]
+fn belief_tracker() {
+   for seed in 0..4 {
+      let mut rng = SmallRng::seed_from_u64(seed);
+      let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 4);
+      let mut players: Vec<Box<dyn CatanPlayer>> = (0..4).map(|p| Box::new(Randomy::new_seeded_player(seed * 4 + p)) as Box<dyn CatanPlayer>).collect();
+      for (p, player) in players.iter_mut().enumerate() {
+         player.new_game(PlayerId::from(p), &state);
+      }
+      let mut trackers: Vec<BeliefTracker> = (0..4u8).map(|p| BeliefTracker::new(PlayerId::from(p), 4)).collect();
+      let mut phase = Phase::START_GAME;
+      let (mut steals, mut audited) = (0, 0);
+      while !matches!(phase, Phase::FinishedGame { .. }) {
+         let player = phase.player();
+         // Trackers read the transfers the player sees, and read them incrementally
+         if steals > 0 && state.get_turn().is_multiple_of(10) && state.get_turn() != audited {
+            audited = state.get_turn();
+            audit::assert_view_only(&state, &layout::DEFAULT, &phase, player, &mut rng, 1, |state| BeliefTracker::from_view(&state.view_for(player)));
+            assert_eq!(BeliefTracker::from_view(&state.view_for(player)), trackers[player.to_usize()]);
+         }
+         let action = players[player.to_usize()].pick_action(&phase, &state);
+         if legal::legal(&phase, &state, action).is_err() {
+            continue;
+         }
+         apply(&mut phase, &mut state, action, &mut rng);
+         for tracker in trackers.iter_mut() {
+            let view = state.view_for(tracker.observer());
+            tracker.update(&view);
+            for p in 0..4u8 {
+               let resources = state.get_player_hand(PlayerId::from(p)).resources;
+               let belief = tracker.hand(PlayerId::from(p));
+               assert_eq!(belief.count as i8, resources.total());
+               for res in Resource::ALL.iter() {
+                  assert!(belief.minimum[*res] <= resources[*res], "{:?} deduced from {:?}", belief, resources);
+                  assert!(belief.maximum()[*res] >= resources[*res], "{:?} deduced from {:?}", belief, resources);
+                  assert!(belief.expected[res.to_usize()] >= belief.minimum[*res] as f32);
+                  assert!(belief.expected[res.to_usize()] <= belief.maximum()[*res] as f32 + 1e-3);
+               }
+               assert!((belief.expected.iter().sum::<f32>() - belief.count as f32).abs() < 1e-3);
+            }
+            assert_eq!(tracker.hand(tracker.observer()).minimum, view.hand().resources);
+         }
+         steals += matches!(action, Action::MoveThief { .. }) as u32;
+      }
+      assert!(steals > 0);
+   }
+}
+
+#[test]
+fn opponent_model() {
+   let mut rng = SmallRng::seed_from_u64(3);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 4);
+   let mut players: Vec<Box<dyn CatanPlayer>> = (0..4).map(|p| Box::new(Randomy::new_seeded_player(p)) as Box<dyn CatanPlayer>).collect();
+   for (p, player) in players.iter_mut().enumerate() {
+      player.new_game(PlayerId::from(p), &state);
+   }
+   let mut phase = Phase::START_GAME;
+   let mut answers = [[0u32; 2]; 4];
+   let mut steals = [[0u32; 4]; 4];
+   let mut purchases = [0u32; 4];
+   while !matches!(phase, Phase::FinishedGame { .. }) {
+      let player = phase.player();
+      let action = players[player.to_usize()].pick_action(&phase, &state);
+      if legal::legal(&phase, &state, action).is_err() {
+         continue;
+      }
+      let transfers = state.get_ledger().transfers().len();
+      // An accepted counter offer is the answer of the opponent who made it
+      let partner = match phase {
+         Phase::Turn { turn_phase: TurnPhase::Trade(offer), .. } => offer.partner,
+         _ => player,
+      };
+      apply(&mut phase, &mut state, action, &mut rng);
+      match action {
+         Action::AcceptTrade => answers[partner.to_usize()] = [answers[partner.to_usize()][0] + 1, answers[partner.to_usize()][1] + 1],
+         Action::RejectTrade | Action::CounterTrade { .. } => answers[player.to_usize()][0] += 1,
+         Action::MoveThief { victim, .. } if state.get_ledger().transfers().len() > transfers => steals[player.to_usize()][victim.to_usize()] += 1,
+         _ if state.get_ledger().transfers()[transfers..].iter().any(|transfer| transfer.kind == TransferKind::Purchase) => purchases[player.to_usize()] += 1,
+         _ => (),
+      }
+   }
+   assert!(answers.iter().any(|answer| answer[1] > 0) && steals.iter().flatten().sum::<u32>() > 0);
+   // The model only reads the public part of the ledger
+   audit::assert_view_only(&state, &layout::DEFAULT, &phase, PlayerId::from(0u8), &mut rng, 1, |state| OpponentModel::from_view(&state.view_for(PlayerId::from(0u8))));
+   for o in 0..4usize {
+      let model = OpponentModel::from_view(&state.view_for(PlayerId::from(o)));
+      for p in 0..4usize {
+         let profile = model.profile(PlayerId::from(p));
+         assert_eq!([profile.offers_answered, profile.offers_accepted], answers[p]);
+         assert_eq!(profile.steals, steals[p].iter().sum::<u32>());
+         assert_eq!(profile.steals_from_observer, steals[p][o]);
+         assert_eq!(profile.purchases, purchases[p]);
+         assert!(profile.last_purchase.unwrap() <= state.get_turn());
+      }
+   }
+}
+
+#[test]
+fn settlement_reach() {
+   let mut rng = SmallRng::seed_from_u64(5);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let mut players: Vec<Box<dyn CatanPlayer>> = (0..3).map(|p| Box::new(Randomy::new_seeded_player(p)) as Box<dyn CatanPlayer>).collect();
+   for (p, player) in players.iter_mut().enumerate() {
+      player.new_game(PlayerId::from(p), &state);
+   }
+   let mut phase = Phase::START_GAME;
+   for _ in 0..1500 {
+      if matches!(phase, Phase::FinishedGame { .. }) {
+         break;
+      }
+      let player = phase.player();
+      if matches!(phase, Phase::Turn { .. }) {
+         let reach = legal::settlement_reach(player, &state, 3);
+         assert_eq!(reach.iter().filter(|(_, roads)| *roads == 0).count(), state.get_layout().intersections.iter()
+            .filter(|i| legal::connected_position(**i, player, &state).unwrap() && legal::available_settlement_position(**i, &state).unwrap()).count());
+         // Each further road brings new intersections within reach, and no nearer one gets further away
+         let closer = legal::settlement_reach(player, &state, 2);
+         assert!(closer.iter().all(|spot| reach.contains(spot)));
+         assert!(reach.iter().all(|(_, roads)| *roads <= 3.min(state.get_player_hand(player).road_pieces)));
+         for (intersection, _) in reach.iter().filter(|(_, roads)| *roads == 1) {
+            assert!(state.intersection_path_neighbours(*intersection).unwrap().iter()
+               .any(|path| legal::can_put_road(player, *path, &state).is_ok()));
+         }
+      }
+      let action = players[player.to_usize()].pick_action(&phase, &state);
+      if legal::legal(&phase, &state, action).is_ok() {
+         apply(&mut phase, &mut state, action, &mut rng);
+      }
+   }
+}
+
+#[test]
+fn tablebase() {
+   assert!(Tablebase::check_rules(&Rules::STANDARD).is_err());
+   let rules = Rules { victory_points: 3, ..Rules::SIMPLIFIED };
+   let mut solved = 0;
+   for seed in 0..20 {
+      let mut rng = SmallRng::seed_from_u64(seed);
+      let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+      state.set_rules(rules);
+      let mut player = Randomy::new_seeded_player(seed);
+      player.new_game(PlayerId::FIRST, &state);
+      let mut phase = Phase::START_GAME;
+      while let Phase::InitialPlacement { .. } = phase {
+         let action = player.pick_action(&phase, &state);
+         if legal::legal(&phase, &state, action).is_ok() {
+            apply(&mut phase, &mut state, action, &mut rng);
+         }
+      }
+      // The first player builds a road up to a free spot, then only misses a brick to settle there and win
+      let first = PlayerId::FIRST;
+      let hand = *state.get_player_hand(first);
+      if Resource::ALL.iter().any(|res| hand.harbor.rate(*res) < 4) {
+         continue;
+      }
+      let spot = legal::settlement_reach(first, &state, 1).into_iter().find(|(_, roads)| *roads == 1);
+      let road = spot.and_then(|(intersection, _)| state.intersection_path_neighbours(intersection).unwrap().into_iter()
+         .find(|path| legal::can_put_road(first, *path, &state).is_ok()));
+      let road = match road {
+         Some(road) => road,
+         None => continue,
+      };
+      state.get_player_hand_mut(first).resources = Resources::ROAD;
+      apply(&mut phase, &mut state, Action::BuildRoad { path: road }, &mut rng);
+      state.get_player_hand_mut(first).resources = Resources::SETTLEMENT - Resources::new_one(Resource::Brick, 1);
+      let bricks: Vec<u8> = state.get_layout().hexes.iter().filter_map(|hex| match state.get_static_hex(*hex).unwrap() {
+         Hex::Land(LandHex::Prod(Resource::Brick, roll)) if *hex != state.get_thief_hex()
+            && state.hex_intersection_neighbours(*hex).unwrap().iter().any(|i| matches!(state.get_dynamic_intersection(*i).unwrap(), Some((p, _)) if p == first)) => Some(roll),
+         _ => None,
+      }).collect();
+      let expected: f64 = (2..=12).filter(|roll| bricks.contains(roll)).map(tablebase::roll_probability).sum();
+      // Nobody wins once the turn is over
+      let tablebase = Tablebase::build(&phase, &state, 0).unwrap();
+      let root = tablebase.probe(&phase, &state).unwrap();
+      assert!((root.wins[0] - expected).abs() < 1e-9, "{:?} instead of {}", root, expected);
+      assert_eq!(&root.wins[1..], &[0.0, 0.0]);
+      assert_eq!(root.best, Some(Action::RollDice));
+      // The first player still wins at least as often with another turn of each player
+      if solved == 0 {
+         let longer = Tablebase::build(&phase, &state, 3).unwrap();
+         assert!(longer.len() > tablebase.len() && longer.probe(&phase, &state).unwrap().wins[0] >= root.wins[0]);
+      }
+      // Positions of other rules aren't probed
+      state.set_rules(Rules { victory_points: 4, ..rules });
+      assert!(tablebase.probe(&phase, &state).is_none());
+      solved += 1;
+   }
+   assert!(solved > 0);
+   assert!(((2..=12).map(tablebase::roll_probability).sum::<f64>() - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn replay_round_trip() {
+   let mut game = Game::new();
+   for seed in 0..3 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   game.record_replays = true;
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let notif = game.play(&mut rng, &mut state, vec![2, 0, 1]);
+   let replay = game.replay.take().unwrap();
+   assert_eq!(notif, Notification::GameFinished { winner: replay.winner.unwrap() });
+   assert_eq!(replay.seating, vec![2, 0, 1]);
+   assert!(replay.plies.iter().any(|ply| ply.roll.is_some()));
+   assert_eq!(Replay::from_json(&replay.to_json()).unwrap(), replay);
+   assert_eq!(Replay::from_bytes(&replay.to_bytes()).unwrap(), replay);
+   // Actions keep the variant index of their category in the binary replays
+   for action in codec::action_list(PlayerId::FIRST, &state, true).iter().chain([Action::Resign, Action::Exit].iter()) {
+      assert_eq!(bincode::serialize(action).unwrap()[..4], (action.category() as u32).to_le_bytes(), "{:?}", action);
+   }
+   // Replays of other rules or engines aren't read
+   let json = replay.to_json();
+   assert!(json.contains("\"discard_limit\":7") && json.contains(&format!("\"engine\":\"{}\"", ENGINE_VERSION)));
+   assert!(Replay::from_json(&json.replace("\"discard_limit\":7", "\"discard_limit\":9")).is_err());
+   assert!(Replay::from_json(&json.replace(&format!("\"engine\":\"{}\"", ENGINE_VERSION), "\"engine\":\"99.0.0\"")).is_err());
+   let mut stale = replay.start.clone();
+   stale.version.rules ^= 1;
+   assert!(Checkpoint::from_bytes(&stale.to_bytes()).is_err());
+   assert_eq!(stale.version.check(&Rules::STANDARD), Err(format!(
+      "Record of rules {:016x}, read as rules {:016x} {:?}", stale.version.rules, Rules::STANDARD.fingerprint(), Rules::STANDARD,
+   )));
+   assert_ne!(Rules::SIMPLIFIED.fingerprint(), Rules::STANDARD.fingerprint());
+   // Playing the replay back rebuilds every state of the game
+   let mut replayer = Replayer::new(&replay, &layout::DEFAULT).unwrap();
+   while replayer.step().is_some() {}
+   assert_eq!(replayer.ply(), replay.plies.len());
+   assert_eq!(*replayer.phase(), Phase::FinishedGame { winner: replay.winner.unwrap() });
+   assert_eq!(StateSnapshot::new(replayer.state()), StateSnapshot::new(&state));
+   replayer.seek(10).unwrap();
+   let (phase, at_ten) = replay.state_at(10, &layout::DEFAULT).unwrap();
+   assert_eq!(phase, *replayer.phase());
+   assert_eq!(StateSnapshot::new(&at_ten), StateSnapshot::new(replayer.state()));
+}
+
+#[test]
+fn why_explains_decisions() {
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let start = why(&state, &Phase::START_GAME);
+   assert_eq!((start.kind, start.player), (PhaseKind::InitialSettlement, PlayerId::FIRST));
+   assert_eq!(start.available, vec![ActionCategory::BuildSettlement]);
+   assert!(start.summary.contains("first initial settlement"));
+   assert_eq!(start.transitions.len(), 1);
+   assert_eq!(start.transitions[0].to, &[PhaseKind::InitialRoad]);
+
+   // Before rolling, without cards, only rolling or resigning is legal
+   let turn = why(&state, &Phase::START_TURNS);
+   assert_eq!(turn.available, vec![ActionCategory::RollDice, ActionCategory::Resign]);
+   assert!(turn.transitions.iter().any(|transition| transition.action == ActionCategory::DevelopmentKnight));
+   assert!(turn.next.starts_with("Rolling the dice"));
+
+   let discarding = PlayerId::from(1u8);
+   state.get_player_hand_mut(discarding).resources = Resources::new(2, 2, 2, 2, 1);
+   let phase = Phase::Turn { player: PlayerId::FIRST, turn_phase: TurnPhase::Discard(discarding), development_phase: DevelopmentPhase::Ready };
+   let discard = why(&state, &phase);
+   assert_eq!((discard.kind, discard.player), (PhaseKind::Discard, discarding));
+   assert!(discard.available.contains(&ActionCategory::Keep) && discard.available.contains(&ActionCategory::Discard));
+   assert!(discard.summary.contains("9 resources"));
+
+   let finished = why(&state, &Phase::FinishedGame { winner: PlayerId::from(2u8) });
+   assert!(finished.available.is_empty() && finished.transitions.is_empty());
+   assert_eq!(finished.summary, "Player 2 won the game");
+}
+
+#[test]
+fn compact_replays() {
+   for (board, players) in [(&*layout::STANDARD_BOARD, 4), (&*layout::EXTENSION_BOARD, 5)] {
+      let mut game = Game::new();
+      game.board = board;
+      for seed in 0..players {
+         game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+      }
+      game.record_replays = true;
+      game.setup_and_play_with_rng(&mut SmallRng::seed_from_u64(players));
+      let replay = game.replay.take().unwrap();
+      let compact = replay.to_compact(board.layout).unwrap();
+      assert!(compact.len() * 5 < replay.to_bytes().len(), "{} bytes for {} bytes", compact.len(), replay.to_bytes().len());
+
+      // The plies, their outcomes and the states they lead to are regenerated
+      let read = Replay::from_compact(&compact, board.layout).unwrap();
+      assert_eq!((&read.start, &read.seating, read.winner), (&replay.start, &replay.seating, replay.winner));
+      let plies = |replay: &Replay| replay.plies.iter().map(|ply| (ply.player, ply.action, ply.roll, ply.drawn)).collect::<Vec<_>>();
+      assert_eq!(plies(&read), plies(&replay));
+      for ply in [replay.plies.len() / 2, replay.plies.len()] {
+         assert_eq!(
+            StateSnapshot::new(&read.state_at(ply, board.layout).unwrap().1),
+            StateSnapshot::new(&replay.state_at(ply, board.layout).unwrap().1),
+         );
+      }
+      assert_eq!(read.to_compact(board.layout).unwrap(), compact);
+
+      assert!(Replay::from_compact(&compact[..compact.len() / 2], board.layout).is_err());
+   }
+}
+
+#[test]
+fn verify_uploaded_games() {
+   let board = &*layout::STANDARD_BOARD;
+   let mut game = Game::new();
+   for seed in 0..4 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   game.record_replays = true;
+   game.setup_and_play_with_rng(&mut SmallRng::seed_from_u64(4));
+   let record = game.export_record().unwrap();
+   let replay = game.replay.take().unwrap();
+   let (_, last) = replay.state_at(replay.plies.len(), board.layout).unwrap();
+
+   // Every format of a game verifies, to the same last state
+   for (bytes, format) in [(replay.to_bytes(), "replay"), (replay.to_json().into_bytes(), "replay"), (record.to_json().into_bytes(), "record"), (record.to_text().into_bytes(), "notation")] {
+      let verification = verify(&bytes, board.layout);
+      assert!(verification.is_ok(), "{:?}", verification);
+      assert_eq!(verification.format, format);
+      assert_eq!((verification.plies, verification.winner), (replay.plies.len(), replay.winner));
+      assert_eq!(verification.digest, Some(StateSnapshot::new(&last).digest()));
+      assert_eq!(verification.rules, Some(Rules::STANDARD.fingerprint()));
+   }
+
+   // Tampered games report their first bad ply
+   let rolled = replay.plies.iter().position(|ply| ply.roll.is_some()).unwrap();
+   let mut tampered = replay.clone();
+   tampered.plies[rolled].roll = Some(tampered.plies[rolled].roll.unwrap() % 12 + 2);
+   let verification = verify(&tampered.to_bytes(), board.layout);
+   assert_eq!((verification.plies, verification.digest), (rolled, None));
+   assert!(verification.error.unwrap().starts_with(&format!("Ply {}: RollDice rolled", rolled + 1)));
+   let mut tampered = replay.clone();
+   tampered.plies[0].action = Action::EndTurn;
+   assert!(verify(&tampered.to_bytes(), board.layout).error.unwrap().starts_with("Ply 1: illegal EndTurn"));
+   let mut tampered = replay.clone();
+   tampered.winner = tampered.winner.map(|winner| PlayerId::from((winner.to_u8() + 1) % 4));
+   assert!(verify(&tampered.to_json().into_bytes(), board.layout).error.unwrap().starts_with("Replay won by"));
+   let verification = verify(b"not a game", board.layout);
+   assert!(!verification.is_ok() && verification.engine.is_none());
+}
+
+#[test]
+fn replay_divergence() {
+   let mut game = Game::new();
+   for seed in 0..3 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   game.record_replays = true;
+   game.setup_and_play_with_rng(&mut SmallRng::seed_from_u64(1));
+   let mut replay = game.replay.take().unwrap();
+   replay.plies.truncate(40);
+   replay.winner = None;
+   let trail = DigestTrail::new(&replay, &layout::DEFAULT).unwrap();
+   assert_eq!(trail.digests.len(), replay.plies.len() + 1);
+   assert_eq!(DigestTrail::from_json(&trail.to_json()).unwrap(), trail);
+   assert_eq!(first_divergence(&replay, &layout::DEFAULT, &trail), None);
+
+   // The first state reached otherwise is reported, with the digest this engine found
+   let mut other = trail.clone();
+   other.digests[7] ^= 1;
+   other.digests[9] ^= 1;
+   assert_eq!(first_divergence(&replay, &layout::DEFAULT, &other), Some(Divergence { ply: 7, expected: Some(other.digests[7]), found: Some(trail.digests[7]), error: None }));
+   other.digests.truncate(5);
+   assert_eq!(first_divergence(&replay, &layout::DEFAULT, &other).unwrap().ply, 5);
+   let mut changed = replay.clone();
+   changed.start.state.players[0].resources += Resources::new_one(Resource::Brick, 1);
+   assert_eq!(first_divergence(&changed, &layout::DEFAULT, &trail).unwrap().ply, 0);
+   // Plies this engine can't play diverge without a digest
+   let mut changed = replay.clone();
+   changed.plies[3].action = Action::EndTurn;
+   let divergence = first_divergence(&changed, &layout::DEFAULT, &trail).unwrap();
+   assert_eq!((divergence.ply, divergence.found), (4, None));
+   assert!(divergence.error.unwrap().starts_with("Ply 4: illegal EndTurn"));
+}
+
+#[test]
+fn corpus_positions() {
+   let board = &*layout::STANDARD_BOARD;
+   let mut games = Vec::new();
+   for seed in 0..2 {
+      let mut game = Game::new();
+      for p in 0..3 {
+         game.add_player(Box::new(Randomy::new_seeded_player(seed * 3 + p)));
+      }
+      game.record_replays = true;
+      game.setup_and_play_with_rng(&mut SmallRng::seed_from_u64(seed));
+      // Games are read from replays and records alike
+      games.push(if seed == 0 { game.replay.take().unwrap().to_bytes() } else { game.export_record().unwrap().to_text().into_bytes() });
+   }
+   let corpus = Corpus::read(games.iter().map(|game| game.as_slice()), board.layout).unwrap();
+   assert_eq!((corpus.len(), corpus.player_count()), (2, 3));
+   let mut rng = SmallRng::seed_from_u64(0);
+   for _ in 0..4 {
+      let checkpoint = corpus.sample(&mut rng, 20).unwrap();
+      assert!(checkpoint.state.turn >= 20 && !matches!(checkpoint.phase, Phase::FinishedGame { winner: _ }));
+      assert_eq!(Checkpoint::from_bytes(&checkpoint.to_bytes()).unwrap(), checkpoint);
+      let mut game = Game::new();
+      for p in 0..3 {
+         game.add_player(Box::new(Randomy::new_seeded_player(p)));
+      }
+      assert!(matches!(game.resume(&mut rng, &checkpoint, vec![0, 1, 2]), Ok(Notification::GameFinished { winner: _ })));
+   }
+   assert_eq!(corpus.sample(&mut rng, u32::MAX), None);
+   assert!(Corpus::read([games[0].as_slice(), b"not a game"], board.layout).err().unwrap().starts_with("Game 2: "));
+}
+
+#[test]
+fn codec_fixtures() {
+   let fixtures = codec::Fixtures::generate(&layout::STANDARD_BOARD, 4, true, 2, 12, 0).unwrap();
+   assert_eq!((fixtures.games.len(), fixtures.vectors.len()), (2, 24));
+   assert_eq!(fixtures.spec.version, codec::CODEC_VERSION);
+   assert_eq!(fixtures.spec.actions[..2], ["MoveThief -4,-4 0", "MoveThief -4,-4 1"]);
+   assert!(fixtures.vectors.iter().all(|vector| vector.mask.len() == fixtures.spec.actions.len() && vector.mask.as_bytes()[vector.played] == b'1'));
+   let read = codec::Fixtures::from_json(&fixtures.to_json()).unwrap();
+   assert_eq!(read, fixtures);
+   assert_eq!(read.validate(), Ok(()));
+
+   // Another implementation disagreeing on a mask, an action or the version is told where
+   let mut other = fixtures.clone();
+   let flipped = if other.vectors[3].mask.starts_with('0') { "1" } else { "0" };
+   other.vectors[3].mask.replace_range(0..1, flipped);
+   other.spec.actions.swap(1, 2);
+   let errors = other.validate().unwrap_err();
+   assert_eq!(errors.len(), 3, "{:?}", errors);
+   assert!(errors.contains(&"Vector 3: mask differs at indices [0]".to_string()));
+   other.spec.version += 1;
+   assert_eq!(other.validate().unwrap_err().len(), 1);
+}
+
+#[test]
+fn notation_round_trip() {
+   for (board, players) in [(&*layout::STANDARD_BOARD, 4), (&*layout::EXTENSION_BOARD, 5)] {
+      let mut game = Game::with_config(GameConfig { initial_resources: Resources::new_one(Resource::Wool, 1), ..GameConfig::STANDARD });
+      game.board = board;
+      for seed in 0..players {
+         game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+      }
+      game.record_replays = true;
+      game.collect_stats = true;
+      game.set_win_prob_estimator(Box::new(LogisticEstimator::DEFAULT));
+      game.setup_and_play_with_rng(&mut SmallRng::seed_from_u64(players));
+      let record = game.export_record().unwrap();
+      assert_eq!(record.names[0], format!("Player {}", game.seating[0]));
+      // The estimates of each turn are kept in the record and the stats
+      let stats = game.stats.as_ref().unwrap();
+      assert_eq!(record.win_probabilities.len(), stats.turns as usize);
+      assert_eq!(record.win_probabilities, stats.win_probabilities);
+      assert_eq!(record.win_probabilities, game.win_probabilities);
+      for estimates in record.win_probabilities.iter() {
+         assert_eq!(estimates.len(), players as usize);
+         assert!((estimates.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+      }
+      assert!(record.moves.iter().any(|played| matches!(played.outcome, Some(Outcome::Rolled(_)))));
+      assert!(record.moves.iter().any(|played| matches!(played.outcome, Some(Outcome::Stole(_)))));
+      assert!(record.moves.iter().any(|played| matches!(played.outcome, Some(Outcome::Kept(_)))));
+      // Offers left waiting are kept with the answers of their turn
+      assert!(!record.negotiations.is_empty());
+      for (number, negotiation) in record.negotiations.iter() {
+         let played = record.moves[number - 1];
+         match played.action {
+            Action::ProposeTrade { give, receive } => {
+               assert_eq!((negotiation.proposer, negotiation.offer.give, negotiation.offer.receive), (played.player, give, receive));
+            }
+            Action::RejectTrade => assert_eq!(negotiation.responses.last(), Some(&(played.player, TradeAnswer::Rejected))),
+            Action::ReviseTrade { give, receive } => {
+               assert_eq!((negotiation.proposer, negotiation.offer.give, negotiation.offer.receive), (played.player, give, receive));
+               assert!(!negotiation.offer.countered);
+            }
+            Action::CounterTrade { .. } => {
+               assert!(negotiation.offer.countered);
+               assert_eq!(negotiation.responses.last(), Some(&(played.player, TradeAnswer::Countered)));
+            }
+            other => panic!("{:?} left an offer waiting", other),
+         }
+      }
+      let text = record.to_text();
+      assert!(text.lines().any(|line| line.starts_with("offer ")));
+      assert_eq!(Record::from_text(&text).unwrap(), record);
+      assert_eq!(Record::from_json(&record.to_json()).unwrap(), record);
+
+      // Importing the record plays the same game with the outcomes it holds
+      let replay = game.replay.take().unwrap();
+      let mut imported = Game::new();
+      imported.import_record(&Record::from_text(&text).unwrap()).unwrap();
+      assert_eq!(imported.board, board);
+      let imported = imported.replay.unwrap();
+      assert_eq!(imported.winner, replay.winner);
+      assert_eq!(imported.start, replay.start);
+      let actions = |replay: &Replay| replay.plies.iter().map(|ply| (ply.player, ply.action, ply.roll, ply.drawn)).collect::<Vec<_>>();
+      assert_eq!(actions(&imported), actions(&replay));
+      let end = replay.plies.len();
+      assert_eq!(
+         StateSnapshot::new(&imported.state_at(end, board.layout).unwrap().1),
+         StateSnapshot::new(&replay.state_at(end, board.layout).unwrap().1),
+      );
+
+      // Outcomes the engine can't give are refused
+      let roll = record.moves.iter().position(|played| matches!(played.outcome, Some(Outcome::Rolled(_)))).unwrap();
+      let mut wrong = record.clone();
+      wrong.moves[roll].outcome = Some(Outcome::Rolled(13));
+      assert!(wrong.to_replay().unwrap_err().starts_with(&format!("Move {}:", roll + 1)));
+      wrong.moves[roll].outcome = None;
+      assert!(wrong.to_replay().is_err());
+   }
+   assert_eq!(parse_action("TradeBankBundle brick=2 wool=1 for ore=1"), Ok(Action::TradeBankBundle {
+      trades: Resources::new_one(Resource::Brick, 2) + Resources::new_one(Resource::Wool, 1),
+      asked: Resources::new_one(Resource::Ore, 1),
+   }));
+   for action in [Action::MoveThief { hex: Coord::new(-2, 4), victim: PlayerId::NONE }, Action::Keep { resources: Resources::ZERO }, Action::ProposeTrade { give: Resources::ZERO, receive: Resources::new_one(Resource::Grain, 1) }] {
+      assert_eq!(parse_action(&write_action(action)), Ok(action));
+   }
+}
+
+#[test]
+fn crash_dumps() {
+   /// Observer panicking at the first robber move
+   struct Crash;
+   impl GameObserver for Crash {
+      fn event(&mut self, event: &GameEvent, _state: &State) {
+         assert!(!matches!(event, GameEvent::RobberMoved { .. }), "Robber moved");
+      }
+   }
+   let directory = std::env::temp_dir().join(format!("catan-crash-dumps-{}", std::process::id()));
+   let mut game = Game::new();
+   for seed in 0..3 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   game.subscribe(Box::new(Crash));
+   game.record_replays = true;
+   game.crash_dumps = Some(directory.clone());
+   let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| game.setup_and_play_with_rng(&mut SmallRng::seed_from_u64(0)))).unwrap_err();
+   let message = panic.downcast_ref::<String>().unwrap();
+   assert!(message.starts_with("Robber moved (crash dump written to "), "{}", message);
+   let path = &message["Robber moved (crash dump written to ".len()..message.len() - 1];
+   let dump = CrashDump::from_json(&fs::read_to_string(path).unwrap()).unwrap();
+   assert_eq!(dump.message, "Robber moved");
+   assert!(matches!(dump.action, Some(Action::MoveThief { .. })));
+   assert!(dump.phase.is_thief());
+   assert_eq!(dump.history.len(), CRASH_HISTORY);
+   assert!(dump.state.is_some());
+   // The replay plays the game back to the action which panicked
+   let replay = dump.replay.unwrap();
+   assert_eq!(replay.plies.last().map(|ply| ply.action), dump.action);
+   assert_eq!(&replay.plies[replay.plies.len() - 1 - CRASH_HISTORY..replay.plies.len() - 1].iter().map(|ply| (ply.player, ply.action)).collect::<Vec<_>>(), &dump.history);
+   fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn simulator_undoes_actions() {
+   use rand::seq::IndexedRandom;
+
+   let mut rng = SmallRng::seed_from_u64(4);
+   let state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let mut simulator = Simulator::new(Phase::START_GAME, state, 4);
+   assert!(simulator.apply_action(Action::EndTurn).is_err());
+   assert!(!simulator.undo_action());
+   let mut checkpoints = vec![simulator.checkpoint()];
+   while simulator.winner().is_none() && simulator.depth() < 2000 {
+      let legal_actions = simulator.legal_actions();
+      let action = *legal_actions.choose(&mut rng).unwrap();
+      simulator.apply_action(action).unwrap();
+      checkpoints.push(simulator.checkpoint());
+   }
+   assert!(simulator.depth() > 100);
+
+   // The determinized copy only changes what the player can't see
+   let copy = simulator.determinized(PlayerId::FIRST, 0);
+   assert_eq!(copy.phase(), simulator.phase());
+   assert_eq!(copy.state().get_player_hand(PlayerId::FIRST), simulator.state().get_player_hand(PlayerId::FIRST));
+   assert_eq!(copy.depth(), 0);
+
+   while let Some(checkpoint) = checkpoints.pop() {
+      assert_eq!(simulator.checkpoint(), checkpoint);
+      assert_eq!(simulator.undo_action(), !checkpoints.is_empty());
+   }
+   assert_eq!(simulator.depth(), 0);
+}
+
+#[test]
+fn game_events() {
+   let mut game = Game::new();
+   for seed in 0..4 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   let log = EventLog::new();
+   game.subscribe(Box::new(log.clone()));
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 4);
+   let winner = match game.play(&mut rng, &mut state, vec![0, 1, 2, 3]) {
+      Notification::GameFinished { winner } => winner,
+      notif => panic!("Unexpected end {:?}", notif),
+   };
+   let events = log.take();
+   assert!(log.take().is_empty());
+   assert_eq!(events.last(), Some(&GameEvent::GameEnded { winner }));
+   let count = |matches: fn(&GameEvent) -> bool| events.iter().filter(|event| matches(event)).count();
+   assert_eq!(count(|event| matches!(event, GameEvent::DiceRolled { .. })), game.dice.game.total() as usize);
+   let roads = state.get_layout().paths.iter().filter(|path| state.get_dynamic_path(**path).unwrap().is_some()).count();
+   assert_eq!(count(|event| matches!(event, GameEvent::BuildingPlaced { building: Building::Road, .. })), roads);
+   assert_eq!(
+      count(|event| matches!(event, GameEvent::BuildingPlaced { building: Building::Settlement, .. } | GameEvent::BuildingPlaced { building: Building::City, .. })),
+      state.get_building_history().placements().len(),
+   );
+   let transfers = state.get_ledger().transfers();
+   let kind_count = |kind: TransferKind| transfers.iter().filter(|transfer| transfer.kind == kind).count();
+   assert_eq!(count(|event| matches!(event, GameEvent::CardStolen { resource: Some(_), .. })), kind_count(TransferKind::Steal));
+   assert_eq!(count(|event| matches!(event, GameEvent::TradeExecuted { .. })), (kind_count(TransferKind::TradeBank) + kind_count(TransferKind::TradePlayer)) / 2);
+   let production: Vec<(PlayerId, Resources)> = events.iter().flat_map(|event| match event {
+      GameEvent::ResourcesDistributed { roll: _, resources } => resources.iter().enumerate()
+         .filter(|(_, resources)| resources.total() > 0)
+         .map(|(p, resources)| (PlayerId::from(p), *resources))
+         .collect(),
+      _ => Vec::new(),
+   }).collect();
+   let produced: Vec<(PlayerId, Resources)> = transfers.iter()
+      .filter(|transfer| transfer.kind == TransferKind::Production)
+      .map(|transfer| (transfer.to, transfer.resources))
+      .collect();
+   assert_eq!(production, produced);
+   let holder = events.iter().rev().find_map(|event| match event {
+      GameEvent::LongestRoadChanged { holder } => Some(*holder),
+      _ => None,
+   });
+   assert_eq!(holder.flatten(), state.get_longest_road());
+   // Cards stolen between other players are hidden
+   let (thief, victim) = events.iter().find_map(|event| match event {
+      GameEvent::CardStolen { thief, victim, resource: _ } => Some((*thief, *victim)),
+      _ => None,
+   }).unwrap();
+   let stolen = GameEvent::CardStolen { thief, victim, resource: Some(Resource::Ore) };
+   let other = (0..4u8).map(PlayerId::from).find(|player| *player != thief && *player != victim).unwrap();
+   assert_eq!(stolen.seen_by(other, &Rules::STANDARD), GameEvent::CardStolen { thief, victim, resource: None });
+   assert_eq!(stolen.seen_by(victim, &Rules::STANDARD), stolen);
+   assert_eq!(stolen.seen_by(other, &Rules { hidden_hands: false, ..Rules::STANDARD }), stolen);
+}
+
+#[test]
+fn game_stats() {
+   let mut game = Game::new();
+   for seed in 0..4 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   let mut rng = SmallRng::seed_from_u64(1);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 4);
+   game.play(&mut rng, &mut state, vec![0, 1, 2, 3]);
+   assert!(game.stats.is_none());
+   game.collect_stats = true;
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 4);
+   let winner = match game.play(&mut rng, &mut state, vec![0, 1, 2, 3]) {
+      Notification::GameFinished { winner } => winner,
+      notif => panic!("Unexpected end {:?}", notif),
+   };
+   let stats = game.stats.as_ref().unwrap();
+   assert_eq!(stats.winner, Some(winner));
+   assert_eq!(stats.turns, state.get_turn());
+   assert_eq!(stats.dice, game.dice.game);
+   let transfers = state.get_ledger().transfers();
+   let kind_count = |kind: TransferKind| transfers.iter().filter(|transfer| transfer.kind == kind).count() as u32;
+   assert_eq!(stats.bank_trades.iter().sum::<u32>(), kind_count(TransferKind::TradeBank) / 2);
+   for (p, harbor_trades) in stats.harbor_trades.iter().enumerate() {
+      let received = transfers.iter()
+         .filter(|transfer| transfer.kind == TransferKind::TradeBank && transfer.to == PlayerId::from(p))
+         .map(|transfer| transfer.resources.total() as u32)
+         .sum::<u32>();
+      assert!(harbor_trades.iter().sum::<u32>() <= received);
+   }
+   assert_eq!(stats.player_trades.iter().sum::<u32>(), kind_count(TransferKind::TradePlayer));
+   // A knight winning the largest army may end the game before the thief moves
+   let knights = stats.development_played.iter().map(|played| played.knight as u32).sum::<u32>();
+   let placements = stats.robber_placements.len() as u32;
+   assert!(placements <= stats.dice.get(7) + knights && placements + 1 >= stats.dice.get(7) + knights);
+   for p in 0..4u8 {
+      let player = PlayerId::from(p);
+      let hand = state.get_player_hand(player);
+      assert_eq!(stats.development_played[player.to_usize()], hand.played_development_cards);
+      assert_eq!(stats.development_played[player.to_usize()].knight, hand.knights);
+      let net = state.get_ledger().net(player);
+      // Resources gained, less the ones traded, given to the thief, discarded and paid, are the ones held
+      for resource in Resource::ALL.iter() {
+         let lost = transfers.iter()
+            .filter(|transfer| transfer.from == player)
+            .map(|transfer| transfer.resources[*resource] as i32)
+            .sum::<i32>();
+         let traded = transfers.iter()
+            .filter(|transfer| transfer.to == player && (transfer.kind == TransferKind::TradeBank || transfer.kind == TransferKind::TradePlayer))
+            .map(|transfer| transfer.resources[*resource] as i32)
+            .sum::<i32>();
+         assert_eq!(stats.resources_gained[player.to_usize()][resource.to_usize()] as i32 + traded - lost, net[resource.to_usize()]);
+         assert_eq!(net[resource.to_usize()], hand.resources[*resource] as i32);
+      }
+   }
+}
+
+/// Allocations of a game are counted, with `cargo test --features alloc-counter allocations`
+///
+/// The counts include the allocations of the tests running alongside, so only their consistency is checked.
+#[cfg(feature = "alloc-counter")]
+#[test]
+fn allocations_per_game() {
+   use crate::alloc_counter::Allocations;
+   let mut game = Game::new();
+   for seed in 0..3 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   game.record_replays = true;
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let (_, allocations) = Allocations::during(|| game.play(&mut rng, &mut state, vec![0, 1, 2]));
+   let actions = game.replay.as_ref().unwrap().plies.len() as u64;
+   assert!(allocations.count > 0 && allocations.bytes > 0);
+   assert!(Allocations::now().count >= allocations.count);
+   assert!(allocations.per(actions) > 0.0);
+}
+
+/// Games of a seed are the same on every platform, the board, the dice, the enumeration of the actions and the players included
+///
+/// [SmallRng] being another generator on 32-bit platforms, the digests are only pinned on 64-bit ones.
+/// Seeded games are the same on every platform, iteration orders being the ones of ordered containers
+///
+/// The boards only depend on the setup, and their digests never change. The digests of the final states change with
+/// anything changing the games of a seed: a rule, the actions and their order in the action list, the draws of the players,
+/// or a field of [StateSnapshot]. Such a change updates them, after checking the games were meant to change.
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn seeded_games_are_pinned() {
+   let digests: Vec<(u64, u64)> = [("standard", 4, 7), ("extension", 6, 11)].iter().map(|(board, players, seed)| {
+      let mut game = Game::new();
+      game.board = BoardLayout::named(board).unwrap();
+      game.add_player(Box::new(Heuristic::new_seeded_player(*seed)));
+      for p in 1..*players {
+         game.add_player(Box::new(Randomy::new_seeded_player(seed + p)));
+      }
+      let mut rng = SmallRng::seed_from_u64(*seed);
+      let mut state: State = Box::new(TricellState::new(game.board.layout, *players as usize));
+      game.setup_and_play_on(&mut rng, &mut state);
+      let snapshot = StateSnapshot::new(&state);
+      (snapshot.board_digest(), snapshot.digest())
+   }).collect();
+   assert_eq!(digests.iter().map(|(board, _)| *board).collect::<Vec<_>>(), vec![0x69b8_b60b_8c7b_8338, 0x5a36_8709_d977_38ef]);
+   assert_eq!(digests.iter().map(|(_, state)| *state).collect::<Vec<_>>(), vec![0xb2f1_24f9_f636_0e85, 0xd4cd_e9ec_a178_e389]);
+}
+
+#[test]
+fn mcts_game() {
+   let config = MctsConfig { playouts: 4, playout_depth: 4, ..MctsConfig::DEFAULT };
+   let mut game = Game::new();
+   game.add_player(Box::new(Mcts::new_seeded_player(config, 0)));
+   game.add_player(Box::new(Randomy::new_seeded_player(1)));
+   game.add_player(Box::new(Randomy::new_seeded_player(2)));
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let notif = game.play(&mut rng, &mut state, vec![0, 1, 2]);
+   assert_ne!(notif, Notification::GameFinished{ winner: PlayerId::NONE });
+}
+
+#[test]
+fn search_log() {
+   let config = MctsConfig { playouts: 4, playout_depth: 4, ..MctsConfig::DEFAULT };
+   let log = SearchLog::new();
+   let mut game = Game::new();
+   game.record_replays = true;
+   game.add_player(Box::new(Mcts::new_logged_player(config, 0, log.clone())));
+   game.add_player(Box::new(Randomy::new_seeded_player(1)));
+   game.add_player(Box::new(Randomy::new_seeded_player(2)));
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   game.play(&mut rng, &mut state, vec![0, 1, 2]);
+   let records = log.take();
+   assert!(!records.is_empty() && log.take().is_empty());
+   let played: Vec<Action> = game.replay.unwrap().plies.iter().filter(|ply| ply.player == PlayerId::FIRST).map(|ply| ply.action).collect();
+   for record in records.iter() {
+      assert_eq!(record.player, PlayerId::FIRST);
+      assert!(record.actions.len() > 1 && record.actions.contains(&record.played) && played.contains(&record.played));
+      assert_eq!(record.visits.iter().sum::<u32>(), 4);
+      assert!(record.values.iter().all(|value| (0.0..=1.0).contains(value)));
+   }
+}
+
+#[test]
+fn search_tree_export() {
+   let config = MctsConfig { playouts: 20, playout_depth: 4, ..MctsConfig::DEFAULT };
+   let mut rng = SmallRng::seed_from_u64(0);
+   let state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   let tree = Mcts::search_tree(config, 0, &Phase::START_GAME, &state);
+   let root = &tree.nodes[0];
+   assert_eq!((root.action, root.visits), (None, 20));
+   assert_eq!(root.children.iter().map(|child| tree.nodes[*child].visits).sum::<u32>(), 20);
+   assert_eq!(tree, Mcts::search_tree(config, 0, &Phase::START_GAME, &state));
+   assert_eq!(serde_json::from_str::<SearchTree>(&tree.to_json()).unwrap(), tree);
+   let dot = tree.to_dot(0);
+   assert!(dot.starts_with("digraph") && dot.matches(" -> ").count() == tree.nodes.len() - 1);
+   assert_eq!(tree.to_dot(u32::MAX).matches(" -> ").count(), 0);
+}
+
+#[test]
+fn blunder_report() {
+   let mut game = Game::new();
+   for seed in 0..3 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   game.record_replays = true;
+   game.setup_and_play_with_rng(&mut SmallRng::seed_from_u64(0));
+   let mut replay = game.replay.take().unwrap();
+   replay.plies.truncate(40);
+   replay.winner = None;
+   let config = MctsConfig { playouts: 60, playout_depth: 4, ..MctsConfig::DEFAULT };
+   let report = find_blunders(&replay, &layout::DEFAULT, config, 0, 0.0).unwrap();
+   assert!(report.scored > 0 && report.scored <= report.decisions && report.decisions <= replay.plies.len());
+   // Random players rarely play the action the search prefers
+   assert!(!report.blunders.is_empty());
+   assert!(report.blunders.iter().all(|blunder| blunder.played != blunder.preferred && blunder.drop() >= 0.0));
+   assert!(report.blunders.iter().all(|blunder| replay.plies[blunder.ply].action == blunder.played));
+   // Each decision is searched from its own seed, a higher threshold keeping the worst blunders
+   assert_eq!(find_blunders(&replay, &layout::DEFAULT, config, 0, 0.0).unwrap(), report);
+   let worst = find_blunders(&replay, &layout::DEFAULT, config, 0, 0.1).unwrap();
+   assert_eq!(worst.blunders, report.blunders.iter().filter(|blunder| blunder.drop() >= 0.1).cloned().collect::<Vec<_>>());
+   assert!(report.to_json().contains("\"blunders\":["));
+}
+
+#[test]
+fn tempered_players_game() {
+   let play = || {
+      let config = MctsConfig { playouts: 4, playout_depth: 4, temperature: 1.0, ..MctsConfig::DEFAULT };
+      let mut game = Game::new();
+      game.record_replays = true;
+      game.add_player(Box::new(Heuristic::new_tempered_player(5.0, 0)));
+      game.add_player(Box::new(Mcts::new_seeded_player(config, 1)));
+      game.add_player(Box::new(Randomy::new_seeded_player(2)));
+      let mut rng = SmallRng::seed_from_u64(0);
+      let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+      let notif = game.play(&mut rng, &mut state, vec![0, 1, 2]);
+      assert_ne!(notif, Notification::GameFinished{ winner: PlayerId::NONE });
+      game.replay.unwrap()
+   };
+   // Sampled actions still only depend on the seeds
+   assert_eq!(play(), play());
+}
+
+#[test]
+fn heuristic_beats_random() {
+   let mut wins = 0;
+   for seed in 0..10 {
+      let mut game = Game::new();
+      game.add_player(Box::new(Heuristic::new_seeded_player(seed)));
+      game.add_player(Box::new(Randomy::new_seeded_player(seed + 1)));
+      game.add_player(Box::new(Randomy::new_seeded_player(seed + 2)));
+      let mut rng = SmallRng::seed_from_u64(seed);
+      let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+      if game.play(&mut rng, &mut state, vec![0, 1, 2]) == (Notification::GameFinished { winner: PlayerId::FIRST }) {
+         wins += 1;
+      }
+   }
+   assert!(wins >= 8, "Heuristic player won {} of 10 games", wins);
+}
+
+#[test]
+fn epsilon_greedy_game() {
+   let play = |player: Box<dyn CatanPlayer>| {
+      let mut game = Game::new();
+      game.record_replays = true;
+      game.add_player(player);
+      game.add_player(Box::new(Randomy::new_seeded_player(1)));
+      game.add_player(Box::new(Randomy::new_seeded_player(2)));
+      let mut rng = SmallRng::seed_from_u64(0);
+      let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+      let notif = game.play(&mut rng, &mut state, vec![0, 1, 2]);
+      assert_ne!(notif, Notification::GameFinished{ winner: PlayerId::NONE });
+      game.replay.unwrap()
+   };
+   // Without exploration the wrapper plays as the inner player
+   assert_eq!(
+      play(Box::new(EpsilonGreedy::new_seeded(Heuristic::new_seeded_player(0), 0.0, 0))),
+      play(Box::new(Heuristic::new_seeded_player(0))),
+   );
+   play(Box::new(EpsilonGreedy::new_seeded(Heuristic::new_seeded_player(0), 1.0, 0)));
+}
+
+#[test]
+fn arena_tournament() {
+   let mut arena = Arena::new();
+   arena.seats = Some(3);
+   arena.add(PlayerInfo::new("heuristic"), |seed| Box::new(Heuristic::new_seeded_player(seed)));
+   arena.add(PlayerInfo::new("random 1"), |seed| Box::new(Randomy::new_seeded_player(seed)));
+   arena.add(PlayerInfo::new("random 2"), |seed| Box::new(Randomy::new_seeded_player(seed)));
+   arena.add(PlayerInfo::new("random 3"), |seed| Box::new(Randomy::new_seeded_player(seed)));
+   arena.threads = 3;
+   let report = arena.run(8, 0).unwrap();
+   arena.threads = 1;
+   assert_eq!(report, arena.run(8, 0).unwrap());
+   assert_eq!(report.interrupted(), 0);
+   // Four tables of three out of four entrants, each entrant sitting at three of them
+   assert_eq!(report.entrants.iter().map(|entrant| entrant.games).collect::<Vec<_>>(), vec![6, 6, 6, 6]);
+   assert_eq!(report.entrants.iter().map(|entrant| entrant.wins).sum::<u32>(), 8);
+   let elo: f64 = report.entrants.iter().map(|entrant| entrant.elo).sum();
+   assert!((elo - 4.0 * INITIAL_ELO).abs() < 1e-6);
+   assert!(report.average_length() > 0.0);
+   arena.seats = Some(5);
+   assert!(arena.run(1, 0).is_err());
+}
+
+/// Player leaving the game as soon as it is asked for an action
+struct Exiting;
+
+impl CatanPlayer for Exiting {
+   fn new_game(&mut self, _position: PlayerId, _state: &State) {}
+
+   fn pick_action(&mut self, _phase: &Phase, _state: &State) -> Action {
+      Action::Exit
+   }
+
+   fn bad_action(&mut self, _error: Error) {}
+
+   fn notify(&mut self, _notification: &Notification) {}
+
+   fn results(&mut self, _state: &State, _winner: PlayerId) {}
+}
+
+#[test]
+fn arena_games_without_result() {
+   let mut arena = Arena::new();
+   arena.threads = 2;
+   arena.add(PlayerInfo::new("heuristic"), |seed| Box::new(Heuristic::new_seeded_player(seed)));
+   arena.add(PlayerInfo::new("exiting"), |_| Box::new(Exiting));
+   // The games are reported without a result rather than ending the tournament
+   let report = arena.run(3, 0).unwrap();
+   assert_eq!(report.interrupted(), 3);
+   assert!(report.games.iter().all(|game| game.winner.is_none()));
+   assert!(report.entrants.iter().all(|entrant| entrant.games == 0 && entrant.elo == INITIAL_ELO));
+   assert_eq!(report.average_length(), 0.0);
+}
+
+#[test]
+fn hall_of_fame_ratings() {
+   let directory = std::env::temp_dir().join(format!("catan-hall-of-fame-{}", std::process::id()));
+   let _ = fs::remove_dir_all(&directory);
+   let mut hall = HallOfFame::open(&directory).unwrap();
+   fs::write(directory.join("model.onnx"), b"weights").unwrap();
+   hall.add_model("checkpoint", &directory.join("model.onnx")).unwrap();
+   hall.add_bot("heuristic", "heuristic").unwrap();
+   hall.add_bot("random", "random").unwrap();
+   assert!(hall.add_bot("random", "random").is_err());
+   assert_eq!(fs::read(hall.model_path(hall.member("checkpoint").unwrap()).unwrap()).unwrap(), b"weights");
+
+   let mut arena = Arena::new();
+   for member in hall.members().iter().filter(|member| member.bot.is_some()) {
+      let heuristic = member.bot.as_deref() == Some("heuristic");
+      arena.add_rated(PlayerInfo::new(&member.name), member.elo, move |seed| if heuristic {
+         Box::new(Heuristic::new_seeded_player(seed))
+      } else {
+         Box::new(Randomy::new_seeded_player(seed))
+      });
+   }
+   let report = arena.run(4, 0).unwrap();
+   hall.record(&report).unwrap();
+
+   // Ratings are saved, and seed the next tournaments
+   let reopened = HallOfFame::open(&directory).unwrap();
+   assert_eq!(reopened.members(), hall.members());
+   assert_eq!(reopened.member("heuristic").unwrap().games, 4);
+   assert_eq!(reopened.member("checkpoint").unwrap().elo, INITIAL_ELO);
+   assert_eq!(reopened.ranked()[0].name, "heuristic");
+   hall.remove("checkpoint").unwrap();
+   assert!(!directory.join("checkpoint.onnx").exists());
+   fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn arena_metrics() {
+   let directory = std::env::temp_dir().join(format!("catan-metrics-{}", std::process::id()));
+   let _ = fs::remove_dir_all(&directory);
+   fs::create_dir_all(&directory).unwrap();
+   let mut arena = Arena::new();
+   arena.add(PlayerInfo::new("heuristic"), |seed| Box::new(Heuristic::new_seeded_player(seed)));
+   arena.add(PlayerInfo::new("random, seeded"), |seed| Box::new(Randomy::new_seeded_player(seed)));
+   arena.set_metrics(metrics::open(directory.join("arena.csv")).unwrap());
+   arena.run(3, 0).unwrap();
+   arena.run(2, 3).unwrap();
+   let csv = fs::read_to_string(directory.join("arena.csv")).unwrap();
+   let rows: Vec<Vec<&str>> = csv.lines().skip(1).map(|line| line.splitn(3, ',').collect()).collect();
+   // Steps go on across tournaments, ratings being written at the end of each
+   assert_eq!(rows.iter().filter(|row| row[2].starts_with("arena/turns,")).count(), 5);
+   assert_eq!(rows.iter().filter(|row| row[2].trim_start_matches('"').starts_with("arena/elo/")).map(|row| row[1]).collect::<Vec<_>>(), vec!["3", "3", "5", "5"]);
+   assert!(rows.iter().any(|row| row[2].starts_with("\"arena/win_rate/random, seeded\",")));
+
+   // TFRecords of events, with their checksums
+   assert_eq!(metrics::crc32c(b"123456789"), 0xe306_9283);
+   let mut sink = metrics::TensorBoardSink::create(&directory).unwrap();
+   sink.scalar("arena/turns", 1, 80.0).unwrap();
+   sink.flush().unwrap();
+   let events = fs::read(sink.path()).unwrap();
+   let mut records = Vec::new();
+   let mut offset = 0;
+   while offset < events.len() {
+      let length = &events[offset..offset + 8];
+      assert_eq!(&events[offset + 8..offset + 12], &metrics::masked_crc32c(length).to_le_bytes());
+      let length = u64::from_le_bytes(std::convert::TryInto::try_into(length).unwrap()) as usize;
+      let data = &events[offset + 12..offset + 12 + length];
+      assert_eq!(&events[offset + 12 + length..offset + 16 + length], &metrics::masked_crc32c(data).to_le_bytes());
+      records.push(data.to_vec());
+      offset += 16 + length;
+   }
+   assert_eq!(records.len(), 2);
+   assert!(records[0].windows(13).any(|bytes| bytes == b"brain.Event:2"));
+   assert!(records[1].windows(11).any(|bytes| bytes == b"arena/turns"));
+   assert!(records[1].windows(4).any(|bytes| bytes == 80f32.to_le_bytes()));
+   fs::remove_dir_all(&directory).unwrap();
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn remote_player_game() {
+   use std::time::Duration;
+   use rand::Rng;
+   use tungstenite::Message;
+   use crate::server::Server;
+
+   // Hosts a seeded game for a client leaving its fifth observation unanswered until it is told of the timeout
+   let host = || {
+      let mut server = Server::bind("127.0.0.1:0").unwrap();
+      server.read_timeout = Some(Duration::from_millis(500));
+      let address = server.local_addr().unwrap();
+      let client = std::thread::spawn(move || {
+         let (mut socket, _) = tungstenite::connect(format!("ws://{}", address)).unwrap();
+         socket.send(Message::text(r#"{"type": "join", "name": "remote"}"#)).unwrap();
+         let mut rng = SmallRng::seed_from_u64(0);
+         let (mut observations, mut timeouts) = (0, Vec::new());
+         loop {
+            let message: serde_json::Value = serde_json::from_str(socket.read().unwrap().to_text().unwrap()).unwrap();
+            match message["type"].as_str().unwrap() {
+               "observation" => {
+                  observations += 1;
+                  if observations != 5 {
+                     let index = rng.random_range(0..message["legal_actions"].as_array().unwrap().len());
+                     socket.send(Message::text(serde_json::json!({ "index": index, "sequence": message["sequence"] }).to_string())).unwrap();
+                  }
+               }
+               "timeout" => {
+                  timeouts.push(serde_json::from_value::<Action>(message["action"].clone()).unwrap());
+                  // Too late, the answer is dropped
+                  socket.send(Message::text(serde_json::json!({ "index": 0, "sequence": 5 }).to_string())).unwrap();
+               }
+               "results" => return (message["victory_points"].as_array().unwrap().len(), timeouts),
+               _ => (),
+            }
+         }
+      });
+      let mut game = Game::new();
+      game.record_replays = true;
+      game.add_player(Box::new(Randomy::new_seeded_player(1)));
+      game.add_player(Box::new(Randomy::new_seeded_player(2)));
+      let notif = server.host(&mut game, 1, 3).unwrap();
+      assert_ne!(notif, Notification::GameFinished{ winner: PlayerId::NONE });
+      assert_eq!(game.infos[2].name, "remote");
+      let (players, timeouts) = client.join().unwrap();
+      assert_eq!(players, 3);
+      assert_eq!(timeouts.len(), 1);
+      game.replay.take().unwrap()
+   };
+   // Seeded, the game is played again the same way
+   assert_eq!(host().plies, host().plies);
+}
+
+#[test]
+fn descriptions_are_translated() {
+   assert_eq!(Lang::from_code("fr"), Some(Lang::Fr));
+   assert_eq!(Lang::from_code("xx"), None);
+   let trade = Action::TradeBankBundle { trades: Resources::new(2, 0, 1, 0, 0), asked: Resources::new(0, 0, 0, 3, 0) };
+   assert_eq!(trade.describe(Lang::En), "Trade 2 brick, 1 ore for 3 grain with the bank");
+   assert_eq!(trade.describe(Lang::Fr), "Échanger 2 argile, 1 minerai contre 3 blé avec la banque");
+   let thief = Action::MoveThief { hex: Coord::new(1, -1), victim: PlayerId::from(2u8) };
+   assert_eq!(thief.describe(Lang::En), "Move the thief to (1,-1) and steal from player 2");
+
+   let stolen = GameEvent::CardStolen { thief: PlayerId::FIRST, victim: PlayerId::from(1u8), resource: None };
+   assert_eq!(stolen.describe(Lang::En), "player 0 stole a card from player 1");
+   let played = Notification::ActionPlayed { by: PlayerId::FIRST, action: Action::RollDice };
+   assert_eq!(played.describe(Lang::Fr), "joueur 0 : Lancer les dés");
+
+   // Every action of the mask has a description in every language
+   let mut rng = SmallRng::seed_from_u64(0);
+   let state = setup::random_default::<TricellState, SmallRng>(&mut rng, 2);
+   let mut actions = Vec::new();
+   generate_possible_actions(&mut actions, PlayerId::FIRST, &state);
+   for lang in Lang::ALL.iter() {
+      assert!(actions.iter().all(|action| !action.describe(*lang).is_empty()));
+   }
+}
+
+#[cfg(feature = "onnx")]
+#[test]
+fn onnx_win_estimator() {
+   use prost::Message;
+   use tract_onnx::pb::{ModelProto, GraphProto, NodeProto, TensorProto, ValueInfoProto, OperatorSetIdProto, TypeProto, TensorShapeProto, type_proto};
+   use tract_onnx::pb::tensor_shape_proto::{Dimension, dimension};
+   use crate::game::{OnnxEstimator, WinProbEstimator, WinFeatures};
+
+   // The default logistic model written as an ONNX graph: sigmoid(features x weights + bias)
+   let node = |op_type: &str, input: &[&str], output: &str| NodeProto {
+      op_type: op_type.to_string(),
+      input: input.iter().map(|name| name.to_string()).collect(),
+      output: vec![output.to_string()],
+      ..NodeProto::default()
+   };
+   let tensor = |name: &str, dims: Vec<i64>, float_data: Vec<f32>| TensorProto { name: name.to_string(), dims, data_type: 1, float_data, ..TensorProto::default() };
+   // Float tensors, the input holding the features of a player per row
+   let value = |name: &str, shape: Option<TensorShapeProto>| ValueInfoProto {
+      name: name.to_string(),
+      r#type: Some(TypeProto {
+         value: Some(type_proto::Value::TensorType(type_proto::Tensor { elem_type: 1, shape })),
+         ..TypeProto::default()
+      }),
+      ..ValueInfoProto::default()
+   };
+   let rows = TensorShapeProto { dim: vec![
+      Dimension { value: Some(dimension::Value::DimParam("players".to_string())), ..Dimension::default() },
+      Dimension { value: Some(dimension::Value::DimValue(WinFeatures::COUNT as i64)), ..Dimension::default() },
+   ]};
+   let model = ModelProto {
+      ir_version: 7,
+      opset_import: vec![OperatorSetIdProto { domain: String::new(), version: 13 }],
+      graph: Some(GraphProto {
+         node: vec![node("MatMul", &["features", "weights"], "z"), node("Add", &["z", "bias"], "logit"), node("Sigmoid", &["logit"], "scores")],
+         initializer: vec![
+            tensor("weights", vec![WinFeatures::COUNT as i64, 1], LogisticEstimator::DEFAULT.weights.to_vec()),
+            tensor("bias", vec![1], vec![LogisticEstimator::DEFAULT.bias]),
+         ],
+         input: vec![value("features", Some(rows))],
+         output: vec![value("scores", None)],
+         ..GraphProto::default()
+      }),
+      ..ModelProto::default()
+   };
+   let path = std::env::temp_dir().join(format!("catan_win_estimator_{}.onnx", std::process::id()));
+   fs::write(&path, model.encode_to_vec()).unwrap();
+
+   let estimator = OnnxEstimator::from_path(&path, 4).unwrap();
+   let mut game = Game::new();
+   for seed in 0..4 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 4);
+   game.play(&mut rng, &mut state, vec![0, 1, 2, 3]);
+   let (onnx, logistic) = (estimator.estimate(&state), LogisticEstimator::DEFAULT.estimate(&state));
+   assert!(onnx.iter().zip(logistic.iter()).all(|(a, b)| (a - b).abs() < 1e-5), "{:?} {:?}", onnx, logistic);
+   // The model only estimates games of its number of players
+   let three = setup::random_default::<TricellState, SmallRng>(&mut rng, 3);
+   assert!(estimator.scores(&three).is_err());
+   fs::write(&path, b"not a model").unwrap();
+   assert!(OnnxEstimator::from_path(&path, 4).is_err());
+   fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "database")]
+#[test]
+fn game_database() {
+   use crate::database::{GameDatabase, GameQuery};
+
+   let mut arena = Arena::new();
+   arena.seats = Some(2);
+   arena.threads = 1;
+   arena.add(PlayerInfo::new("heuristic"), |seed| Box::new(Heuristic::new_seeded_player(seed)));
+   arena.add(PlayerInfo::new("random 1"), |seed| Box::new(Randomy::new_seeded_player(seed)));
+   arena.add(PlayerInfo::new("random 2"), |seed| Box::new(Randomy::new_seeded_player(seed)));
+   let report = arena.run(6, 0).unwrap();
+
+   let path = std::env::temp_dir().join(format!("catan_database_{}.sqlite", std::process::id()));
+   let mut database = GameDatabase::open(&path).unwrap();
+   let id = database.insert_tournament("round robin", &report).unwrap();
+   let alone = database.insert_game(&report.games[0], &["heuristic", "random 1", "random 2"]).unwrap();
+   drop(database);
+
+   // Tournaments come back whole from the file
+   let database = GameDatabase::open(&path).unwrap();
+   assert_eq!(database.tournaments().unwrap(), vec![(id, "round robin".to_string())]);
+   let stored = database.tournament(id).unwrap().unwrap();
+   assert_eq!(stored.report, report);
+   assert_eq!(database.tournament(id + 1).unwrap(), None);
+
+   let games = database.games(&GameQuery { players: vec!["heuristic".to_string(), "random 2".to_string()], ..GameQuery::default() }).unwrap();
+   assert_eq!(games.len(), 2);
+   assert!(games.iter().all(|game| game.players.contains(&"heuristic".to_string()) && game.players.contains(&"random 2".to_string())));
+   assert_eq!(games[0].tournament, Some(id));
+   let rules = GameQuery { rules: Some(report.games[0].version.rules), ..GameQuery::default() };
+   assert_eq!(database.games(&rules).unwrap().len(), 7);
+   assert!(database.games(&GameQuery { rules: Some(0), ..GameQuery::default() }).unwrap().is_empty());
+   let board = database.games(&GameQuery { board: Some(report.games[0].board), ..GameQuery::default() }).unwrap();
+   assert_eq!(board.iter().map(|game| game.id).next_back(), Some(alone));
+   assert_eq!(board[0].record, report.games[0]);
+   assert_eq!(database.games(&GameQuery { limit: Some(3), ..GameQuery::default() }).unwrap().len(), 3);
+   fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "database")]
+#[test]
+fn database_positions() {
+   use crate::database::{GameDatabase, GameQuery, PositionQuery};
+
+   let mut arena = Arena::new();
+   arena.threads = 1;
+   arena.record_replays = true;
+   arena.add(PlayerInfo::new("heuristic"), |seed| Box::new(Heuristic::new_seeded_player(seed)));
+   arena.add(PlayerInfo::new("random"), |seed| Box::new(Randomy::new_seeded_player(seed)));
+   let report = arena.run(3, 0).unwrap();
+   let mut database = GameDatabase::in_memory().unwrap();
+   database.insert_tournament("replays", &report).unwrap();
+   let games = database.games(&GameQuery::default()).unwrap();
+   assert_eq!(games.iter().map(|game| game.record.clone()).collect::<Vec<_>>(), report.games);
+
+   let query = PositionQuery {
+      turns: Some((10, 30)),
+      vp_difference: Some((-1, 1)),
+      actions: vec![ActionCategory::BuildSettlement, ActionCategory::BuildCity],
+      ..PositionQuery::default()
+   };
+   let layout = layout::STANDARD_BOARD.layout;
+   let positions = database.sample_positions(&query, 1000, layout, &mut SmallRng::seed_from_u64(0)).unwrap();
+   assert!(!positions.is_empty());
+   for position in positions.iter() {
+      let state = position.checkpoint.state.restore::<TricellState>(layout).unwrap();
+      assert!((10..=30).contains(&state.get_turn()));
+      assert!(matches!(position.action, Action::BuildSettlement { .. } | Action::BuildCity { .. }));
+      assert_eq!(position.checkpoint.phase.player(), position.player);
+      assert!(legal::legal(&position.checkpoint.phase, &state, position.action).is_ok());
+   }
+   // Samples are drawn among every matching position, the same for a seed
+   let sample = database.sample_positions(&query, 3, layout, &mut SmallRng::seed_from_u64(1)).unwrap();
+   assert_eq!(sample.len(), 3.min(positions.len()));
+   assert!(sample.iter().all(|position| positions.contains(position)));
+   assert_eq!(sample, database.sample_positions(&query, 3, layout, &mut SmallRng::seed_from_u64(1)).unwrap());
+}
+
+#[test]
+fn state_diffs() {
+   use crate::state::diff;
+
+   let mut game = Game::new();
+   for seed in 0..2 {
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+   }
+   game.record_replays = true;
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = setup::random_default::<TricellState, SmallRng>(&mut rng, 2);
+   game.play(&mut rng, &mut state, vec![0, 1]);
+   let replay = game.replay.take().unwrap();
+   let layout = state.get_layout();
+   let (_, start) = replay.state_at(0, layout).unwrap();
+   let (_, placed) = replay.state_at(2, layout).unwrap();
+
+   // The first settlement and road show on the board of the second state only
+   assert!(diff::differences(&start, &start).is_empty());
+   let differences = diff::differences(&start, &placed);
+   assert!(differences.iter().any(|line| line.starts_with("road ") && line.ends_with("None -> Some(PlayerId(0))")));
+   assert!(differences.iter().any(|line| line.starts_with("building ")));
+   assert!(differences.contains(&"player 0 total vp: 0 -> 1".to_string()));
+   assert!(!diff::board_text(&start).contains("(0)") && diff::board_text(&placed).contains("(0)"));
+   let text = diff::side_by_side(&start, &placed, false);
+   let board_lines = diff::board_text(&start).lines().count();
+   assert!(text.lines().take(board_lines).any(|line| line.contains(" * ")));
+   assert!(text.lines().skip(board_lines).eq(differences.iter().map(|line| line.as_str())));
+   assert!(diff::side_by_side(&start, &placed, true).contains("\x1b[7m"));
+   assert!(!diff::side_by_side(&start, &start, true).contains("\x1b[7m"));
+}
@@ -0,0 +1,320 @@
+//! Loader for the rules scenarios of `tests/rules`
+//!
+//! A scenario file is a list of commands, one per line, `#` starting a comment.
+//! Each `scenario <name>` line starts a new scenario on an empty board.
+//!
+//! Setup commands:
+//! - `players <count>`
+//! - `hex <x>,<y> <resource> <number>` or `hex <x>,<y> desert`
+//! - `harbor <x>,<y> generic` or `harbor <x>,<y> <resource>`
+//! - `thief <x>,<y>`
+//! - `settlement <player> <x>,<y>`, `city <player> <x>,<y>` and `road <player> <x>,<y>`
+//! - `resources <player> <resource>=<count>...` sets the whole hand
+//! - `development <player> <card>=<count>...` sets the playable development cards
+//! - `bank <resource>=<count>...` only changes the given resources
+//! - `pieces <player> road|settlement|city=<count>...` sets the pieces left
+//! - `rules road_building_fallback=forfeit|forbid roll_before_building=on|off city_reservation=on|off resigned_pieces=frozen|removed token_swap_every=<turns> trade_proposals_per_turn=<count> setup_resources=second|first|both|none cap_<card>=<count>|none`
+//! - `turn <number>` sets the turns ended so far
+//! - `phase initial <player> first|second settlement|road`
+//! - `phase turn <player> preroll|free|thief [ready|knight|road_building|year_of_plenty|played]`
+//! - `phase discard <player> <discarding player>...`
+//!
+//! Check commands:
+//! - `legal <action>` and `illegal <action> [=> <error name>]`
+//! - `play <action>` checks the action is legal and applies it
+//! - `expect road <player> <length>`, `expect longest_road <player>|none`
+//! - `expect resources <player> <resource>=<count>...`, `expect vp <player> <count>`
+//! - `expect player <player>` checks the player to decide, `expect winner <player>|none` the winner of the game
+//! - `expect number <x>,<y> <number>` checks the number token of a producing hex
+//! - `expect harbor_trades <player> generic|<resource>=<count>...` checks the trades made at each harbor rate
+//! - `expect answers <player> accepted|rejected|countered...` checks the answers to trade offers recorded for the player
+//!
+//! Actions are written as their variant name followed by their parameters,
+//! like `BuildRoad 1,1`, `TradeBank brick ore`, `TradeBankBundle brick=1 wool=1 for ore=2`, `ProposeTrade brick=1 for ore=1`, `MoveThief 0,0 1`, `Keep brick=2 ore=1` or `Discard wool`
+
+use std::fs;
+use std::path::Path;
+
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use crate::board::layout;
+use crate::game::{Phase, TurnPhase, DevelopmentPhase, RoadBuildingFallback, ResignedPieces, SetupResources, TRANSITIONS, legal};
+use crate::game::apply;
+use crate::state::{State, StateMaker, TricellState, PlayerId};
+use crate::notation::{parse_coord, parse_player, parse_number, parse_resource, parse_card, parse_counts, parse_resources, arg, read_action, answer_name};
+use crate::utils::{Hex, LandHex, Harbor, DevelopmentCards};
+
+struct Scenario {
+    name: String,
+    state: State,
+    phase: Phase,
+    rng: SmallRng,
+}
+
+impl Scenario {
+    fn new(name: &str, player_count: u8) -> Scenario {
+        Scenario {
+            name: name.to_string(),
+            state: TricellState::new_empty(&layout::DEFAULT, player_count),
+            phase: Phase::Turn { player: PlayerId::FIRST, turn_phase: TurnPhase::Free, development_phase: DevelopmentPhase::Ready },
+            rng: SmallRng::seed_from_u64(0),
+        }
+    }
+}
+
+fn parse_phase(args: &[&str]) -> Result<Phase, String> {
+    let player = parse_player(arg(args, 1)?)?;
+    Ok(match arg(args, 0)? {
+        "initial" => Phase::InitialPlacement {
+            player,
+            placing_second: arg(args, 2)? == "second",
+            placing_road: arg(args, 3)? == "road",
+        },
+        "turn" => Phase::Turn {
+            player,
+            turn_phase: match arg(args, 2)? {
+                "preroll" => TurnPhase::PreRoll,
+                "free" => TurnPhase::Free,
+                "thief" => TurnPhase::MoveThief,
+                other => return Err(format!("Unknown turn phase {}", other)),
+            },
+            development_phase: match args.get(3).copied().unwrap_or("ready") {
+                "ready" => DevelopmentPhase::Ready,
+                "knight" => DevelopmentPhase::KnightActive,
+                "road_building" => DevelopmentPhase::RoadBuildingActive { two_left: true },
+                "year_of_plenty" => DevelopmentPhase::YearOfPlentyActive { two_left: true },
+                "played" => DevelopmentPhase::DevelopmentPlayed,
+                other => return Err(format!("Unknown development phase {}", other)),
+            },
+        },
+        "discard" => Phase::Turn {
+            player,
+            turn_phase: TurnPhase::Discard(parse_player(arg(args, 2)?)?),
+            development_phase: DevelopmentPhase::Ready,
+        },
+        other => return Err(format!("Unknown phase {}", other)),
+    })
+}
+
+fn expect<T: PartialEq + std::fmt::Debug>(what: &str, expected: T, actual: T) -> Result<(), String> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(format!("Expected {} {:?}, got {:?}", what, expected, actual))
+    }
+}
+
+fn run_command(scenario: &mut Scenario, command: &str, args: &[&str]) -> Result<(), String> {
+    let state = &mut scenario.state;
+    match command {
+        "hex" => {
+            let coord = parse_coord(arg(args, 0)?)?;
+            let hex = match arg(args, 1)? {
+                "desert" => LandHex::Desert,
+                res => LandHex::Prod(parse_resource(res)?, parse_number(arg(args, 2)?)? as u8),
+            };
+            state.set_static_hex(coord, Hex::Land(hex)).map_err(|e| format!("{:?}", e))?;
+        }
+        "harbor" => {
+            let coord = parse_coord(arg(args, 0)?)?;
+            let harbor = match arg(args, 1)? {
+                "generic" => Harbor::Generic,
+                res => Harbor::Special(parse_resource(res)?),
+            };
+            state.set_static_harbor(coord, harbor).map_err(|e| format!("{:?}", e))?;
+        }
+        "thief" => state.set_thief_hex(parse_coord(arg(args, 0)?)?),
+        "settlement" | "city" => {
+            let player = parse_player(arg(args, 0)?)?;
+            let coord = parse_coord(arg(args, 1)?)?;
+            let is_city = command == "city";
+            state.set_dynamic_intersection(coord, player, is_city).map_err(|e| format!("{:?}", e))?;
+            let harbor = state.get_static_harbor(coord).map_err(|e| format!("{:?}", e))?;
+            let hand = state.get_player_hand_mut(player);
+            if is_city {
+                hand.city_pieces -= 1;
+                hand.building_vp += 2;
+            } else {
+                hand.settlement_pieces -= 1;
+                hand.building_vp += 1;
+            }
+            hand.harbor.add(harbor);
+        }
+        "road" => {
+            let player = parse_player(arg(args, 0)?)?;
+            let coord = parse_coord(arg(args, 1)?)?;
+            state.set_dynamic_path(coord, player).map_err(|e| format!("{:?}", e))?;
+            state.get_player_hand_mut(player).road_pieces -= 1;
+            state.update_longest_road(player, coord);
+        }
+        "resources" => {
+            let player = parse_player(arg(args, 0)?)?;
+            state.get_player_hand_mut(player).resources = parse_resources(&args[1..])?;
+        }
+        "development" => {
+            let player = parse_player(arg(args, 0)?)?;
+            let mut cards = DevelopmentCards::new();
+            for (name, count) in parse_counts(&args[1..])? {
+                cards[parse_card(name)?] = count as u8;
+            }
+            state.get_player_hand_mut(player).development_cards = cards;
+        }
+        "bank" => {
+            for (name, count) in parse_counts(args)? {
+                state.get_bank_resources_mut()[parse_resource(name)?] = count;
+            }
+        }
+        "turn" => state.set_turn(parse_number(arg(args, 0)?)? as u32),
+        "pieces" => {
+            let hand = state.get_player_hand_mut(parse_player(arg(args, 0)?)?);
+            for (name, count) in parse_counts(&args[1..])? {
+                match name {
+                    "road" => hand.road_pieces = count as u8,
+                    "settlement" => hand.settlement_pieces = count as u8,
+                    "city" => hand.city_pieces = count as u8,
+                    _ => return Err(format!("Bad piece {}", name)),
+                }
+            }
+        }
+        "rules" => {
+            let mut rules = state.get_rules();
+            for pair in args {
+                match pair.split_once('=') {
+                    Some(("road_building_fallback", "forfeit")) => rules.road_building_fallback = RoadBuildingFallback::Forfeit,
+                    Some(("road_building_fallback", "forbid")) => rules.road_building_fallback = RoadBuildingFallback::Forbid,
+                    Some(("roll_before_building", "on")) => rules.roll_before_building = true,
+                    Some(("roll_before_building", "off")) => rules.roll_before_building = false,
+                    Some(("city_reservation", "on")) => rules.city_reservation = true,
+                    Some(("city_reservation", "off")) => rules.city_reservation = false,
+                    Some(("resigned_pieces", "frozen")) => rules.resigned_pieces = ResignedPieces::Frozen,
+                    Some(("resigned_pieces", "removed")) => rules.resigned_pieces = ResignedPieces::Removed,
+                    Some(("setup_resources", "second")) => rules.setup_resources = SetupResources::Second,
+                    Some(("setup_resources", "first")) => rules.setup_resources = SetupResources::First,
+                    Some(("setup_resources", "both")) => rules.setup_resources = SetupResources::Both,
+                    Some(("setup_resources", "none")) => rules.setup_resources = SetupResources::None,
+                    Some(("token_swap_every", turns)) => rules.token_swap_every = parse_number(turns)? as u32,
+                    Some(("trade_proposals_per_turn", count)) => rules.trade_proposals_per_turn = parse_number(count)? as u8,
+                    Some((key, cap)) if key.starts_with("cap_") => {
+                        let cap = if cap == "none" { None } else { Some(parse_number(cap)? as u8) };
+                        rules.development_caps.set_cap(parse_card(&key["cap_".len()..])?, cap)?;
+                    }
+                    _ => return Err(format!("Bad rule {}", pair)),
+                }
+            }
+            state.set_rules(rules);
+        }
+        "phase" => {
+            scenario.phase = parse_phase(args)?;
+            // The discarding players hold their discards in the order they make them
+            if arg(args, 0)? == "discard" {
+                let discards = args[2..].iter().map(|p| Ok((parse_player(p)?, None))).collect::<Result<_, String>>()?;
+                scenario.state.hold_discards(discards);
+            }
+        }
+        "legal" => {
+            let action = read_action(args)?;
+            legal::legal(&scenario.phase, state, action).map_err(|e| format!("{:?} should be legal: {:?}", action, e))?;
+        }
+        "illegal" => {
+            let (action, error_name) = match args.iter().position(|word| *word == "=>") {
+                Some(i) => (read_action(&args[..i])?, Some(arg(args, i + 1)?)),
+                None => (read_action(args)?, None),
+            };
+            match legal::legal(&scenario.phase, state, action) {
+                Ok(()) => return Err(format!("{:?} should be illegal", action)),
+                Err(error) => {
+                    if let Some(name) = error_name {
+                        if !format!("{:?}", error).starts_with(name) {
+                            return Err(format!("{:?} should fail with {}, got {:?}", action, name, error));
+                        }
+                    }
+                }
+            }
+        }
+        "play" => {
+            let action = read_action(args)?;
+            legal::legal(&scenario.phase, state, action).map_err(|e| format!("{:?} should be legal: {:?}", action, e))?;
+            let from = scenario.phase.kind();
+            apply(&mut scenario.phase, state, action, &mut scenario.rng);
+            let to = scenario.phase.kind();
+            if !TRANSITIONS.iter().any(|transition| transition.from == from && transition.action == action.category() && transition.to.contains(&to)) {
+                return Err(format!("{:?} from {:?} to {:?} isn't listed in the transitions", action, from, to));
+            }
+        }
+        "expect" => {
+            let what = arg(args, 0)?;
+            match what {
+                "road" => expect(what, parse_number(arg(args, 2)?)? as u8, state.get_player_hand(parse_player(arg(args, 1)?)?).continous_road)?,
+                "longest_road" => expect(what, parse_player(arg(args, 1)?)?.option(), state.get_longest_road().map(|(p, _)| p))?,
+                "resources" => expect(what, parse_resources(&args[2..])?, state.get_player_hand(parse_player(arg(args, 1)?)?).resources)?,
+                "vp" => expect(what, parse_number(arg(args, 2)?)? as u8, state.get_player_total_vp(parse_player(arg(args, 1)?)?))?,
+                "player" => expect(what, parse_player(arg(args, 1)?)?, scenario.phase.player())?,
+                "winner" => {
+                    let winner = match scenario.phase {
+                        Phase::FinishedGame { winner } => winner.option(),
+                        _ => None,
+                    };
+                    expect(what, parse_player(arg(args, 1)?)?.option(), winner)?
+                }
+                "number" => {
+                    let number = match state.get_static_hex(parse_coord(arg(args, 1)?)?).map_err(|e| format!("{:?}", e))? {
+                        Hex::Land(LandHex::Prod(_, number)) => Some(number),
+                        _ => None,
+                    };
+                    expect(what, Some(parse_number(arg(args, 2)?)? as u8), number)?
+                }
+                "harbor_trades" => {
+                    let mut trades = [0; Harbor::COUNT];
+                    for (name, count) in parse_counts(&args[2..])? {
+                        let harbor = if name == "generic" { Harbor::Generic } else { Harbor::Special(parse_resource(name)?) };
+                        trades[harbor.to_usize()] = count as u32;
+                    }
+                    expect(what, trades, state.get_ledger().harbor_usage(parse_player(arg(args, 1)?)?))?
+                }
+                "answers" => {
+                    let player = parse_player(arg(args, 1)?)?;
+                    let answers: Vec<&str> = state.get_ledger().trade_responses().iter()
+                        .filter(|response| response.responder == player)
+                        .map(|response| answer_name(response.answer))
+                        .collect();
+                    expect(what, args[2..].to_vec(), answers)?
+                }
+                other => return Err(format!("Unknown expectation {}", other)),
+            }
+        }
+        other => return Err(format!("Unknown command {}", other)),
+    }
+    Ok(())
+}
+
+/// Runs every scenario of a file, returning one message per failed line
+pub fn run_file(path: &Path) -> Vec<String> {
+    let content = fs::read_to_string(path).expect("Failed reading scenario file");
+    let mut failures = Vec::new();
+    let mut name = String::new();
+    let mut scenario: Option<Scenario> = None;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let location = format!("{}:{} [{}]", path.display(), i + 1, name);
+        match words.first() {
+            None => continue,
+            Some(&"scenario") => {
+                name = words[1..].join(" ");
+                scenario = None;
+            }
+            Some(&"players") => match parse_number(words.get(1).copied().unwrap_or("")) {
+                Ok(count) => scenario = Some(Scenario::new(&name, count as u8)),
+                Err(error) => failures.push(format!("{} {}", location, error)),
+            },
+            Some(command) => match scenario.as_mut() {
+                Some(scenario) => if let Err(error) = run_command(scenario, command, &words[1..]) {
+                    failures.push(format!("{} {}: {}", location, scenario.name, error));
+                },
+                None => failures.push(format!("{} {} before players", location, command)),
+            }
+        }
+    }
+    failures
+}
@@ -1,8 +1,9 @@
 use std::ops::{Index, IndexMut, AddAssign};
+use serde::{Serialize, Deserialize};
 
 /******* DevelopmentCard *******/
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum DevelopmentCard {
     Knight = 0,
     RoadBuilding = 1,
@@ -33,7 +34,7 @@ impl DevelopmentCard {
 
 /******* DevelopmentCards *******/
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct DevelopmentCards {
     pub knight: u8,
     pub road_building: u8,
@@ -66,6 +67,12 @@ impl DevelopmentCards {
     }
 }
 
+impl Default for DevelopmentCards {
+    fn default() -> Self {
+        DevelopmentCards::new()
+    }
+}
+
 impl Index<DevelopmentCard> for DevelopmentCards {
     type Output = u8;
 
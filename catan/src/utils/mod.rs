@@ -6,6 +6,8 @@ pub use resource::{Resource, Resources};
 pub use crate::board::{Coord, CoordType};
 pub use crate::state::PlayerId;
 
+use serde::{Serialize, Deserialize};
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Empty;
 
@@ -13,19 +15,19 @@ impl Empty {
     pub const INSTANCE: Empty = Empty {};
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Hex {
     Water,
     Land(LandHex),
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum LandHex {
     Prod(Resource, u8),
     Desert,
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Harbor {
     None,
     Generic,
@@ -42,6 +44,14 @@ impl Hex {
             _ => None,
         }
     }
+
+    /// Number of ways two dice can roll the hex's number token (0 for the desert and water)
+    pub fn get_pips(&self) -> u8 {
+        match self.get_num() {
+            Some(num) => 6 - (7 - num as i8).unsigned_abs(),
+            None => 0,
+        }
+    }
 }
 
 impl Harbor {
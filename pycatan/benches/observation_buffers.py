@@ -0,0 +1,86 @@
+"""Steps per second of the environments, returning new observation arrays or writing them into reused buffers
+
+    python pycatan/benches/observation_buffers.py [--steps STEPS] [--games GAMES]
+
+Actions are picked uniformly among the legal ones, with the same seed for both variants.
+With pycatan built with the `alloc-counter` feature (`maturin develop --features alloc-counter`),
+the allocations made per observation, by the game threads and the bindings, are reported too.
+"""
+
+import argparse
+import time
+
+import numpy as np
+
+import pycatan
+
+
+def single_play(env, rng, steps):
+    observation = env.reset(0)
+    for _ in range(steps):
+        if observation[-1]:
+            observation = env.reset()
+        observation = env.play(rng.choice(np.flatnonzero(observation[-2])))
+
+
+def single_step_into(env, rng, steps):
+    buffers = env.observation_buffers()
+    done = env.reset_into(buffers, 0)[-1]
+    for _ in range(steps):
+        if done:
+            done = env.reset_into(buffers)[-1]
+        done = env.step_into(rng.choice(np.flatnonzero(buffers[-1])), buffers)[-1]
+
+
+def pick_actions(rng, masks):
+    return [rng.choice(np.flatnonzero(mask)) for mask in masks]
+
+
+def vec_step(env, rng, steps):
+    observation = env.reset(0)
+    for _ in range(steps):
+        observation = env.step(pick_actions(rng, observation[-4]))
+
+
+def vec_step_into(env, rng, steps):
+    buffers = env.observation_buffers()
+    env.reset_into(buffers, 0)
+    for _ in range(steps):
+        env.step_into(pick_actions(rng, buffers[-4]), buffers)
+
+
+def measure(name, run, env, steps, observations):
+    allocations = pycatan.allocations()
+    start = time.perf_counter()
+    run(env, np.random.default_rng(0), steps)
+    elapsed = time.perf_counter() - start
+    env.close()
+    report = f"{name:24} {steps * observations / elapsed:10.0f} observations/s"
+    if allocations is not None:
+        end = pycatan.allocations()
+        count = (end["count"] - allocations["count"]) / (steps * observations)
+        size = (end["bytes"] - allocations["bytes"]) / (steps * observations)
+        report += f" {count:10.1f} allocations/observation {size:10.0f} bytes/observation"
+    print(report)
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("--steps", type=int, default=20000)
+    parser.add_argument("--games", type=int, default=16)
+    args = parser.parse_args()
+
+    for name, format in [
+        ("default", pycatan.PyObservationFormat()),
+        ("hidden+global", pycatan.PyObservationFormat(include_hidden=True, include_global=True)),
+    ]:
+        print(f"# {name} format")
+        measure("SingleEnvironment.play", single_play, pycatan.SingleEnvironment.new(format, seed=0), args.steps, 1)
+        measure("SingleEnvironment.step_into", single_step_into, pycatan.SingleEnvironment.new(format, seed=0), args.steps, 1)
+        vec_steps = args.steps // args.games
+        measure("VecEnvironment.step", vec_step, pycatan.VecEnvironment.new(format, args.games, seed=0), vec_steps, args.games)
+        measure("VecEnvironment.step_into", vec_step_into, pycatan.VecEnvironment.new(format, args.games, seed=0), vec_steps, args.games)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,512 @@
+# Type stubs of the pycatan extension module, packaged by maturin next to the compiled module.
+# Keep in sync with the #[pymethods] of src/.
+#
+# The classes are also registered in the submodules pycatan.env (environments, arena, simulator, observation format, rewards, game config)
+# and pycatan.actions (actions, discard codec and codec fixtures), pycatan.render holds
+# checkpoint_text(checkpoint: bytes, layout: str = "standard") -> str
+# checkpoint_diff(before: bytes, after: bytes, layout: str = "standard", color: bool = False) -> str, the boards side by side and their differences
+# and pycatan.notation converts replays from get_replay to the game notation of catan::notation and back:
+# export_record(replay: Union[str, bytes], layout: str = "standard", json: bool = False) -> str
+# import_record(record: str, binary: bool = False) -> Tuple[Union[str, bytes], str], the replay and its board,
+# and pycatan.rules explains the decision of a checkpoint from save_state:
+# why(checkpoint: bytes, layout: str = "standard") -> Dict[str, Any], with its kind, player, summary, next,
+# available action categories and transitions
+#
+# When the engine panics in a game thread, the RuntimeError raised gives the path of a JSON crash dump of the game,
+# written into $PYCATAN_CRASH_DUMPS, or else pycatan-crashes in the temporary directory
+
+from typing import Any, Awaitable, Callable, Dict, List, Optional, Tuple, Union
+
+import numpy as np
+import numpy.typing as npt
+
+Array = npt.NDArray[Any]
+BoolArray = npt.NDArray[np.bool_]
+
+# (player, board, flat, [hidden], [global], [hexes, intersections, paths], [tokens], [stage, stage_actions], [priors], [budget], [reward], [events], actions, done)
+# the optional elements being present as given by the observation format and the environment,
+# and every element but the player and done being None once the game is finished
+Observation = Tuple[Any, ...]
+
+Hand = Tuple[int, int, int, int, int]
+Coordinate = Tuple[int, int]
+# Counts by resource name: brick, lumber, ore, grain and wool
+ResourceCounts = Dict[str, int]
+# {"kind": "DiceRolled" | "ResourcesDistributed" | "RobberMoved" | "CardStolen" | "BuildingPlaced" | "TradeExecuted" | "LongestRoadChanged" | "GameEnded", ...},
+# players being relative to the player seeing the event
+Event = Dict[str, Any]
+# policy(board, flat, mask) -> action index, an opponent deciding from the observation of its seat
+Policy = Callable[[Array, Array, BoolArray], int]
+
+
+class PyObservationFormat:
+    def __new__(
+        cls,
+        half_width: Optional[int] = None,
+        half_height: Optional[int] = None,
+        # unused, the boards being always updated incrementally
+        use_python_state: bool = False,
+        include_hidden: bool = False,
+        include_building_age: bool = False,
+        include_deck: bool = False,
+        include_global: bool = False,
+        trade_bundles: bool = False,
+        include_stage: bool = False,
+        layout: str = "standard",
+        # checks that the legal actions, board and flat arrays don't read hidden information, leaving out the hidden and global arrays, slow
+        audit: bool = False,
+        include_graph: bool = False,
+        width: Optional[int] = None,
+        height: Optional[int] = None,
+        crop: bool = False,
+        include_tokens: bool = False,
+        # appends the resources surely held by each opponent and the percent chances of its cards, as deduced by the observer
+        include_beliefs: bool = False,
+        # appends the trade answers, steals and purchases of each opponent so far in the game
+        include_opponent_model: bool = False,
+        # adds the road building, year of plenty and monopole cards each opponent played to its block
+        include_played_development: bool = False,
+        # adds a board channel of the intersections the observer may settle on within this many more roads
+        settlement_reach: Optional[int] = None,
+        # adds the probabilities of a heuristic player to play each action, the softmax of its scores divided by this temperature
+        action_priors: Optional[float] = None,
+        # appends the fewest, most and expected (in hundredths) cards of each resource of each opponent, as deduced by the observer
+        include_card_counts: bool = False,
+        # appends the trade offers each player rejected, countered and accepted so far in the turn, the observer first
+        include_trade_answers: bool = False,
+    ) -> "PyObservationFormat": ...
+
+    def schema(self, players: int) -> Dict[str, List[str]]: ...
+    def map(self, coord: Tuple[int, int]) -> Tuple[int, int]: ...
+    def unmap(self, cell: Tuple[int, int]) -> Tuple[int, int]: ...
+    def hexes(self) -> List[Tuple[int, int]]: ...
+    def paths(self) -> List[Tuple[int, int]]: ...
+    def intersections(self) -> List[Tuple[int, int]]: ...
+    def graph_edges(self) -> Tuple[npt.NDArray[np.int64], npt.NDArray[np.int64]]: ...
+
+
+class PyDiscardCodec:
+    def __new__(cls, count: int) -> "PyDiscardCodec": ...
+    @staticmethod
+    def for_hand(hand: Hand) -> "PyDiscardCodec": ...
+    @property
+    def count(self) -> int: ...
+    def __len__(self) -> int: ...
+    def encode(self, discarded: Hand) -> int: ...
+    def decode(self, index: int) -> Hand: ...
+    def mask(self, hand: Hand) -> BoolArray: ...
+
+
+class Action:
+    @staticmethod
+    def end_turn() -> "Action": ...
+    @staticmethod
+    def roll_dice() -> "Action": ...
+    @staticmethod
+    def move_thief(hex: Coordinate, victim: int) -> "Action": ...
+    @staticmethod
+    def build_road(path: Coordinate) -> "Action": ...
+    @staticmethod
+    def build_settlement(intersection: Coordinate) -> "Action": ...
+    @staticmethod
+    def build_city(intersection: Coordinate) -> "Action": ...
+    # declares the settlement to upgrade on a later turn, with the city_reservation rule
+    @staticmethod
+    def reserve_city(intersection: Coordinate) -> "Action": ...
+    @staticmethod
+    def trade_bank(given: str, asked: str) -> "Action": ...
+    @staticmethod
+    def trade_bank_bundle(trades: ResourceCounts, asked: ResourceCounts) -> "Action": ...
+    @staticmethod
+    def propose_trade(give: ResourceCounts, receive: ResourceCounts) -> "Action": ...
+    @staticmethod
+    def accept_trade() -> "Action": ...
+    @staticmethod
+    def reject_trade() -> "Action": ...
+    @staticmethod
+    def counter_trade(give: ResourceCounts, receive: ResourceCounts) -> "Action": ...
+    # takes back the offer of the player, as it answers a counter offer
+    @staticmethod
+    def withdraw_trade() -> "Action": ...
+    # answers a counter offer with new terms, offered to the same opponent
+    @staticmethod
+    def revise_trade(give: ResourceCounts, receive: ResourceCounts) -> "Action": ...
+    @staticmethod
+    def buy_development() -> "Action": ...
+    @staticmethod
+    def play_knight() -> "Action": ...
+    @staticmethod
+    def play_road_building() -> "Action": ...
+    @staticmethod
+    def play_year_of_plenty() -> "Action": ...
+    @staticmethod
+    def choose_free_resource(resource: str) -> "Action": ...
+    @staticmethod
+    def play_monopole(resource: str) -> "Action": ...
+    @staticmethod
+    def keep(resources: ResourceCounts) -> "Action": ...
+    # discards one card, a discard being made either at once by keep or card by card
+    @staticmethod
+    def discard(resource: str) -> "Action": ...
+    @property
+    def kind(self) -> str: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+
+
+class PyRewardConfig:
+    def __new__(
+        cls,
+        vp_delta: float = 1.0,
+        win_bonus: float = 0.0,
+        income: float = 0.0,
+        building: float = 0.0,
+        illegal_penalty: float = 0.0,
+        normalize_vp: bool = False,
+        scale: float = 1.0,
+    ) -> "PyRewardConfig": ...
+
+
+class GameConfig:
+    def __new__(
+        cls,
+        victory_points: int = 10,
+        discard_limit: int = 7,
+        robber_from_turn: int = 0,
+        initial_resources: Optional[ResourceCounts] = None,
+        bank: Optional[ResourceCounts] = None,
+        development_cards: Optional[Dict[str, int]] = None,
+        # most cards of each kind a player can play in a game, by card name
+        development_caps: Optional[Dict[str, int]] = None,
+        # cities only upgrade the settlements reserved on an earlier turn
+        city_reservation: bool = False,
+        # "frozen" or "removed", the pieces of the players who resign
+        resigned_pieces: str = "frozen",
+        # turns between two number tokens swapping places, never when 0
+        token_swap_every: int = 0,
+        # observations only show the development deck size in buckets of 5 cards
+        bucketed_deck: bool = False,
+        # "second", "first", "both" or "none", the initial settlements giving the resources around them
+        setup_resources: str = "second",
+        # most trades the player whose turn it is can offer in a turn, unlimited when 0
+        trade_proposals_per_turn: int = 0,
+    ) -> "GameConfig": ...
+
+
+# Raised with illegal_actions="raise", args being (message, action index, legal action mask)
+class IllegalActionError(ValueError): ...
+
+
+class SingleEnvironment:
+    @staticmethod
+    def new(
+        format: PyObservationFormat,
+        opponents: int = 2,
+        curriculum: bool = False,
+        turn_budget: Optional[float] = None,
+        auto_resolve: bool = False,
+        seed: Optional[int] = None,
+        opponent: str = "random",
+        playouts: int = 100,
+        opponent_budget: Optional[float] = None,
+        rewards: Optional[PyRewardConfig] = None,
+        config: Optional[GameConfig] = None,
+        illegal_actions: str = "retry",
+        # opponent names or policies, or dicts with an "opponent" and optionally "playouts", "opponent_budget" and "opponent_temperature"
+        # or a HallOfFame, whose bots are the opponents
+        opponent_pool: Union[List[Union[str, Policy, Dict[str, Any]]], "HallOfFame", None] = None,
+        # "uniform" or "prioritized"
+        pool_sampling: str = "uniform",
+        opponent_temperature: float = 0.0,
+        events: bool = False,
+        # replaces opponent, an exception it raises being raised by the call waiting for the next observation
+        opponent_policy: Optional[Policy] = None,
+        stats: bool = False,
+        # root visits and values of the decisions of the mcts opponents, given by search_stats
+        search_stats: bool = False,
+    ) -> "SingleEnvironment": ...
+
+    def pool_win_rates(self) -> List[Optional[float]]: ...
+    def start(self) -> Observation: ...
+    def reset(self, seed: Optional[int] = None) -> Observation: ...
+    # (board, flat, [hidden], [global], [hexes, intersections, paths], [tokens], actions), zeroed
+    def observation_buffers(self) -> List[Array]: ...
+    # write the observation arrays into the buffers, returning (seat, [budget], [reward], [events], done)
+    def reset_into(self, buffers: List[Array], seed: Optional[int] = None) -> Tuple[Any, ...]: ...
+    def step_into(self, action: int, buffers: List[Array]) -> Tuple[Any, ...]: ...
+    def metrics(self) -> Dict[str, Any]: ...
+    def set_stage(self, stage: int) -> None: ...
+    def stage(self) -> int: ...
+    def stage_actions(self) -> List[npt.NDArray[np.uint16]]: ...
+    def describe_action(self, index: int) -> Dict[str, Any]: ...
+    def index_to_action(self, index: int) -> Action: ...
+    def action_to_index(self, action: Action) -> int: ...
+    def describe_legal_actions(self, mask: Union[BoolArray, List[bool]]) -> List[Dict[str, Any]]: ...
+    # thief (hexes, victims), road (paths), settlement and city (intersections) action indices, -1 when missing
+    def graph_actions(self) -> Dict[str, npt.NDArray[np.int64]]: ...
+    def play(self, action: int) -> Observation: ...
+    # awaitable play and reset for asyncio, run in the default executor of the running loop
+    def astep(self, action: int) -> Awaitable[Observation]: ...
+    def areset(self, seed: Optional[int] = None) -> Awaitable[Observation]: ...
+    # raises TimeoutError, the observation being then received by wait
+    def play_timeout(self, action: int, timeout_ms: int) -> Observation: ...
+    def wait(self, timeout_ms: Optional[int] = None) -> Observation: ...
+    def close(self) -> None: ...
+    def save_state(self) -> bytes: ...
+    def load_state(self, state: bytes) -> Observation: ...
+    # replays or records of games, resets resuming a position drawn among the ones from turn min_turn on, None starting new games again
+    def set_start_positions(self, games: Optional[List[bytes]], min_turn: int = 0) -> None: ...
+    def enable_replay(self) -> None: ...
+    # compact: bytes keeping only the actions and random draws, read back by Trajectory
+    def get_replay(self, binary: bool = False, compact: bool = False) -> Union[str, bytes, None]: ...
+    def result(self) -> Tuple[int, bool]: ...
+    def reward(self) -> Optional[float]: ...
+    def events(self) -> Optional[List[Event]]: ...
+    def development_usage(self) -> Dict[str, Any]: ...
+    # rolls, resources_gained, robber_placements, development_played, bank_trades, harbor_trades, player_trades, turns and winner, by seat
+    def stats(self) -> Optional[Dict[str, Any]]: ...
+    # seat, turn, actions (mask indices, None when missing), visits, values and played, by decision of the mcts opponents
+    def search_stats(self) -> Optional[List[Dict[str, Any]]]: ...
+
+
+class MultiEnvironment:
+    @staticmethod
+    def new(
+        format: PyObservationFormat,
+        players: int = 3,
+        curriculum: bool = False,
+        mirror: bool = False,
+        turn_budget: Optional[float] = None,
+        auto_resolve: bool = False,
+        seed: Optional[int] = None,
+        names: Optional[List[str]] = None,
+        metadata: Optional[List[Dict[str, str]]] = None,
+        rewards: Optional[PyRewardConfig] = None,
+        config: Optional[GameConfig] = None,
+        illegal_actions: str = "retry",
+        reward_scales: Optional[List[float]] = None,
+        events: bool = False,
+        cores: Optional[List[int]] = None,
+        nice: Optional[int] = None,
+        stats: bool = False,
+        # players controlled by each agent, observations then starting with the agent to answer them
+        agents: Optional[List[List[int]]] = None,
+    ) -> "MultiEnvironment": ...
+
+    def start(self) -> Observation: ...
+    def reset(self, seed: Optional[int] = None) -> Observation: ...
+    def metrics(self) -> Dict[str, Any]: ...
+    def seat_results(self) -> Dict[str, Any]: ...
+    def set_stage(self, stage: int) -> None: ...
+    def stage(self) -> int: ...
+    def stage_actions(self) -> List[npt.NDArray[np.uint16]]: ...
+    def describe_action(self, index: int) -> Dict[str, Any]: ...
+    def index_to_action(self, index: int) -> Action: ...
+    def action_to_index(self, action: Action) -> int: ...
+    def describe_legal_actions(self, mask: Union[BoolArray, List[bool]]) -> List[Dict[str, Any]]: ...
+    # thief (hexes, victims), road (paths), settlement and city (intersections) action indices, -1 when missing
+    def graph_actions(self) -> Dict[str, npt.NDArray[np.int64]]: ...
+    def play(self, player: int, action: int) -> Observation: ...
+    # plays for the player of the last observation, which the agent must control
+    def play_agent(self, agent: int, action: int) -> Observation: ...
+    def agent_players(self) -> Optional[List[List[int]]]: ...
+    # resigns the player deciding, the others playing on without it
+    def resign(self, player: int) -> Observation: ...
+    def astep(self, player: int, action: int) -> Awaitable[Observation]: ...
+    def areset(self, seed: Optional[int] = None) -> Awaitable[Observation]: ...
+    def play_timeout(self, player: int, action: int, timeout_ms: int) -> Observation: ...
+    def wait(self, timeout_ms: Optional[int] = None) -> Observation: ...
+    def close(self) -> None: ...
+    def result(self, detailed: bool = False) -> Union[Tuple[npt.NDArray[np.uint8], int], Dict[str, Any]]: ...
+    def development_usage(self) -> List[Dict[str, Any]]: ...
+
+
+class VecEnvironment:
+    @staticmethod
+    def new(
+        format: PyObservationFormat,
+        games: int,
+        opponents: int = 2,
+        auto_resolve: bool = False,
+        seed: Optional[int] = None,
+        opponent: str = "random",
+        playouts: int = 100,
+        opponent_budget: Optional[float] = None,
+        rewards: Optional[PyRewardConfig] = None,
+        config: Optional[GameConfig] = None,
+        illegal_actions: str = "retry",
+        opponent_temperature: float = 0.0,
+        # CSV file when ending with .csv, else directory of TensorBoard event files
+        metrics: Optional[str] = None,
+        # Cores the game threads are pinned to in turn, and their niceness on Linux, raising a RuntimeError when they can't be placed
+        cores: Optional[List[int]] = None,
+        nice: Optional[int] = None,
+    ) -> "VecEnvironment": ...
+
+    def __len__(self) -> int: ...
+    def describe_action(self, index: int) -> Dict[str, Any]: ...
+    def index_to_action(self, index: int) -> Action: ...
+    def action_to_index(self, action: Action) -> int: ...
+    def describe_legal_actions(self, mask: Union[BoolArray, List[bool]]) -> List[Dict[str, Any]]: ...
+    # thief (hexes, victims), road (paths), settlement and city (intersections) action indices, -1 when missing
+    def graph_actions(self) -> Dict[str, npt.NDArray[np.int64]]: ...
+    # (board, flat, [hidden], [global], [hexes, intersections, paths], [tokens], [priors], actions, done, won, victory_points, [rewards]), stacked
+    def reset(self, seed: Optional[int] = None) -> Tuple[Array, ...]: ...
+    def step(self, actions: List[int]) -> Tuple[Array, ...]: ...
+    def astep(self, actions: List[int]) -> Awaitable[Tuple[Array, ...]]: ...
+    def areset(self, seed: Optional[int] = None) -> Awaitable[Tuple[Array, ...]]: ...
+    # zeroed arrays shaped as the observations of step, which reset_into and step_into write into
+    def observation_buffers(self) -> List[Array]: ...
+    def reset_into(self, buffers: List[Array], seed: Optional[int] = None) -> None: ...
+    def step_into(self, actions: List[int], buffers: List[Array]) -> None: ...
+    def close(self) -> None: ...
+
+
+class GymEnvironment:
+    def __new__(
+        cls,
+        format: PyObservationFormat,
+        opponents: int = 2,
+        auto_resolve: bool = False,
+        seed: Optional[int] = None,
+        opponent: str = "random",
+        playouts: int = 100,
+        opponent_budget: Optional[float] = None,
+        rewards: Optional[PyRewardConfig] = None,
+        config: Optional[GameConfig] = None,
+        illegal_actions: str = "retry",
+        opponent_temperature: float = 0.0,
+    ) -> "GymEnvironment": ...
+
+    def observation_space_shapes(self) -> Dict[str, List[int]]: ...
+    def action_space_size(self) -> int: ...
+    def describe_action(self, index: int) -> Dict[str, Any]: ...
+    def index_to_action(self, index: int) -> Action: ...
+    def action_to_index(self, action: Action) -> int: ...
+    def describe_legal_actions(self, mask: Union[BoolArray, List[bool]]) -> List[Dict[str, Any]]: ...
+    # thief (hexes, victims), road (paths), settlement and city (intersections) action indices, -1 when missing
+    def graph_actions(self) -> Dict[str, npt.NDArray[np.int64]]: ...
+    def reset(self, seed: Optional[int] = None, options: Optional[Dict[str, Any]] = None) -> Tuple[Dict[str, Array], Dict[str, Any]]: ...
+    def step(self, action: int) -> Tuple[Dict[str, Array], float, bool, bool, Dict[str, Any]]: ...
+    def astep(self, action: int) -> Awaitable[Tuple[Dict[str, Array], float, bool, bool, Dict[str, Any]]]: ...
+    def areset(self, seed: Optional[int] = None, options: Optional[Dict[str, Any]] = None) -> Awaitable[Tuple[Dict[str, Array], Dict[str, Any]]]: ...
+    def close(self) -> None: ...
+
+
+class Arena:
+    # players are "random", "heuristic", "mcts" or callables returning an action index given an observation
+    def __new__(
+        cls,
+        format: PyObservationFormat,
+        players: List[Union[str, Callable[[Observation], int]]],
+        seats: Optional[int] = None,
+        threads: Optional[int] = None,
+        names: Optional[List[str]] = None,
+        playouts: int = 100,
+        opponent_budget: Optional[float] = None,
+        config: Optional[GameConfig] = None,
+        opponent_temperature: float = 0.0,
+        # members join the players, the policy of a model being loader(model_path)
+        hall_of_fame: Optional["HallOfFame"] = None,
+        loader: Optional[Callable[[str], Callable[[Observation], int]]] = None,
+        # CSV file when ending with .csv, else directory of TensorBoard event files
+        metrics: Optional[str] = None,
+        # games of each run are inserted with their replays as a tournament
+        database: Optional["GameDatabase"] = None,
+    ) -> "Arena": ...
+
+    # average_length, interrupted, players (name, games, wins, win_rate, average_vp, elo),
+    # games (seats, victory_points, winner, turns) and with a database the tournament id
+    def run(self, games: int, seed: int = 0, label: str = "arena") -> Dict[str, Any]: ...
+
+
+class HallOfFame:
+    def __new__(cls, directory: str) -> "HallOfFame": ...
+    @property
+    def directory(self) -> str: ...
+    # members are dicts with their name, model path or bot, elo, games and wins
+    def add_model(self, name: str, model: str) -> Dict[str, Any]: ...
+    def add_bot(self, name: str, bot: str) -> Dict[str, Any]: ...
+    def remove(self, name: str) -> None: ...
+    def members(self, ranked: bool = False) -> List[Dict[str, Any]]: ...
+    def __len__(self) -> int: ...
+
+
+class GameDatabase:
+    # SQLite file, created when missing
+    def __new__(cls, path: str) -> "GameDatabase": ...
+    def tournaments(self) -> List[Tuple[int, str]]: ...
+    # games are dicts of their id, tournament, players, victory_points, winner, turns, rules, board and whether they have a replay
+    def games(
+        self,
+        players: Optional[List[str]] = None,
+        rules: Optional[int] = None,
+        board: Optional[int] = None,
+        tournament: Optional[int] = None,
+        finished: bool = False,
+        limit: Optional[int] = None,
+    ) -> List[Dict[str, Any]]: ...
+    # positions are dicts of their game, ply, seat, turn, checkpoint, observation and action index,
+    # actions naming categories like "BuildSettlement"
+    def sample(
+        self,
+        count: int,
+        format: PyObservationFormat,
+        seed: int = 0,
+        turns: Optional[Tuple[int, int]] = None,
+        vp_difference: Optional[Tuple[int, int]] = None,
+        actions: Optional[List[str]] = None,
+        players: Optional[List[str]] = None,
+        rules: Optional[int] = None,
+        board: Optional[int] = None,
+        tournament: Optional[int] = None,
+    ) -> List[Dict[str, Any]]: ...
+
+
+class Simulator:
+    # position of a checkpoint from save_state, actions being the indices of the observations of the format
+    def __new__(cls, checkpoint: bytes, format: PyObservationFormat, seed: int = 0) -> "Simulator": ...
+    # seat of the player to decide, None once the game is finished
+    def player(self) -> Optional[int]: ...
+    def winner(self) -> Optional[int]: ...
+    def depth(self) -> int: ...
+    def legal_actions(self) -> BoolArray: ...
+    def observation(self) -> Optional[Observation]: ...
+    # raises an IllegalActionError when the action is illegal
+    def step(self, action: int) -> None: ...
+    def undo(self) -> bool: ...
+    def victory_points(self) -> List[int]: ...
+    # hidden hands and development cards of the other seats dealt again
+    def determinized(self, seat: int, seed: int = 0) -> "Simulator": ...
+    def save_state(self) -> bytes: ...
+
+
+class Trajectory:
+    # game of a compact replay from get_replay(compact=True), regenerating its observations on demand
+    def __new__(cls, compact: bytes, format: PyObservationFormat) -> "Trajectory": ...
+    def __len__(self) -> int: ...
+    def seating(self) -> List[int]: ...
+    def winner(self) -> Optional[int]: ...
+    def seat(self, ply: int) -> int: ...
+    # observation of the seat playing the ply, reading plies in order playing the game once
+    def observation(self, ply: int) -> Observation: ...
+    # index in the action mask of the action played, None when missing from it
+    def action(self, ply: int) -> Optional[int]: ...
+    def to_replay(self, binary: bool = False) -> Union[str, bytes]: ...
+
+
+# n SingleEnvironments of the keyword arguments of SingleEnvironment.new but seed, their seeds drawn from base_seed,
+# a list opponent_pool being shuffled for each of them
+def make_envs(n: int, base_seed: int, **cfg: Any) -> List[SingleEnvironment]: ...
+
+
+# Test vectors of the action codec of catan::codec and of the observations of the format, as JSON
+# {"codec": {"spec", "games", "vectors"}, "flat_names", "board_channel_names", "board_shape", "observations": [{"flat", "board"}]},
+# for other implementations to check themselves against
+def codec_fixtures(format: PyObservationFormat, players: int = 4, games: int = 2, positions: int = 20, seed: int = 0) -> str: ...
+# Differences between fixtures, possibly written by another implementation, and what pycatan computes in the format, empty when they agree
+def validate_codec_fixtures(fixtures: str, format: PyObservationFormat) -> List[str]: ...
+
+
+# allocations made so far by every thread, {"count": ..., "bytes": ...},
+# None unless pycatan was built with the alloc-counter feature
+def allocations() -> Optional[Dict[str, int]]: ...
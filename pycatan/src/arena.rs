@@ -0,0 +1,175 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::exceptions::{PyValueError, PyTypeError};
+use std::sync::{Arc, Mutex};
+
+use catan::arena::{Arena, PlayerFactory};
+use catan::metrics;
+use catan::player::PlayerInfo;
+use super::{PyObservationFormat, PyGameConfig};
+use super::py_game_config::game_config;
+use super::environment::Opponent;
+use super::callback_player::{CallbackPlayer, CallbackPolicy, PolicyInput};
+use super::hall_of_fame::PyHallOfFame;
+use super::database::PyGameDatabase;
+
+/// Tournament between bots and Python policies over seeded games, see [Arena]
+///
+/// Each player is `random`, `heuristic` or `mcts`, or a Python callable returning the index of its action
+/// given an observation `(seat, board, flat, ..., actions, done)` of the format.
+/// Games are played in parallel by `threads` threads, all the available ones by default,
+/// except with Python policies, whose games are played one after the other on the calling thread.
+#[pyclass(name="Arena")]
+pub struct PyArena {
+    arena: Arena,
+    /// First exception raised by a policy
+    error: Arc<Mutex<Option<PyErr>>>,
+    /// Hall of fame whose members play, recording their ratings
+    hall_of_fame: Option<Py<PyHallOfFame>>,
+    /// Database the games of each run are inserted into, with their replays
+    database: Option<Py<PyGameDatabase>>,
+}
+
+/// Player of a Python policy
+fn policy_factory(format: PyObservationFormat, policy: Bound<'_, PyAny>, error: &Arc<Mutex<Option<PyErr>>>) -> PlayerFactory {
+    let policy = Arc::new(CallbackPolicy { policy: policy.unbind(), input: PolicyInput::Observation, format, error: error.clone() });
+    Box::new(move |_| Box::new(CallbackPlayer::new(policy.clone())))
+}
+
+#[pymethods]
+impl PyArena {
+
+    /// `seats` players out of the `players` play each game, all of them by default,
+    /// `names` name the players, and `playouts`, `opponent_budget` and `opponent_temperature` configure the bots as in `SingleEnvironment`
+    ///
+    /// The members of a `hall_of_fame` join the players with their ratings, the policy of a model being given by `loader(model_path)`,
+    /// and their new ratings are recorded after each [run](PyArena::run), the next runs going on from them.
+    /// With a `metrics` path, a CSV file when it ends with `.csv` or else a directory of TensorBoard event files,
+    /// the metrics of the games are written as they are played, see [Arena::set_metrics].
+    /// With a `database`, a `GameDatabase`, the games of each run are recorded and inserted into it as a tournament.
+    #[new]
+    #[pyo3(signature = (format, players, seats=None, threads=None, names=None, playouts=100, opponent_budget=None, config=None, opponent_temperature=0.0, hall_of_fame=None, loader=None, metrics=None, database=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        format: &PyObservationFormat,
+        players: Vec<PyObject>,
+        seats: Option<usize>,
+        threads: Option<usize>,
+        names: Option<Vec<String>>,
+        playouts: usize,
+        opponent_budget: Option<f64>,
+        config: Option<PyGameConfig>,
+        opponent_temperature: f32,
+        hall_of_fame: Option<Py<PyHallOfFame>>,
+        loader: Option<PyObject>,
+        metrics: Option<&str>,
+        database: Option<Py<PyGameDatabase>>,
+        py: Python,
+    ) -> PyResult<PyArena> {
+        if let Some(names) = &names {
+            if names.len() != players.len() {
+                return Err(PyValueError::new_err(format!("Got {} names for {} players", names.len(), players.len())));
+            }
+        }
+        let members = hall_of_fame.as_ref().map_or(0, |hall| hall.borrow(py).hall.members().len());
+        let mut arena = Arena::new();
+        arena.config = game_config(config, format, seats.unwrap_or(players.len() + members))?;
+        arena.board = format.board;
+        arena.seats = seats;
+        if let Some(threads) = threads {
+            arena.threads = threads;
+        }
+        arena.record_replays = database.is_some();
+        if let Some(path) = metrics {
+            arena.set_metrics(metrics::open(path).map_err(PyValueError::new_err)?);
+        }
+        let error = Arc::new(Mutex::new(None));
+        let format = *format;
+        for (i, player) in players.into_iter().enumerate() {
+            let player = player.into_bound(py);
+            let (default_name, factory): (String, PlayerFactory) = if let Ok(name) = player.extract::<String>() {
+                let opponent = Opponent::from_name(&name, playouts, opponent_budget, opponent_temperature)?;
+                (name, Box::new(move |seed| opponent.player(seed)))
+            } else if player.is_callable() {
+                // Python code can't run in parallel with the calling thread, which holds the GIL
+                arena.threads = 1;
+                (format!("policy {}", i), policy_factory(format, player, &error))
+            } else {
+                return Err(PyTypeError::new_err(format!("Player {} is neither a player name nor a callable", i)));
+            };
+            let name = names.as_ref().map_or(default_name, |names| names[i].clone());
+            arena.add(PlayerInfo::new(&name), factory);
+        }
+        if let Some(hall) = &hall_of_fame {
+            let hall = &hall.borrow(py).hall;
+            for member in hall.members() {
+                let factory: PlayerFactory = match (&member.bot, hall.model_path(member)) {
+                    (Some(bot), _) => {
+                        let opponent = Opponent::from_name(bot, playouts, opponent_budget, opponent_temperature)?;
+                        Box::new(move |seed| opponent.player(seed))
+                    }
+                    (None, Some(model)) => {
+                        let loader = loader.as_ref().ok_or_else(|| PyValueError::new_err("A loader is needed for the models of the hall of fame"))?;
+                        arena.threads = 1;
+                        policy_factory(format, loader.call1(py, (model.display().to_string(),))?.into_bound(py), &error)
+                    }
+                    (None, None) => return Err(PyValueError::new_err(format!("Member {} has neither a model nor a bot", member.name))),
+                };
+                arena.add_rated(PlayerInfo::new(&member.name), member.elo, factory);
+            }
+        }
+        Ok(PyArena {
+            arena,
+            error,
+            hall_of_fame,
+            database,
+        })
+    }
+
+    /// Plays `games` games, the game `i` being seeded by `seed + i`, returning a dict of results
+    ///
+    /// The dict holds the `average_length` in turns of the finished games, the number of `interrupted` games or games ended without a result,
+    /// the `players` with their `name`, `games`, `wins`, `win_rate`, `average_vp` and `elo`,
+    /// and the `games` with the player at each of the `seats`, the `victory_points` of each seat, the seat of the `winner` and the `turns` played.
+    /// With a database, the games are inserted as a tournament of the `label`, whose id is the `tournament` of the dict.
+    /// An exception raised by a policy is raised once the games are over.
+    #[pyo3(signature = (games, seed=0, label="arena"))]
+    fn run(&mut self, py: Python, games: usize, seed: u64, label: &str) -> PyResult<PyObject> {
+        let report = self.arena.run(games, seed).map_err(PyValueError::new_err)?;
+        if let Some(error) = self.error.lock().unwrap().take() {
+            return Err(error);
+        }
+        if let Some(hall) = &self.hall_of_fame {
+            hall.borrow_mut(py).hall.record(&report).map_err(PyValueError::new_err)?;
+            self.arena.update_ratings(&report);
+        }
+        let result = PyDict::new(py);
+        if let Some(database) = &self.database {
+            let tournament = database.borrow_mut(py).database.insert_tournament(label, &report).map_err(PyValueError::new_err)?;
+            result.set_item("tournament", tournament)?;
+        }
+        result.set_item("average_length", report.average_length())?;
+        result.set_item("interrupted", report.interrupted())?;
+        let players = report.entrants.iter().map(|entrant| {
+            let player = PyDict::new(py);
+            player.set_item("name", &entrant.info.name)?;
+            player.set_item("games", entrant.games)?;
+            player.set_item("wins", entrant.wins)?;
+            player.set_item("win_rate", entrant.win_rate())?;
+            player.set_item("average_vp", entrant.average_vp())?;
+            player.set_item("elo", entrant.elo)?;
+            Ok(player)
+        }).collect::<PyResult<Vec<_>>>()?;
+        result.set_item("players", players)?;
+        let records = report.games.iter().map(|game| {
+            let record = PyDict::new(py);
+            record.set_item("seats", &game.entrants)?;
+            record.set_item("victory_points", game.victory_points.iter().map(|vp| *vp as u32).collect::<Vec<_>>())?;
+            record.set_item("winner", game.winner.map(|winner| winner.to_u8()))?;
+            record.set_item("turns", game.turns)?;
+            Ok(record)
+        }).collect::<PyResult<Vec<_>>>()?;
+        result.set_item("games", records)?;
+        Ok(result.into_any().unbind())
+    }
+}
@@ -0,0 +1,109 @@
+use numpy::convert::IntoPyArray;
+use pyo3::prelude::*;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+
+use catan::game::{legal, Action, Error, Notification, Phase, DiceStatistics};
+use catan::state::{State, PlayerId};
+use catan::player::CatanPlayer;
+use super::{PyCatanObservation, PyObservationFormat, PythonPlayer, PlayerResult};
+use super::environment::to_py_tuple;
+
+/// Arguments a policy is called with
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) enum PolicyInput {
+    /// `policy(observation)`, the observation `(seat, board, flat, ..., actions, done)` of the format
+    Observation,
+    /// `policy(board, flat, mask)`, the mask being the legal actions
+    Arrays,
+}
+
+/// Python callable returning the index of the action of a player
+#[derive(Debug)]
+pub(crate) struct CallbackPolicy {
+    pub policy: PyObject,
+    pub input: PolicyInput,
+    pub format: PyObservationFormat,
+    /// First exception raised by a policy sharing the slot, interrupting the games
+    pub error: Arc<Mutex<Option<PyErr>>>,
+}
+
+/// Player asking a Python callable for the index of its action, see [CallbackPolicy]
+///
+/// The GIL is only taken while the callable decides, the thread waiting for the game having to release it.
+/// Illegal actions are replaced by the action played when a turn budget runs out,
+/// and an exception raised by the callable interrupts the game.
+pub(crate) struct CallbackPlayer {
+    player: PythonPlayer,
+    policy: Arc<CallbackPolicy>,
+    seat: PlayerId,
+    // The Python player reports its observations and results, unused here
+    _observations: Receiver<Option<(u8, PyCatanObservation)>>,
+    _results: Receiver<PlayerResult>,
+}
+
+impl CallbackPlayer {
+    pub(crate) fn new(policy: Arc<CallbackPolicy>) -> CallbackPlayer {
+        let (_, action_receiver) = channel();
+        let (observation_sender, observations) = channel();
+        let (result_sender, results) = channel();
+        let dice = Arc::new(Mutex::new(DiceStatistics::new()));
+        CallbackPlayer {
+            player: PythonPlayer::new(0, policy.format, action_receiver, observation_sender, result_sender, dice, None),
+            policy,
+            seat: PlayerId::NONE,
+            _observations: observations,
+            _results: results,
+        }
+    }
+}
+
+impl CatanPlayer for CallbackPlayer {
+    fn new_game(&mut self, position: PlayerId, state: &State) {
+        self.seat = position;
+        self.player.new_game(position, state);
+    }
+
+    fn pick_action(&mut self, phase: &Phase, state: &State) -> Action {
+        if self.policy.error.lock().unwrap().is_some() {
+            return Action::Exit;
+        }
+        let legal_actions = self.player.make_legal_actions(phase, state);
+        let observation = self.player.observe(phase, state, &legal_actions);
+        let picked = Python::with_gil(|py| {
+            let picked = match self.policy.input {
+                PolicyInput::Observation => {
+                    let observation = to_py_tuple(py, &self.policy.format, false, false, false, Some((self.seat.to_u8(), observation)));
+                    self.policy.policy.call1(py, (observation,))?
+                }
+                PolicyInput::Arrays => {
+                    let arrays = (observation.board.into_pyarray(py), observation.flat.into_pyarray(py), observation.actions.into_pyarray(py));
+                    self.policy.policy.call1(py, arrays)?
+                }
+            };
+            picked.extract::<usize>(py)
+        });
+        match picked {
+            Ok(index) => match self.player.decode_action(index, state) {
+                Some(action) if legal::legal(phase, state, action).is_ok() => action,
+                _ => self.player.fallback_action(&legal_actions),
+            },
+            Err(error) => {
+                self.policy.error.lock().unwrap().get_or_insert(error);
+                Action::Exit
+            }
+        }
+    }
+
+    fn bad_action(&mut self, error: Error) {
+        self.player.bad_action(error);
+    }
+
+    fn notify(&mut self, notification: &Notification) {
+        self.player.notify(notification);
+    }
+
+    fn results(&mut self, state: &State, winner: PlayerId) {
+        self.player.results(state, winner);
+    }
+}
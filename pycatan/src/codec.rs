@@ -0,0 +1,118 @@
+use ndarray::Array1;
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use serde::{Serialize, Deserialize};
+
+use catan::codec::{Fixtures, CodecSpec, action_mask};
+use super::{PyObservationFormat, PyCatanObservation};
+
+/// Arrays observed by the player to act at a test vector
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct Observation {
+    flat: Vec<i32>,
+    /// Board array in row-major order, of shape `board_shape`
+    board: Vec<i32>,
+}
+
+/// Test vectors of the codec of `catan::codec`, with the observations of a format
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+struct ObservationFixtures {
+    codec: Fixtures,
+    flat_names: Vec<String>,
+    board_channel_names: Vec<String>,
+    /// Width, height and channels of the board arrays
+    board_shape: (usize, usize, usize),
+    /// Observation of each test vector
+    observations: Vec<Observation>,
+}
+
+/// Observation of each test vector of the fixtures, in the format
+fn observe(fixtures: &Fixtures, format: PyObservationFormat) -> Result<Vec<Observation>, String> {
+    let replays = fixtures.games.iter().map(|record| record.to_replay()).collect::<Result<Vec<_>, _>>()?;
+    fixtures.vectors.iter().map(|vector| {
+        let replay = replays.get(vector.game).ok_or_else(|| format!("No game {}", vector.game))?;
+        let (phase, state) = replay.state_at(vector.ply, format.board.layout).map_err(|error| format!("Failed replaying: {:?}", error))?;
+        let mask: Array1<bool> = action_mask(&phase, &state, format.trade_bundles).into_iter().collect();
+        let observation = PyCatanObservation::new_array(format, vector.player, &state, &phase, mask);
+        Ok(Observation { flat: observation.flat.to_vec(), board: observation.board.iter().copied().collect() })
+    }).collect()
+}
+
+/// JSON of the fixtures of `codec_fixtures`
+pub(crate) fn write_fixtures(format: PyObservationFormat, players: u8, games: usize, positions: usize, seed: u64) -> Result<String, String> {
+    let codec = Fixtures::generate(format.board, players, format.trade_bundles, games, positions, seed)?;
+    let observations = observe(&codec, format)?;
+    let fixtures = ObservationFixtures {
+        codec,
+        flat_names: format.flat_names(players),
+        board_channel_names: format.board_channel_names(players),
+        board_shape: (format.width, format.height, format.board_channels(players)),
+        observations,
+    };
+    Ok(serde_json::to_string(&fixtures).expect("Failed serializing fixtures"))
+}
+
+/// Differences found by `validate_codec_fixtures`, an error for unreadable fixtures
+pub(crate) fn check_fixtures(fixtures: &str, format: PyObservationFormat) -> Result<Vec<String>, String> {
+    let fixtures: ObservationFixtures = serde_json::from_str(fixtures).map_err(|error| format!("Bad fixtures: {}", error))?;
+    let mut errors = fixtures.codec.validate().err().unwrap_or_default();
+    let players = fixtures.codec.spec.players;
+    let spec = CodecSpec::new(format.board, players, format.trade_bundles)?;
+    if (&spec.board, spec.trade_bundles) != (&fixtures.codec.spec.board, fixtures.codec.spec.trade_bundles) {
+        errors.push(format!("Fixtures of the {} board with trade bundles {}, read on the {} board with trade bundles {}",
+            fixtures.codec.spec.board, fixtures.codec.spec.trade_bundles, spec.board, spec.trade_bundles));
+    }
+    if fixtures.flat_names != format.flat_names(players) || fixtures.board_channel_names != format.board_channel_names(players) {
+        errors.push("Flat or board channel names differ from the ones of the format".to_string());
+    }
+    let shape = (format.width, format.height, format.board_channels(players));
+    if fixtures.board_shape != shape {
+        errors.push(format!("Board shape {:?} instead of {:?}", fixtures.board_shape, shape));
+    }
+    if !errors.is_empty() {
+        return Ok(errors);
+    }
+    let observations = match observe(&fixtures.codec, format) {
+        Ok(observations) => observations,
+        Err(error) => return Ok(vec![error]),
+    };
+    if fixtures.observations.len() != observations.len() {
+        errors.push(format!("{} observations for {} test vectors", fixtures.observations.len(), observations.len()));
+    }
+    for (i, (observation, expected)) in fixtures.observations.iter().zip(observations.iter()).enumerate() {
+        let flat: Vec<&str> = fixtures.flat_names.iter().enumerate()
+            .filter(|(index, _)| observation.flat.get(*index) != expected.flat.get(*index))
+            .map(|(_, name)| name.as_str())
+            .collect();
+        if !flat.is_empty() || observation.flat.len() != expected.flat.len() {
+            errors.push(format!("Vector {}: flat differs at {:?}", i, flat));
+        }
+        let channels = shape.2;
+        let board: Vec<&str> = fixtures.board_channel_names.iter().enumerate()
+            .filter(|(channel, _)| (0..shape.0 * shape.1).any(|cell| observation.board.get(cell * channels + channel) != expected.board.get(cell * channels + channel)))
+            .map(|(_, name)| name.as_str())
+            .collect();
+        if !board.is_empty() || observation.board.len() != expected.board.len() {
+            errors.push(format!("Vector {}: board differs at {:?}", i, board));
+        }
+    }
+    Ok(errors)
+}
+
+/// Test vectors of the action codec and of the observations of the format, as JSON, for other implementations to check themselves against
+///
+/// `codec` holds the spec of the action masks, `games` games of random players as records and `positions` positions of each,
+/// see `catan::codec`, and `observations` the `flat` and `board` arrays of the player to act at each position,
+/// named by `flat_names` and `board_channel_names`.
+#[pyfunction]
+#[pyo3(signature = (format, players=4, games=2, positions=20, seed=0))]
+pub fn codec_fixtures(format: PyObservationFormat, players: u8, games: usize, positions: usize, seed: u64) -> PyResult<String> {
+    write_fixtures(format, players, games, positions, seed).map_err(PyValueError::new_err)
+}
+
+/// Differences between fixtures from `codec_fixtures`, possibly written by another implementation, and what this one computes in the format,
+/// empty when they agree
+#[pyfunction]
+pub fn validate_codec_fixtures(fixtures: &str, format: PyObservationFormat) -> PyResult<Vec<String>> {
+    check_fixtures(fixtures, format).map_err(PyValueError::new_err)
+}
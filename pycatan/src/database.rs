@@ -0,0 +1,129 @@
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+use pyo3::exceptions::PyValueError;
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use catan::database::{GameDatabase, GameQuery, PositionQuery, StoredGame};
+use catan::game::ActionCategory;
+use super::PyObservationFormat;
+use super::trajectory::{observed_state, Observers};
+
+/// SQLite store of the games and tournaments of arenas, see [GameDatabase]
+///
+/// An `Arena` given the path of the database inserts the games of each run, with their replays,
+/// whose positions are then sampled by criteria into supervised training sets.
+#[pyclass(name="GameDatabase", unsendable)]
+pub struct PyGameDatabase {
+    pub(crate) database: GameDatabase,
+}
+
+fn game_query(players: Option<Vec<String>>, rules: Option<u64>, board: Option<u64>, tournament: Option<i64>) -> GameQuery {
+    GameQuery {
+        players: players.unwrap_or_default(),
+        rules,
+        board,
+        tournament,
+        ..GameQuery::default()
+    }
+}
+
+fn game_to_py(py: Python, game: &StoredGame) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("id", game.id)?;
+    dict.set_item("tournament", game.tournament)?;
+    dict.set_item("players", &game.players)?;
+    dict.set_item("victory_points", game.record.victory_points.iter().map(|vp| *vp as u32).collect::<Vec<_>>())?;
+    dict.set_item("winner", game.record.winner.map(|winner| winner.to_u8()))?;
+    dict.set_item("turns", game.record.turns)?;
+    dict.set_item("rules", game.record.version.rules)?;
+    dict.set_item("board", game.record.board)?;
+    dict.set_item("replay", game.record.replay.is_some())?;
+    Ok(dict.into_any().unbind())
+}
+
+#[pymethods]
+impl PyGameDatabase {
+    /// Database of the file at `path`, created when missing
+    #[new]
+    fn new(path: &str) -> PyResult<PyGameDatabase> {
+        Ok(PyGameDatabase {
+            database: GameDatabase::open(path).map_err(PyValueError::new_err)?,
+        })
+    }
+
+    /// Ids and labels of the tournaments, as `(id, label)`
+    fn tournaments(&self) -> PyResult<Vec<(i64, String)>> {
+        self.database.tournaments().map_err(PyValueError::new_err)
+    }
+
+    /// Games every one of the `players` played, under the `rules` fingerprint, on the `board` digest and in the `tournament`,
+    /// as dicts of their `id`, `tournament`, `players` and `victory_points` of each seat, seat of the `winner`, `turns`, `rules`, `board`
+    /// and whether they have a `replay`
+    #[pyo3(signature = (players=None, rules=None, board=None, tournament=None, finished=false, limit=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn games(
+        &self,
+        py: Python,
+        players: Option<Vec<String>>,
+        rules: Option<u64>,
+        board: Option<u64>,
+        tournament: Option<i64>,
+        finished: bool,
+        limit: Option<usize>,
+    ) -> PyResult<Vec<PyObject>> {
+        let query = GameQuery { finished, limit, ..game_query(players, rules, board, tournament) };
+        let games = self.database.games(&query).map_err(PyValueError::new_err)?;
+        games.iter().map(|game| game_to_py(py, game)).collect()
+    }
+
+    /// Up to `count` positions of the games with a replay, drawn uniformly among the ones matching the criteria, see [GameDatabase::sample_positions]
+    ///
+    /// Positions are taken from `turns` `(first, last)`, where the victory points of the player to act
+    /// over the best of its opponents are within `vp_difference` `(least, most)`, and the action played is of one of the `actions` categories,
+    /// like `"BuildSettlement"`, the games being picked as in `games`.
+    /// Each position is a dict of its `game`, `ply`, `seat`, `turn`, the `checkpoint` bytes as `save_state` gives them,
+    /// the `observation` `(seat, board, flat, ..., actions, done)` of the format and the index in the mask of the `action` played.
+    #[pyo3(signature = (count, format, seed=0, turns=None, vp_difference=None, actions=None, players=None, rules=None, board=None, tournament=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn sample(
+        &self,
+        py: Python,
+        count: usize,
+        format: PyObservationFormat,
+        seed: u64,
+        turns: Option<(u32, u32)>,
+        vp_difference: Option<(i32, i32)>,
+        actions: Option<Vec<String>>,
+        players: Option<Vec<String>>,
+        rules: Option<u64>,
+        board: Option<u64>,
+        tournament: Option<i64>,
+    ) -> PyResult<Vec<PyObject>> {
+        let actions = actions.unwrap_or_default().iter().map(|name| {
+            ActionCategory::ALL.iter().copied().find(|category| format!("{:?}", category) == *name)
+                .ok_or_else(|| PyValueError::new_err(format!("Unknown action category {}", name)))
+        }).collect::<PyResult<Vec<_>>>()?;
+        let query = PositionQuery {
+            games: game_query(players, rules, board, tournament),
+            turns,
+            vp_difference,
+            actions,
+        };
+        let positions = self.database.sample_positions(&query, count, format.board.layout, &mut SmallRng::seed_from_u64(seed))
+            .map_err(PyValueError::new_err)?;
+        positions.iter().map(|position| {
+            let state = observed_state(&position.checkpoint.state, format)?;
+            let mut observers = Observers::new(&state, format);
+            let dict = PyDict::new(py);
+            dict.set_item("game", position.game)?;
+            dict.set_item("ply", position.ply)?;
+            dict.set_item("seat", position.player.to_u8())?;
+            dict.set_item("turn", state.get_turn())?;
+            dict.set_item("checkpoint", PyBytes::new(py, &position.checkpoint.to_bytes()))?;
+            dict.set_item("observation", observers.observation(py, &format, &position.checkpoint.phase, &state))?;
+            dict.set_item("action", observers.action(position.player, position.action, &state))?;
+            Ok(dict.into_any().unbind())
+        }).collect()
+    }
+}
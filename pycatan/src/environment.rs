@@ -1,142 +1,1462 @@
 use ndarray::Array1;
 use pyo3::prelude::*;
- use pyo3::IntoPyObjectExt;
+use pyo3::types::{PyDict, PyBytes, PyTuple, PyList};
+use pyo3::exceptions::{PyValueError, PyRuntimeError, PyTimeoutError, PyTypeError};
+use pyo3::IntoPyObjectExt;
+use pyo3::create_exception;
 use numpy::convert::IntoPyArray;
 use std::thread;
-use std::sync::mpsc::{channel, Sender, Receiver};
-use rand::SeedableRng;
+use std::any::Any;
+use std::sync::mpsc::{channel, Sender, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+use std::path::PathBuf;
+use rand::{Rng, SeedableRng};
 use rand::rngs::SmallRng;
 use rand::seq::SliceRandom;
 
-use catan::game::Game;
-use catan::state::State;
-use catan::player::Randomy;
-use catan::board::setup::random_default_setup_existing_state;
-use catan::board::layout;
-use super::{PythonState, PyCatanObservation, PyObservationFormat, PythonPlayer};
+use catan::game::{Game, Notification, GameEvent, EventLog, DiceStatistics, RollCounts, SeatStatistics, DevelopmentUsage, GameStats, Curriculum, Checkpoint, Phase, RewardConfig, RewardTracker, GameConfig};
+use catan::state::{State, PlayerId, TricellState};
+use catan::game::Action;
+use catan::player::{Randomy, Heuristic, Mcts, MctsConfig, CatanPlayer, PlayerInfo, SearchLog, SearchRecord};
+use catan::player::relative::player_id_to_relative;
+use catan::board::layout::BoardLayout;
+use catan::utils::{Resource, Harbor};
+use catan::replay::{Replay, Corpus};
+use super::{PythonState, PyCatanObservation, PyObservationFormat, PyRewardConfig, PyGameConfig, PythonPlayer, PlayerResult};
+use super::python_player::{action_list, stage_indices, IllegalActions, RESET_ACTION, SAVE_ACTION, RESIGN_ACTION};
+use super::py_action::{PyAction, event_to_py, describe_action, index_to_action, action_to_index, describe_legal_actions, graph_actions};
+use super::py_game_config::game_config;
+use super::py_observation_format::resource_name;
+use super::observation_buffers;
+use super::hall_of_fame::PyHallOfFame;
+use super::callback_player::{CallbackPlayer, CallbackPolicy, PolicyInput};
+use super::thread_placement::ThreadPlacement;
 
 use std::sync::Mutex;
 
 
-fn to_py_tuple(py: Python, hidden_state: bool, observation: Option<(u8, PyCatanObservation)>) -> PyObject {
+pub(crate) fn to_py_tuple(py: Python, format: &PyObservationFormat, budget: bool, rewards: bool, events: bool, observation: Option<(u8, PyCatanObservation)>) -> PyObject {
     let elements: Vec<PyObject> = if let Some((id, observation)) = observation {
-        if hidden_state {
-            vec![
-                // u8 -> PyInt -> PyObject
-                id.into_py_any(py).unwrap(),
-
-                // ndarray -> PyArray -> PyObject
-                observation.board.into_pyarray(py).into(),
-                observation.flat.into_pyarray(py).into(),
-                observation.hidden.unwrap().into_pyarray(py).into(),
-                observation.actions.into_pyarray(py).into(),
-                false.into_py_any(py).unwrap(),
-            ]
-        } else {
-            vec![
-                id.into_py_any(py).unwrap(),
-                observation.board.into_pyarray(py).into(),
-                observation.flat.into_pyarray(py).into(),
-                observation.actions.into_pyarray(py).into(),
-                false.into_py_any(py).unwrap(),
-            ]
+        let mut elements = vec![
+            // u8 -> PyInt -> PyObject
+            id.into_py_any(py).unwrap(),
+
+            // ndarray -> PyArray -> PyObject
+            observation.board.into_pyarray(py).into(),
+            observation.flat.into_pyarray(py).into(),
+        ];
+        if let Some(hidden) = observation.hidden {
+            elements.push(hidden.into_pyarray(py).into());
+        }
+        if let Some(global) = observation.global {
+            elements.push(global.into_pyarray(py).into());
+        }
+        if let Some(graph) = observation.graph {
+            elements.push(graph.hexes.into_pyarray(py).into());
+            elements.push(graph.intersections.into_pyarray(py).into());
+            elements.push(graph.paths.into_pyarray(py).into());
+        }
+        if let Some(tokens) = observation.tokens {
+            elements.push(tokens.into_pyarray(py).into());
+        }
+        if let (Some(stage), Some(stage_actions)) = (observation.stage, observation.stage_actions) {
+            elements.push(stage.into_py_any(py).unwrap());
+            elements.push(stage_actions.into_pyarray(py).into());
         }
+        if let Some(priors) = observation.priors {
+            elements.push(priors.into_pyarray(py).into());
+        }
+        if let Some(budget) = observation.budget {
+            elements.push(budget.into_py_any(py).unwrap());
+        }
+        if let Some(reward) = observation.reward {
+            elements.push(reward.into_py_any(py).unwrap());
+        }
+        if let Some(events) = observation.events {
+            let events: Vec<PyObject> = events.iter().map(|event| event_to_py(py, event).unwrap()).collect();
+            elements.push(events.into_py_any(py).unwrap());
+        }
+        elements.push(observation.actions.into_pyarray(py).into());
+        elements.push(false.into_py_any(py).unwrap());
+        elements
     } else {
-        if hidden_state {
-            vec![
-                0i32.into_py_any(py).unwrap(),
-                py.None(),
-                py.None(),
-                py.None(),
-                py.None(),
-                true.into_py_any(py).unwrap(),
-            ]
-        } else {
-            vec![
-                0i32.into_py_any(py).unwrap(),
-                py.None(),
-                py.None(),
-                py.None(),
-                true.into_py_any(py).unwrap(),
-            ]
+        let mut elements = vec![
+            0i32.into_py_any(py).unwrap(),
+            py.None(),
+            py.None(),
+        ];
+        if format.include_hidden {
+            elements.push(py.None());
         }
+        if format.include_global {
+            elements.push(py.None());
+        }
+        if format.include_graph {
+            elements.push(py.None());
+            elements.push(py.None());
+            elements.push(py.None());
+        }
+        if format.include_tokens {
+            elements.push(py.None());
+        }
+        if format.include_stage {
+            elements.push(py.None());
+            elements.push(py.None());
+        }
+        if format.action_priors.is_some() {
+            elements.push(py.None());
+        }
+        if budget {
+            elements.push(py.None());
+        }
+        if rewards {
+            elements.push(py.None());
+        }
+        if events {
+            elements.push(py.None());
+        }
+        elements.push(py.None());
+        elements.push(true.into_py_any(py).unwrap());
+        elements
     };
 
     // 型が統一されたVec<PyObject>からタプルを作成し、PyObjectに変換して返す
     elements.into_pyobject(py).unwrap().unbind().into_any()
 }
 
-#[pyclass]
-pub struct SingleEnvironment {
-    action_sender: Sender<u16>,
-    observation_receiver: Mutex<Receiver<Option<(u8, PyCatanObservation)>>>,
-    result_receiver: Mutex<Receiver<(u8,bool)>>,
-    game_thread: thread::JoinHandle<()>,
-    include_hidden: bool,
+fn roll_counts_to_py(py: Python, counts: &RollCounts) -> PyObject {
+    let counts: Array1<u32> = counts.counts().iter().copied().collect();
+    counts.into_pyarray(py).into_py_any(py).unwrap()
 }
 
-#[pymethods]
-impl SingleEnvironment {
+fn seeded_rng(seed: Option<u64>) -> SmallRng {
+    match seed {
+        Some(seed) => SmallRng::seed_from_u64(seed),
+        None => SmallRng::from_rng(&mut rand::rng()),
+    }
+}
 
-    #[staticmethod]
-    #[pyo3(signature = (format, opponents=2))]
-    fn new(format: &PyObservationFormat, opponents: usize) -> SingleEnvironment {
-        let format = *format;
+fn curriculum_for(enabled: bool) -> Arc<Mutex<Curriculum>> {
+    Arc::new(Mutex::new(if enabled { Curriculum::default() } else { Curriculum::standard() }))
+}
+
+fn set_curriculum_stage(curriculum: &Mutex<Curriculum>, stage: usize) -> PyResult<()> {
+    let mut curriculum = curriculum.lock().unwrap();
+    if stage >= curriculum.stage_count() {
+        return Err(PyValueError::new_err(format!("Stage {} out of the {} stages of the curriculum", stage, curriculum.stage_count())));
+    }
+    curriculum.set_stage(stage);
+    Ok(())
+}
+
+/// Actions of the action masks, for a game of `players` players
+pub(crate) fn game_actions(format: PyObservationFormat, players: usize) -> Vec<Action> {
+    let state: State = Box::new(PythonState::new(format.board.layout, players as u8, format));
+    action_list(format, PlayerId::FIRST, &state)
+}
+
+fn stage_indices_to_py(py: Python, actions: &[Action]) -> Vec<PyObject> {
+    stage_indices(actions).iter().map(|indices| {
+        let indices: Array1<u16> = indices.iter().map(|i| *i as u16).collect();
+        indices.into_pyarray(py).into_py_any(py).unwrap()
+    }).collect()
+}
+
+/// Decision of a search opponent, its actions given by their index in the action mask of its seat, None when missing from the mask
+fn search_record_to_py(py: Python, actions: &[Action], player_count: u8, record: &SearchRecord) -> PyResult<PyObject> {
+    let index = |action: Action| {
+        // Victims of the thief are relative to the player in the action masks
+        let action = match action {
+            Action::MoveThief { hex, victim } => Action::MoveThief { hex, victim: player_id_to_relative(record.player, victim, player_count) },
+            action => action,
+        };
+        actions.iter().position(|a| *a == action)
+    };
+    let dict = PyDict::new(py);
+    dict.set_item("seat", record.player.to_u8())?;
+    dict.set_item("turn", record.turn)?;
+    dict.set_item("actions", record.actions.iter().map(|action| index(*action)).collect::<Vec<_>>())?;
+    dict.set_item("visits", record.visits.clone())?;
+    dict.set_item("values", record.values.clone())?;
+    dict.set_item("played", index(record.played))?;
+    Ok(dict.into_any().unbind())
+}
+
+fn development_usage_to_py(py: Python, usage: &DevelopmentUsage) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("bought", usage.bought)?;
+    for (name, cards) in [("pre_roll", usage.pre_roll), ("post_roll", usage.post_roll)] {
+        let played = PyDict::new(py);
+        played.set_item("knight", cards.knight)?;
+        played.set_item("road_building", cards.road_building)?;
+        played.set_item("year_of_plenty", cards.year_of_plenty)?;
+        played.set_item("monopole", cards.monopole)?;
+        dict.set_item(name, played)?;
+    }
+    Ok(dict.into_any().unbind())
+}
+
+/// Metrics of a game, the counts of each player being indexed by seat
+fn game_stats_to_py(py: Python, stats: &GameStats) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("rolls", roll_counts_to_py(py, &stats.dice))?;
+    let gained = stats.resources_gained.iter().map(|gained| {
+        let resources = PyDict::new(py);
+        for resource in Resource::ALL.iter() {
+            resources.set_item(resource_name(*resource), gained[resource.to_usize()])?;
+        }
+        Ok(resources.into_any().unbind())
+    }).collect::<PyResult<Vec<PyObject>>>()?;
+    dict.set_item("resources_gained", gained)?;
+    let placements: Vec<(u8, (i8, i8))> = stats.robber_placements.iter().map(|(player, hex)| (player.to_u8(), (hex.x, hex.y))).collect();
+    dict.set_item("robber_placements", placements)?;
+    let played = stats.development_played.iter().map(|cards| {
+        let played = PyDict::new(py);
+        played.set_item("knight", cards.knight)?;
+        played.set_item("road_building", cards.road_building)?;
+        played.set_item("year_of_plenty", cards.year_of_plenty)?;
+        played.set_item("monopole", cards.monopole)?;
+        Ok(played.into_any().unbind())
+    }).collect::<PyResult<Vec<PyObject>>>()?;
+    dict.set_item("development_played", played)?;
+    dict.set_item("bank_trades", stats.bank_trades.clone())?;
+    let harbor_trades = stats.harbor_trades.iter().map(|trades| {
+        let harbors = PyDict::new(py);
+        harbors.set_item("generic", trades[Harbor::Generic.to_usize()])?;
+        for resource in Resource::ALL.iter() {
+            harbors.set_item(resource_name(*resource), trades[Harbor::Special(*resource).to_usize()])?;
+        }
+        Ok(harbors.into_any().unbind())
+    }).collect::<PyResult<Vec<PyObject>>>()?;
+    dict.set_item("harbor_trades", harbor_trades)?;
+    dict.set_item("player_trades", stats.player_trades.clone())?;
+    dict.set_item("turns", stats.turns)?;
+    dict.set_item("winner", stats.winner.map(|winner| winner.to_u8()))?;
+    Ok(dict.into_any().unbind())
+}
+
+fn metrics_to_py(py: Python, dice: &DiceStatistics) -> PyResult<PyObject> {
+    let metrics = PyDict::new(py);
+    metrics.set_item("rolls", roll_counts_to_py(py, &dice.game))?;
+    metrics.set_item("rolls_chi_square", dice.game.chi_square())?;
+    metrics.set_item("rolls_p_value", dice.game.p_value())?;
+    metrics.set_item("run_rolls", roll_counts_to_py(py, &dice.run))?;
+    metrics.set_item("run_rolls_chi_square", dice.run.chi_square())?;
+    metrics.set_item("run_rolls_p_value", dice.run.p_value())?;
+    Ok(metrics.into_any().unbind())
+}
+
+/// Kind of the opponents of a Python player
+#[derive(Clone, Debug)]
+pub(crate) enum Opponent {
+    Random,
+    /// Heuristic player, tempered by the temperature
+    Heuristic(f32),
+    Mcts(MctsConfig),
+    /// Python policy, see [CallbackPlayer]
+    Callback(Arc<CallbackPolicy>),
+}
+
+impl Opponent {
+    /// Opponent named `random`, `heuristic` or `mcts`, the search of `mcts` opponents running `playouts` playouts in at most `budget` seconds
+    ///
+    /// With a positive `temperature`, `heuristic` and `mcts` opponents sample their actions instead of always playing the best one.
+    pub(crate) fn from_name(name: &str, playouts: usize, budget: Option<f64>, temperature: f32) -> PyResult<Opponent> {
+        if temperature < 0.0 {
+            return Err(PyValueError::new_err(format!("Opponent temperature {} can't be negative", temperature)));
+        }
+        match name {
+            "random" => Ok(Opponent::Random),
+            "heuristic" => Ok(Opponent::Heuristic(temperature)),
+            "mcts" => Ok(Opponent::Mcts(MctsConfig {
+                playouts,
+                time_budget: budget.map(Duration::from_secs_f64),
+                temperature,
+                ..MctsConfig::DEFAULT
+            })),
+            _ => Err(PyValueError::new_err(format!("Unknown opponent {}, expected random, heuristic or mcts", name))),
+        }
+    }
+
+    /// Opponent named, as [from_name](Opponent::from_name), or Python callable `policy(board, flat, mask)`
+    /// returning the index of its action, its exceptions being kept in `error`
+    fn from_py(
+        opponent: &Bound<'_, PyAny>,
+        format: PyObservationFormat,
+        error: &Arc<Mutex<Option<PyErr>>>,
+        playouts: usize,
+        budget: Option<f64>,
+        temperature: f32,
+    ) -> PyResult<Opponent> {
+        if let Ok(name) = opponent.extract::<String>() {
+            Opponent::from_name(&name, playouts, budget, temperature)
+        } else if opponent.is_callable() {
+            let policy = CallbackPolicy { policy: opponent.clone().unbind(), input: PolicyInput::Arrays, format, error: error.clone() };
+            Ok(Opponent::Callback(Arc::new(policy)))
+        } else {
+            Err(PyTypeError::new_err("Opponent neither a name nor a callable"))
+        }
+    }
+
+    /// Player of the opponent, a search opponent recording its decisions into the log when given one
+    pub(crate) fn logged_player(&self, seed: u64, log: Option<&SearchLog>) -> Box<dyn CatanPlayer> {
+        match (self, log) {
+            (Opponent::Mcts(config), Some(log)) => Box::new(Mcts::new_logged_player(*config, seed, log.clone())),
+            _ => self.player(seed),
+        }
+    }
+
+    pub(crate) fn player(&self, seed: u64) -> Box<dyn CatanPlayer> {
+        match self {
+            Opponent::Random => Box::new(Randomy::new_seeded_player(seed)),
+            Opponent::Heuristic(temperature) if *temperature > 0.0 => Box::new(Heuristic::new_tempered_player(*temperature, seed)),
+            Opponent::Heuristic(_) => Box::new(Heuristic::new_seeded_player(seed)),
+            Opponent::Mcts(config) => Box::new(Mcts::new_seeded_player(*config, seed)),
+            Opponent::Callback(policy) => Box::new(CallbackPlayer::new(policy.clone())),
+        }
+    }
+}
+
+/// Games of an opponent of a pool remembered for its win rate
+const RECENT_GAMES: usize = 100;
+
+/// Opponents a Python player is matched against, the opponent of each seat being sampled per game
+///
+/// Prioritized sampling weighs each opponent by the rate of recent games the Python player didn't win against it,
+/// so that harder opponents are played more often.
+pub(crate) struct OpponentPool {
+    opponents: Vec<Opponent>,
+    prioritized: bool,
+    /// Whether the Python player won each recent game against each opponent
+    recent: Vec<VecDeque<bool>>,
+}
+
+impl OpponentPool {
+    pub(crate) fn new(opponents: Vec<Opponent>, sampling: &str) -> PyResult<OpponentPool> {
+        if opponents.is_empty() {
+            return Err(PyValueError::new_err("Empty opponent pool"));
+        }
+        let prioritized = match sampling {
+            "uniform" => false,
+            "prioritized" => true,
+            _ => return Err(PyValueError::new_err(format!("Unknown pool sampling {}, expected uniform or prioritized", sampling))),
+        };
+        Ok(OpponentPool {
+            recent: vec![VecDeque::new(); opponents.len()],
+            opponents,
+            prioritized,
+        })
+    }
+
+    /// Pool of the opponents named or Python policies, as `SingleEnvironment` opponents, or given as dicts with an `opponent`,
+    /// and optionally `playouts`, `opponent_budget` and `opponent_temperature` overriding the environment ones,
+    /// or pool of the bots of a hall of fame
+    #[allow(clippy::too_many_arguments)]
+    fn from_py(
+        pool: &Bound<'_, PyAny>,
+        sampling: &str,
+        format: PyObservationFormat,
+        error: &Arc<Mutex<Option<PyErr>>>,
+        playouts: usize,
+        budget: Option<f64>,
+        temperature: f32,
+    ) -> PyResult<OpponentPool> {
+        if let Ok(hall) = pool.downcast::<PyHallOfFame>() {
+            let opponents = hall.borrow().hall.members().iter()
+                .filter_map(|member| member.bot.as_ref())
+                .map(|bot| Opponent::from_name(bot, playouts, budget, temperature))
+                .collect::<PyResult<Vec<_>>>()?;
+            return OpponentPool::new(opponents, sampling);
+        }
+        let entries: Vec<Bound<'_, PyAny>> = pool.extract()?;
+        let opponents = entries.iter().map(|entry| {
+            let entry = match entry.downcast::<PyDict>() {
+                Ok(entry) => entry,
+                Err(_) => return Opponent::from_py(entry, format, error, playouts, budget, temperature),
+            };
+            let opponent = entry.get_item("opponent")?.ok_or_else(|| PyValueError::new_err("Pool entry without opponent"))?;
+            let playouts = entry.get_item("playouts")?.map_or(Ok(playouts), |playouts| playouts.extract())?;
+            let budget = entry.get_item("opponent_budget")?.map_or(Ok(budget), |budget| budget.extract())?;
+            let temperature = entry.get_item("opponent_temperature")?.map_or(Ok(temperature), |temperature| temperature.extract())?;
+            Opponent::from_py(&opponent, format, error, playouts, budget, temperature)
+        }).collect::<PyResult<Vec<_>>>()?;
+        OpponentPool::new(opponents, sampling)
+    }
+
+    /// Pool of a single opponent, playing every game
+    pub(crate) fn single(opponent: Opponent) -> Arc<Mutex<OpponentPool>> {
+        Arc::new(Mutex::new(OpponentPool::new(vec![opponent], "uniform").unwrap()))
+    }
+
+    /// Rate of the recent games won by the Python player against each opponent, None before the first game
+    fn win_rates(&self) -> Vec<Option<f64>> {
+        self.recent.iter()
+            .map(|games| (!games.is_empty()).then(|| games.iter().filter(|won| **won).count() as f64 / games.len() as f64))
+            .collect()
+    }
+
+    fn sample(&self, rng: &mut SmallRng) -> usize {
+        // A single opponent doesn't draw from the rng, keeping seeded games unchanged
+        if self.opponents.len() == 1 {
+            return 0;
+        }
+        if !self.prioritized {
+            return rng.random_range(0..self.opponents.len());
+        }
+        // Opponents never played against count as even, and beaten ones keep a small chance
+        let weights: Vec<f64> = self.win_rates().iter().map(|rate| (1.0 - rate.unwrap_or(0.5)).max(0.05)).collect();
+        let mut picked = rng.random::<f64>() * weights.iter().sum::<f64>();
+        for (i, weight) in weights.iter().enumerate() {
+            if picked < *weight {
+                return i;
+            }
+            picked -= weight;
+        }
+        weights.len() - 1
+    }
+
+    fn record(&mut self, lineup: &[usize], won: bool) {
+        for opponent in lineup.iter() {
+            let games = &mut self.recent[*opponent];
+            games.push_back(won);
+            if games.len() > RECENT_GAMES {
+                games.pop_front();
+            }
+        }
+    }
+}
+
+create_exception!(pycatan, IllegalActionError, PyValueError, "Illegal action, whose args are a message, the action index and the legal action mask");
+
+/// Args of the error of an illegal action refused by a Python player: a message, the action index and the legal action mask
+pub(crate) fn rejection_args(observation: &Option<(u8, PyCatanObservation)>) -> Option<(String, u16, Vec<bool>)> {
+    let (_, observation) = observation.as_ref()?;
+    let index = observation.rejected?;
+    Some((format!("Illegal action {}", index), index, observation.actions.to_vec()))
+}
+
+/// Error of an illegal action refused by a Python player, the observation still waiting for its answer
+fn rejection_error(observation: &Option<(u8, PyCatanObservation)>) -> Option<PyErr> {
+    rejection_args(observation).map(IllegalActionError::new_err)
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic.downcast_ref::<&str>().map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Directory of the crash dumps of the game threads, `PYCATAN_CRASH_DUMPS` or else `pycatan-crashes` in the temporary directory
+///
+/// The exception raised when a game thread panics gives the path of its dump, see `catan::game::CrashDump`
+fn crash_dump_directory() -> PathBuf {
+    std::env::var_os("PYCATAN_CRASH_DUMPS").map(PathBuf::from).unwrap_or_else(|| std::env::temp_dir().join("pycatan-crashes"))
+}
+
+/// Spawns a game thread playing `play` once it is pinned and given its priority as the game thread `i`,
+/// raising the error of its placement when it fails
+fn spawn_game_thread(placement: Option<(ThreadPlacement, usize)>, play: impl FnOnce() + Send + 'static) -> PyResult<thread::JoinHandle<()>> {
+    let (placed_sender, placed_receiver) = channel();
+    let game_thread = thread::spawn(move || {
+        let placed = placement.map_or(Ok(()), |(placement, i)| placement.apply(i));
+        let failed = placed.is_err();
+        placed_sender.send(placed).expect("Failed sending thread placement");
+        if !failed {
+            play();
+        }
+    });
+    match without_gil(move || placed_receiver.recv()) {
+        Ok(Ok(())) => Ok(game_thread),
+        Ok(Err(error)) => {
+            join_game_thread(game_thread)?;
+            Err(PyRuntimeError::new_err(error))
+        }
+        Err(_) => Err(thread_error(&mut Some(game_thread), "Failed placing game thread")),
+    }
+}
+
+/// Waits for a game thread without holding the GIL, which its Python opponents take to decide
+fn without_gil<T: Send>(wait: impl FnOnce() -> T + Send) -> T {
+    Python::with_gil(|py| py.allow_threads(wait))
+}
+
+/// Awaitable result of a method of an environment, called in the default executor of the running asyncio loop
+///
+/// The method waits for the game thread without holding the GIL, so that the loop keeps running other tasks meanwhile.
+/// The environment being borrowed by the call until it returns, it can't be used by the other tasks in the meantime.
+pub(crate) fn run_in_executor<'py>(environment: &Bound<'py, PyAny>, method: &str, args: Bound<'py, PyTuple>) -> PyResult<Bound<'py, PyAny>> {
+    let py = environment.py();
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    // run_in_executor(executor, method, *args), None being the default executor
+    let mut call = vec![py.None().into_bound(py), environment.getattr(method)?];
+    call.extend(args);
+    event_loop.call_method1("run_in_executor", PyTuple::new(py, call)?)
+}
+
+/// Waits for the end of a game thread, raising its panic if it panicked
+fn join_game_thread(game_thread: thread::JoinHandle<()>) -> PyResult<()> {
+    game_thread.thread().unpark();
+    without_gil(|| game_thread.join())
+        .map_err(|panic| PyRuntimeError::new_err(format!("Game thread panicked: {}", panic_message(panic.as_ref()))))
+}
+
+/// Error of a game thread which can't be reached, with its panic message when it panicked
+fn thread_error(game_thread: &mut Option<thread::JoinHandle<()>>, error: &str) -> PyErr {
+    match game_thread.take().map(join_game_thread) {
+        Some(Err(panic)) => PyRuntimeError::new_err(format!("{}: {}", error, panic)),
+        Some(Ok(())) => PyRuntimeError::new_err(format!("{}: the game thread stopped", error)),
+        None => PyRuntimeError::new_err(format!("{}: the environment is closed", error)),
+    }
+}
+
+/// Message of a game thread, waiting at most `timeout`
+fn receive_from<T: Send>(receiver: &Mutex<Receiver<T>>, game_thread: &mut Option<thread::JoinHandle<()>>, timeout: Option<Duration>, error: &str) -> PyResult<T> {
+    let received = without_gil(|| match timeout {
+        Some(timeout) => receiver.lock().unwrap().recv_timeout(timeout),
+        None => receiver.lock().unwrap().recv().map_err(RecvTimeoutError::from),
+    });
+    received.map_err(|received| match received {
+        RecvTimeoutError::Timeout => PyTimeoutError::new_err(format!("{}: nothing received in {:?}", error, timeout.unwrap_or_default())),
+        RecvTimeoutError::Disconnected => thread_error(game_thread, error),
+    })
+}
+
+/// Sends a message to a game thread, waking it up
+fn send_to<T>(sender: Option<&Sender<T>>, game_thread: &mut Option<thread::JoinHandle<()>>, message: T, error: &str) -> PyResult<()> {
+    let sender = sender.ok_or_else(|| PyRuntimeError::new_err(format!("{}: the environment is closed", error)))?;
+    sender.send(message).map_err(|_| thread_error(game_thread, error))?;
+    if let Some(game_thread) = game_thread {
+        game_thread.thread().unpark();
+    }
+    Ok(())
+}
+
+/// Resumes the game of a checkpoint, the Python player, added last, taking the seat of the player to act
+fn resume_game(game: &mut Game, rng: &mut SmallRng, checkpoint: &Checkpoint, format: PyObservationFormat, opponents: usize) -> Notification {
+    let mut players_order: Vec<usize> = (0..opponents).collect();
+    players_order.insert(checkpoint.phase.player().to_usize(), opponents);
+    let mut state: State = Box::new(PythonState::new(format.board.layout, opponents as u8 + 1, format));
+    game.resume_on(rng, checkpoint, &mut state, players_order).expect("Failed restoring checkpoint")
+}
+
+/// How the game thread starts its next game
+pub(crate) enum GameStart {
+    /// A new game, reseeding the game thread when given a seed
+    New(Option<u64>),
+    /// The game of a checkpoint, the Python player sitting at the seat of the player to act
+    Resume(Box<Checkpoint>),
+}
+
+/// Game thread of a Python player against bot opponents, starting each game when asked
+pub(crate) struct SingleGame {
+    /// Senders to the game thread, dropped once the environment is closed
+    action_sender: Option<Sender<(u32, u16)>>,
+    control_sender: Option<Sender<GameStart>>,
+    observation_receiver: Mutex<Receiver<Option<(u8, PyCatanObservation)>>>,
+    result_receiver: Mutex<Receiver<PlayerResult>>,
+    checkpoint_receiver: Mutex<Receiver<Vec<u8>>>,
+    /// Replay of each game, sent once the game returns
+    replay_receiver: Mutex<Receiver<Option<Replay>>>,
+    record_replays: Arc<AtomicBool>,
+    /// Games which ended, and whose replays were received
+    games_ended: u32,
+    replays_received: u32,
+    replay: Option<Replay>,
+    player_count: usize,
+    board: &'static BoardLayout,
+    /// Game thread, None once closed or stopped
+    game_thread: Option<thread::JoinHandle<()>>,
+    sequence: u32,
+    /// Is the player expected to answer the last observation
+    pending: bool,
+    /// Is an observation expected from the game thread
+    waiting: bool,
+    /// Exception raised by a Python opponent, interrupting its game
+    pub(crate) policy_error: Arc<Mutex<Option<PyErr>>>,
+    /// Games whose positions new games start from, played from the turn given on
+    start_positions: Arc<Mutex<Option<(Corpus, u32)>>>,
+}
+
+impl SingleGame {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn spawn(
+        format: PyObservationFormat,
+        opponents: usize,
+        pool: Arc<Mutex<OpponentPool>>,
+        curriculum: Arc<Mutex<Curriculum>>,
+        dice: Arc<Mutex<DiceStatistics>>,
+        turn_budget: Option<Duration>,
+        auto_resolve: bool,
+        seed: Option<u64>,
+        rewards: Option<RewardConfig>,
+        events: bool,
+        stats: bool,
+        searches: bool,
+        config: GameConfig,
+        illegal_actions: IllegalActions,
+        placement: Option<(ThreadPlacement, usize)>,
+    ) -> PyResult<SingleGame> {
+        let (control_sender, control_receiver) = channel::<GameStart>();
         let (action_sender, action_receiver) = channel();
         let (observation_sender, observation_receiver) = channel();
         let (result_sender, result_receiver) = channel();
-        let game_thread = thread::spawn(move || {
-            let mut game = Game::new();
+        let (checkpoint_sender, checkpoint_receiver) = channel();
+        let (replay_sender, replay_receiver) = channel();
+        let record_replays = Arc::new(AtomicBool::new(false));
+        let game_record_replays = record_replays.clone();
+        let reset_sender = observation_sender.clone();
+        let start_positions: Arc<Mutex<Option<(Corpus, u32)>>> = Arc::default();
+        let game_start_positions = start_positions.clone();
+        let game_thread = spawn_game_thread(placement, move || {
+            let mut rng = seeded_rng(seed);
+            let mut game = Game::with_config(config);
+            game.board = format.board;
+            game.crash_dumps = Some(crash_dump_directory());
+            // Search opponents log their decisions for the Python player to report them
+            let search_log = if searches { Some(SearchLog::new()) } else { None };
+            // Pool opponent of each seat of the opponents, sampled again for each new game
+            let mut lineup = vec![0; opponents];
             for _ in 0..opponents {
-                game.add_player(Box::new(Randomy::new_player()));
+                game.add_player(pool.lock().unwrap().opponents[0].logged_player(rng.random(), search_log.as_ref()));
             };
-            game.add_player(Box::new(PythonPlayer::new(0, format, action_receiver, observation_sender, result_sender)));
-            loop {
-                game.setup_and_play();
+            let mut player = PythonPlayer::new(0, format, action_receiver, observation_sender, result_sender, dice, turn_budget);
+            player.auto_resolve = auto_resolve;
+            player.checkpoint_sender = Some(checkpoint_sender);
+            player.rewards = rewards.map(RewardTracker::new);
+            player.illegal_actions = illegal_actions;
+            if events {
+                let log = EventLog::new();
+                game.subscribe(Box::new(log.clone()));
+                player.events = Some(log);
+            }
+            if stats {
+                player.stats = Some(GameStats::new(opponents as u8 + 1));
             }
-        });
-        SingleEnvironment {
-            action_sender,
+            player.searches = search_log.clone();
+            game.add_player(Box::new(player));
+            while let Ok(start) = control_receiver.recv() {
+                game.record_replays = game_record_replays.load(Ordering::Relaxed);
+                let notification = match start {
+                    GameStart::New(seed) => {
+                        if let Some(seed) = seed {
+                            rng = SmallRng::seed_from_u64(seed);
+                        }
+                        let pool_guard = pool.lock().unwrap();
+                        for (i, player) in game.players.iter_mut().take(opponents).enumerate() {
+                            let sampled = pool_guard.sample(&mut rng);
+                            if seed.is_some() || sampled != lineup[i] {
+                                *player = pool_guard.opponents[sampled].logged_player(rng.random(), search_log.as_ref());
+                                lineup[i] = sampled;
+                            }
+                        }
+                        drop(pool_guard);
+                        let position = game_start_positions.lock().unwrap().as_ref().and_then(|(corpus, min_turn)| corpus.sample(&mut rng, *min_turn));
+                        if let Some(checkpoint) = position {
+                            resume_game(&mut game, &mut rng, &checkpoint, format, opponents)
+                        } else {
+                            game.config.rules = config.rules.with_toggles(curriculum.lock().unwrap().rules());
+                            let mut state: State = Box::new(PythonState::new(format.board.layout, opponents as u8 + 1, format));
+                            let notification = game.setup_and_play_on(&mut rng, &mut state);
+                            if let Notification::GameFinished { winner } = notification {
+                                if winner != PlayerId::NONE {
+                                    // The Python player was added last
+                                    let won = game.seating[winner.to_usize()] == opponents;
+                                    pool.lock().unwrap().record(&lineup, won);
+                                }
+                            }
+                            notification
+                        }
+                    }
+                    GameStart::Resume(checkpoint) => resume_game(&mut game, &mut rng, &checkpoint, format, opponents),
+                };
+                replay_sender.send(game.replay.take()).expect("Failed sending replay");
+                if notification == (Notification::GameFinished { winner: PlayerId::NONE }) {
+                    // The game was reset before its end
+                    reset_sender.send(None).expect("Failed sending game reset");
+                }
+            }
+        })?;
+        Ok(SingleGame {
+            action_sender: Some(action_sender),
+            control_sender: Some(control_sender),
             observation_receiver: Mutex::new(observation_receiver),
             result_receiver: Mutex::new(result_receiver),
-            game_thread,
-            include_hidden: format.include_hidden,
+            checkpoint_receiver: Mutex::new(checkpoint_receiver),
+            replay_receiver: Mutex::new(replay_receiver),
+            record_replays,
+            games_ended: 0,
+            replays_received: 0,
+            replay: None,
+            player_count: opponents + 1,
+            board: format.board,
+            game_thread: Some(game_thread),
+            sequence: 0,
+            pending: false,
+            waiting: false,
+            policy_error: Arc::default(),
+            start_positions,
+        })
+    }
+
+    /// Next observation of the player, None once the game is finished
+    pub(crate) fn receive(&mut self, error: &str) -> PyResult<Option<(u8, PyCatanObservation)>> {
+        self.receive_timeout(error, None)
+    }
+
+    /// Next observation of the player waiting at most `timeout`, the observation can be received again after a timeout
+    pub(crate) fn receive_timeout(&mut self, error: &str, timeout: Option<Duration>) -> PyResult<Option<(u8, PyCatanObservation)>> {
+        if !self.waiting {
+            return Err(PyRuntimeError::new_err(format!("{}: no observation expected", error)));
+        }
+        let observation = receive_from(&self.observation_receiver, &mut self.game_thread, timeout, error)?;
+        self.waiting = false;
+        if let Some(error) = rejection_error(&observation) {
+            return Err(error);
+        }
+        self.pending = observation.is_some();
+        match &observation {
+            Some((_, observation)) => self.sequence = observation.sequence,
+            None => self.games_ended += 1,
+        }
+        // The game of an opponent raising an exception ends without result
+        if let (None, Some(error)) = (&observation, self.policy_error.lock().unwrap().take()) {
+            return Err(error);
+        }
+        Ok(observation)
+    }
+
+    /// Answers the last observation, the next one can then be received
+    pub(crate) fn play(&mut self, action: u16) -> PyResult<()> {
+        if self.waiting {
+            return Err(PyRuntimeError::new_err("The observation to answer wasn't received"));
+        }
+        send_to(self.action_sender.as_ref(), &mut self.game_thread, (self.sequence, action), "Failed to send action")?;
+        self.waiting = true;
+        Ok(())
+    }
+
+    /// Ends the game in progress, if any
+    fn abort(&mut self) -> PyResult<()> {
+        if self.waiting {
+            self.receive("Failed to read observation")?;
+        }
+        if self.pending {
+            send_to(self.action_sender.as_ref(), &mut self.game_thread, (self.sequence, RESET_ACTION), "Failed to send reset")?;
+            // Observations sent before the reset was noticed are dropped
+            while receive_from(&self.observation_receiver, &mut self.game_thread, None, "Failed to read reset")?.is_some() {}
+            self.pending = false;
+            self.games_ended += 1;
+        }
+        Ok(())
+    }
+
+    /// Ends the game in progress, if any, and starts a new one, its first observation can then be received
+    pub(crate) fn reset(&mut self, seed: Option<u64>) -> PyResult<()> {
+        self.abort()?;
+        send_to(self.control_sender.as_ref(), &mut self.game_thread, GameStart::New(seed), "Failed to start game")?;
+        self.waiting = true;
+        Ok(())
+    }
+
+    /// Checkpoint of the game waiting for the answer to the last observation, None without such a game
+    pub(crate) fn save(&mut self) -> PyResult<Option<Vec<u8>>> {
+        if !self.pending || self.waiting {
+            return Ok(None);
+        }
+        send_to(self.action_sender.as_ref(), &mut self.game_thread, (self.sequence, SAVE_ACTION), "Failed to send save")?;
+        receive_from(&self.checkpoint_receiver, &mut self.game_thread, None, "Failed to read checkpoint").map(Some)
+    }
+
+    /// Ends the game in progress, if any, and resumes the game of the checkpoint, its next observation can then be received
+    pub(crate) fn load(&mut self, bytes: &[u8]) -> PyResult<()> {
+        let checkpoint = Checkpoint::from_bytes(bytes).map_err(PyValueError::new_err)?;
+        if checkpoint.state.players.len() != self.player_count {
+            return Err(PyValueError::new_err(format!("Checkpoint of {} players for a game of {}", checkpoint.state.players.len(), self.player_count)));
+        }
+        if let Phase::FinishedGame { winner: _ } = checkpoint.phase {
+            return Err(PyValueError::new_err("Checkpoint of a finished game"));
+        }
+        checkpoint.state.restore::<TricellState>(self.board.layout).map_err(|error| PyValueError::new_err(format!("Bad checkpoint board: {:?}", error)))?;
+        self.abort()?;
+        send_to(self.control_sender.as_ref(), &mut self.game_thread, GameStart::Resume(Box::new(checkpoint)), "Failed to resume game")?;
+        self.waiting = true;
+        Ok(())
+    }
+
+    /// New games start from positions of the games of the corpus played from turn `min_turn` on, or from their start without corpus
+    pub(crate) fn set_start_positions(&self, games: Option<Vec<Vec<u8>>>, min_turn: u32) -> PyResult<()> {
+        let corpus = match games {
+            Some(games) => {
+                let corpus = Corpus::read(games.iter().map(|game| game.as_slice()), self.board.layout).map_err(PyValueError::new_err)?;
+                if !corpus.is_empty() && corpus.player_count() != self.player_count {
+                    return Err(PyValueError::new_err(format!("Games of {} players for a game of {}", corpus.player_count(), self.player_count)));
+                }
+                Some((corpus, min_turn))
+            }
+            None => None,
+        };
+        *self.start_positions.lock().unwrap() = corpus;
+        Ok(())
+    }
+
+    /// Games are recorded from the next one on
+    pub(crate) fn enable_replay(&self) {
+        self.record_replays.store(true, Ordering::Relaxed);
+    }
+
+    /// Replay of the last game which ended, when it was recorded
+    pub(crate) fn replay(&mut self) -> PyResult<Option<&Replay>> {
+        while self.replays_received < self.games_ended {
+            self.replay = receive_from(&self.replay_receiver, &mut self.game_thread, None, "Failed to read replay")?;
+            self.replays_received += 1;
+        }
+        Ok(self.replay.as_ref())
+    }
+
+    pub(crate) fn result(&mut self) -> PyResult<PlayerResult> {
+        receive_from(&self.result_receiver, &mut self.game_thread, None, "Failed to read results")
+    }
+
+    /// Ends the game in progress, if any, and stops the game thread, raising its panic if it panicked
+    pub(crate) fn close(&mut self) -> PyResult<()> {
+        // Without senders, the Python player ends the game in progress, and the game thread stops
+        self.action_sender = None;
+        self.control_sender = None;
+        self.pending = false;
+        self.waiting = false;
+        match self.game_thread.take() {
+            Some(game_thread) => join_game_thread(game_thread),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for SingleGame {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+#[pyclass]
+pub struct SingleEnvironment {
+    game: SingleGame,
+    format: PyObservationFormat,
+    dice: Arc<Mutex<DiceStatistics>>,
+    curriculum: Arc<Mutex<Curriculum>>,
+    development: Vec<DevelopmentUsage>,
+    /// Actions of the action masks
+    actions: Vec<Action>,
+    player_count: usize,
+    budget: bool,
+    rewards: bool,
+    /// Reward of the end of the game of the last result
+    reward: Option<f32>,
+    events: bool,
+    /// Events since the last observation of the game of the last result
+    last_events: Option<Vec<GameEvent>>,
+    /// Metrics of the game of the last result, when collected
+    last_stats: Option<GameStats>,
+    /// Decisions of the search opponents in the game of the last result, when logged
+    last_searches: Option<Vec<SearchRecord>>,
+    pool: Arc<Mutex<OpponentPool>>,
+}
+
+impl SingleEnvironment {
+    fn receive_observation(&mut self, py: Python, error: &str, timeout: Option<Duration>) -> PyResult<PyObject> {
+        let observation = self.game.receive_timeout(error, timeout)?;
+        Ok(to_py_tuple(py, &self.format, self.budget, self.rewards, self.events, observation))
+    }
+
+    fn check_buffers(&self, buffers: &[Bound<PyAny>]) -> PyResult<()> {
+        if self.format.include_stage {
+            return Err(PyValueError::new_err("Stage observations can't be written into buffers"));
+        }
+        if self.format.action_priors.is_some() {
+            return Err(PyValueError::new_err("Action priors can't be written into buffers"));
+        }
+        observation_buffers::check_count(&self.format, buffers, 0)
+    }
+
+    /// Writes the next observation into the buffers, returning `(seat, [budget], [reward], [events], done)`
+    fn receive_into(&mut self, py: Python, buffers: &[Bound<PyAny>], error: &str) -> PyResult<PyObject> {
+        let observation = self.game.receive(error)?;
+        let mut elements = vec![observation.as_ref().map_or(0, |(seat, _)| *seat).into_py_any(py)?];
+        if let Some((_, observation)) = &observation {
+            observation_buffers::write_observation(buffers, None, observation)?;
+        }
+        if self.budget {
+            elements.push(observation.as_ref().and_then(|(_, observation)| observation.budget).into_py_any(py)?);
+        }
+        if self.rewards {
+            elements.push(observation.as_ref().and_then(|(_, observation)| observation.reward).into_py_any(py)?);
+        }
+        if self.events {
+            let events = match observation.as_ref().and_then(|(_, observation)| observation.events.as_ref()) {
+                Some(events) => events.iter().map(|event| event_to_py(py, event)).collect::<PyResult<Vec<PyObject>>>()?.into_py_any(py)?,
+                None => py.None(),
+            };
+            elements.push(events);
         }
+        elements.push(observation.is_none().into_py_any(py)?);
+        elements.into_py_any(py)
     }
+}
 
+/// `n` environments of the same configuration for independent runs, each with its own seed drawn from `base_seed`
+///
+/// The keyword arguments are the ones of [SingleEnvironment::new] but `seed`. Seeds are drawn by a rng seeded by `base_seed`
+/// instead of counting up from it, so that runs of neighbouring base seeds don't share environments.
+/// A list `opponent_pool` becomes a pool of each environment, shuffled by the same rng,
+/// so that the environments start against other opponents and keep their own win rates.
+#[pyfunction]
+#[pyo3(signature = (n, base_seed, **cfg))]
+pub fn make_envs(py: Python, n: usize, base_seed: u64, cfg: Option<&Bound<'_, PyDict>>) -> PyResult<Vec<Py<SingleEnvironment>>> {
+    let cfg = match cfg {
+        Some(cfg) => cfg.copy()?,
+        None => PyDict::new(py),
+    };
+    if cfg.contains("seed")? {
+        return Err(PyValueError::new_err("The seeds of the environments are drawn from base_seed"));
+    }
+    let pool = match cfg.get_item("opponent_pool")? {
+        Some(pool) if pool.is_instance_of::<PyList>() => Some(pool.extract::<Vec<PyObject>>()?),
+        _ => None,
+    };
+    let mut rng = SmallRng::seed_from_u64(base_seed);
+    let class = py.get_type::<SingleEnvironment>();
+    (0..n).map(|_| {
+        let kwargs = cfg.copy()?;
+        kwargs.set_item("seed", rng.random::<u64>())?;
+        if let Some(pool) = &pool {
+            let mut order: Vec<usize> = (0..pool.len()).collect();
+            order.shuffle(&mut rng);
+            kwargs.set_item("opponent_pool", PyList::new(py, order.iter().map(|i| pool[*i].bind(py)))?)?;
+        }
+        class.call_method("new", (), Some(&kwargs))?.extract()
+    }).collect()
+}
+
+#[pymethods]
+impl SingleEnvironment {
+
+    /// With a `turn_budget` in seconds, observations carry the seconds left to the turn,
+    /// and the rest of the turn is played automatically once it runs out
+    ///
+    /// With `auto_resolve`, actions that are the only legal one, like most dice rolls, and discards of hands of a single
+    /// resource are played without an observation
+    ///
+    /// With a `seed`, the boards, players order, dice, development cards and opponents' choices are reproducible,
+    /// unless the opponents' search has a time budget
+    ///
+    /// `opponent` is `random`, `heuristic` for scripted opponents, or `mcts` for Monte-Carlo tree search opponents running `playouts` playouts per decision,
+    /// in at most `opponent_budget` seconds.
+    /// With a positive `opponent_temperature`, `heuristic` and `mcts` opponents sample their actions,
+    /// by a softmax of their scores or of the log of their visits divided by the temperature, instead of always playing the best one
+    ///
+    /// With `rewards`, observations carry the reward received since the previous observation,
+    /// the reward of the end of the game being given by [reward](SingleEnvironment::reward) after the result
+    ///
+    /// With `events`, observations carry the list of the events of the game since the previous observation, as dicts,
+    /// like dice rolls, buildings placed or cards stolen, the last ones being given by [events](SingleEnvironment::events) after the result
+    ///
+    /// `config` gives the house rules and starting conditions of the games, the rule toggles being the ones of the curriculum
+    ///
+    /// Illegal actions are refused by the game, which observes again, with `illegal_actions="retry"`,
+    /// raise an `IllegalActionError` with `"raise"`, or are replaced by ending the turn or rolling the dice when possible with `"resolve"`
+    ///
+    /// With an `opponent_pool`, replacing `opponent`, the opponent of each seat is sampled out of the pool for each new game,
+    /// `"uniform"`ly or `"prioritized"` by `pool_sampling` towards the opponents the player recently won the least against.
+    /// Entries are opponent names, or dicts with an `opponent`, and optionally `playouts`, `opponent_budget` and `opponent_temperature`.
+    /// The pool can also be a `HallOfFame`, whose bots are the opponents, in the order of its members
+    ///
+    /// An `opponent_policy`, replacing `opponent`, is a Python callable `policy(board, flat, mask)` returning the index of its action,
+    /// given the observation of its seat in the format, like a frozen policy of an earlier training.
+    /// Pool entries can be such callables too. An exception raised by the policy interrupts the game,
+    /// and is raised by the call waiting for the next observation.
+    ///
+    /// With `stats`, metrics of each game, like the dice rolled, the resources gained, the thief moves, the development cards played,
+    /// the trades and the turns played, are given by [stats](SingleEnvironment::stats) after the result
+    ///
+    /// With `search_stats`, the root visits and values of each decision of the `mcts` opponents are given by
+    /// [search_stats](SingleEnvironment::search_stats) after the result, as soft targets to distill the search into a policy
+    #[staticmethod]
+    #[pyo3(signature = (format, opponents=2, curriculum=false, turn_budget=None, auto_resolve=false, seed=None, opponent="random", playouts=100, opponent_budget=None, rewards=None, config=None, illegal_actions="retry", opponent_pool=None, pool_sampling="uniform", opponent_temperature=0.0, events=false, opponent_policy=None, stats=false, search_stats=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        format: &PyObservationFormat,
+        opponents: usize,
+        curriculum: bool,
+        turn_budget: Option<f64>,
+        auto_resolve: bool,
+        seed: Option<u64>,
+        opponent: &str,
+        playouts: usize,
+        opponent_budget: Option<f64>,
+        rewards: Option<PyRewardConfig>,
+        config: Option<PyGameConfig>,
+        illegal_actions: &str,
+        opponent_pool: Option<Bound<'_, PyAny>>,
+        pool_sampling: &str,
+        opponent_temperature: f32,
+        events: bool,
+        opponent_policy: Option<Bound<'_, PyAny>>,
+        stats: bool,
+        search_stats: bool,
+    ) -> PyResult<SingleEnvironment> {
+        let illegal_actions = IllegalActions::from_name(illegal_actions)?;
+        let config = game_config(config, format, opponents + 1)?;
+        let format = *format;
+        let policy_error = Arc::new(Mutex::new(None));
+        let pool = match (opponent_pool, opponent_policy) {
+            (Some(pool), _) => Arc::new(Mutex::new(OpponentPool::from_py(&pool, pool_sampling, format, &policy_error, playouts, opponent_budget, opponent_temperature)?)),
+            (None, Some(policy)) => OpponentPool::single(Opponent::from_py(&policy, format, &policy_error, playouts, opponent_budget, opponent_temperature)?),
+            (None, None) => OpponentPool::single(Opponent::from_name(opponent, playouts, opponent_budget, opponent_temperature)?),
+        };
+        let dice = Arc::new(Mutex::new(DiceStatistics::new()));
+        let curriculum = curriculum_for(curriculum);
+        let player_budget = turn_budget.map(Duration::from_secs_f64);
+        let mut game = SingleGame::spawn(format, opponents, pool.clone(), curriculum.clone(), dice.clone(), player_budget, auto_resolve, seed, rewards.map(|rewards| rewards.config), events, stats, search_stats, config, illegal_actions, None)?;
+        game.policy_error = policy_error;
+        Ok(SingleEnvironment {
+            game,
+            format,
+            dice,
+            curriculum,
+            development: vec![DevelopmentUsage::new()],
+            actions: game_actions(format, opponents + 1),
+            player_count: opponents + 1,
+            budget: turn_budget.is_some(),
+            rewards: rewards.is_some(),
+            reward: None,
+            events,
+            last_events: None,
+            last_stats: None,
+            last_searches: None,
+            pool,
+        })
+    }
+
+    /// Rate of the last games won against each opponent of the pool, None for opponents not played yet
+    fn pool_win_rates(&self) -> Vec<Option<f64>> {
+        self.pool.lock().unwrap().win_rates()
+    }
+
+    /// Starts the next game, see [reset](SingleEnvironment::reset)
     fn start(&mut self, py: Python) -> PyResult<PyObject> {
-        Ok(to_py_tuple(py, self.include_hidden, self.observation_receiver.lock().unwrap().recv().expect("Failed to read start observation")))
+        self.reset(py, None)
+    }
+
+    /// Ends the game in progress, if any, and starts a new one, reseeding the environment when given a `seed`
+    #[pyo3(signature = (seed=None))]
+    fn reset(&mut self, py: Python, seed: Option<u64>) -> PyResult<PyObject> {
+        self.game.reset(seed)?;
+        self.receive_observation(py, "Failed to read start observation", None)
+    }
+
+    /// Zeroed arrays `(board, flat, [hidden], [global], [hexes, intersections, paths], [tokens], actions)`,
+    /// for [reset_into](SingleEnvironment::reset_into) and [step_into](SingleEnvironment::step_into) to write the observations into
+    fn observation_buffers(&self, py: Python) -> Vec<PyObject> {
+        observation_buffers::allocate(py, &PyCatanObservation::sample(self.format, self.player_count, self.actions.len()), None)
+    }
+
+    /// Starts a new game as [reset](SingleEnvironment::reset), writing its first observation into the `buffers`
+    /// given by [observation_buffers](SingleEnvironment::observation_buffers), see [step_into](SingleEnvironment::step_into)
+    #[pyo3(signature = (buffers, seed=None))]
+    fn reset_into(&mut self, py: Python, buffers: Vec<Bound<'_, PyAny>>, seed: Option<u64>) -> PyResult<PyObject> {
+        self.check_buffers(&buffers)?;
+        self.game.reset(seed)?;
+        self.receive_into(py, &buffers, "Failed to read start observation")
+    }
+
+    /// Plays an action as [play](SingleEnvironment::play), writing the arrays of the next observation into the `buffers`
+    /// given by [observation_buffers](SingleEnvironment::observation_buffers) instead of allocating new ones
+    ///
+    /// Returns `(seat, [budget], [reward], done)`, the buffers being left untouched once the game is over.
+    /// Formats including the decision stage aren't supported.
+    fn step_into(&mut self, py: Python, action: u16, buffers: Vec<Bound<'_, PyAny>>) -> PyResult<PyObject> {
+        self.check_buffers(&buffers)?;
+        self.game.play(action)?;
+        self.receive_into(py, &buffers, "Failed to read play observation")
+    }
+
+    fn metrics(&self, py: Python) -> PyResult<PyObject> {
+        metrics_to_py(py, &self.dice.lock().unwrap())
+    }
+
+    /// Rules of the given curriculum stage are used from the next game on
+    fn set_stage(&mut self, stage: usize) -> PyResult<()> {
+        set_curriculum_stage(&self.curriculum, stage)
+    }
+
+    fn stage(&self) -> usize {
+        self.curriculum.lock().unwrap().stage()
+    }
+
+    /// Indices in the action mask of the actions of each decision stage, ordered like the stage values
+    fn stage_actions(&self, py: Python) -> Vec<PyObject> {
+        stage_indices_to_py(py, &self.actions)
+    }
+
+    /// Kind and parameters of the action at the index of the action mask, as a dict
+    ///
+    /// For instance `{"kind": "DevelopmentMonopole", "resource": "ore"}`,
+    /// year of plenty being played first, then followed by two `ChooseFreeResource`
+    fn describe_action(&self, py: Python, index: usize) -> PyResult<PyObject> {
+        describe_action(py, &self.actions, index)
+    }
+
+    /// Action at the index of the action mask
+    fn index_to_action(&self, index: usize) -> PyResult<PyAction> {
+        index_to_action(&self.actions, index)
+    }
+
+    /// Index in the action mask of an action
+    fn action_to_index(&self, action: &PyAction) -> PyResult<usize> {
+        action_to_index(&self.actions, action)
+    }
+
+    /// Descriptions of the legal actions of an action mask, each with its `index`
+    fn describe_legal_actions(&self, py: Python, mask: Vec<bool>) -> PyResult<Vec<PyObject>> {
+        describe_legal_actions(py, &self.actions, mask)
+    }
+
+    /// Indices in the action mask of the actions on the nodes and edges of the board graph, see `PyObservationFormat.graph_edges`
+    fn graph_actions(&self, py: Python) -> PyResult<PyObject> {
+        graph_actions(py, &self.actions, self.format.board.layout)
     }
 
     fn play(&mut self, py: Python, action: u16) -> PyResult<PyObject> {
-        self.action_sender.send(action).expect("Failed to send action");
-        self.game_thread.thread().unpark();
-        Ok(to_py_tuple(py, self.include_hidden, self.observation_receiver.lock().unwrap().recv().expect("Failed to read play observation")))
+        self.game.play(action)?;
+        self.receive_observation(py, "Failed to read play observation", None)
+    }
+
+    /// Awaitable [play](SingleEnvironment::play) for asyncio, waiting for the game in the default executor of the running loop
+    fn astep<'py>(slf: &Bound<'py, Self>, action: u16) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.as_any(), "play", (action,).into_pyobject(slf.py())?)
+    }
+
+    /// Awaitable [reset](SingleEnvironment::reset) for asyncio, see [astep](SingleEnvironment::astep)
+    #[pyo3(signature = (seed=None))]
+    fn areset<'py>(slf: &Bound<'py, Self>, seed: Option<u64>) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.as_any(), "reset", (seed,).into_pyobject(slf.py())?)
+    }
+
+    /// Plays an action, waiting at most `timeout_ms` milliseconds for the next observation
+    ///
+    /// Raises `TimeoutError` when the observation doesn't come in time, it can then be waited for with [wait](SingleEnvironment::wait)
+    fn play_timeout(&mut self, py: Python, action: u16, timeout_ms: u64) -> PyResult<PyObject> {
+        self.game.play(action)?;
+        self.receive_observation(py, "Failed to read play observation", Some(Duration::from_millis(timeout_ms)))
+    }
+
+    /// Observation which timed out, waiting at most `timeout_ms` milliseconds when given
+    #[pyo3(signature = (timeout_ms=None))]
+    fn wait(&mut self, py: Python, timeout_ms: Option<u64>) -> PyResult<PyObject> {
+        self.receive_observation(py, "Failed to read observation", timeout_ms.map(Duration::from_millis))
+    }
+
+    /// Ends the game in progress, if any, and stops the game thread, the environment can't be used afterwards
+    ///
+    /// Raises the panic of the game thread, if it panicked
+    fn close(&mut self) -> PyResult<()> {
+        self.game.close()
+    }
+
+    /// Checkpoint of the game, as bytes, taken while the last observation waits for its action
+    fn save_state<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let bytes = self.game.save()?.ok_or_else(|| PyValueError::new_err("No observation waiting for an action"))?;
+        Ok(PyBytes::new(py, &bytes))
+    }
+
+    /// Ends the game in progress, if any, and resumes the game of a checkpoint from [save_state](SingleEnvironment::save_state)
+    ///
+    /// The game keeps the rules it was saved with, and returns the observation the checkpoint was taken at
+    fn load_state(&mut self, py: Python, state: &[u8]) -> PyResult<PyObject> {
+        self.game.load(state)?;
+        self.receive_observation(py, "Failed to read loaded observation", None)
+    }
+
+    /// Starts the next games from positions of a corpus of games, or from the start of new games when `games` is None
+    ///
+    /// `games` are replays, as JSON or bincode bytes, or records, as JSON or notation bytes, of games of as many players on the board of the format.
+    /// Each reset then resumes the position before a ply drawn uniformly among the plies of the corpus played from turn `min_turn` on,
+    /// the Python player sitting at the seat of the player to act, and starts a new game when no game lasted that long.
+    /// Single positions are resumed with [load_state](SingleEnvironment::load_state).
+    #[pyo3(signature = (games, min_turn=0))]
+    fn set_start_positions(&mut self, games: Option<Vec<Vec<u8>>>, min_turn: u32) -> PyResult<()> {
+        self.game.set_start_positions(games, min_turn)
+    }
+
+    /// Records the games from the next one on, see [get_replay](SingleEnvironment::get_replay)
+    fn enable_replay(&self) {
+        self.game.enable_replay();
+    }
+
+    /// Replay of the last finished or reset game, as JSON or bincode bytes, None when it wasn't recorded
+    ///
+    /// The replay holds the starting state, and every action with its dice roll, drawn card and random draws,
+    /// so that `catan::replay::Replayer` can rebuild the state at any ply
+    ///
+    /// With `compact`, the replay is the bytes of `catan::replay::Replay::to_compact`, keeping only the actions and random draws,
+    /// which a `Trajectory` plays again to regenerate the observations
+    #[pyo3(signature = (binary=false, compact=false))]
+    fn get_replay(&mut self, py: Python, binary: bool, compact: bool) -> PyResult<PyObject> {
+        let layout = self.format.board.layout;
+        Ok(match self.game.replay()? {
+            None => py.None(),
+            Some(replay) if compact => PyBytes::new(py, &replay.to_compact(layout).map_err(PyRuntimeError::new_err)?).into_py_any(py)?,
+            Some(replay) if binary => PyBytes::new(py, &replay.to_bytes()).into_py_any(py)?,
+            Some(replay) => replay.to_json().into_py_any(py)?,
+        })
     }
 
     fn result(&mut self, _py: Python) -> PyResult<(u8,bool)> {
-        Ok(self.result_receiver.lock().unwrap().recv().expect("Failed to read results"))
+        let result = self.game.result()?;
+        self.development[0] = result.development;
+        self.reward = result.reward;
+        self.last_events = result.events;
+        self.last_stats = result.stats;
+        self.last_searches = result.searches;
+        Ok((result.victory_points, result.winner))
+    }
+
+    /// Reward received since the last observation of the game of the last result, with the win bonus, None without rewards
+    fn reward(&self) -> Option<f32> {
+        self.reward
+    }
+
+    /// Events since the last observation of the game of the last result, ending with `GameEnded`, None without events
+    fn events(&self, py: Python) -> PyResult<Option<Vec<PyObject>>> {
+        self.last_events.as_ref().map(|events| events.iter().map(|event| event_to_py(py, event)).collect()).transpose()
+    }
+
+    /// Development cards bought and played, before or after rolling, in the game of the last result
+    fn development_usage(&self, py: Python) -> PyResult<PyObject> {
+        development_usage_to_py(py, &self.development[0])
+    }
+
+    /// Metrics of the game of the last result, by seat, None without stats
+    fn stats(&self, py: Python) -> PyResult<Option<PyObject>> {
+        self.last_stats.as_ref().map(|stats| game_stats_to_py(py, stats)).transpose()
+    }
+
+    /// Decisions of the search opponents in the game of the last result, None without search stats
+    ///
+    /// Each decision gives the `seat` and `turn` it was made at, its legal `actions` as indices in the action mask of the seat,
+    /// the root `visits` and mean `values` of each, and the index of the action `played`
+    fn search_stats(&self, py: Python) -> PyResult<Option<Vec<PyObject>>> {
+        self.last_searches.as_ref().map(|searches| {
+            searches.iter().map(|record| search_record_to_py(py, &self.actions, self.player_count as u8, record)).collect()
+        }).transpose()
     }
 }
 
 
+/// Outcomes of the games played by a [MultiEnvironment], by seat
+struct SeatResults {
+    seats: SeatStatistics,
+    /// Winning seat of each game, grouped by board
+    board_winners: Vec<Vec<u8>>,
+}
+
 #[pyclass]
 pub struct MultiEnvironment {
     players: usize,
-    action_senders: Vec<Sender<u16>>,
+    /// Senders to the game thread, dropped once the environment is closed
+    action_senders: Vec<Sender<(u32, u16)>>,
+    /// Starts the next game, reseeding the environment when given a seed
+    control_sender: Option<Sender<Option<u64>>>,
     observation_receiver: Mutex<Receiver<Option<(u8, PyCatanObservation)>>>,
-    result_receivers: Vec<Mutex<Receiver<(u8,bool)>>>,
-    game_thread: thread::JoinHandle<()>,
-    include_hidden: bool,
+    result_receivers: Vec<Mutex<Receiver<PlayerResult>>>,
+    /// Game thread, None once closed or stopped
+    game_thread: Option<thread::JoinHandle<()>>,
+    format: PyObservationFormat,
+    dice: Arc<Mutex<DiceStatistics>>,
+    curriculum: Arc<Mutex<Curriculum>>,
+    development: Vec<DevelopmentUsage>,
+    /// Actions of the action masks
+    actions: Vec<Action>,
+    seat_results: Arc<Mutex<SeatResults>>,
+    budget: bool,
+    sequences: Vec<u32>,
+    infos: Vec<PlayerInfo>,
+    seats: Vec<u8>,
+    /// Player expected to answer the last observation
+    pending: Option<u8>,
+    /// Is an observation expected from the game thread
+    waiting: bool,
+    rewards: bool,
+    events: bool,
+    stats: bool,
+    /// Agent controlling each player, when agents control several players
+    agents: Option<Vec<usize>>,
+}
+
+impl MultiEnvironment {
+    fn receive(&mut self, error: &str, timeout: Option<Duration>) -> PyResult<Option<(u8, PyCatanObservation)>> {
+        if !self.waiting {
+            return Err(PyRuntimeError::new_err(format!("{}: no observation expected", error)));
+        }
+        let observation = receive_from(&self.observation_receiver, &mut self.game_thread, timeout, error)?;
+        self.waiting = false;
+        if let Some(error) = rejection_error(&observation) {
+            return Err(error);
+        }
+        self.pending = observation.as_ref().map(|(id, _)| *id);
+        if let Some((id, observation)) = &observation {
+            self.sequences[*id as usize] = observation.sequence;
+        }
+        Ok(observation)
+    }
+
+    fn receive_observation(&mut self, py: Python, error: &str, timeout: Option<Duration>) -> PyResult<PyObject> {
+        let observation = self.receive(error, timeout)?;
+        let player = observation.as_ref().map_or(0, |(id, _)| *id as usize);
+        let tuple = to_py_tuple(py, &self.format, self.budget, self.rewards, self.events, observation);
+        match &self.agents {
+            // The observation is tagged with the agent controlling the player first
+            Some(agents) => {
+                let mut elements = vec![agents[player].into_py_any(py)?];
+                elements.extend(tuple.bind(py).downcast::<PyTuple>()?.iter().map(|element| element.unbind()));
+                Ok(PyTuple::new(py, elements)?.into_any().unbind())
+            }
+            None => Ok(tuple),
+        }
+    }
+
+    /// Agent controlling each player, every player being controlled by exactly one of the agents
+    fn agents_of_players(agents: &[Vec<usize>], players: usize) -> PyResult<Vec<usize>> {
+        let mut controlling = vec![None; players];
+        for (agent, controlled) in agents.iter().enumerate() {
+            for player in controlled {
+                match controlling.get_mut(*player) {
+                    Some(slot @ None) => *slot = Some(agent),
+                    Some(Some(_)) => return Err(PyValueError::new_err(format!("Player {} is controlled by two agents", player))),
+                    None => return Err(PyValueError::new_err(format!("No player {} among {}", player, players))),
+                }
+            }
+        }
+        controlling.iter().enumerate()
+            .map(|(player, agent)| agent.ok_or_else(|| PyValueError::new_err(format!("Player {} isn't controlled by any agent", player))))
+            .collect()
+    }
+
+    fn send_action(&mut self, player: u8, action: u16) -> PyResult<()> {
+        if player as usize >= self.players {
+            return Err(PyValueError::new_err(format!("No player {} among {}", player, self.players)));
+        }
+        if self.waiting {
+            return Err(PyRuntimeError::new_err("The observation to answer wasn't received"));
+        }
+        send_to(self.action_senders.get(player as usize), &mut self.game_thread, (self.sequences[player as usize], action), "Failed to send action")?;
+        self.waiting = true;
+        Ok(())
+    }
+
+    /// Ends the game in progress, if any, and stops the game thread, raising its panic if it panicked
+    fn stop(&mut self) -> PyResult<()> {
+        // Without senders, the Python players end the game in progress, and the game thread stops
+        self.action_senders.clear();
+        self.control_sender = None;
+        self.pending = None;
+        self.waiting = false;
+        match self.game_thread.take() {
+            Some(game_thread) => join_game_thread(game_thread),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for MultiEnvironment {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
 }
 
 #[pymethods]
 impl MultiEnvironment {
 
+    /// With `mirror`, each board is played once per rotation of the players around the table,
+    /// with the same dice seed, so that every player sits once at every seat
     #[staticmethod]
-    #[pyo3(signature = (format, players=3))]
-    fn new(format: &PyObservationFormat, players: usize) -> MultiEnvironment {
+    /// With a `turn_budget` in seconds, observations carry the seconds left to the turn,
+    /// and the rest of the turn is played automatically once it runs out
+    ///
+    /// With `auto_resolve`, actions that are the only legal one, like most dice rolls, and discards of hands of a single
+    /// resource are played without an observation
+    ///
+    /// With a `seed`, the boards, players order, dice and development cards are reproducible
+    ///
+    /// `names` and `metadata` describe each player, and are reported by `result(detailed=True)`
+    ///
+    /// With `rewards`, observations carry the reward received since the previous observation of the player,
+    /// and `result(detailed=True)` the reward of the end of the game of each player,
+    /// the rewards of each player being multiplied by its `reward_scales`
+    ///
+    /// `config` gives the house rules and starting conditions of the games, the rule toggles being the ones of the curriculum
+    ///
+    /// `illegal_actions` handles illegal actions as in `SingleEnvironment`
+    ///
+    /// With `events`, observations carry the events seen by the player since its previous observation as in `SingleEnvironment`,
+    /// and `result(detailed=True)` the last events of each player
+    ///
+    /// With `stats`, `result(detailed=True)` also gives the metrics of the game as in `SingleEnvironment`, by seat
+    ///
+    /// With `cores`, the game thread is pinned to the first of the cores, and with `nice`, it runs at the niceness,
+    /// from -20, the highest priority, to 19, for environments played in parallel on a busy host,
+    /// a `RuntimeError` being raised when the game thread can't be placed
+    ///
+    /// `agents` lists the players each agent controls, every player being controlled by exactly one agent.
+    /// Observations then start with the agent to answer them, before the player, and agents play with `play_agent`
+    #[pyo3(signature = (format, players=3, curriculum=false, mirror=false, turn_budget=None, auto_resolve=false, seed=None, names=None, metadata=None, rewards=None, config=None, illegal_actions="retry", reward_scales=None, events=false, cores=None, nice=None, stats=false, agents=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        format: &PyObservationFormat,
+        players: usize,
+        curriculum: bool,
+        mirror: bool,
+        turn_budget: Option<f64>,
+        auto_resolve: bool,
+        seed: Option<u64>,
+        names: Option<Vec<String>>,
+        metadata: Option<Vec<BTreeMap<String, String>>>,
+        rewards: Option<PyRewardConfig>,
+        config: Option<PyGameConfig>,
+        illegal_actions: &str,
+        reward_scales: Option<Vec<f32>>,
+        events: bool,
+        cores: Option<Vec<usize>>,
+        nice: Option<i32>,
+        stats: bool,
+        agents: Option<Vec<Vec<usize>>>,
+    ) -> PyResult<MultiEnvironment> {
+        let agents = agents.map(|agents| MultiEnvironment::agents_of_players(&agents, players)).transpose()?;
+        let illegal_actions = IllegalActions::from_name(illegal_actions)?;
+        let placement = ThreadPlacement::new(cores, nice)?;
+        let reward_scales = reward_scales.unwrap_or_else(|| vec![1.0; players]);
+        if reward_scales.len() != players {
+            return Err(PyValueError::new_err("One reward scale is needed per player"));
+        }
+        let config = game_config(config, format, players)?;
+        let reward_config = rewards.map(|rewards| rewards.config);
         let format = *format;
+        let mut infos = Vec::new();
+        for id in 0..players {
+            let mut info = match &names {
+                Some(names) => PlayerInfo::new(names.get(id).ok_or_else(|| PyValueError::new_err("One name is needed per player"))?),
+                None => PlayerInfo::new(&format!("Player {}", id)),
+            };
+            if let Some(metadata) = &metadata {
+                let metadata = metadata.get(id).ok_or_else(|| PyValueError::new_err("One metadata dict is needed per player"))?;
+                info.metadata = metadata.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            }
+            infos.push(info);
+        }
+        let game_infos = infos.clone();
+        let player_budget = turn_budget.map(Duration::from_secs_f64);
         let mut action_senders = Vec::new();
         let mut action_receivers = Vec::new();
         let mut result_senders = Vec::new();
@@ -150,53 +1470,309 @@ impl MultiEnvironment {
             result_receivers.push(result_receiver);
         }
         let (observation_sender, observation_receiver) = channel();
-        let game_thread = thread::spawn(move || {
-            let mut game = Game::new();
-            for (id, (action_receiver, result_sender)) in action_receivers.into_iter().zip(result_senders.into_iter()).enumerate() {
-                game.add_player(Box::new(
-                    PythonPlayer::new(id as u8, format, action_receiver, observation_sender.clone(), result_sender))
-                );
+        let (control_sender, control_receiver) = channel::<Option<u64>>();
+        let dice = Arc::new(Mutex::new(DiceStatistics::new()));
+        let player_dice = dice.clone();
+        let curriculum = curriculum_for(curriculum);
+        let game_curriculum = curriculum.clone();
+        let seat_results = Arc::new(Mutex::new(SeatResults {
+            seats: SeatStatistics::new(players as u8),
+            board_winners: Vec::new(),
+        }));
+        let game_seat_results = seat_results.clone();
+        let game_thread = spawn_game_thread(Some((placement, 0)), move || {
+            let mut game = Game::with_config(config);
+            game.crash_dumps = Some(crash_dump_directory());
+            for (id, ((action_receiver, result_sender), info)) in action_receivers.into_iter().zip(result_senders.into_iter()).zip(game_infos).enumerate() {
+                let mut player = PythonPlayer::new(id as u8, format, action_receiver, observation_sender.clone(), result_sender, player_dice.clone(), player_budget);
+                player.auto_resolve = auto_resolve;
+                player.rewards = reward_config.map(|config| RewardTracker::new(RewardConfig { scale: config.scale * reward_scales[id], ..config }));
+                player.illegal_actions = illegal_actions;
+                if events {
+                    let log = EventLog::new();
+                    game.subscribe(Box::new(log.clone()));
+                    player.events = Some(log);
+                }
+                // Every player sees the same game, so only the first one collects its metrics
+                if stats && id == 0 {
+                    player.stats = Some(GameStats::new(players as u8));
+                }
+                game.add_named_player(Box::new(player), info);
             };
-            let mut rng = SmallRng::from_rng(&mut rand::rng());
-            loop {
-                let mut state = PythonState::new(&layout::DEFAULT, players as u8, format);
-                random_default_setup_existing_state::<PythonState, SmallRng>(&mut rng, &mut state);
-                let mut players_order: Vec<usize> = (0..players).collect();
-                players_order.shuffle(&mut rng);
+            let reset_sender = observation_sender.clone();
+            let mut rng = seeded_rng(seed);
+            let rotations = if mirror { players } else { 1 };
+            let mut rotation = rotations;
+            let mut board_seed = 0;
+            let mut players_order: Vec<usize> = (0..players).collect();
+            let mut winners = Vec::new();
+            while let Ok(seed) = control_receiver.recv() {
+                if let Some(seed) = seed {
+                    rng = SmallRng::seed_from_u64(seed);
+                    rotation = rotations;
+                }
+                if rotation == rotations {
+                    board_seed = rng.random();
+                    players_order = (0..players).collect();
+                    players_order.shuffle(&mut rng);
+                    rotation = 0;
+                    winners.clear();
+                }
+                game.config.rules = config.rules.with_toggles(game_curriculum.lock().unwrap().rules());
+                let mut board_rng = SmallRng::seed_from_u64(board_seed);
+                let mut state = PythonState::new(format.board.layout, players as u8, format);
+                format.board.setup_existing_state(&mut board_rng, &mut state);
                 let mut state: State = Box::new(state);
-                game.play(&mut rng, &mut state, players_order);
+                match game.play(&mut board_rng, &mut state, players_order.clone()) {
+                    Notification::GameFinished { winner: PlayerId::NONE } => {
+                        // The game was reset before its end, the next one starts on a new board
+                        reset_sender.send(None).expect("Failed sending game reset");
+                        rotation = rotations;
+                        continue;
+                    }
+                    Notification::GameFinished { winner } => {
+                        game_seat_results.lock().unwrap().seats.record(&state, winner);
+                        winners.push(winner.to_u8());
+                    }
+                    _ => (),
+                }
+                players_order.rotate_left(1);
+                rotation += 1;
+                if rotation == rotations {
+                    game_seat_results.lock().unwrap().board_winners.push(std::mem::take(&mut winners));
+                }
             }
-        });
-        MultiEnvironment {
+        })?;
+        Ok(MultiEnvironment {
             players,
             action_senders,
+            control_sender: Some(control_sender),
             observation_receiver: Mutex::new(observation_receiver),
             result_receivers: result_receivers.into_iter().map(Mutex::new).collect(),
-            game_thread,
-            include_hidden: format.include_hidden,
-        }
+            game_thread: Some(game_thread),
+            format,
+            dice,
+            curriculum,
+            development: vec![DevelopmentUsage::new(); players],
+            actions: game_actions(format, players),
+            seat_results,
+            budget: turn_budget.is_some(),
+            sequences: vec![0; players],
+            infos,
+            seats: vec![0; players],
+            pending: None,
+            waiting: false,
+            rewards: rewards.is_some(),
+            events,
+            stats,
+            agents,
+        })
     }
 
+    /// Starts the next game, see [reset](MultiEnvironment::reset)
     fn start(&mut self, py: Python) -> PyResult<PyObject> {
-        Ok(to_py_tuple(py, self.include_hidden, self.observation_receiver.lock().unwrap().recv().expect("Failed to read start observation")))
+        self.reset(py, None)
+    }
+
+    /// Ends the game in progress, if any, and starts a new one, reseeding the environment when given a `seed`
+    #[pyo3(signature = (seed=None))]
+    fn reset(&mut self, py: Python, seed: Option<u64>) -> PyResult<PyObject> {
+        if self.waiting {
+            self.receive("Failed to read observation", None)?;
+        }
+        if let Some(player) = self.pending.take() {
+            send_to(self.action_senders.get(player as usize), &mut self.game_thread, (self.sequences[player as usize], RESET_ACTION), "Failed to send reset")?;
+            // Observations sent before the reset was noticed are dropped
+            while receive_from(&self.observation_receiver, &mut self.game_thread, None, "Failed to read reset")?.is_some() {}
+        }
+        send_to(self.control_sender.as_ref(), &mut self.game_thread, seed, "Failed to start game")?;
+        self.waiting = true;
+        self.receive_observation(py, "Failed to read start observation", None)
+    }
+
+    fn metrics(&self, py: Python) -> PyResult<PyObject> {
+        metrics_to_py(py, &self.dice.lock().unwrap())
+    }
+
+    /// Wins and victory points by seat, and the winning seat of each game grouped by board
+    fn seat_results(&self, py: Python) -> PyResult<PyObject> {
+        let results = self.seat_results.lock().unwrap();
+        let dict = PyDict::new(py);
+        dict.set_item("games", results.seats.games())?;
+        dict.set_item("seat_wins", results.seats.wins().clone())?;
+        dict.set_item("seat_victory_points", results.seats.victory_points().clone())?;
+        dict.set_item("board_winners", results.board_winners.clone())?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Rules of the given curriculum stage are used from the next game on
+    fn set_stage(&mut self, stage: usize) -> PyResult<()> {
+        set_curriculum_stage(&self.curriculum, stage)
+    }
+
+    fn stage(&self) -> usize {
+        self.curriculum.lock().unwrap().stage()
+    }
+
+    /// Indices in the action mask of the actions of each decision stage, ordered like the stage values
+    fn stage_actions(&self, py: Python) -> Vec<PyObject> {
+        stage_indices_to_py(py, &self.actions)
+    }
+
+    /// Kind and parameters of the action at the index of the action mask, as a dict
+    ///
+    /// For instance `{"kind": "DevelopmentMonopole", "resource": "ore"}`,
+    /// year of plenty being played first, then followed by two `ChooseFreeResource`
+    fn describe_action(&self, py: Python, index: usize) -> PyResult<PyObject> {
+        describe_action(py, &self.actions, index)
+    }
+
+    /// Action at the index of the action mask
+    fn index_to_action(&self, index: usize) -> PyResult<PyAction> {
+        index_to_action(&self.actions, index)
+    }
+
+    /// Index in the action mask of an action
+    fn action_to_index(&self, action: &PyAction) -> PyResult<usize> {
+        action_to_index(&self.actions, action)
+    }
+
+    /// Descriptions of the legal actions of an action mask, each with its `index`
+    fn describe_legal_actions(&self, py: Python, mask: Vec<bool>) -> PyResult<Vec<PyObject>> {
+        describe_legal_actions(py, &self.actions, mask)
+    }
+
+    /// Indices in the action mask of the actions on the nodes and edges of the board graph, see `PyObservationFormat.graph_edges`
+    fn graph_actions(&self, py: Python) -> PyResult<PyObject> {
+        graph_actions(py, &self.actions, self.format.board.layout)
     }
 
     fn play(&mut self, py: Python, player: u8, action: u16) -> PyResult<PyObject> {
-        self.action_senders[player as usize].send(action).expect("Failed to send action");
-        self.game_thread.thread().unpark();
-        Ok(to_py_tuple(py, self.include_hidden, self.observation_receiver.lock().unwrap().recv().expect("Failed to read play observation")))
+        self.send_action(player, action)?;
+        self.receive_observation(py, "Failed to read play observation", None)
+    }
+
+    /// Plays an action for the player the agent answers the last observation of, see `agents`
+    fn play_agent(&mut self, py: Python, agent: usize, action: u16) -> PyResult<PyObject> {
+        let player = match (&self.agents, self.pending) {
+            (Some(agents), Some(player)) if agents[player as usize] == agent => player,
+            (Some(_), Some(player)) => return Err(PyValueError::new_err(format!("Agent {} doesn't control player {}", agent, player))),
+            (Some(_), None) => return Err(PyRuntimeError::new_err("No observation to answer")),
+            (None, _) => return Err(PyRuntimeError::new_err("The environment was created without agents")),
+        };
+        self.play(py, player, action)
+    }
+
+    /// Players controlled by each agent, None without agents
+    fn agent_players(&self) -> Option<Vec<Vec<u8>>> {
+        self.agents.as_ref().map(|agents| {
+            let mut players = vec![Vec::new(); agents.iter().max().map_or(0, |agent| agent + 1)];
+            for (player, agent) in agents.iter().enumerate() {
+                players[*agent].push(player as u8);
+            }
+            players
+        })
+    }
+
+    /// Resigns the player deciding, the others playing on without it, and returns the next observation
+    ///
+    /// Its pieces stay on the board or are removed after the `resigned_pieces` of the game config.
+    /// Resigning is refused during the initial placement, the same decision being observed again.
+    fn resign(&mut self, py: Python, player: u8) -> PyResult<PyObject> {
+        self.send_action(player, RESIGN_ACTION)?;
+        self.receive_observation(py, "Failed to read resign observation", None)
+    }
+
+    /// Awaitable [play](MultiEnvironment::play) for asyncio, waiting for the game in the default executor of the running loop
+    fn astep<'py>(slf: &Bound<'py, Self>, player: u8, action: u16) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.as_any(), "play", (player, action).into_pyobject(slf.py())?)
     }
 
-    fn result(&mut self, py: Python) -> PyResult<(PyObject, u8)> {
+    /// Awaitable [reset](MultiEnvironment::reset) for asyncio, see [astep](MultiEnvironment::astep)
+    #[pyo3(signature = (seed=None))]
+    fn areset<'py>(slf: &Bound<'py, Self>, seed: Option<u64>) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.as_any(), "reset", (seed,).into_pyobject(slf.py())?)
+    }
+
+    /// Plays an action, waiting at most `timeout_ms` milliseconds for the next observation
+    ///
+    /// Raises `TimeoutError` when the observation doesn't come in time, it can then be waited for with [wait](MultiEnvironment::wait)
+    fn play_timeout(&mut self, py: Python, player: u8, action: u16, timeout_ms: u64) -> PyResult<PyObject> {
+        self.send_action(player, action)?;
+        self.receive_observation(py, "Failed to read play observation", Some(Duration::from_millis(timeout_ms)))
+    }
+
+    /// Observation which timed out, waiting at most `timeout_ms` milliseconds when given
+    #[pyo3(signature = (timeout_ms=None))]
+    fn wait(&mut self, py: Python, timeout_ms: Option<u64>) -> PyResult<PyObject> {
+        self.receive_observation(py, "Failed to read observation", timeout_ms.map(Duration::from_millis))
+    }
+
+    /// Ends the game in progress, if any, and stops the game thread, the environment can't be used afterwards
+    ///
+    /// Raises the panic of the game thread, if it panicked
+    fn close(&mut self) -> PyResult<()> {
+        self.stop()
+    }
+
+    /// Victory points of each player and the winner
+    ///
+    /// When `detailed`, returns a dict also holding the seat, name and metadata of each player, with agents the agent controlling it,
+    /// with rewards, the reward of the end of the game of each player,
+    /// with events, the events each player saw since its last observation,
+    /// and with stats, the metrics of the game
+    #[pyo3(signature = (detailed=false))]
+    fn result(&mut self, py: Python, detailed: bool) -> PyResult<PyObject> {
         let mut winner = 0;
         let mut vps = Array1::<u8>::zeros(self.players);
+        let mut rewards = Array1::<f32>::zeros(self.players);
+        let mut events = Vec::new();
+        let mut stats = None;
         for player in 0..self.players {
-            let result = self.result_receivers[player].lock().unwrap().recv().expect("Failed to read results");
-            vps[player] = result.0;
-            if result.1 {
+            let result = receive_from(&self.result_receivers[player], &mut self.game_thread, None, "Failed to read results")?;
+            vps[player] = result.victory_points;
+            rewards[player] = result.reward.unwrap_or(0.0);
+            events.push(result.events.unwrap_or_default());
+            if result.winner {
                 winner = player;
             }
+            self.development[player] = result.development;
+            self.seats[player] = result.seat.to_u8();
+            stats = stats.or(result.stats);
+        }
+        let vps = vps.into_pyarray(py).into_py_any(py).unwrap();
+        if !detailed {
+            return (vps, winner as u8).into_py_any(py);
+        }
+        let dict = PyDict::new(py);
+        dict.set_item("victory_points", vps)?;
+        dict.set_item("winner", winner as u8)?;
+        dict.set_item("seats", self.seats.clone())?;
+        dict.set_item("names", self.infos.iter().map(|info| info.name.clone()).collect::<Vec<_>>())?;
+        let metadata = self.infos.iter()
+            .map(|info| info.metadata.iter().cloned().collect::<BTreeMap<String, String>>())
+            .collect::<Vec<_>>();
+        dict.set_item("metadata", metadata)?;
+        if let Some(agents) = &self.agents {
+            dict.set_item("agents", agents.clone())?;
         }
-        Ok((vps.into_pyarray(py).into_py_any(py).unwrap(), winner as u8))
+        if self.rewards {
+            dict.set_item("rewards", rewards.into_pyarray(py))?;
+        }
+        if self.events {
+            let events = events.iter()
+                .map(|events| events.iter().map(|event| event_to_py(py, event)).collect::<PyResult<Vec<PyObject>>>())
+                .collect::<PyResult<Vec<_>>>()?;
+            dict.set_item("events", events)?;
+        }
+        if self.stats {
+            dict.set_item("stats", stats.map(|stats| game_stats_to_py(py, &stats)).transpose()?)?;
+        }
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Development cards bought and played, before or after rolling, by each player in the game of the last result
+    fn development_usage(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        self.development.iter().map(|usage| development_usage_to_py(py, usage)).collect()
     }
 }
@@ -0,0 +1,219 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use numpy::convert::IntoPyArray;
+use std::sync::{Arc, Mutex};
+
+use catan::game::{Action, DiceStatistics, Curriculum};
+use super::{PyCatanObservation, PyObservationFormat, PyRewardConfig, PyGameConfig};
+use super::py_game_config::game_config;
+use super::python_player::IllegalActions;
+use super::environment::{SingleGame, OpponentPool, Opponent, game_actions, run_in_executor};
+use super::py_action::{PyAction, describe_action, index_to_action, action_to_index, describe_legal_actions, graph_actions};
+
+/// Observation as a dict of arrays, the action mask under `action_mask`
+///
+/// Decision stages are left out, their action masks having a length varying with the stage
+fn observation_to_py(py: Python, observation: PyCatanObservation) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    dict.set_item("board", observation.board.into_pyarray(py))?;
+    dict.set_item("flat", observation.flat.into_pyarray(py))?;
+    if let Some(hidden) = observation.hidden {
+        dict.set_item("hidden", hidden.into_pyarray(py))?;
+    }
+    if let Some(global) = observation.global {
+        dict.set_item("global", global.into_pyarray(py))?;
+    }
+    if let Some(graph) = observation.graph {
+        dict.set_item("hexes", graph.hexes.into_pyarray(py))?;
+        dict.set_item("intersections", graph.intersections.into_pyarray(py))?;
+        dict.set_item("paths", graph.paths.into_pyarray(py))?;
+    }
+    if let Some(tokens) = observation.tokens {
+        dict.set_item("tokens", tokens.into_pyarray(py))?;
+    }
+    if let Some(priors) = observation.priors {
+        dict.set_item("action_priors", priors.into_pyarray(py))?;
+    }
+    dict.set_item("action_mask", observation.actions.into_pyarray(py))?;
+    Ok(dict.into_any().unbind())
+}
+
+/// Game of a Python player against bot opponents, following the Gymnasium API
+///
+/// Observations are dicts of arrays, shaped as given by `observation_space_shapes()`,
+/// and actions are indices of the `action_mask` among `action_space_size()` actions.
+/// Without `rewards`, the reward is 1 when the player wins the game and 0 otherwise, given with the last step.
+#[pyclass]
+pub struct GymEnvironment {
+    game: SingleGame,
+    format: PyObservationFormat,
+    /// Actions of the action masks
+    actions: Vec<Action>,
+    player_count: usize,
+    /// Seat of the player in the game in progress
+    seat: u8,
+    /// Is a game waiting for an action
+    running: bool,
+}
+
+impl GymEnvironment {
+    /// Observation of the start of a game, whose arrays give the shapes of every observation
+    fn sample_observation(&self) -> PyCatanObservation {
+        PyCatanObservation::sample(self.format, self.player_count, self.actions.len())
+    }
+}
+
+#[pymethods]
+impl GymEnvironment {
+
+    /// `opponent`, `playouts`, `opponent_budget` and `opponent_temperature` choose the opponents as in `SingleEnvironment`,
+    /// `rewards` gives the reward of each step, `config` the house rules and starting conditions of the games,
+    /// and `illegal_actions` handles illegal actions as in `SingleEnvironment`
+    #[new]
+    #[pyo3(signature = (format, opponents=2, auto_resolve=false, seed=None, opponent="random", playouts=100, opponent_budget=None, rewards=None, config=None, illegal_actions="retry", opponent_temperature=0.0))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        format: &PyObservationFormat,
+        opponents: usize,
+        auto_resolve: bool,
+        seed: Option<u64>,
+        opponent: &str,
+        playouts: usize,
+        opponent_budget: Option<f64>,
+        rewards: Option<PyRewardConfig>,
+        config: Option<PyGameConfig>,
+        illegal_actions: &str,
+        opponent_temperature: f32,
+    ) -> PyResult<GymEnvironment> {
+        let illegal_actions = IllegalActions::from_name(illegal_actions)?;
+        let config = game_config(config, format, opponents + 1)?;
+        let format = *format;
+        let opponent = Opponent::from_name(opponent, playouts, opponent_budget, opponent_temperature)?;
+        let curriculum = Arc::new(Mutex::new(Curriculum::standard()));
+        let dice = Arc::new(Mutex::new(DiceStatistics::new()));
+        Ok(GymEnvironment {
+            game: SingleGame::spawn(format, opponents, OpponentPool::single(opponent), curriculum, dice, None, auto_resolve, seed, rewards.map(|rewards| rewards.config), false, false, false, config, illegal_actions, None)?,
+            format,
+            actions: game_actions(format, opponents + 1),
+            player_count: opponents + 1,
+            seat: 0,
+            running: false,
+        })
+    }
+
+    /// Shape of each array of the observations, by key
+    fn observation_space_shapes(&self, py: Python) -> PyResult<PyObject> {
+        let observation = self.sample_observation();
+        let shapes = PyDict::new(py);
+        shapes.set_item("board", observation.board.shape().to_vec())?;
+        shapes.set_item("flat", observation.flat.shape().to_vec())?;
+        if let Some(hidden) = observation.hidden {
+            shapes.set_item("hidden", hidden.shape().to_vec())?;
+        }
+        if let Some(global) = observation.global {
+            shapes.set_item("global", global.shape().to_vec())?;
+        }
+        if let Some(graph) = observation.graph {
+            shapes.set_item("hexes", graph.hexes.shape().to_vec())?;
+            shapes.set_item("intersections", graph.intersections.shape().to_vec())?;
+            shapes.set_item("paths", graph.paths.shape().to_vec())?;
+        }
+        if let Some(tokens) = observation.tokens {
+            shapes.set_item("tokens", tokens.shape().to_vec())?;
+        }
+        if self.format.action_priors.is_some() {
+            shapes.set_item("action_priors", observation.actions.shape().to_vec())?;
+        }
+        shapes.set_item("action_mask", observation.actions.shape().to_vec())?;
+        Ok(shapes.into_any().unbind())
+    }
+
+    fn action_space_size(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Kind and parameters of the action at the index of the action mask, as a dict
+    fn describe_action(&self, py: Python, index: usize) -> PyResult<PyObject> {
+        describe_action(py, &self.actions, index)
+    }
+
+    /// Action at the index of the action mask
+    fn index_to_action(&self, index: usize) -> PyResult<PyAction> {
+        index_to_action(&self.actions, index)
+    }
+
+    /// Index in the action mask of an action
+    fn action_to_index(&self, action: &PyAction) -> PyResult<usize> {
+        action_to_index(&self.actions, action)
+    }
+
+    /// Descriptions of the legal actions of an action mask, each with its `index`
+    fn describe_legal_actions(&self, py: Python, mask: Vec<bool>) -> PyResult<Vec<PyObject>> {
+        describe_legal_actions(py, &self.actions, mask)
+    }
+
+    /// Indices in the action mask of the actions on the nodes and edges of the board graph, see `PyObservationFormat.graph_edges`
+    fn graph_actions(&self, py: Python) -> PyResult<PyObject> {
+        graph_actions(py, &self.actions, self.format.board.layout)
+    }
+
+    /// Ends the game in progress, if any, and starts a new one, returning `(observation, info)`
+    ///
+    /// A `seed` reseeds the environment, and `info` holds the `seat` of the player
+    #[pyo3(signature = (seed=None, options=None))]
+    fn reset(&mut self, py: Python, seed: Option<u64>, options: Option<PyObject>) -> PyResult<(PyObject, PyObject)> {
+        // Gymnasium options have no meaning here
+        let _ = options;
+        self.game.reset(seed)?;
+        let (seat, observation) = self.game.receive("Failed to read start observation")?.expect("Game finished before its start");
+        self.seat = seat;
+        self.running = true;
+        let info = PyDict::new(py);
+        info.set_item("seat", seat)?;
+        Ok((observation_to_py(py, observation)?, info.into_any().unbind()))
+    }
+
+    /// Plays the action of an index of the action mask, returning `(observation, reward, terminated, truncated, info)`
+    ///
+    /// Once the game is over, the observation is the one of the start of a game with no legal action,
+    /// and `info` holds the `victory_points` of the player and whether it is the `winner`
+    fn step(&mut self, py: Python, action: u16) -> PyResult<(PyObject, f32, bool, bool, PyObject)> {
+        if !self.running {
+            return Err(pyo3::exceptions::PyRuntimeError::new_err("No game in progress, reset must be called first"));
+        }
+        self.game.play(action)?;
+        let info = PyDict::new(py);
+        info.set_item("seat", self.seat)?;
+        match self.game.receive("Failed to read step observation")? {
+            Some((_, observation)) => {
+                let reward = observation.reward.unwrap_or(0.0);
+                Ok((observation_to_py(py, observation)?, reward, false, false, info.into_any().unbind()))
+            }
+            None => {
+                self.running = false;
+                let result = self.game.result()?;
+                info.set_item("victory_points", result.victory_points)?;
+                info.set_item("winner", result.winner)?;
+                let reward = result.reward.unwrap_or(if result.winner { 1.0 } else { 0.0 });
+                Ok((observation_to_py(py, self.sample_observation())?, reward, true, false, info.into_any().unbind()))
+            }
+        }
+    }
+
+    /// Awaitable [step](GymEnvironment::step) for asyncio, waiting for the game in the default executor of the running loop
+    fn astep<'py>(slf: &Bound<'py, Self>, action: u16) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.as_any(), "step", (action,).into_pyobject(slf.py())?)
+    }
+
+    /// Awaitable [reset](GymEnvironment::reset) for asyncio, see [astep](GymEnvironment::astep)
+    #[pyo3(signature = (seed=None, options=None))]
+    fn areset<'py>(slf: &Bound<'py, Self>, seed: Option<u64>, options: Option<PyObject>) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.as_any(), "reset", (seed, options).into_pyobject(slf.py())?)
+    }
+
+    /// Ends the game in progress, if any, and stops the game thread, raising its panic if it panicked
+    fn close(&mut self) -> PyResult<()> {
+        self.running = false;
+        self.game.close()
+    }
+}
@@ -0,0 +1,76 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::exceptions::PyValueError;
+use std::path::Path;
+
+use catan::hall_of_fame::{HallOfFame, Member};
+use super::environment::Opponent;
+
+/// Directory of model checkpoints, like ONNX files, and bots with their ratings, see [HallOfFame]
+///
+/// An `Arena` given the hall of fame seats its members and records their new ratings,
+/// and a `SingleEnvironment` given it as `opponent_pool` samples its bots as opponents.
+#[pyclass(name="HallOfFame")]
+pub struct PyHallOfFame {
+    pub(crate) hall: HallOfFame,
+}
+
+impl PyHallOfFame {
+    fn member_to_py(&self, py: Python, member: &Member) -> PyResult<PyObject> {
+        let dict = PyDict::new(py);
+        dict.set_item("name", &member.name)?;
+        dict.set_item("model", self.hall.model_path(member))?;
+        dict.set_item("bot", &member.bot)?;
+        dict.set_item("elo", member.elo)?;
+        dict.set_item("games", member.games)?;
+        dict.set_item("wins", member.wins)?;
+        Ok(dict.into_any().unbind())
+    }
+}
+
+#[pymethods]
+impl PyHallOfFame {
+    /// Hall of fame of the `directory`, created when missing
+    #[new]
+    fn new(directory: &str) -> PyResult<PyHallOfFame> {
+        Ok(PyHallOfFame {
+            hall: HallOfFame::open(directory).map_err(PyValueError::new_err)?,
+        })
+    }
+
+    #[getter]
+    fn directory(&self) -> String {
+        self.hall.directory().display().to_string()
+    }
+
+    /// Adds a checkpoint, copying its `model` file into the directory
+    fn add_model(&mut self, py: Python, name: &str, model: &str) -> PyResult<PyObject> {
+        let member = self.hall.add_model(name, Path::new(model)).map_err(PyValueError::new_err)?.clone();
+        self.member_to_py(py, &member)
+    }
+
+    /// Adds a bot, `random`, `heuristic` or `mcts`
+    fn add_bot(&mut self, py: Python, name: &str, bot: &str) -> PyResult<PyObject> {
+        Opponent::from_name(bot, 0, None, 0.0)?;
+        let member = self.hall.add_bot(name, bot).map_err(PyValueError::new_err)?.clone();
+        self.member_to_py(py, &member)
+    }
+
+    /// Removes a member, deleting its model file
+    fn remove(&mut self, name: &str) -> PyResult<()> {
+        self.hall.remove(name).map_err(PyValueError::new_err)?;
+        Ok(())
+    }
+
+    /// Members as dicts with their `name`, `model` path or `bot`, `elo`, and the `games` and `wins` of the recorded tournaments,
+    /// in the order they were added, or from the best rated with `ranked`
+    #[pyo3(signature = (ranked=false))]
+    fn members(&self, py: Python, ranked: bool) -> PyResult<Vec<PyObject>> {
+        let members: Vec<&Member> = if ranked { self.hall.ranked() } else { self.hall.members().iter().collect() };
+        members.into_iter().map(|member| self.member_to_py(py, member)).collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.hall.members().len()
+    }
+}
@@ -1,22 +1,124 @@
 mod environment;
+mod vec_environment;
+mod gym_environment;
 mod python_state;
 mod python_player;
 mod py_catan_observation;
 mod py_observation_format;
+mod py_discard_codec;
+mod codec;
+mod py_reward_config;
+mod py_game_config;
+mod py_action;
+mod render;
+mod notation;
+mod rules;
+mod tokens;
+mod arena;
+mod observation_buffers;
+mod hall_of_fame;
+mod callback_player;
+mod thread_placement;
+mod simulator;
+mod trajectory;
+mod database;
+
+#[cfg(test)]
+mod test;
 
 use pyo3::prelude::*;
 
-use environment::{SingleEnvironment, MultiEnvironment};
+use environment::{SingleEnvironment, MultiEnvironment, IllegalActionError};
+use vec_environment::VecEnvironment;
+use gym_environment::GymEnvironment;
+use arena::PyArena;
+use hall_of_fame::PyHallOfFame;
+use simulator::PySimulator;
+use trajectory::PyTrajectory;
+use database::PyGameDatabase;
 use python_state::PythonState;
-use python_player::PythonPlayer;
+use python_player::{PythonPlayer, PlayerResult};
 use py_catan_observation::PyCatanObservation;
 pub use py_observation_format::PyObservationFormat;
+pub use py_discard_codec::PyDiscardCodec;
+pub use py_reward_config::PyRewardConfig;
+pub use py_game_config::PyGameConfig;
+pub use py_action::PyAction;
 
-#[pymodule]
-fn pycatan(m: &Bound<'_, PyModule>) -> PyResult<()> {
+/// Adds a submodule, importable as `pycatan.<name>`
+fn add_submodule<'py>(parent: &Bound<'py, PyModule>, name: &str, fill: impl FnOnce(&Bound<'py, PyModule>) -> PyResult<()>) -> PyResult<()> {
+    let py = parent.py();
+    let module = PyModule::new(py, name)?;
+    fill(&module)?;
+    parent.add_submodule(&module)?;
+    py.import("sys")?.getattr("modules")?.set_item(format!("pycatan.{}", name), &module)?;
+    Ok(())
+}
+
+fn add_env_classes(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<SingleEnvironment>()?;
     m.add_class::<MultiEnvironment>()?;
+    m.add_class::<VecEnvironment>()?;
+    m.add_class::<GymEnvironment>()?;
+    m.add_class::<PyArena>()?;
+    m.add_class::<PyHallOfFame>()?;
+    m.add_class::<PySimulator>()?;
+    m.add_class::<PyTrajectory>()?;
+    m.add_class::<PyGameDatabase>()?;
     m.add_class::<PyObservationFormat>()?;
+    m.add_class::<PyRewardConfig>()?;
+    m.add_class::<PyGameConfig>()?;
+    m.add("IllegalActionError", m.py().get_type::<IllegalActionError>())?;
+    m.add_function(wrap_pyfunction!(environment::make_envs, m)?)?;
+    Ok(())
+}
+
+fn add_actions_classes(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDiscardCodec>()?;
+    m.add_class::<PyAction>()?;
+    m.add_function(wrap_pyfunction!(codec::codec_fixtures, m)?)?;
+    m.add_function(wrap_pyfunction!(codec::validate_codec_fixtures, m)?)?;
+    Ok(())
+}
+
+/// Allocations made so far by every thread, as `{"count": ..., "bytes": ...}`,
+/// or None when pycatan wasn't built with the `alloc-counter` feature
+#[pyfunction]
+fn allocations(py: Python) -> PyResult<Option<PyObject>> {
+    #[cfg(feature = "alloc-counter")]
+    {
+        let allocations = catan::alloc_counter::Allocations::now();
+        let dict = pyo3::types::PyDict::new(py);
+        dict.set_item("count", allocations.count)?;
+        dict.set_item("bytes", allocations.bytes)?;
+        Ok(Some(dict.into_any().unbind()))
+    }
+    #[cfg(not(feature = "alloc-counter"))]
+    {
+        let _ = py;
+        Ok(None)
+    }
+}
+
+/// Environments in `pycatan.env`, actions and their codecs in `pycatan.actions`, drawings in `pycatan.render`
+/// game records in `pycatan.notation` and explanations of the decisions in `pycatan.rules`,
+/// the classes being also available from `pycatan` itself
+#[pymodule]
+fn pycatan(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    add_env_classes(m)?;
+    add_actions_classes(m)?;
+    add_submodule(m, "env", add_env_classes)?;
+    add_submodule(m, "actions", add_actions_classes)?;
+    add_submodule(m, "render", |render| {
+        render.add_function(wrap_pyfunction!(render::checkpoint_text, render)?)?;
+        render.add_function(wrap_pyfunction!(render::checkpoint_diff, render)?)
+    })?;
+    add_submodule(m, "notation", |notation| {
+        notation.add_function(wrap_pyfunction!(notation::export_record, notation)?)?;
+        notation.add_function(wrap_pyfunction!(notation::import_record, notation)?)
+    })?;
+    add_submodule(m, "rules", |rules| rules.add_function(wrap_pyfunction!(rules::why, rules)?))?;
+    m.add_function(wrap_pyfunction!(allocations, m)?)?;
 
     Ok(())
 }
@@ -0,0 +1,33 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::types::PyBytes;
+use pyo3::IntoPyObjectExt;
+
+use catan::board::layout::BoardLayout;
+use catan::notation::Record;
+use catan::replay::Replay;
+
+/// Record of a replay from `get_replay`, JSON or bincode bytes, as the text of `catan::notation` or as JSON
+#[pyfunction]
+#[pyo3(signature = (replay, layout="standard", json=false))]
+pub fn export_record(replay: &Bound<PyAny>, layout: &str, json: bool) -> PyResult<String> {
+    let board = BoardLayout::named(layout).map_err(PyValueError::new_err)?;
+    let replay = match replay.downcast::<PyBytes>() {
+        Ok(bytes) => Replay::from_bytes(bytes.as_bytes()),
+        Err(_) => Replay::from_json(&replay.extract::<String>()?),
+    }.map_err(PyValueError::new_err)?;
+    let record = Record::from_replay(&replay, board.layout, vec![String::new(); replay.seating.len()]).map_err(PyValueError::new_err)?;
+    Ok(if json { record.to_json() } else { record.to_text() })
+}
+
+/// Replay of the game of a record, text or JSON, playing its moves with their outcomes,
+/// returned as `get_replay` does, with the name of its board
+#[pyfunction]
+#[pyo3(signature = (record, binary=false))]
+pub fn import_record(py: Python, record: &str, binary: bool) -> PyResult<(PyObject, String)> {
+    let record = if record.trim_start().starts_with('{') { Record::from_json(record) } else { Record::from_text(record) }
+        .map_err(PyValueError::new_err)?;
+    let replay = record.to_replay().map_err(PyValueError::new_err)?;
+    let replay = if binary { PyBytes::new(py, &replay.to_bytes()).into_py_any(py)? } else { replay.to_json().into_py_any(py)? };
+    Ok((replay, record.board))
+}
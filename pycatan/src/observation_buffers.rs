@@ -0,0 +1,60 @@
+use ndarray::{ArrayViewD, Axis, IxDyn};
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyValueError, PyTypeError};
+use numpy::{Element, PyArrayDyn, PyArrayMethods};
+
+use super::{PyCatanObservation, PyObservationFormat};
+
+/// Integer arrays of the observations of the format, the action mask excepted
+fn array_count(format: &PyObservationFormat) -> usize {
+    2 + format.include_hidden as usize + format.include_global as usize + 3 * format.include_graph as usize + format.include_tokens as usize
+}
+
+/// Zeroed numpy arrays shaped as the arrays of the observation, the action mask last,
+/// with a first axis of `rows` observations when given
+pub(crate) fn allocate(py: Python, sample: &PyCatanObservation, rows: Option<usize>) -> Vec<PyObject> {
+    let shape = |shape: &[usize]| rows.into_iter().chain(shape.iter().copied()).collect::<Vec<_>>();
+    let mut buffers: Vec<PyObject> = sample.arrays().iter()
+        .map(|array| PyArrayDyn::<i32>::zeros(py, IxDyn(&shape(array.shape())), false).into_any().unbind())
+        .collect();
+    buffers.push(PyArrayDyn::<bool>::zeros(py, IxDyn(&shape(sample.actions.shape())), false).into_any().unbind());
+    buffers
+}
+
+/// Copies `source` into the numpy array, or into its row `row` when given
+pub(crate) fn write<T: Element + Copy>(buffer: &Bound<PyAny>, row: Option<usize>, source: ArrayViewD<T>) -> PyResult<()> {
+    let buffer = buffer.downcast::<PyArrayDyn<T>>()
+        .map_err(|_| PyTypeError::new_err(format!("Expected a numpy array of {}", std::any::type_name::<T>())))?;
+    let mut buffer = buffer.try_readwrite()?;
+    let mut array = buffer.as_array_mut();
+    let mut target = match row {
+        Some(row) if array.ndim() > 0 && row < array.len_of(Axis(0)) => array.index_axis_mut(Axis(0), row),
+        Some(row) => return Err(PyValueError::new_err(format!("Buffer of shape {:?} has no row {}", array.shape(), row))),
+        None => array.view_mut(),
+    };
+    if target.shape() != source.shape() {
+        return Err(PyValueError::new_err(format!("Buffer of shape {:?} for an array of shape {:?}", target.shape(), source.shape())));
+    }
+    target.assign(&source);
+    Ok(())
+}
+
+/// Copies the arrays of the observation into the buffers, given in the order of [allocate] and counted by [check_count],
+/// returning the number of buffers written
+pub(crate) fn write_observation(buffers: &[Bound<PyAny>], row: Option<usize>, observation: &PyCatanObservation) -> PyResult<usize> {
+    let arrays = observation.arrays();
+    for (buffer, array) in buffers.iter().zip(arrays.iter()) {
+        write(buffer, row, array.view())?;
+    }
+    write(&buffers[arrays.len()], row, observation.actions.view().into_dyn())?;
+    Ok(arrays.len() + 1)
+}
+
+/// Checks there are as many buffers as the format has observation arrays, plus `extra` ones
+pub(crate) fn check_count(format: &PyObservationFormat, buffers: &[Bound<PyAny>], extra: usize) -> PyResult<()> {
+    let expected = array_count(format) + 1 + extra;
+    if buffers.len() != expected {
+        return Err(PyValueError::new_err(format!("Got {} buffers, expected {}", buffers.len(), expected)));
+    }
+    Ok(())
+}
@@ -0,0 +1,336 @@
+use std::collections::BTreeMap;
+use ndarray::{Array1, Array2};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::exceptions::PyValueError;
+use numpy::convert::IntoPyArray;
+
+use catan::game::{Action, GameEvent};
+use catan::state::PlayerId;
+use catan::board::Layout;
+use catan::utils::{Coord, Resource, Resources};
+
+fn resource_name(resource: Resource) -> &'static str {
+    match resource {
+        Resource::Brick => "brick",
+        Resource::Lumber => "lumber",
+        Resource::Ore => "ore",
+        Resource::Grain => "grain",
+        Resource::Wool => "wool",
+    }
+}
+
+fn resources_to_py(py: Python, resources: Resources) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    for resource in Resource::ALL.iter() {
+        if resources[*resource] > 0 {
+            dict.set_item(resource_name(*resource), resources[*resource])?;
+        }
+    }
+    Ok(dict.into_any().unbind())
+}
+
+/// Kind and parameters of an action, as a dict
+///
+/// Coordinates are (x, y) tuples, and thief victims are relative to the player, 0 being the player itself
+pub(crate) fn action_to_py(py: Python, action: &Action) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    let kind = format!("{:?}", action.category());
+    dict.set_item("kind", kind)?;
+    match *action {
+        Action::MoveThief { hex, victim } => {
+            dict.set_item("hex", (hex.x, hex.y))?;
+            dict.set_item("victim", victim.to_u8())?;
+        }
+        Action::BuildRoad { path } => dict.set_item("path", (path.x, path.y))?,
+        Action::BuildSettlement { intersection } | Action::BuildCity { intersection } | Action::ReserveCity { intersection } => {
+            dict.set_item("intersection", (intersection.x, intersection.y))?;
+        }
+        Action::TradeBank { given, asked } => {
+            dict.set_item("given", resource_name(given))?;
+            dict.set_item("asked", resource_name(asked))?;
+        }
+        Action::TradeBankBundle { trades, asked } => {
+            dict.set_item("trades", resources_to_py(py, trades)?)?;
+            dict.set_item("asked", resources_to_py(py, asked)?)?;
+        }
+        Action::ProposeTrade { give, receive } | Action::CounterTrade { give, receive } | Action::ReviseTrade { give, receive } => {
+            dict.set_item("give", resources_to_py(py, give)?)?;
+            dict.set_item("receive", resources_to_py(py, receive)?)?;
+        }
+        Action::ChooseFreeResource { resource } | Action::DevelopmentMonopole { resource } | Action::Discard { resource } => {
+            dict.set_item("resource", resource_name(resource))?;
+        }
+        Action::Keep { resources } => dict.set_item("resources", resources_to_py(py, resources)?)?,
+        _ => (),
+    }
+    Ok(dict.into_any().unbind())
+}
+
+/// Kind and parameters of an event, as a dict
+///
+/// Players are relative to the player seeing the event, as in [action_to_py], and the resources of the cards it didn't see are None
+pub(crate) fn event_to_py(py: Python, event: &GameEvent) -> PyResult<PyObject> {
+    let dict = PyDict::new(py);
+    let kind = match event {
+        GameEvent::DiceRolled { .. } => "DiceRolled",
+        GameEvent::ResourcesDistributed { .. } => "ResourcesDistributed",
+        GameEvent::RobberMoved { .. } => "RobberMoved",
+        GameEvent::CardStolen { .. } => "CardStolen",
+        GameEvent::BuildingPlaced { .. } => "BuildingPlaced",
+        GameEvent::TradeExecuted { .. } => "TradeExecuted",
+        GameEvent::LongestRoadChanged { .. } => "LongestRoadChanged",
+        GameEvent::GameEnded { .. } => "GameEnded",
+    };
+    dict.set_item("kind", kind)?;
+    match event {
+        GameEvent::DiceRolled { player, roll } => {
+            dict.set_item("player", player.to_u8())?;
+            dict.set_item("roll", roll)?;
+        }
+        GameEvent::ResourcesDistributed { roll, resources } => {
+            dict.set_item("roll", roll)?;
+            let resources: PyResult<Vec<PyObject>> = resources.iter().map(|resources| resources_to_py(py, *resources)).collect();
+            dict.set_item("resources", resources?)?;
+        }
+        GameEvent::RobberMoved { player, hex } => {
+            dict.set_item("player", player.to_u8())?;
+            dict.set_item("hex", (hex.x, hex.y))?;
+        }
+        GameEvent::CardStolen { thief, victim, resource } => {
+            dict.set_item("thief", thief.to_u8())?;
+            dict.set_item("victim", victim.to_u8())?;
+            dict.set_item("resource", resource.map(resource_name))?;
+        }
+        GameEvent::BuildingPlaced { player, building, coord } => {
+            dict.set_item("player", player.to_u8())?;
+            dict.set_item("building", format!("{:?}", building).to_lowercase())?;
+            dict.set_item("coord", (coord.x, coord.y))?;
+        }
+        GameEvent::TradeExecuted { player, partner, given, received } => {
+            dict.set_item("player", player.to_u8())?;
+            dict.set_item("partner", partner.map(|partner| partner.to_u8()))?;
+            dict.set_item("given", resources_to_py(py, *given)?)?;
+            dict.set_item("received", resources_to_py(py, *received)?)?;
+        }
+        GameEvent::LongestRoadChanged { holder } => {
+            dict.set_item("player", holder.map(|(player, _)| player.to_u8()))?;
+            dict.set_item("length", holder.map_or(0, |(_, length)| length))?;
+        }
+        GameEvent::GameEnded { winner } => dict.set_item("winner", winner.to_u8())?,
+    }
+    Ok(dict.into_any().unbind())
+}
+
+pub(crate) fn describe_action(py: Python, actions: &[Action], index: usize) -> PyResult<PyObject> {
+    let action = actions.get(index).ok_or_else(|| PyValueError::new_err(format!("Index {} out of the {} actions", index, actions.len())))?;
+    action_to_py(py, action)
+}
+
+fn resource_from_name(name: &str) -> PyResult<Resource> {
+    Resource::ALL.iter().copied().find(|resource| resource_name(*resource) == name)
+        .ok_or_else(|| PyValueError::new_err(format!("Unknown resource {}, expected brick, lumber, ore, grain or wool", name)))
+}
+
+/// Resources of counts by resource name, read in the order of the names for the same name to be reported whatever the dict
+pub(crate) fn resources_from_py(counts: BTreeMap<String, i8>) -> PyResult<Resources> {
+    let mut resources = Resources::ZERO;
+    for (name, count) in counts {
+        resources[resource_from_name(&name)?] += count;
+    }
+    Ok(resources)
+}
+
+pub(crate) fn index_to_action(actions: &[Action], index: usize) -> PyResult<PyAction> {
+    let action = actions.get(index).ok_or_else(|| PyValueError::new_err(format!("Index {} out of the {} actions", index, actions.len())))?;
+    Ok(PyAction { action: *action })
+}
+
+pub(crate) fn action_to_index(actions: &[Action], action: &PyAction) -> PyResult<usize> {
+    actions.iter().position(|a| *a == action.action)
+        .ok_or_else(|| PyValueError::new_err(format!("{:?} is not in the actions", action.action)))
+}
+
+/// Descriptions of the actions of a mask, each with its `index`
+pub(crate) fn describe_legal_actions(py: Python, actions: &[Action], mask: Vec<bool>) -> PyResult<Vec<PyObject>> {
+    mask.iter().zip(actions.iter()).enumerate()
+        .filter(|(_, (legal, _))| **legal)
+        .map(|(index, (_, action))| {
+            let description = action_to_py(py, action)?;
+            description.bind(py).set_item("index", index)?;
+            Ok(description)
+        })
+        .collect()
+}
+
+/// Indices in the action mask of the actions on each node and edge of the board graph, as a dict of arrays
+///
+/// `road` gives the index of each path, `settlement` and `city` the ones of each intersection,
+/// and `thief` the ones of each hex by relative victim, in the order of the layout
+pub(crate) fn graph_actions(py: Python, actions: &[Action], layout: &Layout) -> PyResult<PyObject> {
+    let index = |wanted: Action| actions.iter().position(|action| *action == wanted).map_or(-1, |i| i as i64);
+    let victims = actions.iter().filter(|action| matches!(action, Action::MoveThief { hex: _, victim: _ })).count() / layout.hexes.len().max(1);
+    let mut thief = Array2::<i64>::from_elem((layout.hexes.len(), victims), -1);
+    for (i, action) in actions.iter().enumerate() {
+        if let Action::MoveThief { hex, victim } = action {
+            let h = layout.hexes.iter().position(|coord| coord == hex).expect("Hex out of the layout");
+            thief[(h, victim.to_usize())] = i as i64;
+        }
+    }
+    let road: Array1<i64> = layout.paths.iter().map(|path| index(Action::BuildRoad { path: *path })).collect();
+    let settlement: Array1<i64> = layout.intersections.iter().map(|intersection| index(Action::BuildSettlement { intersection: *intersection })).collect();
+    let city: Array1<i64> = layout.intersections.iter().map(|intersection| index(Action::BuildCity { intersection: *intersection })).collect();
+    let dict = PyDict::new(py);
+    dict.set_item("thief", thief.into_pyarray(py))?;
+    dict.set_item("road", road.into_pyarray(py))?;
+    dict.set_item("settlement", settlement.into_pyarray(py))?;
+    dict.set_item("city", city.into_pyarray(py))?;
+    Ok(dict.into_any().unbind())
+}
+
+/// Action of the action masks, built from its kind and parameters
+///
+/// Coordinates are (x, y) tuples, and thief victims are relative to the player, 0 being the player itself.
+/// Resources are named brick, lumber, ore, grain and wool, and counted in dicts of names.
+#[pyclass(name = "Action", eq, frozen)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PyAction {
+    pub action: Action,
+}
+
+#[pymethods]
+impl PyAction {
+
+    #[staticmethod]
+    fn end_turn() -> Self {
+        PyAction { action: Action::EndTurn }
+    }
+
+    #[staticmethod]
+    fn roll_dice() -> Self {
+        PyAction { action: Action::RollDice }
+    }
+
+    #[staticmethod]
+    fn move_thief(hex: (i8, i8), victim: u8) -> Self {
+        PyAction { action: Action::MoveThief { hex: Coord::new(hex.0, hex.1), victim: PlayerId::from(victim) } }
+    }
+
+    #[staticmethod]
+    fn build_road(path: (i8, i8)) -> Self {
+        PyAction { action: Action::BuildRoad { path: Coord::new(path.0, path.1) } }
+    }
+
+    #[staticmethod]
+    fn build_settlement(intersection: (i8, i8)) -> Self {
+        PyAction { action: Action::BuildSettlement { intersection: Coord::new(intersection.0, intersection.1) } }
+    }
+
+    #[staticmethod]
+    fn build_city(intersection: (i8, i8)) -> Self {
+        PyAction { action: Action::BuildCity { intersection: Coord::new(intersection.0, intersection.1) } }
+    }
+
+    /// Declares the settlement to upgrade on a later turn, with the city_reservation rule
+    #[staticmethod]
+    fn reserve_city(intersection: (i8, i8)) -> Self {
+        PyAction { action: Action::ReserveCity { intersection: Coord::new(intersection.0, intersection.1) } }
+    }
+
+    #[staticmethod]
+    fn trade_bank(given: &str, asked: &str) -> PyResult<Self> {
+        Ok(PyAction { action: Action::TradeBank { given: resource_from_name(given)?, asked: resource_from_name(asked)? } })
+    }
+
+    #[staticmethod]
+    fn trade_bank_bundle(trades: BTreeMap<String, i8>, asked: BTreeMap<String, i8>) -> PyResult<Self> {
+        Ok(PyAction { action: Action::TradeBankBundle { trades: resources_from_py(trades)?, asked: resources_from_py(asked)? } })
+    }
+
+    #[staticmethod]
+    fn propose_trade(give: BTreeMap<String, i8>, receive: BTreeMap<String, i8>) -> PyResult<Self> {
+        Ok(PyAction { action: Action::ProposeTrade { give: resources_from_py(give)?, receive: resources_from_py(receive)? } })
+    }
+
+    #[staticmethod]
+    fn accept_trade() -> Self {
+        PyAction { action: Action::AcceptTrade }
+    }
+
+    #[staticmethod]
+    fn reject_trade() -> Self {
+        PyAction { action: Action::RejectTrade }
+    }
+
+    #[staticmethod]
+    fn counter_trade(give: BTreeMap<String, i8>, receive: BTreeMap<String, i8>) -> PyResult<Self> {
+        Ok(PyAction { action: Action::CounterTrade { give: resources_from_py(give)?, receive: resources_from_py(receive)? } })
+    }
+
+    /// Takes back the offer of the player, as it answers a counter offer
+    #[staticmethod]
+    fn withdraw_trade() -> Self {
+        PyAction { action: Action::WithdrawTrade }
+    }
+
+    /// Answers a counter offer with new terms, offered to the same opponent
+    #[staticmethod]
+    fn revise_trade(give: BTreeMap<String, i8>, receive: BTreeMap<String, i8>) -> PyResult<Self> {
+        Ok(PyAction { action: Action::ReviseTrade { give: resources_from_py(give)?, receive: resources_from_py(receive)? } })
+    }
+
+    #[staticmethod]
+    fn buy_development() -> Self {
+        PyAction { action: Action::BuyDevelopment }
+    }
+
+    #[staticmethod]
+    fn play_knight() -> Self {
+        PyAction { action: Action::DevelopmentKnight }
+    }
+
+    #[staticmethod]
+    fn play_road_building() -> Self {
+        PyAction { action: Action::DevelopmentRoadBuilding }
+    }
+
+    #[staticmethod]
+    fn play_year_of_plenty() -> Self {
+        PyAction { action: Action::DevelopmentYearOfPlenty }
+    }
+
+    #[staticmethod]
+    fn choose_free_resource(resource: &str) -> PyResult<Self> {
+        Ok(PyAction { action: Action::ChooseFreeResource { resource: resource_from_name(resource)? } })
+    }
+
+    #[staticmethod]
+    fn play_monopole(resource: &str) -> PyResult<Self> {
+        Ok(PyAction { action: Action::DevelopmentMonopole { resource: resource_from_name(resource)? } })
+    }
+
+    #[staticmethod]
+    fn keep(resources: BTreeMap<String, i8>) -> PyResult<Self> {
+        Ok(PyAction { action: Action::Keep { resources: resources_from_py(resources)? } })
+    }
+
+    #[staticmethod]
+    fn discard(resource: &str) -> PyResult<Self> {
+        Ok(PyAction { action: Action::Discard { resource: resource_from_name(resource)? } })
+    }
+
+    #[getter]
+    fn kind(&self) -> String {
+        format!("{:?}", self.action.category())
+    }
+
+    /// Kind and parameters of the action, as a dict like the ones of `describe_action`
+    #[pyo3(name = "to_dict")]
+    fn as_dict(&self, py: Python) -> PyResult<PyObject> {
+        action_to_py(py, &self.action)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.action)
+    }
+}
@@ -1,53 +1,17 @@
-use ndarray::{Array1, Array3, ArrayD, ArrayViewD, ArrayViewMutD};
+use ndarray::{Array1, Array2, Array3, ArrayViewD};
 use pyo3::prelude::*;
-use numpy::{IntoPyArray, PyArrayDyn};
-use numpy::{PyReadonlyArrayDyn, PyReadwriteArrayDyn, ToPyArray};
 
-use catan::state::{State, PlayerHand, PlayerId};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+use catan::state::{State, VisibleState, PlayerHand, PublicHand, PlayerId, TricellState, BeliefTracker, OpponentModel, TradeAnswer};
 use catan::utils::{Hex, LandHex, Harbor, Resource, DevelopmentCard};
-use catan::game::{Phase, TurnPhase, DevelopmentPhase};
+use catan::game::{Phase, TurnPhase, DevelopmentPhase, GameEvent, legal, responses_of_turn};
 use catan::player::relative;
 
 use super::{PyObservationFormat, PythonState};
-
-fn axpy(a: f64, x: ArrayViewD<f64>, y: ArrayViewD<f64>) -> ArrayD<f64> {
-    a * &x + &y
-}
-
-fn mult(a: f64, mut x: ArrayViewMutD<f64>) {
-    x *= a;
-}
-
-#[pyfunction(name = "axpy")]
-fn axpy_py<'py>(
-    py: Python<'py>,
-    a: f64,
-
-    x: PyReadonlyArrayDyn<'py, f64>,
-    y: PyReadonlyArrayDyn<'py, f64>,
-) -> Bound<'py, PyArrayDyn<f64>> {
-
-    let x_view = x.as_array();
-    let y_view = y.as_array();
-
-    axpy(a, x_view, y_view).into_pyarray(py)
-}
-
-#[pyfunction(name = "mult")]
-
-fn mult_py(a: f64, mut x: PyReadwriteArrayDyn<f64>) -> PyResult<()> {
-    let x_view_mut = x.as_array_mut();
-    mult(a, x_view_mut);
-    Ok(())
-}
-
-#[pymodule]
-fn rust_ext(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(axpy_py, m)?)?;
-    m.add_function(wrap_pyfunction!(mult_py, m)?)?;
-
-    Ok(())
-}
+use super::py_observation_format::{OPPONENT_MODEL, PLAYED_DEVELOPMENT};
+use super::tokens::Vocabulary;
 
 #[allow(dead_code)]
 fn jsettlers_u(resource: Resource) -> usize {
@@ -72,29 +36,64 @@ fn jsettlers_resource(value: usize) -> Resource {
     }
 }
 
+/// Features of the board nodes and edges, in the order of the layout, edges being given by
+/// [graph_edges](PyObservationFormat::graph_edges)
+#[derive(Clone)]
+pub(crate) struct GraphObservation {
+    /// Production number by resource (5), desert and thief of each hex
+    pub hexes: Array2<i32>,
+    /// Harbors (6), then buildings by player relative to the observer, 1 for a settlement and 2 for a city, of each intersection
+    pub intersections: Array2<i32>,
+    /// Roads by player relative to the observer, of each path
+    pub paths: Array2<i32>,
+}
+
 #[pyclass]
+#[derive(Clone)]
 pub(crate) struct PyCatanObservation {
     pub actions: Array1<bool>,
     pub board: Array3<i32>,
     pub flat: Array1<i32>,
     pub hidden: Option<Array1<i32>>,
+    pub global: Option<Array1<i32>>,
+    /// Seconds left to the player's turn budget
+    pub budget: Option<f32>,
+    /// Decision stage, as a [DecisionStage](catan::game::DecisionStage) value
+    pub stage: Option<u8>,
+    /// Legal actions among the actions of the stage
+    pub stage_actions: Option<Array1<bool>>,
+    /// Probabilities of a heuristic player to play each action, aligned with the action mask
+    pub priors: Option<Array1<f32>>,
+    /// Board as a graph
+    pub graph: Option<GraphObservation>,
+    /// Game as a sequence of tokens
+    pub tokens: Option<Array1<i32>>,
+    /// Reward received since the previous observation of the player
+    pub reward: Option<f32>,
+    /// Events seen since the previous observation of the player, players being relative to it
+    pub events: Option<Vec<GameEvent>>,
+    /// Index of the observation among the ones sent by the player, to match the action answering it
+    pub sequence: u32,
+    /// Illegal action refused while the observation keeps waiting for its answer
+    pub rejected: Option<u16>,
 }
 
 impl PyCatanObservation {
-    pub fn generate_board(format: PyObservationFormat, player: PlayerId, state: &State) -> Array3<i32> {
-        let player_count = state.player_count();
-        let mut board = Array3::<i32>::zeros((format.width,format.height, 13 + 2 * player_count as usize));
-        let layout = state.get_layout();
+    pub fn generate_board(format: PyObservationFormat, view: &VisibleState) -> Array3<i32> {
+        let player = view.player();
+        let player_count = view.player_count();
+        let mut board = Array3::<i32>::zeros((format.width,format.height, format.board_channels(player_count)));
+        let layout = view.get_layout();
         // ## Hexes [0,7[
         for coord in layout.hexes.iter() {
-            let hex = state.get_static_hex(*coord).unwrap();
+            let hex = view.get_static_hex(*coord).unwrap();
             if let Hex::Land(hex) = hex {
                 let (x,y) = format.map(*coord);
                 match hex {
                     LandHex::Desert => { board[(x, y, 5)] = 1; },
                     LandHex::Prod(res, num) => { board[(x, y, res.to_usize())] = num.into(); },
                 }
-                if *coord == state.get_thief_hex() {
+                if *coord == view.get_thief_hex() {
                     board[(x, y, 6)] = 1;
                 }
             }
@@ -102,7 +101,7 @@ impl PyCatanObservation {
         // ## Paths [7,7+player_count[
         let c = 7;
         for coord in layout.paths.iter() {
-            let path = state.get_dynamic_path(*coord).unwrap();
+            let path = view.get_dynamic_path(*coord).unwrap();
             if let Some(p) = path {
                 let p = relative::player_id_to_relative(player, p, player_count);
                 let (x,y) = format.map(*coord);
@@ -114,13 +113,13 @@ impl PyCatanObservation {
         // ## Intersections [7+player_count, 13+2×player_count[
         for coord in layout.intersections.iter() {
             let (x,y) = format.map(*coord);
-            let harbor = state.get_static_harbor(*coord).unwrap();
+            let harbor = view.get_static_harbor(*coord).unwrap();
             match harbor {
                 Harbor::Generic => { board[(x, y, c_harbor + 5)] = 1; }
                 Harbor::Special(res) => { board[(x, y, c_harbor + res.to_usize())] = 1; }
                 _ => (),
             }
-            let intersection = state.get_dynamic_intersection(*coord).unwrap();
+            let intersection = view.get_dynamic_intersection(*coord).unwrap();
             if let Some((p, is_city)) = intersection {
                 let p = relative::player_id_to_relative(player, p, player_count);
                 board[(x, y, c_buildings + p.to_usize())] = if is_city { 2 } else { 1 };
@@ -129,6 +128,63 @@ impl PyCatanObservation {
         board
     }
 
+    /// Board as features of the hexes, intersections and paths, the channels being the ones of the board array
+    pub fn generate_graph(view: &VisibleState) -> GraphObservation {
+        let player = view.player();
+        let player_count = view.player_count();
+        let layout = view.get_layout();
+        let (hex_channels, intersection_channels, path_channels) = PyObservationFormat::graph_channels(player_count);
+        // ## Hexes 7
+        let mut hexes = Array2::<i32>::zeros((layout.hexes.len(), hex_channels));
+        for (i, coord) in layout.hexes.iter().enumerate() {
+            if let Hex::Land(hex) = view.get_static_hex(*coord).unwrap() {
+                match hex {
+                    LandHex::Desert => { hexes[(i, 5)] = 1; },
+                    LandHex::Prod(res, num) => { hexes[(i, res.to_usize())] = num.into(); },
+                }
+                if *coord == view.get_thief_hex() {
+                    hexes[(i, 6)] = 1;
+                }
+            }
+        }
+        // ## Intersections 6+player_count
+        let mut intersections = Array2::<i32>::zeros((layout.intersections.len(), intersection_channels));
+        for (i, coord) in layout.intersections.iter().enumerate() {
+            match view.get_static_harbor(*coord).unwrap() {
+                Harbor::Generic => { intersections[(i, 5)] = 1; }
+                Harbor::Special(res) => { intersections[(i, res.to_usize())] = 1; }
+                _ => (),
+            }
+            if let Some((p, is_city)) = view.get_dynamic_intersection(*coord).unwrap() {
+                let p = relative::player_id_to_relative(player, p, player_count);
+                intersections[(i, 6 + p.to_usize())] = if is_city { 2 } else { 1 };
+            }
+        }
+        // ## Paths player_count
+        let mut paths = Array2::<i32>::zeros((layout.paths.len(), path_channels));
+        for (i, coord) in layout.paths.iter().enumerate() {
+            if let Some(p) = view.get_dynamic_path(*coord).unwrap() {
+                let p = relative::player_id_to_relative(player, p, player_count);
+                paths[(i, p.to_usize())] = 1;
+            }
+        }
+        GraphObservation {
+            hexes,
+            intersections,
+            paths,
+        }
+    }
+
+    // Fills the building age channel: for each settled intersection, the number of turns since it was settled plus one
+    pub fn fill_building_age(format: PyObservationFormat, board: &mut Array3<i32>, view: &VisibleState) {
+        let c_age = 13 + 2 * view.player_count() as usize;
+        let turn = view.get_turn();
+        for placement in view.get_building_history().placements().iter().filter(|p| !p.is_city) {
+            let (x,y) = format.map(placement.intersection);
+            board[(x, y, c_age)] = (turn - placement.turn + 1) as i32;
+        }
+    }
+
     // Fills 27 cells
     pub fn fill_flat_visible(array: &mut Array1::<i32>, index: usize, hand: &PlayerHand, has_longest_road: bool, has_largest_army: bool) {
         for res in 0..Resource::COUNT {
@@ -153,58 +209,158 @@ impl PyCatanObservation {
     }
 
     // Fills 8 cells
-    pub fn fill_flat_concealed(array: &mut Array1::<i32>, index: usize, hand: &PlayerHand, has_longest_road: bool, has_largest_army: bool) {
-        array[index] = hand.resources.total().into();
+    pub fn fill_flat_concealed(array: &mut Array1::<i32>, index: usize, hand: &PublicHand) {
+        array[index] = hand.resource_count.into();
         array[index + 1] = hand.road_pieces.into();
         array[index + 2] = hand.settlement_pieces.into();
         array[index + 3] = hand.city_pieces.into();
         array[index + 4] = hand.knights.into();
-        array[index + 5] = hand.development_cards.total().into();
-        array[index + 6] = if has_longest_road { 1 } else { 0 };
-        array[index + 7] = if has_largest_army { 1 } else { 0 };
+        array[index + 5] = hand.development_card_count.into();
+        array[index + 6] = if hand.has_longest_road { 1 } else { 0 };
+        array[index + 7] = if hand.has_largest_army { 1 } else { 0 };
     }
 
-    pub fn generate_flat(player: PlayerId, state: &State, phase: &Phase) -> Array1<i32> {
-        let player_count = state.player_count();
-        let mut flat = Array1::<i32>::zeros(29+(player_count as usize)*8);
-        let longest_road = match state.get_longest_road() {
-            None => PlayerId::NONE,
-            Some((player_id, _)) => player_id,
-        };
-        let largest_army = match state.get_largest_army() {
-            None => PlayerId::NONE,
-            Some((player_id, _)) => player_id,
-        };
+    pub fn generate_flat(format: PyObservationFormat, view: &VisibleState, phase: &Phase) -> Array1<i32> {
+        let player = view.player();
+        let player_count = view.player_count();
+        let mut flat = Array1::<i32>::zeros(format.flat_size(player_count));
         // ## Player 27
-        let hand = &state.get_player_hand(player);
-        PyCatanObservation::fill_flat_visible(&mut flat, 0, hand, longest_road == player, largest_army == player);
-        // ## Opponents (p-1)*8
+        let own = view.public_hand(player);
+        PyCatanObservation::fill_flat_visible(&mut flat, 0, view.hand(), own.has_longest_road, own.has_largest_army);
+        // ## Opponents (p-1)*8, or (p-1)*11 with the played development cards
+        let concealed_size = format.concealed_size();
         for opp in 1..player_count {
-            let player_index = 19+(opp as usize)*8;
+            let player_index = 27+(opp as usize - 1)*concealed_size;
             let player = relative::offset_to_player_id(player, opp, player_count);
-            let hand = &state.get_player_hand(player);
-            PyCatanObservation::fill_flat_concealed(&mut flat, player_index, hand, longest_road == player, largest_army == player);
+            let hand = view.public_hand(player);
+            PyCatanObservation::fill_flat_concealed(&mut flat, player_index, &hand);
+            if format.include_played_development {
+                for (i, card) in PLAYED_DEVELOPMENT.iter().enumerate() {
+                    flat[player_index + 8 + i] = hand.played_development_cards[*card].into();
+                }
+            }
         }
         // ## State 6
-        let c_state = 19+(player_count as usize)*8;
-        let bank_resources = state.get_bank_resources();
+        let c_state = 27+(player_count as usize - 1)*concealed_size;
+        let bank_resources = view.get_bank_resources();
         for res in 0..Resource::COUNT {
             flat[c_state + res] = bank_resources[res].into();
         }
-        flat[c_state+5] = state.get_development_cards().total().into();
-        // ## Phase 4
-        let c_phase = c_state + 6;
+        flat[c_state+5] = view.development_deck_size().into();
+        // ## Resigned opponents p-1
+        // The opponents keep their relative seats once they left, so the channels of the others don't move
+        for opp in 1..player_count {
+            let resigned = view.public_hand(relative::offset_to_player_id(player, opp, player_count)).resigned;
+            flat[c_state + 5 + opp as usize] = if resigned { 1 } else { 0 };
+        }
+        // ## Phase 12
+        let c_phase = c_state + 5 + player_count as usize;
         if let Phase::Turn { player: _, turn_phase, development_phase } = phase {
             flat[c_phase] = if let TurnPhase::PreRoll = turn_phase { 1 } else { 0 };
             flat[c_phase+1] = if let DevelopmentPhase::Ready = development_phase { 1 } else { 0 };
             flat[c_phase+2] = if let DevelopmentPhase::RoadBuildingActive { two_left } = development_phase { if *two_left { 2 } else { 1 } } else { 0 };
             flat[c_phase+3] = if let DevelopmentPhase::YearOfPlentyActive { two_left } = development_phase { if *two_left { 2 } else { 1 } } else { 0 };
+            // Building is allowed before rolling when the rules don't make the players roll first
+            flat[c_phase+4] = if view.get_rules().building_allowed(*turn_phase) { 1 } else { 0 };
+            flat[c_phase+5] = if let TurnPhase::MoveThief = turn_phase { 1 } else { 0 };
+        }
+        // Cards the observer still has to discard, and the ones it discarded so far card by card
+        if let Some((discarded, left)) = view.pending_discard() {
+            flat[c_phase+6] = left.into();
+            for res in 0..Resource::COUNT {
+                flat[c_phase + 7 + res] = discarded[res].into();
+            }
+        }
+        // ## Trade offer 13
+        // Resources given and received by the player whose turn it is, then this player and the partner relative to the observer
+        let c_trade = c_phase + 12;
+        if let Phase::Turn { player: proposer, turn_phase: TurnPhase::Trade(offer), development_phase: _ } = phase {
+            for res in 0..Resource::COUNT {
+                flat[c_trade + res] = offer.give[res].into();
+                flat[c_trade + 5 + res] = offer.receive[res].into();
+            }
+            flat[c_trade + 10] = relative::player_id_to_relative(player, *proposer, player_count).to_u8().into();
+            flat[c_trade + 11] = relative::player_id_to_relative(player, offer.partner, player_count).to_u8().into();
+            flat[c_trade + 12] = if offer.countered { 1 } else { 0 };
+        }
+        // ## Beliefs (p-1)*10
+        if format.include_beliefs {
+            let c_beliefs = c_trade + 13;
+            let tracker = BeliefTracker::from_view(view);
+            for opp in 1..player_count {
+                let index = c_beliefs + (opp as usize - 1) * 2 * Resource::COUNT;
+                let belief = tracker.hand(relative::offset_to_player_id(player, opp, player_count));
+                for res in Resource::ALL.iter() {
+                    flat[index + res.to_usize()] = belief.minimum[*res].into();
+                    flat[index + Resource::COUNT + res.to_usize()] = (100.0 * belief.probability(*res)).round() as i32;
+                }
+            }
+        }
+        // ## Opponent model (p-1)*7
+        if format.include_opponent_model {
+            let c_model = c_trade + 13 + if format.include_beliefs { (player_count as usize - 1) * 2 * Resource::COUNT } else { 0 };
+            let model = OpponentModel::from_view(view);
+            let turn = view.get_turn();
+            for opp in 1..player_count {
+                let index = c_model + (opp as usize - 1) * OPPONENT_MODEL.len();
+                let profile = model.profile(relative::offset_to_player_id(player, opp, player_count));
+                flat[index] = profile.offers_answered as i32;
+                flat[index + 1] = (100.0 * profile.acceptance_rate()).round() as i32;
+                flat[index + 2] = profile.steals as i32;
+                flat[index + 3] = (100.0 * profile.observer_targeting()).round() as i32;
+                flat[index + 4] = profile.purchases as i32;
+                flat[index + 5] = (10.0 * profile.tempo(turn)).round() as i32;
+                flat[index + 6] = turn.saturating_sub(profile.last_purchase.unwrap_or(0)) as i32;
+            }
+        }
+        // ## Card counts (p-1)*15
+        if format.include_card_counts {
+            let mut c_counts = c_trade + 13;
+            if format.include_beliefs {
+                c_counts += (player_count as usize - 1) * 2 * Resource::COUNT;
+            }
+            if format.include_opponent_model {
+                c_counts += (player_count as usize - 1) * OPPONENT_MODEL.len();
+            }
+            let tracker = BeliefTracker::from_view(view);
+            for opp in 1..player_count {
+                let index = c_counts + (opp as usize - 1) * 3 * Resource::COUNT;
+                let belief = tracker.hand(relative::offset_to_player_id(player, opp, player_count));
+                let maximum = belief.maximum();
+                for res in Resource::ALL.iter() {
+                    flat[index + res.to_usize()] = belief.minimum[*res].into();
+                    flat[index + Resource::COUNT + res.to_usize()] = maximum[*res].into();
+                    flat[index + 2 * Resource::COUNT + res.to_usize()] = (100.0 * belief.expected[res.to_usize()]).round() as i32;
+                }
+            }
+        }
+        // ## Trade answers p*3
+        if format.include_trade_answers {
+            let mut c_answers = c_trade + 13;
+            if format.include_beliefs {
+                c_answers += (player_count as usize - 1) * 2 * Resource::COUNT;
+            }
+            if format.include_opponent_model {
+                c_answers += (player_count as usize - 1) * OPPONENT_MODEL.len();
+            }
+            if format.include_card_counts {
+                c_answers += (player_count as usize - 1) * 3 * Resource::COUNT;
+            }
+            for response in responses_of_turn(view.trade_responses(), view.get_turn()) {
+                let answer = match response.answer {
+                    TradeAnswer::Rejected => 0,
+                    TradeAnswer::Countered => 1,
+                    TradeAnswer::Accepted => 2,
+                };
+                let offset = relative::player_id_to_relative(player, response.responder, player_count).to_usize();
+                flat[c_answers + answer * player_count as usize + offset] += 1;
+            }
         }
         flat
     }
 
-
-    pub fn generate_hidden(player: PlayerId, state: &State, _phase: &Phase) -> Array1<i32> {
+    /// Opponents' hands, fully shown when the rules don't hide them (see [Rules](catan::game::Rules))
+    pub fn generate_hidden(format: PyObservationFormat, player: PlayerId, state: &State, _phase: &Phase) -> Array1<i32> {
         let player_count = state.player_count();
         let longest_road = match state.get_longest_road() {
             None => PlayerId::NONE,
@@ -214,27 +370,109 @@ impl PyCatanObservation {
             None => PlayerId::NONE,
             Some((player_id, _)) => player_id,
         };
-        let mut hidden = Array1::<i32>::zeros((player_count as usize - 1)*27);
+        let open_hands = !state.get_rules().hidden_hands;
+        let deck_size = if format.include_deck { DevelopmentCard::COUNT } else { 0 };
+        let mut hidden = Array1::<i32>::zeros((player_count as usize - 1)*27 + deck_size);
         // ## Opponents (p-1)*27
         for opp in 1..player_count {
             let player_index = (opp as usize - 1)*27;
             let player = relative::offset_to_player_id(player, opp, player_count);
-            let hand = &state.get_player_hand(player);
-            PyCatanObservation::fill_flat_concealed(&mut hidden, player_index, hand, longest_road == player, largest_army == player);
+            if open_hands {
+                let hand = &state.get_player_hand(player);
+                PyCatanObservation::fill_flat_visible(&mut hidden, player_index, hand, longest_road == player, largest_army == player);
+            } else {
+                PyCatanObservation::fill_flat_concealed(&mut hidden, player_index, &state.view_for(player).public_hand(player));
+            }
         };
+        // ## Development deck 5 (oracle)
+        if format.include_deck {
+            let c_deck = (player_count as usize - 1)*27;
+            let deck = state.get_development_cards();
+            for d in DevelopmentCard::ALL.iter() {
+                hidden[c_deck + d.to_usize()] = deck[*d].into();
+            }
+        }
         hidden
     }
 
+    /// Observation shared by every player, for centralized critics
+    ///
+    /// Contains the full hand of every player in seat order (27 cells each), the bank (5), the development deck (5) and the current player (1)
+    pub fn generate_global(state: &State, phase: &Phase) -> Array1<i32> {
+        let player_count = state.player_count() as usize;
+        let mut global = Array1::<i32>::zeros(player_count*27 + 11);
+        let longest_road = state.get_longest_road().map(|(p, _)| p);
+        let largest_army = state.get_largest_army().map(|(p, _)| p);
+        // ## Players p*27
+        for p in 0..player_count {
+            let player = PlayerId::from(p);
+            let hand = state.get_player_hand(player);
+            PyCatanObservation::fill_flat_visible(&mut global, p*27, hand, longest_road == Some(player), largest_army == Some(player));
+        }
+        // ## Bank 5
+        let c_bank = player_count*27;
+        let bank_resources = state.get_bank_resources();
+        for res in 0..Resource::COUNT {
+            global[c_bank + res] = bank_resources[res].into();
+        }
+        // ## Development deck 5
+        let c_deck = c_bank + 5;
+        let deck = state.get_development_cards();
+        for d in DevelopmentCard::ALL.iter() {
+            global[c_deck + d.to_usize()] = deck[*d].into();
+        }
+        // ## Current player 1
+        global[c_deck + 5] = phase.player().to_u8().into();
+        global
+    }
+
+    // Fills the settlement reach channel, after the building age one: for each intersection within reach, the roads left to the limit plus one
+    pub fn fill_settlement_reach(format: PyObservationFormat, board: &mut Array3<i32>, player: PlayerId, state: &State) {
+        if let Some(max_roads) = format.settlement_reach {
+            let c_reach = format.board_channels(state.player_count()) - 1;
+            for (intersection, roads) in legal::settlement_reach(player, state, max_roads) {
+                let (x,y) = format.map(intersection);
+                board[(x, y, c_reach)] = i32::from(max_roads) + 1 - i32::from(roads);
+            }
+        }
+    }
+
     pub(crate) fn new_array(format: PyObservationFormat, player: PlayerId, state: &State, phase: &Phase, legal_actions: Array1<bool>) -> PyCatanObservation {
+        let view = state.view_for(player);
         // # BOARD
-        let board = PyCatanObservation::generate_board(format, player, state);
+        let mut board = PyCatanObservation::generate_board(format, &view);
+        if format.include_building_age {
+            PyCatanObservation::fill_building_age(format, &mut board, &view);
+        }
+        PyCatanObservation::fill_settlement_reach(format, &mut board, player, state);
 
         // # FLAT
-        let flat = PyCatanObservation::generate_flat(player, state, phase);
+        let flat = PyCatanObservation::generate_flat(format, &view, phase);
 
         // # HIDDEN
         let hidden = if format.include_hidden {
-            Some(PyCatanObservation::generate_hidden(player, state, phase))
+            Some(PyCatanObservation::generate_hidden(format, player, state, phase))
+        } else {
+            None
+        };
+
+        // # GLOBAL
+        let global = if format.include_global {
+            Some(PyCatanObservation::generate_global(state, phase))
+        } else {
+            None
+        };
+
+        // # GRAPH
+        let graph = if format.include_graph {
+            Some(PyCatanObservation::generate_graph(&view))
+        } else {
+            None
+        };
+
+        // # TOKENS
+        let tokens = if format.include_tokens {
+            Some(Vocabulary::new(view.get_layout(), view.player_count()).tokens(&view, phase))
         } else {
             None
         };
@@ -245,19 +483,56 @@ impl PyCatanObservation {
             board,
             flat,
             hidden,
+            global,
+            budget: None,
+            stage: None,
+            stage_actions: None,
+            priors: None,
+            graph,
+            tokens,
+            reward: None,
+            events: None,
+            sequence: 0,
+            rejected: None,
         }
     }
 
     pub(crate) fn new_python_array(format: PyObservationFormat, player: PlayerId, py_state: &PythonState, state: &State, phase: &Phase, legal_actions: Array1<bool>) -> PyCatanObservation {
+        let view = state.view_for(player);
         // # BOARD
-        let board = py_state.boards[player.to_usize()].clone();
+        let mut board = py_state.boards[player.to_usize()].clone();
+        if format.include_building_age {
+            PyCatanObservation::fill_building_age(format, &mut board, &view);
+        }
+        PyCatanObservation::fill_settlement_reach(format, &mut board, player, state);
 
         // # FLAT
-        let flat = PyCatanObservation::generate_flat(player, state, phase);
+        let flat = PyCatanObservation::generate_flat(format, &view, phase);
 
         // # HIDDEN
         let hidden = if format.include_hidden {
-            Some(PyCatanObservation::generate_hidden(player, state, phase))
+            Some(PyCatanObservation::generate_hidden(format, player, state, phase))
+        } else {
+            None
+        };
+
+        // # GLOBAL
+        let global = if format.include_global {
+            Some(PyCatanObservation::generate_global(state, phase))
+        } else {
+            None
+        };
+
+        // # GRAPH
+        let graph = if format.include_graph {
+            Some(PyCatanObservation::generate_graph(&view))
+        } else {
+            None
+        };
+
+        // # TOKENS
+        let tokens = if format.include_tokens {
+            Some(Vocabulary::new(view.get_layout(), view.player_count()).tokens(&view, phase))
         } else {
             None
         };
@@ -268,6 +543,43 @@ impl PyCatanObservation {
             board,
             flat,
             hidden,
+            global,
+            budget: None,
+            stage: None,
+            stage_actions: None,
+            priors: None,
+            graph,
+            tokens,
+            reward: None,
+            events: None,
+            sequence: 0,
+            rejected: None,
+        }
+    }
+
+    /// Observation of the start of a game, whose arrays give the shapes of every observation
+    pub(crate) fn sample(format: PyObservationFormat, player_count: usize, action_count: usize) -> PyCatanObservation {
+        let state = format.board.setup::<TricellState, SmallRng>(&mut SmallRng::seed_from_u64(0), player_count as u8);
+        PyCatanObservation::new_array(format, PlayerId::FIRST, &state, &Phase::START_GAME, Array1::default(action_count))
+    }
+
+    /// Integer arrays of the observation, in the order of the observation tuples
+    pub(crate) fn arrays(&self) -> Vec<ArrayViewD<'_, i32>> {
+        let mut arrays = vec![self.board.view().into_dyn(), self.flat.view().into_dyn()];
+        if let Some(hidden) = &self.hidden {
+            arrays.push(hidden.view().into_dyn());
+        }
+        if let Some(global) = &self.global {
+            arrays.push(global.view().into_dyn());
+        }
+        if let Some(graph) = &self.graph {
+            arrays.push(graph.hexes.view().into_dyn());
+            arrays.push(graph.intersections.view().into_dyn());
+            arrays.push(graph.paths.view().into_dyn());
+        }
+        if let Some(tokens) = &self.tokens {
+            arrays.push(tokens.view().into_dyn());
         }
+        arrays
     }
 }
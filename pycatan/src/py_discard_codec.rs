@@ -0,0 +1,63 @@
+use ndarray::Array1;
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use numpy::convert::IntoPyArray;
+use numpy::PyArray1;
+
+use catan::game::DiscardCodec;
+use catan::utils::{Resource, Resources};
+
+fn to_resources(counts: [i8; Resource::COUNT]) -> Resources {
+    Resources::new(counts[0], counts[1], counts[2], counts[3], counts[4])
+}
+
+/// Discards of `count` cards as indices, see [DiscardCodec]
+///
+/// During a discard, the environments accept `len(actions) + index` as an action,
+/// discarding the resources of the index from the hand of the player.
+#[pyclass]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PyDiscardCodec {
+    codec: DiscardCodec,
+}
+
+#[pymethods]
+impl PyDiscardCodec {
+
+    #[new]
+    pub fn new(count: u8) -> Self {
+        PyDiscardCodec { codec: DiscardCodec::new(count) }
+    }
+
+    /// Codec of the cards a hand of (brick, lumber, ore, grain, wool) has to discard
+    #[staticmethod]
+    pub fn for_hand(hand: [i8; Resource::COUNT]) -> Self {
+        PyDiscardCodec { codec: DiscardCodec::for_hand(to_resources(hand)) }
+    }
+
+    #[getter]
+    pub fn count(&self) -> u8 {
+        self.codec.count()
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.codec.size()
+    }
+
+    pub fn encode(&self, discarded: [i8; Resource::COUNT]) -> PyResult<usize> {
+        self.codec.encode(to_resources(discarded))
+            .ok_or_else(|| PyValueError::new_err(format!("A discard of {} cards is expected", self.codec.count())))
+    }
+
+    pub fn decode(&self, index: usize) -> PyResult<[i8; Resource::COUNT]> {
+        let discarded = self.codec.decode(index)
+            .ok_or_else(|| PyValueError::new_err(format!("Index {} out of the {} discards", index, self.codec.size())))?;
+        Ok([discarded[0], discarded[1], discarded[2], discarded[3], discarded[4]])
+    }
+
+    /// Legal discards of the hand
+    pub fn mask<'py>(&self, py: Python<'py>, hand: [i8; Resource::COUNT]) -> Bound<'py, PyArray1<bool>> {
+        let mask: Array1<bool> = self.codec.mask(to_resources(hand)).into_iter().collect();
+        mask.into_pyarray(py)
+    }
+}
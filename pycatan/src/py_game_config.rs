@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+
+use catan::game::{GameConfig, Rules, DevelopmentCaps, ResignedPieces, SetupResources};
+use catan::utils::{DevelopmentCard, DevelopmentCards};
+use super::PyObservationFormat;
+use super::py_action::resources_from_py;
+use super::py_observation_format::development_name;
+
+fn developments_from_py(counts: BTreeMap<String, u8>) -> PyResult<DevelopmentCards> {
+    let mut cards = DevelopmentCards::new();
+    for (name, count) in counts {
+        let card = DevelopmentCard::ALL.iter().copied().find(|card| development_name(*card) == name)
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown development card {}", name)))?;
+        cards[card] += count;
+    }
+    Ok(cards)
+}
+
+fn development_caps_from_py(caps: BTreeMap<String, u8>) -> PyResult<DevelopmentCaps> {
+    let mut development_caps = DevelopmentCaps::NONE;
+    for (name, cap) in caps {
+        let card = DevelopmentCard::ALL.iter().copied().find(|card| development_name(*card) == name)
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown development card {}", name)))?;
+        development_caps.set_cap(card, Some(cap)).map_err(PyValueError::new_err)?;
+    }
+    Ok(development_caps)
+}
+
+/// House rules and starting conditions of the games, see [GameConfig]
+///
+/// Resources are given as counts by resource name, and development cards by card name
+/// (knight, road_building, year_of_plenty, monopole and victory_point).
+/// `development_caps` caps the cards of each kind a player can play in a game, the illegal plays being masked.
+/// `resigned_pieces` is "frozen" or "removed", what happens to the pieces of a player who resigns.
+/// `token_swap_every` swaps the number tokens of two producing hexes every that many turns, never when 0.
+/// `setup_resources` is "second", "first", "both" or "none", the initial settlements giving the resources around them.
+/// `bucketed_deck` only shows the size of the development deck in buckets of 5 cards in the observations.
+/// `city_reservation` only lets the settlements reserved on an earlier turn be upgraded to cities.
+/// `trade_proposals_per_turn` caps the trades offered to the opponents in a turn, the offers past it being masked, unlimited when 0.
+/// The rule toggles stay the ones of the environment curriculum.
+#[pyclass(name="GameConfig")]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PyGameConfig {
+    pub config: GameConfig,
+}
+
+#[pymethods]
+impl PyGameConfig {
+
+    #[new]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (victory_points=10, discard_limit=7, robber_from_turn=0, initial_resources=None, bank=None, development_cards=None, development_caps=None, city_reservation=false, resigned_pieces="frozen", token_swap_every=0, bucketed_deck=false, setup_resources="second", trade_proposals_per_turn=0))]
+    pub fn new(
+        victory_points: u8,
+        discard_limit: u8,
+        robber_from_turn: u32,
+        initial_resources: Option<BTreeMap<String, i8>>,
+        bank: Option<BTreeMap<String, i8>>,
+        development_cards: Option<BTreeMap<String, u8>>,
+        development_caps: Option<BTreeMap<String, u8>>,
+        city_reservation: bool,
+        resigned_pieces: &str,
+        token_swap_every: u32,
+        bucketed_deck: bool,
+        setup_resources: &str,
+        trade_proposals_per_turn: u8,
+    ) -> PyResult<Self> {
+        let resigned_pieces = match resigned_pieces {
+            "frozen" => ResignedPieces::Frozen,
+            "removed" => ResignedPieces::Removed,
+            other => return Err(PyValueError::new_err(format!("Unknown resigned pieces {}, frozen or removed", other))),
+        };
+        let setup_resources = match setup_resources {
+            "second" => SetupResources::Second,
+            "first" => SetupResources::First,
+            "both" => SetupResources::Both,
+            "none" => SetupResources::None,
+            other => return Err(PyValueError::new_err(format!("Unknown setup resources {}, second, first, both or none", other))),
+        };
+        if victory_points == 0 {
+            return Err(PyValueError::new_err("At least one victory point is needed to win"));
+        }
+        Ok(PyGameConfig {
+            config: GameConfig {
+                rules: Rules {
+                    victory_points,
+                    discard_limit,
+                    robber_from_turn,
+                    development_caps: development_caps.map_or(Ok(DevelopmentCaps::NONE), development_caps_from_py)?,
+                    city_reservation,
+                    resigned_pieces,
+                    token_swap_every,
+                    bucketed_deck,
+                    setup_resources,
+                    trade_proposals_per_turn,
+                    ..Rules::STANDARD
+                },
+                initial_resources: initial_resources.map_or(Ok(GameConfig::STANDARD.initial_resources), resources_from_py)?,
+                bank: bank.map(resources_from_py).transpose()?,
+                development_cards: development_cards.map(developments_from_py).transpose()?,
+            },
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.config)
+    }
+}
+
+/// Configuration of the games of an environment, checked against its board
+pub(crate) fn game_config(config: Option<PyGameConfig>, format: &PyObservationFormat, player_count: usize) -> PyResult<GameConfig> {
+    let config = config.map_or(GameConfig::STANDARD, |config| config.config);
+    config.check(format.board, player_count as u8).map_err(PyValueError::new_err)?;
+    Ok(config)
+}
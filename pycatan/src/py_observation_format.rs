@@ -1,43 +1,436 @@
+use ndarray::Array2;
 use pyo3::prelude::*;
-use catan::utils::{Coord};
+use pyo3::exceptions::PyValueError;
+use numpy::convert::IntoPyArray;
+use pyo3::types::PyDict;
+use catan::utils::{Coord, Resource, DevelopmentCard};
+use catan::board::layout::BoardLayout;
+use catan::board::utils::topology::Topology;
+use super::tokens::Vocabulary;
 
 #[pyclass]
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct PyObservationFormat {
-    pub half_width: usize,
-    pub half_height: usize,
+    /// Cell of the coordinate (0, 0) in the board array
+    pub x_offset: isize,
+    pub y_offset: isize,
     pub width: usize,
     pub height: usize,
+    /// Unused, environments always keeping the boards up to date as the state changes, see [PythonState](crate::PythonState)
     pub use_python_state: bool,
     pub include_hidden: bool,
+    pub include_building_age: bool,
+    pub include_deck: bool,
+    pub include_global: bool,
+    /// Adds bundles of two bank trades to the actions, right after the Keep actions
+    pub trade_bundles: bool,
+    /// Adds the decision stage and the legal actions among the ones of the stage
+    pub include_stage: bool,
+    /// Board the games are played on
+    pub board: &'static BoardLayout,
+    /// Debug mode checking observations don't leak hidden information, slow
+    ///
+    /// The hidden and global arrays, which show what the observer can't see on purpose, are left out of the check.
+    pub audit: bool,
+    /// Adds the board as a graph, for graph neural networks
+    pub include_graph: bool,
+    /// Adds the game as a sequence of tokens, for transformers
+    pub include_tokens: bool,
+    /// Appends to the flat array what the observer deduced of the resources of the opponents, see [BeliefTracker](catan::state::BeliefTracker)
+    pub include_beliefs: bool,
+    /// Adds a board channel of the intersections the observer may settle on within this many more roads, see [settlement_reach](catan::game::legal::settlement_reach)
+    pub settlement_reach: Option<u8>,
+    /// Adds to the block of each opponent in the flat array the development cards it played, knights being already counted
+    pub include_played_development: bool,
+    /// Appends to the flat array the behavior of the opponents so far in the game, see [OpponentModel](catan::state::OpponentModel)
+    pub include_opponent_model: bool,
+    /// Appends to the flat array the fewest, most and expected cards of each resource of the opponents, see [HandBelief](catan::state::HandBelief)
+    pub include_card_counts: bool,
+    /// Appends to the flat array the answers of each player to the trade offers of the turn so far, see [Negotiation](catan::game::Negotiation)
+    pub include_trade_answers: bool,
+    /// Adds priors of the legal actions, the softmax of their [Heuristic](catan::player::Heuristic) scores divided by this temperature
+    pub action_priors: Option<f32>,
 }
 
 impl PyObservationFormat {
     pub fn map(&self, coord: Coord) -> (usize, usize) {
-        let x = (coord.x + self.half_width as i8) as usize;
-        let y = (coord.y + self.half_height as i8) as usize;
+        let x = (coord.x as isize + self.x_offset) as usize;
+        let y = (coord.y as isize + self.y_offset) as usize;
         (x,y)
     }
+
+    /// Coordinate of a cell of the board array, inverse of [map](PyObservationFormat::map)
+    pub fn unmap(&self, (x, y): (usize, usize)) -> Coord {
+        Coord::new((x as isize - self.x_offset) as i8, (y as isize - self.y_offset) as i8)
+    }
+
+    /// Smallest and largest x and y of the hexes, paths and intersections of a board
+    fn bounds(board: &BoardLayout) -> (isize, isize, isize, isize) {
+        let layout = board.layout;
+        let coords = || layout.hexes.iter().chain(layout.paths.iter()).chain(layout.intersections.iter());
+        let xs = coords().map(|coord| coord.x as isize);
+        let ys = coords().map(|coord| coord.y as isize);
+        (xs.clone().min().unwrap_or(0), xs.max().unwrap_or(0), ys.clone().min().unwrap_or(0), ys.max().unwrap_or(0))
+    }
+
+    /// Number of channels of the board array
+    pub fn board_channels(&self, player_count: u8) -> usize {
+        let mut channels = 13 + 2 * player_count as usize;
+        if self.include_building_age {
+            channels += 1;
+        }
+        if self.settlement_reach.is_some() {
+            channels += 1;
+        }
+        channels
+    }
+
+    /// Names of the channels of the board array, players being named relative to the observer
+    pub fn board_channel_names(&self, player_count: u8) -> Vec<String> {
+        let mut names = hex_channel_names();
+        names.extend(relative_names("road", player_count));
+        names.extend(harbor_channel_names());
+        names.extend(relative_names("building", player_count));
+        if self.include_building_age {
+            names.push("building_age".to_string());
+        }
+        if self.settlement_reach.is_some() {
+            names.push("settlement_reach".to_string());
+        }
+        debug_assert_eq!(names.len(), self.board_channels(player_count));
+        names
+    }
+
+    /// Number of cells of the block of each opponent in the flat array
+    pub fn concealed_size(&self) -> usize {
+        if self.include_played_development { PUBLIC_HAND.len() + PLAYED_DEVELOPMENT.len() } else { PUBLIC_HAND.len() }
+    }
+
+    /// Number of cells of the flat array
+    pub fn flat_size(&self, player_count: u8) -> usize {
+        let mut size = 49 + 9 * player_count as usize;
+        size += (player_count as usize - 1) * (self.concealed_size() - PUBLIC_HAND.len());
+        if self.include_beliefs {
+            size += (player_count as usize - 1) * 2 * Resource::COUNT;
+        }
+        if self.include_opponent_model {
+            size += (player_count as usize - 1) * OPPONENT_MODEL.len();
+        }
+        if self.include_card_counts {
+            size += (player_count as usize - 1) * 3 * Resource::COUNT;
+        }
+        if self.include_trade_answers {
+            size += player_count as usize * TRADE_ANSWERS.len();
+        }
+        size
+    }
+
+    /// Names of the cells of the flat array
+    pub fn flat_names(&self, player_count: u8) -> Vec<String> {
+        let mut names: Vec<String> = visible_hand_names().into_iter().map(|name| format!("self_{}", name)).collect();
+        for opponent in 1..player_count {
+            names.extend(PUBLIC_HAND.iter().map(|name| format!("p+{}_{}", opponent, name)));
+            if self.include_played_development {
+                names.extend(PLAYED_DEVELOPMENT.iter().map(|card| format!("p+{}_played_{}", opponent, development_name(*card))));
+            }
+        }
+        names.extend(Resource::ALL.iter().map(|res| format!("bank_{}", resource_name(*res))));
+        names.push("development_deck".to_string());
+        names.extend((1..player_count).map(|opponent| format!("p+{}_resigned", opponent)));
+        names.extend(["pre_roll", "development_ready", "road_building_left", "year_of_plenty_left", "can_build", "moving_thief", "discard_left"].iter().map(|name| name.to_string()));
+        names.extend(Resource::ALL.iter().map(|res| format!("discarded_{}", resource_name(*res))));
+        names.extend(Resource::ALL.iter().map(|res| format!("trade_give_{}", resource_name(*res))));
+        names.extend(Resource::ALL.iter().map(|res| format!("trade_receive_{}", resource_name(*res))));
+        names.extend(["trade_proposer", "trade_partner", "trade_countered"].iter().map(|name| name.to_string()));
+        if self.include_beliefs {
+            for opponent in 1..player_count {
+                names.extend(Resource::ALL.iter().map(|res| format!("p+{}_minimum_{}", opponent, resource_name(*res))));
+                names.extend(Resource::ALL.iter().map(|res| format!("p+{}_percent_{}", opponent, resource_name(*res))));
+            }
+        }
+        if self.include_opponent_model {
+            for opponent in 1..player_count {
+                names.extend(OPPONENT_MODEL.iter().map(|name| format!("p+{}_{}", opponent, name)));
+            }
+        }
+        if self.include_card_counts {
+            for opponent in 1..player_count {
+                for bound in ["cards_min", "cards_max", "cards_expected"] {
+                    names.extend(Resource::ALL.iter().map(|res| format!("p+{}_{}_{}", opponent, bound, resource_name(*res))));
+                }
+            }
+        }
+        if self.include_trade_answers {
+            for answer in TRADE_ANSWERS.iter() {
+                names.extend(relative_names(&format!("turn_{}", answer), player_count));
+            }
+        }
+        debug_assert_eq!(names.len(), self.flat_size(player_count));
+        names
+    }
+
+    /// Number of channels of the hex, intersection and path features of the graph
+    pub fn graph_channels(player_count: u8) -> (usize, usize, usize) {
+        (7, 6 + player_count as usize, player_count as usize)
+    }
+
+    /// Edges of the board graph, as `(2, n)` arrays of node indices in the order of the layout
+    ///
+    /// Returns the intersections joined by each path, in the order of the paths,
+    /// then the hex and intersection of each hex corner.
+    /// The actions on each node and edge are given by `graph_actions` of the environments
+    pub fn graph_edges(&self) -> (Array2<i64>, Array2<i64>) {
+        let layout = self.board.layout;
+        let intersection_index = |coord: Coord| layout.intersections.iter().position(|i| *i == coord).expect("Intersection out of the layout") as i64;
+        let mut paths = Array2::<i64>::zeros((2, layout.paths.len()));
+        for (i, path) in layout.paths.iter().enumerate() {
+            let ends = Coord::TOPOLOGY.path_intersection_neighbours(*path).expect("Wrong path");
+            paths[(0, i)] = intersection_index(ends[0]);
+            paths[(1, i)] = intersection_index(ends[1]);
+        }
+        let mut corners = Vec::new();
+        for (i, hex) in layout.hexes.iter().enumerate() {
+            for intersection in Coord::TOPOLOGY.hex_intersection_neighbours(*hex).expect("Wrong hex") {
+                corners.push((i as i64, intersection_index(intersection)));
+            }
+        }
+        let mut hexes = Array2::<i64>::zeros((2, corners.len()));
+        for (i, (hex, intersection)) in corners.into_iter().enumerate() {
+            hexes[(0, i)] = hex;
+            hexes[(1, i)] = intersection;
+        }
+        (paths, hexes)
+    }
+}
+
+const PUBLIC_HAND: [&str; 8] = ["resources", "road_pieces", "settlement_pieces", "city_pieces", "knights", "development_cards", "longest_road", "largest_army"];
+
+/// Development cards whose plays are added to the block of each opponent, see [PyObservationFormat::concealed_size]
+pub(crate) const PLAYED_DEVELOPMENT: [DevelopmentCard; 3] = [DevelopmentCard::RoadBuilding, DevelopmentCard::YearOfPlenty, DevelopmentCard::Monopole];
+
+/// Behavior of an opponent, see [PyCatanObservation::generate_flat](crate::PyCatanObservation::generate_flat)
+pub(crate) const OPPONENT_MODEL: [&str; 7] = ["offers_answered", "accepted_percent", "steals", "steals_from_self_percent", "purchases", "purchases_per_10_turns", "turns_since_purchase"];
+
+/// Answers to the trade offers of the turn counted for each player, see [PyCatanObservation::generate_flat](crate::PyCatanObservation::generate_flat)
+pub(crate) const TRADE_ANSWERS: [&str; 3] = ["rejected", "countered", "accepted"];
+
+pub(crate) fn resource_name(resource: Resource) -> &'static str {
+    match resource {
+        Resource::Brick => "brick",
+        Resource::Lumber => "lumber",
+        Resource::Ore => "ore",
+        Resource::Grain => "grain",
+        Resource::Wool => "wool",
+    }
+}
+
+pub(crate) fn development_name(card: DevelopmentCard) -> &'static str {
+    match card {
+        DevelopmentCard::Knight => "knight",
+        DevelopmentCard::RoadBuilding => "road_building",
+        DevelopmentCard::YearOfPlenty => "year_of_plenty",
+        DevelopmentCard::Monopole => "monopole",
+        DevelopmentCard::VictoryPoint => "victory_point",
+    }
+}
+
+fn hex_channel_names() -> Vec<String> {
+    let mut names: Vec<String> = Resource::ALL.iter().map(|res| format!("{}_hex", resource_name(*res))).collect();
+    names.push("desert".to_string());
+    names.push("robber".to_string());
+    names
+}
+
+fn harbor_channel_names() -> Vec<String> {
+    let mut names: Vec<String> = Resource::ALL.iter().map(|res| format!("harbor_{}", resource_name(*res))).collect();
+    names.push("harbor_generic".to_string());
+    names
+}
+
+/// `<prefix>_self`, then `<prefix>_p+<offset>` for the following players
+fn relative_names(prefix: &str, player_count: u8) -> Vec<String> {
+    (0..player_count).map(|offset| if offset == 0 { format!("{}_self", prefix) } else { format!("{}_p+{}", prefix, offset) }).collect()
+}
+
+/// Names of the 27 cells of a visible hand
+fn visible_hand_names() -> Vec<String> {
+    let mut names: Vec<String> = Resource::ALL.iter().map(|res| resource_name(*res).to_string()).collect();
+    names.extend(["road_pieces", "settlement_pieces", "city_pieces", "knights"].iter().map(|name| name.to_string()));
+    names.extend(DevelopmentCard::ALL.iter().map(|card| development_name(*card).to_string()));
+    names.extend(DevelopmentCard::ALL.iter().map(|card| format!("new_{}", development_name(*card))));
+    names.extend(harbor_channel_names());
+    names.push("longest_road".to_string());
+    names.push("largest_army".to_string());
+    names
 }
 
 #[pymethods]
 impl PyObservationFormat {
 
+    /// The board array is `width` by `height` cells, or `2*half_width+1` by `2*half_height+1`, the board being centered in it.
+    /// It defaults to the size of the layout, or of the smallest box holding the board with `crop`
+    ///
+    /// With `include_beliefs`, the flat array ends with what the observer deduced of the resources of each opponent from the transfers it saw:
+    /// the count of each resource surely in its hand, then the percent chance of a card of its hand to be of each resource.
+    /// Unlike `include_hidden`, these features only use what the observer may see.
+    ///
+    /// With `settlement_reach`, the board array ends with a channel of the intersections the observer may settle on
+    /// after building at most that many roads, valued `settlement_reach + 1` minus the roads to build, and 0 out of reach.
+    ///
+    /// With `include_played_development`, the block of each opponent ends with the road building, year of plenty and monopole cards it played.
+    ///
+    /// With `include_opponent_model`, it then ends with the behavior of each opponent so far in the game:
+    /// the trade offers it answered and the percent it accepted, the cards it stole and the percent stolen from the observer,
+    /// its purchases, their count per 10 turns and the turns since the last one.
+    ///
+    /// With `include_card_counts`, it then ends with the card counting of each opponent, from the same deductions as `include_beliefs`:
+    /// the fewest and most cards of each resource its hand may hold, then the expected count of each resource in hundredths of a card.
+    ///
+    /// With `include_trade_answers`, it then ends with the answers given to the trade offers of the turn so far, the offer waiting
+    /// for an answer being in the `trade_` cells: the offers each player rejected, then countered, then accepted, the observer first.
+    /// The player whose turn it is answers the counter offers.
+    ///
+    /// With `action_priors`, observations add a float array aligned with the action mask, of the probabilities a heuristic player
+    /// would play each legal action with: the softmax of its scores divided by `action_priors`, 0 for the illegal actions.
+    /// Its scores range over about 200, a temperature of 10 keeping several actions likely.
     #[new]
-    #[pyo3(signature = (half_width=10, half_height=5, use_python_state=false, include_hidden=false))]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (half_width=None, half_height=None, use_python_state=false, include_hidden=false, include_building_age=false, include_deck=false, include_global=false, trade_bundles=false, include_stage=false, layout="standard", audit=false, include_graph=false, width=None, height=None, crop=false, include_tokens=false, include_beliefs=false, include_opponent_model=false, include_played_development=false, settlement_reach=None, action_priors=None, include_card_counts=false, include_trade_answers=false))]
     pub fn new(
-        half_width: usize,
-        half_height: usize,
+        half_width: Option<usize>,
+        half_height: Option<usize>,
         use_python_state: bool,
-        include_hidden: bool
-    ) -> Self {
-        PyObservationFormat {
-            half_width,
-            half_height,
-            width: 2*half_width+1,
-            height: 2*half_height+1,
+        include_hidden: bool,
+        include_building_age: bool,
+        include_deck: bool,
+        include_global: bool,
+        trade_bundles: bool,
+        include_stage: bool,
+        layout: &str,
+        audit: bool,
+        include_graph: bool,
+        width: Option<usize>,
+        height: Option<usize>,
+        crop: bool,
+        include_tokens: bool,
+        include_beliefs: bool,
+        include_opponent_model: bool,
+        include_played_development: bool,
+        settlement_reach: Option<u8>,
+        action_priors: Option<f32>,
+        include_card_counts: bool,
+        include_trade_answers: bool,
+    ) -> PyResult<Self> {
+        if action_priors.is_some_and(|temperature| temperature.is_nan() || temperature <= 0.0) {
+            return Err(PyValueError::new_err("The temperature of the action priors must be positive"));
+        }
+        let board = BoardLayout::named(layout).map_err(PyValueError::new_err)?;
+        let (min_x, max_x, min_y, max_y) = if crop {
+            PyObservationFormat::bounds(board)
+        } else {
+            let (half_width, half_height) = (board.layout.half_width as isize, board.layout.half_height as isize);
+            (-half_width, half_width, -half_height, half_height)
+        };
+        let (board_width, board_height) = ((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize);
+        let size = |half: Option<usize>, size: Option<usize>, board_size: usize| match (half, size) {
+            (Some(_), Some(_)) => Err(PyValueError::new_err("Half sizes and sizes can't be both given")),
+            (Some(half), None) => Ok(2*half+1),
+            (None, Some(size)) => Ok(size),
+            (None, None) => Ok(board_size),
+        };
+        let width = size(half_width, width, board_width)?;
+        let height = size(half_height, height, board_height)?;
+        if width < board_width || height < board_height {
+            return Err(PyValueError::new_err(format!("Format of {}x{} too small for the {}x{} board",
+                width, height, board_width, board_height)));
+        }
+        Ok(PyObservationFormat {
+            x_offset: ((width - board_width) / 2) as isize - min_x,
+            y_offset: ((height - board_height) / 2) as isize - min_y,
+            width,
+            height,
             use_python_state,
             include_hidden,
+            include_building_age,
+            include_deck,
+            include_global,
+            trade_bundles,
+            include_stage,
+            board,
+            audit,
+            include_graph,
+            include_tokens,
+            include_beliefs,
+            include_opponent_model,
+            include_played_development,
+            settlement_reach,
+            action_priors,
+            include_card_counts,
+            include_trade_answers,
+        })
+    }
+
+    /// Names of the channels of the board array and of the cells of the flat array, for games of `players` players
+    ///
+    /// Players are named relative to the observer, `road_self` being the observer's roads and `road_p+1` the ones of the next player.
+    /// With the graph, names of the channels of its `hexes`, `intersections` and `paths` are added,
+    /// and with the tokens, the name of each token, see [Vocabulary].
+    fn schema(&self, py: Python, players: u8) -> PyResult<PyObject> {
+        let schema = PyDict::new(py);
+        schema.set_item("board", self.board_channel_names(players))?;
+        schema.set_item("flat", self.flat_names(players))?;
+        if self.include_graph {
+            let mut intersections = harbor_channel_names();
+            intersections.extend(relative_names("building", players));
+            schema.set_item("hexes", hex_channel_names())?;
+            schema.set_item("intersections", intersections)?;
+            schema.set_item("paths", relative_names("road", players))?;
+        }
+        if self.include_tokens {
+            schema.set_item("tokens", Vocabulary::new(self.board.layout, players).names())?;
+        }
+        Ok(schema.into_any().unbind())
+    }
+
+    /// Cell `(x, y)` of the board array holding a coordinate
+    #[pyo3(name = "map")]
+    fn py_map(&self, coord: (i8, i8)) -> PyResult<(usize, usize)> {
+        let (x, y) = (coord.0 as isize + self.x_offset, coord.1 as isize + self.y_offset);
+        if x < 0 || x >= self.width as isize || y < 0 || y >= self.height as isize {
+            return Err(PyValueError::new_err(format!("Coordinate {:?} out of the {}x{} board array", coord, self.width, self.height)));
+        }
+        Ok((x as usize, y as usize))
+    }
+
+    /// Coordinate of a cell `(x, y)` of the board array, inverse of `map`
+    #[pyo3(name = "unmap")]
+    fn py_unmap(&self, cell: (usize, usize)) -> PyResult<(i8, i8)> {
+        if cell.0 >= self.width || cell.1 >= self.height {
+            return Err(PyValueError::new_err(format!("Cell {:?} out of the {}x{} board array", cell, self.width, self.height)));
         }
+        let coord = self.unmap(cell);
+        Ok((coord.x, coord.y))
+    }
+
+    /// Coordinates of the hexes of the layout, in the order of the actions and graph nodes
+    fn hexes(&self) -> Vec<(i8, i8)> {
+        self.board.layout.hexes.iter().map(|coord| (coord.x, coord.y)).collect()
+    }
+
+    /// Coordinates of the paths of the layout, in the order of the actions and graph edges
+    fn paths(&self) -> Vec<(i8, i8)> {
+        self.board.layout.paths.iter().map(|coord| (coord.x, coord.y)).collect()
+    }
+
+    /// Coordinates of the intersections of the layout, in the order of the actions and graph nodes
+    fn intersections(&self) -> Vec<(i8, i8)> {
+        self.board.layout.intersections.iter().map(|coord| (coord.x, coord.y)).collect()
+    }
+
+    /// Edges of the board graph as `(path_edges, hex_edges)`, see [graph_edges](PyObservationFormat::graph_edges)
+    #[pyo3(name = "graph_edges")]
+    fn py_graph_edges(&self, py: Python) -> (PyObject, PyObject) {
+        let (paths, hexes) = self.graph_edges();
+        (paths.into_pyarray(py).into_any().unbind(), hexes.into_pyarray(py).into_any().unbind())
     }
 }
@@ -0,0 +1,40 @@
+use pyo3::prelude::*;
+
+use catan::game::RewardConfig;
+
+/// Weights of the rewards carried by the observations, see [RewardConfig]
+///
+/// Each observation carries the reward received since the previous observation of the player,
+/// and the result of the game the rest of it, with the `win_bonus`.
+#[pyclass]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct PyRewardConfig {
+    pub config: RewardConfig,
+}
+
+#[pymethods]
+impl PyRewardConfig {
+
+    /// With `normalize_vp`, victory point rewards are divided by the victory points winning the game,
+    /// and every reward is multiplied by `scale`
+    #[new]
+    #[pyo3(signature = (vp_delta=1.0, win_bonus=0.0, income=0.0, building=0.0, illegal_penalty=0.0, normalize_vp=false, scale=1.0))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(vp_delta: f32, win_bonus: f32, income: f32, building: f32, illegal_penalty: f32, normalize_vp: bool, scale: f32) -> Self {
+        PyRewardConfig {
+            config: RewardConfig {
+                vp_delta,
+                win_bonus,
+                income,
+                building,
+                illegal_penalty,
+                normalize_vp,
+                scale,
+            },
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self.config)
+    }
+}
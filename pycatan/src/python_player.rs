@@ -1,34 +1,173 @@
 use ndarray::Array1;
-use std::sync::mpsc::{Sender, Receiver};
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use std::sync::mpsc::{Sender, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
-use catan::state::{State, PlayerId};
-use catan::game::{legal, Phase, Action, Error, Notification, TurnPhase, DevelopmentPhase};
-use catan::player::CatanPlayer;
-use catan::player::generate_possible_actions;
-use catan::utils::Resources;
+use catan::state::{State, PlayerId, audit};
+use catan::game::{legal, Phase, Action, ActionCategory, Error, Notification, TurnPhase, DevelopmentPhase, DecisionStage, DiceStatistics, DevelopmentUsage, GameStats, DiscardCodec, Checkpoint, RewardTracker, GameEvent, EventLog};
+use catan::player::{CatanPlayer, Heuristic};
+use catan::player::relative::player_id_to_relative;
+use catan::player::{generate_possible_actions, generate_trade_bundles, SearchLog, SearchRecord};
+use catan::utils::{Resource, Resources};
 
 use super::{PythonState, PyCatanObservation, PyObservationFormat};
 //generate_possible_actions(&mut self.possible_actions, self.position, state);
 
+/// What a Python player does with an illegal action
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(crate) enum IllegalActions {
+    /// The game refuses it and asks again, with a new observation
+    Retry,
+    /// The environment raises an `IllegalActionError`, the observation still waiting for its answer
+    Raise,
+    /// The action played when the turn budget runs out is played instead
+    Resolve,
+}
+
+impl IllegalActions {
+    pub(crate) fn from_name(name: &str) -> PyResult<IllegalActions> {
+        match name {
+            "retry" => Ok(IllegalActions::Retry),
+            "raise" => Ok(IllegalActions::Raise),
+            "resolve" => Ok(IllegalActions::Resolve),
+            _ => Err(PyValueError::new_err(format!("Unknown illegal action handling {}, expected retry, raise or resolve", name))),
+        }
+    }
+}
+
+/// Outcome of a game for one Python player
+pub(crate) struct PlayerResult {
+    pub seat: PlayerId,
+    pub victory_points: u8,
+    pub winner: bool,
+    pub development: DevelopmentUsage,
+    /// Reward received since the last observation, when rewards are tracked
+    pub reward: Option<f32>,
+    /// Events seen since the last observation, when events are logged
+    pub events: Option<Vec<GameEvent>>,
+    /// Metrics of the game, when collected
+    pub stats: Option<GameStats>,
+    /// Decisions searched by the search opponents during the game, when logged
+    pub searches: Option<Vec<SearchRecord>>,
+}
+
+/// Every action a player can pick, in the order of the action masks
+pub(crate) fn action_list(format: PyObservationFormat, position: PlayerId, state: &State) -> Vec<Action> {
+    let mut possible_actions = Vec::new();
+    generate_possible_actions(&mut possible_actions, position, state);
+    if format.trade_bundles {
+        generate_trade_bundles(&mut possible_actions);
+    }
+    possible_actions
+}
+
+/// Indices in the action list of the actions of each decision stage
+pub(crate) fn stage_indices(possible_actions: &[Action]) -> Vec<Vec<usize>> {
+    DecisionStage::ALL.iter().map(|stage| {
+        possible_actions.iter().enumerate()
+            .filter(|(_, action)| stage.contains(action))
+            .map(|(i, _)| i)
+            .collect()
+    }).collect()
+}
+
+/// Softmax of the [Heuristic] scores of the legal actions divided by the temperature, 0 for the illegal ones
+pub(crate) fn action_priors(possible_actions: &[Action], legal_actions: &Array1<bool>, position: PlayerId, phase: &Phase, state: &State, temperature: f32) -> Array1<f32> {
+    let legal: Vec<Action> = possible_actions.iter().zip(legal_actions.iter()).filter(|(_, legal)| **legal).map(|(action, _)| *action).collect();
+    let scores = Heuristic::action_scores(position, phase, state, &legal);
+    let best = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let weights: Vec<f32> = scores.iter().map(|score| ((score - best) / temperature).exp()).collect();
+    let total: f32 = weights.iter().sum();
+    let mut weights = weights.into_iter();
+    legal_actions.iter().map(|legal| if *legal { weights.next().unwrap() / total } else { 0.0 }).collect()
+}
+
+/// The event as seen by the player, with the players relative to it
+pub(crate) fn relative_event(event: &GameEvent, position: PlayerId, state: &State) -> GameEvent {
+    let count = state.player_count();
+    let relative = |player: PlayerId| if player == PlayerId::NONE { player } else { player_id_to_relative(position, player, count) };
+    match event.seen_by(position, &state.get_rules()) {
+        GameEvent::DiceRolled { player, roll } => GameEvent::DiceRolled { player: relative(player), roll },
+        GameEvent::ResourcesDistributed { roll, resources } => GameEvent::ResourcesDistributed {
+            roll,
+            resources: (0..count).map(|p| resources[(position.to_u8() + p) as usize % count as usize]).collect(),
+        },
+        GameEvent::RobberMoved { player, hex } => GameEvent::RobberMoved { player: relative(player), hex },
+        GameEvent::CardStolen { thief, victim, resource } => GameEvent::CardStolen { thief: relative(thief), victim: relative(victim), resource },
+        GameEvent::BuildingPlaced { player, building, coord } => GameEvent::BuildingPlaced { player: relative(player), building, coord },
+        GameEvent::TradeExecuted { player, partner, given, received } => {
+            GameEvent::TradeExecuted { player: relative(player), partner: partner.map(relative), given, received }
+        }
+        GameEvent::LongestRoadChanged { holder } => GameEvent::LongestRoadChanged { holder: holder.map(|(player, length)| (relative(player), length)) },
+        GameEvent::GameEnded { winner } => GameEvent::GameEnded { winner: relative(winner) },
+    }
+}
+
+/// Action index ending the game in progress, sent by the environments when resetting
+pub(crate) const RESET_ACTION: u16 = u16::MAX;
+
+/// Action index asking for a checkpoint of the game, the observation still waiting for its answer
+pub(crate) const SAVE_ACTION: u16 = u16::MAX - 1;
+
+/// Action index resigning the player, the game going on among the others, see [Action::Resign]
+pub(crate) const RESIGN_ACTION: u16 = u16::MAX - 2;
+
 pub struct PythonPlayer {
     id: u8,
     position: PlayerId,
     format: PyObservationFormat,
-    action_receiver: Receiver<u16>,
+    action_receiver: Receiver<(u32, u16)>,
     observation_sender: Sender<Option<(u8, PyCatanObservation)>>,
-    result_sender: Sender<(u8, bool)>,
+    result_sender: Sender<PlayerResult>,
+    dice: Arc<Mutex<DiceStatistics>>,
+    turn_budget: Option<Duration>,
+    /// Deadline of the current turn, set by the first decision of the turn
+    deadline: Option<Instant>,
+    sequence: u32,
+    rolled: bool,
+    development: DevelopmentUsage,
+    /// Forced actions, when a single action is legal or a discard is of a single resource, are played without asking Python
+    pub(crate) auto_resolve: bool,
+    /// Where checkpoints asked with [SAVE_ACTION] are sent
+    pub(crate) checkpoint_sender: Option<Sender<Vec<u8>>>,
+    /// Rewards carried by the observations
+    pub(crate) rewards: Option<RewardTracker>,
+    /// Events carried by the observations, logged by the game
+    pub(crate) events: Option<EventLog>,
+    /// Metrics of the game, collected when set and started again by each new game
+    pub(crate) stats: Option<GameStats>,
+    /// Decisions searched by the search opponents, logged by them and taken by each new game and result
+    pub(crate) searches: Option<SearchLog>,
+    pub(crate) illegal_actions: IllegalActions,
     possible_actions: Array1<Action>,
     action_length: usize,
+    /// Index of the first Keep action
+    discard_index: usize,
+    /// Index of the first trade bundle, right after the Keep actions
+    bundle_index: usize,
+    /// Index of the first trade offer, right after the trade bundles if any
+    trade_index: usize,
+    /// Index of the first card by card Discard action, right after the trades between players
+    card_discard_index: usize,
+    /// Index of the first city reservation, right after the card by card discards
+    reserve_index: usize,
+    /// Index of the withdrawal of an offer, followed by its revisions, right after the city reservations
+    revise_index: usize,
+    stage_indices: Vec<Vec<usize>>,
 }
 
 impl PythonPlayer {
     pub(crate) fn new<'a>(
         id: u8,
         format: PyObservationFormat,
-        action_receiver: Receiver<u16>,
+        action_receiver: Receiver<(u32, u16)>,
         observation_sender: Sender<Option<(u8, PyCatanObservation)>>,
-        result_sender: Sender<(u8,bool)>
+        result_sender: Sender<PlayerResult>,
+        dice: Arc<Mutex<DiceStatistics>>,
+        turn_budget: Option<Duration>,
     ) -> PythonPlayer {
         PythonPlayer {
             id,
@@ -37,8 +176,115 @@ impl PythonPlayer {
             action_receiver,
             observation_sender,
             result_sender,
+            dice,
+            turn_budget,
+            deadline: None,
+            sequence: 0,
+            rolled: false,
+            development: DevelopmentUsage::new(),
+            auto_resolve: false,
+            checkpoint_sender: None,
+            rewards: None,
+            events: None,
+            stats: None,
+            searches: None,
+            illegal_actions: IllegalActions::Retry,
             possible_actions: vec![Action::EndTurn;0].into_iter().collect(),
             action_length: 0,
+            discard_index: 0,
+            trade_index: 0,
+            card_discard_index: 0,
+            reserve_index: 0,
+            revise_index: 0,
+            bundle_index: 0,
+            stage_indices: Vec::new(),
+        }
+    }
+
+    /// Observation of the decision, with its reward and decision stage
+    pub(crate) fn observe(&mut self, phase: &Phase, state: &State, legal_actions: &Array1<bool>) -> PyCatanObservation {
+        let mut observation = match state.as_any().downcast_ref::<PythonState>() {
+            Some(python_state) => PyCatanObservation::new_python_array(self.format, self.position, python_state, state, phase, legal_actions.clone()),
+            None => PyCatanObservation::new_array(self.format, self.position, state, phase, legal_actions.clone()),
+        };
+        observation.reward = self.rewards.as_mut().map(|rewards| rewards.take(state));
+        observation.events = self.take_events(state);
+        if self.format.include_stage {
+            let stage = phase.decision_stage();
+            observation.stage = Some(stage as u8);
+            observation.stage_actions = Some(self.stage_indices[stage as usize].iter().map(|i| legal_actions[*i]).collect());
+        }
+        if let Some(temperature) = self.format.action_priors {
+            observation.priors = Some(action_priors(self.possible_actions.as_slice().unwrap(), legal_actions, self.position, phase, state, temperature));
+        }
+        observation
+    }
+
+    /// Events logged since the last time they were taken, as seen by the player
+    fn take_events(&self, state: &State) -> Option<Vec<GameEvent>> {
+        self.events.as_ref().map(|log| log.take().iter().map(|event| relative_event(event, self.position, state)).collect())
+    }
+
+    /// Action played when the turn budget runs out: ending the turn or rolling if possible, else the first legal action
+    pub(crate) fn fallback_action(&self, legal_actions: &Array1<bool>) -> Action {
+        for action in [Action::EndTurn, Action::RollDice] {
+            if let Some(i) = self.possible_actions.iter().position(|a| *a == action) {
+                if legal_actions[i] {
+                    return action;
+                }
+            }
+        }
+        let i = legal_actions.iter().position(|legal| *legal).expect("No legal action to fall back to");
+        self.possible_actions[i]
+    }
+
+    /// Action of the index, indices past the action list being discards encoded by a [DiscardCodec],
+    /// None past the discards of the hand
+    pub(crate) fn decode_action(&self, index: usize, state: &State) -> Option<Action> {
+        if index < self.action_length {
+            return Some(self.possible_actions[index]);
+        }
+        let hand = state.get_player_hand(self.position).resources;
+        DiscardCodec::for_hand(hand).keep(hand, index - self.action_length)
+    }
+
+    /// Discard of a hand of a single resource, every way of discarding it keeping the same cards
+    pub(crate) fn trivial_discard(&self, phase: &Phase, state: &State) -> Option<Action> {
+        let Phase::Turn { player: _, turn_phase: TurnPhase::Discard(player), development_phase: _ } = phase else {
+            return None;
+        };
+        let hand = state.get_player_hand(*player).resources;
+        let mut held = Resource::ALL.iter().filter(|resource| hand[**resource] > 0);
+        let (Some(resource), None) = (held.next(), held.next()) else {
+            return None;
+        };
+        let action = Action::Keep { resources: Resources::new_one(*resource, (hand.total() + 1) / 2) };
+        legal::legal(phase, state, action).is_ok().then_some(action)
+    }
+
+    /// Sends the observation of the decision under a new sequence number, returning a copy of it when illegal actions are raised
+    fn ask(&mut self, phase: &Phase, state: &State, legal_actions: &Array1<bool>) -> Option<PyCatanObservation> {
+        let remaining = self.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        self.sequence += 1;
+        let mut observation = self.observe(phase, state, legal_actions);
+        observation.budget = remaining.map(|remaining| remaining.as_secs_f32());
+        observation.sequence = self.sequence;
+        let rejection = (self.illegal_actions == IllegalActions::Raise).then(|| observation.clone());
+        self.observation_sender.send(Some((self.id, observation))).expect("Failed sending observation");
+        rejection
+    }
+
+    /// Index of an action, the inverse of [decode_action](PythonPlayer::decode_action), None for an action out of the mask
+    pub(crate) fn encode_action(&self, action: Action, state: &State) -> Option<usize> {
+        if let Some(index) = self.possible_actions.iter().position(|a| *a == action) {
+            return Some(index);
+        }
+        match action {
+            Action::Keep { resources } => {
+                let hand = state.get_player_hand(self.position).resources;
+                DiscardCodec::for_hand(hand).encode(hand - resources).map(|index| self.action_length + index)
+            }
+            _ => None,
         }
     }
 
@@ -49,10 +295,11 @@ impl PythonPlayer {
         }
     }
 
-    fn make_legal_actions(&mut self, phase: &Phase, state: &State) -> Array1<bool> {
+    pub(crate) fn make_legal_actions(&mut self, phase: &Phase, state: &State) -> Array1<bool> {
         match phase {
             Phase::InitialPlacement { player: _, placing_second: _, placing_road } => self.make_legal_initial_actions(phase, state, *placing_road),
             Phase::Turn { player: _, turn_phase: TurnPhase::Discard(_), development_phase: _ } => self.make_legal_discards(phase, state),
+            Phase::Turn { player: _, turn_phase: TurnPhase::Trade(_), development_phase: _ } => self.make_legal_trade_answers(phase, state),
             Phase::Turn { player: _, turn_phase, development_phase } => self.make_legal_turn_actions(phase, state, *turn_phase, *development_phase),
             _ => Array1::default(self.action_length),
         }
@@ -81,7 +328,17 @@ impl PythonPlayer {
 
     fn make_legal_discards(&mut self, phase: &Phase, state: &State) -> Array1<bool> {
         let mut legal_actions = Array1::default(self.action_length);
-        self.update_legal_actions_slice(&mut legal_actions, phase, state, self.action_length - 70, self.action_length);
+        // The 70 ways of keeping 4 cards, and the discards of one card of each resource
+        self.update_legal_actions_slice(&mut legal_actions, phase, state, self.discard_index, self.bundle_index);
+        self.update_legal_actions_slice(&mut legal_actions, phase, state, self.card_discard_index, self.reserve_index);
+        legal_actions
+    }
+
+    fn make_legal_trade_answers(&mut self, phase: &Phase, state: &State) -> Array1<bool> {
+        let mut legal_actions = Array1::default(self.action_length);
+        self.update_legal_actions_slice(&mut legal_actions, phase, state, self.trade_index + 60, self.card_discard_index);
+        // The proposer answers a counter offer by withdrawing or revising its offer too
+        self.update_legal_actions_slice(&mut legal_actions, phase, state, self.revise_index, self.revise_index + 61);
         legal_actions
     }
 
@@ -123,13 +380,17 @@ impl PythonPlayer {
             }
         }
         index += 2*intersection_actions;
+        // ## City reservations, at the end of the list
+        if state.get_rules().city_reservation && state.get_rules().building_allowed(turn_phase) && hand.city_pieces > 0 {
+            self.update_legal_actions_slice(&mut legal_actions, phase, state, self.reserve_index, self.reserve_index + intersection_actions);
+        }
 
         // # FLAT
         // ## TurnPhase
         if let Phase::Turn { player: _, turn_phase, development_phase: _ } = phase {
             match turn_phase {
                 TurnPhase::PreRoll => legal_actions[index] = true,
-                TurnPhase::Free => legal_actions[index+1] = true,
+                TurnPhase::Free | TurnPhase::SpecialBuilding(_) => legal_actions[index+1] = true,
                 _ => (),
             }
         }
@@ -137,19 +398,24 @@ impl PythonPlayer {
         // ## Trade
         self.update_legal_actions_slice(&mut legal_actions, phase, state, index, index + 20);
         index += 20;
+        if state.get_rules().bank_trading_allowed(turn_phase) {
+            self.update_legal_actions_slice(&mut legal_actions, phase, state, self.bundle_index, self.trade_index);
+        }
+        if turn_phase == TurnPhase::Free && state.get_rules().domestic_trading {
+            self.update_legal_actions_slice(&mut legal_actions, phase, state, self.trade_index, self.trade_index + 60);
+        }
         // ## Development
         legal_actions[index] = legal::legal(phase, state, self.possible_actions[index]).is_ok();
         index += 1;
         match development_phase {
             DevelopmentPhase::Ready => {
                 let dvp_cards =  hand.development_cards;
-                legal_actions[index] = dvp_cards.knight > 0;
-                legal_actions[index+1] = dvp_cards.road_building > 0 && hand.road_pieces > 0;
-                legal_actions[index+2] = dvp_cards.year_of_plenty > 0;
+                legal_actions[index] = dvp_cards.knight > 0 && legal::legal(phase, state, self.possible_actions[index]).is_ok();
+                legal_actions[index+1] = dvp_cards.road_building > 0 && hand.road_pieces > 0 && legal::legal(phase, state, self.possible_actions[index+1]).is_ok();
+                // Year of plenty needs a resource in the bank
+                legal_actions[index+2] = dvp_cards.year_of_plenty > 0 && legal::legal(phase, state, self.possible_actions[index+2]).is_ok();
                 if dvp_cards.monopole > 0 {
-                    for i in index+8..index+13 {
-                        legal_actions[i] = true;
-                    }
+                    self.update_legal_actions_slice(&mut legal_actions, phase, state, index + 8, index + 13);
                 }
             }
             DevelopmentPhase::YearOfPlentyActive { two_left: _ } => {
@@ -164,9 +430,32 @@ impl PythonPlayer {
 impl CatanPlayer for PythonPlayer {
     fn new_game(&mut self, position: PlayerId, state: &State) {
         self.position = position;
+        self.rolled = false;
+        self.deadline = None;
+        self.development = DevelopmentUsage::new();
+        if let Some(rewards) = &mut self.rewards {
+            rewards.new_game(position, state);
+        }
+        if let Some(stats) = &mut self.stats {
+            *stats = GameStats::new(state.player_count());
+        }
+        if let Some(searches) = &self.searches {
+            searches.take();
+        }
+        // Every player sees the same rolls, so only the first one keeps track of them
+        if self.id == 0 {
+            self.dice.lock().unwrap().new_game();
+        }
         if self.action_length == 0 {
-            let mut possible_action_vec = Vec::new();
-            generate_possible_actions(&mut possible_action_vec, self.position, state);
+            let possible_action_vec = action_list(self.format, self.position, state);
+            let position = |category| possible_action_vec.iter().position(|action: &Action| action.category() == category);
+            self.discard_index = position(ActionCategory::Keep).expect("No discard in the actions");
+            self.trade_index = position(ActionCategory::ProposeTrade).expect("No trade offer in the actions");
+            self.card_discard_index = position(ActionCategory::Discard).expect("No card by card discard in the actions");
+            self.reserve_index = position(ActionCategory::ReserveCity).expect("No city reservation in the actions");
+            self.revise_index = position(ActionCategory::WithdrawTrade).expect("No offer withdrawal in the actions");
+            self.bundle_index = position(ActionCategory::TradeBankBundle).unwrap_or(self.trade_index);
+            self.stage_indices = stage_indices(&possible_action_vec);
             self.possible_actions = possible_action_vec.into_iter().collect();
             self.action_length = self.possible_actions.len();
         } else {
@@ -185,30 +474,150 @@ impl CatanPlayer for PythonPlayer {
 
     fn pick_action(&mut self, phase: &Phase, state: &State) -> Action {
         let legal_actions = self.make_legal_actions(phase, state);
-        self.observation_sender.send(
-            Some((
-                self.id,
-                match state.as_any().downcast_ref::<PythonState>() {
-                    Some(python_state) => PyCatanObservation::new_python_array(self.format, self.position, python_state, state, phase, legal_actions),
-                    None => PyCatanObservation::new_array(self.format, self.position, state, phase, legal_actions),
+        // The turn of an initial placement starts with the settlement
+        if let Phase::InitialPlacement { player: _, placing_second: _, placing_road: false } = phase {
+            self.deadline = None;
+        }
+        if let Some(budget) = self.turn_budget {
+            self.deadline.get_or_insert_with(|| Instant::now() + budget);
+        }
+        if self.auto_resolve {
+            let mut legal = legal_actions.iter().enumerate().filter(|(_, legal)| **legal);
+            if let (Some((i, _)), None) = (legal.next(), legal.next()) {
+                return self.possible_actions[i];
+            }
+            if let Some(action) = self.trivial_discard(phase, state) {
+                return action;
+            }
+        }
+        let remaining = self.deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        // Once the budget is spent, the rest of the turn is played without asking
+        if remaining == Some(Duration::ZERO) {
+            return self.fallback_action(&legal_actions);
+        }
+        if self.format.audit {
+            // The observation and legal actions must be the same on every state the player can't tell apart,
+            // the hidden and global arrays showing the rest of the state on purpose
+            let (format, position) = (self.format, self.position);
+            audit::assert_view_only(state, format.board.layout, phase, position, &mut rand::rng(), 1, |state| {
+                let legal_actions = self.make_legal_actions(phase, state);
+                let observation = PyCatanObservation::new_array(format, position, state, phase, legal_actions.clone());
+                (legal_actions, observation.board, observation.flat)
+            });
+        }
+        let mut rejection = self.ask(phase, state, &legal_actions);
+        loop {
+            let (sequence, action) = match self.deadline {
+                Some(deadline) => match self.action_receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(received) => received,
+                    Err(RecvTimeoutError::Timeout) => return self.fallback_action(&legal_actions),
+                    // The environment closed, the game ends
+                    Err(RecvTimeoutError::Disconnected) => return Action::Exit,
+                },
+                None => {
+                    thread::park();
+                    match self.action_receiver.recv() {
+                        Ok(received) => received,
+                        Err(_) => return Action::Exit,
+                    }
                 }
-            ))
-        ).expect("Failed sending observation");
-        thread::park();
-        self.possible_actions[self.action_receiver.recv().expect("Failed receiving action") as usize]
+            };
+            if action == RESET_ACTION {
+                return Action::Exit;
+            }
+            if action == SAVE_ACTION {
+                if let Some(sender) = &self.checkpoint_sender {
+                    sender.send(Checkpoint::new(*phase, state).to_bytes()).expect("Failed sending checkpoint");
+                }
+                continue;
+            }
+            // Actions answering an observation that timed out are dropped
+            if sequence != self.sequence {
+                continue;
+            }
+            // Refused during the initial placement, the decision being observed again
+            if action == RESIGN_ACTION {
+                return Action::Resign;
+            }
+            // In the action space, illegal actions are refused by the game when retried
+            if let Some(decoded) = self.decode_action(action as usize, state) {
+                if self.illegal_actions == IllegalActions::Retry || legal::legal(phase, state, decoded).is_ok() {
+                    return decoded;
+                }
+            }
+            if let Some(rejection) = &rejection {
+                let mut rejection = rejection.clone();
+                rejection.rejected = Some(action);
+                self.observation_sender.send(Some((self.id, rejection))).expect("Failed sending rejection");
+                continue;
+            }
+            if let Some(rewards) = &mut self.rewards {
+                rewards.illegal_action();
+            }
+            // Out of the action space, the decision is observed again
+            if self.illegal_actions == IllegalActions::Retry {
+                rejection = self.ask(phase, state, &legal_actions);
+                continue;
+            }
+            return self.fallback_action(&legal_actions);
+        }
     }
 
     fn bad_action(&mut self, error: Error) {
         println!("{:?}", error);
+        if let Some(rewards) = &mut self.rewards {
+            rewards.illegal_action();
+        }
     }
 
-    fn notify(&mut self, _: &Notification) {}
+    fn notify(&mut self, notification: &Notification) {
+        if let Some(rewards) = &mut self.rewards {
+            rewards.notify(notification);
+        }
+        if let Notification::ActionPlayed { by, action } = notification {
+            // A new turn starts with a new budget
+            if let Action::EndTurn = action {
+                self.deadline = None;
+            }
+            if let Some(stats) = &mut self.stats {
+                stats.record_action(*by, *action);
+            }
+            if *by == self.position {
+                self.development.record(*action, self.rolled);
+                match action {
+                    Action::RollDice => self.rolled = true,
+                    Action::EndTurn => self.rolled = false,
+                    _ => (),
+                }
+            }
+        }
+        if let Some(roll) = notification.roll() {
+            if self.id == 0 {
+                self.dice.lock().unwrap().record(roll);
+            }
+            if let Some(stats) = &mut self.stats {
+                stats.record_roll(roll);
+            }
+        }
+    }
 
     fn results(&mut self, state: &State, winner: PlayerId) {
         if self.id==0 {
             self.observation_sender.send(None).expect("Failed sending game finished");
         }
-        self.result_sender.send((state.get_player_total_vp(self.position), self.position == winner)).expect("Failed sending game results");
+        if let Some(stats) = &mut self.stats {
+            stats.finish(state, winner);
+        }
+        self.result_sender.send(PlayerResult {
+            seat: self.position,
+            victory_points: state.get_player_total_vp(self.position),
+            winner: self.position == winner,
+            development: self.development,
+            reward: self.rewards.as_mut().map(|rewards| rewards.finish(state, winner)),
+            events: self.take_events(state),
+            stats: self.stats.clone(),
+            searches: self.searches.as_ref().map(|searches| searches.take()),
+        }).expect("Failed sending game results");
     }
 }
 
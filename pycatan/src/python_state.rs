@@ -3,11 +3,14 @@ use ndarray::{Array1, Array3};
 
 use catan::board::{Layout, Error};
 use catan::utils::{Hex, LandHex, Harbor, Coord, DevelopmentCards, Resources};
-use catan::state::PlayerHand;
+use catan::state::{PlayerHand, Ledger, BuildingHistory};
+use catan::game::Rules;
 use catan::state::{State, TricellState, StateTrait, StateMaker, PlayerId};
 
 use super::PyObservationFormat;
 
+/// State keeping the board array of every player up to date as the hexes, harbors, thief, roads and buildings are set,
+/// so that observations copy the boards instead of building them
 pub struct PythonState {
     format: PyObservationFormat,
     player_count: usize,
@@ -21,22 +24,27 @@ impl PythonState {
         PythonState {
             format,
             player_count,
-            boards: vec![Array3::<i32>::zeros((format.width,format.height, 13 + 2 * players as usize)); player_count].into(),
+            boards: vec![Array3::<i32>::zeros((format.width,format.height, format.board_channels(players))); player_count].into(),
             state: TricellState::new_empty(layout, players),
         }
     }
 
     pub fn set_all(&mut self, coord: Coord, channel: usize, value: i32)  {
-        let x = (coord.x + self.format.half_width as i8) as usize;
-        let y = (coord.y + self.format.half_height as i8) as usize;
+        let (x, y) = self.format.map(coord);
         for board in self.boards.iter_mut() {
             board[(x, y, channel)] = value;
         }
     }
 
+    /// Thief cell of a hex, only set on land hexes
+    fn set_thief(&mut self, coord: Coord, value: i32) {
+        if let Ok(Hex::Land(_)) = self.state.get_static_hex(coord) {
+            self.set_all(coord, 6, value);
+        }
+    }
+
     pub fn set_roll(&mut self, coord: Coord, channel: usize, value: i32, player: PlayerId) {
-        let x = (coord.x + self.format.half_width as i8) as usize;
-        let y = (coord.y + self.format.half_height as i8) as usize;
+        let (x, y) = self.format.map(coord);
         let mut i = player.to_usize();
         for board in self.boards.iter_mut() {
             board[(x, y, channel + i)] = value;
@@ -50,7 +58,7 @@ impl PythonState {
 }
 
 impl StateTrait for PythonState {
-    fn get_layout(&self) -> &Layout { self.state.get_layout() }
+    fn get_layout(&self) -> &'static Layout { self.state.get_layout() }
 
     fn player_count(&self) -> u8 { self.state.player_count() }
 
@@ -65,9 +73,9 @@ impl StateTrait for PythonState {
     fn get_thief_hex(&self) -> Coord { self.state.get_thief_hex() }
 
     fn set_thief_hex(&mut self, coord: Coord) {
-        self.set_all(self.state.get_thief_hex(), 6, 0);
-        self.set_all(coord, 6, 1);
+        self.set_thief(self.state.get_thief_hex(), 0);
         self.state.set_thief_hex(coord);
+        self.set_thief(coord, 1);
     }
 
     fn hold_discards(&mut self, discards: Vec<(PlayerId, Option<Resources>)>) { self.state.hold_discards(discards) }
@@ -78,6 +86,20 @@ impl StateTrait for PythonState {
 
     fn apply_discards(&mut self) { self.state.apply_discards() }
 
+    fn get_rules(&self) -> Rules { self.state.get_rules() }
+
+    fn set_rules(&mut self, rules: Rules) { self.state.set_rules(rules) }
+
+    fn get_turn(&self) -> u32 { self.state.get_turn() }
+
+    fn next_turn(&mut self) { self.state.next_turn() }
+
+    fn set_turn(&mut self, turn: u32) { self.state.set_turn(turn) }
+
+    fn get_ledger(&self) -> &Ledger { self.state.get_ledger() }
+
+    fn get_ledger_mut(&mut self) -> &mut Ledger { self.state.get_ledger_mut() }
+
     // --- player related --- //
 
     fn get_player_hand(&self, player: PlayerId) -> &PlayerHand { self.state.get_player_hand(player) }
@@ -86,6 +108,8 @@ impl StateTrait for PythonState {
 
     fn get_longest_road(&self) -> Option<(PlayerId, u8)> { self.state.get_longest_road() }
 
+    fn set_longest_road(&mut self, player: Option<PlayerId>) { self.state.set_longest_road(player) }
+
     fn reset_longest_road(&mut self, player: PlayerId) { self.state.reset_longest_road(player) }
 
     fn update_longest_road(&mut self, player: PlayerId, root_path: Coord) { self.state.update_longest_road(player, root_path) }
@@ -94,6 +118,8 @@ impl StateTrait for PythonState {
 
     fn update_largest_army(&mut self, player: PlayerId) { self.state.update_largest_army(player) }
 
+    fn set_largest_army(&mut self, player: Option<PlayerId>) { self.state.set_largest_army(player) }
+
     // --- static board --- //
 
     fn set_static_hex(&mut self, coord: Coord, hex: Hex) -> Result<(), Error> {
@@ -107,6 +133,9 @@ impl StateTrait for PythonState {
                     self.set_all(coord, 5, 1);
                 },
             }
+            if coord == self.state.get_thief_hex() {
+                self.set_all(coord, 6, 1);
+            }
         }
         Ok(())
     }
@@ -115,7 +144,9 @@ impl StateTrait for PythonState {
 
     fn set_static_harbor(&mut self, coord: Coord, harbor: Harbor) -> Result<(), Error> {
         self.state.set_static_harbor(coord, harbor)?;
-        self.set_all(coord, 7 + self.player_count + harbor.to_usize(), 1);
+        if harbor != Harbor::None {
+            self.set_all(coord, 7 + self.player_count + harbor.to_usize(), 1);
+        }
         Ok(())
     }
 
@@ -139,6 +170,34 @@ impl StateTrait for PythonState {
 
     fn get_dynamic_intersection(&self, coord: Coord) -> Result<Option<(PlayerId, bool)>, Error> { self.state.get_dynamic_intersection(coord) }
 
+    fn remove_pieces(&mut self, player: PlayerId) {
+        let layout = self.state.get_layout();
+        let paths: Vec<Coord> = layout.paths.iter().copied()
+            .filter(|path| self.state.get_dynamic_path(*path).unwrap() == Some(player))
+            .collect();
+        let intersections: Vec<Coord> = layout.intersections.iter().copied()
+            .filter(|intersection| self.state.get_dynamic_intersection(*intersection).unwrap().is_some_and(|(p, _)| p == player))
+            .collect();
+        self.state.remove_pieces(player);
+        for path in paths {
+            self.set_roll(path, 7, 0, player);
+        }
+        for intersection in intersections {
+            self.set_roll(intersection, 13 + self.player_count, 0, player);
+        }
+    }
+
+    fn get_building_history(&self) -> &BuildingHistory { self.state.get_building_history() }
+
+    fn clone_state(&self) -> State {
+        Box::new(PythonState {
+            format: self.format,
+            player_count: self.player_count,
+            boards: self.boards.clone(),
+            state: self.state.clone_state(),
+        })
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
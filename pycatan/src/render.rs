@@ -0,0 +1,32 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+
+use catan::board::layout::BoardLayout;
+use catan::game::Checkpoint;
+use catan::state::{State, TricellState, diff};
+
+/// Text drawing of the board of a checkpoint from `save_state`, followed by its phase
+#[pyfunction]
+#[pyo3(signature = (checkpoint, layout="standard"))]
+pub fn checkpoint_text(checkpoint: &[u8], layout: &str) -> PyResult<String> {
+    let (checkpoint, state) = restore(checkpoint, layout)?;
+    Ok(format!("{}{:?}\n", state, checkpoint.phase))
+}
+
+/// Boards of two checkpoints from `save_state` side by side with their pieces, followed by every difference of their states,
+/// see `catan::state::diff::side_by_side`, the changed cells being highlighted for a terminal with `color`
+#[pyfunction]
+#[pyo3(signature = (before, after, layout="standard", color=false))]
+pub fn checkpoint_diff(before: &[u8], after: &[u8], layout: &str, color: bool) -> PyResult<String> {
+    let (_, before) = restore(before, layout)?;
+    let (_, after) = restore(after, layout)?;
+    Ok(diff::side_by_side(&before, &after, color))
+}
+
+fn restore(checkpoint: &[u8], layout: &str) -> PyResult<(Checkpoint, State)> {
+    let board = BoardLayout::named(layout).map_err(PyValueError::new_err)?;
+    let checkpoint = Checkpoint::from_bytes(checkpoint).map_err(PyValueError::new_err)?;
+    let state = checkpoint.state.restore::<TricellState>(board.layout)
+        .map_err(|error| PyValueError::new_err(format!("Failed restoring checkpoint: {:?}", error)))?;
+    Ok((checkpoint, state))
+}
@@ -0,0 +1,36 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3::exceptions::PyValueError;
+
+use catan::board::layout::BoardLayout;
+use catan::game::Checkpoint;
+use catan::state::TricellState;
+
+/// Explanation of the decision of a checkpoint from `save_state`, see `catan::rules::why`
+///
+/// The dict gives the `kind` of phase, the `player` to decide, a `summary` of the decision, what leads to the `next` phase,
+/// the categories of action `available` to the player, and the `transitions` out of the kind of phase,
+/// each with its `action` category and the kinds of phase it leads `to`
+#[pyfunction]
+#[pyo3(signature = (checkpoint, layout="standard"))]
+pub fn why(py: Python, checkpoint: &[u8], layout: &str) -> PyResult<PyObject> {
+    let board = BoardLayout::named(layout).map_err(PyValueError::new_err)?;
+    let checkpoint = Checkpoint::from_bytes(checkpoint).map_err(PyValueError::new_err)?;
+    let state = checkpoint.state.restore::<TricellState>(board.layout)
+        .map_err(|error| PyValueError::new_err(format!("Failed restoring checkpoint: {:?}", error)))?;
+    let why = catan::rules::why(&state, &checkpoint.phase);
+    let dict = PyDict::new(py);
+    dict.set_item("kind", format!("{:?}", why.kind))?;
+    dict.set_item("player", why.player.to_u8())?;
+    dict.set_item("summary", why.summary)?;
+    dict.set_item("next", why.next)?;
+    dict.set_item("available", why.available.iter().map(|category| format!("{:?}", category)).collect::<Vec<_>>())?;
+    let transitions = why.transitions.iter().map(|transition| {
+        let dict = PyDict::new(py);
+        dict.set_item("action", format!("{:?}", transition.action))?;
+        dict.set_item("to", transition.to.iter().map(|kind| format!("{:?}", kind)).collect::<Vec<_>>())?;
+        Ok(dict)
+    }).collect::<PyResult<Vec<_>>>()?;
+    dict.set_item("transitions", transitions)?;
+    Ok(dict.into_any().unbind())
+}
@@ -0,0 +1,144 @@
+use ndarray::Array1;
+use numpy::convert::IntoPyArray;
+use numpy::PyArray1;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use pyo3::exceptions::PyValueError;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+
+use catan::game::{Checkpoint, DiceStatistics, Simulator};
+use catan::state::{State, PlayerId, TricellState, StateMaker};
+use catan::player::CatanPlayer;
+use super::{PyCatanObservation, PyObservationFormat, PythonPlayer, PythonState, PlayerResult};
+use super::environment::{to_py_tuple, IllegalActionError};
+
+/// Position of a checkpoint rolled forward and back by Python tree searches, without spawning an environment
+///
+/// Actions are the indices of the observations of the format, `step` pushing the position it leaves
+/// and `undo` popping it back. Dice, cards and stolen resources are drawn from the seeded rng of the simulator.
+#[pyclass(name = "Simulator", unsendable)]
+pub struct PySimulator {
+    simulator: Simulator,
+    format: PyObservationFormat,
+    /// Player of each seat, only used to encode the observations and decode the actions
+    seats: Vec<PythonPlayer>,
+    // The Python players report their observations and results, unused here
+    _observations: Receiver<Option<(u8, PyCatanObservation)>>,
+    _results: Receiver<PlayerResult>,
+}
+
+impl PySimulator {
+    fn new(simulator: Simulator, format: PyObservationFormat) -> PySimulator {
+        let (observation_sender, observations) = channel();
+        let (result_sender, results) = channel();
+        let dice = Arc::new(Mutex::new(DiceStatistics::new()));
+        let seats = (0..simulator.state().player_count()).map(|p| {
+            let (_, action_receiver) = channel();
+            let mut player = PythonPlayer::new(p, format, action_receiver, observation_sender.clone(), result_sender.clone(), dice.clone(), None);
+            player.new_game(PlayerId::from(p), simulator.state());
+            player
+        }).collect();
+        PySimulator { simulator, format, seats, _observations: observations, _results: results }
+    }
+
+    /// Seat of the player to decide, and its legal action mask
+    fn decision(&mut self) -> Option<(PlayerId, Array1<bool>)> {
+        let player = self.simulator.player();
+        if player == PlayerId::NONE {
+            return None;
+        }
+        let legal_actions = self.seats[player.to_usize()].make_legal_actions(self.simulator.phase(), self.simulator.state());
+        Some((player, legal_actions))
+    }
+}
+
+#[pymethods]
+impl PySimulator {
+
+    /// Simulator of a checkpoint from `save_state`, on the board of the format
+    #[new]
+    #[pyo3(signature = (checkpoint, format, seed=0))]
+    fn py_new(checkpoint: &[u8], format: PyObservationFormat, seed: u64) -> PyResult<PySimulator> {
+        let checkpoint = Checkpoint::from_bytes(checkpoint).map_err(PyValueError::new_err)?;
+        checkpoint.check().map_err(PyValueError::new_err)?;
+        let players = checkpoint.state.players.len() as u8;
+        let mut state: State = if format.use_python_state {
+            Box::new(PythonState::new(format.board.layout, players, format))
+        } else {
+            TricellState::new_empty(format.board.layout, players)
+        };
+        checkpoint.state.restore_into(&mut state)
+            .map_err(|error| PyValueError::new_err(format!("Failed restoring checkpoint: {:?}", error)))?;
+        Ok(PySimulator::new(Simulator::new(checkpoint.phase, state, seed), format))
+    }
+
+    /// Seat of the player to decide, None once the game is finished
+    fn player(&self) -> Option<u8> {
+        let player = self.simulator.player();
+        if player == PlayerId::NONE { None } else { Some(player.to_u8()) }
+    }
+
+    fn winner(&self) -> Option<u8> {
+        self.simulator.winner().map(|winner| winner.to_u8())
+    }
+
+    /// Actions stepped and not undone yet
+    fn depth(&self) -> usize {
+        self.simulator.depth()
+    }
+
+    /// Legal action mask of the player to decide, all false once the game is finished
+    fn legal_actions<'py>(&mut self, py: Python<'py>) -> Bound<'py, PyArray1<bool>> {
+        match self.decision() {
+            Some((_, legal_actions)) => legal_actions.into_pyarray(py),
+            None => self.seats[0].make_legal_actions(self.simulator.phase(), self.simulator.state()).into_pyarray(py),
+        }
+    }
+
+    /// Observation `(seat, board, flat, ..., actions, done)` of the player to decide, None once the game is finished
+    fn observation(&mut self, py: Python) -> Option<PyObject> {
+        let (player, legal_actions) = self.decision()?;
+        let observation = self.seats[player.to_usize()].observe(self.simulator.phase(), self.simulator.state(), &legal_actions);
+        Some(to_py_tuple(py, &self.format, false, false, false, Some((player.to_u8(), observation))))
+    }
+
+    /// Plays the action of the player to decide, raising an `IllegalActionError` when it is illegal
+    fn step(&mut self, action: usize) -> PyResult<()> {
+        let (player, legal_actions) = self.decision().ok_or_else(|| PyValueError::new_err("The game is finished"))?;
+        let decoded = self.seats[player.to_usize()].decode_action(action, self.simulator.state())
+            .ok_or_else(|| IllegalActionError::new_err((format!("Action {} out of the action space", action), action, legal_actions.to_vec())))?;
+        if !legal_actions.get(action).copied().unwrap_or(true) {
+            return Err(IllegalActionError::new_err((format!("Illegal action {}", action), action, legal_actions.to_vec())));
+        }
+        self.simulator.apply_action(decoded)
+            .map_err(|error| IllegalActionError::new_err((format!("Illegal action {}: {:?}", action, error), action, legal_actions.to_vec())))?;
+        Ok(())
+    }
+
+    /// Takes back the last action stepped, False when there is none
+    fn undo(&mut self) -> bool {
+        self.simulator.undo_action()
+    }
+
+    /// Victory points of each seat, hidden ones included
+    fn victory_points(&self) -> Vec<u8> {
+        let state = self.simulator.state();
+        (0..state.player_count()).map(|p| state.get_player_total_vp(PlayerId::from(p))).collect()
+    }
+
+    /// Copy of the position as the seat sees it, the hidden hands and development cards of the others being dealt again,
+    /// without the actions to undo
+    #[pyo3(signature = (seat, seed=0))]
+    fn determinized(&self, seat: u8, seed: u64) -> PyResult<PySimulator> {
+        if seat >= self.simulator.state().player_count() {
+            return Err(PyValueError::new_err(format!("No seat {}", seat)));
+        }
+        Ok(PySimulator::new(self.simulator.determinized(PlayerId::from(seat), seed), self.format))
+    }
+
+    /// Checkpoint of the current position, as the bytes of `save_state`
+    fn save_state<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.simulator.checkpoint().to_bytes())
+    }
+}
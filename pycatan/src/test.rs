@@ -0,0 +1,570 @@
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use rand::SeedableRng;
+use rand::seq::IndexedRandom;
+use rand::rngs::SmallRng;
+
+use catan::game::{Game, Action, Error, Notification, Phase, TurnPhase, DevelopmentPhase, Checkpoint, GameEvent, Rules, Simulator, DiceStatistics, legal};
+use catan::state::{State, PlayerId};
+use catan::player::{CatanPlayer, Randomy};
+use catan::replay::Replay;
+use catan::board::setup;
+use catan::board::layout::BoardLayout;
+use catan::utils::{Coord, Resource, Resources};
+
+use super::{PyObservationFormat, PyCatanObservation, PythonState};
+use super::python_player::{PythonPlayer, IllegalActions, RESET_ACTION, relative_event, action_list};
+use super::codec::{write_fixtures, check_fixtures};
+use super::environment::rejection_args;
+
+/// Format of the board, `padding` empty cells around it
+fn format(layout: &str, padding: usize) -> PyObservationFormat {
+   let board = BoardLayout::named(layout).unwrap();
+   let (half_width, half_height) = (board.layout.half_width as usize + padding, board.layout.half_height as usize + padding);
+   PyObservationFormat {
+      x_offset: half_width as isize,
+      y_offset: half_height as isize,
+      width: 2 * half_width + 1,
+      height: 2 * half_height + 1,
+      use_python_state: true,
+      include_hidden: false,
+      include_building_age: false,
+      include_deck: false,
+      include_global: false,
+      trade_bundles: false,
+      include_stage: false,
+      board,
+      audit: false,
+      include_graph: false,
+      include_tokens: false,
+      include_beliefs: false,
+      include_opponent_model: false,
+      include_played_development: false,
+      settlement_reach: None,
+      action_priors: None,
+      include_card_counts: false,
+      include_trade_answers: false,
+   }
+}
+
+/// Asserts the boards kept up to date by the state are the ones rebuilt from scratch
+fn assert_boards_match(format: PyObservationFormat, state: &State) {
+   let python_state = state.as_any().downcast_ref::<PythonState>().expect("Not a Python state");
+   for p in 0..state.player_count() {
+      let player = PlayerId::from(p);
+      assert_eq!(
+         python_state.boards[p as usize], PyCatanObservation::generate_board(format, &state.view_for(player)),
+         "Board of {:?} at turn {}", player, state.get_turn(),
+      );
+   }
+}
+
+/// Player checking the boards before each of its decisions
+struct BoardChecker {
+   player: Box<dyn CatanPlayer>,
+   format: PyObservationFormat,
+}
+
+impl CatanPlayer for BoardChecker {
+   fn new_game(&mut self, position: PlayerId, state: &State) {
+      assert_boards_match(self.format, state);
+      self.player.new_game(position, state);
+   }
+
+   fn pick_action(&mut self, phase: &Phase, state: &State) -> Action {
+      assert_boards_match(self.format, state);
+      self.player.pick_action(phase, state)
+   }
+
+   fn bad_action(&mut self, error: Error) {
+      self.player.bad_action(error);
+   }
+
+   fn notify(&mut self, notification: &Notification) {
+      self.player.notify(notification);
+   }
+
+   fn results(&mut self, state: &State, winner: PlayerId) {
+      assert_boards_match(self.format, state);
+      self.player.results(state, winner);
+   }
+}
+
+type ObservationReceiver = Receiver<Option<(u8, PyCatanObservation)>>;
+
+/// Python player of the format, with the sender of its actions and the receiver of its observations
+fn python_player(format: PyObservationFormat, turn_budget: Option<Duration>) -> (PythonPlayer, Sender<(u32, u16)>, ObservationReceiver) {
+   let (action_sender, action_receiver) = channel();
+   let (observation_sender, observation_receiver) = channel();
+   let (result_sender, _) = channel();
+   let player = PythonPlayer::new(0, format, action_receiver, observation_sender, result_sender, Arc::new(Mutex::new(DiceStatistics::new())), turn_budget);
+   (player, action_sender, observation_receiver)
+}
+
+/// Plays a game of the Python player against two random players, the Python side answering each observation with `answer`,
+/// returning how the game ended and its replay
+fn play_python_game(format: PyObservationFormat, seed: u64, mut answer: impl FnMut(&PyCatanObservation) -> u16) -> (Notification, Replay) {
+   let (action_sender, action_receiver) = channel();
+   let (observation_sender, observations) = channel();
+   let (result_sender, _results) = channel();
+   let player = PythonPlayer::new(0, format, action_receiver, observation_sender, result_sender, Arc::new(Mutex::new(DiceStatistics::new())), Some(Duration::from_secs(60)));
+   let game = thread::spawn(move || {
+      let mut game = Game::new();
+      game.board = format.board;
+      game.record_replays = true;
+      game.add_player(Box::new(Randomy::new_seeded_player(seed)));
+      game.add_player(Box::new(Randomy::new_seeded_player(seed + 1)));
+      game.add_player(Box::new(player));
+      let mut rng = SmallRng::seed_from_u64(seed);
+      let mut state: State = Box::new(PythonState::new(format.board.layout, 3, format));
+      let notification = game.setup_and_play_on(&mut rng, &mut state);
+      (notification, game.replay.take().expect("No replay recorded"))
+   });
+   while let Ok(Some((_, observation))) = observations.recv() {
+      // Sent to a finished game, the last answer may not be read
+      let _ = action_sender.send((observation.sequence, answer(&observation)));
+   }
+   game.join().expect("Game thread failed")
+}
+
+/// Answers picking a legal action with the rng
+fn random_answer(seed: u64) -> impl FnMut(&PyCatanObservation) -> u16 {
+   let mut rng = SmallRng::seed_from_u64(seed);
+   move |observation| {
+      let legal: Vec<usize> = observation.actions.iter().enumerate().filter(|(_, legal)| **legal).map(|(i, _)| i).collect();
+      *legal.choose(&mut rng).unwrap() as u16
+   }
+}
+
+/// Simulator of a game of 3 players on the format, played at random for some actions
+fn random_position(format: PyObservationFormat, seed: u64, actions: usize) -> Simulator {
+   let mut rng = SmallRng::seed_from_u64(seed);
+   let mut state = PythonState::new(format.board.layout, 3, format);
+   setup::random_default_setup_existing_state(&mut rng, &mut state);
+   let mut simulator = Simulator::new(Phase::START_GAME, Box::new(state), seed);
+   for _ in 0..actions {
+      let legal_actions: Vec<Action> = simulator.legal_actions().into_iter().filter(|action| *action != Action::Resign).collect();
+      simulator.apply_action(*legal_actions.choose(&mut rng).unwrap()).unwrap();
+   }
+   simulator
+}
+
+#[test]
+fn incremental_boards_match_rebuilds() {
+   for (layout, padding, players) in [("standard", 0, 3), ("standard", 2, 4), ("extension", 0, 5)] {
+      let format = format(layout, padding);
+      for seed in 0..2 {
+         let mut game = Game::new();
+         game.board = format.board;
+         for p in 0..players {
+            game.add_player(Box::new(BoardChecker { player: Box::new(Randomy::new_seeded_player(seed + p)), format }));
+         }
+         let mut rng = SmallRng::seed_from_u64(seed);
+         let mut state: State = Box::new(PythonState::new(format.board.layout, players as u8, format));
+         let notification = game.setup_and_play_on(&mut rng, &mut state);
+         assert_ne!(notification, Notification::GameFinished { winner: PlayerId::NONE });
+
+         // Boards restored from a checkpoint are the ones rebuilt too
+         let checkpoint = Checkpoint::new(Phase::START_GAME, &state);
+         let mut restored: State = Box::new(PythonState::new(format.board.layout, players as u8, format));
+         checkpoint.state.restore_into(&mut restored).unwrap();
+         assert_boards_match(format, &restored);
+      }
+   }
+}
+
+#[test]
+fn simulator_copies_boards() {
+   let format = format("standard", 0);
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = PythonState::new(format.board.layout, 3, format);
+   setup::random_default_setup_existing_state(&mut rng, &mut state);
+   let mut simulator = Simulator::new(Phase::START_GAME, Box::new(state), 0);
+   for _ in 0..300 {
+      let action = *simulator.legal_actions().choose(&mut rng).unwrap();
+      simulator.apply_action(action).unwrap();
+   }
+   // Undone positions keep the boards they were left with
+   while simulator.undo_action() {
+      assert_boards_match(format, simulator.state());
+   }
+}
+
+#[test]
+fn belief_features_are_named() {
+   for players in [3, 4] {
+      let format = PyObservationFormat { include_beliefs: true, ..format("standard", 0) };
+      let observation = PyCatanObservation::sample(format, players, 0);
+      let names = format.flat_names(players as u8);
+      assert_eq!(observation.flat.len(), names.len());
+      assert_eq!(names.len(), 49 + 9 * players + 10 * (players - 1));
+      assert_eq!(names[names.len() - 1], format!("p+{}_percent_wool", players - 1));
+      let format = PyObservationFormat { include_opponent_model: true, ..format };
+      let observation = PyCatanObservation::sample(format, players, 0);
+      assert_eq!(observation.flat.len(), format.flat_names(players as u8).len());
+      assert_eq!(format.flat_size(players as u8), 49 + 9 * players + 17 * (players - 1));
+   }
+}
+
+#[test]
+fn card_count_features_bound_the_hands() {
+   let format = PyObservationFormat { include_beliefs: true, include_opponent_model: true, include_card_counts: true, ..format("standard", 0) };
+   let names = format.flat_names(3);
+   assert_eq!(names.len(), format.flat_size(3));
+   assert_eq!(names[names.len() - 30], "p+1_cards_min_brick");
+   assert_eq!(names[names.len() - 1], "p+2_cards_expected_wool");
+   for seed in 0..3 {
+      let simulator = random_position(format, seed, 300);
+      let player = PlayerId::from(0u8);
+      let observation = PyCatanObservation::new_array(format, player, simulator.state(), simulator.phase(), Default::default());
+      let counts = &observation.flat.as_slice().unwrap()[names.len() - 30..];
+      for opp in 1..3u8 {
+         let hand = simulator.state().get_player_hand(PlayerId::from(opp)).resources;
+         let block = &counts[(opp as usize - 1) * 15..opp as usize * 15];
+         for res in Resource::ALL.iter() {
+            let i = res.to_usize();
+            assert!(block[i] <= hand[*res] as i32 && hand[*res] as i32 <= block[5 + i], "{:?} counted as {:?}", hand, block);
+            assert!(100 * block[i] <= block[10 + i] && block[10 + i] <= 100 * block[5 + i]);
+         }
+         assert!((block[10..].iter().sum::<i32>() - 100 * hand.total() as i32).abs() <= 5);
+      }
+   }
+}
+
+#[test]
+fn trade_answer_features_count_the_turn() {
+   let format = PyObservationFormat { include_card_counts: true, include_trade_answers: true, ..format("standard", 0) };
+   let names = format.flat_names(3);
+   assert_eq!(names.len(), format.flat_size(3));
+   assert_eq!(names[names.len() - 9..names.len() - 6], ["turn_rejected_self", "turn_rejected_p+1", "turn_rejected_p+2"]);
+   let mut simulator = random_position(format, 4, 200);
+   let mut rng = SmallRng::seed_from_u64(4);
+   // Answers are only counted from the start of a turn
+   let (mut turn, mut answers, mut answered) = (simulator.state().get_turn(), [[0; 3]; 3], 0);
+   let mut counting = false;
+   for _ in 0..400 {
+      let legal_actions: Vec<Action> = simulator.legal_actions().into_iter().filter(|action| *action != Action::Resign).collect();
+      let Some(action) = legal_actions.choose(&mut rng).copied() else { break };
+      let player = simulator.phase().player();
+      // An accepted counter offer is the answer of the opponent who made it
+      let partner = match simulator.phase() {
+         Phase::Turn { turn_phase: TurnPhase::Trade(offer), .. } => offer.partner,
+         _ => player,
+      };
+      simulator.apply_action(action).unwrap();
+      if simulator.state().get_turn() != turn {
+         (turn, answers, counting) = (simulator.state().get_turn(), [[0; 3]; 3], true);
+      }
+      let (answer, responder) = match action {
+         Action::RejectTrade => (0, player),
+         Action::CounterTrade { .. } => (1, player),
+         Action::AcceptTrade => (2, partner),
+         _ => continue,
+      };
+      if !counting {
+         continue;
+      }
+      answers[answer][responder.to_usize()] += 1;
+      answered += 1;
+      // Counted relative to each observer
+      for observer in 0..3u8 {
+         let observation = PyCatanObservation::new_array(format, PlayerId::from(observer), simulator.state(), simulator.phase(), Default::default());
+         let cells = &observation.flat.as_slice().unwrap()[names.len() - 9..];
+         for (kind, counts) in answers.iter().enumerate() {
+            for p in 0..3 {
+               assert_eq!(cells[kind * 3 + (p + 3 - observer as usize) % 3], counts[p]);
+            }
+         }
+      }
+   }
+   assert!(answered > 0);
+}
+
+#[test]
+fn played_development_features_are_named() {
+   for players in [3, 4] {
+      let format = PyObservationFormat { include_played_development: true, ..format("standard", 0) };
+      let observation = PyCatanObservation::sample(format, players, 0);
+      let names = format.flat_names(players as u8);
+      assert_eq!(observation.flat.len(), names.len());
+      assert_eq!(names[27 + 8], "p+1_played_road_building");
+      assert_eq!(names[27 + 11 * (players - 1)], "bank_brick");
+   }
+}
+
+#[test]
+fn settlement_reach_channel() {
+   let format = PyObservationFormat { settlement_reach: Some(2), ..format("standard", 0) };
+   let mut rng = SmallRng::seed_from_u64(0);
+   let mut state = PythonState::new(format.board.layout, 3, format);
+   setup::random_default_setup_existing_state(&mut rng, &mut state);
+   let mut simulator = Simulator::new(Phase::START_GAME, Box::new(state), 0);
+   for _ in 0..30 {
+      let action = *simulator.legal_actions().choose(&mut rng).unwrap();
+      simulator.apply_action(action).unwrap();
+   }
+   let player = PlayerId::from(0u8);
+   let observation = PyCatanObservation::new_array(format, player, simulator.state(), simulator.phase(), Default::default());
+   assert_eq!(format.board_channel_names(3).last().unwrap(), "settlement_reach");
+   let channel = observation.board.index_axis(ndarray::Axis(2), format.board_channels(3) - 1).to_owned();
+   let reach = legal::settlement_reach(player, simulator.state(), 2);
+   assert!(!reach.is_empty());
+   assert_eq!(channel.iter().filter(|cell| **cell > 0).count(), reach.len());
+   for (intersection, roads) in reach {
+      assert_eq!(channel[format.map(intersection)], 3 - roads as i32);
+   }
+}
+
+#[test]
+fn building_age_channel() {
+   let format = PyObservationFormat { include_building_age: true, settlement_reach: Some(2), ..format("standard", 0) };
+   let simulator = random_position(format, 0, 300);
+   let (phase, state) = (simulator.phase(), simulator.state());
+   let player = PlayerId::from(0u8);
+   let observation = PyCatanObservation::new_array(format, player, state, phase, Default::default());
+   // The age channel comes right before the settlement reach one
+   let names = format.board_channel_names(3);
+   assert_eq!(names[names.len() - 2], "building_age");
+   let channel = observation.board.index_axis(ndarray::Axis(2), format.board_channels(3) - 2).to_owned();
+   let settlements: Vec<_> = state.get_building_history().placements().iter().filter(|placement| !placement.is_city).collect();
+   assert!(state.get_turn() > 0 && settlements.len() >= 6);
+   assert_eq!(channel.iter().filter(|cell| **cell > 0).count(), settlements.len());
+   for placement in settlements {
+      assert_eq!(channel[format.map(placement.intersection)], (state.get_turn() - placement.turn + 1) as i32);
+   }
+   // Settled during the initial placement, the first settlements are the oldest
+   let first = state.get_building_history().placements()[0];
+   assert_eq!(channel[format.map(first.intersection)], channel.iter().copied().max().unwrap());
+   let python_state = state.as_any().downcast_ref::<PythonState>().unwrap();
+   let python_observation = PyCatanObservation::new_python_array(format, player, python_state, state, phase, Default::default());
+   assert_eq!(python_observation.board, observation.board);
+}
+
+#[test]
+fn audit_leaves_hidden_arrays_out() {
+   let format = PyObservationFormat { audit: true, include_hidden: true, include_global: true, include_deck: true, ..format("standard", 0) };
+   let mut rng = SmallRng::seed_from_u64(1);
+   let mut simulator = random_position(format, 1, 0);
+   let (mut player, actions, observations) = python_player(format, Some(Duration::from_secs(60)));
+   for sequence in 1..=150 {
+      let (phase, state) = (*simulator.phase(), simulator.state());
+      player.new_game(phase.player(), state);
+      let legal_actions: Vec<Action> = simulator.legal_actions().into_iter().filter(|action| *action != Action::Resign).collect();
+      let action = *legal_actions.choose(&mut rng).unwrap();
+      actions.send((sequence, player.encode_action(action, state).unwrap() as u16)).unwrap();
+      assert_eq!(player.pick_action(&phase, state), action);
+      let (_, observation) = observations.recv().unwrap().unwrap();
+      assert!(observation.hidden.is_some() && observation.global.is_some());
+      simulator.apply_action(action).unwrap();
+   }
+}
+
+#[test]
+fn action_list_appends_new_actions() {
+   let format = format("standard", 0);
+   let state: State = Box::new(PythonState::new(format.board.layout, 3, format));
+   let actions = action_list(format, PlayerId::FIRST, &state);
+   let bundled = action_list(PyObservationFormat { trade_bundles: true, ..format }, PlayerId::FIRST, &state);
+   // Trade bundles follow the Keep actions, the actions added after them keeping their order
+   let keep_end = actions.iter().rposition(|action| matches!(action, Action::Keep { .. })).unwrap() + 1;
+   assert!(matches!(actions[keep_end], Action::ProposeTrade { .. }));
+   assert!(matches!(bundled[keep_end], Action::TradeBankBundle { .. }));
+   let unbundled: Vec<Action> = bundled.iter().copied().filter(|action| !matches!(action, Action::TradeBankBundle { .. })).collect();
+   assert_eq!(unbundled, actions);
+   assert!(matches!(actions[keep_end + 82], Action::Discard { resource: Resource::Brick }));
+   let withdraw = actions.len() - 61;
+   assert!(matches!(actions[withdraw - 1], Action::ReserveCity { .. }) && matches!(actions[withdraw], Action::WithdrawTrade));
+   assert!(matches!(actions.last(), Some(Action::ReviseTrade { .. })) && matches!(bundled.last(), Some(Action::ReviseTrade { .. })));
+}
+
+#[test]
+fn codec_fixtures_validate() {
+   let format = format("standard", 1);
+   let fixtures = write_fixtures(format, 3, 1, 5, 0).unwrap();
+   assert_eq!(check_fixtures(&fixtures, format).unwrap(), Vec::<String>::new());
+   // An implementation disagreeing on a feature is told which one
+   let mut other: serde_json::Value = serde_json::from_str(&fixtures).unwrap();
+   let bank = format.flat_names(3).iter().position(|name| name == "bank_brick").unwrap();
+   let value = other["observations"][2]["flat"][bank].as_i64().unwrap();
+   other["observations"][2]["flat"][bank] = (value + 1).into();
+   assert_eq!(check_fixtures(&other.to_string(), format).unwrap(), vec!["Vector 2: flat differs at [\"bank_brick\"]"]);
+   let played = PyObservationFormat { include_played_development: true, ..format };
+   assert_eq!(check_fixtures(&fixtures, played).unwrap(), vec!["Flat or board channel names differ from the ones of the format"]);
+}
+
+#[test]
+fn events_are_relative() {
+   let format = format("standard", 0);
+   let mut state: State = Box::new(PythonState::new(format.board.layout, 3, format));
+   state.set_rules(Rules::STANDARD);
+   let seen = |event: GameEvent| relative_event(&event, PlayerId::from(1u8), &state);
+   let resources = vec![Resources::new_one(Resource::Ore, 1), Resources::ZERO, Resources::new_one(Resource::Wool, 2)];
+   assert_eq!(
+      seen(GameEvent::ResourcesDistributed { roll: 6, resources: resources.clone() }),
+      GameEvent::ResourcesDistributed { roll: 6, resources: vec![resources[1], resources[2], resources[0]] },
+   );
+   assert_eq!(
+      seen(GameEvent::CardStolen { thief: PlayerId::from(0u8), victim: PlayerId::from(2u8), resource: Some(Resource::Ore) }),
+      GameEvent::CardStolen { thief: PlayerId::from(2u8), victim: PlayerId::from(1u8), resource: None },
+   );
+   assert_eq!(
+      seen(GameEvent::TradeExecuted { player: PlayerId::from(1u8), partner: None, given: resources[2], received: resources[0] }),
+      GameEvent::TradeExecuted { player: PlayerId::from(0u8), partner: None, given: resources[2], received: resources[0] },
+   );
+   assert_eq!(
+      seen(GameEvent::RobberMoved { player: PlayerId::from(2u8), hex: Coord::new(0, 0) }),
+      GameEvent::RobberMoved { player: PlayerId::from(1u8), hex: Coord::new(0, 0) },
+   );
+}
+
+#[test]
+fn action_priors_follow_the_mask() {
+   let format = PyObservationFormat { action_priors: Some(10.0), ..format("standard", 0) };
+   let mut observed = 0;
+   let (notification, _) = play_python_game(format, 2, |observation| {
+      let priors = observation.priors.as_ref().expect("No action priors");
+      assert_eq!(priors.len(), observation.actions.len());
+      assert!(priors.iter().zip(observation.actions.iter()).all(|(prior, legal)| *legal || *prior == 0.0));
+      assert!((priors.sum() - 1.0).abs() < 1e-4, "Priors summing to {}", priors.sum());
+      observed += 1;
+      // Playing the likeliest action plays as the heuristic player
+      priors.iter().enumerate().max_by(|(_, a), (_, b)| a.total_cmp(b)).unwrap().0 as u16
+   });
+   assert!(matches!(notification, Notification::GameFinished { winner: _ }) && observed > 0);
+}
+
+#[test]
+fn actions_out_of_the_action_space_are_refused() {
+   let format = format("standard", 0);
+   let simulator = random_position(format, 2, 40);
+   let (phase, state) = (*simulator.phase(), simulator.state());
+   let action = simulator.legal_actions().into_iter().find(|action| *action != Action::Resign).unwrap();
+   for illegal_actions in [IllegalActions::Retry, IllegalActions::Resolve] {
+      let (mut player, actions, observations) = python_player(format, Some(Duration::from_secs(60)));
+      player.illegal_actions = illegal_actions;
+      player.new_game(phase.player(), state);
+      let out = (player.encode_action(action, state).unwrap() + 10_000) as u16;
+      assert_eq!(player.decode_action(out as usize, state), None);
+      actions.send((1, out)).unwrap();
+      actions.send((2, player.encode_action(action, state).unwrap() as u16)).unwrap();
+      let picked = player.pick_action(&phase, state);
+      let (_, observation) = observations.recv().unwrap().unwrap();
+      assert_eq!(observation.sequence, 1);
+      match illegal_actions {
+         // Asked again under a new sequence number
+         IllegalActions::Retry => {
+            assert_eq!(picked, action);
+            assert_eq!(observations.recv().unwrap().unwrap().1.sequence, 2);
+         }
+         // Never played as a discard keeping the whole hand, the fallback action is
+         _ => assert_eq!(picked, player.fallback_action(&observation.actions)),
+      }
+   }
+}
+
+#[test]
+fn reset_action_ends_the_game() {
+   let format = format("standard", 0);
+   let mut answer = random_answer(0);
+   let mut observed = 0;
+   let (notification, replay) = play_python_game(format, 0, |observation| {
+      observed += 1;
+      if observed == 20 { RESET_ACTION } else { answer(observation) }
+   });
+   assert_eq!(notification, Notification::GameFinished { winner: PlayerId::NONE });
+   assert_eq!(replay.winner, None);
+   assert_eq!(observed, 20);
+}
+
+#[test]
+fn seeded_python_games_repeat() {
+   let format = format("standard", 0);
+   let (notification, replay) = play_python_game(format, 4, random_answer(4));
+   assert_ne!(notification, Notification::GameFinished { winner: PlayerId::NONE });
+   // The seed of a reset and the actions of the Python side decide the whole game
+   assert_eq!(play_python_game(format, 4, random_answer(4)), (notification, replay.clone()));
+   assert_ne!(play_python_game(format, 5, random_answer(4)).1.plies, replay.plies);
+}
+
+#[test]
+fn trivial_discards_are_resolved() {
+   let format = format("standard", 0);
+   let mut state: State = Box::new(PythonState::new(format.board.layout, 3, format));
+   state.set_rules(Rules::STANDARD);
+   let discarder = PlayerId::from(1u8);
+   let phase = Phase::Turn { player: PlayerId::FIRST, turn_phase: TurnPhase::Discard(discarder), development_phase: DevelopmentPhase::Ready };
+   let (mut player, _actions, observations) = python_player(format, Some(Duration::from_secs(60)));
+   player.auto_resolve = true;
+   player.new_game(discarder, &state);
+   state.get_player_hand_mut(discarder).resources = Resources::new_one(Resource::Ore, 9);
+   // Several Keep and Discard actions are legal, all of them keeping 5 ore
+   assert!(player.make_legal_actions(&phase, &state).iter().filter(|legal| **legal).count() > 1);
+   assert_eq!(player.pick_action(&phase, &state), Action::Keep { resources: Resources::new_one(Resource::Ore, 5) });
+   assert!(observations.try_recv().is_err());
+   state.get_player_hand_mut(discarder).resources += Resources::new_one(Resource::Wool, 1);
+   assert_eq!(player.trivial_discard(&phase, &state), None);
+}
+
+#[test]
+fn illegal_actions_are_retried_raised_or_resolved() {
+   let format = format("standard", 0);
+   let simulator = random_position(format, 3, 40);
+   let (phase, state) = (*simulator.phase(), simulator.state());
+   let action = simulator.legal_actions().into_iter().find(|action| *action != Action::Resign).unwrap();
+   for illegal_actions in [IllegalActions::Retry, IllegalActions::Raise, IllegalActions::Resolve] {
+      let (mut player, actions, observations) = python_player(format, Some(Duration::from_secs(60)));
+      player.illegal_actions = illegal_actions;
+      player.new_game(phase.player(), state);
+      let mask = player.make_legal_actions(&phase, state);
+      let illegal = mask.iter().position(|legal| !legal).unwrap();
+      actions.send((1, illegal as u16)).unwrap();
+      actions.send((1, player.encode_action(action, state).unwrap() as u16)).unwrap();
+      let picked = player.pick_action(&phase, state);
+      let observation = observations.recv().unwrap();
+      assert_eq!(rejection_args(&observation), None);
+      match illegal_actions {
+         // Played, for the game to refuse it
+         IllegalActions::Retry => assert_eq!(picked, player.decode_action(illegal, state).unwrap()),
+         // Sent back with the observation, still answered by the next action
+         IllegalActions::Raise => {
+            assert_eq!(picked, action);
+            let rejection = observations.recv().unwrap();
+            assert_eq!(rejection.as_ref().unwrap().1.sequence, 1);
+            assert_eq!(rejection_args(&rejection), Some((format!("Illegal action {}", illegal), illegal as u16, mask.to_vec())));
+         }
+         IllegalActions::Resolve => {
+            assert_eq!(picked, player.fallback_action(&mask));
+            assert!(mask[player.encode_action(picked, state).unwrap()]);
+         }
+      }
+      assert!(observations.try_recv().is_err());
+   }
+}
+
+#[test]
+fn turn_budget_falls_back_and_drops_late_actions() {
+   let format = format("standard", 0);
+   let simulator = random_position(format, 3, 40);
+   let (phase, state) = (*simulator.phase(), simulator.state());
+   let action = simulator.legal_actions().into_iter().find(|action| *action != Action::Resign).unwrap();
+   let (mut player, actions, observations) = python_player(format, Some(Duration::from_millis(50)));
+   player.new_game(phase.player(), state);
+   let mask = player.make_legal_actions(&phase, state);
+   // Unanswered, the decision is played by the fallback action once the budget is spent
+   assert_eq!(player.pick_action(&phase, state), player.fallback_action(&mask));
+   let (_, observation) = observations.recv().unwrap().unwrap();
+   assert!(observation.budget.is_some_and(|budget| budget > 0.0 && budget <= 0.05));
+   // The rest of the turn is played without asking
+   assert_eq!(player.pick_action(&phase, state), player.fallback_action(&mask));
+   assert!(observations.try_recv().is_err());
+   // Answering the observation that timed out, a late action is dropped in the next turn
+   player.notify(&Notification::ActionPlayed { by: phase.player(), action: Action::EndTurn });
+   let late = (0..mask.len()).find(|i| mask[*i] && player.decode_action(*i, state) != Some(action)).unwrap();
+   actions.send((observation.sequence, late as u16)).unwrap();
+   actions.send((observation.sequence + 1, player.encode_action(action, state).unwrap() as u16)).unwrap();
+   assert_eq!(player.pick_action(&phase, state), action);
+   assert_eq!(observations.recv().unwrap().unwrap().1.sequence, observation.sequence + 1);
+}
@@ -0,0 +1,63 @@
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use core_affinity::CoreId;
+
+/// Cores and priority of the game threads of an environment, reducing their jitter on busy training hosts
+///
+/// The game thread `i` is pinned to the core `cores[i % cores.len()]`, and every game thread runs at the niceness `nice`,
+/// from -20, the highest priority, to 19. Priorities are only set on Linux, and raising them usually needs privileges.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub(crate) struct ThreadPlacement {
+    cores: Vec<CoreId>,
+    nice: Option<i32>,
+}
+
+impl ThreadPlacement {
+    pub(crate) fn new(cores: Option<Vec<usize>>, nice: Option<i32>) -> PyResult<ThreadPlacement> {
+        let cores = cores.unwrap_or_default();
+        if !cores.is_empty() {
+            let available = core_affinity::get_core_ids().unwrap_or_default();
+            if let Some(core) = cores.iter().find(|core| !available.contains(&CoreId { id: **core })) {
+                return Err(PyValueError::new_err(format!("Core {} isn't among the {} cores available", core, available.len())));
+            }
+        }
+        if let Some(nice) = nice {
+            if !(-20..=19).contains(&nice) {
+                return Err(PyValueError::new_err(format!("Niceness {} out of -20 to 19", nice)));
+            }
+        }
+        Ok(ThreadPlacement {
+            cores: cores.into_iter().map(|id| CoreId { id }).collect(),
+            nice,
+        })
+    }
+
+    /// Pins the calling thread, the game thread `i`, and sets its priority
+    pub(crate) fn apply(&self, i: usize) -> Result<(), String> {
+        if !self.cores.is_empty() {
+            let core = self.cores[i % self.cores.len()];
+            if !core_affinity::set_for_current(core) {
+                return Err(format!("Failed pinning game thread {} to core {}", i, core.id));
+            }
+        }
+        if let Some(nice) = self.nice {
+            set_niceness(nice).map_err(|error| format!("Failed setting the niceness of game thread {} to {}: {}", i, nice, error))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_niceness(nice: i32) -> Result<(), String> {
+    // Linux threads have their own niceness, 0 selecting the calling thread
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().to_string())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_niceness(_nice: i32) -> Result<(), String> {
+    Err("thread priorities are only set on Linux".to_string())
+}
@@ -0,0 +1,216 @@
+use ndarray::Array1;
+
+use catan::state::{VisibleState, PlayerId};
+use catan::utils::{Hex, LandHex, Harbor, Resource, DevelopmentCard};
+use catan::game::{Phase, TurnPhase, DecisionStage};
+use catan::board::Layout;
+use catan::player::relative;
+
+use super::py_observation_format::{resource_name, development_name};
+
+/// Words of the vocabulary, before the resources, development cards, numbers, players, stages, counts and board positions
+const WORDS: [&str; 27] = [
+    "<pad>", "<bos>", "<eos>",
+    "hex", "thief", "harbor", "road", "settlement", "city", "player", "bank", "deck", "stage", "trade", "give", "receive",
+    "desert", "generic",
+    "resource_cards", "development_cards", "road_pieces", "settlement_pieces", "city_pieces", "knights", "victory_points", "longest_road", "largest_army",
+];
+
+/// Largest count with its own token, larger counts sharing it
+const MAX_COUNT: usize = 31;
+
+/// Vocabulary of the token observations of games of a number of players on a layout
+///
+/// The game is a sequence of entities, each a word followed by its attributes:
+/// `hex <hex> <resource> <number>` or `hex <hex> desert`, `thief <hex>`, `harbor <intersection> <resource or generic>`,
+/// `settlement <intersection> <player>`, `city <intersection> <player>`, `road <path> <player>`,
+/// `player <player>` followed by pairs of an attribute and its count, `bank` followed by the count of each resource,
+/// `deck <count>`, `stage <stage>`, and `trade <proposer> <partner>` followed by `give` and `receive` and their resource counts.
+/// Players are relative to the observer, `self` being the observer.
+/// The sequence starts with `<bos>`, ends with `<eos>` and is padded with `<pad>`, whose token is 0.
+pub(crate) struct Vocabulary {
+    player_count: u8,
+    hexes: usize,
+    paths: usize,
+    intersections: usize,
+}
+
+impl Vocabulary {
+    pub fn new(layout: &Layout, player_count: u8) -> Vocabulary {
+        Vocabulary {
+            player_count,
+            hexes: layout.hexes.len(),
+            paths: layout.paths.len(),
+            intersections: layout.intersections.len(),
+        }
+    }
+
+    /// Name of each token
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = WORDS.iter().map(|word| word.to_string()).collect();
+        names.extend(Resource::ALL.iter().map(|res| resource_name(*res).to_string()));
+        names.extend(DevelopmentCard::ALL.iter().map(|card| development_name(*card).to_string()));
+        names.extend((2..=12).map(|number| format!("number_{}", number)));
+        names.extend((0..self.player_count).map(|offset| if offset == 0 { "self".to_string() } else { format!("p+{}", offset) }));
+        names.extend(DecisionStage::ALL.iter().map(|stage| format!("{:?}", stage)));
+        names.extend((0..=MAX_COUNT).map(|count| count.to_string()));
+        names.extend((0..self.hexes).map(|i| format!("hex_{}", i)));
+        names.extend((0..self.paths).map(|i| format!("path_{}", i)));
+        names.extend((0..self.intersections).map(|i| format!("intersection_{}", i)));
+        debug_assert_eq!(names.len(), self.size());
+        names
+    }
+
+    pub fn size(&self) -> usize {
+        self.offset_intersections() + self.intersections
+    }
+
+    /// Length of the token sequences, long enough for any game
+    pub fn max_length(&self) -> usize {
+        let players = self.player_count as usize;
+        let board = 4 * self.hexes + 2 + 3 * self.intersections + 3 * self.intersections + 3 * self.paths;
+        let hands = (2 + 2 * (Resource::COUNT + DevelopmentCard::COUNT + 5) + 2) + (players - 1) * (2 + 2 * 7 + 2);
+        let rest = (1 + 2 * Resource::COUNT) + 2 + 2 + (3 + 2 * (1 + 2 * Resource::COUNT));
+        2 + board + hands + rest
+    }
+
+    fn word(&self, word: &str) -> i32 {
+        WORDS.iter().position(|w| *w == word).expect("Word out of the vocabulary") as i32
+    }
+
+    fn resource(&self, resource: Resource) -> i32 {
+        (WORDS.len() + resource.to_usize()) as i32
+    }
+
+    fn development(&self, card: DevelopmentCard) -> i32 {
+        (WORDS.len() + Resource::COUNT + card.to_usize()) as i32
+    }
+
+    fn number(&self, number: u8) -> i32 {
+        (WORDS.len() + Resource::COUNT + DevelopmentCard::COUNT) as i32 + number as i32 - 2
+    }
+
+    fn player(&self, observer: PlayerId, player: PlayerId) -> i32 {
+        let offset = relative::player_id_to_relative(observer, player, self.player_count).to_usize();
+        (WORDS.len() + Resource::COUNT + DevelopmentCard::COUNT + 11 + offset) as i32
+    }
+
+    fn stage(&self, stage: DecisionStage) -> i32 {
+        (WORDS.len() + Resource::COUNT + DevelopmentCard::COUNT + 11 + self.player_count as usize + stage as usize) as i32
+    }
+
+    fn offset_counts(&self) -> usize {
+        WORDS.len() + Resource::COUNT + DevelopmentCard::COUNT + 11 + self.player_count as usize + DecisionStage::COUNT
+    }
+
+    fn count(&self, count: impl Into<i32>) -> i32 {
+        (self.offset_counts() + (count.into().max(0) as usize).min(MAX_COUNT)) as i32
+    }
+
+    fn hex(&self, i: usize) -> i32 {
+        (self.offset_counts() + MAX_COUNT + 1 + i) as i32
+    }
+
+    fn path(&self, i: usize) -> i32 {
+        (self.offset_counts() + MAX_COUNT + 1 + self.hexes + i) as i32
+    }
+
+    fn offset_intersections(&self) -> usize {
+        self.offset_counts() + MAX_COUNT + 1 + self.hexes + self.paths
+    }
+
+    fn intersection(&self, i: usize) -> i32 {
+        (self.offset_intersections() + i) as i32
+    }
+
+    /// Tokens of the game seen by a player, padded to [max_length](Vocabulary::max_length)
+    pub fn tokens(&self, view: &VisibleState, phase: &Phase) -> Array1<i32> {
+        let observer = view.player();
+        let layout = view.get_layout();
+        let mut tokens = vec![self.word("<bos>")];
+        // ## Board
+        for (i, coord) in layout.hexes.iter().enumerate() {
+            if let Hex::Land(hex) = view.get_static_hex(*coord).unwrap() {
+                tokens.extend([self.word("hex"), self.hex(i)]);
+                match hex {
+                    LandHex::Desert => tokens.push(self.word("desert")),
+                    LandHex::Prod(res, num) => tokens.extend([self.resource(res), self.number(num)]),
+                }
+            }
+        }
+        if let Some(i) = layout.hexes.iter().position(|coord| *coord == view.get_thief_hex()) {
+            tokens.extend([self.word("thief"), self.hex(i)]);
+        }
+        for (i, coord) in layout.intersections.iter().enumerate() {
+            match view.get_static_harbor(*coord).unwrap() {
+                Harbor::Generic => tokens.extend([self.word("harbor"), self.intersection(i), self.word("generic")]),
+                Harbor::Special(res) => tokens.extend([self.word("harbor"), self.intersection(i), self.resource(res)]),
+                _ => (),
+            }
+        }
+        for (i, coord) in layout.intersections.iter().enumerate() {
+            if let Some((p, is_city)) = view.get_dynamic_intersection(*coord).unwrap() {
+                let building = self.word(if is_city { "city" } else { "settlement" });
+                tokens.extend([building, self.intersection(i), self.player(observer, p)]);
+            }
+        }
+        for (i, coord) in layout.paths.iter().enumerate() {
+            if let Some(p) = view.get_dynamic_path(*coord).unwrap() {
+                tokens.extend([self.word("road"), self.path(i), self.player(observer, p)]);
+            }
+        }
+        // ## Players, from the observer on
+        for offset in 0..self.player_count {
+            let player = relative::offset_to_player_id(observer, offset, self.player_count);
+            let public = view.public_hand(player);
+            tokens.extend([self.word("player"), self.player(observer, player)]);
+            if offset == 0 {
+                let hand = view.hand();
+                for res in Resource::ALL.iter() {
+                    tokens.extend([self.resource(*res), self.count(hand.resources[*res])]);
+                }
+                for card in DevelopmentCard::ALL.iter() {
+                    tokens.extend([self.development(*card), self.count(hand.development_cards[*card] + hand.new_development_cards[*card])]);
+                }
+            } else {
+                tokens.extend([self.word("resource_cards"), self.count(public.resource_count)]);
+                tokens.extend([self.word("development_cards"), self.count(public.development_card_count)]);
+            }
+            tokens.extend([self.word("road_pieces"), self.count(public.road_pieces)]);
+            tokens.extend([self.word("settlement_pieces"), self.count(public.settlement_pieces)]);
+            tokens.extend([self.word("city_pieces"), self.count(public.city_pieces)]);
+            tokens.extend([self.word("knights"), self.count(public.knights)]);
+            tokens.extend([self.word("victory_points"), self.count(public.public_vp)]);
+            if public.has_longest_road {
+                tokens.push(self.word("longest_road"));
+            }
+            if public.has_largest_army {
+                tokens.push(self.word("largest_army"));
+            }
+        }
+        // ## Bank, deck and stage
+        tokens.push(self.word("bank"));
+        let bank = view.get_bank_resources();
+        for res in Resource::ALL.iter() {
+            tokens.extend([self.resource(*res), self.count(bank[*res])]);
+        }
+        tokens.extend([self.word("deck"), self.count(view.development_deck_size())]);
+        tokens.extend([self.word("stage"), self.stage(phase.decision_stage())]);
+        if let Phase::Turn { player: proposer, turn_phase: TurnPhase::Trade(offer), development_phase: _ } = phase {
+            tokens.extend([self.word("trade"), self.player(observer, *proposer), self.player(observer, offer.partner)]);
+            for (word, resources) in [("give", offer.give), ("receive", offer.receive)] {
+                tokens.push(self.word(word));
+                for res in Resource::ALL.iter() {
+                    tokens.extend([self.resource(*res), self.count(resources[*res])]);
+                }
+            }
+        }
+        tokens.push(self.word("<eos>"));
+        debug_assert!(tokens.len() <= self.max_length());
+        let mut padded = Array1::<i32>::zeros(self.max_length());
+        for (i, token) in tokens.into_iter().enumerate() {
+            padded[i] = token;
+        }
+        padded
+    }
+}
@@ -0,0 +1,164 @@
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use pyo3::exceptions::{PyValueError, PyIndexError};
+use pyo3::IntoPyObjectExt;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+
+use catan::game::{Action, DiceStatistics, Phase};
+use catan::replay::Replay;
+use catan::state::{State, PlayerId, TricellState, StateMaker, StateSnapshot};
+use catan::player::CatanPlayer;
+use super::{PyCatanObservation, PyObservationFormat, PythonPlayer, PythonState, PlayerResult};
+use super::environment::to_py_tuple;
+
+/// Game of a compact replay from `get_replay`, whose observations are regenerated on demand instead of being stored
+///
+/// Plies are played forward from the last one asked for, going back to the start of the game for an earlier one,
+/// so that reading them in order plays the game once.
+#[pyclass(name = "Trajectory", unsendable)]
+pub struct PyTrajectory {
+    replay: Replay,
+    format: PyObservationFormat,
+    seats: Observers,
+    /// Plies played on the phase and state
+    ply: usize,
+    phase: Phase,
+    state: State,
+}
+
+/// State of a snapshot, of the kind the format observes
+pub(crate) fn observed_state(snapshot: &StateSnapshot, format: PyObservationFormat) -> PyResult<State> {
+    let players = snapshot.players.len() as u8;
+    let mut state: State = if format.use_python_state {
+        Box::new(PythonState::new(format.board.layout, players, format))
+    } else {
+        TricellState::new_empty(format.board.layout, players)
+    };
+    snapshot.restore_into(&mut state)
+        .map_err(|error| PyValueError::new_err(format!("Failed restoring state: {:?}", error)))?;
+    Ok(state)
+}
+
+/// Player of each seat, only used to encode the observations and the actions of positions
+pub(crate) struct Observers {
+    seats: Vec<PythonPlayer>,
+    // The Python players report their observations and results, unused here
+    _observations: Receiver<Option<(u8, PyCatanObservation)>>,
+    _results: Receiver<PlayerResult>,
+}
+
+impl Observers {
+    pub(crate) fn new(state: &State, format: PyObservationFormat) -> Observers {
+        let (observation_sender, observations) = channel();
+        let (result_sender, results) = channel();
+        let dice = Arc::new(Mutex::new(DiceStatistics::new()));
+        let seats = (0..state.player_count()).map(|p| {
+            let (_, action_receiver) = channel();
+            let mut player = PythonPlayer::new(p, format, action_receiver, observation_sender.clone(), result_sender.clone(), dice.clone(), None);
+            player.new_game(PlayerId::from(p), state);
+            player
+        }).collect();
+        Observers {
+            seats,
+            _observations: observations,
+            _results: results,
+        }
+    }
+
+    /// Observation `(seat, board, flat, ..., actions, done)` of the player to act
+    pub(crate) fn observation(&mut self, py: Python, format: &PyObservationFormat, phase: &Phase, state: &State) -> PyObject {
+        let player = phase.player();
+        let seat = &mut self.seats[player.to_usize()];
+        let legal_actions = seat.make_legal_actions(phase, state);
+        let observation = seat.observe(phase, state, &legal_actions);
+        to_py_tuple(py, format, false, false, false, Some((player.to_u8(), observation)))
+    }
+
+    /// Index of the action of a player in its action mask, None for an action missing from the mask
+    pub(crate) fn action(&self, player: PlayerId, action: Action, state: &State) -> Option<usize> {
+        self.seats[player.to_usize()].encode_action(action, state)
+    }
+}
+
+impl PyTrajectory {
+
+    /// Plays the game up to a ply, the decision of a player
+    fn seek(&mut self, ply: usize) -> PyResult<()> {
+        if ply >= self.replay.plies.len() {
+            return Err(PyIndexError::new_err(format!("No ply {} in a trajectory of {}", ply, self.replay.plies.len())));
+        }
+        if ply < self.ply {
+            self.state = observed_state(&self.replay.start.state, self.format)?;
+            self.phase = self.replay.start.phase;
+            self.ply = 0;
+        }
+        while self.ply < ply {
+            self.replay.play_ply(self.ply, &mut self.phase, &mut self.state);
+            self.ply += 1;
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PyTrajectory {
+
+    /// Trajectory of the bytes of `get_replay(compact=True)`, on the board of the format
+    #[new]
+    fn py_new(compact: &[u8], format: PyObservationFormat) -> PyResult<PyTrajectory> {
+        let replay = Replay::from_compact(compact, format.board.layout).map_err(PyValueError::new_err)?;
+        let state = observed_state(&replay.start.state, format)?;
+        Ok(PyTrajectory {
+            phase: replay.start.phase,
+            seats: Observers::new(&state, format),
+            replay,
+            format,
+            ply: 0,
+            state,
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.replay.plies.len()
+    }
+
+    /// Player index sitting at each seat
+    fn seating(&self) -> Vec<usize> {
+        self.replay.seating.clone()
+    }
+
+    /// Winning seat, None when the game didn't finish
+    fn winner(&self) -> Option<u8> {
+        self.replay.winner.map(|winner| winner.to_u8())
+    }
+
+    /// Seat playing a ply
+    fn seat(&self, ply: usize) -> PyResult<u8> {
+        self.replay.plies.get(ply).map(|played| played.player.to_u8())
+            .ok_or_else(|| PyIndexError::new_err(format!("No ply {} in a trajectory of {}", ply, self.replay.plies.len())))
+    }
+
+    /// Observation `(seat, board, flat, ..., actions, done)` of the seat playing a ply
+    fn observation(&mut self, py: Python, ply: usize) -> PyResult<PyObject> {
+        self.seek(ply)?;
+        Ok(self.seats.observation(py, &self.format, &self.phase, &self.state))
+    }
+
+    /// Index in the action mask of the action played at a ply, None for an action missing from the mask
+    fn action(&mut self, ply: usize) -> PyResult<Option<usize>> {
+        self.seek(ply)?;
+        let played = &self.replay.plies[ply];
+        Ok(self.seats.action(played.player, played.action, &self.state))
+    }
+
+    /// Replay of the trajectory, as JSON or bincode bytes as `get_replay` gives them
+    #[pyo3(signature = (binary=false))]
+    fn to_replay(&self, py: Python, binary: bool) -> PyResult<PyObject> {
+        if binary {
+            PyBytes::new(py, &self.replay.to_bytes()).into_py_any(py)
+        } else {
+            self.replay.to_json().into_py_any(py)
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use ndarray::{Array, Array1, Array2, Array3, Axis, Dimension, Ix1, Ix2, Ix3, stack};
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+use pyo3::IntoPyObjectExt;
+use numpy::convert::IntoPyArray;
+use std::sync::{Arc, Mutex};
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
+use catan::game::{Action, DiceStatistics, Curriculum};
+use catan::metrics::{self, MetricsSink};
+use super::{PyCatanObservation, PyObservationFormat, PyRewardConfig, PyGameConfig};
+use super::py_catan_observation::GraphObservation;
+use super::environment::{SingleGame, OpponentPool, Opponent, game_actions, run_in_executor};
+use super::thread_placement::ThreadPlacement;
+use super::py_action::{PyAction, describe_action, index_to_action, action_to_index, describe_legal_actions, graph_actions};
+use super::py_game_config::game_config;
+use super::python_player::IllegalActions;
+use super::observation_buffers;
+
+fn stack_arrays<D: Dimension>(arrays: &[Array<i32, D>]) -> Array<i32, D::Larger> {
+    let views: Vec<_> = arrays.iter().map(|array| array.view()).collect();
+    stack(Axis(0), &views).expect("Observations of different shapes")
+}
+
+/// Games played in parallel threads, each by a Python player against random opponents
+///
+/// Observations of every game are stacked along a first axis. A finished game is replaced by a new one,
+/// whose first observation is returned with the game flagged as done.
+#[pyclass]
+pub struct VecEnvironment {
+    games: Vec<SingleGame>,
+    format: PyObservationFormat,
+    /// Actions of the action masks
+    actions: Vec<Action>,
+    player_count: usize,
+    rewards: bool,
+    /// Behind a mutex for the environment to be shared with Python threads
+    metrics: Option<Mutex<EpisodeMetrics>>,
+}
+
+/// Sink of the outcome of the finished games, the step being the number of the game
+struct EpisodeMetrics {
+    sink: Box<dyn MetricsSink>,
+    games: u64,
+    wins: u64,
+}
+
+impl EpisodeMetrics {
+    fn write_game(&mut self, won: bool, victory_points: u8) -> Result<(), String> {
+        self.games += 1;
+        self.wins += won as u64;
+        self.sink.scalar("selfplay/won", self.games, won as u8 as f64)?;
+        self.sink.scalar("selfplay/victory_points", self.games, victory_points as f64)?;
+        self.sink.scalar("selfplay/win_rate", self.games, self.wins as f64 / self.games as f64)
+    }
+}
+
+/// Next observations of the games, with the outcome of the games which finished before them
+struct Observations {
+    observations: Vec<PyCatanObservation>,
+    done: Array1<bool>,
+    won: Array1<bool>,
+    victory_points: Array1<u8>,
+    rewards: Array1<f32>,
+}
+
+impl VecEnvironment {
+    /// Receives the next observation of every game, starting a new game where one finished
+    fn receive_all(&mut self) -> PyResult<Observations> {
+        let mut observations: Vec<PyCatanObservation> = Vec::new();
+        let mut done = Array1::<bool>::default(self.games.len());
+        let mut won = Array1::<bool>::default(self.games.len());
+        let mut victory_points = Array1::<u8>::zeros(self.games.len());
+        let mut rewards = Array1::<f32>::zeros(self.games.len());
+        for (i, game) in self.games.iter_mut().enumerate() {
+            let observation = match game.receive("Failed to read observation")? {
+                Some((_, observation)) => observation,
+                None => {
+                    let result = game.result()?;
+                    done[i] = true;
+                    won[i] = result.winner;
+                    victory_points[i] = result.victory_points;
+                    // The reward of the end of the game, the start of the next one being unrewarded
+                    rewards[i] = result.reward.unwrap_or(0.0);
+                    if let Some(metrics) = self.metrics.as_mut() {
+                        metrics.get_mut().unwrap().write_game(result.winner, result.victory_points).map_err(PyValueError::new_err)?;
+                    }
+                    game.reset(None)?;
+                    let mut observation = game.receive("Failed to read start observation")?.expect("A game finished before starting").1;
+                    observation.reward = None;
+                    observation
+                }
+            };
+            rewards[i] += observation.reward.unwrap_or(0.0);
+            observations.push(observation);
+        }
+        if done.iter().any(|done| *done) {
+            if let Some(metrics) = self.metrics.as_mut() {
+                metrics.get_mut().unwrap().sink.flush().map_err(PyValueError::new_err)?;
+            }
+        }
+        Ok(Observations {
+            observations,
+            done,
+            won,
+            victory_points,
+            rewards,
+        })
+    }
+
+    /// Receives the next observation of every game, stacked into new arrays
+    fn receive_observations(&mut self, py: Python) -> PyResult<PyObject> {
+        let Observations { observations, done, won, victory_points, rewards } = self.receive_all()?;
+        let boards: Vec<Array3<i32>> = observations.iter().map(|o| o.board.clone()).collect();
+        let flats: Vec<Array1<i32>> = observations.iter().map(|o| o.flat.clone()).collect();
+        let actions: Vec<_> = observations.iter().map(|o| o.actions.view()).collect();
+        let mut elements: Vec<PyObject> = vec![
+            stack_arrays::<Ix3>(&boards).into_pyarray(py).into(),
+            stack_arrays::<Ix1>(&flats).into_pyarray(py).into(),
+        ];
+        if self.format.include_hidden {
+            let hidden: Vec<Array1<i32>> = observations.iter().map(|o| o.hidden.clone().unwrap()).collect();
+            elements.push(stack_arrays::<Ix1>(&hidden).into_pyarray(py).into());
+        }
+        if self.format.include_global {
+            let global: Vec<Array1<i32>> = observations.iter().map(|o| o.global.clone().unwrap()).collect();
+            elements.push(stack_arrays::<Ix1>(&global).into_pyarray(py).into());
+        }
+        if self.format.include_graph {
+            let graphs: Vec<&GraphObservation> = observations.iter().map(|o| o.graph.as_ref().unwrap()).collect();
+            let hexes: Vec<Array2<i32>> = graphs.iter().map(|g| g.hexes.clone()).collect();
+            let intersections: Vec<Array2<i32>> = graphs.iter().map(|g| g.intersections.clone()).collect();
+            let paths: Vec<Array2<i32>> = graphs.iter().map(|g| g.paths.clone()).collect();
+            elements.push(stack_arrays::<Ix2>(&hexes).into_pyarray(py).into());
+            elements.push(stack_arrays::<Ix2>(&intersections).into_pyarray(py).into());
+            elements.push(stack_arrays::<Ix2>(&paths).into_pyarray(py).into());
+        }
+        if self.format.include_tokens {
+            let tokens: Vec<Array1<i32>> = observations.iter().map(|o| o.tokens.clone().unwrap()).collect();
+            elements.push(stack_arrays::<Ix1>(&tokens).into_pyarray(py).into());
+        }
+        if self.format.action_priors.is_some() {
+            let priors: Vec<_> = observations.iter().map(|o| o.priors.as_ref().unwrap().view()).collect();
+            let priors: Array2<f32> = stack(Axis(0), &priors).expect("Action priors of different lengths");
+            elements.push(priors.into_pyarray(py).into());
+        }
+        let actions: Array2<bool> = stack(Axis(0), &actions).expect("Action masks of different lengths");
+        elements.push(actions.into_pyarray(py).into());
+        elements.push(done.into_pyarray(py).into());
+        elements.push(won.into_pyarray(py).into());
+        elements.push(victory_points.into_pyarray(py).into());
+        if self.rewards {
+            elements.push(rewards.into_pyarray(py).into());
+        }
+        elements.into_py_any(py)
+    }
+
+    /// Receives the next observation of every game, written into the rows of the buffers
+    fn receive_into(&mut self, buffers: &[Bound<PyAny>]) -> PyResult<()> {
+        let received = self.receive_all()?;
+        let mut written = 0;
+        for (i, observation) in received.observations.iter().enumerate() {
+            written = observation_buffers::write_observation(buffers, Some(i), observation)?;
+        }
+        observation_buffers::write(&buffers[written], None, received.done.view().into_dyn())?;
+        observation_buffers::write(&buffers[written + 1], None, received.won.view().into_dyn())?;
+        observation_buffers::write(&buffers[written + 2], None, received.victory_points.view().into_dyn())?;
+        if self.rewards {
+            observation_buffers::write(&buffers[written + 3], None, received.rewards.view().into_dyn())?;
+        }
+        Ok(())
+    }
+
+    fn reset_games(&mut self, seed: Option<u64>) -> PyResult<()> {
+        let mut rng = seed.map(SmallRng::seed_from_u64);
+        for game in self.games.iter_mut() {
+            game.reset(rng.as_mut().map(|rng| rng.random()))?;
+        }
+        Ok(())
+    }
+
+    fn play_all(&mut self, actions: Vec<u16>) -> PyResult<()> {
+        if actions.len() != self.games.len() {
+            return Err(PyValueError::new_err(format!("{} actions given for {} games", actions.len(), self.games.len())));
+        }
+        for (game, action) in self.games.iter_mut().zip(actions) {
+            game.play(action)?;
+        }
+        Ok(())
+    }
+
+    fn check_buffers(&self, buffers: &[Bound<PyAny>]) -> PyResult<()> {
+        if self.format.action_priors.is_some() {
+            return Err(PyValueError::new_err("Action priors can't be written into buffers"));
+        }
+        observation_buffers::check_count(&self.format, buffers, 3 + self.rewards as usize)
+    }
+}
+
+#[pymethods]
+impl VecEnvironment {
+
+    /// Runs `games` games, each in its own thread
+    ///
+    /// Observations are tuples of stacked `(board, flat, [hidden], [global], [hexes, intersections, paths], [tokens], actions, done, won, victory_points, [rewards])`,
+    /// `won` and `victory_points` being the outcome of the finished games flagged as `done`
+    ///
+    /// `opponent`, `playouts`, `opponent_budget` and `opponent_temperature` choose the opponents as in `SingleEnvironment`,
+    /// and with `rewards`, the reward of each game since its previous observation is appended.
+    /// `config` gives the house rules and starting conditions of the games,
+    /// and `illegal_actions` handles illegal actions as in `SingleEnvironment`, `"raise"` excepted
+    ///
+    /// With a `metrics` path, a CSV file when it ends with `.csv` or else a directory of TensorBoard event files,
+    /// whether each finished game was `selfplay/won`, its `selfplay/victory_points` and the `selfplay/win_rate` so far are written,
+    /// the step being the number of the game
+    ///
+    /// With `cores`, the thread of the game `i` is pinned to the core `cores[i % len(cores)]`,
+    /// and with `nice`, the game threads run at the niceness, from -20, the highest priority, to 19,
+    /// reducing their jitter on busy hosts. Priorities are only set on Linux, and raising them usually needs privileges:
+    /// a game thread which can't be placed raises a `RuntimeError`
+    #[staticmethod]
+    #[pyo3(signature = (format, games, opponents=2, auto_resolve=false, seed=None, opponent="random", playouts=100, opponent_budget=None, rewards=None, config=None, illegal_actions="retry", opponent_temperature=0.0, metrics=None, cores=None, nice=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        format: &PyObservationFormat,
+        games: usize,
+        opponents: usize,
+        auto_resolve: bool,
+        seed: Option<u64>,
+        opponent: &str,
+        playouts: usize,
+        opponent_budget: Option<f64>,
+        rewards: Option<PyRewardConfig>,
+        config: Option<PyGameConfig>,
+        illegal_actions: &str,
+        opponent_temperature: f32,
+        metrics: Option<&str>,
+        cores: Option<Vec<usize>>,
+        nice: Option<i32>,
+    ) -> PyResult<VecEnvironment> {
+        let illegal_actions = IllegalActions::from_name(illegal_actions)?;
+        if illegal_actions == IllegalActions::Raise {
+            return Err(PyValueError::new_err("Illegal actions of vectorized games can't be raised"));
+        }
+        let config = game_config(config, format, opponents + 1)?;
+        let format = *format;
+        let opponent = Opponent::from_name(opponent, playouts, opponent_budget, opponent_temperature)?;
+        let placement = ThreadPlacement::new(cores, nice)?;
+        let metrics = match metrics {
+            Some(path) => Some(Mutex::new(EpisodeMetrics { sink: metrics::open(path).map_err(PyValueError::new_err)?, games: 0, wins: 0 })),
+            None => None,
+        };
+        let mut rng = seed.map(SmallRng::seed_from_u64);
+        let curriculum = Arc::new(Mutex::new(Curriculum::standard()));
+        let actions = game_actions(format, opponents + 1);
+        let games = (0..games).map(|i| {
+            let seed = rng.as_mut().map(|rng| rng.random());
+            let dice = Arc::new(Mutex::new(DiceStatistics::new()));
+            let placement = Some((placement.clone(), i));
+            SingleGame::spawn(format, opponents, OpponentPool::single(opponent.clone()), curriculum.clone(), dice, None, auto_resolve, seed, rewards.map(|rewards| rewards.config), false, false, false, config, illegal_actions, placement)
+        }).collect::<PyResult<_>>()?;
+        Ok(VecEnvironment {
+            games,
+            format,
+            actions,
+            player_count: opponents + 1,
+            rewards: rewards.is_some(),
+            metrics,
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Kind and parameters of the action at the index of the action masks, as a dict
+    fn describe_action(&self, py: Python, index: usize) -> PyResult<PyObject> {
+        describe_action(py, &self.actions, index)
+    }
+
+    /// Action at the index of the action mask
+    fn index_to_action(&self, index: usize) -> PyResult<PyAction> {
+        index_to_action(&self.actions, index)
+    }
+
+    /// Index in the action mask of an action
+    fn action_to_index(&self, action: &PyAction) -> PyResult<usize> {
+        action_to_index(&self.actions, action)
+    }
+
+    /// Descriptions of the legal actions of an action mask, each with its `index`
+    fn describe_legal_actions(&self, py: Python, mask: Vec<bool>) -> PyResult<Vec<PyObject>> {
+        describe_legal_actions(py, &self.actions, mask)
+    }
+
+    /// Indices in the action mask of the actions on the nodes and edges of the board graph, see `PyObservationFormat.graph_edges`
+    fn graph_actions(&self, py: Python) -> PyResult<PyObject> {
+        graph_actions(py, &self.actions, self.format.board.layout)
+    }
+
+    /// Starts a new game in every thread, reseeding each game from the `seed` when given
+    #[pyo3(signature = (seed=None))]
+    fn reset(&mut self, py: Python, seed: Option<u64>) -> PyResult<PyObject> {
+        self.reset_games(seed)?;
+        self.receive_observations(py)
+    }
+
+    /// Plays one action in every game, all games playing in parallel
+    fn step(&mut self, py: Python, actions: Vec<u16>) -> PyResult<PyObject> {
+        self.play_all(actions)?;
+        self.receive_observations(py)
+    }
+
+    /// Awaitable [step](VecEnvironment::step) for asyncio, waiting for the games in the default executor of the running loop
+    fn astep<'py>(slf: &Bound<'py, Self>, actions: Vec<u16>) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.as_any(), "step", (actions,).into_pyobject(slf.py())?)
+    }
+
+    /// Awaitable [reset](VecEnvironment::reset) for asyncio, see [astep](VecEnvironment::astep)
+    #[pyo3(signature = (seed=None))]
+    fn areset<'py>(slf: &Bound<'py, Self>, seed: Option<u64>) -> PyResult<Bound<'py, PyAny>> {
+        run_in_executor(slf.as_any(), "reset", (seed,).into_pyobject(slf.py())?)
+    }
+
+    /// Zeroed arrays shaped as the observations of [step](VecEnvironment::step), `done`, `won`, `victory_points` and `rewards` included,
+    /// for [reset_into](VecEnvironment::reset_into) and [step_into](VecEnvironment::step_into) to write the observations into
+    fn observation_buffers(&self, py: Python) -> Vec<PyObject> {
+        let sample = PyCatanObservation::sample(self.format, self.player_count, self.actions.len());
+        let mut buffers = observation_buffers::allocate(py, &sample, Some(self.games.len()));
+        buffers.push(Array1::<bool>::default(self.games.len()).into_pyarray(py).into());
+        buffers.push(Array1::<bool>::default(self.games.len()).into_pyarray(py).into());
+        buffers.push(Array1::<u8>::zeros(self.games.len()).into_pyarray(py).into());
+        if self.rewards {
+            buffers.push(Array1::<f32>::zeros(self.games.len()).into_pyarray(py).into());
+        }
+        buffers
+    }
+
+    /// Starts a new game in every thread as [reset](VecEnvironment::reset), writing the observations into the `buffers`
+    /// given by [observation_buffers](VecEnvironment::observation_buffers)
+    #[pyo3(signature = (buffers, seed=None))]
+    fn reset_into(&mut self, buffers: Vec<Bound<'_, PyAny>>, seed: Option<u64>) -> PyResult<()> {
+        self.check_buffers(&buffers)?;
+        self.reset_games(seed)?;
+        self.receive_into(&buffers)
+    }
+
+    /// Plays one action in every game as [step](VecEnvironment::step), writing the observations into the `buffers`
+    /// given by [observation_buffers](VecEnvironment::observation_buffers) instead of stacking them into new arrays
+    fn step_into(&mut self, actions: Vec<u16>, buffers: Vec<Bound<'_, PyAny>>) -> PyResult<()> {
+        self.check_buffers(&buffers)?;
+        self.play_all(actions)?;
+        self.receive_into(&buffers)
+    }
+
+    /// Ends the games in progress and stops their threads, raising the panic of a thread if one panicked
+    fn close(&mut self) -> PyResult<()> {
+        let mut closed = Ok(());
+        for game in self.games.iter_mut() {
+            closed = closed.and(game.close());
+        }
+        closed
+    }
+}